@@ -0,0 +1,11 @@
+// How errors are reported to the user, rows and columns are counted from zero and shown from one
+
+// kind is the start of the report, like "Error" or "Type error"
+pub fn located_message(kind: &str, message: &str, row: usize, col_start: usize) -> String {
+    format!("{}: {} (line {}, col {})", kind, message, row + 1, col_start + 1)
+}
+
+// The line below a source line which marks the characters from col_start up to col_end
+pub fn span_marker(col_start: usize, col_end: usize) -> String {
+    format!("{}{}", " ".repeat(col_start), "^".repeat(col_end.saturating_sub(col_start)))
+}
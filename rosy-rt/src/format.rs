@@ -0,0 +1,31 @@
+// How values are written by print and println.
+// The native print routines write integers the same way as format_integer.
+
+pub fn format_integer(value: i64) -> String {
+    value.to_string()
+}
+
+pub fn format_boolean(value: bool) -> String {
+    value.to_string()
+}
+
+// A float always shows that it is one, 1.0 instead of 1
+pub fn format_float(value: f64) -> String {
+    format!("{value:?}")
+}
+
+pub fn format_error(message: &str) -> String {
+    format!("error: {message}")
+}
+
+pub fn format_list(elements: &[String]) -> String {
+    format!("[{}]", elements.join(", "))
+}
+
+// A tuple of one element is written with a comma, as in the source: (1,)
+pub fn format_tuple(elements: &[String]) -> String {
+    match elements {
+        [element] => format!("({},)", element),
+        _ => format!("({})", elements.join(", ")),
+    }
+}
@@ -0,0 +1,26 @@
+use std::ops::Range;
+
+// Checks an index into a list, bytes or tuple of the given length, kind names the value in the error
+pub fn check_index(index: i64, length: usize, kind: &str) -> Result<usize, String> {
+    match usize::try_from(index) {
+        Ok(index) if index < length => Ok(index),
+        _ => Err(format!("Index {index} out of bounds for {kind} of length {length}")),
+    }
+}
+
+// The elements from start up to, but not including, end
+pub fn check_slice(start: i64, end: i64, length: usize, kind: &str) -> Result<Range<usize>, String> {
+    if start < 0 || start > end || end > length as i64 {
+        return Err(format!("Cannot slice from {start} to {end} in {kind} of length {length}"));
+    }
+    Ok(start as usize..end as usize)
+}
+
+// The elements from start up to, but not including, end, as taken by the slicing syntax xs[1:4] and xs[:n].
+// A left out bound is the start or end of the value, bounds outside of it are clamped and a start after the end gives nothing.
+pub fn clamp_slice(start: Option<i64>, end: Option<i64>, length: usize) -> Range<usize> {
+    let clamp = |bound: i64| bound.clamp(0, length as i64) as usize;
+    let start = start.map_or(0, clamp);
+    let end = end.map_or(length, clamp).max(start);
+    start..end
+}
@@ -0,0 +1,9 @@
+// The runtime library of rosy.
+// The interpreter calls these functions for its built-in behaviour, and compiled executables are linked
+// against the native routines in the native module, so a program prints the same text either way.
+
+pub mod error;
+pub mod format;
+pub mod index;
+pub mod native;
+pub mod text;
@@ -0,0 +1,121 @@
+// Native routines which compiled programs are linked against.
+// They use the Windows x64 calling convention like the rest of the generated code,
+// and write their output through the KERNEL32 functions, which the executable writers resolve.
+
+pub struct NativeRoutine {
+    pub code: &'static [u8],
+    // The functions the routine provides and where in the code they start
+    pub entries: &'static [(&'static str, usize)],
+    // The KERNEL32 functions the routine calls, with the position of the 4 byte displacement of each call
+    pub imports: &'static [(&'static str, usize)],
+}
+
+// print(integer) and println(integer), written like format_integer with a minus sign for negative numbers.
+// The digits are written backwards into a buffer on the stack, ending with the newline of println.
+pub const INTEGER_OUTPUT: NativeRoutine = NativeRoutine {
+    code: &[
+        // rosy_rt_print_int
+        0x31, 0xD2, // xor edx, edx
+        0xEB, 0x05, // jmp write
+        // rosy_rt_println_int
+        0xBA, 0x01, 0x00, 0x00, 0x00, // mov edx, 1
+        // write: the integer is in rcx and edx tells whether to end with a newline
+        0x53, // push rbx
+        0x56, // push rsi
+        0x57, // push rdi
+        0x48, 0x83, 0xEC, 0x40, // sub rsp, 64 (shadow space, the fifth argument and a 24 byte buffer)
+        0x48, 0x8D, 0x7C, 0x24, 0x40, // lea rdi, [rsp + 64] (end of the buffer)
+        0x48, 0x89, 0xFE, // mov rsi, rdi
+        0x85, 0xD2, // test edx, edx
+        0x74, 0x06, // jz digits
+        0x48, 0xFF, 0xCE, // dec rsi
+        0xC6, 0x06, 0x0A, // mov byte [rsi], '\n'
+        // digits: of the absolute value, which is also right for the smallest integer when divided unsigned
+        0x48, 0x89, 0xCB, // mov rbx, rcx
+        0x48, 0x89, 0xC8, // mov rax, rcx
+        0x48, 0x85, 0xC0, // test rax, rax
+        0x79, 0x03, // jns positive
+        0x48, 0xF7, 0xD8, // neg rax
+        0xB9, 0x0A, 0x00, 0x00, 0x00, // positive: mov ecx, 10
+        0x31, 0xD2, // loop: xor edx, edx
+        0x48, 0xF7, 0xF1, // div rcx
+        0x80, 0xC2, 0x30, // add dl, '0'
+        0x48, 0xFF, 0xCE, // dec rsi
+        0x88, 0x16, // mov [rsi], dl
+        0x48, 0x85, 0xC0, // test rax, rax
+        0x75, 0xEE, // jnz loop
+        0x48, 0x85, 0xDB, // test rbx, rbx
+        0x79, 0x06, // jns output
+        0x48, 0xFF, 0xCE, // dec rsi
+        0xC6, 0x06, 0x2D, // mov byte [rsi], '-'
+        0x48, 0xC7, 0xC1, 0xF5, 0xFF, 0xFF, 0xFF, // output: mov rcx, -11 (standard output)
+        0xFF, 0x15, 0x00, 0x00, 0x00, 0x00, // call [GetStdHandle]
+        0x48, 0x89, 0xC1, // mov rcx, rax
+        0x48, 0x89, 0xF2, // mov rdx, rsi
+        0x49, 0x89, 0xF8, // mov r8, rdi
+        0x49, 0x29, 0xF0, // sub r8, rsi
+        0x45, 0x31, 0xC9, // xor r9d, r9d
+        0x48, 0xC7, 0x44, 0x24, 0x20, 0x00, 0x00, 0x00, 0x00, // mov qword [rsp + 32], 0
+        0xFF, 0x15, 0x00, 0x00, 0x00, 0x00, // call [WriteFile]
+        0x48, 0x83, 0xC4, 0x40, // add rsp, 64
+        0x5F, // pop rdi
+        0x5E, // pop rsi
+        0x5B, // pop rbx
+        0xC3, // ret
+    ],
+    entries: &[("rosy_rt_print_int", 0), ("rosy_rt_println_int", 4)],
+    imports: &[("GetStdHandle", 91), ("WriteFile", 121)],
+};
+
+pub const ROUTINES: [NativeRoutine; 1] = [INTEGER_OUTPUT];
+
+// Appends the routines which the machine code calls to it, and turns those calls into direct calls.
+// The extern calls which are left, including those of the routines, are resolved by the executable writer.
+pub fn link(machine_code: &mut Vec<u8>, extern_calls: &mut Vec<(String, usize)>) {
+    let mut routine_locations: Vec<Option<usize>> = vec![None; ROUTINES.len()];
+    let mut remaining_calls = Vec::new();
+
+    for (name, at) in extern_calls.drain(..) {
+        let Some((routine_index, entry)) = find_entry(&name) else {
+            remaining_calls.push((name, at));
+            continue;
+        };
+
+        let routine_location = match routine_locations[routine_index] {
+            Some(location) => location,
+            None => {
+                let routine = &ROUTINES[routine_index];
+                // Routines start on 16 bytes, the padding is never executed
+                while !machine_code.len().is_multiple_of(16) {
+                    machine_code.push(0xCC);
+                }
+                let location = machine_code.len();
+                machine_code.extend_from_slice(routine.code);
+                for (import, offset) in routine.imports {
+                    remaining_calls.push((import.to_string(), location + offset));
+                }
+                routine_locations[routine_index] = Some(location);
+                location
+            }
+        };
+
+        // call [rip + disp32] becomes call rel32 followed by a nop, which has the same length
+        let call_start = at - 2;
+        let offset = (routine_location + entry) as i64 - (call_start + 5) as i64;
+        machine_code[call_start] = 0xE8;
+        machine_code[call_start + 1..call_start + 5].copy_from_slice(&(offset as i32).to_le_bytes());
+        machine_code[call_start + 5] = 0x90;
+    }
+
+    *extern_calls = remaining_calls;
+}
+
+fn find_entry(name: &str) -> Option<(usize, usize)> {
+    ROUTINES.iter().enumerate().find_map(|(routine_index, routine)| {
+        routine
+            .entries
+            .iter()
+            .find(|(entry, _)| *entry == name)
+            .map(|(_, offset)| (routine_index, *offset))
+    })
+}
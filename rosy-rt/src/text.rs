@@ -0,0 +1,47 @@
+use crate::index::check_slice;
+
+// Positions in strings count characters, not bytes
+
+// The index of the first occurrence of pattern in text, or -1 if it does not occur
+pub fn find(text: &str, pattern: &str) -> i64 {
+    match text.find(pattern) {
+        Some(byte_index) => text[..byte_index].chars().count() as i64,
+        None => -1,
+    }
+}
+
+pub fn slice(text: &str, start: i64, end: i64) -> Result<String, String> {
+    let range = check_slice(start, end, text.chars().count(), "a string")?;
+    Ok(text.chars().skip(range.start).take(range.len()).collect())
+}
+
+// The longest string, in bytes, which padding and repeating may make. A program asking for more
+// gets an error instead of the allocation failing, which would abort the whole process.
+pub const MAX_STRING_LENGTH: usize = 1 << 28;
+
+// The fill characters needed to make text as wide as width
+fn padding(text: &str, width: i64, fill: char) -> Result<String, String> {
+    let missing = (width.max(0) as usize).saturating_sub(text.chars().count());
+    match missing.checked_mul(fill.len_utf8()) {
+        Some(length) if length <= MAX_STRING_LENGTH => Ok(std::iter::repeat_n(fill, missing).collect()),
+        _ => Err(format!("Cannot pad a string to a width of {}, as the result would be too large", width)),
+    }
+}
+
+pub fn pad_left(text: &str, width: i64, fill: char) -> Result<String, String> {
+    Ok(padding(text, width, fill)? + text)
+}
+
+pub fn pad_right(text: &str, width: i64, fill: char) -> Result<String, String> {
+    Ok(text.to_string() + &padding(text, width, fill)?)
+}
+
+pub fn repeat(text: &str, count: i64) -> Result<String, String> {
+    if count < 0 {
+        return Err(format!("Cannot repeat a string {} times", count));
+    }
+    match (count as usize).checked_mul(text.len()) {
+        Some(length) if length <= MAX_STRING_LENGTH => Ok(text.repeat(count as usize)),
+        _ => Err(format!("Cannot repeat a string {} times, as the result would be too large", count)),
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+
+use crate::parser::BaseExpr;
+use crate::parser::BaseExprData;
+use crate::parser::RecExpr;
+use crate::typechecker;
+use crate::typechecker::FunctionType;
+use crate::typechecker::Type;
+use crate::visitor;
+use crate::visitor::Visitor;
+
+// Annotations are collected per source row, so that every line of the original
+// source can be reprinted with the types that were inferred for it
+type RowAnnotations = BTreeMap<usize, Vec<String>>;
+
+// Reprints the source with the inferred types as a sidebar of comments:
+//   x = f(3)   # x: int
+// The typed program is the output of typechecker::type_check_program,
+// the untyped base expressions are only used to find the rows where functions are defined
+pub fn annotate_source(
+    lines: &[&str],
+    untyped_program: &[BaseExpr<()>],
+    typed_program: &(Vec<BaseExpr<Type>>, Vec<FunctionType>),
+) -> String {
+    let mut annotations: RowAnnotations = BTreeMap::new();
+
+    collect_annotations(&typed_program.0, &mut annotations);
+
+    for function in typed_program.1.iter() {
+        collect_annotations(&function.content, &mut annotations);
+
+        // Annotate the definition line with the signature of every instance of the function
+        if let Some(row) = find_function_definition_row(&function.name, untyped_program) {
+            add_annotation(&mut annotations, row, format_signature(function));
+        }
+    }
+
+    // All annotations are aligned into a single column behind the longest annotated line
+    let cleaned_lines: Vec<String> = lines.iter().map(|line| line.replace('\r', "")).collect();
+    let sidebar_column = annotations
+        .keys()
+        .filter_map(|row| cleaned_lines.get(*row))
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0)
+        + 3;
+
+    let mut output = String::new();
+    for (row, line) in cleaned_lines.iter().enumerate() {
+        output.push_str(line);
+        if let Some(row_annotations) = annotations.get(&row) {
+            let padding = sidebar_column - line.chars().count();
+            output.push_str(&" ".repeat(padding));
+            output.push_str("# ");
+            output.push_str(&row_annotations.join(", "));
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+fn add_annotation(annotations: &mut RowAnnotations, row: usize, annotation: String) {
+    let row_annotations = annotations.entry(row).or_default();
+
+    // The same line can be typechecked multiple times (e.g. a function called with different types),
+    // identical annotations are only shown once
+    if !row_annotations.contains(&annotation) {
+        row_annotations.push(annotation);
+    }
+}
+
+fn collect_annotations(base_expressions: &[BaseExpr<Type>], annotations: &mut RowAnnotations) {
+    AnnotationCollector { annotations }.visit_body(base_expressions);
+}
+
+// Annotates every statement that binds a variable with the type of the variable
+struct AnnotationCollector<'a> {
+    annotations: &'a mut RowAnnotations,
+}
+
+impl Visitor<Type> for AnnotationCollector<'_> {
+    fn visit_base_expr(&mut self, base_expr: &BaseExpr<Type>) {
+        if let Some(annotation) = statement_annotation(base_expr) {
+            add_annotation(self.annotations, base_expr.span.row, annotation);
+        }
+
+        visitor::walk_base_expr(self, base_expr);
+    }
+
+    // Only statements are annotated
+    fn visit_rec_expr(&mut self, _rec_expr: &RecExpr<Type>) {}
+}
+
+// The type of the variable bound by the statement, such as "x: int",
+// or None if the statement does not bind a variable
+pub fn statement_annotation(base_expr: &BaseExpr<Type>) -> Option<String> {
+    match &base_expr.data {
+        BaseExprData::VariableAssignment { var_name, expr } => {
+            Some(format!("{}: {}", var_name, expr.generic_data))
+        }
+        BaseExprData::DestructuringAssignment { var_names, expr } => {
+            let element_types = typechecker::destructured_types(&expr.generic_data, var_names.len());
+            let annotations: Vec<String> = var_names
+                .iter()
+                .zip(&element_types)
+                .map(|(var_name, element_type)| format!("{}: {}", var_name, element_type))
+                .collect();
+            Some(annotations.join(", "))
+        }
+        BaseExprData::ForLoop {
+            var_name, until, ..
+        } => {
+            let iteration_variable_type = match &until.generic_data {
+                Type::List(element_type) => *element_type.clone(),
+                Type::Bytes => Type::Integer,
+                other => other.clone(),
+            };
+            Some(format!("{}: {}", var_name, iteration_variable_type))
+        }
+        BaseExprData::With { var_name, .. } => {
+            Some(format!("{}: {}", var_name, Type::Resource))
+        }
+        _ => None,
+    }
+}
+
+fn find_function_definition_row(name: &str, base_expressions: &[BaseExpr<()>]) -> Option<usize> {
+    base_expressions
+        .iter()
+        .find_map(|base_expr| match &base_expr.data {
+            BaseExprData::FunctionDefinition { fun_name, .. } if fun_name == name => {
+                Some(base_expr.span.row)
+            }
+            _ => None,
+        })
+}
+
+pub fn format_signature(function: &FunctionType) -> String {
+    let params: Vec<String> = function
+        .param_names
+        .iter()
+        .zip(function.param_types.iter())
+        .map(|(name, param_type)| format!("{}: {}", name, param_type))
+        .collect();
+
+    format!(
+        "{}({}) -> {}",
+        function.name,
+        params.join(", "),
+        function.return_type
+    )
+}
@@ -0,0 +1,141 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::engine::Engine;
+use crate::interpreter::ProgramState;
+use crate::parser::BaseExprData;
+use crate::tokenizer::Error;
+
+#[derive(Clone)]
+pub struct BenchOptions {
+    // Untimed runs before the measurements, so the timings are not affected by a cold start
+    pub warmup: usize,
+    // The number of timed runs of every benchmark
+    pub iterations: usize,
+    // Only runs the benchmarks whose name contains this text
+    pub filter: Option<String>,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        BenchOptions {
+            warmup: 3,
+            iterations: 10,
+            filter: None,
+        }
+    }
+}
+
+// The measurements of a single benchmark function
+pub struct BenchResult {
+    pub name: String,
+    // The time of every timed run, in the order they ran
+    pub durations: Vec<Duration>,
+    // The number of statements a single run executes
+    pub statements_per_run: usize,
+}
+
+impl BenchResult {
+    pub fn mean(&self) -> Duration {
+        match self.durations.len() {
+            0 => Duration::ZERO,
+            runs => self.durations.iter().sum::<Duration>() / runs as u32,
+        }
+    }
+
+    pub fn median(&self) -> Duration {
+        let mut sorted = self.durations.clone();
+        sorted.sort();
+        match sorted.len() {
+            0 => Duration::ZERO,
+            runs if runs % 2 == 0 => (sorted[runs / 2 - 1] + sorted[runs / 2]) / 2,
+            runs => sorted[runs / 2],
+        }
+    }
+
+    // The population standard deviation of the run times
+    pub fn std_dev(&self) -> Duration {
+        if self.durations.is_empty() {
+            return Duration::ZERO;
+        }
+
+        let mean = self.mean().as_secs_f64();
+        let variance = self
+            .durations
+            .iter()
+            .map(|duration| (duration.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / self.durations.len() as f64;
+        Duration::from_secs_f64(variance.sqrt())
+    }
+}
+
+// Benchmark functions are the top level functions whose name starts with bench_
+fn is_bench_name(name: &str) -> bool {
+    name.starts_with("bench_")
+}
+
+// Runs the top level statements of the program, then measures every benchmark function,
+// in the order they are defined. Benchmarks take no arguments, an error in one stops the run.
+pub fn run_benchmarks(
+    engine: &Engine,
+    lines: Vec<&str>,
+    options: &BenchOptions,
+) -> Result<Vec<BenchResult>, Error> {
+    let base_expressions = engine.parse(lines)?;
+
+    let mut benchmarks = Vec::new();
+    for base_expr in base_expressions.iter() {
+        let BaseExprData::FunctionDefinition { fun_name, args, .. } = &base_expr.data else {
+            continue;
+        };
+        if !is_bench_name(fun_name) {
+            continue;
+        }
+        if let Some(filter) = &options.filter {
+            if !fun_name.contains(filter.as_str()) {
+                continue;
+            }
+        }
+        if !args.is_empty() {
+            return Err(Error::located(
+                format!("Benchmark {} cannot have parameters", fun_name),
+                base_expr.span(),
+            ));
+        }
+        benchmarks.push(fun_name.clone());
+    }
+
+    let mut program = ProgramState::new(base_expressions, engine.builtins(), &engine.options);
+    while !program.step(usize::MAX)? {}
+
+    let mut results = Vec::new();
+    for name in benchmarks {
+        for _ in 0..options.warmup {
+            program.call(&name, Vec::new())?;
+        }
+
+        // Statements are counted in a separate run, since collecting statistics slows the interpreter down
+        let collect_stats = program.context.options.collect_stats;
+        program.context.options.collect_stats = true;
+        let statements_before = program.context.stats.statements_executed;
+        program.call(&name, Vec::new())?;
+        let statements_per_run = program.context.stats.statements_executed - statements_before;
+        program.context.options.collect_stats = collect_stats;
+
+        let mut durations = Vec::new();
+        for _ in 0..options.iterations {
+            let start = Instant::now();
+            program.call(&name, Vec::new())?;
+            durations.push(start.elapsed());
+        }
+
+        results.push(BenchResult {
+            name,
+            durations,
+            statements_per_run,
+        });
+    }
+
+    Ok(results)
+}
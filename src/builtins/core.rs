@@ -0,0 +1,248 @@
+use super::Builtin;
+use super::BuiltinModule;
+use crate::interpreter::value_to_string;
+use crate::interpreter::value_type_to_string;
+use crate::interpreter::Context;
+use crate::interpreter::Value;
+use crate::parser;
+use crate::typechecker::Type;
+
+// The functions every rosy program can expect to exist.
+// Output is written to the interpreter terminal as well as to stdout, unless InterpreterOptions::quiet is set.
+pub fn module() -> BuiltinModule {
+    BuiltinModule {
+        name: "core",
+        functions: vec![
+            Builtin {
+                name: "print",
+                param_names: &["*values"],
+                signature: printable_signature,
+                implementation: print,
+            },
+            Builtin {
+                name: "println",
+                param_names: &["*values"],
+                signature: printable_signature,
+                implementation: println,
+            },
+            Builtin {
+                name: "dump",
+                param_names: &["value"],
+                signature: |arg_types| match arg_types {
+                    [_] => Some(Type::Undefined),
+                    _ => None,
+                },
+                implementation: dump,
+            },
+            Builtin {
+                name: "breakpoint",
+                param_names: &[],
+                signature: |arg_types| match arg_types {
+                    [] => Some(Type::Undefined),
+                    _ => None,
+                },
+                implementation: breakpoint,
+            },
+            Builtin {
+                name: "is_error",
+                param_names: &["value"],
+                signature: |arg_types| match arg_types {
+                    [_] => Some(Type::Boolean),
+                    _ => None,
+                },
+                implementation: is_error,
+            },
+            Builtin {
+                name: "error_message",
+                param_names: &["result"],
+                signature: |arg_types| match arg_types {
+                    [Type::Result(_)] => Some(Type::String),
+                    _ => None,
+                },
+                implementation: error_message,
+            },
+            Builtin {
+                name: "unwrap",
+                param_names: &["result"],
+                signature: |arg_types| match arg_types {
+                    [Type::Result(value_type)] => Some(*value_type.clone()),
+                    _ => None,
+                },
+                implementation: unwrap,
+            },
+            Builtin {
+                name: "close",
+                param_names: &["resource"],
+                signature: |arg_types| match arg_types {
+                    [Type::Resource] => Some(Type::Undefined),
+                    _ => None,
+                },
+                implementation: close,
+            },
+            Builtin {
+                name: "freeze",
+                param_names: &["value"],
+                signature: |arg_types| match arg_types {
+                    [value_type @ (Type::List(_) | Type::Dict(_, _) | Type::Struct(_))] => Some(value_type.clone()),
+                    _ => None,
+                },
+                implementation: freeze,
+            },
+        ],
+    }
+}
+
+// print and println take one or more values, which are written one after another without anything between them
+fn printable_signature(arg_types: &[Type]) -> Option<Type> {
+    let is_printable = |arg_type: &Type| {
+        matches!(
+            arg_type,
+            Type::String | Type::Integer | Type::Float | Type::Boolean | Type::Rational | Type::Complex
+        )
+    };
+    match !arg_types.is_empty() && arg_types.iter().all(is_printable) {
+        true => Some(Type::Undefined),
+        false => None,
+    }
+}
+
+fn print(args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    // The output was already shown before the debugger went back
+    if context.is_re_executing() {
+        return Ok(None);
+    }
+
+    let last_terminal_line = context.terminal.last_mut().unwrap();
+    for arg in args {
+        let value_string = value_to_string(&arg);
+        if !context.options.quiet {
+            print!("{}", value_string);
+        }
+        last_terminal_line.push_str(&value_string);
+    }
+
+    Ok(None)
+}
+
+fn println(args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    if context.is_re_executing() {
+        return Ok(None);
+    }
+
+    print(args, context)?;
+    context.terminal.push(String::new());
+    if !context.options.quiet {
+        println!();
+    }
+
+    Ok(None)
+}
+
+// Prints a value with its type for debugging, one line per value with the elements of lists
+// and generators indented below them
+fn dump(args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    let value = match args.as_slice() {
+        [value] => value,
+        _ => return Err("dump expects a value".to_string()),
+    };
+
+    let mut lines = Vec::new();
+    dump_lines(value, 0, &mut lines);
+    for line in lines {
+        println(vec![Value::String(line.into())], context)?;
+    }
+
+    Ok(None)
+}
+
+fn dump_lines(value: &Value, depth: usize, lines: &mut Vec<String>) {
+    let indentation = "    ".repeat(depth);
+    let type_name = value_type_to_string(value);
+
+    match value {
+        Value::List(values) => {
+            lines.push(format!("{}{} of length {}", indentation, type_name, values.len()));
+            for element in values {
+                dump_lines(element, depth + 1, lines);
+            }
+        }
+        // The values of a generator are only made when they are taken
+        Value::Generator(_) => lines.push(format!("{}generator", indentation)),
+        Value::Function { name, args, .. } => {
+            lines.push(format!("{}function {}({})", indentation, name, parser::parameter_names(args).join(", ")));
+        }
+        Value::StandardFunction(builtin) => {
+            lines.push(format!(
+                "{}standard function {}({})",
+                indentation,
+                builtin.name,
+                builtin.param_names.join(", ")
+            ));
+        }
+        Value::String(text) => lines.push(format!("{}string {:?}", indentation, text)),
+        Value::Error(message) => lines.push(format!("{}error {:?}", indentation, message)),
+        Value::Resource(resource) => {
+            lines.push(format!("{}resource {} {}", indentation, resource.kind, resource.id));
+        }
+        Value::Bytes(bytes) => lines.push(format!(
+            "{}bytes of length {} {}",
+            indentation,
+            bytes.len(),
+            value_to_string(value)
+        )),
+        _ => lines.push(format!("{}{} {}", indentation, type_name, value_to_string(value))),
+    }
+}
+
+// Pauses the program in the debugger when it runs in debug mode, does nothing otherwise
+fn breakpoint(_args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    context.request_breakpoint();
+    Ok(None)
+}
+
+fn is_error(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Error(_)] => Ok(Some(Value::Bool(true))),
+        [_] => Ok(Some(Value::Bool(false))),
+        _ => Err("is_error expects a value".to_string()),
+    }
+}
+
+fn error_message(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Error(message)] => Ok(Some(Value::String(message.as_str().into()))),
+        [_] => Err("error_message expects an error, check is_error first".to_string()),
+        _ => Err("error_message expects an error".to_string()),
+    }
+}
+
+// Gives the value of a successful result, or stops the program with the error
+fn unwrap(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.into_iter().next() {
+        Some(Value::Error(message)) => Err(message),
+        Some(value) => Ok(Some(value)),
+        None => Err("unwrap expects a value".to_string()),
+    }
+}
+
+// Closing a resource which is already closed does nothing, so it can also be closed inside a with block
+fn close(args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Resource(resource)] => {
+            context.close_resource(resource);
+            Ok(None)
+        }
+        _ => Err("close expects a resource".to_string()),
+    }
+}
+
+// The value with its elements or fields protected from assignment, see Value::Frozen.
+// It stays frozen when it is assigned or passed to a function, so a callback cannot change it.
+fn freeze(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.into_iter().next() {
+        Some(value @ (Value::List(_) | Value::Dict(_) | Value::Struct { .. })) => {
+            Ok(Some(Value::Frozen(Box::new(value))))
+        }
+        _ => Err("freeze expects a list, dict or struct".to_string()),
+    }
+}
@@ -0,0 +1,153 @@
+use super::Builtin;
+use super::BuiltinModule;
+use crate::interpreter::Context;
+use crate::interpreter::DictKey;
+use crate::interpreter::Value;
+use crate::typechecker::Type;
+
+// Reading the keys and values of dicts. Dicts are values like lists, so remove gives a new dict
+// which is assigned back to the variable: ages = remove(ages, "bob").
+// Keys are given in the order in which they were added, the same order in which a dict is printed and looped over.
+pub fn module() -> BuiltinModule {
+    BuiltinModule {
+        name: "dict",
+        functions: vec![
+            Builtin {
+                name: "keys",
+                param_names: &["dict"],
+                signature: keys_signature,
+                implementation: keys,
+            },
+            Builtin {
+                name: "values",
+                param_names: &["dict"],
+                signature: values_signature,
+                implementation: values,
+            },
+            Builtin {
+                name: "items",
+                param_names: &["dict"],
+                signature: items_signature,
+                implementation: items,
+            },
+            Builtin {
+                name: "get",
+                param_names: &["dict", "key", "default"],
+                signature: get_signature,
+                implementation: get,
+            },
+            Builtin {
+                name: "remove",
+                param_names: &["dict", "key"],
+                signature: remove_signature,
+                implementation: remove,
+            },
+        ],
+    }
+}
+
+// Whether a value of the given type fits where the dict has the expected type.
+// The types of an empty dict are undefined, so anything fits them.
+fn fits(expected: &Type, given: &Type) -> bool {
+    *expected == Type::Undefined || expected == given
+}
+
+fn keys_signature(arg_types: &[Type]) -> Option<Type> {
+    match arg_types {
+        [Type::Dict(key_type, _)] => Some(Type::List(key_type.clone())),
+        _ => None,
+    }
+}
+
+fn values_signature(arg_types: &[Type]) -> Option<Type> {
+    match arg_types {
+        [Type::Dict(_, value_type)] => Some(Type::List(value_type.clone())),
+        _ => None,
+    }
+}
+
+fn items_signature(arg_types: &[Type]) -> Option<Type> {
+    match arg_types {
+        [Type::Dict(key_type, value_type)] => Some(Type::List(Box::new(Type::Tuple(vec![
+            *key_type.clone(),
+            *value_type.clone(),
+        ])))),
+        _ => None,
+    }
+}
+
+fn get_signature(arg_types: &[Type]) -> Option<Type> {
+    match arg_types {
+        [Type::Dict(key_type, value_type), key, default]
+            if fits(key_type, key) && fits(value_type, default) && *default != Type::Undefined =>
+        {
+            Some(default.clone())
+        }
+        _ => None,
+    }
+}
+
+fn remove_signature(arg_types: &[Type]) -> Option<Type> {
+    match arg_types {
+        [dict_type @ Type::Dict(key_type, _), key] if fits(key_type, key) => Some(dict_type.clone()),
+        _ => None,
+    }
+}
+
+fn keys(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.into_iter().next() {
+        Some(Value::Dict(entries)) => Ok(Some(Value::List(
+            entries.keys().map(DictKey::to_value).collect(),
+        ))),
+        _ => Err("keys expects a dict".to_string()),
+    }
+}
+
+fn values(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.into_iter().next() {
+        Some(Value::Dict(entries)) => Ok(Some(Value::List(
+            entries.into_values().collect(),
+        ))),
+        _ => Err("values expects a dict".to_string()),
+    }
+}
+
+// The entries of the dict as (key, value) tuples
+fn items(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.into_iter().next() {
+        Some(Value::Dict(entries)) => Ok(Some(Value::List(
+            entries
+                .into_iter()
+                .map(|(key, value)| Value::Tuple(vec![key.to_value(), value]))
+                .collect(),
+        ))),
+        _ => Err("items expects a dict".to_string()),
+    }
+}
+
+// The value of the key, or the default when the dict does not have the key
+fn get(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let mut args = args.into_iter();
+    match (args.next(), args.next(), args.next()) {
+        (Some(Value::Dict(mut entries)), Some(key), Some(default)) => {
+            let value = DictKey::from_value(&key).and_then(|key| entries.swap_remove(&key));
+            Ok(Some(value.unwrap_or(default)))
+        }
+        _ => Err("get expects a dict, a key and a default value".to_string()),
+    }
+}
+
+// The dict without the key, which is the same dict when it does not have the key.
+// The other keys keep their order.
+fn remove(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let mut args = args.into_iter();
+    match (args.next(), args.next()) {
+        (Some(Value::Dict(mut entries)), Some(key)) => {
+            if let Some(key) = DictKey::from_value(&key) {
+                entries.shift_remove(&key);
+            }
+            Ok(Some(Value::Dict(entries)))
+        }
+        _ => Err("remove expects a dict and a key".to_string()),
+    }
+}
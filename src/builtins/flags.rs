@@ -0,0 +1,123 @@
+use super::expect_arguments;
+use super::Builtin;
+use super::BuiltinModule;
+use crate::interpreter::Context;
+use crate::interpreter::Value;
+use crate::typechecker::Type;
+
+// Command-line flags, read from the program arguments passed through `rosy run file -- ...`.
+// Flags are written as --name=value or --name value, boolean flags can also be written as just --name.
+pub fn module() -> BuiltinModule {
+    BuiltinModule {
+        name: "flags",
+        functions: vec![
+            Builtin {
+                name: "flag_string",
+                param_names: &["name", "default"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::String, Type::String], Type::String)
+                },
+                implementation: flag_string,
+            },
+            Builtin {
+                name: "flag_int",
+                param_names: &["name", "default"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::String, Type::Integer], Type::Integer)
+                },
+                implementation: flag_int,
+            },
+            Builtin {
+                name: "flag_bool",
+                param_names: &["name", "default"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::String, Type::Boolean], Type::Boolean)
+                },
+                implementation: flag_bool,
+            },
+        ],
+    }
+}
+
+enum FlagValue {
+    // The flag was not passed
+    Missing,
+    // The flag was passed without a value
+    Present,
+    Value(String),
+}
+
+// Finds the last occurrence of the flag, so that later arguments override earlier ones.
+// Boolean flags only take a value in the --name=value form, other flags also read it from the next argument.
+fn find_flag(arguments: &[String], name: &str, takes_value: bool) -> FlagValue {
+    let flag = format!("--{}", name);
+    let mut found = FlagValue::Missing;
+
+    let mut i = 0;
+    while i < arguments.len() {
+        let argument = &arguments[i];
+        if let Some(value) = argument
+            .strip_prefix(&flag)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            found = FlagValue::Value(value.to_string());
+        } else if *argument == flag {
+            match arguments.get(i + 1) {
+                Some(next) if takes_value && !next.starts_with("--") => {
+                    found = FlagValue::Value(next.clone());
+                    i += 1;
+                }
+                _ => found = FlagValue::Present,
+            }
+        }
+        i += 1;
+    }
+
+    found
+}
+
+fn flag_string(args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    let (name, default) = match args.as_slice() {
+        [Value::String(name), Value::String(default)] => (name, default),
+        _ => return Err("flag_string expects a name and a default string".to_string()),
+    };
+
+    match find_flag(&context.options.program_arguments, name, true) {
+        FlagValue::Missing => Ok(Some(Value::String(default.clone()))),
+        FlagValue::Present => Err(format!("Flag --{} expects a value", name)),
+        FlagValue::Value(value) => Ok(Some(Value::String(value.into()))),
+    }
+}
+
+fn flag_int(args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    let (name, default) = match args.as_slice() {
+        [Value::String(name), Value::Number(default)] => (name, default),
+        _ => return Err("flag_int expects a name and a default integer".to_string()),
+    };
+
+    match find_flag(&context.options.program_arguments, name, true) {
+        FlagValue::Missing => Ok(Some(Value::Number(*default))),
+        FlagValue::Present => Err(format!("Flag --{} expects a value", name)),
+        FlagValue::Value(value) => match value.parse::<i64>() {
+            Ok(number) => Ok(Some(Value::Number(number))),
+            Err(_) => Err(format!("Flag --{} expects an integer, got '{}'", name, value)),
+        },
+    }
+}
+
+fn flag_bool(args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    let (name, default) = match args.as_slice() {
+        [Value::String(name), Value::Bool(default)] => (name, default),
+        _ => return Err("flag_bool expects a name and a default boolean".to_string()),
+    };
+
+    match find_flag(&context.options.program_arguments, name, false) {
+        FlagValue::Missing => Ok(Some(Value::Bool(*default))),
+        FlagValue::Present => Ok(Some(Value::Bool(true))),
+        FlagValue::Value(value) => match value.as_str() {
+            "true" => Ok(Some(Value::Bool(true))),
+            "false" => Ok(Some(Value::Bool(false))),
+            _ => Err(format!("Flag --{} expects true or false, got '{}'", name, value)),
+        },
+    }
+}
@@ -0,0 +1,435 @@
+use super::expect_arguments;
+use super::try_builtin;
+use super::Builtin;
+use super::BuiltinModule;
+use crate::interpreter::Context;
+use crate::interpreter::Value;
+use crate::typechecker::Type;
+
+// File system access, which sandboxed embedders will usually want to leave out
+pub fn module() -> BuiltinModule {
+    BuiltinModule {
+        name: "io",
+        functions: vec![
+            Builtin {
+                name: "read_file",
+                param_names: &["path"],
+                signature: |arg_types| expect_arguments(arg_types, &[Type::String], Type::String),
+                implementation: read_file,
+            },
+            Builtin {
+                name: "try_read_file",
+                param_names: &["path"],
+                signature: |arg_types| {
+                    expect_arguments(
+                        arg_types,
+                        &[Type::String],
+                        Type::Result(Box::new(Type::String)),
+                    )
+                },
+                implementation: |args, context| try_builtin(read_file(args, context)),
+            },
+            Builtin {
+                name: "write_file",
+                param_names: &["path", "content"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::String, Type::String], Type::Undefined)
+                },
+                implementation: write_file,
+            },
+            Builtin {
+                name: "read_bytes",
+                param_names: &["path"],
+                signature: |arg_types| expect_arguments(arg_types, &[Type::String], Type::Bytes),
+                implementation: read_bytes,
+            },
+            Builtin {
+                name: "try_read_bytes",
+                param_names: &["path"],
+                signature: |arg_types| {
+                    expect_arguments(
+                        arg_types,
+                        &[Type::String],
+                        Type::Result(Box::new(Type::Bytes)),
+                    )
+                },
+                implementation: |args, context| try_builtin(read_bytes(args, context)),
+            },
+            Builtin {
+                name: "write_bytes",
+                param_names: &["path", "bytes"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::String, Type::Bytes], Type::Undefined)
+                },
+                implementation: write_bytes,
+            },
+            Builtin {
+                name: "write_ppm",
+                param_names: &["path", "width", "height", "pixels"],
+                signature: |arg_types| {
+                    expect_arguments(
+                        arg_types,
+                        &[
+                            Type::String,
+                            Type::Integer,
+                            Type::Integer,
+                            Type::List(Box::new(Type::Integer)),
+                        ],
+                        Type::Undefined,
+                    )
+                },
+                implementation: write_ppm,
+            },
+            Builtin {
+                name: "read_csv",
+                param_names: &["path"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::String], csv_rows_type())
+                },
+                implementation: read_csv,
+            },
+            Builtin {
+                name: "try_read_csv",
+                param_names: &["path"],
+                signature: |arg_types| {
+                    expect_arguments(
+                        arg_types,
+                        &[Type::String],
+                        Type::Result(Box::new(csv_rows_type())),
+                    )
+                },
+                implementation: |args, context| try_builtin(read_csv(args, context)),
+            },
+            Builtin {
+                name: "write_csv",
+                param_names: &["path", "rows"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::String, csv_rows_type()], Type::Undefined)
+                },
+                implementation: write_csv,
+            },
+            Builtin {
+                name: "open_file",
+                param_names: &["path", "mode"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::String, Type::String], Type::Resource)
+                },
+                implementation: open_file,
+            },
+            Builtin {
+                name: "read_lines",
+                param_names: &["file"],
+                signature: |arg_types| {
+                    expect_arguments(
+                        arg_types,
+                        &[Type::Resource],
+                        Type::List(Box::new(Type::String)),
+                    )
+                },
+                implementation: read_lines,
+            },
+            Builtin {
+                name: "write_line",
+                param_names: &["file", "line"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::Resource, Type::String], Type::Undefined)
+                },
+                implementation: write_line,
+            },
+        ],
+    }
+}
+
+fn read_file(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::String(path)] => match std::fs::read_to_string(path) {
+            Ok(content) => Ok(Some(Value::String(content.into()))),
+            Err(error) => Err(format!("Could not read file {}: {}", path, error)),
+        },
+        _ => Err("read_file expects a path".to_string()),
+    }
+}
+
+fn write_file(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::String(path), Value::String(content)] => match std::fs::write(path, content.as_str()) {
+            Ok(_) => Ok(None),
+            Err(error) => Err(format!("Could not write file {}: {}", path, error)),
+        },
+        _ => Err("write_file expects a path and a string".to_string()),
+    }
+}
+
+fn read_bytes(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::String(path)] => match std::fs::read(path) {
+            Ok(content) => Ok(Some(Value::Bytes(content))),
+            Err(error) => Err(format!("Could not read file {}: {}", path, error)),
+        },
+        _ => Err("read_bytes expects a path".to_string()),
+    }
+}
+
+fn write_bytes(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::String(path), Value::Bytes(content)] => match std::fs::write(path, content) {
+            Ok(_) => Ok(None),
+            Err(error) => Err(format!("Could not write file {}: {}", path, error)),
+        },
+        _ => Err("write_bytes expects a path and bytes".to_string()),
+    }
+}
+
+// Writes a binary PPM image, where pixels is a flat list of red, green and blue values
+// for every pixel, row by row starting at the top left
+fn write_ppm(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let (path, width, height, pixels) = match args.as_slice() {
+        [Value::String(path), Value::Number(width), Value::Number(height), Value::List(pixels)] => {
+            (path, *width, *height, pixels)
+        }
+        _ => {
+            return Err(
+                "write_ppm expects a path, a width, a height and a list of pixels".to_string(),
+            )
+        }
+    };
+
+    if width <= 0 || height <= 0 {
+        return Err(format!(
+            "Cannot write an image of {} by {} pixels",
+            width, height
+        ));
+    }
+
+    let expected_length = width * height * 3;
+    if pixels.len() as i64 != expected_length {
+        return Err(format!(
+            "An image of {} by {} pixels needs {} color values, got {}",
+            width,
+            height,
+            expected_length,
+            pixels.len()
+        ));
+    }
+
+    let mut content = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for value in pixels {
+        match value {
+            Value::Number(color) if (0..=255).contains(color) => content.push(*color as u8),
+            Value::Number(color) => {
+                return Err(format!("Color value {} is not between 0 and 255", color));
+            }
+            _ => return Err("write_ppm expects every color value to be an integer".to_string()),
+        }
+    }
+
+    match std::fs::write(path, content) {
+        Ok(_) => Ok(None),
+        Err(error) => Err(format!("Could not write file {}: {}", path, error)),
+    }
+}
+
+// A file opened by open_file, which is closed when the resource is closed
+enum OpenFile {
+    Reader(std::io::BufReader<std::fs::File>),
+    Writer(std::fs::File),
+}
+
+// Opens a file as a resource, the mode is "r" to read, "w" to overwrite or "a" to append
+fn open_file(args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    let (path, mode) = match args.as_slice() {
+        [Value::String(path), Value::String(mode)] => (path, mode),
+        _ => return Err("open_file expects a path and a mode".to_string()),
+    };
+
+    let file = match mode.as_str() {
+        "r" => {
+            std::fs::File::open(path).map(|file| OpenFile::Reader(std::io::BufReader::new(file)))
+        }
+        "w" => std::fs::File::create(path).map(OpenFile::Writer),
+        "a" => std::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .map(OpenFile::Writer),
+        _ => return Err(format!("Unknown file mode {}, expected r, w or a", mode)),
+    };
+
+    match file {
+        Ok(file) => Ok(Some(context.open_resource("file", Box::new(file)))),
+        Err(error) => Err(format!("Could not open file {}: {}", path, error)),
+    }
+}
+
+// The remaining lines of a file opened for reading
+fn read_lines(args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    use std::io::BufRead;
+
+    let resource = match args.as_slice() {
+        [Value::Resource(resource)] => resource,
+        _ => return Err("read_lines expects a file".to_string()),
+    };
+
+    let reader = match context.resource_mut::<OpenFile>(resource) {
+        Some(OpenFile::Reader(reader)) => reader,
+        Some(OpenFile::Writer(_)) => {
+            return Err("Cannot read from a file opened for writing".to_string())
+        }
+        None => return Err("read_lines expects an open file".to_string()),
+    };
+
+    let mut lines = Vec::new();
+    for line in reader.lines() {
+        match line {
+            Ok(line) => lines.push(Value::String(line.into())),
+            Err(error) => return Err(format!("Could not read file: {}", error)),
+        }
+    }
+
+    Ok(Some(Value::List(lines)))
+}
+
+fn write_line(args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    use std::io::Write;
+
+    let (resource, line) = match args.as_slice() {
+        [Value::Resource(resource), Value::String(line)] => (resource, line),
+        _ => return Err("write_line expects a file and a string".to_string()),
+    };
+
+    let file = match context.resource_mut::<OpenFile>(resource) {
+        Some(OpenFile::Writer(file)) => file,
+        Some(OpenFile::Reader(_)) => {
+            return Err("Cannot write to a file opened for reading".to_string())
+        }
+        None => return Err("write_line expects an open file".to_string()),
+    };
+
+    match writeln!(file, "{}", line) {
+        Ok(_) => Ok(None),
+        Err(error) => Err(format!("Could not write file: {}", error)),
+    }
+}
+
+// CSV files are represented as a list of rows, each of which is a list of fields
+fn csv_rows_type() -> Type {
+    Type::List(Box::new(Type::List(Box::new(Type::String))))
+}
+
+fn read_csv(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let path = match args.as_slice() {
+        [Value::String(path)] => path,
+        _ => return Err("read_csv expects a path".to_string()),
+    };
+
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(error) => return Err(format!("Could not read file {}: {}", path, error)),
+    };
+
+    let rows = parse_csv(&content)
+        .map_err(|message| format!("Could not read csv file {}: {}", path, message))?;
+
+    Ok(Some(Value::List(
+        rows.into_iter()
+            .map(|row| Value::List(row.into_iter().map(|field| Value::String(field.into())).collect()))
+            .collect(),
+    )))
+}
+
+fn write_csv(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let (path, rows) = match args.as_slice() {
+        [Value::String(path), Value::List(rows)] => (path, rows),
+        _ => return Err("write_csv expects a path and a list of rows".to_string()),
+    };
+
+    let mut content = String::new();
+    for row in rows {
+        let Value::List(fields) = row else {
+            return Err("write_csv expects every row to be a list of strings".to_string());
+        };
+
+        let mut formatted_fields = Vec::new();
+        for field in fields {
+            let Value::String(field) = field else {
+                return Err("write_csv expects every field to be a string".to_string());
+            };
+            formatted_fields.push(format_csv_field(field));
+        }
+
+        content.push_str(&formatted_fields.join(","));
+        content.push('\n');
+    }
+
+    match std::fs::write(path, content) {
+        Ok(_) => Ok(None),
+        Err(error) => Err(format!("Could not write file {}: {}", path, error)),
+    }
+}
+
+// Fields containing separators, quotes or newlines are quoted, with quotes doubled
+fn format_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Parses comma separated values, where fields can be quoted to contain commas, quotes and newlines
+fn parse_csv(content: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    // Whether anything was read since the last row ended, so that a trailing newline does not add an empty row
+    let mut row_started = false;
+
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => {
+                in_quotes = true;
+                row_started = true;
+            }
+            ',' => {
+                row.push(std::mem::take(&mut field));
+                row_started = true;
+            }
+            '\r' => {}
+            '\n' => {
+                if row_started || !field.is_empty() {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                row_started = false;
+            }
+            _ => {
+                field.push(c);
+                row_started = true;
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err("unterminated quoted field".to_string());
+    }
+    if row_started || !field.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
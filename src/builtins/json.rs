@@ -0,0 +1,329 @@
+use super::Builtin;
+use super::BuiltinModule;
+use crate::interpreter::Context;
+use crate::interpreter::value_to_string;
+use crate::interpreter::value_type_to_string;
+use crate::interpreter::DictKey;
+use crate::interpreter::Value;
+use crate::typechecker;
+use crate::typechecker::Type;
+use indexmap::IndexMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+// Writing values as JSON and reading structs back from it, for config files and APIs.
+// Structs become objects with their fields by name. from_json(Point, text) takes the struct to read,
+// every field of it has to be in the JSON and nothing else may be. Objects inside become dicts.
+pub fn module() -> BuiltinModule {
+    BuiltinModule {
+        name: "json",
+        functions: vec![
+            Builtin {
+                name: "to_json",
+                param_names: &["value"],
+                signature: to_json_signature,
+                implementation: to_json,
+            },
+            Builtin {
+                name: "from_json",
+                param_names: &["struct", "text"],
+                // The typechecker does not know struct names as values, see typechecker::check_type_rec
+                signature: |_| None,
+                implementation: from_json,
+            },
+        ],
+    }
+}
+
+fn to_json_signature(arg_types: &[Type]) -> Option<Type> {
+    match arg_types {
+        [value_type] if is_json_type(value_type) => Some(Type::String),
+        _ => None,
+    }
+}
+
+// Whether values of the type can be written as JSON. Dict keys become the names in an object, so they have to
+// be strings. The elements of an empty list or dict are undefined.
+fn is_json_type(value_type: &Type) -> bool {
+    let is_json_element = |element_type: &Type| *element_type == Type::Undefined || is_json_type(element_type);
+    match value_type {
+        Type::Integer | Type::Float | Type::Boolean | Type::String => true,
+        Type::List(element_type) => is_json_element(element_type),
+        Type::Tuple(element_types) => element_types.iter().all(is_json_type),
+        Type::Dict(key_type, value_type) => {
+            matches!(**key_type, Type::String | Type::Undefined) && is_json_element(value_type)
+        }
+        Type::Struct(struct_type) => struct_type.fields.iter().all(|(_, field_type)| is_json_type(field_type)),
+        _ => false,
+    }
+}
+
+fn to_json(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [value] => {
+            let mut json = String::new();
+            write_json(value, &mut json)?;
+            Ok(Some(Value::String(json.into())))
+        }
+        _ => Err("to_json expects one value".to_string()),
+    }
+}
+
+fn write_json(value: &Value, json: &mut String) -> Result<(), String> {
+    match value {
+        Value::Number(number) => json.push_str(&number.to_string()),
+        // Written with a decimal point or an exponent, so it is read back as a float
+        Value::Float(number) if number.is_finite() => json.push_str(&format!("{:?}", number)),
+        Value::Float(number) => return Err(format!("Cannot write {} as JSON", number)),
+        Value::Bool(value) => json.push_str(&value.to_string()),
+        Value::String(text) => write_json_string(text, json),
+        Value::List(values) | Value::Tuple(values) => {
+            json.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    json.push_str(", ");
+                }
+                write_json(value, json)?;
+            }
+            json.push(']');
+        }
+        Value::Dict(entries) => {
+            json.push('{');
+            for (index, (key, value)) in entries.iter().enumerate() {
+                let DictKey::String(key) = key else {
+                    return Err("Only dicts with string keys can be written as JSON".to_string());
+                };
+                if index > 0 {
+                    json.push_str(", ");
+                }
+                write_json_string(key, json);
+                json.push_str(": ");
+                write_json(value, json)?;
+            }
+            json.push('}');
+        }
+        Value::Struct { fields, .. } => {
+            json.push('{');
+            for (index, (field_name, value)) in fields.iter().enumerate() {
+                if index > 0 {
+                    json.push_str(", ");
+                }
+                write_json_string(field_name, json);
+                json.push_str(": ");
+                write_json(value, json)?;
+            }
+            json.push('}');
+        }
+        other => return Err(format!("Cannot write a {} as JSON", value_type_to_string(other))),
+    }
+    Ok(())
+}
+
+fn write_json_string(text: &str, json: &mut String) {
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+// The struct with its fields read from the JSON object of the same names
+fn from_json(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let (name, fields, text) = match args.as_slice() {
+        [Value::StructDefinition { name, fields }, Value::String(text)] => (name, fields, text),
+        _ => return Err("from_json expects a struct and a string".to_string()),
+    };
+
+    let mut chars = text.chars().peekable();
+    skip_whitespace(&mut chars);
+    if chars.peek() != Some(&'{') {
+        return Err(format!("Expected a JSON object for struct {}", name));
+    }
+    let mut entries = read_object(&mut chars)?;
+    skip_whitespace(&mut chars);
+    if let Some(c) = chars.next() {
+        return Err(format!("Unexpected '{}' after the JSON object", c));
+    }
+
+    let mut values = Vec::new();
+    for field in fields {
+        match entries.shift_remove(&DictKey::String(field.clone())) {
+            Some(value) => values.push((field.clone(), value)),
+            None => return Err(format!("The JSON has no value for the field {} of struct {}", field, name)),
+        }
+    }
+    if let Some(key) = entries.keys().next() {
+        let key = value_to_string(&key.to_value());
+        return Err(format!("The JSON has a value for {}, which is not a field of struct {}", key, name));
+    }
+
+    Ok(Some(Value::Struct {
+        name: name.clone(),
+        fields: values,
+    }))
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn expect_char(chars: &mut Peekable<Chars>, expected: char) -> Result<(), String> {
+    skip_whitespace(chars);
+    match chars.next() {
+        Some(c) if c == expected => Ok(()),
+        Some(c) => Err(format!("Expected '{}' in the JSON, but found '{}'", expected, c)),
+        None => Err(format!("Expected '{}' in the JSON, but it ended", expected)),
+    }
+}
+
+// A JSON value as a rosy value. Numbers without a decimal point or exponent become integers.
+// A list or dict can only hold values of one type, which is checked as it is read.
+fn read_value(chars: &mut Peekable<Chars>) -> Result<Value, String> {
+    skip_whitespace(chars);
+    let value = match chars.peek() {
+        Some('{') => Value::Dict(read_object(chars)?),
+        Some('[') => {
+            chars.next();
+            let mut values = Vec::new();
+            skip_whitespace(chars);
+            if chars.next_if_eq(&']').is_none() {
+                loop {
+                    values.push(read_value(chars)?);
+                    skip_whitespace(chars);
+                    if chars.next_if_eq(&',').is_none() {
+                        expect_char(chars, ']')?;
+                        break;
+                    }
+                }
+            }
+            Value::List(values)
+        }
+        Some('"') => Value::String(read_string(chars)?.into()),
+        Some(c) if *c == '-' || c.is_ascii_digit() => read_number(chars)?,
+        Some(c) if c.is_alphabetic() => {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_alphabetic()) {
+                word.push(c);
+            }
+            match word.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "null" => return Err("JSON null has no value in rosy".to_string()),
+                _ => return Err(format!("Unexpected '{}' in the JSON", word)),
+            }
+        }
+        Some(c) => return Err(format!("Unexpected '{}' in the JSON", c)),
+        None => return Err("Expected a value in the JSON, but it ended".to_string()),
+    };
+
+    if typechecker::value_to_type(&value).is_none() {
+        let kind = match value {
+            Value::Dict(_) => "object",
+            _ => "array",
+        };
+        return Err(format!("The values in a JSON {} have different types", kind));
+    }
+    Ok(value)
+}
+
+// The names and values of a JSON object, starting at its opening brace
+fn read_object(chars: &mut Peekable<Chars>) -> Result<IndexMap<DictKey, Value>, String> {
+    chars.next();
+    let mut entries = IndexMap::new();
+    skip_whitespace(chars);
+    if chars.next_if_eq(&'}').is_some() {
+        return Ok(entries);
+    }
+    loop {
+        skip_whitespace(chars);
+        if chars.peek() != Some(&'"') {
+            return Err("Expected a string as the name of a value in a JSON object".to_string());
+        }
+        let key = read_string(chars)?;
+        expect_char(chars, ':')?;
+        // A name given twice keeps its last value
+        entries.insert(DictKey::String(key), read_value(chars)?);
+        skip_whitespace(chars);
+        if chars.next_if_eq(&',').is_none() {
+            expect_char(chars, '}')?;
+            return Ok(entries);
+        }
+    }
+}
+
+fn read_number(chars: &mut Peekable<Chars>) -> Result<Value, String> {
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+        number.push(c);
+    }
+    if number.contains(['.', 'e', 'E']) {
+        match number.parse::<f64>() {
+            Ok(number) => Ok(Value::Float(number)),
+            Err(_) => Err(format!("{} is not a number", number)),
+        }
+    } else {
+        match number.parse::<i64>() {
+            Ok(number) => Ok(Value::Number(number)),
+            Err(_) => Err(format!("{} is not an integer", number)),
+        }
+    }
+}
+
+fn read_string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    chars.next();
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(text),
+            Some('\\') => match chars.next() {
+                Some('"') => text.push('"'),
+                Some('\\') => text.push('\\'),
+                Some('/') => text.push('/'),
+                Some('b') => text.push('\u{8}'),
+                Some('f') => text.push('\u{c}'),
+                Some('n') => text.push('\n'),
+                Some('r') => text.push('\r'),
+                Some('t') => text.push('\t'),
+                Some('u') => {
+                    let mut code = read_code_unit(chars)?;
+                    // A character outside the basic plane is written as two escapes
+                    if (0xD800..0xDC00).contains(&code) {
+                        if chars.next() != Some('\\') || chars.next() != Some('u') {
+                            return Err("Expected the second half of a surrogate pair in a JSON string".to_string());
+                        }
+                        let low = read_code_unit(chars)?;
+                        if !(0xDC00..0xE000).contains(&low) {
+                            return Err("Expected the second half of a surrogate pair in a JSON string".to_string());
+                        }
+                        code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                    }
+                    match char::from_u32(code) {
+                        Some(c) => text.push(c),
+                        None => return Err(format!("\\u{:04x} is not a character", code)),
+                    }
+                }
+                Some(c) => return Err(format!("Unknown escape \\{} in a JSON string", c)),
+                None => return Err("A JSON string is not closed".to_string()),
+            },
+            Some(c) => text.push(c),
+            None => return Err("A JSON string is not closed".to_string()),
+        }
+    }
+}
+
+// The four hexadecimal digits after \u
+fn read_code_unit(chars: &mut Peekable<Chars>) -> Result<u32, String> {
+    let digits: String = chars.take(4).collect();
+    match u32::from_str_radix(&digits, 16) {
+        Ok(code) if digits.len() == 4 => Ok(code),
+        _ => Err(format!("\\u{} is not a character", digits)),
+    }
+}
@@ -0,0 +1,101 @@
+use super::Builtin;
+use super::BuiltinModule;
+use crate::interpreter::Context;
+use crate::interpreter::Value;
+use crate::typechecker::Type;
+
+// Helpers for lists, such as grids represented as a list of rows
+pub fn module() -> BuiltinModule {
+    BuiltinModule {
+        name: "list",
+        functions: vec![
+            Builtin {
+                name: "make_grid",
+                param_names: &["rows", "cols", "fill"],
+                signature: make_grid_signature,
+                implementation: make_grid,
+            },
+            Builtin {
+                name: "dimensions",
+                param_names: &["grid"],
+                signature: dimensions_signature,
+                implementation: dimensions,
+            },
+        ],
+    }
+}
+
+fn make_grid_signature(arg_types: &[Type]) -> Option<Type> {
+    match arg_types {
+        [Type::Integer, Type::Integer, fill_type] if *fill_type != Type::Undefined => Some(
+            Type::List(Box::new(Type::List(Box::new(fill_type.clone())))),
+        ),
+        _ => None,
+    }
+}
+
+fn dimensions_signature(arg_types: &[Type]) -> Option<Type> {
+    match arg_types {
+        [Type::List(row_type)] if matches!(**row_type, Type::List(_)) => {
+            Some(Type::List(Box::new(Type::Integer)))
+        }
+        _ => None,
+    }
+}
+
+// The most cells a grid made by make_grid may have. A program asking for more gets an error
+// instead of the allocation failing, which would abort the whole process.
+const MAX_GRID_CELLS: i64 = 1 << 24;
+
+// A grid of rows by cols, with every cell set to fill
+fn make_grid(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let (rows, cols, fill) = match args.as_slice() {
+        [Value::Number(rows), Value::Number(cols), fill] => (*rows, *cols, fill),
+        _ => return Err("make_grid expects two integers and a fill value".to_string()),
+    };
+
+    if rows < 0 || cols < 0 {
+        return Err(format!("Cannot make a grid of {} by {}", rows, cols));
+    }
+    // Every row is allocated even when it has no cells
+    if rows.checked_mul(cols.max(1)).is_none_or(|cells| cells > MAX_GRID_CELLS) {
+        return Err(format!(
+            "Cannot make a grid of {} by {}, as it would have more than {} cells",
+            rows, cols, MAX_GRID_CELLS
+        ));
+    }
+
+    let row = Value::List(vec![fill.clone(); cols as usize]);
+    Ok(Some(Value::List(vec![row; rows as usize])))
+}
+
+// The number of rows and columns of a grid, as [rows, cols]
+fn dimensions(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let rows = match args.as_slice() {
+        [Value::List(rows)] => rows,
+        _ => return Err("dimensions expects a list of lists".to_string()),
+    };
+
+    let mut cols = None;
+    for row in rows {
+        let Value::List(row) = row else {
+            return Err("dimensions expects a list of lists".to_string());
+        };
+
+        match cols {
+            Some(cols) if cols != row.len() => {
+                return Err(format!(
+                    "Grid rows have different lengths: {} and {}",
+                    cols,
+                    row.len()
+                ));
+            }
+            _ => cols = Some(row.len()),
+        }
+    }
+
+    Ok(Some(Value::List(vec![
+        Value::Number(rows.len() as i64),
+        Value::Number(cols.unwrap_or(0) as i64),
+    ])))
+}
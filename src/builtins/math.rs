@@ -0,0 +1,295 @@
+use super::expect_arguments;
+use super::try_builtin;
+use super::Builtin;
+use super::BuiltinModule;
+use crate::complex::Complex;
+use crate::interpreter::Context;
+use crate::interpreter::Value;
+use crate::rational::Rational;
+use crate::typechecker::Type;
+
+pub fn module() -> BuiltinModule {
+    BuiltinModule {
+        name: "math",
+        functions: vec![
+            Builtin {
+                name: "abs",
+                param_names: &["value"],
+                signature: |arg_types| expect_arguments(arg_types, &[Type::Integer], Type::Integer),
+                implementation: abs,
+            },
+            Builtin {
+                name: "min",
+                param_names: &["a", "b"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::Integer, Type::Integer], Type::Integer)
+                },
+                implementation: min,
+            },
+            Builtin {
+                name: "max",
+                param_names: &["a", "b"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::Integer, Type::Integer], Type::Integer)
+                },
+                implementation: max,
+            },
+            Builtin {
+                name: "random",
+                param_names: &["low", "high"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::Integer, Type::Integer], Type::Integer)
+                },
+                implementation: random,
+            },
+            Builtin {
+                name: "to_base",
+                param_names: &["value", "base"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::Integer, Type::Integer], Type::String)
+                },
+                implementation: to_base,
+            },
+            Builtin {
+                name: "from_base",
+                param_names: &["text", "base"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::String, Type::Integer], Type::Integer)
+                },
+                implementation: from_base,
+            },
+            Builtin {
+                name: "try_from_base",
+                param_names: &["text", "base"],
+                signature: |arg_types| {
+                    expect_arguments(
+                        arg_types,
+                        &[Type::String, Type::Integer],
+                        Type::Result(Box::new(Type::Integer)),
+                    )
+                },
+                implementation: |args, context| try_builtin(from_base(args, context)),
+            },
+            Builtin {
+                name: "rational",
+                param_names: &["numerator", "denominator"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::Integer, Type::Integer], Type::Rational)
+                },
+                implementation: rational,
+            },
+            Builtin {
+                name: "complex",
+                param_names: &["re", "im"],
+                signature: complex_signature,
+                implementation: complex,
+            },
+            Builtin {
+                name: "real",
+                param_names: &["value"],
+                signature: |arg_types| expect_arguments(arg_types, &[Type::Complex], Type::Float),
+                implementation: real,
+            },
+            Builtin {
+                name: "imag",
+                param_names: &["value"],
+                signature: |arg_types| expect_arguments(arg_types, &[Type::Complex], Type::Float),
+                implementation: imag,
+            },
+            Builtin {
+                name: "magnitude",
+                param_names: &["value"],
+                signature: |arg_types| expect_arguments(arg_types, &[Type::Complex], Type::Float),
+                implementation: magnitude,
+            },
+            Builtin {
+                name: "float",
+                param_names: &["value"],
+                signature: float_signature,
+                implementation: float,
+            },
+            Builtin {
+                name: "int",
+                param_names: &["value"],
+                signature: |arg_types| expect_arguments(arg_types, &[Type::Float], Type::Integer),
+                implementation: int,
+            },
+        ],
+    }
+}
+
+fn abs(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Number(value)] => match value.checked_abs() {
+            Some(absolute) => Ok(Some(Value::Number(absolute))),
+            None => Err(format!("The absolute value of {} is too large for an integer", value)),
+        },
+        _ => Err("abs expects a single integer".to_string()),
+    }
+}
+
+fn min(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Number(a), Value::Number(b)] => Ok(Some(Value::Number(*a.min(b)))),
+        _ => Err("min expects two integers".to_string()),
+    }
+}
+
+fn max(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Number(a), Value::Number(b)] => Ok(Some(Value::Number(*a.max(b)))),
+        _ => Err("max expects two integers".to_string()),
+    }
+}
+
+// A random integer from low up to, but not including, high
+fn random(args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Number(low), Value::Number(high)] => {
+            if low >= high {
+                return Err(format!("Cannot pick a random number from {} up to {}", low, high));
+            }
+            let range = high.abs_diff(*low);
+            let offset = context.next_random() % range;
+            Ok(Some(Value::Number(low.wrapping_add_unsigned(offset))))
+        }
+        _ => Err("random expects two integers".to_string()),
+    }
+}
+
+fn check_base(base: i64) -> Result<u32, String> {
+    if (2..=36).contains(&base) {
+        Ok(base as u32)
+    } else {
+        Err(format!("Base {} is not between 2 and 36", base))
+    }
+}
+
+// Writes the integer in the given base, using lowercase letters for digits above 9
+fn to_base(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let (value, base) = match args.as_slice() {
+        [Value::Number(value), Value::Number(base)] => (*value, check_base(*base)?),
+        _ => return Err("to_base expects two integers".to_string()),
+    };
+
+    let mut remaining = value.unsigned_abs();
+    let mut digits = Vec::new();
+    loop {
+        let digit = (remaining % base as u64) as u32;
+        digits.push(std::char::from_digit(digit, base).unwrap());
+        remaining /= base as u64;
+        if remaining == 0 {
+            break;
+        }
+    }
+
+    if value < 0 {
+        digits.push('-');
+    }
+
+    Ok(Some(Value::String(digits.iter().rev().collect::<String>().into())))
+}
+
+// Reads an integer written in the given base, digits above 9 can be upper or lowercase
+fn from_base(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let (text, base) = match args.as_slice() {
+        [Value::String(text), Value::Number(base)] => (text, check_base(*base)?),
+        _ => return Err("from_base expects a string and an integer".to_string()),
+    };
+
+    match i64::from_str_radix(text, base) {
+        Ok(value) => Ok(Some(Value::Number(value))),
+        Err(error) => Err(format!(
+            "Cannot read '{}' as a number in base {}: {}",
+            text, base, error
+        )),
+    }
+}
+
+// The exact fraction numerator / denominator, in lowest terms
+fn rational(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Number(numerator), Value::Number(denominator)] => Ok(Some(Value::Rational(
+            Rational::new(*numerator, *denominator)?,
+        ))),
+        _ => Err("rational expects two integers".to_string()),
+    }
+}
+
+// The parts of a complex number can be integers or rational numbers
+fn complex_signature(arg_types: &[Type]) -> Option<Type> {
+    match arg_types {
+        [Type::Integer | Type::Rational, Type::Integer | Type::Rational] => Some(Type::Complex),
+        _ => None,
+    }
+}
+
+fn complex_part(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(value) => Some(*value as f64),
+        Value::Rational(value) => Some(value.to_f64()),
+        _ => None,
+    }
+}
+
+fn complex(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [re, im] => match (complex_part(re), complex_part(im)) {
+            (Some(re), Some(im)) => Ok(Some(Value::Complex(Complex::new(re, im)))),
+            _ => Err("complex expects two integers or rational numbers".to_string()),
+        },
+        _ => Err("complex expects a real and an imaginary part".to_string()),
+    }
+}
+
+fn real(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Complex(value)] => Ok(Some(Value::Float(value.re))),
+        _ => Err("real expects a complex number".to_string()),
+    }
+}
+
+fn imag(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Complex(value)] => Ok(Some(Value::Float(value.im))),
+        _ => Err("imag expects a complex number".to_string()),
+    }
+}
+
+fn magnitude(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Complex(value)] => Ok(Some(Value::Float(value.magnitude()))),
+        _ => Err("magnitude expects a complex number".to_string()),
+    }
+}
+
+fn float_signature(arg_types: &[Type]) -> Option<Type> {
+    match arg_types {
+        [Type::Integer | Type::Rational | Type::Float] => Some(Type::Float),
+        _ => None,
+    }
+}
+
+// Converts an integer or rational number to a float, see LanguageOptions::strict_numerics
+fn float(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Number(value)] => Ok(Some(Value::Float(*value as f64))),
+        [Value::Rational(value)] => Ok(Some(Value::Float(value.to_f64()))),
+        [Value::Float(value)] => Ok(Some(Value::Float(*value))),
+        _ => Err("float expects an integer, rational number or float".to_string()),
+    }
+}
+
+// Converts a float to an integer, rounding towards zero
+fn int(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Float(value)] => {
+            let truncated = value.trunc();
+            if !truncated.is_finite() || truncated < i64::MIN as f64 || truncated >= i64::MAX as f64 {
+                return Err(format!("Cannot convert {} to an integer", value));
+            }
+            Ok(Some(Value::Number(truncated as i64)))
+        }
+        _ => Err("int expects a float".to_string()),
+    }
+}
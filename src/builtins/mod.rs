@@ -0,0 +1,280 @@
+use crate::interpreter::Context;
+use crate::interpreter::Value;
+use crate::typechecker::type_to_string;
+use crate::typechecker::Type;
+
+#[cfg(feature = "core")]
+mod core;
+#[cfg(feature = "dict")]
+mod dict;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "flags")]
+mod flags;
+#[cfg(feature = "io")]
+mod io;
+#[cfg(feature = "list")]
+mod list;
+#[cfg(feature = "math")]
+mod math;
+#[cfg(feature = "string")]
+mod string;
+#[cfg(feature = "terminal")]
+mod terminal;
+#[cfg(feature = "testing")]
+mod testing;
+#[cfg(feature = "time")]
+mod time;
+
+// The builtin functions are grouped into modules, each of which can be left out of the build
+// by disabling its cargo feature. This allows embedders to compile a minimal interpreter,
+// for example one without the io module so scripts cannot touch the file system.
+
+// Computes the return type of a builtin for the given argument types,
+// or None if the builtin cannot be called with these argument types
+pub type BuiltinSignature = fn(&[Type]) -> Option<Type>;
+
+// Runs a builtin on the given argument values.
+// Errors are reported at the location of the function call by the interpreter.
+pub type BuiltinImplementation = fn(Vec<Value>, &mut Context) -> Result<Option<Value>, String>;
+
+#[derive(Clone)]
+pub struct Builtin {
+    pub name: &'static str,
+    // A name written as *values stands for one or more arguments, as for print
+    pub param_names: &'static [&'static str],
+    pub signature: BuiltinSignature,
+    pub implementation: BuiltinImplementation,
+}
+
+impl Builtin {
+    // The names of the parameters for a call with this many arguments.
+    // A *values name is repeated for every argument it stands for: values, values_2.
+    pub fn param_names_for(&self, arg_count: usize) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for param_name in self.param_names {
+            match param_name.strip_prefix('*') {
+                Some(name) => {
+                    let count = arg_count.saturating_sub(names.len()).max(1);
+                    names.extend((1..=count).map(|number| match number {
+                        1 => name.to_string(),
+                        _ => format!("{}_{}", name, number),
+                    }));
+                }
+                None => names.push(param_name.to_string()),
+            }
+        }
+        names
+    }
+
+    // The ways this builtin can be called written as rosy code, one for each number of arguments it accepts,
+    // such as slice(text: string | bytes, start: integer, end: integer).
+    // The signature is a function, so the accepted types are found by trying it with the probe types.
+    pub fn describe_signatures(&self) -> Vec<String> {
+        let probe_types = probe_types();
+        let mut descriptions = Vec::new();
+
+        for arg_count in 0..=self.param_names.len() {
+            // The types accepted for each parameter, over all accepted calls with this many arguments
+            let mut accepted_types: Vec<Vec<Type>> = vec![Vec::new(); arg_count];
+            let mut is_accepted = false;
+
+            for arg_types in type_combinations(&probe_types, arg_count) {
+                if (self.signature)(&arg_types).is_none() {
+                    continue;
+                }
+                is_accepted = true;
+                for (accepted, arg_type) in accepted_types.iter_mut().zip(arg_types) {
+                    if !accepted.contains(&arg_type) {
+                        accepted.push(arg_type);
+                    }
+                }
+            }
+
+            if !is_accepted {
+                continue;
+            }
+
+            let params: Vec<String> = self
+                .param_names
+                .iter()
+                .zip(accepted_types.iter())
+                .map(|(param_name, accepted)| {
+                    format!("{}: {}", param_name, describe_types(accepted, &probe_types))
+                })
+                .collect();
+            descriptions.push(format!("{}({})", self.name, params.join(", ")));
+        }
+
+        descriptions
+    }
+}
+
+// The argument types a signature is tried with, one or more of every kind of type
+fn probe_types() -> Vec<Type> {
+    Vec::from([
+        Type::Integer,
+        Type::Float,
+        Type::Boolean,
+        Type::String,
+        Type::Bytes,
+        Type::Rational,
+        Type::Complex,
+        Type::Resource,
+        Type::List(Box::new(Type::Integer)),
+        Type::List(Box::new(Type::Float)),
+        Type::List(Box::new(Type::String)),
+        Type::List(Box::new(Type::List(Box::new(Type::Integer)))),
+        Type::Generator(Box::new(Type::Integer)),
+        Type::Result(Box::new(Type::Integer)),
+        Type::Result(Box::new(Type::String)),
+        Type::Dict(Box::new(Type::String), Box::new(Type::Integer)),
+    ])
+}
+
+// Every list of the given length made of the given types
+fn type_combinations(types: &[Type], length: usize) -> Vec<Vec<Type>> {
+    let mut combinations: Vec<Vec<Type>> = Vec::from([Vec::new()]);
+    for _ in 0..length {
+        combinations = combinations
+            .iter()
+            .flat_map(|combination| {
+                types.iter().map(move |next_type| {
+                    let mut extended = combination.clone();
+                    extended.push(next_type.clone());
+                    extended
+                })
+            })
+            .collect();
+    }
+    combinations
+}
+
+// Describes the accepted types of a parameter like: integer | float.
+// A kind of type of which every probe is accepted, such as all lists, is described by just its kind.
+fn describe_types(accepted: &[Type], probe_types: &[Type]) -> String {
+    if accepted.len() == probe_types.len() {
+        return String::from("any");
+    }
+
+    let kind_name = |value_type: &Type| match value_type {
+        Type::List(_) => Some("list"),
+        Type::Generator(_) => Some("generator"),
+        Type::Result(_) => Some("result"),
+        Type::Dict(_, _) => Some("dict"),
+        _ => None,
+    };
+
+    let mut descriptions: Vec<String> = Vec::new();
+    for accepted_type in accepted {
+        let description = match kind_name(accepted_type) {
+            Some(kind)
+                if probe_types
+                    .iter()
+                    .filter(|probe_type| kind_name(probe_type) == Some(kind))
+                    .all(|probe_type| accepted.contains(probe_type)) =>
+            {
+                kind.to_string()
+            }
+            _ => type_to_string(accepted_type),
+        };
+        if !descriptions.contains(&description) {
+            descriptions.push(description);
+        }
+    }
+
+    descriptions.join(" | ")
+}
+
+pub struct BuiltinModule {
+    pub name: &'static str,
+    pub functions: Vec<Builtin>,
+}
+
+// All builtin modules which are enabled in this build
+#[allow(unused_mut, clippy::vec_init_then_push)]
+pub fn enabled_modules() -> Vec<BuiltinModule> {
+    let mut modules = Vec::new();
+
+    #[cfg(feature = "core")]
+    modules.push(core::module());
+    #[cfg(feature = "math")]
+    modules.push(math::module());
+    #[cfg(feature = "io")]
+    modules.push(io::module());
+    #[cfg(feature = "string")]
+    modules.push(string::module());
+    #[cfg(feature = "time")]
+    modules.push(time::module());
+    #[cfg(feature = "flags")]
+    modules.push(flags::module());
+    #[cfg(feature = "terminal")]
+    modules.push(terminal::module());
+    #[cfg(feature = "list")]
+    modules.push(list::module());
+    #[cfg(feature = "dict")]
+    modules.push(dict::module());
+    #[cfg(feature = "json")]
+    modules.push(json::module());
+    #[cfg(feature = "testing")]
+    modules.push(testing::module());
+
+    modules
+}
+
+pub fn enabled_builtins() -> Vec<Builtin> {
+    enabled_modules()
+        .into_iter()
+        .flat_map(|module| module.functions)
+        .collect()
+}
+
+pub fn find_builtin(name: &str) -> Option<Builtin> {
+    enabled_builtins()
+        .into_iter()
+        .find(|builtin| builtin.name == name)
+}
+
+pub fn is_builtin(name: &str) -> bool {
+    find_builtin(name).is_some()
+}
+
+// Builtins whose result only depends on their arguments and which have no other effect,
+// so a call with constant arguments can be evaluated before the program runs, see consteval
+const PURE_BUILTINS: &[&str] = &[
+    "abs", "min", "max", "to_base", "from_base", "try_from_base", "rational", "complex", "real", "imag",
+    "magnitude", "float", "int", "find", "slice", "to_bytes", "from_bytes", "try_from_bytes", "pad_left",
+    "pad_right", "repeat", "make_grid", "dimensions", "keys", "values", "items", "get", "remove", "to_json", "is_error",
+    "error_message", "unwrap",
+];
+
+pub fn is_pure(name: &str) -> bool {
+    PURE_BUILTINS.contains(&name) && is_builtin(name)
+}
+
+// Signature helper for builtins which take a fixed list of argument types
+#[cfg(any(
+    feature = "math",
+    feature = "io",
+    feature = "string",
+    feature = "time",
+    feature = "flags",
+    feature = "terminal"
+))]
+fn expect_arguments(arg_types: &[Type], expected: &[Type], return_type: Type) -> Option<Type> {
+    if arg_types == expected {
+        Some(return_type)
+    } else {
+        None
+    }
+}
+
+// Turns the failure of a builtin into an error value, used by the try_ variants of builtins
+// which can fail so a script can recover using is_error() and error_message()
+#[cfg(any(feature = "math", feature = "io", feature = "string"))]
+fn try_builtin(result: Result<Option<Value>, String>) -> Result<Option<Value>, String> {
+    match result {
+        Err(message) => Ok(Some(Value::Error(message))),
+        result => result,
+    }
+}
@@ -0,0 +1,234 @@
+use super::expect_arguments;
+use super::try_builtin;
+use super::Builtin;
+use super::BuiltinModule;
+use crate::interpreter::Context;
+use crate::interpreter::Value;
+use crate::typechecker::Type;
+
+// String functions. Positions and lengths count characters, not bytes.
+// Also contains the conversions between strings and bytes.
+pub fn module() -> BuiltinModule {
+    BuiltinModule {
+        name: "string",
+        functions: vec![
+            Builtin {
+                name: "find",
+                param_names: &["text", "pattern"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::String, Type::String], Type::Integer)
+                },
+                implementation: find,
+            },
+            Builtin {
+                name: "slice",
+                param_names: &["text", "start", "end"],
+                signature: slice_signature,
+                implementation: slice,
+            },
+            Builtin {
+                name: "to_bytes",
+                param_names: &["value", "encoding"],
+                signature: to_bytes_signature,
+                implementation: to_bytes,
+            },
+            Builtin {
+                name: "from_bytes",
+                param_names: &["bytes", "encoding"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::Bytes, Type::String], Type::String)
+                },
+                implementation: from_bytes,
+            },
+            Builtin {
+                name: "try_from_bytes",
+                param_names: &["bytes", "encoding"],
+                signature: |arg_types| {
+                    expect_arguments(
+                        arg_types,
+                        &[Type::Bytes, Type::String],
+                        Type::Result(Box::new(Type::String)),
+                    )
+                },
+                implementation: |args, context| try_builtin(from_bytes(args, context)),
+            },
+            Builtin {
+                name: "pad_left",
+                param_names: &["text", "width", "fill"],
+                signature: pad_signature,
+                implementation: pad_left,
+            },
+            Builtin {
+                name: "pad_right",
+                param_names: &["text", "width", "fill"],
+                signature: pad_signature,
+                implementation: pad_right,
+            },
+            Builtin {
+                name: "repeat",
+                param_names: &["text", "count"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::String, Type::Integer], Type::String)
+                },
+                implementation: repeat,
+            },
+        ],
+    }
+}
+
+// Both strings and bytes can be sliced, giving a value of the same type
+fn slice_signature(arg_types: &[Type]) -> Option<Type> {
+    match arg_types {
+        [Type::String, Type::Integer, Type::Integer] => Some(Type::String),
+        [Type::Bytes, Type::Integer, Type::Integer] => Some(Type::Bytes),
+        _ => None,
+    }
+}
+
+// Strings are encoded with an encoding, a list of integers is converted byte by byte
+fn to_bytes_signature(arg_types: &[Type]) -> Option<Type> {
+    match arg_types {
+        [Type::String, Type::String] => Some(Type::Bytes),
+        [Type::List(element_type)] if **element_type == Type::Integer => Some(Type::Bytes),
+        _ => None,
+    }
+}
+
+// The fill string is optional and defaults to a space
+fn pad_signature(arg_types: &[Type]) -> Option<Type> {
+    match arg_types {
+        [Type::String, Type::Integer] | [Type::String, Type::Integer, Type::String] => {
+            Some(Type::String)
+        }
+        _ => None,
+    }
+}
+
+// The index of the first occurrence of pattern in text, or -1 if it does not occur
+fn find(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::String(text), Value::String(pattern)] => {
+            Ok(Some(Value::Number(rosy_rt::text::find(text, pattern))))
+        }
+        _ => Err("find expects two strings".to_string()),
+    }
+}
+
+// The characters (or bytes) from start up to, but not including, end
+fn slice(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Bytes(bytes), Value::Number(start), Value::Number(end)] => {
+            let range = rosy_rt::index::check_slice(*start, *end, bytes.len(), "bytes")?;
+            Ok(Some(Value::Bytes(bytes[range].to_vec())))
+        }
+        [Value::String(text), Value::Number(start), Value::Number(end)] => {
+            Ok(Some(Value::String(rosy_rt::text::slice(text, *start, *end)?.into())))
+        }
+        _ => Err("slice expects a string or bytes and two integers".to_string()),
+    }
+}
+
+fn to_bytes(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::String(text), Value::String(encoding)] => {
+            Ok(Some(Value::Bytes(encode(text, encoding)?)))
+        }
+        [Value::List(values)] => {
+            let mut bytes = Vec::new();
+            for value in values {
+                match value {
+                    Value::Number(byte) if (0..=255).contains(byte) => bytes.push(*byte as u8),
+                    Value::Number(byte) => {
+                        return Err(format!("Cannot convert {} to a byte", byte));
+                    }
+                    _ => return Err("to_bytes expects a list of integers".to_string()),
+                }
+            }
+            Ok(Some(Value::Bytes(bytes)))
+        }
+        _ => Err("to_bytes expects a string and an encoding, or a list of integers".to_string()),
+    }
+}
+
+fn from_bytes(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Bytes(bytes), Value::String(encoding)] => {
+            Ok(Some(Value::String(decode(bytes, encoding)?.into())))
+        }
+        _ => Err("from_bytes expects bytes and an encoding".to_string()),
+    }
+}
+
+// The supported encodings are utf-8, ascii and latin-1
+fn encode(text: &str, encoding: &str) -> Result<Vec<u8>, String> {
+    match encoding.to_lowercase().as_str() {
+        "utf-8" | "utf8" => Ok(text.as_bytes().to_vec()),
+        "ascii" => match text.chars().find(|c| !c.is_ascii()) {
+            Some(c) => Err(format!("Cannot encode '{}' as ascii", c)),
+            None => Ok(text.as_bytes().to_vec()),
+        },
+        "latin-1" | "latin1" => text
+            .chars()
+            .map(|c| match u8::try_from(c) {
+                Ok(byte) => Ok(byte),
+                Err(_) => Err(format!("Cannot encode '{}' as latin-1", c)),
+            })
+            .collect(),
+        _ => Err(format!("Unknown encoding '{}'", encoding)),
+    }
+}
+
+fn decode(bytes: &[u8], encoding: &str) -> Result<String, String> {
+    match encoding.to_lowercase().as_str() {
+        "utf-8" | "utf8" => match String::from_utf8(bytes.to_vec()) {
+            Ok(text) => Ok(text),
+            Err(error) => Err(format!("Cannot decode bytes as utf-8: {}", error)),
+        },
+        "ascii" => match bytes.iter().find(|byte| !byte.is_ascii()) {
+            Some(byte) => Err(format!("Cannot decode byte {} as ascii", byte)),
+            None => Ok(bytes.iter().map(|byte| *byte as char).collect()),
+        },
+        "latin-1" | "latin1" => Ok(bytes.iter().map(|byte| *byte as char).collect()),
+        _ => Err(format!("Unknown encoding '{}'", encoding)),
+    }
+}
+
+fn get_padding<'a>(args: &'a [Value], function_name: &str) -> Result<(&'a str, i64, char), String> {
+    let (text, width, fill) = match args {
+        [Value::String(text), Value::Number(width)] => (text, width, " "),
+        [Value::String(text), Value::Number(width), Value::String(fill)] => {
+            (text, width, fill.as_str())
+        }
+        _ => {
+            return Err(format!(
+                "{} expects a string, a width and optionally a fill string",
+                function_name
+            ))
+        }
+    };
+
+    let mut fill_characters = fill.chars();
+    match (fill_characters.next(), fill_characters.next()) {
+        (Some(fill), None) => Ok((text.as_str(), *width, fill)),
+        _ => Err(format!("{} expects a single fill character", function_name)),
+    }
+}
+
+fn pad_left(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let (text, width, fill) = get_padding(&args, "pad_left")?;
+    Ok(Some(Value::String(rosy_rt::text::pad_left(text, width, fill)?.into())))
+}
+
+fn pad_right(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let (text, width, fill) = get_padding(&args, "pad_right")?;
+    Ok(Some(Value::String(rosy_rt::text::pad_right(text, width, fill)?.into())))
+}
+
+fn repeat(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::String(text), Value::Number(count)] => {
+            Ok(Some(Value::String(rosy_rt::text::repeat(text, *count)?.into())))
+        }
+        _ => Err("repeat expects a string and an integer".to_string()),
+    }
+}
@@ -0,0 +1,163 @@
+use std::io::Write;
+
+use crossterm::cursor;
+use crossterm::event;
+use crossterm::event::Event;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEventKind;
+use crossterm::terminal;
+
+use super::expect_arguments;
+use super::Builtin;
+use super::BuiltinModule;
+use crate::interpreter::Context;
+use crate::interpreter::Value;
+use crate::typechecker::Type;
+
+// Control over the terminal, for small games and interactive tools.
+// Only the text of the output is kept in the interpreter terminal, not the cursor movements.
+pub fn module() -> BuiltinModule {
+    BuiltinModule {
+        name: "terminal",
+        functions: vec![
+            Builtin {
+                name: "clear_screen",
+                param_names: &[],
+                signature: |arg_types| expect_arguments(arg_types, &[], Type::Undefined),
+                implementation: clear_screen,
+            },
+            Builtin {
+                name: "move_cursor",
+                param_names: &["x", "y"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::Integer, Type::Integer], Type::Undefined)
+                },
+                implementation: move_cursor,
+            },
+            Builtin {
+                name: "colored",
+                param_names: &["text", "color"],
+                signature: |arg_types| {
+                    expect_arguments(arg_types, &[Type::String, Type::String], Type::String)
+                },
+                implementation: colored,
+            },
+            Builtin {
+                name: "read_key",
+                param_names: &[],
+                signature: |arg_types| expect_arguments(arg_types, &[], Type::String),
+                implementation: read_key,
+            },
+        ],
+    }
+}
+
+fn clear_screen(_args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    let mut stdout = std::io::stdout();
+    match crossterm::execute!(
+        stdout,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::MoveTo(0, 0)
+    ) {
+        Ok(_) => {}
+        Err(error) => return Err(format!("Could not clear the screen: {}", error)),
+    }
+
+    context.terminal.clear();
+    context.terminal.push(String::new());
+
+    Ok(None)
+}
+
+// Moves the cursor to the given column and row, both starting at 0
+fn move_cursor(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let (x, y) = match args.as_slice() {
+        [Value::Number(x), Value::Number(y)] => (x, y),
+        _ => return Err("move_cursor expects two integers".to_string()),
+    };
+
+    let (Ok(column), Ok(row)) = (u16::try_from(*x), u16::try_from(*y)) else {
+        return Err(format!("Cannot move the cursor to ({}, {})", x, y));
+    };
+
+    let mut stdout = std::io::stdout();
+    match crossterm::execute!(stdout, cursor::MoveTo(column, row)) {
+        Ok(_) => Ok(None),
+        Err(error) => Err(format!("Could not move the cursor: {}", error)),
+    }
+}
+
+// Wraps the text in the escape codes for the given foreground color
+fn colored(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let (text, color) = match args.as_slice() {
+        [Value::String(text), Value::String(color)] => (text, color),
+        _ => return Err("colored expects a string and a color".to_string()),
+    };
+
+    let code = match color.to_lowercase().as_str() {
+        "black" => 30,
+        "red" => 31,
+        "green" => 32,
+        "yellow" => 33,
+        "blue" => 34,
+        "magenta" => 35,
+        "cyan" => 36,
+        "white" => 37,
+        _ => return Err(format!("Unknown color '{}'", color)),
+    };
+
+    Ok(Some(Value::String(format!("\x1b[{}m{}\x1b[0m", code, text).into())))
+}
+
+// Waits for a single key press without echoing it.
+// Characters are returned as themselves, other keys by name, such as "up" or "enter".
+fn read_key(_args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    let key = context.traced_input("key", read_key_from_terminal)?;
+    Ok(Some(Value::String(key.into())))
+}
+
+fn read_key_from_terminal() -> Result<String, String> {
+    // Output written with print() has to be visible before waiting
+    let _ = std::io::stdout().flush();
+
+    if let Err(error) = terminal::enable_raw_mode() {
+        return Err(format!("Could not read a key: {}", error));
+    }
+
+    let key = loop {
+        match event::read() {
+            Ok(Event::Key(key_event)) if key_event.kind != KeyEventKind::Release => {
+                if let Some(key) = key_name(key_event.code) {
+                    break Ok(key);
+                }
+            }
+            Ok(_) => {}
+            Err(error) => break Err(format!("Could not read a key: {}", error)),
+        }
+    };
+
+    let _ = terminal::disable_raw_mode();
+
+    key
+}
+
+fn key_name(code: KeyCode) -> Option<String> {
+    let name = match code {
+        KeyCode::Char(c) => return Some(c.to_string()),
+        KeyCode::Enter => "enter",
+        KeyCode::Esc => "escape",
+        KeyCode::Backspace => "backspace",
+        KeyCode::Tab => "tab",
+        KeyCode::Delete => "delete",
+        KeyCode::Up => "up",
+        KeyCode::Down => "down",
+        KeyCode::Left => "left",
+        KeyCode::Right => "right",
+        KeyCode::Home => "home",
+        KeyCode::End => "end",
+        KeyCode::PageUp => "page_up",
+        KeyCode::PageDown => "page_down",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
@@ -0,0 +1,108 @@
+use super::Builtin;
+use super::BuiltinModule;
+use crate::interpreter::value_to_string;
+use crate::interpreter::Context;
+use crate::interpreter::Value;
+use crate::typechecker::Type;
+
+// Assertions for the test functions run by `rosy test`
+pub fn module() -> BuiltinModule {
+    BuiltinModule {
+        name: "testing",
+        functions: vec![Builtin {
+            name: "assert_eq",
+            param_names: &["actual", "expected"],
+            signature: |arg_types| match arg_types {
+                [actual, expected] if actual == expected => Some(Type::Undefined),
+                _ => None,
+            },
+            implementation: assert_eq,
+        }],
+    }
+}
+
+// Fails with every difference between the values, lists are compared element by element
+fn assert_eq(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    let (actual, expected) = match args.as_slice() {
+        [actual, expected] => (actual, expected),
+        _ => return Err("assert_eq expects two values".to_string()),
+    };
+
+    let mut differences = Vec::new();
+    collect_differences(actual, expected, "", &mut differences);
+
+    match differences.as_slice() {
+        [] => Ok(None),
+        [difference] => Err(format!("assert_eq failed: {}", difference)),
+        _ => Err(format!(
+            "assert_eq failed with {} differences:\n    {}",
+            differences.len(),
+            differences.join("\n    ")
+        )),
+    }
+}
+
+fn collect_differences(
+    actual: &Value,
+    expected: &Value,
+    path: &str,
+    differences: &mut Vec<String>,
+) {
+    match (actual, expected) {
+        (Value::List(actual_values), Value::List(expected_values)) => {
+            for (i, (actual_value, expected_value)) in
+                actual_values.iter().zip(expected_values).enumerate()
+            {
+                let element_path = format!("{}[{}]", path, i);
+                collect_differences(actual_value, expected_value, &element_path, differences);
+            }
+
+            // Elements only one of the lists has
+            for (i, actual_value) in actual_values.iter().enumerate().skip(expected_values.len()) {
+                differences.push(format!(
+                    "at {}[{}]: unexpected {}",
+                    path,
+                    i,
+                    describe(actual_value)
+                ));
+            }
+            for (i, expected_value) in expected_values.iter().enumerate().skip(actual_values.len())
+            {
+                differences.push(format!(
+                    "at {}[{}]: missing {}",
+                    path,
+                    i,
+                    describe(expected_value)
+                ));
+            }
+        }
+        _ => {
+            let actual_description = describe(actual);
+            let expected_description = describe(expected);
+            if actual_description == expected_description {
+                return;
+            }
+
+            let location = match path {
+                "" => String::new(),
+                path => format!("at {}: ", path),
+            };
+            differences.push(format!(
+                "{}expected {}, found {}",
+                location, expected_description, actual_description
+            ));
+        }
+    }
+}
+
+// Strings are quoted, so "1" and 1 are told apart
+fn describe(value: &Value) -> String {
+    match value {
+        Value::String(text) => format!("\"{}\"", text),
+        Value::List(values) => {
+            let values: Vec<String> = values.iter().map(describe).collect();
+            format!("[{}]", values.join(", "))
+        }
+        other => value_to_string(other),
+    }
+}
@@ -0,0 +1,52 @@
+use super::expect_arguments;
+use super::Builtin;
+use super::BuiltinModule;
+use crate::interpreter::Context;
+use crate::interpreter::Suspension;
+use crate::interpreter::Value;
+use crate::typechecker::Type;
+
+pub fn module() -> BuiltinModule {
+    BuiltinModule {
+        name: "time",
+        functions: vec![
+            Builtin {
+                name: "now",
+                param_names: &[],
+                signature: |arg_types| expect_arguments(arg_types, &[], Type::Integer),
+                implementation: now,
+            },
+            Builtin {
+                name: "sleep",
+                param_names: &["milliseconds"],
+                signature: |arg_types| expect_arguments(arg_types, &[Type::Integer], Type::Undefined),
+                implementation: sleep,
+            },
+        ],
+    }
+}
+
+// The current time in milliseconds since the unix epoch, which is frozen in deterministic mode
+fn now(_args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    Ok(Some(Value::Number(context.now()?)))
+}
+
+// Blocks the thread, or suspends the program when the host polls it
+fn sleep(args: Vec<Value>, context: &mut Context) -> Result<Option<Value>, String> {
+    let milliseconds = match args.as_slice() {
+        [Value::Number(milliseconds)] if *milliseconds >= 0 => *milliseconds as u64,
+        [Value::Number(milliseconds)] => {
+            return Err(format!("Cannot sleep for {} milliseconds", milliseconds))
+        }
+        _ => return Err("sleep expects an integer".to_string()),
+    };
+    let duration = std::time::Duration::from_millis(milliseconds);
+
+    if context.options.suspend_on_sleep {
+        context.suspend(Suspension::Sleep(duration));
+    } else {
+        std::thread::sleep(duration);
+    }
+
+    Ok(None)
+}
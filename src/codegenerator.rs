@@ -545,24 +545,25 @@ pub fn generate_code(tac: &Vec<TacInstruction>, register_allocation: &HashMap<St
 					}
 					(TacValue::Constant(imm_left), TacValue::Constant(imm_right)) => {
 
+						// Constants are folded the way the processor computes them, wrapping around on overflow
 						match op {
 							BinOp::Add => {
-								let result = *imm_left + *imm_right;
+								let result = imm_left.wrapping_add(*imm_right);
 								instructions.push(Instruction::Mov(Argument::Register(dest_reg), Argument::Immediate(result as i64)));
 							}
 							BinOp::Sub => {
-								let result = *imm_left - *imm_right;
+								let result = imm_left.wrapping_sub(*imm_right);
 								instructions.push(Instruction::Mov(Argument::Register(dest_reg), Argument::Immediate(result as i64)));
 							}
 							BinOp::Mul => {
-								let result = *imm_left * *imm_right;
+								let result = imm_left.wrapping_mul(*imm_right);
 								instructions.push(Instruction::Mov(Argument::Register(dest_reg), Argument::Immediate(result as i64)));
 							}
 							BinOp::Div => {
 								if *imm_right == 0 {
 									return Err(Error::SimpleError{message: "Division by zero".to_string()});
 								}
-								let result = *imm_left / *imm_right;
+								let result = imm_left.wrapping_div(*imm_right);
 								instructions.push(Instruction::Mov(Argument::Register(dest_reg), Argument::Immediate(result as i64)));
 							}
 							BinOp::Or => {
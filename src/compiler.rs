@@ -3,6 +3,7 @@ use std::fmt::format;
 use crate::codegenerator::Instruction;
 use crate::parser::BaseExpr;
 use crate::tac;
+use crate::tac::TacInstruction;
 use crate::tokenizer::Error;
 use crate::typechecker::FunctionType;
 use crate::typechecker::Type;
@@ -10,17 +11,52 @@ use crate::registerallocation::registerallocator;
 use crate::codegenerator;
 use crate::livenessanalysis;
 use crate::uniquify;
+use crate::verifier;
 use crate::registerallocation::interferencegraph;
 use crate::registerallocation::variableclassifier;
 use crate::variablecollector;
 use crate::instructionsimplifier;
 use crate::instructionsimplifier::AssemblyInstruction;
 
+// The platforms compile can write executables for, the code is the same for both but the file format differs
+#[derive(Clone, Copy, PartialEq, Debug, clap::ValueEnum)]
+pub enum Target {
+    // A PE executable which imports its functions from KERNEL32.dll
+    #[value(name = "x86_64-pc-windows", alias = "x86_64-pc-windows-msvc")]
+    Windows,
+    // A statically linked ELF executable with a runtime stub in place of KERNEL32.dll, see elfwriter
+    #[value(name = "x86_64-unknown-linux", alias = "x86_64-unknown-linux-gnu")]
+    Linux,
+}
+
+impl Target {
+    // The platform the compiler itself runs on
+    pub fn host() -> Target {
+        match cfg!(windows) {
+            true => Target::Windows,
+            false => Target::Linux,
+        }
+    }
+
+    pub fn executable_extension(&self) -> &'static str {
+        match self {
+            Target::Windows => "exe",
+            Target::Linux => "",
+        }
+    }
+}
+
 pub fn compile(
     base_expressions: (Vec<BaseExpr<Type>>, Vec<FunctionType>),
 ) -> Result<Vec<AssemblyInstruction>, Error> {
     let tac_instructions = tac::generate_tac(base_expressions.0, base_expressions.1)?;
+    verifier::verify("generating three address code", tac_instructions.as_slice(), verifier::check_tac_instructions)?;
+
+    compile_tac(&tac_instructions)
+}
 
+// Compiles three address code to assembly, used for whole programs and for IR read from a .rir file
+pub fn compile_tac(tac_instructions: &Vec<TacInstruction>) -> Result<Vec<AssemblyInstruction>, Error> {
     let all_variable_names = variablecollector::collect_variable_names(&tac_instructions);
     let function_arguments = variableclassifier::get_function_arguments(&tac_instructions);
 
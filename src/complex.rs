@@ -0,0 +1,88 @@
+use std::fmt;
+use std::ops;
+
+use crate::rational::Rational;
+
+// A complex number with floating point parts
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Complex {
+        Complex { re, im }
+    }
+
+    pub fn from_integer(value: i64) -> Complex {
+        Complex::new(value as f64, 0.0)
+    }
+
+    pub fn from_rational(value: Rational) -> Complex {
+        Complex::new(value.to_f64(), 0.0)
+    }
+
+    // Division by zero is an error rather than giving infinite parts
+    pub fn checked_div(self, other: Complex) -> Result<Complex, String> {
+        let divisor = other.re * other.re + other.im * other.im;
+        if divisor == 0.0 {
+            return Err("Division by zero".to_string());
+        }
+
+        Ok(Complex::new(
+            (self.re * other.re + self.im * other.im) / divisor,
+            (self.im * other.re - self.re * other.im) / divisor,
+        ))
+    }
+
+    pub fn magnitude(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+}
+
+impl ops::Add for Complex {
+    type Output = Complex;
+
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl ops::Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl ops::Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl ops::Neg for Complex {
+    type Output = Complex;
+
+    fn neg(self) -> Complex {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+// Written as a+bi, e.g. 1.5-2i
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.im < 0.0 {
+            write!(f, "{}-{}i", self.re, -self.im)
+        } else {
+            write!(f, "{}+{}i", self.re, self.im)
+        }
+    }
+}
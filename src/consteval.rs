@@ -0,0 +1,280 @@
+use crate::builtins;
+use crate::interpreter;
+use crate::interpreter::Value;
+use crate::language::LanguageOptions;
+use crate::parser;
+use crate::parser::BaseExpr;
+use crate::parser::BaseExprData;
+use crate::parser::RecExpr;
+use crate::parser::RecExprData;
+use crate::tokenizer::Error;
+use crate::tokenizer::Span;
+use crate::typechecker::FunctionType;
+use crate::typechecker::Type;
+use crate::visitor;
+use crate::visitor::Folder;
+use crate::visitor::Visitor;
+
+// Evaluates the expressions of a typed program which only depend on literals, such as 60 * 60 * 24
+// or pad_left("7", 3, "0"), and puts their result into the program as a literal.
+// Operators and pure builtins are evaluated by the interpreter, so the results are the same as at runtime.
+// An expression which fails to evaluate, such as 1 / 0, is left alone so the error happens when the program runs,
+// and so is an integer operation which overflows or a builtin call which makes a large string or list.
+// Gives the number of expressions which were replaced by a literal.
+pub fn evaluate_constants(
+    typed_program: &mut (Vec<BaseExpr<Type>>, Vec<FunctionType>),
+    language: &LanguageOptions,
+) -> Result<usize, Error> {
+    // Builtins take precedence over functions defined in a typed program
+    let mut evaluator = ConstantEvaluator {
+        language,
+        shadowed_builtins: Vec::new(),
+        evaluated: 0,
+    };
+
+    let base_expressions = std::mem::take(&mut typed_program.0);
+    typed_program.0 = evaluator.fold_body(base_expressions)?;
+
+    for function in typed_program.1.iter_mut() {
+        let content = std::mem::take(&mut function.content);
+        function.content = evaluator.fold_body(content)?;
+    }
+
+    Ok(evaluator.evaluated)
+}
+
+// The same for a program which is interpreted without typechecking it first. The interpreter calls a function
+// the program defines instead of a builtin with the same name, so those calls are left alone.
+pub fn evaluate_constants_untyped(
+    base_expressions: Vec<BaseExpr<()>>,
+    language: &LanguageOptions,
+) -> Result<(Vec<BaseExpr<()>>, usize), Error> {
+    let mut evaluator = ConstantEvaluator {
+        language,
+        shadowed_builtins: defined_names(&base_expressions),
+        evaluated: 0,
+    };
+
+    let base_expressions = evaluator.fold_body(base_expressions)?;
+    Ok((base_expressions, evaluator.evaluated))
+}
+
+// What an expression is annotated with in the tree being evaluated, which the literal of its value has to agree with
+pub trait LiteralAnnotation: Clone {
+    fn literal(value: &Value, annotation: &Self, span: Span) -> Option<RecExprData<Self>>;
+}
+
+impl LiteralAnnotation for Type {
+    fn literal(value: &Value, value_type: &Type, span: Span) -> Option<RecExprData<Type>> {
+        value_to_literal(value, value_type, span)
+    }
+}
+
+// Without types every value with a literal can be put into the program
+impl LiteralAnnotation for () {
+    fn literal(value: &Value, _: &(), span: Span) -> Option<RecExprData<()>> {
+        match value {
+            Value::Number(number) => Some(RecExprData::Number { number: *number }),
+            Value::Float(value) if value.is_finite() => Some(RecExprData::Float { value: *value }),
+            Value::Bool(value) => Some(RecExprData::Boolean { value: *value }),
+            Value::String(value) => Some(RecExprData::String {
+                value: value.to_string(),
+            }),
+            Value::List(values) => {
+                let mut elements = Vec::new();
+                for value in values {
+                    let data = <() as LiteralAnnotation>::literal(value, &(), span)?;
+                    elements.push(RecExpr::new(data, span, ()));
+                }
+                Some(RecExprData::List { elements })
+            }
+            _ => None,
+        }
+    }
+}
+
+struct ConstantEvaluator<'a> {
+    language: &'a LanguageOptions,
+    // Calls to these names are not evaluated, as they do not call the builtin
+    shadowed_builtins: Vec<String>,
+    evaluated: usize,
+}
+
+impl<T: LiteralAnnotation> Folder<T> for ConstantEvaluator<'_> {
+    fn fold_rec_expr(&mut self, rec_expr: RecExpr<T>) -> Result<RecExpr<T>, Error> {
+        // The operands are evaluated first, so whole trees of constants become a single literal
+        let rec_expr = visitor::fold_rec_expr_children(self, rec_expr)?;
+        if !is_evaluable(&rec_expr) || overflows(&rec_expr) || allocates_too_much(&rec_expr) {
+            return Ok(rec_expr);
+        }
+        if let RecExprData::FunctionCall { function_name, .. } = &rec_expr.data {
+            if self.shadowed_builtins.contains(function_name) {
+                return Ok(rec_expr);
+            }
+        }
+
+        let untyped_expr = rec_expr.clone().map_data(&mut |_| ());
+        let literal = match interpreter::evaluate_constant(&untyped_expr, self.language) {
+            Ok(Some(value)) => T::literal(&value, &rec_expr.generic_data, rec_expr.span()),
+            _ => None,
+        };
+
+        match literal {
+            Some(data) => {
+                self.evaluated += 1;
+                Ok(RecExpr { data, ..rec_expr })
+            }
+            None => Ok(rec_expr),
+        }
+    }
+}
+
+// The names the program defines functions, parameters or variables with, anywhere in it
+fn defined_names(base_expressions: &[BaseExpr<()>]) -> Vec<String> {
+    struct DefinitionCollector {
+        names: Vec<String>,
+    }
+
+    impl Visitor<()> for DefinitionCollector {
+        fn visit_base_expr(&mut self, base_expr: &BaseExpr<()>) {
+            match &base_expr.data {
+                BaseExprData::FunctionDefinition { fun_name, args, .. } => {
+                    self.names.push(fun_name.clone());
+                    self.names.extend(parser::parameter_names(args));
+                }
+                BaseExprData::VariableAssignment { var_name, .. } => self.names.push(var_name.clone()),
+                BaseExprData::DestructuringAssignment { var_names, .. } => self.names.extend(var_names.iter().cloned()),
+                _ => {}
+            }
+            visitor::walk_base_expr(self, base_expr);
+        }
+    }
+
+    let mut collector = DefinitionCollector { names: Vec::new() };
+    collector.visit_body(base_expressions);
+    collector.names
+}
+
+// Whether the expression is an operation on literals which can be evaluated before the program runs
+fn is_evaluable<T: Clone>(rec_expr: &RecExpr<T>) -> bool {
+    let is_operation = match &rec_expr.data {
+        RecExprData::FunctionCall { function_name, .. } => builtins::is_pure(function_name),
+        RecExprData::Variable { .. }
+        | RecExprData::Number { .. }
+        | RecExprData::Float { .. }
+        | RecExprData::String { .. }
+        | RecExprData::Boolean { .. }
+        | RecExprData::List { .. }
+        | RecExprData::Assign { .. }
+        | RecExprData::Access { .. } => false,
+        _ => true,
+    };
+    if !is_operation {
+        return false;
+    }
+
+    let mut operands = OperandChecker { all_literals: true };
+    visitor::walk_rec_expr(&mut operands, rec_expr);
+    operands.all_literals
+}
+
+// Whether an operation on integer literals gives a result which does not fit in an integer, such as
+// 9223372036854775807 + 1. The interpreter does not check for this, so it is never evaluated here.
+fn overflows<T: Clone>(rec_expr: &RecExpr<T>) -> bool {
+    let integer = |operand: &RecExpr<T>| match operand.data {
+        RecExprData::Number { number } => Some(number),
+        _ => None,
+    };
+    let integers = |left: &RecExpr<T>, right: &RecExpr<T>| Some((integer(left)?, integer(right)?));
+
+    let result = match &rec_expr.data {
+        RecExprData::Add { left, right } => integers(left, right).map(|(left, right)| left.checked_add(right)),
+        RecExprData::Subtract { left, right } => {
+            integers(left, right).map(|(left, right)| left.checked_sub(right))
+        }
+        RecExprData::Multiply { left, right } => {
+            integers(left, right).map(|(left, right)| left.checked_mul(right))
+        }
+        RecExprData::Divide { left, right } | RecExprData::FloorDivide { left, right } => {
+            integers(left, right).map(|(left, right)| left.checked_div(right))
+        }
+        RecExprData::Power { left, right } => integers(left, right).map(|(left, right)| {
+            u32::try_from(right).ok().and_then(|right| left.checked_pow(right))
+        }),
+        RecExprData::Minus { right } => integer(right).map(i64::checked_neg),
+        _ => None,
+    };
+    matches!(result, Some(None))
+}
+
+// The longest string or list a builtin may make to be put into the program as a literal
+const MAX_LITERAL_LENGTH: i64 = 4096;
+
+// Whether a call to a builtin which allocates its result, such as repeat("ab", 1000000), makes a result
+// too large to put into the program. Those calls are left for the program to make when it runs.
+fn allocates_too_much<T: Clone>(rec_expr: &RecExpr<T>) -> bool {
+    let RecExprData::FunctionCall { function_name, args } = &rec_expr.data else {
+        return false;
+    };
+    let integer = |index: usize| match args.get(index).map(|arg| &arg.data) {
+        Some(RecExprData::Number { number }) => Some(*number),
+        _ => None,
+    };
+
+    let length = match function_name.as_str() {
+        "repeat" => match args.first().map(|arg| &arg.data) {
+            Some(RecExprData::String { value }) => integer(1).and_then(|count| count.checked_mul(value.len() as i64)),
+            _ => None,
+        },
+        "pad_left" | "pad_right" => integer(1),
+        "make_grid" => integer(0).zip(integer(1)).and_then(|(rows, cols)| rows.checked_mul(cols)),
+        _ => return false,
+    };
+    length.is_none_or(|length| length > MAX_LITERAL_LENGTH)
+}
+
+// Checks the direct operands of an expression, without going deeper
+struct OperandChecker {
+    all_literals: bool,
+}
+
+impl<T: Clone> Visitor<T> for OperandChecker {
+    fn visit_rec_expr(&mut self, rec_expr: &RecExpr<T>) {
+        self.all_literals &= is_literal(rec_expr);
+    }
+}
+
+fn is_literal<T: Clone>(rec_expr: &RecExpr<T>) -> bool {
+    match &rec_expr.data {
+        RecExprData::Number { .. }
+        | RecExprData::Float { .. }
+        | RecExprData::String { .. }
+        | RecExprData::Boolean { .. } => true,
+        RecExprData::List { elements } => elements.iter().all(is_literal),
+        _ => false,
+    }
+}
+
+// The literal for a value, if it has one and it agrees with the type the typechecker gave the expression.
+// The elements of a list literal get the place of the whole expression.
+fn value_to_literal(value: &Value, value_type: &Type, span: Span) -> Option<RecExprData<Type>> {
+    match (value, value_type) {
+        (Value::Number(number), Type::Integer) => Some(RecExprData::Number { number: *number }),
+        (Value::Float(value), Type::Float) if value.is_finite() => {
+            Some(RecExprData::Float { value: *value })
+        }
+        (Value::Bool(value), Type::Boolean) => Some(RecExprData::Boolean { value: *value }),
+        (Value::String(value), Type::String) => Some(RecExprData::String {
+            value: value.to_string(),
+        }),
+        (Value::List(values), Type::List(element_type)) => {
+            let mut elements = Vec::new();
+            for value in values {
+                let data = value_to_literal(value, element_type, span)?;
+                elements.push(RecExpr::new(data, span, *element_type.clone()));
+            }
+            Some(RecExprData::List { elements })
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,148 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc::channel;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
+use std::thread::JoinHandle;
+
+// Runs a function as a coroutine on a thread of its own, so that it can pause at any depth of
+// recursion (for example in the middle of a loop in a function called by the program) and
+// continue from there when it is resumed. The caller and the coroutine never run at the same time.
+//
+// The state the function works on, S, is moved into the coroutine when it is resumed and handed
+// back when it pauses or finishes, so the caller can use the state in between.
+// Y is the value the coroutine pauses with, and R the value it finishes with.
+pub struct Coroutine<S, Y, R> {
+    // None once the coroutine is dropped, which makes a paused coroutine unwind
+    to_coroutine: Option<Sender<S>>,
+    from_coroutine: Receiver<Message<S, Y, R>>,
+    finished: bool,
+    thread: Option<JoinHandle<()>>,
+}
+
+pub enum CoroutineStep<Y, R> {
+    Paused(Y),
+    Finished(R),
+}
+
+enum Message<S, Y, R> {
+    Step(S, CoroutineStep<Y, R>),
+    // The coroutine panicked, the panic is continued in the caller
+    Panicked(Box<dyn Any + Send>),
+}
+
+// The end of the channels held by the thread of a coroutine, see Coroutine::pause
+struct Suspender<S, Y, R> {
+    to_caller: Sender<Message<S, Y, R>>,
+    from_caller: Receiver<S>,
+}
+
+// The panic payload which unwinds a paused coroutine that was dropped
+struct Cancelled;
+
+thread_local! {
+    // The Suspender of the coroutine which runs on this thread, if it is one
+    static SUSPENDER: RefCell<Option<Box<dyn Any>>> = RefCell::new(None);
+}
+
+impl<S: Send + 'static, Y: Send + 'static, R: Send + 'static> Coroutine<S, Y, R> {
+    // The function starts running at the first call to resume, with the state given to it
+    pub fn new(function: impl FnOnce(S) -> (S, R) + Send + 'static) -> Coroutine<S, Y, R> {
+        let (to_coroutine, from_caller) = channel::<S>();
+        let (to_caller, from_coroutine) = channel::<Message<S, Y, R>>();
+
+        let thread = std::thread::spawn(move || {
+            let Ok(state) = from_caller.recv() else {
+                return;
+            };
+            let to_caller_at_end = to_caller.clone();
+            SUSPENDER.with(|suspender| {
+                *suspender.borrow_mut() = Some(Box::new(Suspender::<S, Y, R> {
+                    to_caller,
+                    from_caller,
+                }))
+            });
+
+            let message = match std::panic::catch_unwind(AssertUnwindSafe(|| function(state))) {
+                Ok((state, result)) => Message::Step(state, CoroutineStep::Finished(result)),
+                Err(payload) if payload.is::<Cancelled>() => return,
+                Err(payload) => Message::Panicked(payload),
+            };
+            let _ = to_caller_at_end.send(message);
+        });
+
+        Coroutine {
+            to_coroutine: Some(to_coroutine),
+            from_coroutine,
+            finished: false,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // Runs the coroutine with the state until it pauses or finishes, after which the state is handed back.
+    // Must not be called once the coroutine has finished.
+    pub fn resume(&mut self, state: S) -> (S, CoroutineStep<Y, R>) {
+        assert!(!self.finished, "Cannot resume a coroutine which has finished");
+
+        self.to_coroutine.as_ref().unwrap().send(state).unwrap();
+        match self.from_coroutine.recv().unwrap() {
+            Message::Step(state, step) => {
+                self.finished = matches!(step, CoroutineStep::Finished(_));
+                (state, step)
+            }
+            Message::Panicked(payload) => {
+                self.finished = true;
+                std::panic::resume_unwind(payload)
+            }
+        }
+    }
+
+    // Whether the current thread runs a coroutine of this type
+    pub fn is_running_here() -> bool {
+        SUSPENDER.with(|suspender| {
+            suspender
+                .borrow()
+                .as_ref()
+                .is_some_and(|suspender| suspender.is::<Suspender<S, Y, R>>())
+        })
+    }
+
+    // Pauses the coroutine which runs on the current thread, handing the state and value to the caller
+    // of resume. Returns the state it is resumed with. Must only be called when is_running_here.
+    pub fn pause(state: S, value: Y) -> S {
+        SUSPENDER.with(|suspender| {
+            let mut suspender = suspender.borrow_mut();
+            let suspender = suspender
+                .as_mut()
+                .and_then(|suspender| suspender.downcast_mut::<Suspender<S, Y, R>>())
+                .expect("Cannot pause a thread which does not run a coroutine");
+
+            let message = Message::Step(state, CoroutineStep::Paused(value));
+            if suspender.to_caller.send(message).is_err() {
+                std::panic::resume_unwind(Box::new(Cancelled));
+            }
+            match suspender.from_caller.recv() {
+                Ok(state) => state,
+                // The coroutine was dropped while it was paused, its thread ends once the stack is unwound
+                Err(_) => std::panic::resume_unwind(Box::new(Cancelled)),
+            }
+        })
+    }
+}
+
+impl<S, Y, R> Drop for Coroutine<S, Y, R> {
+    fn drop(&mut self) {
+        self.to_coroutine = None;
+        // A coroutine can hold the last reference to itself, in which case it is dropped on its own thread
+        if let Some(thread) = self.thread.take() {
+            if thread.thread().id() != std::thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
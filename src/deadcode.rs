@@ -0,0 +1,199 @@
+use crate::parser::BaseExpr;
+use crate::parser::BaseExprData;
+use crate::parser::RecExpr;
+use crate::parser::RecExprData;
+use crate::tokenizer::Error;
+use crate::visitor;
+use crate::visitor::Folder;
+use crate::visitor::Visitor;
+
+// Removes the statements which can never run: those after a return, break or continue in the same block,
+// loops whose condition is the literal false and the branches of an if which its literal condition rules out.
+// Conditions such as 1 > 2 only become literals through constant evaluation, so this runs after it.
+// Statements which define a variable, function or struct are kept, as later statements may still refer to
+// the name and the typechecker and backend expect to find its definition.
+// Gives the number of statements which were removed.
+pub fn eliminate_dead_code<T: Clone>(body: Vec<BaseExpr<T>>) -> Result<(Vec<BaseExpr<T>>, usize), Error> {
+    let mut eliminator = DeadCodeEliminator { removed: 0 };
+    let body = eliminator.fold_body(body)?;
+    Ok((body, eliminator.removed))
+}
+
+struct DeadCodeEliminator {
+    removed: usize,
+}
+
+// The condition, body and else of an if or else if
+type Branch<T> = (RecExpr<T>, Vec<BaseExpr<T>>, Option<Box<BaseExpr<T>>>);
+
+impl<T: Clone> Folder<T> for DeadCodeEliminator {
+    fn fold_body(&mut self, body: Vec<BaseExpr<T>>) -> Result<Vec<BaseExpr<T>>, Error> {
+        let mut kept = Vec::new();
+        let mut reachable = true;
+
+        for base_expr in body {
+            if !reachable && !defines_names(std::slice::from_ref(&base_expr)) {
+                self.removed += 1;
+                continue;
+            }
+
+            let Some(base_expr) = self.fold_statement(base_expr)? else {
+                self.removed += 1;
+                continue;
+            };
+            if matches!(
+                base_expr.data,
+                BaseExprData::Return { .. } | BaseExprData::Break | BaseExprData::Continue
+            ) {
+                reachable = false;
+            }
+            kept.push(base_expr);
+        }
+
+        Ok(kept)
+    }
+}
+
+impl DeadCodeEliminator {
+    // The statement with its dead parts removed, or None if none of it can run
+    fn fold_statement<T: Clone>(&mut self, base_expr: BaseExpr<T>) -> Result<Option<BaseExpr<T>>, Error> {
+        let base_expr = self.fold_base_expr(base_expr)?;
+        let data = match base_expr.data {
+            BaseExprData::WhileLoop { condition, body }
+                if literal_condition(&condition) == Some(false) && !defines_names(&body) =>
+            {
+                return Ok(None);
+            }
+            BaseExprData::IfStatement {
+                condition,
+                body,
+                else_statement,
+            } => match self.fold_branch(condition, body, else_statement) {
+                Some((condition, body, else_statement)) => BaseExprData::IfStatement {
+                    condition,
+                    body,
+                    else_statement,
+                },
+                None => return Ok(None),
+            },
+            data => data,
+        };
+
+        Ok(Some(BaseExpr { data, ..base_expr }))
+    }
+
+    // Removes the branches of an if, or of the else if following it, which cannot be taken.
+    // An if whose own body cannot run is replaced by the branch after it, as an if on true so the
+    // statement keeps its kind. Gives None when no branch is left.
+    fn fold_branch<T: Clone>(
+        &mut self,
+        condition: RecExpr<T>,
+        body: Vec<BaseExpr<T>>,
+        else_statement: Option<Box<BaseExpr<T>>>,
+    ) -> Option<Branch<T>> {
+        match literal_condition(&condition) {
+            Some(true) => {
+                let else_is_dead = else_statement
+                    .as_deref()
+                    .is_some_and(|else_statement| !defines_names(std::slice::from_ref(else_statement)));
+                if else_is_dead {
+                    self.removed += 1;
+                    return Some((condition, body, None));
+                }
+                Some((condition, body, else_statement))
+            }
+            Some(false) if !defines_names(&body) => {
+                // Without a branch after it the whole statement goes, which the caller counts
+                let else_statement = *else_statement?;
+                self.removed += 1;
+                match else_statement.data {
+                    BaseExprData::ElseIfStatement {
+                        condition,
+                        body,
+                        else_statement,
+                    } => self.fold_branch(condition, body, else_statement),
+                    BaseExprData::ElseStatement { body } => {
+                        let always = RecExpr {
+                            data: RecExprData::Boolean { value: true },
+                            ..condition
+                        };
+                        Some((always, body, None))
+                    }
+                    _ => None,
+                }
+            }
+            _ => {
+                let else_statement = else_statement.and_then(|else_statement| self.fold_else(*else_statement));
+                Some((condition, body, else_statement))
+            }
+        }
+    }
+
+    // The same for the else if or else after a branch which may be taken
+    fn fold_else<T: Clone>(&mut self, else_statement: BaseExpr<T>) -> Option<Box<BaseExpr<T>>> {
+        let data = match else_statement.data {
+            BaseExprData::ElseIfStatement {
+                condition,
+                body,
+                else_statement: next,
+            } => {
+                let Some((condition, body, next)) = self.fold_branch(condition, body, next) else {
+                    self.removed += 1;
+                    return None;
+                };
+                match literal_condition(&condition) {
+                    // The remaining branch always runs when it is reached, so it becomes the else
+                    Some(true) => BaseExprData::ElseStatement { body },
+                    _ => BaseExprData::ElseIfStatement {
+                        condition,
+                        body,
+                        else_statement: next,
+                    },
+                }
+            }
+            data => data,
+        };
+
+        Some(Box::new(BaseExpr { data, ..else_statement }))
+    }
+}
+
+fn literal_condition<T: Clone>(condition: &RecExpr<T>) -> Option<bool> {
+    match condition.data {
+        RecExprData::Boolean { value } => Some(value),
+        _ => None,
+    }
+}
+
+// Whether any of the statements, or the statements inside them, give a name a definition
+fn defines_names<T: Clone>(body: &[BaseExpr<T>]) -> bool {
+    struct DefinitionFinder {
+        found: bool,
+    }
+
+    impl<T: Clone> Visitor<T> for DefinitionFinder {
+        fn visit_base_expr(&mut self, base_expr: &BaseExpr<T>) {
+            match &base_expr.data {
+                BaseExprData::VariableAssignment { .. }
+                | BaseExprData::DestructuringAssignment { .. }
+                | BaseExprData::FunctionDefinition { .. }
+                | BaseExprData::StructDefinition { .. }
+                | BaseExprData::ForLoop { .. }
+                | BaseExprData::With { .. }
+                | BaseExprData::Match { .. } => self.found = true,
+                _ => visitor::walk_base_expr(self, base_expr),
+            }
+        }
+
+        fn visit_rec_expr(&mut self, rec_expr: &RecExpr<T>) {
+            match &rec_expr.data {
+                RecExprData::Assign { .. } => self.found = true,
+                _ => visitor::walk_rec_expr(self, rec_expr),
+            }
+        }
+    }
+
+    let mut finder = DefinitionFinder { found: false };
+    finder.visit_body(body);
+    finder.found
+}
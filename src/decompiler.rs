@@ -0,0 +1,496 @@
+use crate::annotator;
+use crate::builtins;
+use crate::parser::Accessor;
+use crate::parser::BaseExpr;
+use crate::parser::BaseExprData;
+use crate::parser::Pattern;
+use crate::parser::RecExpr;
+use crate::parser::RecExprData;
+use crate::stack;
+use crate::typechecker::FunctionType;
+use crate::typechecker::Type;
+use crate::visitor;
+use crate::visitor::Visitor;
+
+// Turns the syntax tree back into rosy source, so that a rewritten program can be read and run again.
+// Blocks are indented with tabs, and operands are only parenthesized where the parser needs it.
+// Comments and the original layout are not kept in the tree, so they are lost.
+
+// Writes the statements as source, one statement per line
+pub fn program_to_source<T: Clone>(base_expressions: &[BaseExpr<T>]) -> String {
+    let mut generator = SourceGenerator {
+        output: String::new(),
+        indentation: 0,
+        annotate: &|_: &BaseExpr<T>| None,
+        functions: &[],
+        annotate_types: false,
+    };
+    generator.write_body(base_expressions);
+    generator.output
+}
+
+// Writes the output of typechecker::type_check_program as source: the function definitions first,
+// followed by the main program. A function which was typechecked for several argument types is written once,
+// and a function defined inside another is written where it is defined.
+// With annotate_types, the inferred types are added as comments behind the lines that bind a variable:
+//   x = f(3)  # x: int
+pub fn typed_program_to_source(
+    typed_program: &(Vec<BaseExpr<Type>>, Vec<FunctionType>),
+    annotate_types: bool,
+) -> String {
+    let nested_functions = nested_function_names(typed_program);
+    let mut output = String::new();
+    let mut written_functions: Vec<&str> = Vec::new();
+    for function in typed_program.1.iter() {
+        // The builtins that were called are typechecked as functions without content
+        let is_builtin = function.content.is_empty() && builtins::find_builtin(&function.name).is_some();
+        if is_builtin || nested_functions.contains(&function.name) || written_functions.contains(&function.name.as_str()) {
+            continue;
+        }
+        written_functions.push(&function.name);
+        output.push_str(&function_to_source(function, &typed_program.1, annotate_types, 0));
+    }
+
+    let mut generator = typed_generator(&typed_program.1, annotate_types, 0);
+    generator.write_body(&typed_program.0);
+    output.push_str(&generator.output);
+    output
+}
+
+// The definition of the function with the content of its first instance, at the given indentation
+fn function_to_source(function: &FunctionType, functions: &[FunctionType], annotate_types: bool, indentation: usize) -> String {
+    let mut generator = typed_generator(functions, annotate_types, indentation);
+    generator.write(&"\t".repeat(indentation));
+    generator.write(&format!(
+        "fun {}({})",
+        function.name,
+        function.param_names.join(", ")
+    ));
+    if annotate_types {
+        let signatures: Vec<String> = functions
+            .iter()
+            .filter(|instance| instance.name == function.name)
+            .map(annotator::format_signature)
+            .collect();
+        generator.write(&format!("  # {}", signatures.join(", ")));
+    }
+    generator.write("\n");
+    generator.write_block(&function.content);
+    generator.output
+}
+
+fn typed_generator<'a>(
+    functions: &'a [FunctionType],
+    annotate_types: bool,
+    indentation: usize,
+) -> SourceGenerator<'a, Type> {
+    SourceGenerator {
+        output: String::new(),
+        indentation,
+        annotate: match annotate_types {
+            true => &|base_expr: &BaseExpr<Type>| annotator::statement_annotation(base_expr),
+            false => &|_: &BaseExpr<Type>| None,
+        },
+        functions,
+        annotate_types,
+    }
+}
+
+// The functions defined inside other functions, which the typechecker leaves in place as definitions without content
+fn nested_function_names(typed_program: &(Vec<BaseExpr<Type>>, Vec<FunctionType>)) -> Vec<String> {
+    struct DefinitionCollector {
+        names: Vec<String>,
+    }
+
+    impl Visitor<Type> for DefinitionCollector {
+        fn visit_base_expr(&mut self, base_expr: &BaseExpr<Type>) {
+            if let BaseExprData::FunctionDefinition { fun_name, .. } = &base_expr.data {
+                self.names.push(fun_name.clone());
+            }
+            visitor::walk_base_expr(self, base_expr);
+        }
+    }
+
+    let mut collector = DefinitionCollector { names: Vec::new() };
+    for function in typed_program.1.iter() {
+        collector.visit_body(&function.content);
+    }
+    collector.names
+}
+
+// Writes a single expression as source, such as for the replacement text of a fix
+pub fn expression_to_source<T: Clone>(expression: &RecExpr<T>) -> String {
+    expression_with_precedence(expression, LOWEST_PRECEDENCE)
+}
+
+// The fields and indices between the variable and the target of a nested assignment, such as .center or [1]
+pub fn path_to_source<T: Clone>(path: &[Accessor<T>]) -> String {
+    path.iter()
+        .map(|accessor| match accessor {
+            Accessor::Field { name, .. } => format!(".{name}"),
+            Accessor::Index(index) => format!("[{}]", expression_to_source(index)),
+        })
+        .collect()
+}
+
+struct SourceGenerator<'a, T: Clone> {
+    output: String,
+    indentation: usize,
+    // The comment to write behind a statement, if any
+    annotate: &'a dyn Fn(&BaseExpr<T>) -> Option<String>,
+    // The typechecked functions, which a function defined inside another is written from where the
+    // typechecker left its definition without content, annotated as the rest with annotate_types
+    functions: &'a [FunctionType],
+    annotate_types: bool,
+}
+
+impl<T: Clone> SourceGenerator<'_, T> {
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    fn write_body(&mut self, body: &[BaseExpr<T>]) {
+        for base_expr in body {
+            self.write_base_expr(base_expr);
+        }
+    }
+
+    fn write_block(&mut self, body: &[BaseExpr<T>]) {
+        self.indentation += 1;
+        self.write_body(body);
+        self.indentation -= 1;
+    }
+
+    // Writes the first line of a statement, including its annotation
+    fn write_line(&mut self, base_expr: &BaseExpr<T>, line: &str) {
+        self.write(&"\t".repeat(self.indentation));
+        self.write(line);
+        if let Some(annotation) = (self.annotate)(base_expr) {
+            self.write(&format!("  # {annotation}"));
+        }
+        self.write("\n");
+    }
+
+    fn write_base_expr(&mut self, base_expr: &BaseExpr<T>) {
+        // Nested blocks recurse once per level, see stack::ensure_stack
+        stack::ensure_stack(|| match &base_expr.data {
+            BaseExprData::Simple { expr } => {
+                self.write_line(base_expr, &expression_to_source(expr));
+            }
+            BaseExprData::VariableAssignment { var_name, expr } => {
+                let line = format!("{var_name} = {}", expression_to_source(expr));
+                self.write_line(base_expr, &line);
+            }
+            BaseExprData::DestructuringAssignment { var_names, expr } => {
+                let line = format!("{} = {}", var_names.join(", "), expression_to_source(expr));
+                self.write_line(base_expr, &line);
+            }
+            BaseExprData::PlusEqualsStatement { var_name, expr } => {
+                let line = format!("{var_name} += {}", expression_to_source(expr));
+                self.write_line(base_expr, &line);
+            }
+            BaseExprData::FieldAssignment {
+                object,
+                path,
+                field,
+                expr,
+            } => {
+                let path = path_to_source(path);
+                let line = format!("{object}{path}.{field} = {}", expression_to_source(expr));
+                self.write_line(base_expr, &line);
+            }
+            BaseExprData::IndexAssignment {
+                object,
+                path,
+                index,
+                expr,
+            } => {
+                let line = format!(
+                    "{object}{}[{}] = {}",
+                    path_to_source(path),
+                    expression_to_source(index),
+                    expression_to_source(expr)
+                );
+                self.write_line(base_expr, &line);
+            }
+            BaseExprData::IfStatement {
+                condition,
+                body,
+                else_statement,
+            } => {
+                self.write_line(base_expr, &format!("if {}", expression_to_source(condition)));
+                self.write_block(body);
+                if let Some(else_statement) = else_statement {
+                    self.write_base_expr(else_statement);
+                }
+            }
+            BaseExprData::ElseIfStatement {
+                condition,
+                body,
+                else_statement,
+            } => {
+                let line = format!("else if {}", expression_to_source(condition));
+                self.write_line(base_expr, &line);
+                self.write_block(body);
+                if let Some(else_statement) = else_statement {
+                    self.write_base_expr(else_statement);
+                }
+            }
+            BaseExprData::ElseStatement { body } => {
+                self.write_line(base_expr, "else");
+                self.write_block(body);
+            }
+            BaseExprData::ForLoop {
+                var_name,
+                until,
+                body,
+            } => {
+                let line = format!("for {var_name} in {}", expression_to_source(until));
+                self.write_line(base_expr, &line);
+                self.write_block(body);
+            }
+            BaseExprData::WhileLoop { condition, body } => {
+                let line = format!("while {}", expression_to_source(condition));
+                self.write_line(base_expr, &line);
+                self.write_block(body);
+            }
+            // A function defined inside another is only typechecked when it is called, one that is never called has no instance
+            BaseExprData::FunctionDefinition { fun_name, args, body }
+                if !self.functions.is_empty() && args.is_empty() && body.is_empty() =>
+            {
+                if let Some(function) = self.functions.iter().find(|function| function.name == *fun_name) {
+                    let source = function_to_source(function, self.functions, self.annotate_types, self.indentation);
+                    self.write(&source);
+                }
+            }
+            BaseExprData::FunctionDefinition {
+                fun_name,
+                args,
+                body,
+            } => {
+                let parameters: Vec<String> = args
+                    .iter()
+                    .map(|arg| match &arg.default {
+                        Some(default) => format!("{} = {}", arg.name, expression_to_source(default)),
+                        None if arg.rest => format!("*{}", arg.name),
+                        None => arg.name.clone(),
+                    })
+                    .collect();
+                self.write_line(base_expr, &format!("fun {fun_name}({})", parameters.join(", ")));
+                self.write_block(body);
+            }
+            BaseExprData::StructDefinition { name, fields } => {
+                self.write_line(base_expr, &format!("struct {name}"));
+                for field in fields {
+                    self.write(&"\t".repeat(self.indentation + 1));
+                    self.write(field);
+                    self.write("\n");
+                }
+            }
+            BaseExprData::Return { return_value } => {
+                let line = match return_value {
+                    Some(return_value) => format!("return {}", expression_to_source(return_value)),
+                    None => String::from("return"),
+                };
+                self.write_line(base_expr, &line);
+            }
+            BaseExprData::Break => self.write_line(base_expr, "break"),
+            BaseExprData::Continue => self.write_line(base_expr, "continue"),
+            BaseExprData::Yield { value } => {
+                self.write_line(base_expr, &format!("yield {}", expression_to_source(value)));
+            }
+            BaseExprData::Delete { var_name } => {
+                self.write_line(base_expr, &format!("del {var_name}"));
+            }
+            BaseExprData::Swap {
+                first_name,
+                second_name,
+            } => {
+                self.write_line(base_expr, &format!("swap {first_name}, {second_name}"));
+            }
+            BaseExprData::Defer { expr } => {
+                self.write_line(base_expr, &format!("defer {}", expression_to_source(expr)));
+            }
+            BaseExprData::With {
+                resource,
+                var_name,
+                body,
+            } => {
+                let line = format!("with {} as {var_name}", expression_to_source(resource));
+                self.write_line(base_expr, &line);
+                self.write_block(body);
+            }
+            BaseExprData::Match { subject, arms } => {
+                self.write_line(base_expr, &format!("match {}", expression_to_source(subject)));
+                self.indentation += 1;
+                for arm in arms {
+                    self.write(&"\t".repeat(self.indentation));
+                    self.write(&format!("case {}\n", pattern_to_source(&arm.pattern)));
+                    self.write_block(&arm.body);
+                }
+                self.indentation -= 1;
+            }
+        })
+    }
+}
+
+fn pattern_to_source(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Number(number) => number.to_string(),
+        Pattern::String(value) => format!("\"{value}\""),
+        Pattern::Boolean(value) => value.to_string(),
+        Pattern::Binding(name) => name.clone(),
+        Pattern::Wildcard => "_".to_string(),
+    }
+}
+
+// How tightly an expression binds its operands, following the operator precedence of the parser.
+// Every binary operator is left associative, and the unary operators bind tighter than all of them.
+const LOWEST_PRECEDENCE: u8 = 0;
+const UNARY_PRECEDENCE: u8 = 8;
+const ATOM_PRECEDENCE: u8 = 9;
+
+fn precedence<T: Clone>(expression: &RecExpr<T>) -> u8 {
+    match &expression.data {
+        RecExprData::Assign { .. } | RecExprData::Conditional { .. } => LOWEST_PRECEDENCE,
+        // Negative literals are only made by constant evaluation, they are written as a negated literal
+        RecExprData::Number { number } if *number < 0 => LOWEST_PRECEDENCE,
+        RecExprData::Float { value } if value.is_sign_negative() => LOWEST_PRECEDENCE,
+        RecExprData::Or { .. } => 1,
+        RecExprData::And { .. } => 2,
+        RecExprData::Equals { .. } | RecExprData::NotEquals { .. } => 3,
+        RecExprData::GreaterThan { .. }
+        | RecExprData::LessThan { .. }
+        | RecExprData::GreaterThanOrEqual { .. }
+        | RecExprData::LessThanOrEqual { .. } => 4,
+        RecExprData::Add { .. } | RecExprData::Subtract { .. } => 5,
+        RecExprData::Multiply { .. }
+        | RecExprData::Divide { .. }
+        | RecExprData::FloorDivide { .. } => 6,
+        RecExprData::Power { .. } => 7,
+        RecExprData::Minus { .. } | RecExprData::Not { .. } => UNARY_PRECEDENCE,
+        _ => ATOM_PRECEDENCE,
+    }
+}
+
+// Writes the expression, parenthesized if it binds looser than the given precedence
+fn expression_with_precedence<T: Clone>(expression: &RecExpr<T>, minimum_precedence: u8) -> String {
+    // Nested expressions recurse once per level, see stack::ensure_stack
+    stack::ensure_stack(|| {
+        let source = match &expression.data {
+            RecExprData::Variable { name } => name.clone(),
+            RecExprData::Number { number } => number.to_string(),
+            // The debug format always keeps the decimal point, so the literal stays a float
+            RecExprData::Float { value } => format!("{value:?}"),
+            RecExprData::String { value } => format!("\"{value}\""),
+            RecExprData::Boolean { value } => value.to_string(),
+            RecExprData::Assign {
+                variable_name,
+                right,
+            } => format!("{variable_name} = {}", expression_to_source(right)),
+            RecExprData::Add { left, right } => binary_to_source(expression, left, "+", right),
+            RecExprData::Subtract { left, right } => binary_to_source(expression, left, "-", right),
+            RecExprData::Multiply { left, right } => binary_to_source(expression, left, "*", right),
+            RecExprData::Divide { left, right } => binary_to_source(expression, left, "/", right),
+            RecExprData::FloorDivide { left, right } => {
+                binary_to_source(expression, left, "//", right)
+            }
+            RecExprData::Power { left, right } => binary_to_source(expression, left, "^", right),
+            RecExprData::Or { left, right } => binary_to_source(expression, left, "or", right),
+            RecExprData::And { left, right } => binary_to_source(expression, left, "and", right),
+            RecExprData::Equals { left, right } => binary_to_source(expression, left, "==", right),
+            RecExprData::NotEquals { left, right } => {
+                binary_to_source(expression, left, "!=", right)
+            }
+            RecExprData::GreaterThan { left, right } => {
+                binary_to_source(expression, left, ">", right)
+            }
+            RecExprData::LessThan { left, right } => binary_to_source(expression, left, "<", right),
+            RecExprData::GreaterThanOrEqual { left, right } => {
+                binary_to_source(expression, left, ">=", right)
+            }
+            RecExprData::LessThanOrEqual { left, right } => {
+                binary_to_source(expression, left, "<=", right)
+            }
+            // The operand of a unary operator is parenthesized unless it is a single value,
+            // which also keeps two minus signs from being read as one symbol
+            RecExprData::Minus { right } => {
+                format!("-{}", expression_with_precedence(right, ATOM_PRECEDENCE))
+            }
+            RecExprData::Not { right } => {
+                format!("not {}", expression_with_precedence(right, ATOM_PRECEDENCE))
+            }
+            RecExprData::Access { object, variable } => format!("{object}.{variable}"),
+            RecExprData::FunctionCall {
+                function_name,
+                args,
+            } => format!("{function_name}({})", expressions_to_source(args)),
+            RecExprData::List { elements } => format!("[{}]", expressions_to_source(elements)),
+            // A tuple with a single element keeps its comma, (1) would be read as 1
+            RecExprData::Tuple { elements } if elements.len() == 1 => {
+                format!("({},)", expression_to_source(&elements[0]))
+            }
+            RecExprData::Tuple { elements } => format!("({})", expressions_to_source(elements)),
+            RecExprData::KeywordArgument { name, value } => format!("{name} = {}", expression_to_source(value)),
+            // Only the value after else can be another conditional without parentheses
+            RecExprData::Conditional {
+                condition,
+                then_value,
+                else_value,
+            } => format!(
+                "{} if {} else {}",
+                expression_with_precedence(then_value, LOWEST_PRECEDENCE + 1),
+                expression_with_precedence(condition, LOWEST_PRECEDENCE + 1),
+                expression_to_source(else_value)
+            ),
+            RecExprData::Dict { entries } => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(key, value)| {
+                        format!("{}: {}", expression_to_source(key), expression_to_source(value))
+                    })
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            RecExprData::ListAccess { list, index } => format!(
+                "{}[{}]",
+                expression_with_precedence(list, ATOM_PRECEDENCE),
+                expression_to_source(index)
+            ),
+            RecExprData::Slice { list, start, end } => format!(
+                "{}[{}:{}]",
+                expression_with_precedence(list, ATOM_PRECEDENCE),
+                start.as_ref().map(|start| expression_to_source(start)).unwrap_or_default(),
+                end.as_ref().map(|end| expression_to_source(end)).unwrap_or_default()
+            ),
+        };
+
+        match precedence(expression) < minimum_precedence {
+            true => format!("({source})"),
+            false => source,
+        }
+    })
+}
+
+fn binary_to_source<T: Clone>(
+    expression: &RecExpr<T>,
+    left: &RecExpr<T>,
+    operator: &str,
+    right: &RecExpr<T>,
+) -> String {
+    let operator_precedence = precedence(expression);
+    // The right operand of a left associative operator needs parentheses at the same precedence: a - (b - c)
+    format!(
+        "{} {operator} {}",
+        expression_with_precedence(left, operator_precedence),
+        expression_with_precedence(right, operator_precedence + 1)
+    )
+}
+
+fn expressions_to_source<T: Clone>(expressions: &[RecExpr<T>]) -> String {
+    expressions
+        .iter()
+        .map(expression_to_source)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
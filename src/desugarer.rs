@@ -1,8 +1,10 @@
 use crate::parser;
 use crate::parser::BaseExpr;
 use crate::parser::RecExpr;
+use crate::stack;
 
 pub fn desugar(base_expressions: Vec<BaseExpr<()>>) -> Vec<BaseExpr<()>> {
+    let _span = tracing::debug_span!("desugar").entered();
     let mut desugared_expressions = Vec::new();
 
     for base_expr in base_expressions {
@@ -10,10 +12,16 @@ pub fn desugar(base_expressions: Vec<BaseExpr<()>>) -> Vec<BaseExpr<()>> {
         desugared_expressions.extend(desugared_expr);
     }
 
+    tracing::debug!(statements = desugared_expressions.len(), "desugared");
     return desugared_expressions;
 }
 
+// Nested blocks recurse once per level, see stack::ensure_stack
 fn desugar_base_expr(base_expr: BaseExpr<()>) -> Vec<BaseExpr<()>> {
+    stack::ensure_stack(|| desugar_base_expr_unguarded(base_expr))
+}
+
+fn desugar_base_expr_unguarded(base_expr: BaseExpr<()>) -> Vec<BaseExpr<()>> {
     match base_expr.data {
         parser::BaseExprData::PlusEqualsStatement { var_name, expr } => {
             let var_name_len = var_name.len();
@@ -24,22 +32,16 @@ fn desugar_base_expr(base_expr: BaseExpr<()>) -> Vec<BaseExpr<()>> {
                         data: parser::RecExprData::Add {
                             left: Box::new(RecExpr {
                                 data: parser::RecExprData::Variable { name: var_name },
-                                row: base_expr.row,
-                                col_start: base_expr.col_start,
-                                col_end: base_expr.col_start + var_name_len,
+                                span: base_expr.span.first_columns(var_name_len),
                                 generic_data: (),
                             }),
                             right: Box::new(expr),
                         },
-                        row: base_expr.row,
-                        col_start: base_expr.col_start,
-                        col_end: base_expr.col_end,
+                        span: base_expr.span,
                         generic_data: (),
                     },
                 },
-                row: base_expr.row,
-                col_start: base_expr.col_start,
-                col_end: base_expr.col_end,
+                span: base_expr.span,
                 generic_data: (),
             };
             return vec![assignment];
@@ -64,9 +66,59 @@ fn desugar_base_expr(base_expr: BaseExpr<()>) -> Vec<BaseExpr<()>> {
                     until,
                     body: desugared_expressions,
                 },
-                row: base_expr.row,
-                col_start: base_expr.col_start,
-                col_end: base_expr.col_end,
+                span: base_expr.span,
+                generic_data: (),
+            }];
+        }
+        parser::BaseExprData::WhileLoop { condition, body } => {
+            let mut desugared_expressions = Vec::new();
+
+            for base_expr in body {
+                let desugared_expr = desugar_base_expr(base_expr);
+                desugared_expressions.extend(desugared_expr);
+            }
+            vec![BaseExpr {
+                data: parser::BaseExprData::WhileLoop {
+                    condition,
+                    body: desugared_expressions,
+                },
+                span: base_expr.span,
+                generic_data: (),
+            }]
+        }
+        parser::BaseExprData::With {
+            resource,
+            var_name,
+            body,
+        } => {
+            let mut desugared_expressions = Vec::new();
+
+            for base_expr in body {
+                let desugared_expr = desugar_base_expr(base_expr);
+                desugared_expressions.extend(desugared_expr);
+            }
+            return vec![BaseExpr {
+                data: parser::BaseExprData::With {
+                    resource,
+                    var_name,
+                    body: desugared_expressions,
+                },
+                span: base_expr.span,
+                generic_data: (),
+            }];
+        }
+        parser::BaseExprData::Match { subject, arms } => {
+            let arms = arms
+                .into_iter()
+                .map(|arm| {
+                    let span = arm.span();
+                    let body = arm.body.into_iter().flat_map(desugar_base_expr).collect();
+                    parser::MatchArm::new(arm.pattern, body, span)
+                })
+                .collect();
+            return vec![BaseExpr {
+                data: parser::BaseExprData::Match { subject, arms },
+                span: base_expr.span,
                 generic_data: (),
             }];
         }
@@ -97,9 +149,7 @@ fn desugar_base_expr(base_expr: BaseExpr<()>) -> Vec<BaseExpr<()>> {
                     body: desugared_expressions,
                     else_statement: desugared_else,
                 },
-                row: base_expr.row,
-                col_start: base_expr.col_start,
-                col_end: base_expr.col_end,
+                span: base_expr.span,
                 generic_data: (),
             }];
         }
@@ -130,9 +180,7 @@ fn desugar_base_expr(base_expr: BaseExpr<()>) -> Vec<BaseExpr<()>> {
                     body: desugared_expressions,
                     else_statement: desugared_else,
                 },
-                row: base_expr.row,
-                col_start: base_expr.col_start,
-                col_end: base_expr.col_end,
+                span: base_expr.span,
                 generic_data: (),
             }];
         }
@@ -147,9 +195,7 @@ fn desugar_base_expr(base_expr: BaseExpr<()>) -> Vec<BaseExpr<()>> {
                 data: parser::BaseExprData::ElseStatement {
                     body: desugared_expressions,
                 },
-                row: base_expr.row,
-                col_start: base_expr.col_start,
-                col_end: base_expr.col_end,
+                span: base_expr.span,
                 generic_data: (),
             }];
         }
@@ -170,9 +216,7 @@ fn desugar_base_expr(base_expr: BaseExpr<()>) -> Vec<BaseExpr<()>> {
                     args,
                     body: desugared_expressions,
                 },
-                row: base_expr.row,
-                col_start: base_expr.col_start,
-                col_end: base_expr.col_end,
+                span: base_expr.span,
                 generic_data: (),
             }];
         }
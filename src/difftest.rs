@@ -0,0 +1,304 @@
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::compiler::Target;
+use crate::engine::Engine;
+use crate::interpreter::ProgramPoll;
+use crate::interpreter::Suspension;
+use crate::jstranspiler;
+use crate::language::LanguageOptions;
+use crate::optimiser::OptimisationOptions;
+use crate::pipeline;
+use crate::pytranspiler;
+use crate::tokenizer::Error;
+
+// Runs a program on every execution engine and compares what they print and the exit code they end with,
+// so the engines do not drift apart. The interpreter is the reference the other engines are compared with.
+// An engine which cannot run the program at all, such as JavaScript for a program reading files,
+// is skipped instead of counted as a difference.
+
+// A compiled program which runs longer than this is stopped, a miscompiled loop may never end
+const RUN_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Backend {
+    // The tree walking interpreter, which the other engines are compared with
+    Interpreter,
+    // The interpreter compiling the functions which are called often to native code
+    #[cfg(feature = "jit")]
+    Jit,
+    // The executable written by rosy compile, for the platform rosy runs on
+    Native,
+    // The program written by compile --emit js, run with node
+    JavaScript,
+    // The program written by compile --emit python, run with python3
+    Python,
+}
+
+impl Backend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Interpreter => "interpreter",
+            #[cfg(feature = "jit")]
+            Backend::Jit => "jit",
+            Backend::Native => "native",
+            Backend::JavaScript => "javascript",
+            Backend::Python => "python",
+        }
+    }
+}
+
+// The engines of this build, without those whose runtime is not installed, such as node for JavaScript
+pub fn available_backends() -> Vec<Backend> {
+    let mut backends = Vec::from([Backend::Interpreter]);
+    #[cfg(feature = "jit")]
+    backends.push(Backend::Jit);
+    backends.push(Backend::Native);
+    if is_installed("node") {
+        backends.push(Backend::JavaScript);
+    }
+    if is_installed(python_command()) {
+        backends.push(Backend::Python);
+    }
+    backends
+}
+
+fn is_installed(command: &str) -> bool {
+    Command::new(command)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+fn python_command() -> &'static str {
+    match cfg!(windows) {
+        true => "python",
+        false => "python3",
+    }
+}
+
+pub struct DiffOptions {
+    pub backends: Vec<Backend>,
+    pub language: LanguageOptions,
+    // The passes run before the program is compiled or transpiled, the interpreter runs the program as written
+    pub optimisation: OptimisationOptions,
+}
+
+// What a program printed to stdout and the exit code it ended with.
+// A program which stops with an error in the interpreter ends with exit code 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunOutput {
+    pub output: String,
+    pub exit_code: i32,
+}
+
+// How the run of a program on an engine compares with the interpreter
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Same,
+    Different(RunOutput),
+    // Why the engine cannot run the program, such as a builtin without a JavaScript counterpart
+    Skipped(String),
+}
+
+#[derive(Debug)]
+pub struct Comparison {
+    pub backend: Backend,
+    pub outcome: Outcome,
+}
+
+// How all engines together compare with the interpreter on a program
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Verdict {
+    // No engine differed, and at least one gave the same as the interpreter
+    Agreed,
+    Differed,
+    // Every other engine was skipped, so the program was not compared with anything
+    NotCompared,
+}
+
+// How the engines compared with the interpreter on a program, given each comparison
+pub fn verdict(comparisons: &[Comparison]) -> Verdict {
+    if comparisons.iter().any(|comparison| matches!(comparison.outcome, Outcome::Different(_))) {
+        Verdict::Differed
+    } else if comparisons.iter().any(|comparison| comparison.outcome == Outcome::Same) {
+        Verdict::Agreed
+    } else {
+        Verdict::NotCompared
+    }
+}
+
+// Runs the program in the interpreter and on the other engines of the options.
+// Gives what the interpreter printed and how every other engine compares with it.
+// A program the interpreter cannot read gives the error.
+pub fn compare_backends(lines: Vec<&str>, options: &DiffOptions) -> Result<(RunOutput, Vec<Comparison>), Error> {
+    let reference = interpret(lines.clone(), options, false)?;
+
+    let mut comparisons = Vec::new();
+    for backend in options.backends.iter().filter(|backend| **backend != Backend::Interpreter) {
+        let outcome = match run_on(*backend, lines.clone(), options) {
+            Ok(run) if run == reference => Outcome::Same,
+            Ok(run) => Outcome::Different(run),
+            Err(reason) => Outcome::Skipped(reason),
+        };
+        comparisons.push(Comparison {
+            backend: *backend,
+            outcome,
+        });
+    }
+    Ok((reference, comparisons))
+}
+
+// Runs the program on a single engine, an error is the reason the engine cannot run it
+fn run_on(backend: Backend, lines: Vec<&str>, options: &DiffOptions) -> Result<RunOutput, String> {
+    match backend {
+        Backend::Interpreter => interpret(lines, options, false).map_err(|error| error.message().to_string()),
+        #[cfg(feature = "jit")]
+        Backend::Jit => interpret(lines, options, true).map_err(|error| error.message().to_string()),
+        Backend::Native => run_native(lines, options),
+        Backend::JavaScript => {
+            let typed_program = pipeline::typed_program_for_transpiling(lines, &options.language, &options.optimisation)
+                .map_err(|error| error.message().to_string())?;
+            let javascript =
+                jstranspiler::typed_program_to_javascript(&typed_program).map_err(|error| error.message().to_string())?;
+            run_source_file(&javascript, "js", "node")
+        }
+        Backend::Python => {
+            let typed_program = pipeline::typed_program_for_transpiling(lines, &options.language, &options.optimisation)
+                .map_err(|error| error.message().to_string())?;
+            let python = pytranspiler::typed_program_to_python(&typed_program).map_err(|error| error.message().to_string())?;
+            run_source_file(&python, "py", python_command())
+        }
+    }
+}
+
+// Interprets the program without writing to stdout. An error while it runs ends it with exit code 1,
+// an error reading it is returned, as is a panic of the interpreter.
+fn interpret(lines: Vec<&str>, options: &DiffOptions, jit: bool) -> Result<RunOutput, Error> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| interpret_unguarded(lines, options, jit))).unwrap_or_else(|panic| {
+        Err(Error::SimpleError {
+            message: format!("The interpreter stopped: {}", panic_message(panic.as_ref())),
+        })
+    })
+}
+
+fn interpret_unguarded(lines: Vec<&str>, options: &DiffOptions, jit: bool) -> Result<RunOutput, Error> {
+    let mut engine = Engine::new();
+    engine.options.language = options.language.clone();
+    engine.options.quiet = true;
+    engine.options.jit = jit;
+    let mut program = engine.load(lines)?;
+
+    let result = loop {
+        match program.poll() {
+            Ok(ProgramPoll::Finished) => break Ok(()),
+            Ok(ProgramPoll::Suspended(Suspension::Sleep(duration))) => std::thread::sleep(duration),
+            Ok(ProgramPoll::Suspended(Suspension::Host)) => {}
+            Err(error) => break Err(error),
+        }
+    };
+    Ok(RunOutput {
+        output: program.context.terminal.join("\n"),
+        exit_code: match result {
+            Ok(()) => 0,
+            Err(_) => 1,
+        },
+    })
+}
+
+fn run_native(lines: Vec<&str>, options: &DiffOptions) -> Result<RunOutput, String> {
+    let target = Target::host();
+    let path = temporary_path(target.executable_extension());
+
+    // The native backend does not support every part of the language yet, and panics on some of it
+    let built = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        pipeline::build_executable(lines, &path, &options.language, target, &options.optimisation)
+    }));
+    let result = match built {
+        Ok(Ok(())) => run_command(Command::new(&path)),
+        Ok(Err(error)) => Err(error.message().to_string()),
+        Err(panic) => Err(format!("The compiler stopped: {}", panic_message(panic.as_ref()))),
+    };
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    match (panic.downcast_ref::<&str>(), panic.downcast_ref::<String>()) {
+        (Some(message), _) => message.to_string(),
+        (_, Some(message)) => message.clone(),
+        _ => String::from("unknown error"),
+    }
+}
+
+// Writes the source to a temporary file and runs it with the interpreter of its language
+fn run_source_file(source: &str, extension: &str, interpreter: &str) -> Result<RunOutput, String> {
+    let path = temporary_path(extension);
+    std::fs::write(&path, source).map_err(|err| format!("Could not write {}: {}", path.display(), err))?;
+    let mut command = Command::new(interpreter);
+    command.arg(&path);
+    let result = run_command(command);
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+// A path in the temporary directory no other run uses, with the extension, if any
+fn temporary_path(extension: &str) -> PathBuf {
+    let unique = format!(
+        "rosy_difftest_{}_{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|time| time.as_nanos())
+            .unwrap_or(0)
+    );
+    let mut path = std::env::temp_dir().join(unique);
+    if !extension.is_empty() {
+        path.set_extension(extension);
+    }
+    path
+}
+
+// Runs the command and gives what it wrote to stdout. A program stopped by a signal or
+// by the timeout ends with exit code -1.
+fn run_command(mut command: Command) -> Result<RunOutput, String> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| format!("Could not run {:?}: {}", command.get_program(), err))?;
+
+    // Read while the program runs, so it does not block on a full pipe
+    let mut stdout = child.stdout.take().unwrap();
+    let reader = std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        output
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if started.elapsed() > RUN_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break None;
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(5)),
+            Err(err) => return Err(format!("Could not wait for {:?}: {}", command.get_program(), err)),
+        }
+    };
+
+    let output = reader.join().unwrap_or_default();
+    Ok(RunOutput {
+        output: String::from_utf8_lossy(&output).into_owned(),
+        exit_code: status.and_then(|status| status.code()).unwrap_or(-1),
+    })
+}
@@ -0,0 +1,249 @@
+use std::io::prelude::*;
+
+// Writes the machine code as a statically linked Linux executable.
+// The code generator calls the KERNEL32 functions of Windows with the Windows x64 calling convention,
+// so the executable starts with a runtime stub that provides those functions on top of Linux system calls.
+// Each extern call reads the address of its function from a table, like the import address table of an exe.
+
+// Where the file is loaded in memory, the whole file is a single segment
+const IMAGE_BASE: u64 = 0x400000;
+const ELF_HEADER_SIZE: usize = 64;
+const PROGRAM_HEADER_SIZE: usize = 56;
+const PROGRAM_HEADER_COUNT: usize = 2;
+
+struct StubFunction {
+	name: &'static str,
+	code: &'static [u8],
+}
+
+// Calls the program like the Windows loader does, so the stack has the alignment the program expects,
+// and exits if the program ever returns. The call target is filled in once the layout is known.
+const START: [u8; 14] = [
+	0xE8, 0x00, 0x00, 0x00, 0x00, // call program
+	0x31, 0xFF, // xor edi, edi
+	0xB8, 0xE7, 0x00, 0x00, 0x00, // mov eax, 231 (exit_group)
+	0x0F, 0x05, // syscall
+];
+
+// The functions keep the registers which are callee saved on Windows (rdi and rsi among them),
+// a system call itself only changes rax, rcx and r11
+const STUB_FUNCTIONS: [StubFunction; 6] = [
+	// ExitProcess(exit code)
+	StubFunction {
+		name: "ExitProcess",
+		code: &[
+			0x89, 0xCF, // mov edi, ecx
+			0xB8, 0xE7, 0x00, 0x00, 0x00, // mov eax, 231 (exit_group)
+			0x0F, 0x05, // syscall
+		],
+	},
+	// GetStdHandle(-10, -11 or -12) gives the file descriptor 0, 1 or 2
+	StubFunction {
+		name: "GetStdHandle",
+		code: &[
+			0x48, 0xC7, 0xC0, 0xF6, 0xFF, 0xFF, 0xFF, // mov rax, -10
+			0x48, 0x29, 0xC8, // sub rax, rcx
+			0xC3, // ret
+		],
+	},
+	// WriteFile(handle, buffer, length, bytes written or null, overlapped)
+	StubFunction {
+		name: "WriteFile",
+		code: &[
+			0x57, // push rdi
+			0x56, // push rsi
+			0x48, 0x89, 0xCF, // mov rdi, rcx
+			0x48, 0x89, 0xD6, // mov rsi, rdx
+			0x4C, 0x89, 0xC2, // mov rdx, r8
+			0xB8, 0x01, 0x00, 0x00, 0x00, // mov eax, 1 (write)
+			0x0F, 0x05, // syscall
+			0x4D, 0x85, 0xC9, // test r9, r9
+			0x74, 0x03, // jz skip
+			0x41, 0x89, 0x01, // mov [r9], eax
+			// skip: a negative result is an error, which makes WriteFile return false
+			0x48, 0x85, 0xC0, // test rax, rax
+			0x0F, 0x99, 0xC0, // setns al
+			0x0F, 0xB6, 0xC0, // movzx eax, al
+			0x5E, // pop rsi
+			0x5F, // pop rdi
+			0xC3, // ret
+		],
+	},
+	// GetProcessHeap() gives a handle which is never looked at
+	StubFunction {
+		name: "GetProcessHeap",
+		code: &[
+			0xB8, 0x01, 0x00, 0x00, 0x00, // mov eax, 1
+			0xC3, // ret
+		],
+	},
+	// HeapAlloc(heap, flags, size) maps zeroed memory, with the size of the mapping stored in front of it
+	StubFunction {
+		name: "HeapAlloc",
+		code: &[
+			0x57, // push rdi
+			0x56, // push rsi
+			0x31, 0xFF, // xor edi, edi
+			0x49, 0x8D, 0x70, 0x10, // lea rsi, [r8 + 16]
+			0xBA, 0x03, 0x00, 0x00, 0x00, // mov edx, 3 (PROT_READ | PROT_WRITE)
+			0x41, 0xBA, 0x22, 0x00, 0x00, 0x00, // mov r10d, 0x22 (MAP_PRIVATE | MAP_ANONYMOUS)
+			0x49, 0xC7, 0xC0, 0xFF, 0xFF, 0xFF, 0xFF, // mov r8, -1
+			0x45, 0x31, 0xC9, // xor r9d, r9d
+			0xB8, 0x09, 0x00, 0x00, 0x00, // mov eax, 9 (mmap)
+			0x0F, 0x05, // syscall
+			0x48, 0x3D, 0x01, 0xF0, 0xFF, 0xFF, // cmp rax, -4095
+			0x73, 0x09, // jae fail
+			0x48, 0x89, 0x30, // mov [rax], rsi
+			0x48, 0x83, 0xC0, 0x10, // add rax, 16
+			0xEB, 0x02, // jmp done
+			0x31, 0xC0, // fail: xor eax, eax
+			0x5E, // done: pop rsi
+			0x5F, // pop rdi
+			0xC3, // ret
+		],
+	},
+	// HeapFree(heap, flags, pointer) unmaps memory given by HeapAlloc
+	StubFunction {
+		name: "HeapFree",
+		code: &[
+			0x57, // push rdi
+			0x56, // push rsi
+			0x4D, 0x85, 0xC0, // test r8, r8
+			0x74, 0x0E, // jz done
+			0x49, 0x8D, 0x78, 0xF0, // lea rdi, [r8 - 16]
+			0x48, 0x8B, 0x37, // mov rsi, [rdi]
+			0xB8, 0x0B, 0x00, 0x00, 0x00, // mov eax, 11 (munmap)
+			0x0F, 0x05, // syscall
+			0xB8, 0x01, 0x00, 0x00, 0x00, // done: mov eax, 1
+			0x5E, // pop rsi
+			0x5F, // pop rdi
+			0xC3, // ret
+		],
+	},
+];
+
+pub fn write_elf_file(path: &std::path::Path, machine_code: &mut [u8], syscalls_to_resolve: &[(String, usize)], starting_location: usize) -> std::io::Result<()> {
+	let mut file = std::fs::File::create(path)?;
+	file.write_all(&elf_bytes(machine_code, syscalls_to_resolve, starting_location))?;
+
+	// Executables are only run when they have the permission to be
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		file.set_permissions(std::fs::Permissions::from_mode(0o755))?;
+	}
+
+	Ok(())
+}
+
+// Layout of the file: headers, the table of stub function addresses, the startup stub, the stub functions and the program
+fn elf_bytes(machine_code: &mut [u8], syscalls_to_resolve: &[(String, usize)], starting_location: usize) -> Vec<u8> {
+	let table_location = ELF_HEADER_SIZE + PROGRAM_HEADER_SIZE * PROGRAM_HEADER_COUNT;
+	let start_location = table_location + 8 * STUB_FUNCTIONS.len();
+
+	let mut stub: Vec<u8> = Vec::new();
+	write_bytes(&mut stub, &START);
+	let mut function_locations = Vec::new();
+	for function in STUB_FUNCTIONS.iter() {
+		function_locations.push(start_location + stub.len());
+		write_bytes(&mut stub, function.code);
+	}
+	let padding = (16 - (start_location + stub.len()) % 16) % 16;
+	write_zeroes(&mut stub, padding);
+	let code_location = start_location + stub.len();
+
+	// The call to the program is relative to the end of the call instruction
+	let program_offset = (code_location + starting_location) as i64 - (start_location + 5) as i64;
+	write_at_u32(&mut stub, 1, program_offset as u32);
+
+	for (syscall_name, at) in syscalls_to_resolve {
+		let Some(function_index) = STUB_FUNCTIONS.iter().position(|function| function.name == syscall_name) else {
+			panic!("Could not find extern call {}", syscall_name);
+		};
+		let slot_location = table_location + 8 * function_index;
+		let slot_offset = slot_location as i64 - (code_location + at + 4) as i64;
+		write_at_u32(machine_code, *at, slot_offset as u32);
+	}
+
+	let file_size = (code_location + machine_code.len()) as u64;
+
+	let mut bytes: Vec<u8> = Vec::new();
+
+	// ELF header https://refspecs.linuxfoundation.org/elf/gabi4+/ch4.eheader.html
+	write_bytes(&mut bytes, &[0x7F, b'E', b'L', b'F']); // Magic number
+	write_bytes(&mut bytes, &[0x02, 0x01, 0x01, 0x00]); // 64 bit, little endian, version 1, System V ABI
+	write_zeroes(&mut bytes, 8); // ABI version and padding
+	write_u16(&mut bytes, 0x02); // Type: executable
+	write_u16(&mut bytes, 0x3E); // Machine: x86-64
+	write_u32(&mut bytes, 0x01); // Version
+	write_u64(&mut bytes, IMAGE_BASE + start_location as u64); // Entry point
+	write_u64(&mut bytes, ELF_HEADER_SIZE as u64); // Program header table offset
+	write_u64(&mut bytes, 0x00); // Section header table offset, there are no sections
+	write_u32(&mut bytes, 0x00); // Flags
+	write_u16(&mut bytes, ELF_HEADER_SIZE as u16); // ELF header size
+	write_u16(&mut bytes, PROGRAM_HEADER_SIZE as u16); // Program header size
+	write_u16(&mut bytes, PROGRAM_HEADER_COUNT as u16); // Number of program headers
+	write_u16(&mut bytes, 0x40); // Section header size
+	write_u16(&mut bytes, 0x00); // Number of section headers
+	write_u16(&mut bytes, 0x00); // Index of the section name table
+
+	// One loadable segment for the whole file, readable and executable
+	write_u32(&mut bytes, 0x01); // Type: PT_LOAD
+	write_u32(&mut bytes, 0x05); // Flags: read and execute
+	write_u64(&mut bytes, 0x00); // Offset in the file
+	write_u64(&mut bytes, IMAGE_BASE); // Virtual address
+	write_u64(&mut bytes, IMAGE_BASE); // Physical address
+	write_u64(&mut bytes, file_size); // Size in the file
+	write_u64(&mut bytes, file_size); // Size in memory
+	write_u64(&mut bytes, 0x1000); // Alignment
+
+	// The stack is not executable
+	write_u32(&mut bytes, 0x6474E551); // Type: PT_GNU_STACK
+	write_u32(&mut bytes, 0x06); // Flags: read and write
+	write_zeroes(&mut bytes, 40); // Offsets, addresses and sizes are unused
+	write_u64(&mut bytes, 0x10); // Alignment
+
+	// The table of stub function addresses
+	for function_location in function_locations {
+		write_u64(&mut bytes, IMAGE_BASE + function_location as u64);
+	}
+
+	write_bytes(&mut bytes, &stub);
+	write_bytes(&mut bytes, machine_code);
+
+	bytes
+}
+
+fn write_u16(buf: &mut Vec<u8>, value: u16) -> usize {
+	let index = buf.len();
+	buf.extend_from_slice(&value.to_le_bytes());
+	index
+}
+
+fn write_u32(buf: &mut Vec<u8>, value: u32) -> usize {
+	let index = buf.len();
+	buf.extend_from_slice(&value.to_le_bytes());
+	index
+}
+
+fn write_u64(buf: &mut Vec<u8>, value: u64) -> usize {
+	let index = buf.len();
+	buf.extend_from_slice(&value.to_le_bytes());
+	index
+}
+
+fn write_bytes(buf: &mut Vec<u8>, data: &[u8]) -> usize {
+	let index = buf.len();
+	buf.extend_from_slice(data);
+	index
+}
+
+fn write_zeroes(buf: &mut Vec<u8>, count: usize) -> usize {
+	let index = buf.len();
+	buf.resize(buf.len() + count, 0);
+	index
+}
+
+fn write_at_u32(buf: &mut [u8], at: usize, value: u32) {
+	buf[at..at + 4].copy_from_slice(&value.to_le_bytes());
+}
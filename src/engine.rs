@@ -0,0 +1,356 @@
+use crate::builtins;
+use crate::builtins::Builtin;
+use crate::desugarer;
+use crate::interpreter;
+use crate::interpreter::Context;
+use crate::interpreter::InterpreterOptions;
+use crate::interpreter::ProgramState;
+use crate::interpreter::Terminal;
+use crate::interpreter::Value;
+use crate::optimiser;
+use crate::optimiser::OptimisationStats;
+use crate::parser;
+use crate::parser::BaseExpr;
+use crate::parser::BaseExprData;
+use crate::parser::RecExpr;
+use crate::parser::RecExprData;
+use crate::tokenizer::Error;
+use crate::typechecker;
+use crate::verifier;
+use crate::visitor;
+use crate::visitor::Visitor;
+
+// An interpreter instance for embedding rosy in another program.
+// The engine starts out with the builtins enabled in this build,
+// the host can add its own native builtins on top of those.
+pub struct Engine {
+    builtins: Vec<Builtin>,
+    pub options: InterpreterOptions,
+    // The script started with Engine::start, which event handlers are called in
+    script: Option<ProgramState>,
+    // Pairs of event names and the names of the rosy functions handling them, in registration order
+    handlers: Vec<(String, String)>,
+    // Only accepts expressions calling builtins, see Engine::sandboxed
+    sandboxed: bool,
+    // Builtins from plugins point into these libraries, so they are kept loaded
+    // for as long as the engine lives. Fields are dropped in order, so this must stay last.
+    #[cfg(feature = "plugins")]
+    libraries: Vec<libloading::Library>,
+}
+
+// The symbol a plugin library must export to register its builtins:
+//   #[no_mangle]
+//   pub fn rosy_register_plugin(engine: &mut rosy::engine::Engine) { ... }
+// Plugins use the Rust ABI, so they must be built against the same version of rosy
+// with the same compiler as the host.
+#[cfg(feature = "plugins")]
+pub const PLUGIN_REGISTER_SYMBOL: &[u8] = b"rosy_register_plugin";
+
+#[cfg(feature = "plugins")]
+pub type PluginRegisterFunction = fn(&mut Engine);
+
+// Pure builtins left out of a sandboxed engine, as a short call to them can make a string or grid
+// large enough to exhaust the memory of the host
+const SANDBOX_ALLOCATING_BUILTINS: &[&str] = &["repeat", "pad_left", "pad_right", "make_grid"];
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        Engine {
+            builtins: builtins::enabled_builtins(),
+            options: InterpreterOptions::default(),
+            script: None,
+            handlers: Vec::new(),
+            sandboxed: false,
+            #[cfg(feature = "plugins")]
+            libraries: Vec::new(),
+        }
+    }
+
+    // An engine for formulas given by users, which only accepts programs made of expressions.
+    // Loops, function definitions and every other statement are rejected when the program is parsed,
+    // as are calls to builtins with side effects such as print and read_file and to builtins which
+    // can make a result much larger than their arguments, such as repeat. Without those a formula
+    // does a number of steps and makes values bounded by its own length, and cannot read or change
+    // anything outside of the engine. Builtins registered by the host are trusted.
+    pub fn sandboxed() -> Engine {
+        Engine {
+            builtins: builtins::enabled_builtins()
+                .into_iter()
+                .filter(|builtin| {
+                    builtins::is_pure(builtin.name) && !SANDBOX_ALLOCATING_BUILTINS.contains(&builtin.name)
+                })
+                .collect(),
+            sandboxed: true,
+            ..Engine::new()
+        }
+    }
+
+    pub fn is_sandboxed(&self) -> bool {
+        self.sandboxed
+    }
+
+    // Adds a native builtin, replacing any existing builtin with the same name
+    pub fn register_builtin(&mut self, builtin: Builtin) {
+        self.builtins.retain(|existing| existing.name != builtin.name);
+        self.builtins.push(builtin);
+    }
+
+    pub fn builtins(&self) -> &[Builtin] {
+        &self.builtins
+    }
+
+    pub fn run(&self, lines: Vec<&str>) -> Result<Terminal, Error> {
+        Ok(self.execute(lines)?.terminal)
+    }
+
+    // Parses the program with the language options of the engine,
+    // with strict typing the program is also typechecked before it is run.
+    // The optimisation passes selected in the options run on the program as it was written.
+    pub fn parse(&self, lines: Vec<&str>) -> Result<Vec<BaseExpr<()>>, Error> {
+        let language = &self.options.language;
+        let base_expressions = parser::parse_strings_with_options(lines, language)?;
+        verifier::verify("parsing", &base_expressions[..], verifier::check_program)?;
+        if self.sandboxed {
+            for base_expr in &base_expressions {
+                self.check_sandboxed(base_expr)?;
+            }
+        }
+
+        if language.strict_typing {
+            let typed_program = typechecker::type_check_program_with_options(
+                desugarer::desugar(base_expressions.clone()),
+                false,
+                language,
+            )?;
+            verifier::verify("typechecking", &typed_program, verifier::check_typed_program)?;
+        }
+
+        let optimisation = &self.options.optimisation;
+        let mut stats = OptimisationStats::default();
+        let base_expressions =
+            optimiser::optimise_untyped_program(base_expressions, language, optimisation, &mut stats)?;
+        if optimisation.print_stats {
+            print!("{}", stats);
+        }
+
+        Ok(base_expressions)
+    }
+
+    // Prepares the program to be run step by step, for example by a Scheduler
+    pub fn load(&self, lines: Vec<&str>) -> Result<ProgramState, Error> {
+        let base_expressions = self.parse(lines)?;
+
+        Ok(ProgramState::new(base_expressions, &self.builtins, &self.options))
+    }
+
+    // Runs the top level statements of a script and keeps it loaded, so that the functions it defines
+    // can be used as event handlers. Starting another script replaces the previous one.
+    pub fn start(&mut self, lines: Vec<&str>) -> Result<(), Error> {
+        let mut script = self.load(lines)?;
+        while !script.step(usize::MAX)? {}
+
+        self.script = Some(script);
+        Ok(())
+    }
+
+    pub fn script(&self) -> Option<&ProgramState> {
+        self.script.as_ref()
+    }
+
+    // Registers the rosy function with the given name as a handler for the event,
+    // for example engine.on("tick", "update")
+    pub fn on(&mut self, event: &str, function_name: &str) {
+        self.handlers
+            .push((event.to_string(), function_name.to_string()));
+    }
+
+    // Calls every handler of the event with the given arguments, in the order they were registered.
+    // Returns the values returned by the handlers.
+    pub fn trigger(&mut self, event: &str, args: Vec<Value>) -> Result<Vec<Option<Value>>, Error> {
+        let Some(script) = self.script.as_mut() else {
+            return Err(Error::SimpleError {
+                message: format!("Cannot trigger event {} before a script is started", event),
+            });
+        };
+
+        let mut results = Vec::new();
+        for (handled_event, function_name) in self.handlers.iter() {
+            if handled_event == event {
+                results.push(script.call(function_name, args.clone())?);
+            }
+        }
+
+        Ok(results)
+    }
+
+    // Runs the program and returns the whole context of the finished run, including statistics
+    pub fn execute(&self, lines: Vec<&str>) -> Result<Context, Error> {
+        let base_expressions = self.parse(lines)?;
+
+        interpreter::interpret_with_options(base_expressions, &self.builtins, &self.options)
+    }
+
+    // Evaluates a single expression with the given variables, for example a formula given by a user:
+    // engine.eval_with("price * count", &[("price", Value::Number(3)), ("count", Value::Number(4))]).
+    // The expression is typechecked against the types of the values before it is evaluated.
+    pub fn eval_with(&self, expression: &str, variables: &[(&str, Value)]) -> Result<Option<Value>, Error> {
+        let language = &self.options.language;
+        let base_expressions = parser::parse_strings_with_options(expression.split("\n").collect(), language)?;
+        if self.sandboxed {
+            if let [base_expr] = &base_expressions[..] {
+                self.check_sandboxed(base_expr)?;
+            }
+        }
+        let expr = match <[BaseExpr<()>; 1]>::try_from(base_expressions) {
+            Ok([BaseExpr {
+                data: BaseExprData::Simple { expr },
+                ..
+            }]) => expr,
+            Ok([statement]) => {
+                return Err(Error::located(
+                    String::from("Expected an expression, such as price * count, not a statement"),
+                    statement.span(),
+                ))
+            }
+            Err(_) => {
+                return Err(Error::SimpleError {
+                    message: String::from("Expected a single expression, such as price * count"),
+                })
+            }
+        };
+
+        let mut variable_types = Vec::new();
+        for (name, value) in variables {
+            let Some(value_type) = typechecker::value_to_type(value) else {
+                return Err(Error::SimpleError {
+                    message: format!(
+                        "Cannot give {} to an expression, as a {} has no type in a program",
+                        name,
+                        interpreter::value_type_to_string(value)
+                    ),
+                });
+            };
+            variable_types.push((name.to_string(), value_type));
+        }
+        typechecker::type_check_expression(expr.clone(), &variable_types, language)?;
+
+        interpreter::evaluate_expression(&expr, variables, &self.builtins, &self.options)
+    }
+
+    // Rejects a statement in a sandboxed engine, or an expression calling a function which is not one of its builtins
+    fn check_sandboxed(&self, base_expr: &BaseExpr<()>) -> Result<(), Error> {
+        struct CallFinder<'a> {
+            builtins: &'a [Builtin],
+            forbidden_call: Option<RecExpr<()>>,
+        }
+
+        impl Visitor<()> for CallFinder<'_> {
+            fn visit_rec_expr(&mut self, rec_expr: &RecExpr<()>) {
+                if let RecExprData::FunctionCall { function_name, .. } = &rec_expr.data {
+                    if self.forbidden_call.is_none()
+                        && !self.builtins.iter().any(|builtin| builtin.name == function_name)
+                    {
+                        self.forbidden_call = Some(rec_expr.clone());
+                    }
+                }
+                visitor::walk_rec_expr(self, rec_expr);
+            }
+        }
+
+        let BaseExprData::Simple { expr } = &base_expr.data else {
+            return Err(Error::located(
+                format!(
+                    "A sandboxed engine only accepts expressions, such as price * count, not {}",
+                    statement_description(&base_expr.data)
+                ),
+                base_expr.span(),
+            ));
+        };
+
+        let mut finder = CallFinder {
+            builtins: &self.builtins,
+            forbidden_call: None,
+        };
+        finder.visit_rec_expr(expr);
+        match finder.forbidden_call {
+            Some(call) => {
+                let RecExprData::FunctionCall { function_name, .. } = &call.data else {
+                    unreachable!("Only function calls are kept");
+                };
+                Err(Error::located(
+                    format!(
+                        "{} cannot be called in a sandboxed engine, which only has builtins such as abs and max that cannot affect the host",
+                        function_name
+                    ),
+                    call.span(),
+                ))
+            }
+            None => Ok(()),
+        }
+    }
+
+    // Loads a shared library and lets it register its builtins with this engine
+    #[cfg(feature = "plugins")]
+    pub fn load_plugin(&mut self, path: &std::path::Path) -> Result<(), Error> {
+        // Loading a library runs its initialisation code, and the registration function
+        // is trusted to have the signature documented above
+        let library = match unsafe { libloading::Library::new(path) } {
+            Ok(library) => library,
+            Err(error) => {
+                return Err(Error::SimpleError {
+                    message: format!("Could not load plugin {}: {}", path.display(), error),
+                })
+            }
+        };
+
+        let register: PluginRegisterFunction =
+            match unsafe { library.get::<PluginRegisterFunction>(PLUGIN_REGISTER_SYMBOL) } {
+                Ok(register) => *register,
+                Err(error) => {
+                    return Err(Error::SimpleError {
+                        message: format!(
+                            "Plugin {} does not export rosy_register_plugin: {}",
+                            path.display(),
+                            error
+                        ),
+                    })
+                }
+            };
+
+        register(self);
+        self.libraries.push(library);
+
+        Ok(())
+    }
+}
+
+// How a statement is named in the error of a sandboxed engine
+fn statement_description(data: &BaseExprData<()>) -> &'static str {
+    match data {
+        BaseExprData::Simple { .. } => "an expression",
+        BaseExprData::VariableAssignment { .. }
+        | BaseExprData::DestructuringAssignment { .. }
+        | BaseExprData::PlusEqualsStatement { .. }
+        | BaseExprData::FieldAssignment { .. }
+        | BaseExprData::IndexAssignment { .. } => "an assignment",
+        BaseExprData::IfStatement { .. }
+        | BaseExprData::ElseIfStatement { .. }
+        | BaseExprData::ElseStatement { .. }
+        | BaseExprData::Match { .. } => "a condition",
+        BaseExprData::ForLoop { .. } | BaseExprData::WhileLoop { .. } => "a loop",
+        BaseExprData::FunctionDefinition { .. } => "a function definition",
+        BaseExprData::StructDefinition { .. } => "a struct definition",
+        BaseExprData::Return { .. }
+        | BaseExprData::Break
+        | BaseExprData::Continue
+        | BaseExprData::Yield { .. } => "a jump",
+        BaseExprData::Delete { .. } | BaseExprData::Swap { .. } => "a statement on variables",
+        BaseExprData::Defer { .. } | BaseExprData::With { .. } => "a block with cleanup",
+    }
+}
@@ -0,0 +1,255 @@
+use crate::lookup::SymbolTable;
+use crate::parser;
+use crate::parser::BaseExpr;
+use crate::parser::BaseExprData;
+use crate::parser::RecExpr;
+use crate::parser::RecExprData;
+use crate::tokenizer;
+use crate::tokenizer::Error;
+use crate::tokenizer::Span;
+use crate::tokenizer::SymbolType;
+use crate::tokenizer::Token;
+use crate::tokenizer::TokenData;
+use crate::visitor;
+use crate::visitor::Visitor;
+
+// Fixes for common mistakes which can be applied to the source without asking, used by rosy fix.
+// Positions use the same convention as the spans stored in the AST.
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Fix {
+    // What the fix changes, such as "Use == to compare in the condition"
+    pub message: String,
+    pub edit: Edit,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Edit {
+    // Replaces the text covered by the span with the given text
+    Replace { span: Span, text: String },
+    // Removes the whole line
+    RemoveLine { row: usize },
+}
+
+impl Fix {
+    pub fn row(&self) -> usize {
+        match &self.edit {
+            Edit::Replace { span, .. } => span.row,
+            Edit::RemoveLine { row } => *row,
+        }
+    }
+}
+
+// Finds and applies all fixes for the program, returning the fixed lines and the applied fixes in source order.
+// The fixes which make the program parse are applied first, as the other fixes need the parsed program.
+pub fn fix_program(lines: Vec<&str>) -> Result<(Vec<String>, Vec<Fix>), Error> {
+    let condition_fixes = find_assignment_in_condition_fixes(lines.clone())?;
+    let lines = apply_fixes(lines, &condition_fixes);
+
+    let unused_fixes = find_unused_assignment_fixes(lines.iter().map(|line| line.as_str()).collect())?;
+    let lines = apply_fixes(lines.iter().map(|line| line.as_str()).collect(), &unused_fixes);
+
+    let mut fixes = condition_fixes;
+    fixes.extend(unused_fixes);
+    fixes.sort_by_key(|fix| fix.row());
+    Ok((lines, fixes))
+}
+
+// Applies the fixes to the lines. Fixes must not overlap.
+pub fn apply_fixes(lines: Vec<&str>, fixes: &[Fix]) -> Vec<String> {
+    let mut source = lines.join("\n");
+
+    // Replace from back to front, so that the byte offsets of earlier replacements stay valid
+    let mut replacements: Vec<(&Span, &String)> = fixes
+        .iter()
+        .filter_map(|fix| match &fix.edit {
+            Edit::Replace { span, text } => Some((span, text)),
+            Edit::RemoveLine { .. } => None,
+        })
+        .collect();
+    replacements.sort_by_key(|(span, _)| std::cmp::Reverse(span.byte_start));
+    for (span, text) in replacements {
+        source.replace_range(span.byte_range(), text);
+    }
+    let mut new_lines: Vec<String> = source.split('\n').map(|line| line.to_string()).collect();
+
+    // Remove from the bottom up, so that the rows of the other lines stay valid
+    let mut removed_rows: Vec<usize> = fixes
+        .iter()
+        .filter_map(|fix| match fix.edit {
+            Edit::RemoveLine { row } => Some(row),
+            Edit::Replace { .. } => None,
+        })
+        .collect();
+    removed_rows.sort_by_key(|row| std::cmp::Reverse(*row));
+    removed_rows.dedup();
+    for row in removed_rows {
+        new_lines.remove(row);
+    }
+
+    new_lines
+}
+
+// Finds conditions of if, else if and while statements which use = where == was meant, like: if a = 1.
+// This works on the tokens, since such a condition does not parse.
+pub fn find_assignment_in_condition_fixes(lines: Vec<&str>) -> Result<Vec<Fix>, Error> {
+    let mut fixes = Vec::new();
+
+    for token_line in tokenizer::tokenize(lines)? {
+        let condition = match token_line.tokens.as_slice() {
+            [Token {
+                data: TokenData::Symbol {
+                    symbol_type: SymbolType::If | SymbolType::While,
+                },
+                ..
+            }, condition @ ..] => condition,
+            [Token {
+                data: TokenData::Symbol {
+                    symbol_type: SymbolType::Else,
+                },
+                ..
+            }, Token {
+                data: TokenData::Symbol {
+                    symbol_type: SymbolType::If,
+                },
+                ..
+            }, condition @ ..] => condition,
+            _ => continue,
+        };
+
+        if let Some(equals_token) = parser::find_assignment_in_condition(condition) {
+            fixes.push(Fix {
+                message: parser::ASSIGNMENT_IN_CONDITION_MESSAGE.to_string(),
+                edit: Edit::Replace {
+                    span: equals_token.span(),
+                    text: String::from("=="),
+                },
+            });
+        }
+    }
+
+    Ok(fixes)
+}
+
+// Finds assignments to variables which are never read. Only assignments without function calls are removed,
+// since a function call may do something else that is needed, and a block is never left empty.
+pub fn find_unused_assignment_fixes(lines: Vec<&str>) -> Result<Vec<Fix>, Error> {
+    let symbol_table = SymbolTable::build(lines.clone())?;
+    let base_expressions = parser::parse_strings(lines)?;
+
+    let mut finder = PureAssignmentFinder {
+        positions: Vec::new(),
+    };
+    finder.visit_body(&base_expressions);
+
+    // A variable is only removed when all of its assignments can be removed,
+    // so that no assignment such as x += 1 is left without the one defining x
+    let mut removable_positions = Vec::new();
+    for definition in symbol_table.unused_definitions() {
+        let bindings = symbol_table.references_of(definition);
+        if bindings
+            .iter()
+            .all(|binding| finder.positions.contains(&binding.span.byte_start))
+        {
+            removable_positions.extend(bindings.iter().map(|binding| binding.span.byte_start));
+        }
+    }
+
+    let mut fixes = Vec::new();
+    collect_unused_assignment_fixes(&base_expressions, &removable_positions, &mut fixes);
+    Ok(fixes)
+}
+
+fn collect_unused_assignment_fixes(
+    body: &[BaseExpr<()>],
+    removable_positions: &[usize],
+    fixes: &mut Vec<Fix>,
+) {
+    let mut removable = Vec::new();
+    for base_expr in body {
+        if let BaseExprData::VariableAssignment { var_name, .. } = &base_expr.data {
+            if removable_positions.contains(&base_expr.span.byte_start) {
+                removable.push(Fix {
+                    message: format!("Remove the assignment to {}, which is never used", var_name),
+                    edit: Edit::RemoveLine { row: base_expr.span.row },
+                });
+            }
+        }
+
+        match &base_expr.data {
+            BaseExprData::IfStatement {
+                body,
+                else_statement,
+                ..
+            }
+            | BaseExprData::ElseIfStatement {
+                body,
+                else_statement,
+                ..
+            } => {
+                collect_unused_assignment_fixes(body, removable_positions, fixes);
+                if let Some(else_statement) = else_statement {
+                    collect_unused_assignment_fixes(
+                        std::slice::from_ref(&**else_statement),
+                        removable_positions,
+                        fixes,
+                    );
+                }
+            }
+            BaseExprData::ElseStatement { body }
+            | BaseExprData::ForLoop { body, .. }
+            | BaseExprData::WhileLoop { body, .. }
+            | BaseExprData::FunctionDefinition { body, .. }
+            | BaseExprData::With { body, .. } => {
+                collect_unused_assignment_fixes(body, removable_positions, fixes);
+            }
+            BaseExprData::Match { arms, .. } => {
+                for arm in arms {
+                    collect_unused_assignment_fixes(&arm.body, removable_positions, fixes);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // A block needs at least one statement
+    if !body.is_empty() && removable.len() == body.len() {
+        removable.pop();
+    }
+    fixes.extend(removable);
+}
+
+// Finds the byte offsets of the assignments without function calls
+struct PureAssignmentFinder {
+    positions: Vec<usize>,
+}
+
+impl Visitor<()> for PureAssignmentFinder {
+    fn visit_base_expr(&mut self, base_expr: &BaseExpr<()>) {
+        if let BaseExprData::VariableAssignment { expr, .. } = &base_expr.data {
+            if !contains_function_call(expr) {
+                self.positions.push(base_expr.span.byte_start);
+            }
+        }
+        visitor::walk_base_expr(self, base_expr);
+    }
+}
+
+fn contains_function_call(expr: &RecExpr<()>) -> bool {
+    struct CallFinder {
+        found: bool,
+    }
+
+    impl Visitor<()> for CallFinder {
+        fn visit_rec_expr(&mut self, rec_expr: &RecExpr<()>) {
+            if let RecExprData::FunctionCall { .. } = rec_expr.data {
+                self.found = true;
+            }
+            visitor::walk_rec_expr(self, rec_expr);
+        }
+    }
+
+    let mut finder = CallFinder { found: false };
+    finder.visit_rec_expr(expr);
+    finder.found
+}
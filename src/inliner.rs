@@ -0,0 +1,369 @@
+use crate::builtins;
+use crate::parser;
+use crate::parser::BaseExpr;
+use crate::parser::BaseExprData;
+use crate::parser::Pattern;
+use crate::parser::RecExpr;
+use crate::parser::RecExprData;
+use crate::tokenizer::Error;
+use crate::visitor;
+use crate::visitor::Folder;
+use crate::visitor::Visitor;
+
+// Optimisations across function calls, done on the untyped program so the interpreter and the compiler
+// share them. Only functions defined once, at the top level, before any call of them and only ever called
+// by name are changed, so every call of the name is known to call that definition.
+
+// Replaces calls to functions whose body is a single return of an expression on its parameters,
+// such as def square(x): return x * x, by that expression with the arguments filled in.
+// Only calls whose arguments are literals or variables are inlined, so no argument is evaluated
+// more or fewer times than before. Gives the number of calls which were inlined.
+pub fn inline_functions(body: Vec<BaseExpr<()>>) -> Result<(Vec<BaseExpr<()>>, usize), Error> {
+    let functions = candidate_functions(&body)
+        .into_iter()
+        .filter_map(|(name, args, function_body)| {
+            let [BaseExpr {
+                data: BaseExprData::Return {
+                    return_value: Some(return_value),
+                },
+                ..
+            }] = function_body.as_slice()
+            else {
+                return None;
+            };
+            is_inlinable(return_value, &args, &body).then(|| (name, args, return_value.clone()))
+        })
+        .collect();
+
+    let mut inliner = Inliner {
+        functions,
+        inlined: 0,
+    };
+    let body = inliner.fold_body(body)?;
+    Ok((body, inliner.inlined))
+}
+
+// Puts the literal a parameter is given by every call of its function into the body of the function,
+// so that constant evaluation can compute what depends on it, such as def area(r, pi): return pi * r * r
+// called as area(2, 3.14) and area(5, 3.14). The parameter has to keep its value throughout the body.
+// Gives the number of parameters which were specialized.
+pub fn specialize_functions(body: Vec<BaseExpr<()>>) -> Result<(Vec<BaseExpr<()>>, usize), Error> {
+    let mut specializations = Vec::new();
+    for (name, args, function_body) in candidate_functions(&body) {
+        let calls = calls_of(&name, &body);
+        if calls.is_empty() || defines_inner_names(&function_body) {
+            continue;
+        }
+
+        for (position, arg) in args.iter().enumerate() {
+            let literal = &calls[0][position];
+            let is_constant = is_scalar_literal(literal)
+                && calls.iter().all(|call| call[position].data == literal.data);
+            if is_constant && !is_rebound(arg, &function_body) {
+                specializations.push((name.clone(), arg.clone(), literal.data.clone()));
+            }
+        }
+    }
+
+    let mut specializer = Specializer { specializations };
+    let body = specializer.fold_body(body)?;
+    let specialized = specializer.specializations.len();
+    Ok((body, specialized))
+}
+
+// The functions whose every call is known, as their name, parameters and body
+fn candidate_functions(body: &[BaseExpr<()>]) -> Vec<(String, Vec<String>, Vec<BaseExpr<()>>)> {
+    let mut uses = NameUses::default();
+    uses.visit_body(body);
+
+    body.iter()
+        .enumerate()
+        .filter_map(|(position, base_expr)| match &base_expr.data {
+            BaseExprData::FunctionDefinition {
+                fun_name,
+                args,
+                body: function_body,
+            } if calls_of(fun_name, &body[..position]).is_empty() => {
+                Some((fun_name.clone(), parser::parameter_names(args), function_body.clone()))
+            }
+            _ => None,
+        })
+        .filter(|(name, args, _)| {
+            // Builtins go before functions of the same name in a typechecked program, but not in the interpreter
+            !builtins::is_builtin(name)
+                && uses.definitions.iter().filter(|defined| *defined == name).count() == 1
+                && !uses.structs.contains(name)
+                && !uses.other_uses.contains(name)
+                && uses
+                    .calls
+                    .iter()
+                    .filter(|(called, _)| called == name)
+                    .all(|(_, arg_count)| *arg_count == args.len())
+        })
+        .collect()
+}
+
+// How the names in a program are used
+#[derive(Default)]
+struct NameUses {
+    // Functions defined, a name is in here once for every definition
+    definitions: Vec<String>,
+    structs: Vec<String>,
+    // Calls by name with their number of arguments
+    calls: Vec<(String, usize)>,
+    // Names which are read as a value or given a value other than by a function definition
+    other_uses: Vec<String>,
+}
+
+impl Visitor<()> for NameUses {
+    fn visit_base_expr(&mut self, base_expr: &BaseExpr<()>) {
+        match &base_expr.data {
+            BaseExprData::FunctionDefinition { fun_name, args, .. } => {
+                self.definitions.push(fun_name.clone());
+                self.other_uses.extend(parser::parameter_names(args));
+            }
+            BaseExprData::VariableAssignment { var_name, .. }
+            | BaseExprData::PlusEqualsStatement { var_name, .. }
+            | BaseExprData::ForLoop { var_name, .. }
+            | BaseExprData::With { var_name, .. }
+            | BaseExprData::Delete { var_name } => self.other_uses.push(var_name.clone()),
+            BaseExprData::StructDefinition { name, .. } => self.structs.push(name.clone()),
+            BaseExprData::Swap {
+                first_name,
+                second_name,
+            } => self.other_uses.extend([first_name.clone(), second_name.clone()]),
+            BaseExprData::DestructuringAssignment { var_names, .. } => {
+                self.other_uses.extend(var_names.iter().cloned())
+            }
+            BaseExprData::Match { arms, .. } => {
+                for arm in arms {
+                    if let Pattern::Binding(name) = &arm.pattern {
+                        self.other_uses.push(name.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+        visitor::walk_base_expr(self, base_expr);
+    }
+
+    fn visit_rec_expr(&mut self, rec_expr: &RecExpr<()>) {
+        match &rec_expr.data {
+            // Keyword arguments are not in the order of the parameters, such calls are left alone
+            RecExprData::FunctionCall { function_name, args } if parser::has_keyword_arguments(args) => {
+                self.other_uses.push(function_name.clone())
+            }
+            RecExprData::FunctionCall {
+                function_name,
+                args,
+            } => self.calls.push((function_name.clone(), args.len())),
+            RecExprData::Variable { name } | RecExprData::Assign { variable_name: name, .. } => {
+                self.other_uses.push(name.clone())
+            }
+            _ => {}
+        }
+        visitor::walk_rec_expr(self, rec_expr);
+    }
+}
+
+// Whether the expression only reads the parameters, and calls nothing but pure builtins
+// which the program does not define a function for
+fn is_inlinable(rec_expr: &RecExpr<()>, args: &[String], program: &[BaseExpr<()>]) -> bool {
+    struct InlineChecker<'a> {
+        args: &'a [String],
+        defined: Vec<String>,
+        inlinable: bool,
+    }
+
+    impl Visitor<()> for InlineChecker<'_> {
+        fn visit_rec_expr(&mut self, rec_expr: &RecExpr<()>) {
+            match &rec_expr.data {
+                RecExprData::Variable { name } => self.inlinable &= self.args.contains(name),
+                RecExprData::FunctionCall { function_name, .. } => {
+                    self.inlinable &= builtins::is_pure(function_name) && !self.defined.contains(function_name)
+                }
+                RecExprData::Assign { .. } | RecExprData::Access { .. } => self.inlinable = false,
+                _ => {}
+            }
+            visitor::walk_rec_expr(self, rec_expr);
+        }
+    }
+
+    let mut uses = NameUses::default();
+    uses.visit_body(program);
+
+    let mut checker = InlineChecker {
+        args,
+        defined: [uses.definitions, uses.structs, uses.other_uses].concat(),
+        inlinable: true,
+    };
+    checker.visit_rec_expr(rec_expr);
+    checker.inlinable
+}
+
+struct Inliner {
+    // The functions which can be inlined, as their name, parameters and returned expression
+    functions: Vec<(String, Vec<String>, RecExpr<()>)>,
+    inlined: usize,
+}
+
+impl Folder<()> for Inliner {
+    fn fold_rec_expr(&mut self, rec_expr: RecExpr<()>) -> Result<RecExpr<()>, Error> {
+        let rec_expr = visitor::fold_rec_expr_children(self, rec_expr)?;
+        let RecExprData::FunctionCall {
+            function_name,
+            args,
+        } = &rec_expr.data
+        else {
+            return Ok(rec_expr);
+        };
+        let Some((_, params, returned)) = self.functions.iter().find(|(name, _, _)| name == function_name) else {
+            return Ok(rec_expr);
+        };
+        if !args.iter().all(|arg| is_scalar_literal(arg) || matches!(arg.data, RecExprData::Variable { .. })) {
+            return Ok(rec_expr);
+        }
+
+        let mut substitution = Substitution {
+            names: params.clone(),
+            values: args.iter().map(|arg| arg.data.clone()).collect(),
+        };
+        let inlined = substitution.fold_rec_expr(returned.clone())?;
+        self.inlined += 1;
+        // The inlined expression takes the place of the call, so errors in it point at the call
+        visitor::respan(inlined, rec_expr.span())
+
+    }
+}
+
+struct Specializer {
+    // The specialized parameters, as the name of their function, their name and their literal
+    specializations: Vec<(String, String, RecExprData<()>)>,
+}
+
+impl Folder<()> for Specializer {
+    fn fold_base_expr(&mut self, base_expr: BaseExpr<()>) -> Result<BaseExpr<()>, Error> {
+        let BaseExprData::FunctionDefinition { fun_name, .. } = &base_expr.data else {
+            return visitor::fold_base_expr_children(self, base_expr);
+        };
+
+        let (names, values) = self
+            .specializations
+            .iter()
+            .filter(|(function, _, _)| function == fun_name)
+            .map(|(_, name, value)| (name.clone(), value.clone()))
+            .unzip();
+        visitor::fold_base_expr_children(&mut Substitution { names, values }, base_expr)
+    }
+}
+
+// Replaces reads of the names by the expressions given for them
+struct Substitution {
+    names: Vec<String>,
+    values: Vec<RecExprData<()>>,
+}
+
+impl Folder<()> for Substitution {
+    fn fold_rec_expr(&mut self, rec_expr: RecExpr<()>) -> Result<RecExpr<()>, Error> {
+        if let RecExprData::Variable { name } = &rec_expr.data {
+            if let Some(position) = self.names.iter().position(|substituted| substituted == name) {
+                return Ok(RecExpr {
+                    data: self.values[position].clone(),
+                    ..rec_expr
+                });
+            }
+        }
+        visitor::fold_rec_expr_children(self, rec_expr)
+    }
+}
+
+// The argument lists of every call of the function
+fn calls_of(function_name: &str, body: &[BaseExpr<()>]) -> Vec<Vec<RecExpr<()>>> {
+    struct CallCollector<'a> {
+        function_name: &'a str,
+        calls: Vec<Vec<RecExpr<()>>>,
+    }
+
+    impl Visitor<()> for CallCollector<'_> {
+        fn visit_rec_expr(&mut self, rec_expr: &RecExpr<()>) {
+            if let RecExprData::FunctionCall {
+                function_name,
+                args,
+            } = &rec_expr.data
+            {
+                if function_name == self.function_name {
+                    self.calls.push(args.clone());
+                }
+            }
+            visitor::walk_rec_expr(self, rec_expr);
+        }
+    }
+
+    let mut collector = CallCollector {
+        function_name,
+        calls: Vec::new(),
+    };
+    collector.visit_body(body);
+    collector.calls
+}
+
+fn is_scalar_literal(rec_expr: &RecExpr<()>) -> bool {
+    matches!(
+        rec_expr.data,
+        RecExprData::Number { .. } | RecExprData::Float { .. } | RecExprData::String { .. } | RecExprData::Boolean { .. }
+    )
+}
+
+// Whether the body gives the name another value
+fn is_rebound(name: &String, body: &[BaseExpr<()>]) -> bool {
+    let mut rebinding = Rebinding { name, found: false };
+    rebinding.visit_body(body);
+    rebinding.found
+}
+
+struct Rebinding<'a> {
+    name: &'a String,
+    found: bool,
+}
+
+impl Visitor<()> for Rebinding<'_> {
+    fn visit_base_expr(&mut self, base_expr: &BaseExpr<()>) {
+        match &base_expr.data {
+            BaseExprData::VariableAssignment { var_name, .. }
+            | BaseExprData::PlusEqualsStatement { var_name, .. }
+            | BaseExprData::ForLoop { var_name, .. }
+            | BaseExprData::With { var_name, .. }
+            | BaseExprData::Delete { var_name }
+            | BaseExprData::FieldAssignment { object: var_name, .. }
+            | BaseExprData::IndexAssignment { object: var_name, .. } => self.found |= var_name == self.name,
+            BaseExprData::Swap {
+                first_name,
+                second_name,
+            } => self.found |= first_name == self.name || second_name == self.name,
+            BaseExprData::DestructuringAssignment { var_names, .. } => {
+                self.found |= var_names.contains(self.name)
+            }
+            BaseExprData::Match { arms, .. } => {
+                self.found |= arms
+                    .iter()
+                    .any(|arm| arm.pattern == Pattern::Binding(self.name.clone()))
+            }
+            _ => {}
+        }
+        visitor::walk_base_expr(self, base_expr);
+    }
+
+    fn visit_rec_expr(&mut self, rec_expr: &RecExpr<()>) {
+        if let RecExprData::Assign { variable_name, .. } = &rec_expr.data {
+            self.found |= variable_name == self.name;
+        }
+        visitor::walk_rec_expr(self, rec_expr);
+    }
+}
+
+// Whether the body defines functions or structs, which could capture or shadow the parameters
+fn defines_inner_names(body: &[BaseExpr<()>]) -> bool {
+    let mut uses = NameUses::default();
+    uses.visit_body(body);
+    !uses.definitions.is_empty() || !uses.structs.is_empty()
+}
@@ -1,68 +1,186 @@
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fmt::format;
-
+use std::io::BufRead;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use indexmap::IndexMap;
+
+use crate::builtins;
+use crate::builtins::Builtin;
+use crate::complex::Complex;
+use crate::coroutine::Coroutine;
+use crate::coroutine::CoroutineStep;
+#[cfg(feature = "jit")]
+use crate::jit;
+use crate::language::LanguageOptions;
+use crate::optimiser::OptimisationOptions;
 use crate::parser;
+use crate::parser::Accessor;
 use crate::parser::BaseExpr;
 use crate::parser::BaseExprData;
+use crate::parser::Parameter;
+use crate::parser::Pattern;
 use crate::parser::RecExpr;
 use crate::parser::RecExprData;
+use crate::stack;
+use crate::rational::Rational;
+use crate::smallstring::SmallString;
 use crate::tokenizer::Error;
+use crate::tokenizer::Span;
+use crate::trace::Trace;
+use crate::trace::TracedInput;
+use crate::visitor;
+use crate::visitor::Visitor;
 
-#[derive(Clone)]
-enum StandardFunction {
-    Print,
-    PrintLine,
-}
-
-fn add_default_functions_to_env(env: &mut Environment) {
+fn add_default_functions_to_env(env: &mut Environment, builtins: &[Builtin]) {
     let scope = env.last_mut().unwrap();
 
-    scope.push(Binding {
-        name: String::from("print"),
-        value: Value::StandardFunction(StandardFunction::Print),
-    });
-
-    scope.push(Binding {
-        name: String::from("println"),
-        value: Value::StandardFunction(StandardFunction::PrintLine),
-    });
+    for builtin in builtins {
+        scope.push(Binding {
+            name: String::from(builtin.name),
+            value: Value::StandardFunction(builtin.clone()),
+        });
+    }
 }
 
 #[derive(Clone)]
-enum Value {
+pub enum Value {
     Number(i64),
     Bool(bool),
-    String(String),
+    // Short strings are stored inline, see SmallString
+    String(SmallString),
     Function {
         name: String,
-        args: Vec<String>,
-        body: Vec<BaseExpr<()>>,
+        args: Vec<Parameter<()>>,
+        // Shared, so looking up a function does not copy its body
+        body: Arc<Vec<BaseExpr<()>>>,
+        // The variables of the enclosing function which the body uses, as they were when it was defined
+        captured: Arc<Vec<(String, Value)>>,
     },
-    StandardFunction(StandardFunction),
+    StandardFunction(Builtin),
     List(Vec<Value>),
+    // A call to a generator function, which runs up to its next yield whenever a value is needed
+    Generator(Generator),
+    // Raw binary data, for example the content of a non-text file
+    Bytes(Vec<u8>),
+    Rational(Rational),
+    Complex(Complex),
+    // Produced by builtins such as magnitude() and by / on integers with true division
+    Float(f64),
+    // The message of a failed try_ builtin, see is_error() and error_message()
+    Error(String),
+    // An object owned by the host, such as an open file
+    Resource(Resource),
+    // A struct as declared in the program, which constructs instances when called
+    StructDefinition {
+        name: String,
+        fields: Vec<String>,
+    },
+    // An instance of a struct, with its fields in declaration order
+    Struct {
+        name: String,
+        fields: Vec<(String, Value)>,
+    },
+    // The entries of a dict, kept in the order in which their keys were first added
+    Dict(IndexMap<DictKey, Value>),
+    Tuple(Vec<Value>),
+    // A list, dict or struct given by freeze(), whose elements and fields cannot be assigned to.
+    // Only assigning it and passing it to a function keep it frozen, every other use gets the value inside.
+    Frozen(Box<Value>),
+}
+
+impl Value {
+    // The value inside a frozen value, see Value::Frozen
+    pub fn thawed(self) -> Value {
+        match self {
+            Value::Frozen(value) => *value,
+            value => value,
+        }
+    }
+}
+
+// The values which can be used as the key of a dict
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum DictKey {
+    Number(i64),
+    Bool(bool),
+    String(String),
+}
+
+impl DictKey {
+    pub fn from_value(value: &Value) -> Option<DictKey> {
+        match value {
+            Value::Number(number) => Some(DictKey::Number(*number)),
+            Value::Bool(value) => Some(DictKey::Bool(*value)),
+            Value::String(value) => Some(DictKey::String(value.to_string())),
+            _ => None,
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            DictKey::Number(number) => Value::Number(*number),
+            DictKey::Bool(value) => Value::Bool(*value),
+            DictKey::String(value) => Value::String(SmallString::from(value.as_str())),
+        }
+    }
+}
+
+// A handle to an object which is kept in the context, see Context::open_resource
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Resource {
+    pub kind: &'static str,
+    pub id: usize,
 }
 
-fn value_to_string(value: &Value) -> String {
+pub fn value_to_string(value: &Value) -> String {
     match value {
-        Value::Number(value) => return format!("{value}"),
-        Value::Bool(value) => return format!("{value}"),
+        Value::Number(value) => return rosy_rt::format::format_integer(*value),
+        Value::Bool(value) => return rosy_rt::format::format_boolean(*value),
         Value::String(value) => return format!("{value}"),
         Value::Function { name, .. } => return format!("function {}", name),
         Value::StandardFunction(_) => return String::from("standard function"),
+        Value::Generator(_) => return String::from("generator"),
+        Value::Rational(value) => return format!("{value}"),
+        Value::Complex(value) => return format!("{value}"),
+        Value::Float(value) => return rosy_rt::format::format_float(*value),
+        Value::Error(message) => return rosy_rt::format::format_error(message),
+        Value::Resource(resource) => return format!("{} resource", resource.kind),
+        Value::StructDefinition { name, .. } => return format!("struct {}", name),
+        Value::Struct { name, fields } => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(field_name, value)| format!("{}: {}", field_name, value_to_string(value)))
+                .collect();
+            return format!("{} {{ {} }}", name, fields.join(", "));
+        }
+        Value::Bytes(bytes) => {
+            let bytes: Vec<String> = bytes.iter().map(|byte| byte.to_string()).collect();
+            return format!("bytes[{}]", bytes.join(", "));
+        }
+        Value::Dict(entries) => {
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|(key, value)| format!("{}: {}", value_to_string(&key.to_value()), value_to_string(value)))
+                .collect();
+            return format!("{{{}}}", entries.join(", "));
+        }
+        Value::Frozen(value) => return value_to_string(value),
+        Value::Tuple(values) => {
+            let values: Vec<String> = values.iter().map(value_to_string).collect();
+            return rosy_rt::format::format_tuple(&values);
+        }
         Value::List(values) => {
-            let mut result = String::from("[");
-            for (i, value) in values.iter().enumerate() {
-                result.push_str(&value_to_string(value));
-                if i != values.len() - 1 {
-                    result.push_str(", ");
-                }
-            }
-            result.push_str("]");
-            return result;
+            let values: Vec<String> = values.iter().map(value_to_string).collect();
+            return rosy_rt::format::format_list(&values);
         }
     }
 }
 
-fn value_type_to_string(value: &Value) -> String {
+pub fn value_type_to_string(value: &Value) -> String {
     match value {
         Value::Number(_) => return String::from("integer"),
         Value::Bool(_) => return String::from("boolean"),
@@ -70,9 +188,22 @@ fn value_type_to_string(value: &Value) -> String {
         Value::Function { .. } => return String::from("function"),
         Value::StandardFunction(_) => return String::from("standard function"),
         Value::List(_) => return String::from("list"),
+        Value::Generator(_) => return String::from("generator"),
+        Value::Bytes(_) => return String::from("bytes"),
+        Value::Rational(_) => return String::from("rational"),
+        Value::Complex(_) => return String::from("complex"),
+        Value::Float(_) => return String::from("float"),
+        Value::Error(_) => return String::from("error"),
+        Value::Resource(resource) => return String::from(resource.kind),
+        Value::StructDefinition { .. } => return String::from("struct"),
+        Value::Struct { name, .. } => return name.clone(),
+        Value::Dict(_) => return String::from("dict"),
+        Value::Frozen(value) => return format!("frozen {}", value_type_to_string(value)),
+        Value::Tuple(_) => return String::from("tuple"),
     }
 }
 
+#[derive(Clone)]
 struct Binding {
     name: String,
     value: Value,
@@ -84,43 +215,856 @@ type Environment = Vec<Scope>;
 
 pub type Terminal = Vec<String>;
 
+#[derive(Clone, Default)]
+pub struct InterpreterOptions {
+    // Makes the output of a program identical across runs:
+    // the random number generator gets a fixed seed and now() always returns the same time
+    pub deterministic: bool,
+    // Collects execution statistics into Context::stats
+    pub collect_stats: bool,
+    // Makes sleep() suspend the program instead of blocking the thread,
+    // so that a host event loop can wait for it (see ProgramState::poll)
+    pub suspend_on_sleep: bool,
+    // The arguments passed to the program, read by the flags module
+    pub program_arguments: Vec<String>,
+    // Pauses the program at calls to breakpoint() and where an error occurs to inspect it, see run_debugger
+    pub debug: bool,
+    // How the program is read and checked, and the meaning of operators that changed between versions.
+    // See LanguageOptions::load_for_source for reading them from a rosy.toml
+    pub language: LanguageOptions,
+    // Records or replays what the program reads from outside, see Context::traced_input
+    pub trace: TraceMode,
+    // Compiles the functions which are called often to native code, in builds with the jit feature.
+    // Not used in debug mode, where every statement has to be interpreted.
+    pub jit: bool,
+    // The passes run on the program before it is interpreted, none by default, see Engine::parse
+    pub optimisation: OptimisationOptions,
+    // Keeps print and println from writing to stdout, the output is only kept in Context::terminal
+    pub quiet: bool,
+}
+
+#[derive(Clone, Default)]
+pub enum TraceMode {
+    #[default]
+    Off,
+    // Records the seed and inputs into the shared trace, which the host can save after the run,
+    // also when the run failed
+    Record(Arc<Mutex<Trace>>),
+    // Uses the seed and inputs of a recorded run instead of reading them from outside
+    Replay(Trace),
+}
+
+// Why a program handed control back to the host
+#[derive(Clone, PartialEq, Debug)]
+pub enum Suspension {
+    // The program wants to continue after the given duration
+    Sleep(std::time::Duration),
+    // A host builtin suspended the program, for example to wait for a future
+    Host,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ProgramPoll {
+    Finished,
+    Suspended(Suspension),
+}
+
+// Counters describing the cost of a run, collected when InterpreterOptions::collect_stats is set
+#[derive(Clone, Default, Debug)]
+pub struct ExecutionStats {
+    pub statements_executed: usize,
+    // The largest number of bindings alive at the same time, over all scopes
+    pub peak_environment_size: usize,
+    pub lists_allocated: usize,
+    pub strings_allocated: usize,
+    // Function calls which got their scope from the pool instead of allocating it
+    pub scopes_reused: usize,
+    // Function calls which ran as native code compiled by the JIT
+    pub compiled_calls: usize,
+    pub function_calls: BTreeMap<String, usize>,
+}
+
+impl fmt::Display for ExecutionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Statements executed: {}", self.statements_executed)?;
+        writeln!(f, "Peak environment size: {} bindings", self.peak_environment_size)?;
+        writeln!(f, "Lists allocated: {}", self.lists_allocated)?;
+        writeln!(f, "Strings allocated: {}", self.strings_allocated)?;
+        writeln!(f, "Scopes reused: {}", self.scopes_reused)?;
+        writeln!(f, "Compiled calls: {}", self.compiled_calls)?;
+        writeln!(f, "Function calls:")?;
+        for (name, count) in self.function_calls.iter() {
+            writeln!(f, "    {}: {}", name, count)?;
+        }
+        Ok(())
+    }
+}
+
+// The seed and time used in deterministic mode
+const DETERMINISTIC_SEED: u64 = 0x2545_F491_4F6C_DD1D;
+const DETERMINISTIC_TIME: i64 = 0;
+
+// The most scopes kept for reuse, enough for the call depth of most programs.
+// Deeper recursion allocates the scopes beyond this, and they are dropped when the calls return.
+const SCOPE_POOL_CAPACITY: usize = 64;
+
+// State of a single run of a program, which is also available to builtins
+pub struct Context {
+    pub terminal: Terminal,
+    pub options: InterpreterOptions,
+    pub stats: ExecutionStats,
+    random_state: u64,
+    // The deferred expressions of each function call that is running, innermost last
+    deferred: Vec<Vec<RecExpr<()>>>,
+    // The objects behind the resources which are still open
+    resources: Vec<(usize, Box<dyn std::any::Any + Send>)>,
+    next_resource_id: usize,
+    suspension: Option<Suspension>,
+    // The number of statements the program may still run before it pauses, see ProgramState::step
+    statements_left: usize,
+    // Set by breakpoint() in debug mode, the debugger is started once the builtin returns
+    breakpoint_requested: bool,
+    // Where the commands of the debugger are read from, stdin by default
+    debugger_input: Box<dyn BufRead + Send>,
+    // The function calls that are running in debug mode, innermost last
+    call_stack: Vec<CallFrame>,
+    // The state of the program where an error occurred in debug mode
+    post_mortem: Option<PostMortem>,
+    // The number of inputs used so far from the trace that is replayed
+    replay_position: usize,
+    // The number of statements started so far in debug mode, used to step through the program
+    statement_index: usize,
+    // The debugger pauses before the statement with this index
+    pause_at_statement: Option<usize>,
+    // The variables in scope when the debugger was last left, to show what a step changed
+    previous_bindings: Option<BTreeMap<String, String>>,
+    // Set while the debugger runs the program again up to an earlier statement, output is not shown then
+    re_executing: bool,
+    // Set when the debugger asked to run the program again up to the statement with this index
+    restart_at: Option<usize>,
+    // Set when the debugger was told to quit, the program then stops without an error, see quit_program
+    quitting: bool,
+    // The seed and inputs of this run in debug mode, replayed when the debugger runs the program again
+    debug_trace: Trace,
+    // The inputs of the run before the debugger restarted the program, which are replayed first
+    replay_prefix: Option<Trace>,
+    // Emptied scopes of function calls which returned, reused by the next calls, see Context::take_scope
+    scope_pool: Vec<Scope>,
+    // Compiles and runs the hot functions when InterpreterOptions::jit is set, see Context::call_compiled
+    #[cfg(feature = "jit")]
+    jit: Option<jit::Jit>,
+}
+
+impl Context {
+    pub fn new(options: InterpreterOptions) -> Context {
+        let random_state = match (&options.trace, options.deterministic) {
+            (TraceMode::Replay(trace), _) => trace.seed,
+            (_, true) => DETERMINISTIC_SEED,
+            (_, false) => std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(DETERMINISTIC_SEED, |duration| duration.as_nanos() as u64)
+                | 1,
+        };
+        if let TraceMode::Record(trace) = &options.trace {
+            trace.lock().unwrap().seed = random_state;
+        }
+
+        Context {
+            terminal: vec![String::new()],
+            options,
+            stats: ExecutionStats::default(),
+            random_state,
+            deferred: Vec::new(),
+            resources: Vec::new(),
+            next_resource_id: 0,
+            suspension: None,
+            statements_left: usize::MAX,
+            breakpoint_requested: false,
+            debugger_input: Box::new(std::io::BufReader::new(std::io::stdin())),
+            call_stack: Vec::new(),
+            post_mortem: None,
+            replay_position: 0,
+            statement_index: 0,
+            pause_at_statement: None,
+            previous_bindings: None,
+            re_executing: false,
+            restart_at: None,
+            quitting: false,
+            debug_trace: Trace {
+                seed: random_state,
+                inputs: Vec::new(),
+            },
+            replay_prefix: None,
+            scope_pool: Vec::new(),
+            #[cfg(feature = "jit")]
+            jit: None,
+        }
+    }
+
+    // A scope for a function call. Scopes are taken from the pool when possible,
+    // so calls in a loop keep using the same few allocations.
+    fn take_scope(&mut self) -> Scope {
+        match self.scope_pool.pop() {
+            Some(scope) => {
+                if self.options.collect_stats {
+                    self.stats.scopes_reused += 1;
+                }
+                scope
+            }
+            None => Vec::new(),
+        }
+    }
+
+    // Gives the scope of a returned function call back to the pool. Its bindings are dropped right away,
+    // the allocation is kept for the next call.
+    fn recycle_scope(&mut self, mut scope: Scope) {
+        if self.scope_pool.len() < SCOPE_POOL_CAPACITY {
+            scope.clear();
+            self.scope_pool.push(scope);
+        }
+    }
+
+    fn record_statement(&mut self, env: &Environment) {
+        if !self.options.collect_stats {
+            return;
+        }
+
+        self.stats.statements_executed += 1;
+        // Builtins are not counted, since they are bound before the program starts
+        let environment_size = env
+            .iter()
+            .flatten()
+            .filter(|binding| !matches!(binding.value, Value::StandardFunction(_)))
+            .count();
+        self.stats.peak_environment_size = self.stats.peak_environment_size.max(environment_size);
+    }
+
+    fn record_allocation(&mut self, value: Option<&Value>) {
+        if !self.options.collect_stats {
+            return;
+        }
+
+        match value {
+            Some(Value::List(_)) => self.stats.lists_allocated += 1,
+            Some(Value::String(_)) => self.stats.strings_allocated += 1,
+            _ => {}
+        }
+    }
+
+    // The JIT uses the counts to find the functions worth compiling, so they are also kept without statistics
+    fn record_function_call(&mut self, function_name: &str) {
+        if !self.options.collect_stats && !self.options.jit {
+            return;
+        }
+
+        *self
+            .stats
+            .function_calls
+            .entry(function_name.to_string())
+            .or_insert(0) += 1;
+    }
+
+    // Runs a call to a function of the program as native code when the JIT has compiled it,
+    // None means the call still has to be interpreted
+    #[cfg(feature = "jit")]
+    fn call_compiled(&mut self, function_name: &str, function_value: &Value, arg_values: &[Value]) -> Option<Value> {
+        let jit = self.jit.as_mut()?;
+        // A variable holding another function, or a function passed as an argument, is not what the
+        // typechecker saw under this name
+        // Calls leaving out parameters with a default are interpreted, as the defaults are filled in by the interpreter,
+        // and so are calls to functions with a *rest parameter, whose arguments the interpreter collects
+        if !matches!(function_value, Value::Function { name, args, captured, .. }
+            if name == function_name && captured.is_empty() && args.len() == arg_values.len() && parser::rest_position(args).is_none())
+        {
+            return None;
+        }
+
+        let calls_so_far = self.stats.function_calls.get(function_name).copied().unwrap_or(0);
+        let value = jit.call(function_name, calls_so_far, arg_values)?;
+        if self.options.collect_stats {
+            self.stats.compiled_calls += 1;
+        }
+        Some(value)
+    }
+
+    // Hands an object to the script as a resource. The object is dropped when the script closes
+    // the resource, so host objects which need cleanup should do it in their Drop implementation.
+    pub fn open_resource(&mut self, kind: &'static str, object: Box<dyn std::any::Any + Send>) -> Value {
+        let id = self.next_resource_id;
+        self.next_resource_id += 1;
+        self.resources.push((id, object));
+
+        Value::Resource(Resource { kind, id })
+    }
+
+    // The object behind a resource, or None if it has been closed or is not a T
+    pub fn resource_mut<T: 'static>(&mut self, resource: &Resource) -> Option<&mut T> {
+        self.resources
+            .iter_mut()
+            .find(|(id, _)| *id == resource.id)
+            .and_then(|(_, object)| object.downcast_mut::<T>())
+    }
+
+    // Drops the object behind a resource, returns whether it was still open
+    pub fn close_resource(&mut self, resource: &Resource) -> bool {
+        match self.resources.iter().position(|(id, _)| *id == resource.id) {
+            Some(index) => {
+                self.resources.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Asks for the program to be suspended as soon as the builtin which calls this returns,
+    // also within a loop or function. Only has an effect when the program is run with ProgramState::poll.
+    pub fn suspend(&mut self, suspension: Suspension) {
+        self.suspension = Some(suspension);
+    }
+
+    // Pauses the program in the debugger after the current builtin call, when it runs in debug mode
+    pub fn request_breakpoint(&mut self) {
+        // Breakpoints before the statement the debugger went back to were already paused at
+        self.breakpoint_requested = self.options.debug && !self.re_executing;
+    }
+
+    pub fn set_debugger_input(&mut self, input: Box<dyn BufRead + Send>) {
+        self.debugger_input = input;
+    }
+
+    // Keeps the state of the program at the innermost point of an error in debug mode,
+    // before the scopes are removed, for the debugger to show once the error has stopped the program
+    fn record_post_mortem(&mut self, env: &Environment, error: &Error, row: usize) {
+        if !self.options.debug || self.post_mortem.is_some() || self.restart_at.is_some() || self.quitting {
+            return;
+        }
+
+        self.post_mortem = Some(PostMortem {
+            env: env.clone(),
+            call_stack: self.call_stack.clone(),
+            row: error.span().map_or(row, |span| span.row),
+        });
+    }
+
+    // Whether the debugger is running the program again up to an earlier statement,
+    // during which builtins should not show output that was already shown
+    pub fn is_re_executing(&self) -> bool {
+        self.re_executing
+    }
+
+    // Writes a line of debugger output to stdout and the terminal
+    fn write_debugger_line(&mut self, line: &str) {
+        println!("{}", line);
+        self.terminal.last_mut().unwrap().push_str(line);
+        self.terminal.push(String::new());
+    }
+
+    // The next number from a xorshift64* generator
+    pub fn next_random(&mut self) -> u64 {
+        self.random_state ^= self.random_state >> 12;
+        self.random_state ^= self.random_state << 25;
+        self.random_state ^= self.random_state >> 27;
+        self.random_state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // The current time in milliseconds since the unix epoch
+    pub fn now(&mut self) -> Result<i64, String> {
+        let deterministic = self.options.deterministic;
+        let time = self.traced_input("time", || {
+            if deterministic {
+                return Ok(DETERMINISTIC_TIME.to_string());
+            }
+
+            Ok(std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_millis() as i64)
+                .to_string())
+        })?;
+
+        time.parse::<i64>()
+            .map_err(|_| format!("The replayed trace has an invalid time '{}'", time))
+    }
+
+    // Reads an input from outside the program, such as a key press, through the trace of the run.
+    // A recorded run saves the value that was read, and a replayed run gets the recorded value instead of reading.
+    // Builtins which read something that can differ between runs should use this.
+    pub fn traced_input(
+        &mut self,
+        kind: &str,
+        read: impl FnOnce() -> Result<String, String>,
+    ) -> Result<String, String> {
+        // A run restarted by the debugger first replays the inputs of the run before it
+        let replayed = match (&self.replay_prefix, &self.options.trace) {
+            (Some(prefix), _) if self.replay_position < prefix.inputs.len() => {
+                Some(prefix.inputs[self.replay_position].clone())
+            }
+            (_, TraceMode::Replay(trace)) => match trace.inputs.get(self.replay_position) {
+                Some(input) => Some(input.clone()),
+                None => {
+                    return Err(format!(
+                        "The replayed run reads a {} after all recorded inputs were used",
+                        kind
+                    ))
+                }
+            },
+            _ => None,
+        };
+
+        let value = match replayed {
+            Some(input) => {
+                if input.kind != kind {
+                    return Err(format!(
+                        "The replayed run reads a {} where the recorded run read a {}",
+                        kind, input.kind
+                    ));
+                }
+                self.replay_position += 1;
+                input.value
+            }
+            None => {
+                let value = read()?;
+                if let TraceMode::Record(trace) = &self.options.trace {
+                    trace.lock().unwrap().inputs.push(TracedInput {
+                        kind: kind.to_string(),
+                        value: value.clone(),
+                    });
+                }
+                value
+            }
+        };
+
+        if self.options.debug {
+            self.debug_trace.inputs.push(TracedInput {
+                kind: kind.to_string(),
+                value: value.clone(),
+            });
+        }
+        Ok(value)
+    }
+}
+
 enum InterpretationResult {
     Return { value: Option<Value> },
     Break,
+    Continue,
     Empty,
 }
 
 pub fn interpret(base_expressions: Vec<BaseExpr<()>>) -> Result<Terminal, Error> {
-    let mut env: Environment = Vec::new();
+    let context = interpret_with_options(
+        base_expressions,
+        &builtins::enabled_builtins(),
+        &InterpreterOptions::default(),
+    )?;
+
+    return Ok(context.terminal);
+}
+
+// Interprets the program with the given builtins instead of the ones enabled in this build,
+// returning the context of the finished run which holds the terminal and statistics
+pub fn interpret_with_options(
+    base_expressions: Vec<BaseExpr<()>>,
+    builtins: &[Builtin],
+    options: &InterpreterOptions,
+) -> Result<Context, Error> {
+    let mut program = ProgramState::new(base_expressions, builtins, options);
+
+    // Without a host event loop, the sleeps of a suspending program simply block
+    loop {
+        match program.poll()? {
+            ProgramPoll::Finished => break,
+            ProgramPoll::Suspended(Suspension::Sleep(duration)) => std::thread::sleep(duration),
+            ProgramPoll::Suspended(Suspension::Host) => {}
+        }
+    }
 
-    env.push(Vec::new());
+    return Ok(program.context);
+}
+
+// Evaluates an expression which does not use any variables, such as 2 * 3 or abs(-1), on its own.
+// Used to evaluate constant expressions before the program runs, see consteval.
+pub fn evaluate_constant(expr: &RecExpr<()>, language: &LanguageOptions) -> Result<Option<Value>, Error> {
+    let mut env: Environment = Vec::from([Vec::new()]);
+    add_default_functions_to_env(&mut env, &builtins::enabled_builtins());
+
+    let options = InterpreterOptions {
+        deterministic: true,
+        language: language.clone(),
+        ..InterpreterOptions::default()
+    };
+    let mut context = Context::new(options);
+    interpret_expr(expr, &mut env, &mut context)
+}
 
-    add_default_functions_to_env(&mut env);
+// Evaluates a single expression in which the given variables are defined, with the builtins and options
+// of an engine, see Engine::eval_with
+pub fn evaluate_expression(
+    expr: &RecExpr<()>,
+    variables: &[(&str, Value)],
+    builtins: &[Builtin],
+    options: &InterpreterOptions,
+) -> Result<Option<Value>, Error> {
+    let mut env: Environment = Vec::from([Vec::new()]);
+    add_default_functions_to_env(&mut env, builtins);
+    env.push(
+        variables
+            .iter()
+            .map(|(name, value)| Binding {
+                name: name.to_string(),
+                value: value.clone(),
+            })
+            .collect(),
+    );
+
+    let mut context = Context::new(options.clone());
+    interpret_expr(expr, &mut env, &mut context)
+}
 
-    let mut terminal: Terminal = Vec::new();
+// The environment and context of a program, which are moved into a coroutine while it runs
+type ProgramRun = (Environment, Context);
 
-    terminal.push(String::new());
+type ProgramCoroutine = Coroutine<ProgramRun, (), Result<(), Error>>;
 
-    for base_expression in &base_expressions {
-        match interpret_base_expr(base_expression, &mut env, &mut terminal) {
-            Ok(_) => {}
-            Err(e) => return Err(e),
+// Pauses with the yielded value, or with None when the body pauses the program it runs in, see pause_program
+type GeneratorCoroutine = Coroutine<ProgramRun, Option<Value>, Result<(), Error>>;
+
+// Moves the environment and context out to hand them to another coroutine, leaving empty ones behind
+fn take_run(env: &mut Environment, context: &mut Context) -> ProgramRun {
+    (
+        std::mem::take(env),
+        std::mem::replace(context, Context::new(InterpreterOptions::default())),
+    )
+}
+
+fn put_run(env: &mut Environment, context: &mut Context, (run_env, run_context): ProgramRun) {
+    *env = run_env;
+    *context = run_context;
+}
+
+// A call to a generator function. Its body runs as a coroutine which continues up to the next yield
+// whenever a value is taken, so a generator can also yield values forever.
+// Copies of the value share the call, taking a value from one of them moves all of them on.
+#[derive(Clone)]
+pub struct Generator(Arc<Mutex<GeneratorCoroutine>>);
+
+impl Generator {
+    // The body starts running when the first value is taken, in the scope prepared by the call
+    fn new(body: Arc<Vec<BaseExpr<()>>>, function_scope: Scope) -> Generator {
+        let coroutine = GeneratorCoroutine::new(move |(mut env, mut context): ProgramRun| {
+            env.push(function_scope);
+            context.deferred.push(Vec::new());
+            let result = run_function_body(&body, None, &mut env, &mut context);
+            env.pop();
+            ((env, context), result.map(|_| ()))
+        });
+
+        Generator(Arc::new(Mutex::new(coroutine)))
+    }
+
+    // Runs the body up to its next yield, in the environment of the caller. None once the body has returned.
+    // The span is where the value is taken, for the error about a generator taking a value from itself.
+    fn next(&self, env: &mut Environment, context: &mut Context, span: Span) -> Result<Option<Value>, Error> {
+        let Ok(mut coroutine) = self.0.try_lock() else {
+            return Err(Error::located(
+                String::from("Cannot take a value from a generator which is already running"),
+                span,
+            ));
+        };
+
+        loop {
+            if coroutine.is_finished() {
+                return Ok(None);
+            }
+
+            let (run, step) = coroutine.resume(take_run(env, context));
+            put_run(env, context, run);
+            match step {
+                CoroutineStep::Paused(Some(value)) => return Ok(Some(value)),
+                // The body paused the program, for example to sleep, so the caller pauses as well
+                CoroutineStep::Paused(None) => pause_program(env, context),
+                CoroutineStep::Finished(result) => return result.map(|_| None),
+            }
+        }
+    }
+}
+
+// A program that is being interpreted, which can be run a few statements at a time.
+// The program runs as a coroutine, so it can be paused at any statement, also in the middle of a loop
+// or a function call, and continues from there the next time it is stepped or polled.
+pub struct ProgramState {
+    env: Environment,
+    pub context: Context,
+    coroutine: ProgramCoroutine,
+}
+
+impl ProgramState {
+    pub fn new(
+        base_expressions: Vec<BaseExpr<()>>,
+        builtins: &[Builtin],
+        options: &InterpreterOptions,
+    ) -> ProgramState {
+        let mut env: Environment = Vec::new();
+
+        env.push(Vec::new());
+
+        add_default_functions_to_env(&mut env, builtins);
+
+        let context = Context::new(options.clone());
+        #[cfg(feature = "jit")]
+        let context = Context {
+            jit: (options.jit && !options.debug)
+                .then(|| jit::Jit::new(&base_expressions, &options.language)),
+            ..context
+        };
+
+        let builtins = builtins.to_vec();
+        let coroutine = Coroutine::new(move |(mut env, mut context): ProgramRun| {
+            let result = run_program(&base_expressions, &builtins, &mut env, &mut context);
+            ((env, context), result)
+        });
+
+        ProgramState {
+            env,
+            context,
+            coroutine,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.coroutine.is_finished()
+    }
+
+    // Continues the program until it pauses or finishes, returns whether it has finished
+    fn resume(&mut self) -> Result<bool, Error> {
+        let _span = tracing::debug_span!("interpret").entered();
+
+        let (run, step) = self.coroutine.resume(take_run(&mut self.env, &mut self.context));
+        put_run(&mut self.env, &mut self.context, run);
+
+        match step {
+            CoroutineStep::Paused(()) => Ok(false),
+            CoroutineStep::Finished(result) => result.map(|_| true),
+        }
+    }
+
+    // Runs at most the given number of statements, counting the statements in loops and function calls as well,
+    // returns whether the program has finished
+    pub fn step(&mut self, statements: usize) -> Result<bool, Error> {
+        tracing::trace!(statements, "stepping");
+        if self.is_finished() {
+            return Ok(true);
+        }
+
+        self.context.statements_left = statements;
+        while !self.resume()? {
+            if self.context.statements_left == 0 {
+                return Ok(false);
+            }
+            // The program paused for a suspension, which is not waited for when stepping
+            self.context.suspension = None;
+        }
+
+        Ok(true)
+    }
+
+    // Runs the program until it finishes or suspends itself, after which it can be polled again
+    // to continue right after the call which suspended it.
+    pub fn poll(&mut self) -> Result<ProgramPoll, Error> {
+        if self.is_finished() {
+            return Ok(ProgramPoll::Finished);
+        }
+
+        self.context.statements_left = usize::MAX;
+        while !self.resume()? {
+            if let Some(suspension) = self.context.suspension.take() {
+                return Ok(ProgramPoll::Suspended(suspension));
+            }
+        }
+
+        Ok(ProgramPoll::Finished)
+    }
+
+    // The value of a global variable, as it is after the statements that have run so far
+    pub fn global(&self, name: &str) -> Option<Value> {
+        find_in_scope(&name.to_string(), &self.env[0])
+    }
+
+    // Calls a function defined by the program (or a builtin) from the host, with the
+    // globals of the program as they are after the statements that have run so far
+    pub fn call(&mut self, function_name: &str, arg_values: Vec<Value>) -> Result<Option<Value>, Error> {
+        let function_name = function_name.to_string();
+        let function_value = match find_in_env(&function_name, &self.env) {
+            Some(function_value) => function_value,
+            None => {
+                return Err(Error::SimpleError {
+                    message: format!("Function {} not found", function_name),
+                })
+            }
+        };
+
+        // Calls from the host have no location in the source, so errors about the call itself
+        // are reported here rather than by call_function
+        match &function_value {
+            Value::Function { args, .. } if !accepts_argument_count(args, arg_values.len()) => {
+                return Err(Error::SimpleError {
+                    message: format!(
+                        "Function {} expects {}, but got {}",
+                        function_name,
+                        expected_arguments(args),
+                        arg_values.len()
+                    ),
+                })
+            }
+            Value::Function { .. } => {}
+            Value::StandardFunction(builtin) => {
+                self.context.record_function_call(&function_name);
+                return (builtin.implementation)(arg_values, &mut self.context)
+                    .map_err(|message| Error::SimpleError { message });
+            }
+            other => {
+                return Err(Error::SimpleError {
+                    message: format!(
+                        "Expected function, found {} for variable {}",
+                        value_type_to_string(other),
+                        function_name
+                    ),
+                })
+            }
         }
+
+        self.context.record_function_call(&function_name);
+
+        let env_size = self.env.len();
+        let deferred_depth = self.context.deferred.len();
+        let result = call_function(
+            &function_name,
+            function_value,
+            arg_values.into_iter().map(Some).collect(),
+            &mut self.env,
+            &mut self.context,
+            Span::default(),
+        );
+
+        // A call which failed halfway leaves its scope behind, which would shadow the globals in later calls
+        self.env.truncate(env_size);
+        self.context.deferred.truncate(deferred_depth);
+
+        result
+    }
+}
+
+// Runs the top level statements of a program one after the other, within the coroutine of a ProgramState
+fn run_program(
+    base_expressions: &[BaseExpr<()>],
+    builtins: &[Builtin],
+    env: &mut Environment,
+    context: &mut Context,
+) -> Result<(), Error> {
+    let mut next_statement = 0;
+    while next_statement < base_expressions.len() {
+        let base_expression = &base_expressions[next_statement];
+        if let Err(error) = interpret_base_expr(base_expression, env, context) {
+            if context.quitting {
+                return Ok(());
+            }
+            if let Some(pause_at_statement) = context.restart_at.take() {
+                restart(env, context, builtins, pause_at_statement);
+                next_statement = 0;
+                continue;
+            }
+
+            // In debug mode the program can be inspected where the error occurred before it stops
+            context.record_post_mortem(env, &error, base_expression.span.row);
+            if let Some(post_mortem) = context.post_mortem.take() {
+                let action = run_debugger(
+                    &post_mortem.env,
+                    &post_mortem.call_stack,
+                    context,
+                    post_mortem.row,
+                    DebugPause::Error(error.message().to_string()),
+                );
+                if let DebuggerAction::Restart(pause_at_statement) = action {
+                    restart(env, context, builtins, pause_at_statement);
+                    next_statement = 0;
+                    continue;
+                }
+            }
+            return Err(error);
+        }
+
+        next_statement += 1;
+    }
+
+    Ok(())
+}
+
+// Runs the program again from the start, pausing in the debugger before the statement with the given index.
+// The seed and the inputs read so far are replayed so the program takes the same path, and output
+// that was already shown is not shown again. Other effects, such as writing files, do happen again.
+fn restart(env: &mut Environment, context: &mut Context, builtins: &[Builtin], pause_at_statement: usize) {
+    let seed = context.debug_trace.seed;
+    let mut restarted = Context::new(context.options.clone());
+    if let TraceMode::Record(trace) = &restarted.options.trace {
+        trace.lock().unwrap().seed = seed;
     }
+    restarted.random_state = seed;
+    restarted.debug_trace.seed = seed;
+    restarted.replay_prefix = Some(context.debug_trace.clone());
+    restarted.terminal = std::mem::take(&mut context.terminal);
+    restarted.debugger_input = std::mem::replace(&mut context.debugger_input, Box::new(std::io::empty()));
+    restarted.previous_bindings = context.previous_bindings.take();
+    restarted.pause_at_statement = Some(pause_at_statement);
+    restarted.re_executing = true;
+    restarted.statements_left = context.statements_left;
+
+    *env = Vec::from([Vec::new()]);
+    add_default_functions_to_env(env, builtins);
+    *context = restarted;
+}
 
-    return Ok(terminal);
+// Pauses the coroutine which runs the program, handing the environment and context to the host until it
+// continues the program. Within the body of a generator, the generator pauses and its caller passes
+// the pause on. Does nothing when the program is not run by a ProgramState, such as in a call
+// from the host (see ProgramState::call).
+fn pause_program(env: &mut Environment, context: &mut Context) {
+    if ProgramCoroutine::is_running_here() {
+        let run = ProgramCoroutine::pause(take_run(env, context), ());
+        put_run(env, context, run);
+    } else if GeneratorCoroutine::is_running_here() {
+        let run = GeneratorCoroutine::pause(take_run(env, context), None);
+        put_run(env, context, run);
+    }
 }
 
+// Nested blocks recurse once per level, see stack::ensure_stack
 fn interpret_base_expr(
     base_expression: &BaseExpr<()>,
     env: &mut Environment,
-    terminal: &mut Terminal,
+    context: &mut Context,
+) -> Result<InterpretationResult, Error> {
+    stack::ensure_stack(|| interpret_base_expr_unguarded(base_expression, env, context))
+}
+
+fn interpret_base_expr_unguarded(
+    base_expression: &BaseExpr<()>,
+    env: &mut Environment,
+    context: &mut Context,
 ) -> Result<InterpretationResult, Error> {
+    context.record_statement(env);
+    // Once the statements the host asked for have run, the program pauses until it is stepped again
+    if context.statements_left == 0 {
+        pause_program(env, context);
+    }
+    context.statements_left = context.statements_left.saturating_sub(1);
+    if context.options.debug {
+        pause_at_statement(base_expression, env, context)?;
+    }
+
     match base_expression {
         BaseExpr {
             data: BaseExprData::Simple { expr },
             ..
-        } => match interpret_expr(expr, env, terminal) {
+        } => match interpret_expr(expr, env, context) {
             Ok(_) => return Ok(InterpretationResult::Empty),
             Err(e) => return Err(e),
         },
@@ -128,16 +1072,18 @@ fn interpret_base_expr(
             data: BaseExprData::VariableAssignment { var_name, expr },
             ..
         } => {
-            let value = match interpret_expr(expr, env, terminal) {
+            if let Some(result) = assign_in_place(var_name, expr, env, context) {
+                return result;
+            }
+
+            let value = match interpret_expr_keeping_frozen(expr, env, context) {
                 Ok(right) => match right {
                     Some(value) => value,
                     None => {
-                        return Err(Error::LocationError {
-                            message: format!("Cannot assign to empty"),
-                            row: base_expression.row,
-                            col_start: base_expression.col_start,
-                            col_end: base_expression.col_end,
-                        })
+                        return Err(Error::located(
+                            format!("Cannot assign to empty"),
+                            base_expression.span(),
+                        ))
                     }
                 },
                 Err(e) => return Err(e),
@@ -147,6 +1093,45 @@ fn interpret_base_expr(
             update_or_add_in_scope(&value, &var_name, env.last_mut().unwrap());
             return Ok(InterpretationResult::Empty);
         }
+        BaseExpr {
+            data: BaseExprData::DestructuringAssignment { var_names, expr },
+            ..
+        } => {
+            let values = match interpret_expr(expr, env, context)? {
+                Some(Value::Tuple(values) | Value::List(values)) => values,
+                Some(other_value) => {
+                    return Err(Error::located(
+                        format!(
+                            "Cannot assign a value of type {} to {} variables, only a tuple or list can be split",
+                            value_type_to_string(&other_value),
+                            var_names.len()
+                        ),
+                        expr.span(),
+                    ))
+                }
+                None => {
+                    return Err(Error::located(
+                        String::from("Cannot assign to empty"),
+                        base_expression.span(),
+                    ))
+                }
+            };
+            if values.len() != var_names.len() {
+                return Err(Error::located(
+                    format!(
+                        "Cannot assign {} values to {} variables",
+                        values.len(),
+                        var_names.len()
+                    ),
+                    expr.span(),
+                ));
+            }
+
+            for (var_name, value) in var_names.iter().zip(values) {
+                update_or_add_in_scope(&value, var_name, env.last_mut().unwrap());
+            }
+            Ok(InterpretationResult::Empty)
+        }
         BaseExpr {
             data:
                 BaseExprData::IfStatement {
@@ -156,11 +1141,9 @@ fn interpret_base_expr(
                 },
             ..
         } => {
-            let row = condition.row;
-            let col_start = condition.col_start;
-            let col_end = condition.col_end;
+            let span = condition.span;
 
-            let condition = match interpret_expr(condition, env, terminal) {
+            let condition = match interpret_expr(condition, env, context) {
                 Ok(Some(Value::Bool(condition))) => condition,
                 Ok(Some(other_value)) => {
                     return Err(Error::LocationError {
@@ -168,17 +1151,13 @@ fn interpret_base_expr(
                             "Cannot use {} as a condition for an if statement",
                             value_type_to_string(&other_value)
                         ),
-                        row,
-                        col_start,
-                        col_end,
+                        span,
                     })
                 }
                 Ok(None) => {
                     return Err(Error::LocationError {
                         message: format!("Cannot use empty as a condition for an if statement"),
-                        row,
-                        col_start,
-                        col_end,
+                        span,
                     })
                 }
                 Err(e) => return Err(e),
@@ -191,11 +1170,11 @@ fn interpret_base_expr(
                     None => return Ok(InterpretationResult::Empty),
                 };
 
-                return interpret_base_expr(&*else_statement_real, env, terminal);
+                return interpret_base_expr(&*else_statement_real, env, context);
             }
 
             for base_expression in body {
-                let interp_result = match interpret_base_expr(base_expression, env, terminal) {
+                let interp_result = match interpret_base_expr(base_expression, env, context) {
                     Ok(result) => result,
                     Err(e) => return Err(e),
                 };
@@ -209,7 +1188,10 @@ fn interpret_base_expr(
                         });
                     }
                     InterpretationResult::Break => {
-                        return Ok(InterpretationResult::Empty);
+                        return Ok(InterpretationResult::Break);
+                    }
+                    InterpretationResult::Continue => {
+                        return Ok(InterpretationResult::Continue);
                     }
                     InterpretationResult::Empty => {}
                 }
@@ -226,11 +1208,9 @@ fn interpret_base_expr(
                 },
             ..
         } => {
-            let row = condition.row;
-            let col_start = condition.col_start;
-            let col_end = condition.col_end;
+            let span = condition.span;
 
-            let condition = match interpret_expr(condition, env, terminal) {
+            let condition = match interpret_expr(condition, env, context) {
                 Ok(Some(Value::Bool(condition))) => condition,
                 Ok(Some(other_value)) => {
                     return Err(Error::LocationError {
@@ -238,17 +1218,13 @@ fn interpret_base_expr(
                             "Cannot use {} as a condition for an if statement",
                             value_type_to_string(&other_value)
                         ),
-                        row,
-                        col_start,
-                        col_end,
+                        span,
                     })
                 }
                 Ok(None) => {
                     return Err(Error::LocationError {
                         message: format!("Cannot use empty as a condition for an if statement"),
-                        row,
-                        col_start,
-                        col_end,
+                        span,
                     })
                 }
                 Err(e) => return Err(e),
@@ -261,11 +1237,11 @@ fn interpret_base_expr(
                     None => return Ok(InterpretationResult::Empty),
                 };
 
-                return interpret_base_expr(&*else_statement_real, env, terminal);
+                return interpret_base_expr(&*else_statement_real, env, context);
             }
 
             for base_expression in body {
-                let interp_result = match interpret_base_expr(base_expression, env, terminal) {
+                let interp_result = match interpret_base_expr(base_expression, env, context) {
                     Ok(result) => result,
                     Err(e) => return Err(e),
                 };
@@ -279,7 +1255,10 @@ fn interpret_base_expr(
                         });
                     }
                     InterpretationResult::Break => {
-                        return Ok(InterpretationResult::Empty);
+                        return Ok(InterpretationResult::Break);
+                    }
+                    InterpretationResult::Continue => {
+                        return Ok(InterpretationResult::Continue);
                     }
                     InterpretationResult::Empty => {}
                 }
@@ -292,7 +1271,7 @@ fn interpret_base_expr(
             ..
         } => {
             for base_expression in body {
-                let interp_result = match interpret_base_expr(base_expression, env, terminal) {
+                let interp_result = match interpret_base_expr(base_expression, env, context) {
                     Ok(result) => result,
                     Err(e) => return Err(e),
                 };
@@ -306,7 +1285,10 @@ fn interpret_base_expr(
                         });
                     }
                     InterpretationResult::Break => {
-                        return Ok(InterpretationResult::Empty);
+                        return Ok(InterpretationResult::Break);
+                    }
+                    InterpretationResult::Continue => {
+                        return Ok(InterpretationResult::Continue);
                     }
                     InterpretationResult::Empty => {}
                 }
@@ -318,23 +1300,17 @@ fn interpret_base_expr(
             data: BaseExprData::PlusEqualsStatement { var_name, expr },
             ..
         } => {
-            let row = base_expression.row;
-            let col_start = base_expression.col_start;
-            let col_end = base_expression.col_end;
+            let span = base_expression.span;
 
-            let right_side_row = expr.row;
-            let right_side_col_start = expr.col_start;
-            let right_side_col_end = expr.col_end;
+            let right_side_span = expr.span;
 
-            let value = match interpret_expr(expr, env, terminal) {
+            let value = match interpret_expr(expr, env, context) {
                 Ok(right) => match right {
                     Some(value) => value,
                     None => {
                         return Err(Error::LocationError {
                             message: format!("Cannot assign to empty"),
-                            row,
-                            col_start,
-                            col_end,
+                            span,
                         });
                     }
                 },
@@ -342,31 +1318,28 @@ fn interpret_base_expr(
             };
 
             let current_value = match find_in_env(&var_name, env) {
-                Some(value) => value,
+                Some(value) => value.thawed(),
                 None => {
                     return Err(Error::LocationError {
                         message: format!("Variable {} not found", var_name),
-                        row,
-                        col_start,
-                        col_end,
+                        span,
                     });
                 }
             };
 
-            let new_value = match add(&Some(current_value), &Some(value), row, col_start, col_end) {
+            let new_value = match add(&Some(current_value), &Some(value), span) {
                 Ok(new_value) => match new_value {
                     Some(value) => value,
                     None => {
                         return Err(Error::LocationError {
                             message: format!("Cannot assign to empty"),
-                            row,
-                            col_start,
-                            col_end,
+                            span,
                         });
                     }
                 },
                 Err(e) => return Err(e),
             };
+            context.record_allocation(Some(&new_value));
 
             // Now we add this value to the scope
             let scope = env.last_mut().unwrap();
@@ -375,16 +1348,14 @@ fn interpret_base_expr(
                 false => {
                     return Err(Error::LocationError {
                         message: format!("Variable {} not found", var_name),
-                        row: right_side_row,
-                        col_start: right_side_col_start,
-                        col_end: right_side_col_end,
+                        span: right_side_span,
                     });
                 }
             }
             return Ok(InterpretationResult::Empty);
         }
 
-        BaseExpr {
+        definition @ BaseExpr {
             data:
                 BaseExprData::FunctionDefinition {
                     fun_name,
@@ -396,7 +1367,8 @@ fn interpret_base_expr(
             let function = Value::Function {
                 name: fun_name.clone(),
                 args: args.clone(),
-                body: body.clone(),
+                body: Arc::new(body.clone()),
+                captured: Arc::new(capture_bindings(args, std::slice::from_ref(definition), env)),
             };
 
             update_or_add_in_scope(&function, &fun_name, env.last_mut().unwrap());
@@ -405,17 +1377,163 @@ fn interpret_base_expr(
         }
 
         BaseExpr {
-            data: BaseExprData::Return { return_value },
+            data:
+                BaseExprData::FieldAssignment {
+                    object,
+                    path,
+                    field,
+                    expr,
+                },
             ..
         } => {
-            let return_value = match return_value {
-                Some(expr) => expr,
-                None => return Ok(InterpretationResult::Return { value: None }),
-            };
-
-            let return_value = match interpret_expr(return_value, env, terminal) {
+            let path = evaluate_path(path, env, context)?;
+            let value = match interpret_expr(expr, env, context) {
                 Ok(Some(value)) => value,
-                Ok(None) => return Ok(InterpretationResult::Return { value: None }),
+                Ok(None) => {
+                    return Err(Error::located(
+                        format!("Cannot assign empty to field {} of {}", field, object),
+                        expr.span(),
+                    ));
+                }
+                Err(e) => return Err(e),
+            };
+
+            let Some((scope_index, binding_index)) = find_position_in_env(object, env) else {
+                return Err(Error::located(
+                    format!("Variable {} not found", object),
+                    base_expression.span(),
+                ));
+            };
+
+            // The field is changed in place, copies made of the struct before keep their value
+            match value_at_path(&mut env[scope_index][binding_index].value, &path, base_expression.span())? {
+                Value::Struct { name, fields } => {
+                    match fields.iter_mut().find(|(field_name, _)| field_name == field) {
+                        Some((_, field_value)) => *field_value = value,
+                        None => {
+                            return Err(Error::located(
+                                format!("Struct {} has no field {}", name, field),
+                                base_expression.span(),
+                            ));
+                        }
+                    }
+                }
+                other => {
+                    return Err(Error::located(
+                        format!(
+                            "Cannot assign to field {} of {}, which is a {}",
+                            field,
+                            object,
+                            value_type_to_string(other)
+                        ),
+                        base_expression.span(),
+                    ));
+                }
+            }
+
+            return Ok(InterpretationResult::Empty);
+        }
+
+        BaseExpr {
+            data:
+                BaseExprData::IndexAssignment {
+                    object,
+                    path,
+                    index,
+                    expr,
+                },
+            ..
+        } => {
+            let path = evaluate_path(path, env, context)?;
+            let index_value = match interpret_expr(index, env, context) {
+                Ok(Some(value)) => value,
+                Ok(None) => {
+                    return Err(Error::located(format!("Cannot index {} with empty", object), index.span()));
+                }
+                Err(e) => return Err(e),
+            };
+            let value = match interpret_expr(expr, env, context) {
+                Ok(Some(value)) => value,
+                Ok(None) => {
+                    return Err(Error::located(
+                        format!("Cannot assign empty to an element of {}", object),
+                        expr.span(),
+                    ));
+                }
+                Err(e) => return Err(e),
+            };
+
+            let Some((scope_index, binding_index)) = find_position_in_env(object, env) else {
+                return Err(Error::located(
+                    format!("Variable {} not found", object),
+                    base_expression.span(),
+                ));
+            };
+
+            // The element is changed in place, copies made of the list or dict before keep their value
+            match (
+                value_at_path(&mut env[scope_index][binding_index].value, &path, base_expression.span())?,
+                index_value,
+            ) {
+                (Value::List(list), Value::Number(list_index)) => {
+                    match rosy_rt::index::check_index(list_index, list.len(), "list") {
+                        Ok(list_index) => list[list_index] = value,
+                        Err(message) => return Err(Error::located(message, index.span())),
+                    }
+                }
+                (Value::Dict(entries), key) => match DictKey::from_value(&key) {
+                    Some(key) => {
+                        entries.insert(key, value);
+                    }
+                    None => {
+                        return Err(Error::located(
+                            format!("Cannot use a {} as a dict key", value_type_to_string(&key)),
+                            index.span(),
+                        ));
+                    }
+                },
+                (other, index_value) => {
+                    return Err(Error::located(
+                        format!(
+                            "Cannot assign to an element of {}, which is a {}, with an index of type {}",
+                            object,
+                            value_type_to_string(other),
+                            value_type_to_string(&index_value)
+                        ),
+                        base_expression.span(),
+                    ));
+                }
+            }
+
+            return Ok(InterpretationResult::Empty);
+        }
+
+        BaseExpr {
+            data: BaseExprData::StructDefinition { name, fields },
+            ..
+        } => {
+            let struct_definition = Value::StructDefinition {
+                name: name.clone(),
+                fields: fields.clone(),
+            };
+
+            update_or_add_in_scope(&struct_definition, name, env.last_mut().unwrap());
+
+            return Ok(InterpretationResult::Empty);
+        }
+
+        BaseExpr {
+            data: BaseExprData::Return { return_value },
+            ..
+        } => {
+            let return_value = match return_value {
+                Some(expr) => expr,
+                None => return Ok(InterpretationResult::Return { value: None }),
+            };
+
+            let return_value = match interpret_expr_keeping_frozen(return_value, env, context) {
+                Ok(Some(value)) => value,
+                Ok(None) => return Ok(InterpretationResult::Return { value: None }),
                 Err(e) => return Err(e),
             };
 
@@ -431,6 +1549,180 @@ fn interpret_base_expr(
             return Ok(InterpretationResult::Break);
         }
 
+        BaseExpr {
+            data: BaseExprData::Continue,
+            ..
+        } => {
+            return Ok(InterpretationResult::Continue);
+        }
+
+        BaseExpr {
+            data: BaseExprData::Yield { value },
+            ..
+        } => {
+            let value = match interpret_expr(value, env, context) {
+                Ok(Some(value)) => value,
+                Ok(None) => {
+                    return Err(Error::located(
+                        format!("Cannot yield empty"),
+                        base_expression.span(),
+                    ));
+                }
+                Err(e) => return Err(e),
+            };
+
+            // Functions which yield are generators, whose bodies run in a coroutine of their own
+            if !GeneratorCoroutine::is_running_here() {
+                return Err(Error::located(
+                    format!("Cannot yield outside of a function"),
+                    base_expression.span(),
+                ));
+            }
+
+            // The scope and deferred expressions of the generator stay with it while the caller continues
+            let function_scope = env.pop().unwrap();
+            let deferred = context.deferred.pop().unwrap();
+            let run = GeneratorCoroutine::pause(take_run(env, context), Some(value));
+            put_run(env, context, run);
+            env.push(function_scope);
+            context.deferred.push(deferred);
+
+            return Ok(InterpretationResult::Empty);
+        }
+
+        BaseExpr {
+            data: BaseExprData::Defer { expr },
+            ..
+        } => {
+            match context.deferred.last_mut() {
+                Some(deferred) => deferred.push(expr.clone()),
+                None => {
+                    return Err(Error::located(
+                        format!("Cannot defer outside of a function"),
+                        base_expression.span(),
+                    ));
+                }
+            }
+
+            return Ok(InterpretationResult::Empty);
+        }
+
+        BaseExpr {
+            data:
+                BaseExprData::With {
+                    resource,
+                    var_name,
+                    body,
+                },
+            ..
+        } => {
+            let resource = match interpret_expr(resource, env, context) {
+                Ok(Some(Value::Resource(resource))) => resource,
+                Ok(Some(other_value)) => {
+                    return Err(Error::located(
+                        format!(
+                            "Cannot use {} as the resource of a with block",
+                            value_type_to_string(&other_value)
+                        ),
+                        resource.span(),
+                    ))
+                }
+                Ok(None) => {
+                    return Err(Error::located(
+                        format!("Cannot use empty as the resource of a with block"),
+                        resource.span(),
+                    ))
+                }
+                Err(e) => return Err(e),
+            };
+
+            update_or_add_in_scope(&Value::Resource(resource), var_name, env.last_mut().unwrap());
+
+            let mut result = Ok(InterpretationResult::Empty);
+            for base_expression in body {
+                match interpret_base_expr(base_expression, env, context) {
+                    Ok(InterpretationResult::Empty) => {}
+                    other => {
+                        result = other;
+                        break;
+                    }
+                }
+            }
+
+            // The resource is closed however the block ends, it may already have been closed by the block itself
+            context.close_resource(&resource);
+
+            return result;
+        }
+
+        BaseExpr {
+            data: BaseExprData::Match { subject, arms },
+            ..
+        } => {
+            let value = match interpret_expr(subject, env, context) {
+                Ok(Some(value)) => value,
+                Ok(None) => {
+                    return Err(Error::located(
+                        "Cannot match on empty".to_string(),
+                        subject.span(),
+                    ))
+                }
+                Err(e) => return Err(e),
+            };
+
+            // Like an if statement without an else, nothing happens when no case matches
+            let Some(arm) = arms.iter().find(|arm| pattern_matches(&arm.pattern, &value)) else {
+                return Ok(InterpretationResult::Empty);
+            };
+            if let Pattern::Binding(name) = &arm.pattern {
+                update_or_add_in_scope(&value, name, env.last_mut().unwrap());
+            }
+
+            for base_expression in &arm.body {
+                match interpret_base_expr(base_expression, env, context) {
+                    Ok(InterpretationResult::Empty) => {}
+                    other => return other,
+                }
+            }
+
+            return Ok(InterpretationResult::Empty);
+        }
+
+        BaseExpr {
+            data: BaseExprData::Delete { var_name },
+            ..
+        } => {
+            if !remove_from_env(var_name, env) {
+                return Err(Error::located(
+                    format!("Cannot delete {}, it is not defined", var_name),
+                    base_expression.span(),
+                ));
+            }
+
+            return Ok(InterpretationResult::Empty);
+        }
+
+        BaseExpr {
+            data:
+                BaseExprData::Swap {
+                    first_name,
+                    second_name,
+                },
+            ..
+        } => {
+            for name in [first_name, second_name] {
+                if find_position_in_env(name, env).is_none() {
+                    return Err(Error::located(
+                        format!("Cannot swap {}, it is not defined", name),
+                        base_expression.span(),
+                    ));
+                }
+            }
+
+            swap_in_env(first_name, second_name, env);
+            return Ok(InterpretationResult::Empty);
+        }
+
         BaseExpr {
             data:
                 BaseExprData::ForLoop {
@@ -440,55 +1732,70 @@ fn interpret_base_expr(
                 },
             ..
         } => {
-            let row = until_expr.row;
-            let col_start = until_expr.col_start;
-            let col_end = until_expr.col_end;
-
-            let values = match interpret_expr(until_expr, env, terminal) {
+            let span = until_expr.span;
+
+            // The iterable is evaluated once, before the first iteration. Lists are values, so the loop
+            // runs over this snapshot: assigning to the list variable in the body (for example
+            // items = items + [x]) does not change which elements are visited.
+            // A generator is taken a value from before every iteration instead. A dict is looped over by its keys.
+            let mut generator = None;
+            let mut values = match interpret_expr(until_expr, env, context) {
                 Ok(Some(Value::Number(until))) => {
                     (0..until).map(|i| Value::Number(i)).into_iter().collect()
                 }
                 Ok(Some(Value::List(values))) => values,
+                Ok(Some(Value::Generator(iterated_generator))) => {
+                    generator = Some(iterated_generator);
+                    Vec::new()
+                }
+                Ok(Some(Value::Bytes(bytes))) => {
+                    bytes.into_iter().map(|byte| Value::Number(byte as i64)).collect()
+                }
+                // The keys of a dict, in the order in which they were added
+                Ok(Some(Value::Dict(entries))) => entries.keys().map(DictKey::to_value).collect(),
                 Ok(Some(other_value)) => {
                     return Err(Error::LocationError {
                         message: format!(
                             "Cannot use {} as a condition for a for loop",
                             value_type_to_string(&other_value)
                         ),
-                        row,
-                        col_start,
-                        col_end,
+                        span,
                     });
                 }
                 Ok(None) => {
                     return Err(Error::LocationError {
                         message: format!("Cannot use empty as a condition for a for loop"),
-                        row,
-                        col_start,
-                        col_end,
+                        span,
                     });
                 }
                 Err(e) => return Err(e),
-            };
+            }
+            .into_iter();
 
             update_or_add_in_scope(&Value::Number(0), var_name, env.last_mut().unwrap());
 
-            for i in values {
+            loop {
+                let next_value = match &generator {
+                    Some(generator) => generator.next(env, context, until_expr.span())?,
+                    None => values.next(),
+                };
+                let Some(i) = next_value else {
+                    break;
+                };
+
                 let scope = env.last_mut().unwrap();
                 match update_in_scope(&i, &var_name, scope) {
                     true => {}
                     false => {
                         return Err(Error::LocationError {
                             message: format!("Variable {} not found", var_name),
-                            row,
-                            col_start,
-                            col_end,
+                            span,
                         });
                     }
                 }
 
                 for base_expression in body.iter() {
-                    let interp_result = match interpret_base_expr(base_expression, env, terminal) {
+                    let interp_result = match interpret_base_expr(base_expression, env, context) {
                         Ok(result) => result,
                         Err(e) => return Err(e),
                     };
@@ -502,8 +1809,10 @@ fn interpret_base_expr(
                             });
                         }
                         InterpretationResult::Break => {
-                            return Ok(InterpretationResult::Break);
+                            return Ok(InterpretationResult::Empty);
                         }
+                        // Skips the rest of the body, going on with the next value
+                        InterpretationResult::Continue => break,
                         InterpretationResult::Empty => {}
                     }
                 }
@@ -511,23 +1820,178 @@ fn interpret_base_expr(
 
             return Ok(InterpretationResult::Empty);
         }
+
+        BaseExpr {
+            data: BaseExprData::WhileLoop { condition, body },
+            ..
+        } => loop {
+            // The condition is evaluated again before every iteration
+            let condition_value = match interpret_expr(condition, env, context) {
+                Ok(Some(Value::Bool(condition_value))) => condition_value,
+                Ok(Some(other_value)) => {
+                    return Err(Error::located(
+                        format!(
+                            "Cannot use {} as a condition for a while loop",
+                            value_type_to_string(&other_value)
+                        ),
+                        condition.span(),
+                    ));
+                }
+                Ok(None) => {
+                    return Err(Error::located(
+                        "Cannot use empty as a condition for a while loop".to_string(),
+                        condition.span(),
+                    ));
+                }
+                Err(e) => return Err(e),
+            };
+            if !condition_value {
+                return Ok(InterpretationResult::Empty);
+            }
+
+            for base_expression in body.iter() {
+                match interpret_base_expr(base_expression, env, context)? {
+                    InterpretationResult::Return {
+                        value: return_value,
+                    } => {
+                        return Ok(InterpretationResult::Return {
+                            value: return_value,
+                        });
+                    }
+                    InterpretationResult::Break => {
+                        return Ok(InterpretationResult::Empty);
+                    }
+                    // Skips the rest of the body, checking the condition again
+                    InterpretationResult::Continue => break,
+                    InterpretationResult::Empty => {}
+                }
+            }
+        },
+    }
+}
+
+// The operands of an operation on a rational number, where an integer operand is converted to a rational.
+// None if neither operand is a rational number.
+fn rational_operands(left: &Option<Value>, right: &Option<Value>) -> Option<(Rational, Rational)> {
+    match (left, right) {
+        (Some(Value::Rational(left)), Some(Value::Rational(right))) => Some((*left, *right)),
+        (Some(Value::Rational(left)), Some(Value::Number(right))) => {
+            Some((*left, Rational::from_integer(*right)))
+        }
+        (Some(Value::Number(left)), Some(Value::Rational(right))) => {
+            Some((Rational::from_integer(*left), *right))
+        }
+        _ => None,
+    }
+}
+
+// The operands of an operation on a complex number, where other numbers are converted to complex numbers.
+// None if neither operand is a complex number.
+fn complex_operands(left: &Option<Value>, right: &Option<Value>) -> Option<(Complex, Complex)> {
+    fn to_complex(value: &Option<Value>) -> Option<Complex> {
+        match value {
+            Some(Value::Complex(value)) => Some(*value),
+            Some(Value::Number(value)) => Some(Complex::from_integer(*value)),
+            Some(Value::Rational(value)) => Some(Complex::from_rational(*value)),
+            Some(Value::Float(value)) => Some(Complex::new(*value, 0.0)),
+            _ => None,
+        }
+    }
+
+    if !matches!(left, Some(Value::Complex(_))) && !matches!(right, Some(Value::Complex(_))) {
+        return None;
+    }
+
+    match (to_complex(left), to_complex(right)) {
+        (Some(left), Some(right)) => Some((left, right)),
+        _ => None,
+    }
+}
+
+// The operands of a comparison with a float, where integers and rational numbers are converted to floats
+fn float_operands(left: &Option<Value>, right: &Option<Value>) -> Option<(f64, f64)> {
+    fn to_float(value: &Option<Value>) -> Option<f64> {
+        match value {
+            Some(Value::Float(value)) => Some(*value),
+            Some(Value::Number(value)) => Some(*value as f64),
+            Some(Value::Rational(value)) => Some(value.to_f64()),
+            _ => None,
+        }
+    }
+
+    if !matches!(left, Some(Value::Float(_))) && !matches!(right, Some(Value::Float(_))) {
+        return None;
+    }
+
+    match (to_float(left), to_float(right)) {
+        (Some(left), Some(right)) => Some((left, right)),
+        _ => None,
+    }
+}
+
+// Integer division rounded down, where / rounds towards zero: -7 // 2 is -4
+// None when the quotient does not fit in an integer, which is only the case for the smallest integer // -1
+fn floor_divide(left: i64, right: i64) -> Option<i64> {
+    let quotient = left.checked_div(right)?;
+    if left % right != 0 && (left < 0) != (right < 0) {
+        return Some(quotient - 1);
+    }
+    Some(quotient)
+}
+
+fn complex_to_value(
+    result: Result<Complex, String>,
+    span: Span,
+) -> Result<Option<Value>, Error> {
+    match result {
+        Ok(value) => return Ok(Some(Value::Complex(value))),
+        Err(message) => {
+            return Err(Error::LocationError {
+                message,
+                span,
+            });
+        }
+    }
+}
+
+fn rational_to_value(
+    result: Result<Rational, String>,
+    span: Span,
+) -> Result<Option<Value>, Error> {
+    match result {
+        Ok(value) => return Ok(Some(Value::Rational(value))),
+        Err(message) => {
+            return Err(Error::LocationError {
+                message,
+                span,
+            });
+        }
     }
 }
 
 fn add(
     left: &Option<Value>,
     right: &Option<Value>,
-    row: usize,
-    col_start: usize,
-    col_end: usize,
+    span: Span,
 ) -> Result<Option<Value>, Error> {
+    if let Some((left, right)) = complex_operands(left, right) {
+        return complex_to_value(Ok(left + right), span);
+    }
+    if let Some((left, right)) = float_operands(left, right) {
+        return Ok(Some(Value::Float(left + right)));
+    }
+    if let Some((left, right)) = rational_operands(left, right) {
+        return rational_to_value(left.checked_add(right), span);
+    }
+
     match (left, right) {
         (Some(Value::Number(left)), Some(Value::Number(right))) => {
             let result = left + right;
             return Ok(Some(Value::Number(result)));
         }
         (Some(Value::String(left)), Some(Value::String(right))) => {
-            let result = left.clone() + right;
+            let mut result = left.clone();
+            result.push_str(right);
             return Ok(Some(Value::String(result)));
         }
         (Some(Value::List(left_elements)), Some(Value::List(right_elements))) => {
@@ -549,217 +2013,320 @@ fn add(
                     value_type_to_string(left),
                     value_type_to_string(right)
                 ),
-                row,
-                col_start,
-                col_end,
+                span,
             });
         }
         _ => {
             return Err(Error::LocationError {
                 message: format!("Cannot apply operator + on empty"),
-                row,
-                col_start,
-                col_end,
+                span,
             });
         }
     }
 }
 
+// Nested expressions recurse once per level, see stack::ensure_stack.
+// A frozen value gives the value inside, which can be used like any other.
 fn interpret_expr(
     expr: &RecExpr<()>,
     env: &mut Environment,
-    terminal: &mut Terminal,
+    context: &mut Context,
 ) -> Result<Option<Value>, Error> {
+    Ok(interpret_expr_keeping_frozen(expr, env, context)?.map(Value::thawed))
+}
+
+// For the values which are stored as they are, those assigned to a variable, passed to a function or returned
+fn interpret_expr_keeping_frozen(
+    expr: &RecExpr<()>,
+    env: &mut Environment,
+    context: &mut Context,
+) -> Result<Option<Value>, Error> {
+    stack::ensure_stack(|| interpret_expr_unguarded(expr, env, context))
+}
+
+fn interpret_expr_unguarded(
+    expr: &RecExpr<()>,
+    env: &mut Environment,
+    context: &mut Context,
+) -> Result<Option<Value>, Error> {
+    if let Some(result) = compare_integers(expr, env) {
+        return Ok(Some(Value::Bool(result)));
+    }
+
     match &expr.data {
         RecExprData::Variable { name } => match find_in_env(&name, env) {
             Some(value) => return Ok(Some(value)),
             None => {
-                return Err(Error::LocationError {
-                    message: format!("Variable not found: {}", name),
-                    row: expr.row,
-                    col_start: expr.col_start,
-                    col_end: expr.col_end,
-                });
+                return Err(Error::located(format!("Variable not found: {}", name), expr.span()));
             }
         },
         RecExprData::Number { number } => return Ok(Some(Value::Number(*number))),
+        RecExprData::Float { value } => return Ok(Some(Value::Float(*value))),
+        RecExprData::KeywordArgument { .. } => Err(Error::located(
+            String::from("A keyword argument can only be given in a function call"),
+            expr.span(),
+        )),
+        // Only the chosen value is evaluated, so the other may fail or have side effects
+        RecExprData::Conditional {
+            condition,
+            then_value,
+            else_value,
+        } => match interpret_expr(condition, env, context)? {
+            Some(Value::Bool(true)) => interpret_expr(then_value, env, context),
+            Some(Value::Bool(false)) => interpret_expr(else_value, env, context),
+            Some(other_value) => Err(Error::located(
+                format!(
+                    "Cannot use {} as the condition of a conditional expression",
+                    value_type_to_string(&other_value)
+                ),
+                condition.span(),
+            )),
+            None => Err(Error::located(
+                String::from("Cannot use empty as the condition of a conditional expression"),
+                condition.span(),
+            )),
+        },
         RecExprData::Boolean { value } => return Ok(Some(Value::Bool(*value))),
-        RecExprData::String { value } => return Ok(Some(Value::String(value.clone()))),
+        RecExprData::String { value } => {
+            let string = Some(Value::String(SmallString::from(value.as_str())));
+            context.record_allocation(string.as_ref());
+            return Ok(string);
+        }
         RecExprData::Add { left, right } => {
-            let left_value = match interpret_expr(&*left, env, terminal) {
+            let left_value = match interpret_expr(&*left, env, context) {
                 Ok(left_value) => left_value,
                 Err(e) => return Err(e),
             };
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
 
-            let row = expr.row;
-            let col_start = expr.col_start;
-            let col_end = expr.col_end;
+            let span = expr.span;
 
-            return add(&left_value, &right_value, row, col_start, col_end);
+            let result = add(&left_value, &right_value, span)?;
+            context.record_allocation(result.as_ref());
+            return Ok(result);
         }
         RecExprData::Subtract { left, right } => {
-            let left_value = match interpret_expr(&*left, env, terminal) {
+            let left_value = match interpret_expr(&*left, env, context) {
                 Ok(left_value) => left_value,
                 Err(e) => return Err(e),
             };
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
 
+            if let Some((left, right)) = complex_operands(&left_value, &right_value) {
+                return complex_to_value(Ok(left - right), expr.span);
+            }
+            if let Some((left, right)) = float_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Float(left - right)));
+            }
+            if let Some((left, right)) = rational_operands(&left_value, &right_value) {
+                return rational_to_value(left.checked_sub(right), expr.span);
+            }
+
             match (left_value, right_value) {
                 (Some(Value::Number(left_num)), Some(Value::Number(right_num))) => {
                     let result = left_num - right_num;
                     return Ok(Some(Value::Number(result)));
                 }
                 (Some(left_value), Some(right_value)) => {
-                    return Err(Error::LocationError {
-                        message: format!(
+                    return Err(Error::located(
+                        format!(
                             "Cannot apply operator - on types {} and {}",
                             value_type_to_string(&left_value),
                             value_type_to_string(&right_value)
                         ),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                        expr.span(),
+                    ));
                 }
                 _ => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot apply operator - on empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot apply operator - on empty"),
+                        expr.span(),
+                    ));
                 }
             }
         }
         RecExprData::Multiply { left, right } => {
-            let left_value = match interpret_expr(&*left, env, terminal) {
+            let left_value = match interpret_expr(&*left, env, context) {
                 Ok(left_value) => left_value,
                 Err(e) => return Err(e),
             };
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
 
+            if let Some((left, right)) = complex_operands(&left_value, &right_value) {
+                return complex_to_value(Ok(left * right), expr.span);
+            }
+            if let Some((left, right)) = float_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Float(left * right)));
+            }
+            if let Some((left, right)) = rational_operands(&left_value, &right_value) {
+                return rational_to_value(left.checked_mul(right), expr.span);
+            }
+
             match (left_value, right_value) {
                 (Some(Value::Number(left_num)), Some(Value::Number(right_num))) => {
                     let result = left_num * right_num;
                     return Ok(Some(Value::Number(result)));
                 }
                 (Some(left_value), Some(right_value)) => {
-                    return Err(Error::LocationError {
-                        message: format!(
+                    return Err(Error::located(
+                        format!(
                             "Cannot apply operator * on types {} and {}",
                             value_type_to_string(&left_value),
                             value_type_to_string(&right_value)
                         ),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                        expr.span(),
+                    ));
                 }
                 _ => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot apply operator * on empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot apply operator * on empty"),
+                        expr.span(),
+                    ));
                 }
             }
         }
         RecExprData::Divide { left, right } => {
-            let left_value = match interpret_expr(&*left, env, terminal) {
+            let left_value = match interpret_expr(&*left, env, context) {
                 Ok(left_value) => left_value,
                 Err(e) => return Err(e),
             };
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
 
+            if let Some((left, right)) = complex_operands(&left_value, &right_value) {
+                return complex_to_value(left.checked_div(right), expr.span);
+            }
+            if let Some((left, right)) = float_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Float(left / right)));
+            }
+            if let Some((left, right)) = rational_operands(&left_value, &right_value) {
+                return rational_to_value(left.checked_div(right), expr.span);
+            }
+
             match (left_value, right_value) {
+                (Some(Value::Number(_)), Some(Value::Number(0))) => {
+                    return Err(Error::located("Division by zero".to_string(), expr.span()));
+                }
+                // With true division the result is exact, otherwise it is rounded towards zero
+                (Some(Value::Number(left_num)), Some(Value::Number(right_num)))
+                    if context.options.language.true_division =>
+                {
+                    return Ok(Some(Value::Float(left_num as f64 / right_num as f64)));
+                }
                 (Some(Value::Number(left_num)), Some(Value::Number(right_num))) => {
-                    let result = left_num / right_num;
+                    let result = left_num.wrapping_div(right_num);
                     return Ok(Some(Value::Number(result)));
                 }
                 (Some(left_value), Some(right_value)) => {
-                    return Err(Error::LocationError {
-                        message: format!(
+                    return Err(Error::located(
+                        format!(
                             "Cannot apply operator / on types {} and {}",
                             value_type_to_string(&left_value),
                             value_type_to_string(&right_value)
                         ),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                        expr.span(),
+                    ));
                 }
                 _ => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot apply operator / on empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot apply operator / on empty"),
+                        expr.span(),
+                    ));
+                }
+            }
+        }
+        RecExprData::FloorDivide { left, right } => {
+            let left_value = interpret_expr(left, env, context)?;
+            let right_value = interpret_expr(right, env, context)?;
+
+            if let Some((left, right)) = float_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Float((left / right).floor())));
+            }
+
+            match (left_value, right_value) {
+                (Some(Value::Number(_)), Some(Value::Number(0))) => {
+                    Err(Error::located("Division by zero".to_string(), expr.span()))
+                }
+                (Some(Value::Number(left_num)), Some(Value::Number(right_num))) => {
+                    match floor_divide(left_num, right_num) {
+                        Some(result) => Ok(Some(Value::Number(result))),
+                        None => Err(Error::located(
+                            format!("The result of {} // {} is too large for an integer", left_num, right_num),
+                            expr.span(),
+                        )),
+                    }
                 }
+                (Some(left_value), Some(right_value)) => Err(Error::located(
+                    format!(
+                        "Cannot apply operator // on types {} and {}",
+                        value_type_to_string(&left_value),
+                        value_type_to_string(&right_value)
+                    ),
+                    expr.span(),
+                )),
+                _ => Err(Error::located(
+                    "Cannot apply operator // on empty".to_string(),
+                    expr.span(),
+                )),
             }
         }
         RecExprData::Power { left, right } => {
-            let left_value = match interpret_expr(&*left, env, terminal) {
+            let left_value = match interpret_expr(&*left, env, context) {
                 Ok(left_value) => left_value,
                 Err(e) => return Err(e),
             };
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
 
+            if let Some((left, right)) = float_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Float(left.powf(right))));
+            }
+
             match (left_value, right_value) {
                 (Some(Value::Number(left)), Some(Value::Number(right))) => {
                     if right < 0 {
-                        return Err(Error::LocationError {
-                            message: format!("Cannot raise to a negative power"),
-                            row: expr.row,
-                            col_start: expr.col_start,
-                            col_end: expr.col_end,
-                        });
+                        return Err(Error::located(
+                            format!("Cannot raise to a negative power"),
+                            expr.span(),
+                        ));
                     }
 
                     let result = i64::pow(left, right as u32);
                     return Ok(Some(Value::Number(result)));
                 }
                 (Some(left_value), Some(right_value)) => {
-                    return Err(Error::LocationError {
-                        message: format!(
+                    return Err(Error::located(
+                        format!(
                             "Cannot apply operator ^ on types {} and {}",
                             value_type_to_string(&left_value),
                             value_type_to_string(&right_value)
                         ),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                        expr.span(),
+                    ));
                 }
                 _ => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot apply operator ^ on empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot apply operator ^ on empty"),
+                        expr.span(),
+                    ));
                 }
             }
         }
         RecExprData::Minus { right } => {
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
@@ -769,37 +2336,48 @@ fn interpret_expr(
                     let result = -value;
                     return Ok(Some(Value::Number(result)));
                 }
+                Some(Value::Float(value)) => return Ok(Some(Value::Float(-value))),
+                Some(Value::Rational(value)) => {
+                    return rational_to_value(value.checked_neg(), expr.span);
+                }
+                Some(Value::Complex(value)) => return Ok(Some(Value::Complex(-value))),
                 Some(value) => {
-                    return Err(Error::LocationError {
-                        message: format!(
+                    return Err(Error::located(
+                        format!(
                             "Cannot apply operator - on type {}",
                             value_type_to_string(&value)
                         ),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                        expr.span(),
+                    ));
                 }
                 _ => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot apply operator - on empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot apply operator - on empty"),
+                        expr.span(),
+                    ));
                 }
             }
         }
         RecExprData::Equals { left, right } => {
-            let left_value = match interpret_expr(&*left, env, terminal) {
+            let left_value = match interpret_expr(&*left, env, context) {
                 Ok(left_value) => left_value,
                 Err(e) => return Err(e),
             };
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
 
+            if let Some((left, right)) = complex_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Bool(left == right)));
+            }
+            if let Some((left, right)) = float_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Bool(left == right)));
+            }
+            if let Some((left, right)) = rational_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Bool(left == right)));
+            }
+
             match (left_value, right_value) {
                 (Some(Value::Number(left)), Some(Value::Number(right))) => {
                     let result = left == right;
@@ -818,25 +2396,33 @@ fn interpret_expr(
                     return Ok(Some(Value::Bool(false)));
                 }
                 _ => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot apply operator == on empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot apply operator == on empty"),
+                        expr.span(),
+                    ));
                 }
             }
         }
         RecExprData::NotEquals { left, right } => {
-            let left_value = match interpret_expr(&*left, env, terminal) {
+            let left_value = match interpret_expr(&*left, env, context) {
                 Ok(left_value) => left_value,
                 Err(e) => return Err(e),
             };
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
 
+            if let Some((left, right)) = complex_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Bool(left != right)));
+            }
+            if let Some((left, right)) = float_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Bool(left != right)));
+            }
+            if let Some((left, right)) = rational_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Bool(left != right)));
+            }
+
             match (left_value, right_value) {
                 (Some(Value::Number(left)), Some(Value::Number(right))) => {
                     let result = left != right;
@@ -855,169 +2441,179 @@ fn interpret_expr(
                     return Ok(Some(Value::Bool(true)));
                 }
                 _ => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot apply operator != on empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot apply operator != on empty"),
+                        expr.span(),
+                    ));
                 }
             }
         }
         RecExprData::GreaterThan { left, right } => {
-            let left_value = match interpret_expr(&*left, env, terminal) {
+            let left_value = match interpret_expr(&*left, env, context) {
                 Ok(left_value) => left_value,
                 Err(e) => return Err(e),
             };
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
 
+            if let Some((left, right)) = float_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Bool(left > right)));
+            }
+            if let Some((left, right)) = rational_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Bool(left > right)));
+            }
+
             match (left_value, right_value) {
                 (Some(Value::Number(left)), Some(Value::Number(right))) => {
                     let result = left > right;
                     return Ok(Some(Value::Bool(result)));
                 }
                 (Some(left_value), Some(right_value)) => {
-                    return Err(Error::LocationError {
-                        message: format!(
+                    return Err(Error::located(
+                        format!(
                             "Cannot apply operator > on types {} and {}",
                             value_type_to_string(&left_value),
                             value_type_to_string(&right_value)
                         ),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                        expr.span(),
+                    ));
                 }
                 _ => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot apply operator > on empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot apply operator > on empty"),
+                        expr.span(),
+                    ));
                 }
             }
         }
         RecExprData::GreaterThanOrEqual { left, right } => {
-            let left_value = match interpret_expr(&*left, env, terminal) {
+            let left_value = match interpret_expr(&*left, env, context) {
                 Ok(left_value) => left_value,
                 Err(e) => return Err(e),
             };
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
 
+            if let Some((left, right)) = float_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Bool(left >= right)));
+            }
+            if let Some((left, right)) = rational_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Bool(left >= right)));
+            }
+
             match (left_value, right_value) {
                 (Some(Value::Number(left)), Some(Value::Number(right))) => {
                     let result = left >= right;
                     return Ok(Some(Value::Bool(result)));
                 }
                 (Some(left_value), Some(right_value)) => {
-                    return Err(Error::LocationError {
-                        message: format!(
+                    return Err(Error::located(
+                        format!(
                             "Cannot apply operator >= on types {} and {}",
                             value_type_to_string(&left_value),
                             value_type_to_string(&right_value)
                         ),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                        expr.span(),
+                    ));
                 }
                 _ => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot apply operator >= on empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot apply operator >= on empty"),
+                        expr.span(),
+                    ));
                 }
             }
         }
         RecExprData::LessThan { left, right } => {
-            let left_value = match interpret_expr(&*left, env, terminal) {
+            let left_value = match interpret_expr(&*left, env, context) {
                 Ok(left_value) => left_value,
                 Err(e) => return Err(e),
             };
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
 
+            if let Some((left, right)) = float_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Bool(left < right)));
+            }
+            if let Some((left, right)) = rational_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Bool(left < right)));
+            }
+
             match (left_value, right_value) {
                 (Some(Value::Number(left)), Some(Value::Number(right))) => {
                     let result = left < right;
                     return Ok(Some(Value::Bool(result)));
                 }
                 (Some(left_value), Some(right_value)) => {
-                    return Err(Error::LocationError {
-                        message: format!(
+                    return Err(Error::located(
+                        format!(
                             "Cannot apply operator < on types {} and {}",
                             value_type_to_string(&left_value),
                             value_type_to_string(&right_value)
                         ),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                        expr.span(),
+                    ));
                 }
                 _ => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot apply operator < on empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot apply operator < on empty"),
+                        expr.span(),
+                    ));
                 }
             }
         }
         RecExprData::LessThanOrEqual { left, right } => {
-            let left_value = match interpret_expr(&*left, env, terminal) {
+            let left_value = match interpret_expr(&*left, env, context) {
                 Ok(left_value) => left_value,
                 Err(e) => return Err(e),
             };
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
 
+            if let Some((left, right)) = float_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Bool(left <= right)));
+            }
+            if let Some((left, right)) = rational_operands(&left_value, &right_value) {
+                return Ok(Some(Value::Bool(left <= right)));
+            }
+
             match (left_value, right_value) {
                 (Some(Value::Number(left)), Some(Value::Number(right))) => {
                     let result = left <= right;
                     return Ok(Some(Value::Bool(result)));
                 }
                 (Some(left_value), Some(right_value)) => {
-                    return Err(Error::LocationError {
-                        message: format!(
+                    return Err(Error::located(
+                        format!(
                             "Cannot apply operator <= on types {} and {}",
                             value_type_to_string(&left_value),
                             value_type_to_string(&right_value)
                         ),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                        expr.span(),
+                    ));
                 }
                 _ => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot apply operator <= on empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot apply operator <= on empty"),
+                        expr.span(),
+                    ));
                 }
             }
         }
         RecExprData::And { left, right } => {
-            let left_value = match interpret_expr(&*left, env, terminal) {
+            let left_value = match interpret_expr(&*left, env, context) {
                 Ok(left_value) => left_value,
                 Err(e) => return Err(e),
             };
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
@@ -1028,33 +2624,29 @@ fn interpret_expr(
                     return Ok(Some(Value::Bool(result)));
                 }
                 (Some(left_value), Some(right_value)) => {
-                    return Err(Error::LocationError {
-                        message: format!(
+                    return Err(Error::located(
+                        format!(
                             "Cannot apply operator AND on types {} and {}",
                             value_type_to_string(&left_value),
                             value_type_to_string(&right_value)
                         ),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                        expr.span(),
+                    ));
                 }
                 _ => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot apply operator AND on empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot apply operator AND on empty"),
+                        expr.span(),
+                    ));
                 }
             }
         }
         RecExprData::Or { left, right } => {
-            let left_value = match interpret_expr(&*left, env, terminal) {
+            let left_value = match interpret_expr(&*left, env, context) {
                 Ok(left_value) => left_value,
                 Err(e) => return Err(e),
             };
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
@@ -1065,29 +2657,25 @@ fn interpret_expr(
                     return Ok(Some(Value::Bool(result)));
                 }
                 (Some(left_value), Some(right_value)) => {
-                    return Err(Error::LocationError {
-                        message: format!(
+                    return Err(Error::located(
+                        format!(
                             "Cannot apply operator OR on types {} and {}",
                             value_type_to_string(&left_value),
                             value_type_to_string(&right_value)
                         ),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                        expr.span(),
+                    ));
                 }
                 _ => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot apply operator OR on empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot apply operator OR on empty"),
+                        expr.span(),
+                    ));
                 }
             }
         }
         RecExprData::Not { right } => {
-            let right_value = match interpret_expr(&*right, env, terminal) {
+            let right_value = match interpret_expr(&*right, env, context) {
                 Ok(right_value) => right_value,
                 Err(e) => return Err(e),
             };
@@ -1098,23 +2686,19 @@ fn interpret_expr(
                     return Ok(Some(Value::Bool(result)));
                 }
                 Some(right_value) => {
-                    return Err(Error::LocationError {
-                        message: format!(
+                    return Err(Error::located(
+                        format!(
                             "Cannot apply operator NOT on type {}",
                             value_type_to_string(&right_value)
                         ),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                        expr.span(),
+                    ));
                 }
                 _ => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot apply operator NOT on empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot apply operator NOT on empty"),
+                        expr.span(),
+                    ));
                 }
             }
         }
@@ -1125,151 +2709,83 @@ fn interpret_expr(
             let env_variable = match find_in_env(&function_name, env) {
                 Some(env_variable) => env_variable,
                 None => {
-                    return Err(Error::LocationError {
-                        message: format!("Function {} not found", function_name),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Function {} not found", function_name),
+                        expr.span(),
+                    ));
                 }
             };
 
-            // We also need all values that we will pass
+            context.record_function_call(function_name);
+
+            // We also need all values that we will pass, evaluated in the order they are written.
+            // Builtins get the value inside a frozen value.
+            let keeps_frozen = matches!(env_variable, Value::Function { .. } | Value::StructDefinition { .. });
             let mut arg_values = Vec::new();
             for arg in args {
-                let row = arg.row;
-                let col_start = arg.col_start;
-                let col_end = arg.col_end;
+                let span = arg.span;
+                let arg = match &arg.data {
+                    RecExprData::KeywordArgument { value, .. } => value,
+                    _ => arg,
+                };
 
-                match interpret_expr(&arg, env, terminal) {
+                let value = match keeps_frozen {
+                    true => interpret_expr_keeping_frozen(arg, env, context),
+                    false => interpret_expr(arg, env, context),
+                };
+                match value {
                     Ok(Some(value)) => {
                         arg_values.push(value);
                     }
                     Ok(None) => {
                         return Err(Error::LocationError {
                             message: format!("Cannot pass Empty to a function"),
-                            row,
-                            col_start,
-                            col_end,
+                            span,
                         });
                     }
                     Err(e) => return Err(e),
                 }
             }
 
-            match env_variable {
-                Value::Function { name, args, body } => {
-                    // Matching the arguments values with the argument names
-                    let mut function_scope: Scope = Vec::new();
-
-                    if args.len() != arg_values.len() {
-                        return Err(Error::LocationError {
-                            message: format!(
-                                "Expected {} arguments, but got {}",
-                                args.len(),
-                                arg_values.len()
-                            ),
-                            row: expr.row,
-                            col_start: expr.col_start,
-                            col_end: expr.col_end,
-                        });
-                    }
-
-                    for (name, value) in args.iter().zip(arg_values.iter()) {
-                        function_scope.push(Binding {
-                            name: name.clone(),
-                            value: value.clone(),
-                        });
-                    }
-
-                    // Adding this scope to the environment
-                    env.push(function_scope);
-
-                    // Run all sub statements
-                    for base_expression in body {
-                        let row = base_expression.row;
-                        let col_start = base_expression.col_start;
-                        let col_end = base_expression.col_end;
-
-                        let interp_result =
-                            match interpret_base_expr(&base_expression, env, terminal) {
-                                Ok(result) => result,
-                                Err(e) => return Err(e),
-                            };
-
-                        match interp_result {
-                            InterpretationResult::Return {
-                                value: return_value,
-                            } => {
-                                return Ok(return_value);
-                            }
-                            InterpretationResult::Break => {
-                                return Err(Error::LocationError {
-                                    message: format!("Cannot break out of a function"),
-                                    row,
-                                    col_start,
-                                    col_end,
-                                });
-                            }
-                            InterpretationResult::Empty => {}
-                        }
-                    }
-
-                    // Removing the scope
-                    env.pop();
-
-                    // No return statement was found, thus return empty
-                    return Ok(None);
-                }
-                Value::StandardFunction(StandardFunction::Print) => {
-                    let last_terminal_line: &mut String = terminal.last_mut().unwrap();
-                    for arg in arg_values {
-                        let value_string = value_to_string(&arg);
-                        print!("{}", value_string);
-                        last_terminal_line.push_str(&value_string);
-                    }
-
-                    return Ok(None);
-                }
-                Value::StandardFunction(StandardFunction::PrintLine) => {
-                    let last_terminal_line = terminal.last_mut().unwrap();
-                    for arg in arg_values {
-                        let value_string = value_to_string(&arg);
-                        print!("{}", value_string);
-                        last_terminal_line.push_str(&value_string);
-                    }
-                    terminal.push(String::new());
-                    println!();
-                    return Ok(None);
-                }
-                other => {
-                    return Err(Error::LocationError {
-                        message: format!(
-                            "Expected function, found {} for variable {}",
-                            value_type_to_string(&other),
-                            function_name
-                        ),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+            // Keyword arguments are put in the place of the parameter they name
+            if !parser::has_keyword_arguments(args) {
+                #[cfg(feature = "jit")]
+                if let Some(value) = context.call_compiled(function_name, &env_variable, &arg_values) {
+                    return Ok(Some(value));
                 }
             }
+            let param_names: Vec<&str> = match &env_variable {
+                // The *rest parameter only collects positional arguments, so it cannot be named
+                Value::Function { args: params, .. } => params
+                    .iter()
+                    .filter(|param| !param.rest)
+                    .map(|param| param.name.as_str())
+                    .collect(),
+                Value::StructDefinition { fields, .. } => fields.iter().map(|field| field.as_str()).collect(),
+                Value::StandardFunction(builtin) => builtin.param_names.to_vec(),
+                _ => Vec::new(),
+            };
+            let positions = parser::argument_positions(function_name, &param_names, args)?;
+            let arg_values = parser::place_arguments(&positions, arg_values);
+
+            return call_function(
+                function_name,
+                env_variable,
+                arg_values,
+                env,
+                context,
+                expr.span,
+            );
         }
         RecExprData::Assign {
             variable_name,
             right,
         } => {
-            let value = match interpret_expr(&*right, env, terminal) {
+            let value = match interpret_expr(&*right, env, context) {
                 Ok(right) => match right {
                     Some(value) => value,
                     None => {
-                        return Err(Error::LocationError {
-                            message: format!("Cannot assign to empty"),
-                            row: expr.row,
-                            col_start: expr.col_start,
-                            col_end: expr.col_end,
-                        });
+                        return Err(Error::located(format!("Cannot assign to empty"), expr.span()));
                     }
                 },
                 Err(e) => return Err(e),
@@ -1285,22 +2801,51 @@ fn interpret_expr(
             return Ok(None);
         }
         RecExprData::Access { object, variable } => {
-            return Err(Error::SimpleError {
-                message: format!("not implemented"),
-            });
+            let object_value = match find_ref_in_env(object, env) {
+                Some(value) => value,
+                None => {
+                    return Err(Error::located(
+                        format!("Variable {} not found", object),
+                        expr.span(),
+                    ));
+                }
+            };
+
+            match object_value {
+                Value::Struct { name, fields } => {
+                    match fields.iter().find(|(field_name, _)| field_name == variable) {
+                        Some((_, value)) => return Ok(Some(value.clone())),
+                        None => {
+                            return Err(Error::located(
+                                format!("Struct {} has no field {}", name, variable),
+                                expr.span(),
+                            ));
+                        }
+                    }
+                }
+                other => {
+                    return Err(Error::located(
+                        format!(
+                            "Cannot access field {} of {}, which is a {}",
+                            variable,
+                            object,
+                            value_type_to_string(other)
+                        ),
+                        expr.span(),
+                    ));
+                }
+            }
         }
         RecExprData::List { elements } => {
             let mut list = Vec::new();
             for element in elements {
-                let value = match interpret_expr(&element, env, terminal) {
+                let value = match interpret_expr(&element, env, context) {
                     Ok(Some(value)) => value,
                     Ok(None) => {
-                        return Err(Error::LocationError {
-                            message: format!("Cannot add empty to a list"),
-                            row: element.row,
-                            col_start: element.col_start,
-                            col_end: element.col_end,
-                        });
+                        return Err(Error::located(
+                            format!("Cannot add empty to a list"),
+                            element.span(),
+                        ));
                     }
                     Err(e) => return Err(e),
                 };
@@ -1308,66 +2853,963 @@ fn interpret_expr(
                 list.push(value);
             }
 
-            return Ok(Some(Value::List(list)));
+            let list = Some(Value::List(list));
+            context.record_allocation(list.as_ref());
+            return Ok(list);
         }
-        RecExprData::ListAccess { variable, index } => {
-            let variable_value = match find_in_env(&variable, env) {
-                Some(value) => value,
-                None => {
-                    return Err(Error::LocationError {
-                        message: format!("Variable {} not found", variable),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+        RecExprData::Dict { entries } => {
+            let mut dict = IndexMap::new();
+            for (key, value) in entries {
+                let key_value = match interpret_expr(key, env, context) {
+                    Ok(Some(value)) => value,
+                    Ok(None) => {
+                        return Err(Error::located(format!("Cannot use empty as a dict key"), key.span()));
+                    }
+                    Err(e) => return Err(e),
+                };
+                let Some(dict_key) = DictKey::from_value(&key_value) else {
+                    return Err(Error::located(
+                        format!("Cannot use a {} as a dict key", value_type_to_string(&key_value)),
+                        key.span(),
+                    ));
+                };
+                let value = match interpret_expr(value, env, context) {
+                    Ok(Some(value)) => value,
+                    Ok(None) => {
+                        return Err(Error::located(format!("Cannot add empty to a dict"), value.span()));
+                    }
+                    Err(e) => return Err(e),
+                };
+
+                // A key given twice keeps its first position and its last value
+                dict.insert(dict_key, value);
+            }
+
+            return Ok(Some(Value::Dict(dict)));
+        }
+        RecExprData::Tuple { elements } => {
+            let mut values = Vec::new();
+            for element in elements {
+                let value = match interpret_expr(element, env, context) {
+                    Ok(Some(value)) => value,
+                    Ok(None) => {
+                        return Err(Error::located(
+                            format!("Cannot add empty to a tuple"),
+                            element.span(),
+                        ));
+                    }
+                    Err(e) => return Err(e),
+                };
+                values.push(value);
+            }
+
+            return Ok(Some(Value::Tuple(values)));
+        }
+        RecExprData::ListAccess { list, index } => {
+            let variable_value = match interpret_expr(&*list, env, context) {
+                Ok(Some(value)) => value,
+                Ok(None) => {
+                    return Err(Error::located(format!("Cannot index into empty"), list.span()));
                 }
+                Err(e) => return Err(e),
             };
 
-            let index_value = match interpret_expr(&*index, env, terminal) {
+            let index_value = match interpret_expr(&*index, env, context) {
                 Ok(Some(value)) => value,
                 Ok(None) => {
-                    return Err(Error::LocationError {
-                        message: format!("Cannot access list with empty"),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Cannot access list with empty"),
+                        expr.span(),
+                    ));
                 }
                 Err(e) => return Err(e),
             };
 
             match (variable_value, index_value) {
                 (Value::List(list), Value::Number(index)) => {
-                    let index = index as usize;
-                    let len = list.len();
-                    if index >= len {
-                        return Err(Error::LocationError {
-                            message: format!(
-                                "Index {index} out of bounds for list of length {len}"
-                            ),
-                            row: expr.row,
-                            col_start: expr.col_start,
-                            col_end: expr.col_end,
-                        });
+                    match rosy_rt::index::check_index(index, list.len(), "list") {
+                        Ok(index) => return Ok(Some(list[index].clone())),
+                        Err(message) => return Err(Error::located(message, expr.span())),
+                    }
+                }
+                (Value::Bytes(bytes), Value::Number(index)) => {
+                    match rosy_rt::index::check_index(index, bytes.len(), "bytes") {
+                        Ok(index) => return Ok(Some(Value::Number(bytes[index] as i64))),
+                        Err(message) => return Err(Error::located(message, expr.span())),
+                    }
+                }
+                (Value::Tuple(mut values), Value::Number(index)) => {
+                    match rosy_rt::index::check_index(index, values.len(), "tuple") {
+                        Ok(index) => return Ok(Some(values.swap_remove(index))),
+                        Err(message) => return Err(Error::located(message, expr.span())),
+                    }
+                }
+                (Value::Dict(mut entries), key) => {
+                    let value = DictKey::from_value(&key).and_then(|key| entries.swap_remove(&key));
+                    match value {
+                        Some(value) => return Ok(Some(value)),
+                        None => {
+                            return Err(Error::located(
+                                format!("Key {} not found in dict", value_to_string(&key)),
+                                index.span(),
+                            ));
+                        }
                     }
-
-                    return Ok(Some(list[index].clone()));
                 }
                 (variable_value, index_value) => {
-                    return Err(Error::LocationError {
-                        message: format!(
+                    return Err(Error::located(
+                        format!(
                             "Cannot access list with types {} and {}",
                             value_type_to_string(&variable_value),
                             value_type_to_string(&index_value)
                         ),
-                        row: expr.row,
-                        col_start: expr.col_start,
-                        col_end: expr.col_end,
+                        expr.span(),
+                    ));
+                }
+            }
+        }
+        RecExprData::Slice { list, start, end } => {
+            let list_value = match interpret_expr(list, env, context)? {
+                Some(value) => value,
+                None => return Err(Error::located(String::from("Cannot slice empty"), list.span())),
+            };
+            let start = interpret_slice_bound(start.as_deref(), env, context)?;
+            let end = interpret_slice_bound(end.as_deref(), env, context)?;
+
+            let result = match list_value {
+                Value::List(values) => {
+                    Value::List(values[rosy_rt::index::clamp_slice(start, end, values.len())].to_vec())
+                }
+                Value::String(value) => {
+                    let range = rosy_rt::index::clamp_slice(start, end, value.chars().count());
+                    let sliced: String = value.chars().skip(range.start).take(range.len()).collect();
+                    Value::String(SmallString::from(sliced.as_str()))
+                }
+                other_value => {
+                    return Err(Error::located(
+                        format!("Cannot slice {}", value_type_to_string(&other_value)),
+                        list.span(),
+                    ));
+                }
+            };
+            context.record_allocation(Some(&result));
+            Ok(Some(result))
+        }
+    }
+}
+
+// The value of a bound in a slice such as xs[1:4], which is None when it is left out
+fn interpret_slice_bound(
+    bound: Option<&RecExpr<()>>,
+    env: &mut Environment,
+    context: &mut Context,
+) -> Result<Option<i64>, Error> {
+    let Some(bound) = bound else {
+        return Ok(None);
+    };
+    match interpret_expr(bound, env, context)? {
+        Some(Value::Number(number)) => Ok(Some(number)),
+        Some(other_value) => Err(Error::located(
+            format!("Cannot slice with {}, as a bound must be an integer", value_type_to_string(&other_value)),
+            bound.span(),
+        )),
+        None => Err(Error::located(String::from("Cannot slice with empty"), bound.span())),
+    }
+}
+
+// A field or element on the way to the target of a nested assignment, with its index evaluated
+enum PathStep<'a> {
+    Field(&'a str, Span),
+    Index(Value, Span),
+}
+
+// Evaluates the indices along the path of a nested assignment, in source order
+fn evaluate_path<'a>(
+    path: &'a [Accessor<()>],
+    env: &mut Environment,
+    context: &mut Context,
+) -> Result<Vec<PathStep<'a>>, Error> {
+    let mut steps = Vec::new();
+    for accessor in path {
+        steps.push(match accessor {
+            Accessor::Field { name, span } => PathStep::Field(name, *span),
+            Accessor::Index(index) => match interpret_expr(index, env, context)? {
+                Some(index_value) => PathStep::Index(index_value, index.span()),
+                None => return Err(Error::located(String::from("Cannot index with empty"), index.span())),
+            },
+        });
+    }
+    Ok(steps)
+}
+
+// The field or element the path leads to from the value, which a nested assignment changes in place.
+// Nothing in a frozen value can be changed, which is reported at the span of the assignment.
+fn value_at_path<'a>(mut value: &'a mut Value, path: &[PathStep], span: Span) -> Result<&'a mut Value, Error> {
+    let frozen_error = || Error::located(String::from("Cannot assign to a field or element of a frozen value"), span);
+    for step in path {
+        value = match (value, step) {
+            (Value::Frozen(_), _) => return Err(frozen_error()),
+            (Value::Struct { name, fields }, PathStep::Field(field, span)) => {
+                match fields.iter_mut().find(|(field_name, _)| field_name == field) {
+                    Some((_, field_value)) => field_value,
+                    None => return Err(Error::located(format!("Struct {} has no field {}", name, field), *span)),
+                }
+            }
+            (Value::List(list), PathStep::Index(Value::Number(index), span)) => {
+                match rosy_rt::index::check_index(*index, list.len(), "list") {
+                    Ok(index) => &mut list[index],
+                    Err(message) => return Err(Error::located(message, *span)),
+                }
+            }
+            (Value::Dict(entries), PathStep::Index(key, span)) => {
+                match DictKey::from_value(key).and_then(|dict_key| entries.get_mut(&dict_key)) {
+                    Some(entry) => entry,
+                    None => {
+                        return Err(Error::located(
+                            format!("Key {} not found in dict", value_to_string(key)),
+                            *span,
+                        ))
+                    }
+                }
+            }
+            (other, PathStep::Field(field, span)) => {
+                return Err(Error::located(
+                    format!("Cannot access field {} of a value of type {}", field, value_type_to_string(other)),
+                    *span,
+                ))
+            }
+            (other, PathStep::Index(index, span)) => {
+                return Err(Error::located(
+                    format!(
+                        "Cannot access list with types {} and {}",
+                        value_type_to_string(other),
+                        value_type_to_string(index)
+                    ),
+                    *span,
+                ))
+            }
+        };
+    }
+    if let Value::Frozen(_) = value {
+        return Err(frozen_error());
+    }
+    Ok(value)
+}
+
+// Why the program is paused in the debugger
+enum DebugPause {
+    Breakpoint,
+    // The program is about to run the next statement after a step
+    Step,
+    // The program stopped with the error with this message, it can be inspected but not continued
+    Error(String),
+}
+
+// A function call that is running, recorded in debug mode so the debugger can show the call stack
+#[derive(Clone)]
+struct CallFrame {
+    function_name: String,
+    // The line of the call
+    row: usize,
+    // The number of scopes in the environment before the call added its own
+    scope_count: usize,
+}
+
+// The state of the program when an error occurred in debug mode, kept for the debugger
+struct PostMortem {
+    env: Environment,
+    call_stack: Vec<CallFrame>,
+    row: usize,
+}
+
+// A frame as shown by the debugger, innermost first
+struct DebugFrame {
+    function_name: String,
+    row: usize,
+    // The scopes of the environment which belong to this frame
+    scope_start: usize,
+    scope_end: usize,
+}
+
+fn debug_frames(call_stack: &[CallFrame], env: &Environment, row: usize) -> Vec<DebugFrame> {
+    let mut frames = Vec::new();
+    let mut row = row;
+    let mut scope_end = env.len();
+
+    for call in call_stack.iter().rev() {
+        frames.push(DebugFrame {
+            function_name: call.function_name.clone(),
+            row,
+            scope_start: call.scope_count,
+            scope_end,
+        });
+        row = call.row;
+        scope_end = call.scope_count;
+    }
+    frames.push(DebugFrame {
+        function_name: String::from("<main>"),
+        row,
+        scope_start: 0,
+        scope_end,
+    });
+
+    frames
+}
+
+// Evaluates an expression typed in the debugger, in the environment as it is seen from a frame.
+// The environment is copied so the expression cannot change the paused program.
+fn evaluate_in_frame(
+    source: &str,
+    env: &Environment,
+    frame: &DebugFrame,
+    context: &mut Context,
+) -> Result<Value, Error> {
+    let base_expressions =
+        parser::parse_strings_with_options(Vec::from([source]), &context.options.language)?;
+    let expr = match base_expressions.as_slice() {
+        [BaseExpr {
+            data: BaseExprData::Simple { expr },
+            ..
+        }] => expr,
+        _ => {
+            return Err(Error::SimpleError {
+                message: String::from("Expected an expression"),
+            })
+        }
+    };
+
+    let mut frame_env: Environment = env[..frame.scope_end].to_vec();
+    match interpret_expr(expr, &mut frame_env, context)? {
+        Some(value) => Ok(value),
+        None => Err(Error::SimpleError {
+            message: String::from("The expression has no value"),
+        }),
+    }
+}
+
+// What the program should do after the debugger prompt is left
+enum DebuggerAction {
+    Continue,
+    Quit,
+    // Run the program again from the start, pausing before the statement with this index
+    Restart(usize),
+}
+
+// The variables in scope with their values, innermost first so shadowed variables are left out
+fn visible_bindings(env: &Environment) -> BTreeMap<String, String> {
+    let mut bindings = BTreeMap::new();
+    for scope in env.iter().rev() {
+        for binding in scope.iter() {
+            if matches!(binding.value, Value::StandardFunction(_)) {
+                continue;
+            }
+            bindings
+                .entry(binding.name.clone())
+                .or_insert_with(|| value_to_string(&binding.value));
+        }
+    }
+    bindings
+}
+
+// Starts unwinding the program so run_program can run it again up to the given statement, see restart
+fn restart_program(context: &mut Context, pause_at_statement: usize) -> Error {
+    context.restart_at = Some(pause_at_statement);
+    context.re_executing = true;
+    Error::SimpleError {
+        message: String::from("The program is restarted by the debugger"),
+    }
+}
+
+// Starts unwinding the program after the debugger was told to quit. Deferred expressions are skipped and
+// run_program ends the program without an error. A call from the host does return the error.
+fn quit_program(context: &mut Context, span: Span) -> Error {
+    context.quitting = true;
+    Error::located(String::from("Program stopped in the debugger"), span)
+}
+
+// Pauses in the debugger before the statement that is about to run, if the debugger stepped to it
+fn pause_at_statement(
+    base_expression: &BaseExpr<()>,
+    env: &Environment,
+    context: &mut Context,
+) -> Result<(), Error> {
+    context.statement_index += 1;
+    if context.pause_at_statement != Some(context.statement_index) {
+        return Ok(());
+    }
+    context.pause_at_statement = None;
+    context.re_executing = false;
+
+    let call_stack = context.call_stack.clone();
+    match run_debugger(env, &call_stack, context, base_expression.span.row, DebugPause::Step) {
+        DebuggerAction::Continue => Ok(()),
+        DebuggerAction::Quit => Err(quit_program(context, base_expression.span())),
+        DebuggerAction::Restart(pause_at_statement) => Err(restart_program(context, pause_at_statement)),
+    }
+}
+
+// An interactive prompt for inspecting the call stack and variables of a paused program,
+// and for stepping forwards and backwards through its statements.
+// Continuing is only possible at a breakpoint or step, not after an error.
+fn run_debugger(
+    env: &Environment,
+    call_stack: &[CallFrame],
+    context: &mut Context,
+    row: usize,
+    pause: DebugPause,
+) -> DebuggerAction {
+    let action = debugger_prompt(env, call_stack, context, row, &pause);
+    // Kept to show what changed when the debugger pauses again
+    context.previous_bindings = Some(visible_bindings(env));
+    action
+}
+
+fn debugger_prompt(
+    env: &Environment,
+    call_stack: &[CallFrame],
+    context: &mut Context,
+    row: usize,
+    pause: &DebugPause,
+) -> DebuggerAction {
+    let frames = debug_frames(call_stack, env, row);
+    let mut selected = 0;
+
+    // The debugger output starts on its own line
+    if !context.terminal.last().unwrap().is_empty() {
+        context.terminal.push(String::new());
+        println!();
+    }
+    let reason = match pause {
+        DebugPause::Breakpoint => format!("Paused at breakpoint on line {}", row + 1),
+        DebugPause::Step => format!("Paused before line {}", row + 1),
+        DebugPause::Error(message) => format!("Stopped by an error on line {}: {}", row + 1, message),
+    };
+    context.write_debugger_line(&reason);
+    if let (DebugPause::Step, Some(previous)) = (pause, &context.previous_bindings) {
+        for line in bindings_diff(previous, &visible_bindings(env)) {
+            context.write_debugger_line(&line);
+        }
+    }
+    context.write_debugger_line("Type help for the commands");
+
+    loop {
+        print!("(debug) ");
+        let _ = std::io::stdout().flush();
+
+        let mut command = String::new();
+        match context.debugger_input.read_line(&mut command) {
+            Ok(0) | Err(_) => {
+                println!();
+                return match pause {
+                    DebugPause::Error(_) => DebuggerAction::Quit,
+                    _ => DebuggerAction::Continue,
+                };
+            }
+            Ok(_) => {}
+        }
+        let command = command.trim();
+
+        // The command was already echoed by the console, so it is only added to the terminal
+        context.terminal.last_mut().unwrap().push_str(&format!("(debug) {}", command));
+        context.terminal.push(String::new());
+
+        match command.split_once(' ').unwrap_or((command, "")) {
+            ("continue" | "c", _) => match pause {
+                DebugPause::Error(_) => context.write_debugger_line(
+                    "The program cannot continue after an error, use quit to stop",
+                ),
+                _ => return DebuggerAction::Continue,
+            },
+            ("step" | "n", _) => match pause {
+                DebugPause::Error(_) => context.write_debugger_line(
+                    "The program cannot continue after an error, use back to go to the statement before it",
+                ),
+                _ => {
+                    context.pause_at_statement = Some(context.statement_index + 1);
+                    return DebuggerAction::Continue;
+                }
+            },
+            ("back" | "b", _) => {
+                // A step pause is before the current statement ran, the other pauses are in the middle of it
+                let target = match pause {
+                    DebugPause::Step => context.statement_index - 1,
+                    _ => context.statement_index,
+                };
+                if target == 0 {
+                    context.write_debugger_line("Already at the first statement");
+                } else {
+                    return DebuggerAction::Restart(target);
+                }
+            }
+            ("quit" | "q", _) => return DebuggerAction::Quit,
+            ("stack" | "s", _) => {
+                for (index, frame) in frames.iter().enumerate() {
+                    let marker = if index == selected { ">" } else { " " };
+                    context.write_debugger_line(&format!(
+                        "{}#{} {} on line {}",
+                        marker,
+                        index,
+                        frame.function_name,
+                        frame.row + 1
+                    ));
+                }
+            }
+            ("frame" | "f", index) => match index.trim().parse::<usize>() {
+                Ok(index) if index < frames.len() => {
+                    selected = index;
+                    context.write_debugger_line(&format!(
+                        "#{} {} on line {}",
+                        index,
+                        frames[index].function_name,
+                        frames[index].row + 1
+                    ));
+                }
+                _ => context.write_debugger_line(&format!(
+                    "Expected a frame number from 0 to {}",
+                    frames.len() - 1
+                )),
+            },
+            ("vars" | "v", _) => {
+                // Innermost scope first, builtins are left out
+                let frame = &frames[selected];
+                for scope in env[frame.scope_start..frame.scope_end].iter().rev() {
+                    for binding in scope.iter() {
+                        if matches!(binding.value, Value::StandardFunction(_)) {
+                            continue;
+                        }
+                        context.write_debugger_line(&format!(
+                            "{}: {} = {}",
+                            binding.name,
+                            value_type_to_string(&binding.value),
+                            value_to_string(&binding.value)
+                        ));
+                    }
+                }
+            }
+            ("print" | "p", source) => {
+                match evaluate_in_frame(source, env, &frames[selected], context) {
+                    Ok(value) => context.write_debugger_line(&format!(
+                        "{} {}",
+                        value_type_to_string(&value),
+                        value_to_string(&value)
+                    )),
+                    Err(error) => context.write_debugger_line(error.message()),
+                }
+            }
+            ("help" | "h", _) => {
+                context.write_debugger_line("stack         list the function calls, innermost first");
+                context.write_debugger_line("frame <n>     select a function call from the stack");
+                context.write_debugger_line("vars          list the variables of the selected call");
+                context.write_debugger_line("print <expr>  evaluate an expression in the selected call");
+                context.write_debugger_line("step          run until the next statement");
+                context.write_debugger_line("back          go back to before the previous statement");
+                context.write_debugger_line("continue      continue running the program");
+                context.write_debugger_line("quit          stop the program");
+            }
+            ("", _) => {}
+            _ => context.write_debugger_line(&format!(
+                "Unknown command '{}', type help for the commands",
+                command
+            )),
+        }
+    }
+}
+
+// The changes between the variables at the last pause and now, as
+//   + name = value       for a new variable
+//   ~ name = value, was old value
+//   - name               for a variable which is no longer in scope
+fn bindings_diff(previous: &BTreeMap<String, String>, current: &BTreeMap<String, String>) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (name, value) in current.iter() {
+        match previous.get(name) {
+            None => lines.push(format!("+ {} = {}", name, value)),
+            Some(old_value) if old_value != value => {
+                lines.push(format!("~ {} = {}, was {}", name, value, old_value))
+            }
+            Some(_) => {}
+        }
+    }
+    for name in previous.keys() {
+        if !current.contains_key(name) {
+            lines.push(format!("- {}", name));
+        }
+    }
+    lines
+}
+
+// Calls a function value, which is either a function defined in the program or a builtin.
+// The location is that of the function call, and is used for errors.
+fn call_function(
+    function_name: &String,
+    function_value: Value,
+    arg_values: Vec<Option<Value>>,
+    env: &mut Environment,
+    context: &mut Context,
+    span: Span,
+) -> Result<Option<Value>, Error> {
+    match function_value {
+        Value::Function {
+            name,
+            args,
+            body,
+            captured,
+        } => {
+            if !accepts_argument_count(&args, arg_values.len()) {
+                return Err(Error::LocationError {
+                    message: format!(
+                        "Expected {}, but got {}",
+                        expected_arguments(&args),
+                        arg_values.len()
+                    ),
+                    span,
+                });
+            }
+            let arg_values = match parser::rest_position(&args) {
+                Some(position) => parser::collect_rest_arguments(arg_values, position, Value::List),
+                None => arg_values,
+            };
+            // A parameter skipped by the keyword arguments needs a default value
+            let missing = args
+                .iter()
+                .zip(&arg_values)
+                .find(|(arg, value)| value.is_none() && arg.default.is_none());
+            if let Some((arg, _)) = missing {
+                return Err(Error::LocationError {
+                    message: format!("No value is given for the parameter {} of {}", arg.name, function_name),
+                    span,
+                });
+            }
+
+            // Matching the arguments values with the argument names
+            let mut function_scope = context.take_scope();
+            let mut defaulted = Vec::new();
+            for (index, arg) in args.iter().enumerate() {
+                match arg_values.get(index) {
+                    Some(Some(value)) => function_scope.push(Binding {
+                        name: arg.name.clone(),
+                        value: value.clone(),
+                    }),
+                    _ => defaulted.push(arg),
+                }
+            }
+            // Each call starts from the captured values, changes to them last until the call returns
+            for (name, value) in captured.iter() {
+                function_scope.push(Binding {
+                    name: name.clone(),
+                    value: value.clone(),
+                });
+            }
+
+            if context.options.debug {
+                context.call_stack.push(CallFrame {
+                    function_name: function_name.clone(),
+                    row: span.row,
+                    scope_count: env.len(),
+                });
+            }
+
+            // Adding this scope to the environment
+            env.push(function_scope);
+
+            // Expressions deferred by this call, run when it returns
+            context.deferred.push(Vec::new());
+
+            let mut error = None;
+
+            // The parameters left out of the call get their default value, which is evaluated in the
+            // scope of the function so it can use the variables the function captured
+            for arg in defaulted {
+                let Some(default) = &arg.default else {
+                    continue;
+                };
+                match interpret_expr(default, env, context) {
+                    Ok(Some(value)) => env.last_mut().unwrap().push(Binding {
+                        name: arg.name.clone(),
+                        value,
+                    }),
+                    Ok(None) => {
+                        error = Some(Error::located(
+                            format!("Cannot use empty as the default value of {}", arg.name),
+                            default.span(),
+                        ));
+                        break;
+                    }
+                    Err(e) => {
+                        error = Some(e);
+                        break;
+                    }
+                }
+            }
+
+            // A generator function does not run yet, its body runs a bit whenever a value is taken from it
+            if error.is_none() && contains_yield(&body) {
+                let function_scope = env.pop().unwrap();
+                context.deferred.pop();
+                if context.options.debug {
+                    context.call_stack.pop();
+                }
+                return Ok(Some(Value::Generator(Generator::new(body, function_scope))));
+            }
+
+            let result = run_function_body(&body, error, env, context);
+
+            // Removing the scope, also on errors so a host can keep using the environment
+            if let Some(function_scope) = env.pop() {
+                context.recycle_scope(function_scope);
+            }
+            if context.options.debug {
+                context.call_stack.pop();
+            }
+
+            return result;
+        }
+        Value::StructDefinition { name, fields } => {
+            // Fields are never left out, a keyword argument can only change their order
+            let missing = fields.iter().zip(&arg_values).find(|(_, value)| value.is_none());
+            if let Some((field, _)) = missing {
+                return Err(Error::LocationError {
+                    message: format!("No value is given for the field {} of struct {}", field, name),
+                    span,
+                });
+            }
+            let arg_values: Vec<Value> = arg_values.into_iter().flatten().collect();
+            if fields.len() != arg_values.len() {
+                return Err(Error::LocationError {
+                    message: format!(
+                        "Expected {} fields for struct {}, but got {}",
+                        fields.len(),
+                        name,
+                        arg_values.len()
+                    ),
+                    span,
+                });
+            }
+
+            return Ok(Some(Value::Struct {
+                name,
+                fields: fields.into_iter().zip(arg_values).collect(),
+            }));
+        }
+        Value::StandardFunction(builtin) => {
+            let Some(arg_values) = arg_values.into_iter().collect::<Option<Vec<Value>>>() else {
+                return Err(Error::LocationError {
+                    message: format!("{} needs a value for every parameter before the last one given", builtin.name),
+                    span,
+                });
+            };
+            match (builtin.implementation)(arg_values, context) {
+                Ok(return_value) => {
+                    if context.breakpoint_requested {
+                        context.breakpoint_requested = false;
+                        let call_stack = context.call_stack.clone();
+                        match run_debugger(env, &call_stack, context, span.row, DebugPause::Breakpoint) {
+                            DebuggerAction::Continue => {}
+                            DebuggerAction::Quit => {
+                                return Err(quit_program(context, span));
+                            }
+                            DebuggerAction::Restart(pause_at_statement) => {
+                                return Err(restart_program(context, pause_at_statement));
+                            }
+                        }
+                    }
+                    // A builtin such as sleep suspends the program where it was called
+                    if context.suspension.is_some() {
+                        pause_program(env, context);
+                    }
+                    return Ok(return_value);
+                }
+                Err(message) => {
+                    return Err(Error::LocationError {
+                        message,
+                        span,
                     });
                 }
             }
         }
+        other => {
+            return Err(Error::LocationError {
+                message: format!(
+                    "Expected function, found {} for variable {}",
+                    value_type_to_string(&other),
+                    function_name
+                ),
+                span,
+            });
+        }
+    }
+}
+
+// Runs the statements of a function call, whose scope is the last one in the environment, followed by
+// the expressions it deferred. When the call already failed (while evaluating the defaults of its
+// parameters) only the deferred expressions run. Returns what the call returned.
+fn run_function_body(
+    statements: &[BaseExpr<()>],
+    mut error: Option<Error>,
+    env: &mut Environment,
+    context: &mut Context,
+) -> Result<Option<Value>, Error> {
+    // No return statement was found, thus return empty
+    let mut return_value = None;
+    let statements = match error {
+        None => statements,
+        Some(_) => &[],
+    };
+
+    // Run all sub statements
+    for base_expression in statements.iter() {
+        let span = base_expression.span;
+
+        match interpret_base_expr(base_expression, env, context) {
+            Ok(InterpretationResult::Return { value }) => {
+                return_value = value;
+                break;
+            }
+            Ok(InterpretationResult::Break) => {
+                error = Some(Error::LocationError {
+                    message: format!("Cannot break out of a function"),
+                    span,
+                });
+                break;
+            }
+            Ok(InterpretationResult::Continue) => {
+                error = Some(Error::LocationError {
+                    message: "Cannot continue outside of a loop".to_string(),
+                    span,
+                });
+                break;
+            }
+            Ok(InterpretationResult::Empty) => {}
+            Err(e) => {
+                context.record_post_mortem(env, &e, span.row);
+                error = Some(e);
+                break;
+            }
+        }
+    }
+
+    // Deferred expressions run in reverse order within the scope of the function,
+    // also when the function stopped with an error, but not when the debugger quit.
+    // The first error is reported.
+    let deferred = context.deferred.pop().unwrap();
+    let deferred: &[RecExpr<()>] = match context.quitting {
+        true => &[],
+        false => &deferred,
+    };
+    for expression in deferred.iter().rev() {
+        match interpret_expr(expression, env, context) {
+            Ok(_) => {}
+            Err(e) => {
+                if error.is_none() {
+                    error = Some(e);
+                }
+            }
+        }
+    }
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(return_value),
+    }
+}
+
+// Whether the statements yield, which makes the function they belong to a generator.
+// Yields in nested function definitions belong to those functions instead.
+fn contains_yield(base_expressions: &[BaseExpr<()>]) -> bool {
+    base_expressions
+        .iter()
+        .any(|base_expression| match &base_expression.data {
+            BaseExprData::Yield { .. } => true,
+            BaseExprData::IfStatement {
+                body,
+                else_statement,
+                ..
+            }
+            | BaseExprData::ElseIfStatement {
+                body,
+                else_statement,
+                ..
+            } => {
+                contains_yield(body)
+                    || else_statement
+                        .as_ref()
+                        .is_some_and(|else_statement| contains_yield(std::slice::from_ref(&**else_statement)))
+            }
+            BaseExprData::ElseStatement { body }
+            | BaseExprData::ForLoop { body, .. }
+            | BaseExprData::WhileLoop { body, .. }
+            | BaseExprData::With { body, .. } => contains_yield(body),
+            BaseExprData::Match { arms, .. } => arms.iter().any(|arm| contains_yield(&arm.body)),
+            _ => false,
+        })
+}
+
+// Whether the value matches the pattern of a case, a value of another type than a literal never matches it
+fn pattern_matches(pattern: &Pattern, value: &Value) -> bool {
+    match (pattern, value) {
+        (Pattern::Number(number), Value::Number(value)) => number == value,
+        (Pattern::String(string), Value::String(value)) => string == value.as_str(),
+        (Pattern::Boolean(boolean), Value::Bool(value)) => boolean == value,
+        (Pattern::Binding(_) | Pattern::Wildcard, _) => true,
+        _ => false,
+    }
+}
+
+// Removes the innermost binding with the given name, returns whether one was found
+fn remove_from_env(name: &String, env: &mut Environment) -> bool {
+    for scope in env.iter_mut().rev() {
+        match scope.iter().position(|binding| binding.name == *name) {
+            Some(index) => {
+                scope.remove(index);
+                scope.shrink_to_fit();
+                return true;
+            }
+            None => {}
+        }
     }
+    return false;
+}
+
+// The scope index and the index in that scope of the innermost binding with the given name
+fn find_position_in_env(name: &String, env: &Environment) -> Option<(usize, usize)> {
+    for (scope_index, scope) in env.iter().enumerate().rev() {
+        if let Some(binding_index) = scope.iter().position(|binding| binding.name == *name) {
+            return Some((scope_index, binding_index));
+        }
+    }
+    None
+}
+
+// Exchanges the values of the innermost bindings with the given names in place,
+// returns whether both were found
+fn swap_in_env(first_name: &String, second_name: &String, env: &mut Environment) -> bool {
+    let (Some(first), Some(second)) = (
+        find_position_in_env(first_name, env),
+        find_position_in_env(second_name, env),
+    ) else {
+        return false;
+    };
+
+    // Order the positions so the environment can be split into two mutable halves between them
+    let (low, high) = match first < second {
+        true => (first, second),
+        false => (second, first),
+    };
+    if low == high {
+        return true;
+    }
+
+    if low.0 == high.0 {
+        let (before, after) = env[low.0].split_at_mut(high.1);
+        std::mem::swap(&mut before[low.1].value, &mut after[0].value);
+    } else {
+        let (outer, inner) = env.split_at_mut(high.0);
+        std::mem::swap(
+            &mut outer[low.0][low.1].value,
+            &mut inner[0][high.1].value,
+        );
+    }
+    true
 }
 
 fn update_in_env(value: &Value, name: &String, env: &mut Environment) -> bool {
@@ -1400,6 +3842,176 @@ fn update_or_add_in_scope(value: &Value, name: &String, scope: &mut Scope) {
     });
 }
 
+// Specialized handlers for the patterns which are most common in loops.
+// They give the same result as the general path but skip its cloning and matching on every kind of value,
+// and give None for anything they do not cover, which then takes the general path.
+
+// Handles x = x + 1 and x = x + i on integers and list = list + [element], which are also what x += 1 desugars to,
+// by updating the binding in place instead of cloning its value to compute the new one
+fn assign_in_place(
+    var_name: &String,
+    expr: &RecExpr<()>,
+    env: &mut Environment,
+    context: &mut Context,
+) -> Option<Result<InterpretationResult, Error>> {
+    let RecExprData::Add { left, right } = &expr.data else {
+        return None;
+    };
+    if !matches!(&left.data, RecExprData::Variable { name } if name == var_name) {
+        return None;
+    }
+
+    // An assignment only updates the innermost scope, a binding in an outer scope is shadowed instead
+    let binding = env.last()?.iter().find(|binding| binding.name == *var_name)?;
+    match &binding.value {
+        Value::Number(_) => {
+            let number = integer_operand(right, env)?;
+            if let Some(Value::Number(value)) = find_in_innermost_scope_mut(var_name, env) {
+                *value += number;
+            }
+            Some(Ok(InterpretationResult::Empty))
+        }
+        // The list is read before the right side is evaluated, which could assign to it
+        Value::List(_) if !contains_assignment(right) => {
+            let right_value = match interpret_expr(right, env, context) {
+                Ok(right_value) => right_value,
+                Err(e) => return Some(Err(e)),
+            };
+
+            match (find_in_innermost_scope_mut(var_name, env), right_value) {
+                (Some(Value::List(elements)), Some(Value::List(right_elements))) => elements.extend(right_elements),
+                (Some(Value::List(elements)), Some(value)) => elements.push(value),
+                // Such as an empty right side, which gives the same error as the general path
+                (_, right_value) => {
+                    let left_value = find_in_env(var_name, env);
+                    return match add(&left_value, &right_value, expr.span) {
+                        Ok(Some(value)) => {
+                            update_or_add_in_scope(&value, var_name, env.last_mut().unwrap());
+                            Some(Ok(InterpretationResult::Empty))
+                        }
+                        Ok(None) => Some(Err(Error::located("Cannot assign to empty".to_string(), expr.span()))),
+                        Err(e) => Some(Err(e)),
+                    };
+                }
+            }
+            context.record_allocation(Some(&Value::List(Vec::new())));
+            Some(Ok(InterpretationResult::Empty))
+        }
+        _ => None,
+    }
+}
+
+// Handles comparisons of two integers such as the loop condition i < n,
+// which only need to look at the bindings instead of cloning them
+fn compare_integers(expr: &RecExpr<()>, env: &Environment) -> Option<bool> {
+    let (left, right) = match &expr.data {
+        RecExprData::LessThan { left, right }
+        | RecExprData::LessThanOrEqual { left, right }
+        | RecExprData::GreaterThan { left, right }
+        | RecExprData::GreaterThanOrEqual { left, right }
+        | RecExprData::Equals { left, right }
+        | RecExprData::NotEquals { left, right } => (left, right),
+        _ => return None,
+    };
+
+    let left = integer_operand(left, env)?;
+    let right = integer_operand(right, env)?;
+
+    match &expr.data {
+        RecExprData::LessThan { .. } => Some(left < right),
+        RecExprData::LessThanOrEqual { .. } => Some(left <= right),
+        RecExprData::GreaterThan { .. } => Some(left > right),
+        RecExprData::GreaterThanOrEqual { .. } => Some(left >= right),
+        RecExprData::Equals { .. } => Some(left == right),
+        RecExprData::NotEquals { .. } => Some(left != right),
+        _ => None,
+    }
+}
+
+// The value of an integer literal or a variable bound to an integer
+fn integer_operand(operand: &RecExpr<()>, env: &Environment) -> Option<i64> {
+    match &operand.data {
+        RecExprData::Number { number } => Some(*number),
+        RecExprData::Variable { name } => match find_ref_in_env(name, env) {
+            Some(Value::Number(number)) => Some(*number),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn contains_assignment(expr: &RecExpr<()>) -> bool {
+    struct AssignmentFinder {
+        found: bool,
+    }
+
+    impl Visitor<()> for AssignmentFinder {
+        fn visit_rec_expr(&mut self, rec_expr: &RecExpr<()>) {
+            if let RecExprData::Assign { .. } = rec_expr.data {
+                self.found = true;
+            }
+            visitor::walk_rec_expr(self, rec_expr);
+        }
+    }
+
+    let mut finder = AssignmentFinder { found: false };
+    finder.visit_rec_expr(expr);
+    finder.found
+}
+
+// A call can leave out the parameters which have a default value
+fn accepts_argument_count(args: &[Parameter<()>], count: usize) -> bool {
+    let required = args.iter().filter(|arg| arg.default.is_none() && !arg.rest).count();
+    count >= required && (count <= args.len() || parser::rest_position(args).is_some())
+}
+
+// The number of arguments a function takes, for errors about calls giving another number
+fn expected_arguments(args: &[Parameter<()>]) -> String {
+    let required = args.iter().filter(|arg| arg.default.is_none() && !arg.rest).count();
+    match parser::rest_position(args) {
+        Some(_) => format!("at least {} arguments", required),
+        None if required == args.len() => format!("{} arguments", args.len()),
+        None => format!("{} to {} arguments", required, args.len()),
+    }
+}
+
+// The bindings a function defined in the current function captures: the local variables its body and
+// default values use. Functions defined at the top level capture nothing, they read the globals when they are called.
+fn capture_bindings(args: &[Parameter<()>], definition: &[BaseExpr<()>], env: &Environment) -> Vec<(String, Value)> {
+    if env.len() <= 1 {
+        return Vec::new();
+    }
+
+    let mut captured = Vec::new();
+    for name in visitor::referenced_names(definition) {
+        if args.iter().any(|arg| arg.name == name) {
+            continue;
+        }
+        if let Some(value) = env[1..].iter().rev().find_map(|scope| find_in_scope(&name, scope)) {
+            captured.push((name, value));
+        }
+    }
+    captured
+}
+
+fn find_in_innermost_scope_mut<'a>(name: &String, env: &'a mut Environment) -> Option<&'a mut Value> {
+    env.last_mut()?
+        .iter_mut()
+        .find(|binding| binding.name == *name)
+        .map(|binding| &mut binding.value)
+}
+
+// For reading the value, so a frozen value gives the value inside
+fn find_ref_in_env<'a>(name: &String, env: &'a Environment) -> Option<&'a Value> {
+    env.iter()
+        .rev()
+        .find_map(|scope| scope.iter().find(|binding| binding.name == *name))
+        .map(|binding| match &binding.value {
+            Value::Frozen(value) => &**value,
+            value => value,
+        })
+}
+
 fn find_in_env(name: &String, env: &Environment) -> Option<Value> {
     for scope in env.iter().rev() {
         match find_in_scope(name, scope) {
@@ -0,0 +1,930 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use cranelift_codegen::ir;
+use cranelift_codegen::ir::condcodes::IntCC;
+use cranelift_codegen::ir::types::I64;
+use cranelift_codegen::ir::AbiParam;
+use cranelift_codegen::ir::Block;
+use cranelift_codegen::ir::InstBuilder;
+use cranelift_codegen::ir::MemFlags;
+use cranelift_codegen::ir::UserFuncName;
+use cranelift_codegen::settings;
+use cranelift_codegen::settings::Configurable;
+use cranelift_frontend::FunctionBuilder;
+use cranelift_frontend::FunctionBuilderContext;
+use cranelift_frontend::Variable;
+use cranelift_jit::JITBuilder;
+use cranelift_jit::JITModule;
+use cranelift_module::FuncId;
+use cranelift_module::Linkage;
+use cranelift_module::Module;
+
+use crate::desugarer;
+use crate::interpreter::Value;
+use crate::language::LanguageOptions;
+use crate::parser::BaseExpr;
+use crate::parser::BaseExprData;
+use crate::parser::RecExpr;
+use crate::parser::RecExprData;
+use crate::tac;
+use crate::tac::BinOp;
+use crate::tac::ComparisonOp;
+use crate::tac::TacInstruction;
+use crate::tac::TacValue;
+use crate::tac::UnOp;
+use crate::tac::VariableValue;
+use crate::tacformat;
+use crate::tokenizer::Span;
+use crate::typechecker;
+use crate::typechecker::FunctionType;
+use crate::typechecker::Type;
+use crate::uniquify;
+use crate::verifier;
+use crate::visitor;
+use crate::visitor::Visitor;
+
+// Compiles the functions a running program calls often to native code with Cranelift, for rosy run --jit.
+//
+// Once a function has been called HOT_CALL_COUNT times (counted by Context::record_function_call), the
+// functions of the program are typechecked the way the compiler does it for a call with the types of the
+// arguments. That gives the instance of the function for those types and of the functions it calls, which
+// are turned into three address code and from there into native code.
+//
+// Only functions working on integers and booleans, without globals, are compiled. Everything else is
+// interpreted as before, and so is a compiled call which cannot finish the way the interpreter would:
+// a division by zero or a very deep recursion gives up on the call, which is then run by the interpreter
+// to get the same error. Compiled functions have no side effects, so running them again is not noticed.
+
+// The number of calls after which a function is compiled
+pub const HOT_CALL_COUNT: usize = 100;
+// Compiled calls deeper than this give up, since native frames do not get more stack like the interpreter
+const MAX_CALL_DEPTH: i64 = 1000;
+// The stack that must be left to compile a function and make a compiled call, enough for typechecking
+// and Cranelift in debug builds and for MAX_CALL_DEPTH native frames
+const STACK_RED_ZONE: usize = 2 * 1024 * 1024;
+const STACK_SEGMENT_SIZE: usize = 4 * 1024 * 1024;
+
+// A compiled call gets a pointer to two words: a status which is set to STATUS_GAVE_UP when the call
+// cannot be finished, and the depth of the compiled calls
+const STATUS_OFFSET: i32 = 0;
+const DEPTH_OFFSET: i32 = 8;
+const STATUS_GAVE_UP: i64 = 1;
+
+// The entry point of a compiled function as called from the interpreter, taking a pointer to the
+// arguments and a pointer to the status and depth words
+type EntryFunction = extern "C" fn(*const i64, *mut i64) -> i64;
+
+struct CompiledFunction {
+    entry: EntryFunction,
+    return_type: Type,
+}
+
+// A function instance: the name of the function and the types of its arguments
+type Instance = (String, Vec<Type>);
+
+pub struct Jit {
+    // The function and struct definitions at the top level of the program, desugared
+    definitions: Vec<BaseExpr<()>>,
+    language: LanguageOptions,
+    // Names defined more than once (or inside of another function) may be bound to different bodies
+    // while the program runs, so only the functions defined once at the top level are compiled
+    top_level_functions: HashSet<String>,
+    module: Option<JITModule>,
+    // The compiled instances, None for the instances which cannot be compiled
+    compiled: Vec<(Instance, Option<CompiledFunction>)>,
+    // The labels of the instances are only unique within one typechecked program, so the functions of
+    // every compilation get their own prefix in the module
+    compilations: usize,
+}
+
+impl Jit {
+    pub fn new(base_expressions: &[BaseExpr<()>], language: &LanguageOptions) -> Jit {
+        let mut definition_counts = HashMap::new();
+        count_function_definitions(base_expressions, &mut definition_counts);
+        let top_level_functions = base_expressions
+            .iter()
+            .filter_map(|base_expr| match &base_expr.data {
+                BaseExprData::FunctionDefinition { fun_name, .. }
+                    if definition_counts.get(fun_name) == Some(&1) =>
+                {
+                    Some(fun_name.clone())
+                }
+                _ => None,
+            })
+            .collect();
+        let definitions = base_expressions
+            .iter()
+            .filter(|base_expr| {
+                matches!(
+                    base_expr.data,
+                    BaseExprData::FunctionDefinition { .. } | BaseExprData::StructDefinition { .. }
+                )
+            })
+            .cloned()
+            .collect();
+
+        Jit {
+            definitions: desugarer::desugar(definitions),
+            language: language.clone(),
+            top_level_functions,
+            module: native_module(),
+            compiled: Vec::new(),
+            compilations: 0,
+        }
+    }
+
+    // Runs the call as native code when the function is hot and can be compiled.
+    // None means the call has to be interpreted.
+    pub fn call(&mut self, function_name: &str, calls_so_far: usize, arg_values: &[Value]) -> Option<Value> {
+        if calls_so_far < HOT_CALL_COUNT || !self.top_level_functions.contains(function_name) {
+            return None;
+        }
+
+        // The interpreter may already be deep into the stack when a function becomes hot
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || {
+            self.compile_and_call(function_name, arg_values)
+        })
+    }
+
+    fn compile_and_call(&mut self, function_name: &str, arg_values: &[Value]) -> Option<Value> {
+        let mut arg_types = Vec::new();
+        let mut args = Vec::new();
+        for value in arg_values {
+            match value {
+                Value::Number(number) => {
+                    arg_types.push(Type::Integer);
+                    args.push(*number);
+                }
+                Value::Bool(boolean) => {
+                    arg_types.push(Type::Boolean);
+                    args.push(*boolean as i64);
+                }
+                _ => return None,
+            }
+        }
+
+        let instance = (function_name.to_string(), arg_types);
+        if self.find_compiled(&instance).is_none() {
+            self.compile(&instance);
+        }
+        let compiled = self.find_compiled(&instance)?.as_ref()?;
+
+        let mut state = [0i64; 2];
+        let result = (compiled.entry)(args.as_ptr(), state.as_mut_ptr());
+        if state[STATUS_OFFSET as usize / 8] == STATUS_GAVE_UP {
+            return None;
+        }
+
+        match compiled.return_type {
+            Type::Boolean => Some(Value::Bool(result != 0)),
+            _ => Some(Value::Number(result)),
+        }
+    }
+
+    fn find_compiled(&self, instance: &Instance) -> Option<&Option<CompiledFunction>> {
+        self.compiled
+            .iter()
+            .find(|(compiled_instance, _)| compiled_instance == instance)
+            .map(|(_, compiled)| compiled)
+    }
+
+    // Compiles the instance and the instances it calls, or remembers that it cannot be compiled
+    fn compile(&mut self, instance: &Instance) {
+        let _span = tracing::debug_span!("jit").entered();
+        match self.compile_instance(instance) {
+            Ok(compiled) => {
+                for (compiled_instance, function) in compiled {
+                    tracing::debug!("Compiled {}({})", compiled_instance.0, type_names(&compiled_instance.1));
+                    if self.find_compiled(&compiled_instance).is_none() {
+                        self.compiled.push((compiled_instance, Some(function)));
+                    }
+                }
+            }
+            Err(message) => {
+                tracing::debug!("{}({}) cannot be compiled: {}", instance.0, type_names(&instance.1), message);
+                self.compiled.push((instance.clone(), None));
+            }
+        }
+    }
+
+    fn compile_instance(&mut self, instance: &Instance) -> Result<Vec<(Instance, CompiledFunction)>, String> {
+        let functions = self.typecheck_call(instance)?;
+        let index = functions
+            .iter()
+            .position(|function| function.name == instance.0 && function.param_types == instance.1)
+            .ok_or("The typechecker made no instance for the call")?;
+
+        let group = compilation_group(&functions, &self.top_level_functions, index)?;
+        let instances: Vec<&FunctionType> = group.iter().map(|index| &functions[*index]).collect();
+        let instructions =
+            tac::generate_function_tac(&instances, &functions).map_err(|error| format!("{:?}", error))?;
+        verifier::verify(
+            "generating three address code",
+            instructions.as_slice(),
+            verifier::check_tac_instructions,
+        )
+        .map_err(|error| format!("{:?}", error))?;
+
+        self.compilations += 1;
+        let prefix = format!("compilation{}_", self.compilations);
+        let entries = define_functions(&mut self.module, &prefix, &instructions)?;
+
+        Ok(instances
+            .iter()
+            .zip(entries)
+            .map(|(function, entry)| {
+                let compiled = CompiledFunction {
+                    entry,
+                    return_type: function.return_type.clone(),
+                };
+                ((function.name.clone(), function.param_types.clone()), compiled)
+            })
+            .collect())
+    }
+
+    // The function instances of the program for a call to the instance, after the passes the compiler runs
+    // before generating code. Only the definitions are checked, as the functions that can be compiled do
+    // not use the globals.
+    fn typecheck_call(&self, instance: &Instance) -> Result<Vec<FunctionType>, String> {
+        // An empty span on the row after the definitions
+        let span = match self.definitions.last() {
+            Some(definition) => Span {
+                row: definition.span.row_end + 1,
+                col_start: 0,
+                row_end: definition.span.row_end + 1,
+                col_end: 0,
+                byte_start: definition.span.byte_end + 1,
+                byte_end: definition.span.byte_end + 1,
+            },
+            None => Span::default(),
+        };
+        let args = instance
+            .1
+            .iter()
+            .map(|arg_type| {
+                let data = match arg_type {
+                    Type::Boolean => RecExprData::Boolean { value: false },
+                    _ => RecExprData::Number { number: 0 },
+                };
+                RecExpr::new(data, span, ())
+            })
+            .collect();
+        let call = RecExpr::new(
+            RecExprData::FunctionCall {
+                function_name: instance.0.clone(),
+                args,
+            },
+            span,
+            (),
+        );
+
+        let mut program = self.definitions.clone();
+        program.push(BaseExpr::new(BaseExprData::Simple { expr: call }, span, ()));
+        let mut typed_program = typechecker::type_check_program_with_options(program, false, &self.language)
+            .map_err(|error| format!("{:?}", error))?;
+        tac::check_supported_before_uniquify(&typed_program).map_err(|error| format!("{:?}", error))?;
+        uniquify::uniquify(&mut typed_program);
+        Ok(typed_program.1)
+    }
+}
+
+fn type_names(types: &[Type]) -> String {
+    types.iter().map(typechecker::type_to_string).collect::<Vec<String>>().join(", ")
+}
+
+fn count_function_definitions(body: &[BaseExpr<()>], definitions: &mut HashMap<String, usize>) {
+    struct DefinitionCounter<'a> {
+        definitions: &'a mut HashMap<String, usize>,
+    }
+    impl Visitor<()> for DefinitionCounter<'_> {
+        fn visit_base_expr(&mut self, base_expr: &BaseExpr<()>) {
+            if let BaseExprData::FunctionDefinition { fun_name, .. } = &base_expr.data {
+                *self.definitions.entry(fun_name.clone()).or_insert(0) += 1;
+            }
+            visitor::walk_base_expr(self, base_expr);
+        }
+    }
+    DefinitionCounter { definitions }.visit_body(body);
+}
+
+// The instances which have to be compiled to compile the instance with the given index: itself and the
+// instances it calls, directly or through other instances
+fn compilation_group(
+    functions: &[FunctionType],
+    top_level_functions: &HashSet<String>,
+    index: usize,
+) -> Result<Vec<usize>, String> {
+    let mut group = Vec::from([index]);
+    let mut next = 0;
+    while next < group.len() {
+        let function = &functions[group[next]];
+        let callees = compilable_function_callees(functions, top_level_functions, function).ok_or(format!(
+            "{}({}) uses something that cannot be compiled",
+            function.name,
+            type_names(&function.param_types)
+        ))?;
+        for callee in callees {
+            if !group.contains(&callee) {
+                group.push(callee);
+            }
+        }
+        next += 1;
+    }
+    Ok(group)
+}
+
+// Turns the functions in the three address code into native code, giving the entry point of each
+fn define_functions(
+    module: &mut Option<JITModule>,
+    prefix: &str,
+    instructions: &[TacInstruction],
+) -> Result<Vec<EntryFunction>, String> {
+    let Some(jit_module) = module.as_mut() else {
+        return Err("The JIT is not supported on this machine".to_string());
+    };
+
+    let mut bodies = Vec::new();
+    for (index, instruction) in instructions.iter().enumerate() {
+        if let TacInstruction::FunctionLabel(label, params) = instruction {
+            let end = instructions[index + 1..]
+                .iter()
+                .position(|instruction| matches!(instruction, TacInstruction::FunctionLabel(..)))
+                .map_or(instructions.len(), |length| index + 1 + length);
+            bodies.push((label, params, &instructions[index + 1..end]));
+        }
+    }
+
+    // Everything is translated before anything is defined, so that a function which cannot be
+    // translated does not leave functions in the module which call it
+    let mut declared = HashMap::new();
+    let mut function_builder_context = FunctionBuilderContext::new();
+    let mut translated = Vec::new();
+    for (label, params, body) in bodies.iter() {
+        let signature = function_signature(jit_module, params.len());
+        let id = declare(jit_module, &mut declared, prefix, label, &signature)?;
+        let mut function = ir::Function::with_name_signature(UserFuncName::user(0, id.as_u32()), signature);
+        FunctionTranslator::translate(
+            jit_module,
+            &mut declared,
+            prefix,
+            &mut function,
+            &mut function_builder_context,
+            params,
+            body,
+        )?;
+        translated.push((id, function));
+    }
+
+    let mut entry_ids = Vec::new();
+    for (label, params, _) in bodies.iter() {
+        let signature = entry_signature(jit_module);
+        let id = declare(jit_module, &mut declared, prefix, &format!("{}_entry", label), &signature)?;
+        let mut function = ir::Function::with_name_signature(UserFuncName::user(0, id.as_u32()), signature);
+        translate_entry(
+            jit_module,
+            declared[label.as_str()],
+            &mut function,
+            &mut function_builder_context,
+            params.len(),
+        );
+        translated.push((id, function));
+        entry_ids.push(id);
+    }
+
+    let mut context = jit_module.make_context();
+    for (id, function) in translated {
+        context.func = function;
+        if let Err(error) = jit_module.define_function(id, &mut context) {
+            // The module may now hold part of the functions, so nothing more is compiled into it
+            *module = None;
+            return Err(format!("Cranelift could not compile the function: {:?}", error));
+        }
+        jit_module.clear_context(&mut context);
+    }
+    if let Err(error) = jit_module.finalize_definitions() {
+        *module = None;
+        return Err(format!("Cranelift could not link the functions: {:?}", error));
+    }
+
+    Ok(entry_ids
+        .into_iter()
+        .map(|id| {
+            let code = jit_module.get_finalized_function(id);
+            // The entry was built with the signature of EntryFunction, in the calling convention of the host
+            unsafe { std::mem::transmute::<*const u8, EntryFunction>(code) }
+        })
+        .collect())
+}
+
+fn native_module() -> Option<JITModule> {
+    let mut flag_builder = settings::builder();
+    flag_builder.set("opt_level", "speed").ok()?;
+    flag_builder.set("use_colocated_libcalls", "false").ok()?;
+    flag_builder.set("is_pic", "false").ok()?;
+    let isa = cranelift_native::builder()
+        .ok()?
+        .finish(settings::Flags::new(flag_builder))
+        .ok()?;
+    Some(JITModule::new(JITBuilder::with_isa(
+        isa,
+        cranelift_module::default_libcall_names(),
+    )))
+}
+
+// A compiled function takes its arguments followed by the pointer to the status and depth words
+fn function_signature(module: &JITModule, param_count: usize) -> ir::Signature {
+    let mut signature = module.make_signature();
+    for _ in 0..param_count {
+        signature.params.push(AbiParam::new(I64));
+    }
+    signature.params.push(AbiParam::new(module.target_config().pointer_type()));
+    signature.returns.push(AbiParam::new(I64));
+    signature
+}
+
+fn entry_signature(module: &JITModule) -> ir::Signature {
+    let pointer_type = module.target_config().pointer_type();
+    let mut signature = module.make_signature();
+    signature.params.push(AbiParam::new(pointer_type));
+    signature.params.push(AbiParam::new(pointer_type));
+    signature.returns.push(AbiParam::new(I64));
+    signature
+}
+
+fn declare(
+    module: &mut JITModule,
+    declared: &mut HashMap<String, FuncId>,
+    prefix: &str,
+    label: &str,
+    signature: &ir::Signature,
+) -> Result<FuncId, String> {
+    if let Some(id) = declared.get(label) {
+        return Ok(*id);
+    }
+    let id = module
+        .declare_function(&format!("{}{}", prefix, label), Linkage::Local, signature)
+        .map_err(|error| format!("Cannot declare {}: {:?}", label, error))?;
+    declared.insert(label.to_string(), id);
+    Ok(id)
+}
+
+// Loads the arguments from the array the interpreter passes and calls the compiled function
+fn translate_entry(
+    module: &mut JITModule,
+    callee: FuncId,
+    function: &mut ir::Function,
+    function_builder_context: &mut FunctionBuilderContext,
+    param_count: usize,
+) {
+    let mut builder = FunctionBuilder::new(function, function_builder_context);
+    let block = builder.create_block();
+    builder.append_block_params_for_function_params(block);
+    builder.switch_to_block(block);
+    let (args_pointer, state) = (builder.block_params(block)[0], builder.block_params(block)[1]);
+
+    let mut args = Vec::new();
+    for index in 0..param_count {
+        args.push(builder.ins().load(I64, MemFlags::trusted(), args_pointer, (index * 8) as i32));
+    }
+    args.push(state);
+    let callee = module.declare_func_in_func(callee, builder.func);
+    let call = builder.ins().call(callee, &args);
+    let result = builder.inst_results(call)[0];
+    builder.ins().return_(&[result]);
+    builder.seal_all_blocks();
+    builder.finalize();
+}
+
+// Translates the three address code of one function, from the instruction after its FunctionLabel
+struct FunctionTranslator<'a> {
+    builder: FunctionBuilder<'a>,
+    module: &'a mut JITModule,
+    declared: &'a mut HashMap<String, FuncId>,
+    prefix: &'a str,
+    variables: HashMap<String, Variable>,
+    labels: HashMap<String, Block>,
+    state: ir::Value,
+    // Sets the status to STATUS_GAVE_UP and returns
+    give_up: Block,
+}
+
+impl<'a> FunctionTranslator<'a> {
+    fn translate(
+        module: &'a mut JITModule,
+        declared: &'a mut HashMap<String, FuncId>,
+        prefix: &'a str,
+        function: &'a mut ir::Function,
+        function_builder_context: &'a mut FunctionBuilderContext,
+        params: &[String],
+        body: &[TacInstruction],
+    ) -> Result<(), String> {
+        let mut builder = FunctionBuilder::new(function, function_builder_context);
+        let entry = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+        let block_params = builder.block_params(entry).to_vec();
+        let (state, args) = block_params.split_last().unwrap();
+
+        let give_up = builder.create_block();
+        let mut translator = FunctionTranslator {
+            builder,
+            module,
+            declared,
+            prefix,
+            variables: HashMap::new(),
+            labels: HashMap::new(),
+            state: *state,
+            give_up,
+        };
+        for (name, arg) in params.iter().zip(args) {
+            let variable = translator.variable(name);
+            translator.builder.def_var(variable, *arg);
+        }
+        translator.enter();
+
+        for instruction in body {
+            translator.translate_instruction(instruction)?;
+        }
+        // Falling off the end returns nothing, which a compiled function cannot give
+        translator.builder.ins().jump(give_up, &[]);
+
+        let mut builder = translator.builder;
+        builder.switch_to_block(give_up);
+        let status = builder.ins().iconst(I64, STATUS_GAVE_UP);
+        builder.ins().store(MemFlags::trusted(), status, translator.state, STATUS_OFFSET);
+        let zero = builder.ins().iconst(I64, 0);
+        builder.ins().return_(&[zero]);
+
+        builder.seal_all_blocks();
+        builder.finalize();
+        Ok(())
+    }
+
+    // Counts the call in the depth word, giving up when the calls go too deep
+    fn enter(&mut self) {
+        let depth = self.builder.ins().load(I64, MemFlags::trusted(), self.state, DEPTH_OFFSET);
+        let depth = self.builder.ins().iadd_imm(depth, 1);
+        self.builder.ins().store(MemFlags::trusted(), depth, self.state, DEPTH_OFFSET);
+        let too_deep = self.builder.ins().icmp_imm(IntCC::SignedGreaterThan, depth, MAX_CALL_DEPTH);
+        let body = self.builder.create_block();
+        self.builder.ins().brif(too_deep, self.give_up, &[], body, &[]);
+        self.builder.switch_to_block(body);
+    }
+
+    fn variable(&mut self, name: &str) -> Variable {
+        if let Some(variable) = self.variables.get(name) {
+            return *variable;
+        }
+        let variable = Variable::from_u32(self.variables.len() as u32);
+        self.builder.declare_var(variable, I64);
+        self.variables.insert(name.to_string(), variable);
+        variable
+    }
+
+    fn label(&mut self, name: &str) -> Block {
+        if let Some(block) = self.labels.get(name) {
+            return *block;
+        }
+        let block = self.builder.create_block();
+        self.labels.insert(name.to_string(), block);
+        block
+    }
+
+    fn value(&mut self, value: &TacValue) -> Result<ir::Value, String> {
+        match value {
+            TacValue::Constant(number) => Ok(self.builder.ins().iconst(I64, *number)),
+            TacValue::Variable(name) => {
+                let variable = self.variable(name);
+                Ok(self.builder.use_var(variable))
+            }
+            other => Err(format!("Unsupported value {:?}", other)),
+        }
+    }
+
+    fn assign(&mut self, destination: &VariableValue, value: ir::Value) {
+        let name = match destination {
+            VariableValue::Variable(name) | VariableValue::VariableWithRequestedRegister(name, _) => name,
+        };
+        let variable = self.variable(name);
+        self.builder.def_var(variable, value);
+    }
+
+    // Continues in a new block after a jump or return, the code in it can only be reached through a label
+    fn start_unreachable_block(&mut self) {
+        let block = self.builder.create_block();
+        self.builder.switch_to_block(block);
+    }
+
+    // Gives up when the value is not zero, the code after it goes into a new block
+    fn give_up_unless_zero(&mut self, value: ir::Value) {
+        let next = self.builder.create_block();
+        self.builder.ins().brif(value, self.give_up, &[], next, &[]);
+        self.builder.switch_to_block(next);
+    }
+
+    fn translate_instruction(&mut self, instruction: &TacInstruction) -> Result<(), String> {
+        match instruction {
+            TacInstruction::Assign(destination, value) => {
+                let value = self.value(value)?;
+                self.assign(destination, value);
+            }
+            TacInstruction::BinOp(destination, left, operator, right) => {
+                let left = self.value(left)?;
+                let right = self.value(right)?;
+                let result = self.binary_operation(left, *operator, right);
+                self.assign(destination, result);
+            }
+            TacInstruction::UnaryOp(destination, operator, operand) => {
+                let operand = self.value(operand)?;
+                let result = match operator {
+                    UnOp::Neg => self.builder.ins().ineg(operand),
+                    UnOp::Not => {
+                        let is_false = self.builder.ins().icmp_imm(IntCC::Equal, operand, 0);
+                        self.builder.ins().uextend(I64, is_false)
+                    }
+                };
+                self.assign(destination, result);
+            }
+            TacInstruction::Label(name) => {
+                let block = self.label(name);
+                self.builder.ins().jump(block, &[]);
+                self.builder.switch_to_block(block);
+            }
+            TacInstruction::Goto(name) => {
+                let block = self.label(name);
+                self.builder.ins().jump(block, &[]);
+                self.start_unreachable_block();
+            }
+            TacInstruction::CompareAndGoto(left, right, operator, name) => {
+                let left = self.value(left)?;
+                let right = self.value(right)?;
+                let condition = self.builder.ins().icmp(comparison_condition(*operator), left, right);
+                let target = self.label(name);
+                let next = self.builder.create_block();
+                self.builder.ins().brif(condition, target, &[], next, &[]);
+                self.builder.switch_to_block(next);
+            }
+            TacInstruction::Call(label, args, destination) => {
+                // Calls go to functions compiled together with this one, or before it
+                let signature = function_signature(self.module, args.len());
+                let callee = declare(self.module, self.declared, self.prefix, label, &signature)?;
+                self.call(callee, args, destination)?;
+            }
+            TacInstruction::Return(Some(value)) => {
+                let value = self.value(value)?;
+                let depth = self.builder.ins().load(I64, MemFlags::trusted(), self.state, DEPTH_OFFSET);
+                let depth = self.builder.ins().iadd_imm(depth, -1);
+                self.builder.ins().store(MemFlags::trusted(), depth, self.state, DEPTH_OFFSET);
+                self.builder.ins().return_(&[value]);
+                self.start_unreachable_block();
+            }
+            other => {
+                return Err(format!(
+                    "Unsupported instruction {}",
+                    tacformat::instruction_to_text(other)
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn call(
+        &mut self,
+        callee: FuncId,
+        args: &[TacValue],
+        destination: &Option<VariableValue>,
+    ) -> Result<(), String> {
+        let mut arg_values = Vec::new();
+        for arg in args {
+            arg_values.push(self.value(arg)?);
+        }
+        arg_values.push(self.state);
+        let callee = self.module.declare_func_in_func(callee, self.builder.func);
+        let call = self.builder.ins().call(callee, &arg_values);
+        let result = self.builder.inst_results(call)[0];
+
+        // A call which gave up makes its callers give up too
+        let status = self.builder.ins().load(I64, MemFlags::trusted(), self.state, STATUS_OFFSET);
+        self.give_up_unless_zero(status);
+        if let Some(destination) = destination {
+            self.assign(destination, result);
+        }
+        Ok(())
+    }
+
+    fn binary_operation(&mut self, left: ir::Value, operator: BinOp, right: ir::Value) -> ir::Value {
+        let comparison = match operator {
+            BinOp::Add => return self.builder.ins().iadd(left, right),
+            BinOp::Sub => return self.builder.ins().isub(left, right),
+            BinOp::Mul => return self.builder.ins().imul(left, right),
+            // Booleans are 0 or 1
+            BinOp::And => return self.builder.ins().band(left, right),
+            BinOp::Or => return self.builder.ins().bor(left, right),
+            BinOp::Div => {
+                // The interpreter reports a division by zero, and wraps the division of the smallest
+                // integer by -1 where the division instruction would trap
+                let is_zero = self.builder.ins().icmp_imm(IntCC::Equal, right, 0);
+                self.give_up_unless_zero(is_zero);
+                let is_minus_one = self.builder.ins().icmp_imm(IntCC::Equal, right, -1);
+                let one = self.builder.ins().iconst(I64, 1);
+                let divisor = self.builder.ins().select(is_minus_one, one, right);
+                let quotient = self.builder.ins().sdiv(left, divisor);
+                let negated = self.builder.ins().ineg(left);
+                return self.builder.ins().select(is_minus_one, negated, quotient);
+            }
+            BinOp::Eq => ComparisonOp::Eq,
+            BinOp::Ne => ComparisonOp::Ne,
+            BinOp::Lt => ComparisonOp::Lt,
+            BinOp::Le => ComparisonOp::Le,
+            BinOp::Gt => ComparisonOp::Gt,
+            BinOp::Ge => ComparisonOp::Ge,
+        };
+        let condition = self.builder.ins().icmp(comparison_condition(comparison), left, right);
+        self.builder.ins().uextend(I64, condition)
+    }
+}
+
+fn comparison_condition(operator: ComparisonOp) -> IntCC {
+    match operator {
+        ComparisonOp::Eq => IntCC::Equal,
+        ComparisonOp::Ne => IntCC::NotEqual,
+        ComparisonOp::Lt => IntCC::SignedLessThan,
+        ComparisonOp::Le => IntCC::SignedLessThanOrEqual,
+        ComparisonOp::Gt => IntCC::SignedGreaterThan,
+        ComparisonOp::Ge => IntCC::SignedGreaterThanOrEqual,
+    }
+}
+
+// The instances the function calls, or None if the function cannot be compiled. A function can be compiled
+// when it only works on integers and booleans, returns one of them on every path the compiler supports, and
+// only reads its parameters and the variables it assigned before. A compiled for loop keeps counting on its
+// variable and checks its limit again every time, so the body must not assign either of them, and the
+// variable is not read after the loop where the interpreter would have left it at a different value.
+fn compilable_function_callees(
+    functions: &[FunctionType],
+    top_level_functions: &HashSet<String>,
+    function: &FunctionType,
+) -> Option<Vec<usize>> {
+    let is_value_type = |value_type: &Type| matches!(value_type, Type::Integer | Type::Boolean);
+    if !is_value_type(&function.return_type) || !function.param_types.iter().all(is_value_type) {
+        return None;
+    }
+
+    let mut checker = FunctionChecker {
+        functions,
+        top_level_functions,
+        callees: Vec::new(),
+    };
+    let mut defined: HashSet<String> = function.param_names.iter().cloned().collect();
+    checker.check_body(&function.content, &mut defined)?;
+    Some(checker.callees)
+}
+
+struct FunctionChecker<'a> {
+    functions: &'a [FunctionType],
+    top_level_functions: &'a HashSet<String>,
+    callees: Vec<usize>,
+}
+
+impl FunctionChecker<'_> {
+    // Variables assigned in a block are only known to be assigned within it
+    fn check_body(&mut self, body: &[BaseExpr<Type>], defined: &mut HashSet<String>) -> Option<()> {
+        for base_expr in body {
+            self.check_base_expr(base_expr, defined)?;
+        }
+        Some(())
+    }
+
+    fn check_base_expr(&mut self, base_expr: &BaseExpr<Type>, defined: &mut HashSet<String>) -> Option<()> {
+        match &base_expr.data {
+            BaseExprData::Simple { expr } => self.check_rec_expr(expr, defined),
+            BaseExprData::VariableAssignment { var_name, expr } => {
+                self.check_rec_expr(expr, defined)?;
+                defined.insert(var_name.clone());
+                Some(())
+            }
+            BaseExprData::IfStatement {
+                condition,
+                body,
+                else_statement,
+            }
+            | BaseExprData::ElseIfStatement {
+                condition,
+                body,
+                else_statement,
+            } => {
+                self.check_rec_expr(condition, defined)?;
+                self.check_body(body, &mut defined.clone())?;
+                match else_statement {
+                    Some(else_statement) => self.check_base_expr(else_statement, &mut defined.clone()),
+                    None => Some(()),
+                }
+            }
+            BaseExprData::ElseStatement { body } => self.check_body(body, &mut defined.clone()),
+            BaseExprData::WhileLoop { condition, body } => {
+                self.check_rec_expr(condition, defined)?;
+                self.check_body(body, &mut defined.clone())
+            }
+            BaseExprData::ForLoop { var_name, until, body } => {
+                self.check_rec_expr(until, defined)?;
+                let mut limit_variables = HashSet::new();
+                read_variables(until, &mut limit_variables);
+                let mut assigned = HashSet::new();
+                assigned_variables(body, &mut assigned);
+                if assigned.contains(var_name) || !assigned.is_disjoint(&limit_variables) {
+                    return None;
+                }
+
+                let mut body_defined = defined.clone();
+                body_defined.insert(var_name.clone());
+                self.check_body(body, &mut body_defined)?;
+                defined.remove(var_name);
+                Some(())
+            }
+            BaseExprData::Return {
+                return_value: Some(return_value),
+            } => self.check_rec_expr(return_value, defined),
+            _ => None,
+        }
+    }
+
+    fn check_rec_expr(&mut self, rec_expr: &RecExpr<Type>, defined: &HashSet<String>) -> Option<()> {
+        if !matches!(rec_expr.generic_data, Type::Integer | Type::Boolean) {
+            return None;
+        }
+
+        match &rec_expr.data {
+            RecExprData::Number { .. } | RecExprData::Boolean { .. } => Some(()),
+            RecExprData::Variable { name } => defined.contains(name).then_some(()),
+            RecExprData::Add { left, right }
+            | RecExprData::Subtract { left, right }
+            | RecExprData::Multiply { left, right }
+            | RecExprData::Divide { left, right }
+            | RecExprData::Equals { left, right }
+            | RecExprData::NotEquals { left, right }
+            | RecExprData::LessThan { left, right }
+            | RecExprData::LessThanOrEqual { left, right }
+            | RecExprData::GreaterThan { left, right }
+            | RecExprData::GreaterThanOrEqual { left, right }
+            | RecExprData::And { left, right }
+            | RecExprData::Or { left, right } => {
+                self.check_rec_expr(left, defined)?;
+                self.check_rec_expr(right, defined)
+            }
+            RecExprData::Not { right } => self.check_rec_expr(right, defined),
+            RecExprData::FunctionCall { function_name, args } => {
+                // A parameter or variable with the name of a function hides the function
+                if defined.contains(function_name) || !self.top_level_functions.contains(function_name) {
+                    return None;
+                }
+                for arg in args {
+                    self.check_rec_expr(arg, defined)?;
+                }
+                let arg_types: Vec<Type> = args.iter().map(|arg| arg.generic_data.clone()).collect();
+                let callee = self.functions.iter().position(|function| {
+                    &function.name == function_name && function.param_types == arg_types
+                })?;
+                if !self.callees.contains(&callee) {
+                    self.callees.push(callee);
+                }
+                Some(())
+            }
+            _ => None,
+        }
+    }
+}
+
+fn read_variables(rec_expr: &RecExpr<Type>, variables: &mut HashSet<String>) {
+    struct VariableCollector<'a> {
+        variables: &'a mut HashSet<String>,
+    }
+    impl Visitor<Type> for VariableCollector<'_> {
+        fn visit_rec_expr(&mut self, rec_expr: &RecExpr<Type>) {
+            if let RecExprData::Variable { name } = &rec_expr.data {
+                self.variables.insert(name.clone());
+            }
+            visitor::walk_rec_expr(self, rec_expr);
+        }
+    }
+    VariableCollector { variables }.visit_rec_expr(rec_expr);
+}
+
+fn assigned_variables(body: &[BaseExpr<Type>], variables: &mut HashSet<String>) {
+    struct AssignmentCollector<'a> {
+        variables: &'a mut HashSet<String>,
+    }
+    impl Visitor<Type> for AssignmentCollector<'_> {
+        fn visit_base_expr(&mut self, base_expr: &BaseExpr<Type>) {
+            match &base_expr.data {
+                BaseExprData::VariableAssignment { var_name, .. }
+                | BaseExprData::ForLoop { var_name, .. } => {
+                    self.variables.insert(var_name.clone());
+                }
+                _ => {}
+            }
+            visitor::walk_base_expr(self, base_expr);
+        }
+    }
+    AssignmentCollector { variables }.visit_body(body);
+}
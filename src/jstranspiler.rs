@@ -0,0 +1,846 @@
+use std::collections::HashMap;
+
+use crate::builtins;
+use crate::parser::BaseExpr;
+use crate::parser::BaseExprData;
+use crate::parser::MatchArm;
+use crate::parser::Pattern;
+use crate::parser::RecExpr;
+use crate::parser::RecExprData;
+use crate::stack;
+use crate::tokenizer::Error;
+use crate::tokenizer::Span;
+use crate::transpiler;
+use crate::transpiler::string_literal;
+use crate::transpiler::WrittenFunction;
+use crate::transpiler::NAME_PLACEHOLDER;
+use crate::typechecker;
+use crate::typechecker::FunctionType;
+use crate::typechecker::Type;
+use crate::visitor;
+use crate::visitor::Visitor;
+
+// Writes a typechecked program as JavaScript, so that it can run in a web page or under node.
+// The types decide how a value is written where JavaScript differs from rosy: integer division is truncated,
+// floats are printed with a decimal point and dicts become Maps. Lists, dicts and structs are values in rosy,
+// so an assignment to an element or field replaces the variable with a changed copy instead of changing
+// the object other variables may share. Integers are JavaScript numbers, exact up to 2^53, so integer
+// arithmetic checks that its result stays in that range.
+// A function which was typechecked for several argument types is written once for every different body,
+// and a nested function is written at the top level with the variables it captures as its first parameters.
+// Where it is defined, those are bound to the values the variables have then.
+// Generators become generator functions. Defer, with and the builtins without a JavaScript
+// counterpart, such as those for files, give an error.
+
+const INDENTATION: &str = "    ";
+
+type JsFunction = WrittenFunction<Helpers>;
+
+// Names rosy allows which mean something else in JavaScript, including the helpers of the prelude.
+// Variables and functions with these names get an underscore appended.
+const RESERVED_NAMES: &[&str] = &[
+    "arguments", "await", "case", "catch", "class", "const", "console", "debugger", "default", "delete", "do",
+    "enum", "eval", "exactInteger", "export", "extends", "false", "finally", "formatFloat", "function", "import", "in", "Infinity",
+    "instanceof", "integerPower", "interface", "let", "Map", "Math", "NaN", "new", "null", "Number", "package", "pendingLine",
+    "print", "println", "private", "protected", "public", "random", "range", "static", "String", "super",
+    "switch", "this", "throw", "true", "try", "typeof", "undefined", "var", "void", "with", "yield",
+];
+
+// Writes the output of typechecker::type_check_program as JavaScript: the helpers it needs first, then the
+// functions and the main program
+pub fn typed_program_to_javascript(typed_program: &(Vec<BaseExpr<Type>>, Vec<FunctionType>)) -> Result<String, Error> {
+    let uses_print = calls_print(&typed_program.0) || typed_program.1.iter().any(|function| calls_print(&function.content));
+    let functions = transpiler::written_functions(&typed_program.1, js_name, |function, functions| {
+        function_source(function, functions, uses_print)
+    })?;
+
+    let mut generator = JsGenerator::new(&functions, uses_print, 0);
+    generator.write_body(&typed_program.0)?;
+    let main = generator.take_with_declarations(0);
+
+    let mut helpers = generator.helpers;
+    for function in &functions {
+        helpers.include(&function.helpers);
+    }
+    let mut output = helpers.prelude();
+    for function in functions.iter().filter(|function| function.is_first_with_body) {
+        output.push_str(&function.source);
+    }
+    output.push_str(&main);
+    if uses_print {
+        // The last line written by print has not ended yet
+        output.push_str("if (pendingLine !== \"\") {\n    console.log(pendingLine);\n}\n");
+    }
+    Ok(output)
+}
+
+// The source of a single function instance, with a placeholder for its name
+fn function_source(function: &FunctionType, functions: &[JsFunction], uses_print: bool) -> Result<(String, Helpers), Error> {
+    let mut generator = JsGenerator::new(functions, uses_print, 1);
+    let mut parameters = Vec::new();
+    let captured = function.captured.iter().map(|(name, value_type)| (name, value_type));
+    for (name, value_type) in captured.chain(function.param_names.iter().zip(&function.param_types)) {
+        generator.variables.insert(name.clone(), value_type.clone());
+        generator.declared.push(name.clone());
+        parameters.push(js_name(name));
+    }
+
+    generator.write_body(&function.content)?;
+    let body = generator.take_with_declarations(1);
+    let keyword = match function.return_type {
+        Type::Generator(_) => "function*",
+        _ => "function",
+    };
+    let source = format!("{keyword} {NAME_PLACEHOLDER}({}) {{\n{body}}}\n\n", parameters.join(", "));
+    Ok((source, generator.helpers))
+}
+
+fn calls_print(body: &[BaseExpr<Type>]) -> bool {
+    struct PrintFinder {
+        found: bool,
+    }
+
+    impl Visitor<Type> for PrintFinder {
+        fn visit_rec_expr(&mut self, rec_expr: &RecExpr<Type>) {
+            if matches!(&rec_expr.data, RecExprData::FunctionCall { function_name, .. } if function_name == "print") {
+                self.found = true;
+            }
+            visitor::walk_rec_expr(self, rec_expr);
+        }
+    }
+
+    let mut finder = PrintFinder { found: false };
+    finder.visit_body(body);
+    finder.found
+}
+
+// The helper functions written before the program, only those it uses
+#[derive(Default, Clone)]
+struct Helpers {
+    print: bool,
+    format_float: bool,
+    range: bool,
+    random: bool,
+    unpack: bool,
+    exact_integer: bool,
+    integer_power: bool,
+}
+
+impl Helpers {
+    fn include(&mut self, other: &Helpers) {
+        self.print |= other.print;
+        self.format_float |= other.format_float;
+        self.range |= other.range;
+        self.random |= other.random;
+        self.unpack |= other.unpack;
+        self.exact_integer |= other.exact_integer;
+        self.integer_power |= other.integer_power;
+    }
+
+    fn prelude(&self) -> String {
+        let mut prelude = String::new();
+        if self.print {
+            prelude.push_str(
+                "// print leaves the line open, which console.log cannot, so its text waits for the end of the line\n\
+                 let pendingLine = \"\";\n\
+                 function print(value) {\n    pendingLine += value;\n}\n\
+                 function println(value) {\n    console.log(pendingLine + value);\n    pendingLine = \"\";\n}\n\n",
+            );
+        }
+        if self.format_float {
+            prelude.push_str(
+                "// Floats are written as rosy writes them: 3.0 instead of 3, -0.0 instead of 0,\n\
+                 // and 1e20 or 1.5e-5 from 1e16 on and below 1e-4\n\
+                 function formatFloat(value) {\n    \
+                     const magnitude = Math.abs(value);\n    \
+                     if (Number.isNaN(value)) {\n        return \"NaN\";\n    }\n    \
+                     if (magnitude === Infinity) {\n        return value > 0 ? \"inf\" : \"-inf\";\n    }\n    \
+                     if (magnitude !== 0 && (magnitude < 1e-4 || magnitude >= 1e16)) {\n        \
+                         return value.toExponential().replace(\"e+\", \"e\");\n    }\n    \
+                     if (Number.isInteger(value)) {\n        \
+                         return (Object.is(value, -0) ? \"-\" : \"\") + value.toFixed(1);\n    }\n    \
+                     return String(value);\n}\n\n",
+            );
+        }
+        if self.range {
+            prelude.push_str(
+                "// The numbers a for loop over an integer goes through\n\
+                 function* range(count) {\n    for (let i = 0; i < count; i++) {\n        yield i;\n    }\n}\n\n",
+            );
+        }
+        if self.random {
+            prelude.push_str(
+                "// A whole number from low up to, but not including, high\n\
+                 function random(low, high) {\n    return low + Math.floor(Math.random() * (high - low));\n}\n\n",
+            );
+        }
+        if self.unpack {
+            prelude.push_str(
+                "// The elements of a list assigned to several variables, an error if their numbers differ\n\
+                 function unpack(values, count) {\n    if (values.length !== count) {\n        \
+                 throw new Error(`Cannot assign ${values.length} values to ${count} variables`);\n    }\n    \
+                 return values;\n}\n\n",
+            );
+        }
+        if self.exact_integer || self.integer_power {
+            prelude.push_str(
+                "// The result of integer arithmetic, an error where a number can no longer hold it exactly\n\
+                 function exactInteger(value) {\n    if (!Number.isSafeInteger(value)) {\n        \
+                 throw new Error(\"Integer overflow\");\n    }\n    return value;\n}\n\n",
+            );
+        }
+        if self.integer_power {
+            prelude.push_str(
+                "function integerPower(base, exponent) {\n    if (exponent < 0) {\n        \
+                 throw new Error(\"Cannot raise to a negative power\");\n    }\n    \
+                 return exactInteger(base ** exponent);\n}\n\n",
+            );
+        }
+        prelude
+    }
+}
+
+struct JsGenerator<'a> {
+    output: String,
+    indentation: usize,
+    functions: &'a [JsFunction],
+    // The last type given to each variable, which decides how an element of it is assigned
+    variables: HashMap<String, Type>,
+    // The variables which are declared already: the parameters, and those assigned earlier in the body
+    declared: Vec<String>,
+    // The variable holding each nested function defined in the body, by its written name, once bound
+    // to the values it captures
+    closures: HashMap<String, String>,
+    helpers: Helpers,
+}
+
+impl<'a> JsGenerator<'a> {
+    fn new(functions: &'a [JsFunction], uses_print: bool, indentation: usize) -> JsGenerator<'a> {
+        JsGenerator {
+            output: String::new(),
+            indentation,
+            functions,
+            variables: HashMap::new(),
+            declared: Vec::new(),
+            closures: HashMap::new(),
+            helpers: Helpers {
+                print: uses_print,
+                ..Helpers::default()
+            },
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    fn write_line(&mut self, line: &str) {
+        self.write(&INDENTATION.repeat(self.indentation));
+        self.write(line);
+        self.write("\n");
+    }
+
+    fn write_body(&mut self, body: &[BaseExpr<Type>]) -> Result<(), Error> {
+        for base_expr in body {
+            self.write_base_expr(base_expr)?;
+        }
+        Ok(())
+    }
+
+    fn write_block(&mut self, body: &[BaseExpr<Type>]) -> Result<(), Error> {
+        self.indentation += 1;
+        self.write_body(body)?;
+        self.indentation -= 1;
+        Ok(())
+    }
+
+    // The variables are declared with let at the start of the function, so the block a variable is first
+    // assigned in, such as a for loop, does not hide it from the statements after it
+    fn take_with_declarations(&mut self, indentation: usize) -> String {
+        let body = std::mem::take(&mut self.output);
+        let locals: Vec<String> = self
+            .variables
+            .keys()
+            .filter(|name| !self.declared.contains(name))
+            .map(|name| js_name(name))
+            .collect();
+        if locals.is_empty() {
+            return body;
+        }
+        let mut locals = locals;
+        locals.sort();
+        format!("{}let {};\n{body}", INDENTATION.repeat(indentation), locals.join(", "))
+    }
+
+    fn assign(&mut self, name: &str, value_type: &Type) {
+        self.variables.insert(name.to_string(), value_type.clone());
+    }
+
+    fn write_base_expr(&mut self, base_expr: &BaseExpr<Type>) -> Result<(), Error> {
+        // Nested blocks recurse once per level, see stack::ensure_stack
+        stack::ensure_stack(|| match &base_expr.data {
+            BaseExprData::Simple { expr } => {
+                let line = format!("{};", self.expression(expr)?);
+                self.write_line(&line);
+                Ok(())
+            }
+            BaseExprData::VariableAssignment { var_name, expr } => {
+                let line = format!("{} = {};", js_name(var_name), self.expression(expr)?);
+                self.assign(var_name, &expr.generic_data);
+                self.write_line(&line);
+                Ok(())
+            }
+            BaseExprData::DestructuringAssignment { var_names, expr } => {
+                let mut value = self.expression(expr)?;
+                // Only the type of a tuple says how many elements it has
+                if let Type::List(_) = &expr.generic_data {
+                    self.helpers.unpack = true;
+                    value = format!("unpack({value}, {})", var_names.len());
+                }
+                let element_types = typechecker::destructured_types(&expr.generic_data, var_names.len());
+                for (var_name, element_type) in var_names.iter().zip(&element_types) {
+                    self.assign(var_name, element_type);
+                }
+                let names: Vec<String> = var_names.iter().map(|var_name| js_name(var_name)).collect();
+                self.write_line(&format!("[{}] = {value};", names.join(", ")));
+                Ok(())
+            }
+            BaseExprData::PlusEqualsStatement { var_name, expr } => {
+                let line = match (self.variables.get(var_name), &expr.generic_data) {
+                    (Some(Type::Integer), Type::Integer) => {
+                        self.helpers.exact_integer = true;
+                        // The right operand of + needs parentheses at the same precedence
+                        let value = self.expression_with_precedence(expr, 6)?;
+                        format!("{0} = exactInteger({0} + {value});", js_name(var_name))
+                    }
+                    _ => format!("{} += {};", js_name(var_name), self.expression(expr)?),
+                };
+                self.write_line(&line);
+                Ok(())
+            }
+            BaseExprData::FieldAssignment { path, .. } | BaseExprData::IndexAssignment { path, .. }
+                if !path.is_empty() =>
+            {
+                Err(unsupported("An assignment through several fields or indices", base_expr.span()))
+            }
+            BaseExprData::FieldAssignment {
+                object,
+                field,
+                expr,
+                ..
+            } => {
+                let object = js_name(object);
+                let line = format!("{object} = {{ ...{object}, {field}: {} }};", self.expression(expr)?);
+                self.write_line(&line);
+                Ok(())
+            }
+            BaseExprData::IndexAssignment {
+                object,
+                index,
+                expr,
+                ..
+            } => {
+                let index = self.expression(index)?;
+                let value = self.expression(expr)?;
+                let line = match self.variables.get(object) {
+                    Some(Type::Dict(..)) => {
+                        format!("{0} = new Map({0}).set({index}, {value});", js_name(object))
+                    }
+                    _ => format!("{0} = {0}.with({index}, {value});", js_name(object)),
+                };
+                self.write_line(&line);
+                Ok(())
+            }
+            BaseExprData::IfStatement {
+                condition,
+                body,
+                else_statement,
+            } => {
+                self.write(&INDENTATION.repeat(self.indentation));
+                self.write_if(condition, body, else_statement.as_deref())
+            }
+            BaseExprData::ElseIfStatement { .. } | BaseExprData::ElseStatement { .. } => Err(Error::located(
+                "An else can only be written after an if".to_string(),
+                base_expr.span(),
+            )),
+            BaseExprData::ForLoop {
+                var_name,
+                until,
+                body,
+            } => {
+                let iterated = match &until.generic_data {
+                    Type::Integer => {
+                        self.helpers.range = true;
+                        format!("range({})", self.expression(until)?)
+                    }
+                    Type::List(element_type) | Type::Generator(element_type) => {
+                        self.assign(var_name, element_type);
+                        self.expression(until)?
+                    }
+                    other => return Err(unsupported(&format!("A for loop over {:?}", other), until.span())),
+                };
+                if until.generic_data == Type::Integer {
+                    self.assign(var_name, &Type::Integer);
+                }
+                self.write_line(&format!("for ({} of {iterated}) {{", js_name(var_name)));
+                self.write_block(body)?;
+                self.write_line("}");
+                Ok(())
+            }
+            BaseExprData::WhileLoop { condition, body } => {
+                let line = format!("while ({}) {{", self.expression(condition)?);
+                self.write_line(&line);
+                self.write_block(body)?;
+                self.write_line("}");
+                Ok(())
+            }
+            // Functions are written before the program, the definitions of structs are not needed
+            BaseExprData::FunctionDefinition { fun_name, .. } => {
+                self.bind_captured(fun_name, base_expr);
+                Ok(())
+            }
+            BaseExprData::StructDefinition { .. } => Ok(()),
+            BaseExprData::Return { return_value } => {
+                let line = match return_value {
+                    Some(return_value) => format!("return {};", self.expression(return_value)?),
+                    None => String::from("return;"),
+                };
+                self.write_line(&line);
+                Ok(())
+            }
+            BaseExprData::Break => {
+                self.write_line("break;");
+                Ok(())
+            }
+            BaseExprData::Continue => {
+                self.write_line("continue;");
+                Ok(())
+            }
+            BaseExprData::Yield { value } => {
+                let line = format!("yield {};", self.expression(value)?);
+                self.write_line(&line);
+                Ok(())
+            }
+            BaseExprData::Delete { var_name } => {
+                self.write_line(&format!("{} = undefined;", js_name(var_name)));
+                Ok(())
+            }
+            BaseExprData::Swap {
+                first_name,
+                second_name,
+            } => {
+                let (first, second) = (js_name(first_name), js_name(second_name));
+                self.write_line(&format!("[{first}, {second}] = [{second}, {first}];"));
+                Ok(())
+            }
+            BaseExprData::Match { subject, arms } => self.write_match(subject, arms),
+            BaseExprData::Defer { .. } => Err(unsupported("defer", base_expr.span())),
+            BaseExprData::With { .. } => Err(unsupported("with", base_expr.span())),
+        })
+    }
+
+    // Writes an if or else if from its condition on, continuing the line the caller started
+    fn write_if(
+        &mut self,
+        condition: &RecExpr<Type>,
+        body: &[BaseExpr<Type>],
+        else_statement: Option<&BaseExpr<Type>>,
+    ) -> Result<(), Error> {
+        let condition = self.expression(condition)?;
+        self.write(&format!("if ({condition}) {{\n"));
+        self.write_block(body)?;
+        self.write(&INDENTATION.repeat(self.indentation));
+        self.write("}");
+        match else_statement.map(|else_statement| &else_statement.data) {
+            Some(BaseExprData::ElseIfStatement {
+                condition,
+                body,
+                else_statement,
+            }) => {
+                self.write(" else ");
+                self.write_if(condition, body, else_statement.as_deref())
+            }
+            Some(BaseExprData::ElseStatement { body }) => {
+                self.write(" else {\n");
+                self.write_block(body)?;
+                self.write_line("}");
+                Ok(())
+            }
+            _ => {
+                self.write("\n");
+                Ok(())
+            }
+        }
+    }
+
+    // A match becomes a chain of ifs comparing the subject with each literal case, the first case which
+    // binds a name or is a wildcard becomes the else
+    fn write_match(&mut self, subject: &RecExpr<Type>, arms: &[MatchArm<Type>]) -> Result<(), Error> {
+        let subject_source = match &subject.data {
+            RecExprData::Variable { name } => js_name(name),
+            _ => {
+                // The subject is computed once, into a variable no rosy program can name
+                let name = format!("match_{}_{}", subject.span.row, subject.span.col_start);
+                let line = format!("{name} = {};", self.expression(subject)?);
+                self.write_line(&line);
+                self.assign(&name, &subject.generic_data);
+                name
+            }
+        };
+
+        let mut first = true;
+        for arm in arms {
+            let condition = match &arm.pattern {
+                Pattern::Number(number) => Some(number.to_string()),
+                Pattern::String(value) => Some(string_literal(value)),
+                Pattern::Boolean(value) => Some(value.to_string()),
+                Pattern::Binding(_) | Pattern::Wildcard => None,
+            };
+            match (condition, first) {
+                (Some(condition), true) => {
+                    self.write_line(&format!("if ({subject_source} === {condition}) {{"));
+                }
+                (Some(condition), false) => {
+                    self.write_line(&format!("}} else if ({subject_source} === {condition}) {{"));
+                }
+                (None, true) => self.write_line("{"),
+                (None, false) => self.write_line("} else {"),
+            }
+            self.indentation += 1;
+            if let Pattern::Binding(name) = &arm.pattern {
+                self.write_line(&format!("{} = {subject_source};", js_name(name)));
+                self.assign(name, &subject.generic_data);
+            }
+            self.write_body(&arm.body)?;
+            self.indentation -= 1;
+            first = false;
+            // The cases after one which always matches are never reached
+            if matches!(arm.pattern, Pattern::Binding(_) | Pattern::Wildcard) {
+                break;
+            }
+        }
+        if !first {
+            self.write_line("}");
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self, expression: &RecExpr<Type>) -> Result<String, Error> {
+        self.expression_with_precedence(expression, LOWEST_PRECEDENCE)
+    }
+
+    fn expressions(&mut self, expressions: &[RecExpr<Type>]) -> Result<Vec<String>, Error> {
+        expressions.iter().map(|expression| self.expression(expression)).collect()
+    }
+
+    // Writes the expression, parenthesized if it binds looser than the given precedence
+    fn expression_with_precedence(&mut self, expression: &RecExpr<Type>, minimum_precedence: u8) -> Result<String, Error> {
+        // Nested expressions recurse once per level, see stack::ensure_stack
+        stack::ensure_stack(|| {
+            let source = match &expression.data {
+                RecExprData::Variable { name } => js_name(name),
+                // Constant evaluation can make an integer no number holds exactly
+                RecExprData::Number { number } if number.unsigned_abs() > (1 << 53) - 1 => {
+                    return Err(unsupported("An integer beyond 2^53", expression.span()));
+                }
+                RecExprData::Number { number } => number.to_string(),
+                RecExprData::Float { value } if value.is_finite() => format!("{value:?}"),
+                RecExprData::Float { .. } => return Err(unsupported("A float which is not finite", expression.span())),
+                RecExprData::String { value } => string_literal(value),
+                RecExprData::Boolean { value } => value.to_string(),
+                RecExprData::Assign {
+                    variable_name,
+                    right,
+                } => {
+                    self.assign(variable_name, &right.generic_data);
+                    format!("{} = {}", js_name(variable_name), self.expression(right)?)
+                }
+                RecExprData::Add { left, right } if expression.generic_data == Type::Integer => {
+                    self.exact_integer(left, "+", right, 5)?
+                }
+                RecExprData::Subtract { left, right } if expression.generic_data == Type::Integer => {
+                    self.exact_integer(left, "-", right, 5)?
+                }
+                RecExprData::Multiply { left, right } if expression.generic_data == Type::Integer => {
+                    self.exact_integer(left, "*", right, MULTIPLY_PRECEDENCE)?
+                }
+                RecExprData::Add { left, right } => self.binary(expression, left, "+", right)?,
+                RecExprData::Subtract { left, right } => self.binary(expression, left, "-", right)?,
+                RecExprData::Multiply { left, right } => self.binary(expression, left, "*", right)?,
+                // Dividing two integers gives an integer, rounded towards zero
+                RecExprData::Divide { left, right } if expression.generic_data == Type::Integer => {
+                    format!("Math.trunc({})", self.binary_at(MULTIPLY_PRECEDENCE, left, "/", right)?)
+                }
+                RecExprData::Divide { left, right } => self.binary(expression, left, "/", right)?,
+                RecExprData::FloorDivide { left, right } => {
+                    format!("Math.floor({})", self.binary_at(MULTIPLY_PRECEDENCE, left, "/", right)?)
+                }
+                RecExprData::Power { left, right } if expression.generic_data == Type::Integer => {
+                    self.helpers.integer_power = true;
+                    format!("integerPower({}, {})", self.expression(left)?, self.expression(right)?)
+                }
+                // Unlike ^ in rosy, ** groups to the right and cannot follow a unary operator
+                RecExprData::Power { left, right } => format!(
+                    "{} ** {}",
+                    self.expression_with_precedence(left, ATOM_PRECEDENCE)?,
+                    self.expression_with_precedence(right, POWER_PRECEDENCE)?
+                ),
+                RecExprData::Or { left, right } => self.binary(expression, left, "||", right)?,
+                RecExprData::And { left, right } => self.binary(expression, left, "&&", right)?,
+                RecExprData::Equals { left, right } => self.binary(expression, left, "===", right)?,
+                RecExprData::NotEquals { left, right } => self.binary(expression, left, "!==", right)?,
+                RecExprData::GreaterThan { left, right } => self.binary(expression, left, ">", right)?,
+                RecExprData::LessThan { left, right } => self.binary(expression, left, "<", right)?,
+                RecExprData::GreaterThanOrEqual { left, right } => self.binary(expression, left, ">=", right)?,
+                RecExprData::LessThanOrEqual { left, right } => self.binary(expression, left, "<=", right)?,
+                // The operand is parenthesized unless it is a single value, which also keeps - -1 from becoming --1
+                RecExprData::Minus { right } => format!("-{}", self.expression_with_precedence(right, ATOM_PRECEDENCE)?),
+                RecExprData::Not { right } => format!("!{}", self.expression_with_precedence(right, ATOM_PRECEDENCE)?),
+                RecExprData::Access { object, variable } => format!("{}.{variable}", js_name(object)),
+                RecExprData::FunctionCall {
+                    function_name,
+                    args,
+                } => self.call(expression, function_name, args)?,
+                RecExprData::List { elements } | RecExprData::Tuple { elements } => {
+                    format!("[{}]", self.expressions(elements)?.join(", "))
+                }
+                // The typechecker puts the arguments of a call in the order of the parameters
+                RecExprData::KeywordArgument { .. } => {
+                    return Err(unsupported("A keyword argument outside a call", expression.span()))
+                }
+                // The values of ?: group to the right, as those of a conditional in rosy
+                RecExprData::Conditional {
+                    condition,
+                    then_value,
+                    else_value,
+                } => format!(
+                    "{} ? {} : {}",
+                    self.expression_with_precedence(condition, LOWEST_PRECEDENCE + 1)?,
+                    self.expression(then_value)?,
+                    self.expression(else_value)?
+                ),
+                RecExprData::Dict { entries } if entries.is_empty() => String::from("new Map()"),
+                RecExprData::Dict { entries } => {
+                    let mut written = Vec::new();
+                    for (key, value) in entries {
+                        written.push(format!("[{}, {}]", self.expression(key)?, self.expression(value)?));
+                    }
+                    format!("new Map([{}])", written.join(", "))
+                }
+                RecExprData::ListAccess { list, index } => {
+                    let list_source = self.expression_with_precedence(list, ATOM_PRECEDENCE)?;
+                    let index = self.expression(index)?;
+                    match list.generic_data {
+                        Type::Dict(..) => format!("{list_source}.get({index})"),
+                        _ => format!("{list_source}[{index}]"),
+                    }
+                }
+                RecExprData::Slice { list, start, end } => {
+                    let list_source = self.expression_with_precedence(list, ATOM_PRECEDENCE)?;
+                    let start = match start {
+                        Some(start) => self.slice_bound(start)?,
+                        None => String::from("0"),
+                    };
+                    match end {
+                        Some(end) => format!("{list_source}.slice({start}, {})", self.slice_bound(end)?),
+                        None => format!("{list_source}.slice({start})"),
+                    }
+                }
+            };
+
+            Ok(match precedence(expression) < minimum_precedence {
+                true => format!("({source})"),
+                false => source,
+            })
+        })
+    }
+
+    // A negative bound counts from the end in JavaScript but is clamped to the start in rosy
+    fn slice_bound(&mut self, bound: &RecExpr<Type>) -> Result<String, Error> {
+        match bound.data {
+            RecExprData::Number { number } if number >= 0 => Ok(number.to_string()),
+            _ => Ok(format!("Math.max({}, 0)", self.expression(bound)?)),
+        }
+    }
+
+    fn exact_integer(&mut self, left: &RecExpr<Type>, operator: &str, right: &RecExpr<Type>, operator_precedence: u8) -> Result<String, Error> {
+        self.helpers.exact_integer = true;
+        Ok(format!("exactInteger({})", self.binary_at(operator_precedence, left, operator, right)?))
+    }
+
+    fn binary(
+        &mut self,
+        expression: &RecExpr<Type>,
+        left: &RecExpr<Type>,
+        operator: &str,
+        right: &RecExpr<Type>,
+    ) -> Result<String, Error> {
+        self.binary_at(precedence(expression), left, operator, right)
+    }
+
+    fn binary_at(
+        &mut self,
+        operator_precedence: u8,
+        left: &RecExpr<Type>,
+        operator: &str,
+        right: &RecExpr<Type>,
+    ) -> Result<String, Error> {
+        // The right operand of a left associative operator needs parentheses at the same precedence: a - (b - c)
+        Ok(format!(
+            "{} {operator} {}",
+            self.expression_with_precedence(left, operator_precedence)?,
+            self.expression_with_precedence(right, operator_precedence + 1)?
+        ))
+    }
+
+    // A call constructs a struct, calls a builtin or calls a function of the program, in that order as in the typechecker
+    fn call(&mut self, expression: &RecExpr<Type>, function_name: &str, args: &[RecExpr<Type>]) -> Result<String, Error> {
+        if let Type::Struct(struct_type) = &expression.generic_data {
+            if struct_type.name == function_name {
+                let mut fields = Vec::new();
+                for ((field, _), arg) in struct_type.fields.iter().zip(args) {
+                    fields.push(format!("{field}: {}", self.expression(arg)?));
+                }
+                return Ok(format!("{{ {} }}", fields.join(", ")));
+            }
+        }
+
+        if builtins::find_builtin(function_name).is_some() {
+            return self.builtin_call(expression, function_name, args);
+        }
+
+        let function = transpiler::called_instance(self.functions, function_name, args, expression)?;
+        let args = self.expressions(args)?;
+        if let Some(closure) = self.closures.get(&function.written_name) {
+            return Ok(format!("{closure}({})", args.join(", ")));
+        }
+        // Within a nested function itself, the values it captured are its first parameters
+        let written: Vec<String> = function.captured.iter().map(|name| js_name(name)).chain(args).collect();
+        Ok(format!("{}({})", function.written_name, written.join(", ")))
+    }
+
+    // Binds every instance of the nested function to the values of the variables it captures, into a
+    // variable no rosy program can name, which calls after the definition go through
+    fn bind_captured(&mut self, fun_name: &str, definition: &BaseExpr<Type>) {
+        let functions = self.functions;
+        for function in functions.iter().filter(|function| function.name == fun_name && !function.captured.is_empty()) {
+            let closure = match self.closures.get(&function.written_name) {
+                Some(closure) => closure.clone(),
+                None => format!("{}_{}_{}", function.written_name, definition.span.row, definition.span.col_start),
+            };
+            let captured: Vec<String> = function.captured.iter().map(|name| js_name(name)).collect();
+            let line = format!("{closure} = {}.bind(null, {});", function.written_name, captured.join(", "));
+            self.write_line(&line);
+            self.assign(&closure, &Type::Undefined);
+            self.closures.insert(function.written_name.clone(), closure);
+        }
+    }
+
+    fn builtin_call(&mut self, expression: &RecExpr<Type>, function_name: &str, args: &[RecExpr<Type>]) -> Result<String, Error> {
+        let source = match (function_name, args) {
+            ("print", values @ [_, ..]) => format!("print({})", self.printed_values(values)?),
+            ("println", values @ [_, ..]) => match self.helpers.print {
+                true => format!("println({})", self.printed_values(values)?),
+                false => format!("console.log({})", self.printed_values(values)?),
+            },
+            ("abs", [value]) => format!("Math.abs({})", self.expression(value)?),
+            ("min" | "max", [a, b]) => {
+                format!("Math.{function_name}({}, {})", self.expression(a)?, self.expression(b)?)
+            }
+            ("random", [low, high]) => {
+                self.helpers.random = true;
+                format!("random({}, {})", self.expression(low)?, self.expression(high)?)
+            }
+            // Integers and floats are both numbers, the type only changes how the value is printed
+            ("float", [value]) if matches!(value.generic_data, Type::Integer | Type::Float) => {
+                self.expression_with_precedence(value, ATOM_PRECEDENCE)?
+            }
+            ("int", [value]) => format!("Math.trunc({})", self.expression(value)?),
+            ("find", [text, pattern]) => self.method(text, "indexOf", std::slice::from_ref(pattern))?,
+            ("slice", [text, rest @ ..]) if text.generic_data == Type::String => self.method(text, "slice", rest)?,
+            ("pad_left", [text, rest @ ..]) => self.method(text, "padStart", rest)?,
+            ("pad_right", [text, rest @ ..]) => self.method(text, "padEnd", rest)?,
+            ("repeat", [text, count]) => self.method(text, "repeat", std::slice::from_ref(count))?,
+            _ => {
+                return Err(unsupported(&format!("The builtin {}", function_name), expression.span()));
+            }
+        };
+        Ok(source)
+    }
+
+    // A call of the string method on the first argument of a builtin
+    fn method(&mut self, receiver: &RecExpr<Type>, method: &str, args: &[RecExpr<Type>]) -> Result<String, Error> {
+        let receiver = self.expression_with_precedence(receiver, ATOM_PRECEDENCE)?;
+        Ok(format!("{receiver}.{method}({})", self.expressions(args)?.join(", ")))
+    }
+
+    // The text print writes for the value, floats keep their decimal point
+    fn printed(&mut self, value: &RecExpr<Type>) -> Result<String, Error> {
+        match value.generic_data {
+            Type::Float => {
+                self.helpers.format_float = true;
+                Ok(format!("formatFloat({})", self.expression(value)?))
+            }
+            Type::Integer | Type::String | Type::Boolean => self.expression(value),
+            _ => Err(unsupported("Printing this value", value.span())),
+        }
+    }
+
+    // The values of a print call, several of which are joined into one string after all are computed
+    fn printed_values(&mut self, values: &[RecExpr<Type>]) -> Result<String, Error> {
+        match values {
+            [value] => self.printed(value),
+            _ => {
+                let printed = values.iter().map(|value| self.printed(value)).collect::<Result<Vec<_>, _>>()?;
+                Ok(format!("[{}].join(\"\")", printed.join(", ")))
+            }
+        }
+    }
+}
+
+// How tightly an expression binds its operands, following the operator precedence of JavaScript.
+// Calls, member accesses and literals bind tightest.
+const LOWEST_PRECEDENCE: u8 = 0;
+const MULTIPLY_PRECEDENCE: u8 = 6;
+const POWER_PRECEDENCE: u8 = 8;
+const ATOM_PRECEDENCE: u8 = 9;
+
+fn precedence(expression: &RecExpr<Type>) -> u8 {
+    match &expression.data {
+        RecExprData::Assign { .. } | RecExprData::Conditional { .. } => LOWEST_PRECEDENCE,
+        // Negative literals are only made by constant evaluation, they are written with a minus sign
+        RecExprData::Number { number } if *number < 0 => LOWEST_PRECEDENCE,
+        RecExprData::Float { value } if value.is_sign_negative() => LOWEST_PRECEDENCE,
+        RecExprData::Or { .. } => 1,
+        RecExprData::And { .. } => 2,
+        RecExprData::Equals { .. } | RecExprData::NotEquals { .. } => 3,
+        RecExprData::GreaterThan { .. }
+        | RecExprData::LessThan { .. }
+        | RecExprData::GreaterThanOrEqual { .. }
+        | RecExprData::LessThanOrEqual { .. } => 4,
+        // Integer arithmetic is written as a call of exactInteger or integerPower
+        RecExprData::Add { .. } | RecExprData::Subtract { .. } | RecExprData::Multiply { .. } | RecExprData::Power { .. }
+            if expression.generic_data == Type::Integer =>
+        {
+            ATOM_PRECEDENCE
+        }
+        RecExprData::Add { .. } | RecExprData::Subtract { .. } => 5,
+        RecExprData::Divide { .. } if expression.generic_data == Type::Integer => ATOM_PRECEDENCE,
+        RecExprData::FloorDivide { .. } => ATOM_PRECEDENCE,
+        RecExprData::Multiply { .. } | RecExprData::Divide { .. } => MULTIPLY_PRECEDENCE,
+        RecExprData::Minus { .. } | RecExprData::Not { .. } => 7,
+        RecExprData::Power { .. } => POWER_PRECEDENCE,
+        _ => ATOM_PRECEDENCE,
+    }
+}
+
+fn js_name(name: &str) -> String {
+    match RESERVED_NAMES.contains(&name) {
+        true => format!("{name}_"),
+        false => name.to_string(),
+    }
+}
+
+fn unsupported(what: &str, span: Span) -> Error {
+    Error::located(format!("{} cannot be written as JavaScript", what), span)
+}
@@ -0,0 +1,214 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::tokenizer::Error;
+
+// The name of the file the language options of a project are read from.
+// It is looked up in the directory of the source file and its ancestors.
+pub const CONFIG_FILE_NAME: &str = "rosy.toml";
+
+// The experimental features that can be enabled, none at the moment.
+// A feature is added here while its design may still change, so programs have to opt in to using it.
+pub const EXPERIMENTAL_FEATURES: &[&str] = &[];
+
+// Settings that change how programs are read and checked, so the language can change
+// without breaking existing programs. The defaults are the behaviour of the language before these existed.
+#[derive(Clone, PartialEq, Debug)]
+pub struct LanguageOptions {
+    // The number of spaces that make up one level of indentation, a tab is always one level
+    pub indentation_width: usize,
+    // Typechecks a program before running it, so type errors are reported before anything happens
+    pub strict_typing: bool,
+    // Disallows mixing integers and floats in arithmetic and comparisons,
+    // the integer has to be converted with float() instead
+    pub strict_numerics: bool,
+    // Makes / on two integers give a float, instead of an integer rounded towards zero.
+    // Use // for the integer division rounded down.
+    pub true_division: bool,
+    // The enabled features from EXPERIMENTAL_FEATURES
+    pub experimental_features: Vec<String>,
+}
+
+impl Default for LanguageOptions {
+    fn default() -> Self {
+        LanguageOptions {
+            indentation_width: 4,
+            strict_typing: false,
+            strict_numerics: false,
+            true_division: false,
+            experimental_features: Vec::new(),
+        }
+    }
+}
+
+impl LanguageOptions {
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.experimental_features.iter().any(|enabled| enabled == feature)
+    }
+
+    // Enables an experimental feature, which must be one of EXPERIMENTAL_FEATURES
+    pub fn enable_feature(&mut self, feature: &str) -> Result<(), Error> {
+        if !EXPERIMENTAL_FEATURES.contains(&feature) {
+            return Err(Error::SimpleError {
+                message: format!("Unknown experimental feature '{}'", feature),
+            });
+        }
+
+        if !self.has_feature(feature) {
+            self.experimental_features.push(feature.to_string());
+        }
+        Ok(())
+    }
+
+    // Reads the options for the given source file from the closest rosy.toml,
+    // or gives the default options if there is none
+    pub fn load_for_source(source_path: &Path) -> Result<LanguageOptions, Error> {
+        match find_config_file(source_path) {
+            Some(config_path) => {
+                let content = match std::fs::read_to_string(&config_path) {
+                    Ok(content) => content,
+                    Err(error) => {
+                        return Err(Error::SimpleError {
+                            message: format!("Could not read {}: {}", config_path.display(), error),
+                        })
+                    }
+                };
+                LanguageOptions::from_toml(&content)
+            }
+            None => Ok(LanguageOptions::default()),
+        }
+    }
+
+    // Reads the options from the [language] table of a rosy.toml:
+    //   [language]
+    //   indentation_width = 2
+    //   strict_typing = true
+    //   strict_numerics = true
+    //   true_division = true
+    //   experimental_features = ["feature"]
+    // Options that are left out keep their default value.
+    pub fn from_toml(content: &str) -> Result<LanguageOptions, Error> {
+        let mut options = LanguageOptions::default();
+        let mut in_language_table = false;
+
+        for (line_index, line) in content.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            let config_error = |message: String| Error::SimpleError {
+                message: format!("{} line {}: {}", CONFIG_FILE_NAME, line_index + 1, message),
+            };
+
+            // Skip empty lines, which includes lines with only a comment
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                if line != "[language]" {
+                    return Err(config_error(format!("Unknown table {}", line)));
+                }
+                in_language_table = true;
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(config_error(format!("Expected key = value, found '{}'", line)));
+            };
+            let key = key.trim();
+            let value = value.trim();
+
+            if !in_language_table {
+                return Err(config_error(format!("'{}' must be in the [language] table", key)));
+            }
+
+            match key {
+                "indentation_width" => match value.parse::<usize>() {
+                    Ok(width) if width > 0 => options.indentation_width = width,
+                    _ => {
+                        return Err(config_error(format!(
+                            "indentation_width must be a positive integer, found '{}'",
+                            value
+                        )))
+                    }
+                },
+                "strict_typing" => options.strict_typing = parse_bool(key, value).map_err(config_error)?,
+                "strict_numerics" => options.strict_numerics = parse_bool(key, value).map_err(config_error)?,
+                "true_division" => options.true_division = parse_bool(key, value).map_err(config_error)?,
+                "experimental_features" => {
+                    for feature in parse_string_list(key, value).map_err(config_error)? {
+                        if let Err(Error::SimpleError { message }) = options.enable_feature(&feature) {
+                            return Err(config_error(message));
+                        }
+                    }
+                }
+                _ => return Err(config_error(format!("Unknown language option '{}'", key))),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+// The rosy.toml in the directory of the source file or the closest of its ancestors
+fn find_config_file(source_path: &Path) -> Option<PathBuf> {
+    let mut directory = source_path.parent();
+    while let Some(current) = directory {
+        let config_path = current.join(CONFIG_FILE_NAME);
+        if config_path.is_file() {
+            return Some(config_path);
+        }
+        directory = current.parent();
+    }
+
+    None
+}
+
+// The line up to a # outside of a string, which starts a comment
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(format!("{} must be true or false, found '{}'", key, value)),
+    }
+}
+
+// Parses a list of strings such as ["a", "b"]
+fn parse_string_list(key: &str, value: &str) -> Result<Vec<String>, String> {
+    let list_error = || format!("{} must be a list of strings, found '{}'", key, value);
+
+    let Some(items) = value
+        .strip_prefix('[')
+        .and_then(|value| value.strip_suffix(']'))
+    else {
+        return Err(list_error());
+    };
+
+    let mut strings = Vec::new();
+    for item in items.split(',') {
+        let item = item.trim();
+        // Allows a trailing comma and the empty list
+        if item.is_empty() {
+            continue;
+        }
+        match item
+            .strip_prefix('"')
+            .and_then(|item| item.strip_suffix('"'))
+        {
+            Some(string) => strings.push(string.to_string()),
+            None => return Err(list_error()),
+        }
+    }
+
+    Ok(strings)
+}
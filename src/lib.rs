@@ -1,18 +1,48 @@
 use clap::Parser;
+pub mod annotator;
 pub mod assembler;
+pub mod benchmark;
+pub mod builtins;
 pub mod codegenerator;
 pub mod compiler;
+pub mod complex;
+pub mod consteval;
+pub mod coroutine;
+pub mod deadcode;
+pub mod decompiler;
 pub mod desugarer;
+pub mod difftest;
+pub mod elfwriter;
+pub mod engine;
 pub mod exewriter;
+pub mod fixer;
+pub mod inliner;
 pub mod interpreter;
+#[cfg(feature = "jit")]
+pub mod jit;
+pub mod jstranspiler;
+pub mod language;
+pub mod linter;
 pub mod livenessanalysis;
+pub mod lookup;
 pub mod parser;
 pub mod pipeline;
+pub mod pytranspiler;
+pub mod rational;
+pub mod smallstring;
 pub mod tac;
+pub mod tacformat;
+pub mod testrunner;
 pub mod tokenizer;
+pub mod trace;
+pub mod transpiler;
 pub mod typechecker;
 pub mod uniquify;
+pub mod verifier;
+pub mod visitor;
 pub mod registerallocation;
+pub mod scheduler;
+pub mod stack;
 pub mod variablecollector;
 pub mod defaultfunctions;
 pub mod optimiser;
@@ -0,0 +1,424 @@
+use crate::builtins;
+use crate::decompiler;
+use crate::language::LanguageOptions;
+use crate::parser;
+use crate::parser::BaseExpr;
+use crate::parser::BaseExprData;
+use crate::parser::Pattern;
+use crate::parser::RecExpr;
+use crate::parser::RecExprData;
+use crate::tokenizer;
+use crate::tokenizer::Error;
+use crate::tokenizer::Span;
+use crate::tokenizer::SymbolType;
+use crate::tokenizer::Token;
+use crate::tokenizer::TokenData;
+use crate::visitor;
+use crate::visitor::Visitor;
+
+// Warnings about programs which run, but most likely not as meant, used by rosy lint.
+// The checks are heuristics: they only look at the program as written, so they can miss a mistake,
+// but they do not warn about a loop which can end.
+// With --style rosy lint also checks how the program is laid out, see find_style_warnings.
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Warning {
+    // What is likely wrong, such as "This loop never runs, as it counts up to 0"
+    pub message: String,
+    pub span: Span,
+}
+
+// The layout rules checked by rosy lint --style
+#[derive(Clone, PartialEq, Debug)]
+pub struct StyleOptions {
+    // The longest a line may be, in characters with a tab counting as four. 0 turns the check off.
+    pub max_line_length: usize,
+    // The blank lines wanted between two functions defined after each other at the top level
+    pub blank_lines_between_functions: usize,
+}
+
+impl Default for StyleOptions {
+    fn default() -> Self {
+        StyleOptions {
+            max_line_length: 100,
+            blank_lines_between_functions: 1,
+        }
+    }
+}
+
+// Parses the program and finds all warnings for it, in source order
+pub fn lint_program(lines: Vec<&str>, language: &LanguageOptions) -> Result<Vec<Warning>, Error> {
+    let base_expressions = parser::parse_strings_with_options(lines, language)?;
+    Ok(find_loop_warnings(&base_expressions))
+}
+
+// Finds while loops which can never end and for loops which never run
+pub fn find_loop_warnings(base_expressions: &[BaseExpr<()>]) -> Vec<Warning> {
+    let mut finder = LoopWarningFinder { warnings: Vec::new() };
+    finder.visit_body(base_expressions);
+    finder.warnings
+}
+
+struct LoopWarningFinder {
+    warnings: Vec<Warning>,
+}
+
+impl Visitor<()> for LoopWarningFinder {
+    fn visit_base_expr(&mut self, base_expr: &BaseExpr<()>) {
+        match &base_expr.data {
+            BaseExprData::WhileLoop { condition, body } => {
+                if let Some(message) = never_ending_loop_message(condition, body) {
+                    self.warnings.push(Warning {
+                        message,
+                        span: condition.span(),
+                    });
+                }
+            }
+            BaseExprData::ForLoop { until, .. } => {
+                if let Some(message) = never_running_loop_message(until) {
+                    self.warnings.push(Warning {
+                        message,
+                        span: until.span(),
+                    });
+                }
+            }
+            _ => {}
+        }
+        visitor::walk_base_expr(self, base_expr);
+    }
+}
+
+// A while loop never ends once it has started when its condition only reads variables which nothing in
+// the loop changes, and its body has no break or return. Values are never shared, so a function called
+// in the body cannot change them either. The condition may call builtins such as len, but not functions
+// like input whose result can differ between calls.
+fn never_ending_loop_message(condition: &RecExpr<()>, body: &[BaseExpr<()>]) -> Option<String> {
+    if matches!(condition.data, RecExprData::Boolean { value: false })
+        || calls_impure_function(condition)
+        || can_leave_loop(body)
+    {
+        return None;
+    }
+
+    let mut changed = AssignmentCollector { names: Vec::new() };
+    changed.visit_rec_expr(condition);
+    changed.visit_body(body);
+
+    let mut read = VariableCollector { names: Vec::new() };
+    read.visit_rec_expr(condition);
+    if read.names.iter().any(|name| changed.names.contains(name)) {
+        return None;
+    }
+
+    Some(match read.names.is_empty() {
+        true => String::from("This loop never ends, as its condition is always the same and its body does not break or return"),
+        false => format!(
+            "This loop never ends, as its body does not change {} and does not break or return",
+            read.names.join(" or ")
+        ),
+    })
+}
+
+// A for loop over a literal count of at most 0, or over an empty list, never runs its body
+fn never_running_loop_message(until: &RecExpr<()>) -> Option<String> {
+    let counts_to_nothing = match &until.data {
+        RecExprData::Number { number } => *number <= 0,
+        RecExprData::Minus { right } => matches!(right.data, RecExprData::Number { .. }),
+        RecExprData::List { elements } if elements.is_empty() => {
+            return Some(String::from("This loop never runs, as the list it loops over is empty"))
+        }
+        _ => false,
+    };
+    counts_to_nothing.then(|| {
+        format!(
+            "This loop never runs, as it counts up to {}",
+            decompiler::expression_to_source(until)
+        )
+    })
+}
+
+fn calls_impure_function(rec_expr: &RecExpr<()>) -> bool {
+    struct ImpureCallFinder {
+        found: bool,
+    }
+
+    impl Visitor<()> for ImpureCallFinder {
+        fn visit_rec_expr(&mut self, rec_expr: &RecExpr<()>) {
+            if let RecExprData::FunctionCall { function_name, .. } = &rec_expr.data {
+                self.found |= !builtins::is_pure(function_name);
+            }
+            visitor::walk_rec_expr(self, rec_expr);
+        }
+    }
+
+    let mut finder = ImpureCallFinder { found: false };
+    finder.visit_rec_expr(rec_expr);
+    finder.found
+}
+
+// Whether the body has a return, or a break which is not inside a loop of its own
+fn can_leave_loop(body: &[BaseExpr<()>]) -> bool {
+    struct ExitFinder {
+        inner_loops: usize,
+        found: bool,
+    }
+
+    impl Visitor<()> for ExitFinder {
+        fn visit_base_expr(&mut self, base_expr: &BaseExpr<()>) {
+            match &base_expr.data {
+                BaseExprData::Return { .. } => self.found = true,
+                BaseExprData::Break if self.inner_loops == 0 => self.found = true,
+                BaseExprData::ForLoop { .. } | BaseExprData::WhileLoop { .. } => {
+                    self.inner_loops += 1;
+                    visitor::walk_base_expr(self, base_expr);
+                    self.inner_loops -= 1;
+                }
+                // A return or break in a function only leaves that function
+                BaseExprData::FunctionDefinition { .. } => {}
+                _ => visitor::walk_base_expr(self, base_expr),
+            }
+        }
+    }
+
+    let mut finder = ExitFinder {
+        inner_loops: 0,
+        found: false,
+    };
+    finder.visit_body(body);
+    finder.found
+}
+
+// The names of the variables the expression reads, in the order they are first read
+struct VariableCollector {
+    names: Vec<String>,
+}
+
+impl Visitor<()> for VariableCollector {
+    fn visit_rec_expr(&mut self, rec_expr: &RecExpr<()>) {
+        if let RecExprData::Variable { name } = &rec_expr.data {
+            if !self.names.contains(name) {
+                self.names.push(name.clone());
+            }
+        }
+        visitor::walk_rec_expr(self, rec_expr);
+    }
+}
+
+// The names of the variables which are given a new value, or of which a field or element is changed
+struct AssignmentCollector {
+    names: Vec<String>,
+}
+
+impl Visitor<()> for AssignmentCollector {
+    fn visit_base_expr(&mut self, base_expr: &BaseExpr<()>) {
+        match &base_expr.data {
+            BaseExprData::VariableAssignment { var_name, .. }
+            | BaseExprData::PlusEqualsStatement { var_name, .. }
+            | BaseExprData::ForLoop { var_name, .. }
+            | BaseExprData::Delete { var_name }
+            | BaseExprData::With { var_name, .. } => self.names.push(var_name.clone()),
+            BaseExprData::FieldAssignment { object, .. } | BaseExprData::IndexAssignment { object, .. } => {
+                self.names.push(object.clone())
+            }
+            BaseExprData::DestructuringAssignment { var_names, .. } => self.names.extend(var_names.iter().cloned()),
+            BaseExprData::Swap {
+                first_name,
+                second_name,
+            } => {
+                self.names.push(first_name.clone());
+                self.names.push(second_name.clone());
+            }
+            BaseExprData::Match { arms, .. } => {
+                for arm in arms {
+                    if let Pattern::Binding(name) = &arm.pattern {
+                        self.names.push(name.clone());
+                    }
+                }
+            }
+            // The variables of a function are its own
+            BaseExprData::FunctionDefinition { .. } => return,
+            _ => {}
+        }
+        visitor::walk_base_expr(self, base_expr);
+    }
+
+    fn visit_rec_expr(&mut self, rec_expr: &RecExpr<()>) {
+        if let RecExprData::Assign { variable_name, .. } = &rec_expr.data {
+            self.names.push(variable_name.clone());
+        }
+        visitor::walk_rec_expr(self, rec_expr);
+    }
+}
+
+// Finds the lines which are too long, the operators without a single space on each side
+// and the functions which do not have enough blank lines before them, in source order
+pub fn find_style_warnings(
+    lines: Vec<&str>,
+    language: &LanguageOptions,
+    style: &StyleOptions,
+) -> Result<Vec<Warning>, Error> {
+    let base_expressions = parser::parse_strings_with_options(lines.clone(), language)?;
+
+    let mut warnings = Vec::new();
+    if style.max_line_length > 0 {
+        warnings.extend(long_line_warnings(&lines, style.max_line_length));
+    }
+    for token_line in tokenizer::tokenize_with_options(lines.clone(), language)? {
+        warnings.extend(operator_spacing_warnings(&token_line.tokens));
+    }
+    warnings.extend(function_spacing_warnings(&lines, &base_expressions, style.blank_lines_between_functions));
+
+    warnings.sort_by_key(|warning| (warning.span.row, warning.span.col_start));
+    Ok(warnings)
+}
+
+fn long_line_warnings(lines: &[&str], max_line_length: usize) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    for (row, line) in lines.iter().enumerate() {
+        // Columns are counted as the tokenizer does, so the span marks the right characters
+        let length = line.replace('\r', "").replace('\t', "    ").chars().count();
+        if length > max_line_length {
+            warnings.push(Warning {
+                message: format!(
+                    "This line is {} characters long, more than the maximum of {}",
+                    length, max_line_length
+                ),
+                span: Span::in_lines(lines, row, max_line_length, length),
+            });
+        }
+    }
+    warnings
+}
+
+// Binary operators and assignments are written with one space on each side: a = b + 1
+fn operator_spacing_warnings(tokens: &[Token]) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    if is_postfix_step(tokens) {
+        return warnings;
+    }
+    for (index, token) in tokens.iter().enumerate() {
+        let TokenData::Symbol { symbol_type } = &token.data else {
+            continue;
+        };
+        if !is_spaced_operator(symbol_type) {
+            continue;
+        }
+
+        let previous = index.checked_sub(1).map(|previous| &tokens[previous]);
+        // A minus or star without a value before it is a negation or a *rest parameter, which are written against their operand
+        if matches!(symbol_type, SymbolType::Minus | SymbolType::Star) && !previous.is_some_and(ends_value) {
+            continue;
+        }
+
+        // A token of the next line after an unclosed bracket does not count as being next to the operator
+        let spaced_before = previous.is_none_or(|previous| previous.span.row != token.span.row || previous.span.col_end + 1 == token.span.col_start);
+        let spaced_after = tokens
+            .get(index + 1)
+            .is_none_or(|next| next.span.row != token.span.row || token.span.col_end + 1 == next.span.col_start);
+        if !spaced_before || !spaced_after {
+            warnings.push(Warning {
+                message: format!(
+                    "Put one space on each side of {}",
+                    tokenizer::get_symbol_from_type(symbol_type)
+                ),
+                span: token.span(),
+            });
+        }
+    }
+    warnings
+}
+
+fn is_spaced_operator(symbol_type: &SymbolType) -> bool {
+    matches!(
+        symbol_type,
+        SymbolType::Equals
+            | SymbolType::PlusEquals
+            | SymbolType::Plus
+            | SymbolType::Minus
+            | SymbolType::Star
+            | SymbolType::Slash
+            | SymbolType::SlashSlash
+            | SymbolType::Hat
+            | SymbolType::EqualsEquals
+            | SymbolType::NotEquals
+            | SymbolType::GreaterThan
+            | SymbolType::GreaterThanOrEqual
+            | SymbolType::LessThan
+            | SymbolType::LessThanOrEqual
+    )
+}
+
+// Whether the token is the end of a value, so that an operator after it has two operands
+// x++ and x--, whose two pluses or minuses are a single operator written against the variable
+fn is_postfix_step(tokens: &[Token]) -> bool {
+    match tokens {
+        [Token {
+            data: TokenData::Variable { .. },
+            ..
+        }, first @ Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Plus | SymbolType::Minus,
+            },
+            ..
+        }, second] => second.data == first.data && first.span.col_end == second.span.col_start,
+        _ => false,
+    }
+}
+
+fn ends_value(token: &Token) -> bool {
+    match &token.data {
+        TokenData::Symbol { symbol_type } => matches!(
+            symbol_type,
+            SymbolType::ParenthesisClosed
+                | SymbolType::SquareBracketClosed
+                | SymbolType::CurlyBracketClosed
+                | SymbolType::True
+                | SymbolType::False
+        ),
+        _ => true,
+    }
+}
+
+// A function defined right after another one at the top level needs blank lines before it.
+// The comments just above a function belong to it, so the blank lines are counted above those.
+fn function_spacing_warnings(
+    lines: &[&str],
+    base_expressions: &[BaseExpr<()>],
+    blank_lines_between_functions: usize,
+) -> Vec<Warning> {
+    let is_blank = |row: usize| lines[row].trim().is_empty();
+    let is_comment = |row: usize| !is_blank(row) && tokenizer::strip_comment(lines[row]).trim().is_empty();
+
+    let mut warnings = Vec::new();
+    for pair in base_expressions.windows(2) {
+        let [previous, function] = pair else {
+            continue;
+        };
+        if !matches!(previous.data, BaseExprData::FunctionDefinition { .. })
+            || !matches!(function.data, BaseExprData::FunctionDefinition { .. })
+        {
+            continue;
+        }
+
+        let mut row = function.span.row;
+        while row > previous.span.row + 1 && is_comment(row - 1) {
+            row -= 1;
+        }
+        let mut blank_lines = 0;
+        while row > previous.span.row + 1 && is_blank(row - 1) {
+            blank_lines += 1;
+            row -= 1;
+        }
+
+        if blank_lines < blank_lines_between_functions {
+            warnings.push(Warning {
+                message: match blank_lines_between_functions {
+                    1 => String::from("Put a blank line between functions"),
+                    count => format!("Put {} blank lines between functions", count),
+                },
+                span: function.span.first_columns("fun".len()),
+            });
+        }
+    }
+    warnings
+}
@@ -0,0 +1,647 @@
+use std::collections::HashMap;
+
+use crate::desugarer;
+use crate::parser;
+use crate::parser::Accessor;
+use crate::parser::BaseExpr;
+use crate::parser::BaseExprData;
+use crate::parser::Pattern;
+use crate::parser::RecExpr;
+use crate::parser::RecExprData;
+use crate::tokenizer;
+use crate::tokenizer::Error;
+use crate::tokenizer::Span;
+use crate::tokenizer::SymbolType;
+use crate::tokenizer::Token;
+use crate::tokenizer::TokenData;
+use crate::tokenizer::TokenLine;
+use crate::typechecker;
+use crate::typechecker::Type;
+
+// Position based queries on a program, meant to back editor integrations such as hover information.
+// Positions use the same convention as the spans stored in the AST:
+// rows and columns start at 0, col_start is inclusive and col_end is exclusive.
+
+// Finds the type of the innermost typed expression covering the given position in the file
+pub fn type_at(path: &std::path::PathBuf, row: usize, col: usize) -> Option<Type> {
+    let content = std::fs::read_to_string(path).ok()?;
+
+    let lines: Vec<&str> = content.split("\n").collect();
+
+    type_at_in_lines(lines, row, col)
+}
+
+// Finds the type of the innermost typed expression covering the given position in the given lines.
+// Returns None when the program does not typecheck or when no expression covers the position.
+pub fn type_at_in_lines(lines: Vec<&str>, row: usize, col: usize) -> Option<Type> {
+    let base_expressions = parser::parse_strings(lines).ok()?;
+    let desugared_base_expressions = desugarer::desugar(base_expressions);
+    let (typed_base_expressions, typed_functions) =
+        typechecker::type_check_program(desugared_base_expressions, false).ok()?;
+
+    if let Some(found_type) = find_type_in_base_expressions(&typed_base_expressions, row, col) {
+        return Some(found_type);
+    }
+
+    // Function bodies are only typed through their instances, so the first instance covering
+    // the position is used
+    typed_functions
+        .iter()
+        .find_map(|function| find_type_in_base_expressions(&function.content, row, col))
+}
+
+fn find_type_in_base_expressions(
+    base_expressions: &[BaseExpr<Type>],
+    row: usize,
+    col: usize,
+) -> Option<Type> {
+    base_expressions
+        .iter()
+        .find_map(|base_expr| find_type_in_base_expr(base_expr, row, col))
+}
+
+fn find_type_in_base_expr(base_expr: &BaseExpr<Type>, row: usize, col: usize) -> Option<Type> {
+    match &base_expr.data {
+        BaseExprData::Simple { expr } => find_type_in_rec_expr(expr, row, col),
+        BaseExprData::VariableAssignment { var_name, expr }
+        | BaseExprData::PlusEqualsStatement { var_name, expr } => {
+            // The variable being assigned to is the first token of the line
+            let var_name_end = base_expr.span.col_start + var_name.chars().count();
+            let var_name_span = Span {
+                row_end: base_expr.span.row,
+                col_end: var_name_end,
+                byte_end: base_expr.span.byte_start + var_name.len(),
+                ..base_expr.span()
+            };
+            if var_name_span.covers(row, col) {
+                return Some(expr.generic_data.clone());
+            }
+            find_type_in_rec_expr(expr, row, col)
+        }
+        BaseExprData::IfStatement {
+            condition,
+            body,
+            else_statement,
+        }
+        | BaseExprData::ElseIfStatement {
+            condition,
+            body,
+            else_statement,
+        } => find_type_in_rec_expr(condition, row, col)
+            .or_else(|| find_type_in_base_expressions(body, row, col))
+            .or_else(|| {
+                else_statement
+                    .as_ref()
+                    .and_then(|else_statement| find_type_in_base_expr(else_statement, row, col))
+            }),
+        BaseExprData::ElseStatement { body } => find_type_in_base_expressions(body, row, col),
+        BaseExprData::ForLoop {
+            until: condition,
+            body,
+            ..
+        }
+        | BaseExprData::WhileLoop { condition, body } => find_type_in_rec_expr(condition, row, col)
+            .or_else(|| find_type_in_base_expressions(body, row, col)),
+        BaseExprData::FunctionDefinition { body, .. } => {
+            find_type_in_base_expressions(body, row, col)
+        }
+        BaseExprData::Return { return_value } => return_value
+            .as_ref()
+            .and_then(|return_value| find_type_in_rec_expr(return_value, row, col)),
+        BaseExprData::StructDefinition { .. } | BaseExprData::Break | BaseExprData::Continue => None,
+        BaseExprData::Yield { value } => find_type_in_rec_expr(value, row, col),
+        BaseExprData::Delete { .. } | BaseExprData::Swap { .. } => None,
+        BaseExprData::DestructuringAssignment { expr, .. } | BaseExprData::Defer { expr } => {
+            find_type_in_rec_expr(expr, row, col)
+        }
+        BaseExprData::FieldAssignment { path, expr, .. } => find_type_in_path(path, row, col)
+            .or_else(|| find_type_in_rec_expr(expr, row, col)),
+        BaseExprData::IndexAssignment {
+            path, index, expr, ..
+        } => find_type_in_path(path, row, col)
+            .or_else(|| find_type_in_rec_expr(index, row, col))
+            .or_else(|| find_type_in_rec_expr(expr, row, col)),
+        BaseExprData::With { resource, body, .. } => find_type_in_rec_expr(resource, row, col)
+            .or_else(|| find_type_in_base_expressions(body, row, col)),
+        BaseExprData::Match { subject, arms } => find_type_in_rec_expr(subject, row, col).or_else(|| {
+            arms.iter()
+                .find_map(|arm| find_type_in_base_expressions(&arm.body, row, col))
+        }),
+    }
+}
+
+fn find_type_in_path(path: &[Accessor<Type>], row: usize, col: usize) -> Option<Type> {
+    path.iter().find_map(|accessor| match accessor {
+        Accessor::Index(index) => find_type_in_rec_expr(index, row, col),
+        Accessor::Field { .. } => None,
+    })
+}
+
+fn find_type_in_rec_expr(rec_expr: &RecExpr<Type>, row: usize, col: usize) -> Option<Type> {
+    if !rec_expr.span().covers(row, col) {
+        return None;
+    }
+
+    // Prefer the innermost expression covering the position
+    rec_expr_children(rec_expr)
+        .into_iter()
+        .find_map(|child| find_type_in_rec_expr(child, row, col))
+        .or_else(|| Some(rec_expr.generic_data.clone()))
+}
+
+fn rec_expr_children<T: Clone>(rec_expr: &RecExpr<T>) -> Vec<&RecExpr<T>> {
+    match &rec_expr.data {
+        RecExprData::Variable { .. }
+        | RecExprData::Number { .. }
+        | RecExprData::Float { .. }
+        | RecExprData::String { .. }
+        | RecExprData::Boolean { .. }
+        | RecExprData::Access { .. } => Vec::new(),
+        RecExprData::Assign { right, .. }
+        | RecExprData::Minus { right }
+        | RecExprData::Not { right }
+        | RecExprData::KeywordArgument { value: right, .. } => vec![right],
+        RecExprData::Conditional {
+            condition,
+            then_value,
+            else_value,
+        } => vec![then_value, condition, else_value],
+        RecExprData::Add { left, right }
+        | RecExprData::Subtract { left, right }
+        | RecExprData::Multiply { left, right }
+        | RecExprData::Divide { left, right }
+        | RecExprData::FloorDivide { left, right }
+        | RecExprData::Power { left, right }
+        | RecExprData::Or { left, right }
+        | RecExprData::And { left, right }
+        | RecExprData::Equals { left, right }
+        | RecExprData::NotEquals { left, right }
+        | RecExprData::GreaterThan { left, right }
+        | RecExprData::LessThan { left, right }
+        | RecExprData::GreaterThanOrEqual { left, right }
+        | RecExprData::LessThanOrEqual { left, right } => vec![left, right],
+        RecExprData::FunctionCall { args, .. } => args.iter().collect(),
+        RecExprData::List { elements } | RecExprData::Tuple { elements } => elements.iter().collect(),
+        RecExprData::ListAccess { list, index } => vec![list, index],
+        RecExprData::Slice { list, start, end } => {
+            std::iter::once(list).chain(start).chain(end).map(|expr| &**expr).collect()
+        }
+        RecExprData::Dict { entries } => entries.iter().flat_map(|(key, value)| [key, value]).collect(),
+    }
+}
+
+// A single place in the source where an identifier is written
+#[derive(Clone, PartialEq, Debug)]
+pub struct SymbolOccurrence {
+    pub name: String,
+    // The row of the definition of the function that owns the binding, None for global bindings
+    pub scope: Option<usize>,
+    pub span: Span,
+    // Whether this occurrence binds the name (assignment, parameter, for loop variable or function name)
+    pub is_binding: bool,
+}
+
+impl SymbolOccurrence {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+// A function body forms its own scope in rosy: parameters and every name
+// assigned in the body are local, all other names refer to the enclosing scope
+struct FunctionScope {
+    definition_row: usize,
+    parent: Option<usize>,
+    locals: Vec<String>,
+}
+
+struct ScopeCollection {
+    scopes: Vec<FunctionScope>,
+    // The innermost function scope that owns each row
+    row_owners: HashMap<usize, usize>,
+}
+
+// Collects every identifier in the program, resolved to the scope that binds it
+pub fn collect_symbol_occurrences(lines: Vec<&str>) -> Result<Vec<SymbolOccurrence>, Error> {
+    Ok(collect_symbols(lines)?.0)
+}
+
+// The occurrences together with the scopes they were resolved in
+fn collect_symbols(lines: Vec<&str>) -> Result<(Vec<SymbolOccurrence>, ScopeCollection), Error> {
+    let token_lines = tokenizer::tokenize(lines.clone())?;
+    let base_expressions = parser::parse_strings(lines)?;
+
+    let mut scope_collection = ScopeCollection {
+        scopes: Vec::new(),
+        row_owners: HashMap::new(),
+    };
+    collect_function_scopes(&base_expressions, None, &mut scope_collection);
+
+    let mut occurrences = Vec::new();
+
+    for token_line in token_lines.iter() {
+        for (i, token) in token_line.tokens.iter().enumerate() {
+            let TokenData::Variable { name } = &token.data else {
+                continue;
+            };
+
+            let previous_symbol = match i {
+                0 => None,
+                _ => match &token_line.tokens[i - 1].data {
+                    TokenData::Symbol { symbol_type } => Some(symbol_type.clone()),
+                    _ => None,
+                },
+            };
+            let next_symbol = match token_line.tokens.get(i + 1) {
+                Some(Token {
+                    data: TokenData::Symbol { symbol_type },
+                    ..
+                }) => Some(symbol_type.clone()),
+                _ => None,
+            };
+
+            let row_owner = scope_collection.row_owners.get(&token.span.row).copied();
+            let defined_function = scope_collection
+                .scopes
+                .iter()
+                .position(|scope| scope.definition_row == token.span.row);
+
+            let (innermost_scope, is_binding) = match (previous_symbol, defined_function) {
+                // The name of a function is bound in the scope the function is defined in
+                (Some(SymbolType::Fun), _) => (row_owner, true),
+                // Parameters are bound in the scope of the function itself
+                (_, Some(function_index)) => (Some(function_index), true),
+                (Some(SymbolType::For), _) | (Some(SymbolType::As), _) => (row_owner, true),
+                (Some(SymbolType::Case), _) if name != "_" => (row_owner, true),
+                (None, None) => (
+                    row_owner,
+                    next_symbol == Some(SymbolType::Equals)
+                        || next_symbol == Some(SymbolType::PlusEquals),
+                ),
+                _ => (row_owner, false),
+            };
+
+            occurrences.push(SymbolOccurrence {
+                name: name.clone(),
+                scope: resolve_scope(name, innermost_scope, &scope_collection.scopes)
+                    .map(|scope_index| scope_collection.scopes[scope_index].definition_row),
+                span: token.span,
+                is_binding,
+            });
+        }
+    }
+
+    Ok((occurrences, scope_collection))
+}
+
+fn resolve_scope(name: &String, innermost_scope: Option<usize>, scopes: &[FunctionScope]) -> Option<usize> {
+    let mut current_scope = innermost_scope;
+    while let Some(scope_index) = current_scope {
+        if scopes[scope_index].locals.contains(name) {
+            return Some(scope_index);
+        }
+        current_scope = scopes[scope_index].parent;
+    }
+    None
+}
+
+fn collect_function_scopes(
+    base_expressions: &[BaseExpr<()>],
+    owner: Option<usize>,
+    scope_collection: &mut ScopeCollection,
+) {
+    for base_expr in base_expressions {
+        if let Some(owner) = owner {
+            scope_collection.row_owners.insert(base_expr.span.row, owner);
+        }
+
+        match &base_expr.data {
+            BaseExprData::FunctionDefinition {
+                args,
+                body,
+                ..
+            } => {
+                let mut locals = parser::parameter_names(args);
+                collect_assigned_names(body, &mut locals);
+
+                scope_collection.scopes.push(FunctionScope {
+                    definition_row: base_expr.span.row,
+                    parent: owner,
+                    locals,
+                });
+                let function_index = scope_collection.scopes.len() - 1;
+
+                collect_function_scopes(body, Some(function_index), scope_collection);
+            }
+            BaseExprData::IfStatement {
+                body,
+                else_statement,
+                ..
+            }
+            | BaseExprData::ElseIfStatement {
+                body,
+                else_statement,
+                ..
+            } => {
+                collect_function_scopes(body, owner, scope_collection);
+                if let Some(else_statement) = else_statement {
+                    collect_function_scopes(
+                        std::slice::from_ref(&**else_statement),
+                        owner,
+                        scope_collection,
+                    );
+                }
+            }
+            BaseExprData::ElseStatement { body }
+            | BaseExprData::ForLoop { body, .. }
+            | BaseExprData::WhileLoop { body, .. }
+            | BaseExprData::With { body, .. } => {
+                collect_function_scopes(body, owner, scope_collection);
+            }
+            BaseExprData::Match { arms, .. } => {
+                for arm in arms {
+                    if let Some(owner) = owner {
+                        scope_collection.row_owners.insert(arm.span.row, owner);
+                    }
+                    collect_function_scopes(&arm.body, owner, scope_collection);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Collects the names bound by a function body, without descending into nested function bodies
+fn collect_assigned_names(base_expressions: &[BaseExpr<()>], names: &mut Vec<String>) {
+    for base_expr in base_expressions {
+        let assigned_names = match &base_expr.data {
+            BaseExprData::VariableAssignment { var_name, .. }
+            | BaseExprData::PlusEqualsStatement { var_name, .. } => std::slice::from_ref(var_name),
+            BaseExprData::DestructuringAssignment { var_names, .. } => var_names.as_slice(),
+            BaseExprData::ForLoop { var_name, .. } | BaseExprData::With { var_name, .. } => {
+                std::slice::from_ref(var_name)
+            }
+            BaseExprData::FunctionDefinition { fun_name, .. }
+            | BaseExprData::StructDefinition {
+                name: fun_name, ..
+            } => std::slice::from_ref(fun_name),
+            _ => &[],
+        };
+        for assigned_name in assigned_names {
+            if !names.contains(assigned_name) {
+                names.push(assigned_name.clone());
+            }
+        }
+
+        match &base_expr.data {
+            BaseExprData::IfStatement {
+                body,
+                else_statement,
+                ..
+            }
+            | BaseExprData::ElseIfStatement {
+                body,
+                else_statement,
+                ..
+            } => {
+                collect_assigned_names(body, names);
+                if let Some(else_statement) = else_statement {
+                    collect_assigned_names(std::slice::from_ref(&**else_statement), names);
+                }
+            }
+            BaseExprData::ElseStatement { body }
+            | BaseExprData::ForLoop { body, .. }
+            | BaseExprData::WhileLoop { body, .. }
+            | BaseExprData::With { body, .. } => {
+                collect_assigned_names(body, names);
+            }
+            BaseExprData::Match { arms, .. } => {
+                for arm in arms {
+                    if let Pattern::Binding(name) = &arm.pattern {
+                        if !names.contains(name) {
+                            names.push(name.clone());
+                        }
+                    }
+                    collect_assigned_names(&arm.body, names);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Maps every identifier in a program to the place where it is defined.
+// Symbols are identified by their name together with the scope that binds them,
+// the definition of a symbol is the first place where it is bound.
+pub struct SymbolTable {
+    occurrences: Vec<SymbolOccurrence>,
+    // The definition row of the function around each function, None for functions defined at the top level
+    scope_parents: HashMap<usize, Option<usize>>,
+}
+
+impl SymbolTable {
+    pub fn build(lines: Vec<&str>) -> Result<SymbolTable, Error> {
+        let (occurrences, scope_collection) = collect_symbols(lines)?;
+        let scopes = &scope_collection.scopes;
+        let scope_parents = scopes
+            .iter()
+            .map(|scope| (scope.definition_row, scope.parent.map(|parent| scopes[parent].definition_row)))
+            .collect();
+
+        Ok(SymbolTable {
+            occurrences,
+            scope_parents,
+        })
+    }
+
+    // Whether names bound in the outer scope can be seen from the inner scope, which is the case
+    // for the scope itself, the functions it is defined in and the top level
+    fn is_visible_from(&self, outer: Option<usize>, inner: Option<usize>) -> bool {
+        let mut current = inner;
+        loop {
+            if current == outer {
+                return true;
+            }
+            match current {
+                Some(definition_row) => current = self.scope_parents.get(&definition_row).copied().flatten(),
+                None => return false,
+            }
+        }
+    }
+
+    pub fn occurrences(&self) -> &[SymbolOccurrence] {
+        &self.occurrences
+    }
+
+    pub fn occurrence_at(&self, row: usize, col: usize) -> Option<&SymbolOccurrence> {
+        self.occurrences.iter().find(|occurrence| {
+            occurrence.span().covers(row, col)
+        })
+    }
+
+    // All occurrences of the same symbol as the given occurrence, in source order
+    pub fn references_of(&self, symbol: &SymbolOccurrence) -> Vec<&SymbolOccurrence> {
+        self.occurrences
+            .iter()
+            .filter(|occurrence| occurrence.name == symbol.name && occurrence.scope == symbol.scope)
+            .collect()
+    }
+
+    // Returns None for symbols which are not defined by the program itself, such as print
+    pub fn definition_of(&self, symbol: &SymbolOccurrence) -> Option<&SymbolOccurrence> {
+        self.references_of(symbol)
+            .into_iter()
+            .find(|occurrence| occurrence.is_binding)
+    }
+
+    // The definitions of all symbols which are bound but never read
+    pub fn unused_definitions(&self) -> Vec<&SymbolOccurrence> {
+        self.occurrences
+            .iter()
+            .filter(|occurrence| self.definition_of(occurrence) == Some(*occurrence))
+            .filter(|definition| {
+                self.references_of(definition)
+                    .iter()
+                    .all(|reference| reference.is_binding)
+            })
+            .collect()
+    }
+}
+
+fn no_symbol_error(lines: &[&str], row: usize, col: usize) -> Error {
+    Error::located(
+        "No symbol found at this position".to_string(),
+        Span::in_lines(lines, row, col, col + 1),
+    )
+}
+
+// Finds the definition of the symbol at the given position.
+// Returns Ok(None) when the symbol is not defined by the program itself, such as print.
+pub fn find_definition(lines: Vec<&str>, row: usize, col: usize) -> Result<Option<SymbolOccurrence>, Error> {
+    let symbol_table = SymbolTable::build(lines.clone())?;
+
+    let Some(symbol) = symbol_table.occurrence_at(row, col) else {
+        return Err(no_symbol_error(&lines, row, col));
+    };
+
+    Ok(symbol_table.definition_of(symbol).cloned())
+}
+
+// Finds all symbols which are defined but never read, such as unused variables, parameters and functions
+pub fn find_unused_symbols(lines: Vec<&str>) -> Result<Vec<SymbolOccurrence>, Error> {
+    let symbol_table = SymbolTable::build(lines)?;
+
+    Ok(symbol_table
+        .unused_definitions()
+        .into_iter()
+        .cloned()
+        .collect())
+}
+
+// Finds all occurrences of the symbol at the given position, including the position itself
+pub fn find_references(lines: Vec<&str>, row: usize, col: usize) -> Result<Vec<SymbolOccurrence>, Error> {
+    let symbol_table = SymbolTable::build(lines.clone())?;
+
+    let Some(symbol) = symbol_table.occurrence_at(row, col) else {
+        return Err(no_symbol_error(&lines, row, col));
+    };
+
+    Ok(symbol_table
+        .references_of(symbol)
+        .into_iter()
+        .cloned()
+        .collect())
+}
+
+// Renames the symbol at the given position and all of its references, returning the rewritten lines
+pub fn rename_symbol(
+    lines: Vec<&str>,
+    row: usize,
+    col: usize,
+    new_name: &str,
+) -> Result<Vec<String>, Error> {
+    // The new name must be tokenized as a single variable, which rules out keywords and symbols
+    match tokenizer::tokenize(vec![new_name])?.as_slice() {
+        [TokenLine { tokens, .. }]
+            if matches!(tokens.as_slice(), [Token { data: TokenData::Variable { name }, .. }] if name == new_name) => {}
+        _ => {
+            return Err(Error::SimpleError {
+                message: format!("'{}' is not a valid name", new_name),
+            })
+        }
+    }
+
+    let symbol_table = SymbolTable::build(lines.clone())?;
+    let Some(symbol) = symbol_table.occurrence_at(row, col) else {
+        return Err(no_symbol_error(&lines, row, col));
+    };
+    let references: Vec<SymbolOccurrence> = symbol_table.references_of(symbol).into_iter().cloned().collect();
+
+    // Names like print are not defined by the program itself, and can thus not be renamed
+    if !references.iter().any(|reference| reference.is_binding) {
+        return Err(Error::located(
+            format!(
+                "Cannot rename '{}' because it is not defined in this program",
+                references[0].name
+            ),
+            references
+                .iter()
+                .find(|reference| reference.span.covers(row, col))
+                .map_or(Span::in_lines(&lines, row, col, col + 1), |reference| reference.span),
+        ));
+    }
+
+    // The new name must not already mean something where the symbol can be seen, such as the function
+    // a parameter belongs to or a global with that name
+    let collision = symbol_table.occurrences().iter().find(|occurrence| {
+        occurrence.name == new_name
+            && occurrence.name != symbol.name
+            && symbol_table.is_visible_from(occurrence.scope, symbol.scope)
+    });
+    if let Some(collision) = collision {
+        return Err(rename_collision_error(&symbol.name, new_name, collision));
+    }
+
+    let mut source = lines.join("\n");
+
+    // Replace from back to front, so that the byte offsets of earlier references stay valid
+    let mut sorted_references = references.clone();
+    sorted_references.sort_by_key(|reference| std::cmp::Reverse(reference.span.byte_start));
+
+    for reference in sorted_references {
+        source.replace_range(reference.span.byte_range(), new_name);
+    }
+    let new_lines: Vec<String> = source.split('\n').map(|line| line.to_string()).collect();
+
+    // A name used in a function inside the scope of the symbol can still end up meaning something else,
+    // so every occurrence must belong to the same symbol as before the rename. The renamed lines are
+    // tokenized the same way, so the occurrences are in the same order.
+    let renamed_occurrences = collect_symbol_occurrences(new_lines.iter().map(|line| line.as_str()).collect())?;
+    let mut renamed_symbols = HashMap::new();
+    let mut original_symbols = HashMap::new();
+    for (old, new) in symbol_table.occurrences().iter().zip(&renamed_occurrences) {
+        let old_symbol = (old.name.as_str(), old.scope);
+        let new_symbol = (new.name.as_str(), new.scope);
+        if *renamed_symbols.entry(old_symbol).or_insert(new_symbol) != new_symbol
+            || *original_symbols.entry(new_symbol).or_insert(old_symbol) != old_symbol
+        {
+            let collision = match old.name == new_name {
+                true => old,
+                false => new,
+            };
+            return Err(rename_collision_error(&symbol.name, new_name, collision));
+        }
+    }
+
+    Ok(new_lines)
+}
+
+fn rename_collision_error(old_name: &str, new_name: &str, collision: &SymbolOccurrence) -> Error {
+    Error::located(
+        format!(
+            "Cannot rename '{}' to '{}', as '{}' already refers to something else here",
+            old_name, new_name, new_name
+        ),
+        collision.span(),
+    )
+}
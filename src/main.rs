@@ -1,15 +1,28 @@
 use clap::Parser;
+use rosy::compiler::Target;
+use rosy::difftest;
 use rosy::interpreter;
+use rosy::interpreter::TraceMode;
+use rosy::language::LanguageOptions;
+use rosy::linter::StyleOptions;
+use rosy::optimiser::OptLevel;
+use rosy::optimiser::OptimisationOptions;
+use rosy::optimiser::Pass;
 use rosy::parser;
 use rosy::pipeline;
 use rosy::tokenizer;
+use rosy::trace::Trace;
 use std::env;
+use std::sync::Arc;
+use std::sync::Mutex;
 
 // Language features:
 /*
 BaseExpr:
 - Expr
 - Variable assignment: [var_name] = Expr
+- Field assignment: [var_name].[field_name] = Expr
+- Element assignment: [var_name][Expr] = Expr, for lists and dicts
 - if statement:
     if Expr
         BaseExpr+
@@ -22,6 +35,9 @@ BaseExpr:
 - for loop:
     for [var_name] in Expr
         BaseExpr+
+- while loop:
+    while Expr
+        BaseExpr+
 - Function definition:
     fun [fun_name](arg*)
         BaseExpr+
@@ -30,6 +46,7 @@ BaseExpr:
         [var_name]*
 - Return statement: return
 - Break statement: break
+- Continue statement: continue
 
 Expr:
 - Addition: Expr + Expr
@@ -39,19 +56,28 @@ Expr:
 - Negative number: - Expr
 - Variables: [var_name]
 - Integer numbers
+- Float numbers: 3.14, 1e-3
 - Strings: "[str]"
 - False: false
 - True: true
 - Or operator: Expr or Expr
 - And operator: Expr and Expr
 - Equals operator: Expr == Expr
-- struct access: [struct_name].[var_name]
+- struct access: [var_name].[field_name]
 - function call: [fun_name](arg*)
+- struct construction: [struct_name](Expr*), one value per field in order
+- Dict: {Expr: Expr*}, with integer, boolean or string keys
+- Tuple: (Expr, Expr*), with a trailing comma for a single element: (Expr,)
+- Indexing: Expr[Expr], into a list by position or into a dict by key, into a tuple by an integer literal
+- Slicing: Expr[Expr:Expr], of a list or string, where either bound can be left out and bounds outside of it are clamped
 
 Default functions:
 - print(String)
 - print(Integer)
 - print(Boolean)
+
+Comments:
+- Everything after a # outside of a string: a = 1 # the count
 */
 
 // Search for a pattern in a file and display the lines that contain it.
@@ -61,13 +87,128 @@ enum Command {
     Run {
         /// The path to the file to read
         path: std::path::PathBuf,
+        /// Use a fixed random seed and a frozen clock, so the output is identical across runs
+        #[arg(long)]
+        deterministic: bool,
+        /// Print statistics about the execution after the program has finished
+        #[arg(long)]
+        stats: bool,
+        /// Pause at calls to breakpoint() and on errors to inspect the program, as rosy debug does
+        #[arg(long)]
+        debug: bool,
+        /// Save the random seed, the time and the key presses read by the run to this file
+        #[arg(long, value_name = "TRACE")]
+        record: Option<std::path::PathBuf>,
+        /// Run with the random seed, the time and the key presses saved by --record
+        #[arg(long, value_name = "TRACE", conflicts_with = "record")]
+        replay: Option<std::path::PathBuf>,
+        /// Shared libraries to load native builtins from
+        #[cfg(feature = "plugins")]
+        #[arg(long = "plugin")]
+        plugins: Vec<std::path::PathBuf>,
+        /// Compile the functions which are called often to native code
+        #[cfg(feature = "jit")]
+        #[arg(long)]
+        jit: bool,
+        /// The optimisation passes to run before the program is interpreted
+        #[arg(long = "opt", value_enum, value_name = "LEVEL", default_value = "0")]
+        opt_level: OptLevel,
+        #[command(flatten)]
+        optimisation: OptimisationArgs,
+        /// Arguments passed to the program, given after --
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Run the test functions of the source file, whose names start with test_
+    Test {
+        path: std::path::PathBuf,
+        /// Only run the tests whose name contains this text
+        #[arg(long)]
+        filter: Option<String>,
+        /// Stop at the first failing test
+        #[arg(long)]
+        fail_fast: bool,
+    },
+    /// Time the benchmark functions of the source file, whose names start with bench_
+    Bench {
+        path: std::path::PathBuf,
+        /// Untimed runs of every benchmark before it is measured
+        #[arg(long, default_value_t = 3)]
+        warmup: usize,
+        /// Timed runs of every benchmark
+        #[arg(long, default_value_t = 10)]
+        iterations: usize,
+        /// Only run the benchmarks whose name contains this text
+        #[arg(long)]
+        filter: Option<String>,
     },
     /// Compile the source file to an executable
-    Compile { path: std::path::PathBuf },
-    /// Typecheck the source file
-    Typecheck { path: std::path::PathBuf },
-    /// Debug the source file
+    Compile {
+        path: std::path::PathBuf,
+        /// What to write: an executable, the three address code as a .rir file, JavaScript as a .js file or Python as a .py file next to the source
+        #[arg(long, value_enum, default_value_t = Emit::Exe)]
+        emit: Emit,
+        /// The platform to write the executable for, the platform rosy runs on by default
+        #[arg(long, value_enum, default_value_t = Target::host())]
+        target: Target,
+        /// The optimisation passes to run, -O0 compiles the program as written
+        #[arg(short = 'O', long = "opt", value_enum, value_name = "LEVEL", default_value = "1")]
+        opt_level: OptLevel,
+        #[command(flatten)]
+        optimisation: OptimisationArgs,
+    },
+    /// Compile a .rir file of three address code, as written by compile --emit ir, and run it
+    RunIr { path: std::path::PathBuf },
+    /// Typecheck the source file, or all .rosy files in a directory and its subdirectories
+    Typecheck {
+        path: std::path::PathBuf,
+        /// Reprint the source with the inferred types as comments
+        #[arg(long)]
+        annotate: bool,
+        /// Print the program as source after desugaring and typechecking, with --annotate the types are added as comments
+        #[arg(long)]
+        decompile: bool,
+    },
+    /// Run the source file, or all .rosy files in a directory, on every execution engine and compare what they print
+    Difftest {
+        path: std::path::PathBuf,
+        /// The optimisation passes to run before the program is compiled or transpiled
+        #[arg(short = 'O', long = "opt", value_enum, value_name = "LEVEL", default_value = "1")]
+        opt_level: OptLevel,
+        #[command(flatten)]
+        optimisation: OptimisationArgs,
+    },
+    /// Rename the symbol at a position and all of its references
+    Rename {
+        path: std::path::PathBuf,
+        /// The line of the symbol, starting at 1
+        row: usize,
+        /// The column of the symbol, starting at 1
+        col: usize,
+        new_name: String,
+    },
+    /// Run the source file, pausing at calls to breakpoint() and on errors to inspect the program
     Debug { path: std::path::PathBuf },
+    /// Warn about likely mistakes in the source file which do not stop it from running, such as loops which never end
+    Lint {
+        path: std::path::PathBuf,
+        /// Also check the layout: long lines, spaces around operators and blank lines between functions
+        #[arg(long)]
+        style: bool,
+        /// With --style, the longest a line may be in characters, 0 allows any length
+        #[arg(long, default_value_t = 100)]
+        max_line_length: usize,
+        /// With --style, the blank lines wanted between two functions
+        #[arg(long, default_value_t = 1)]
+        blank_lines_between_functions: usize,
+    },
+    /// Fix common mistakes in the source file, such as = in a condition and unused variables
+    Fix {
+        path: std::path::PathBuf,
+        /// List the fixes without changing the file
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[derive(Parser)]
@@ -75,30 +216,463 @@ struct Cli {
     /// The path to the file to read
     #[clap(subcommand)]
     command: Command,
+    #[command(flatten)]
+    language: LanguageArgs,
+    /// Log the internals of the pipeline stages to stderr, for debugging the toolchain
+    #[arg(long, global = true, value_enum, default_value_t = LogLevel::Off)]
+    log_level: LogLevel,
+    /// Check the invariants of the program after every pass of the toolchain, always on in debug builds
+    #[arg(long, global = true)]
+    verify: bool,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Emit {
+    Exe,
+    Ir,
+    Js,
+    Python,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn filter(self) -> tracing::level_filters::LevelFilter {
+        use tracing::level_filters::LevelFilter;
+        match self {
+            LogLevel::Off => LevelFilter::OFF,
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+// Language options given on the command line, these override the ones read from rosy.toml
+#[derive(clap::Args)]
+struct LanguageArgs {
+    /// The number of spaces that make up one level of indentation
+    #[arg(long, global = true)]
+    indentation_width: Option<usize>,
+    /// Typecheck the program before running it
+    #[arg(long, global = true)]
+    strict_typing: bool,
+    /// Reject arithmetic and comparisons mixing integers and floats without a float() conversion
+    #[arg(long, global = true)]
+    strict_numerics: bool,
+    /// Make / on two integers give a float, use // for integer division
+    #[arg(long, global = true)]
+    true_division: bool,
+    /// Enable an experimental language feature
+    #[arg(long = "experimental", global = true)]
+    experimental_features: Vec<String>,
+}
+
+#[derive(clap::Args)]
+struct OptimisationArgs {
+    /// Leave out a pass which the optimisation level would run, can be given more than once
+    #[arg(long = "disable-pass", value_enum, value_name = "PASS")]
+    disabled_passes: Vec<Pass>,
+    /// Print what each optimisation pass changed
+    #[arg(long)]
+    print_opt_stats: bool,
+}
+
+impl OptimisationArgs {
+    fn options(self, level: OptLevel) -> OptimisationOptions {
+        OptimisationOptions {
+            level,
+            disabled: self.disabled_passes,
+            print_stats: self.print_opt_stats,
+        }
+    }
+}
+
+// Reads the language options for the source file, exits when they are invalid
+fn load_language_options(path: &std::path::Path, args: &LanguageArgs) -> LanguageOptions {
+    let mut language = match LanguageOptions::load_for_source(path) {
+        Ok(language) => language,
+        Err(err) => {
+            pipeline::print_error(&err, &Vec::new());
+            std::process::exit(1);
+        }
+    };
+
+    match args.indentation_width {
+        Some(0) => {
+            println!("Error: --indentation-width must be at least 1");
+            std::process::exit(1);
+        }
+        Some(width) => language.indentation_width = width,
+        None => {}
+    }
+    if args.strict_typing {
+        language.strict_typing = true;
+    }
+    if args.strict_numerics {
+        language.strict_numerics = true;
+    }
+    if args.true_division {
+        language.true_division = true;
+    }
+    for feature in args.experimental_features.iter() {
+        if let Err(err) = language.enable_feature(feature) {
+            pipeline::print_error(&err, &Vec::new());
+            std::process::exit(1);
+        }
+    }
+
+    return language;
 }
 
 pub fn main() {
     //env::set_var("RUST_BACKTRACE", "1");
-    let args = Cli::parse();
+    let cli = Cli::parse();
 
-    match args.command {
-        Command::Run { path } => match pipeline::run_pipeline_from_path(&path) {
-            Ok(_) => {}
-            Err(err) => println!("{err}"),
-        },
-        Command::Compile { path } => {
-            let output_path = std::path::PathBuf::from("output.exe");
-            match pipeline::run_compilation_pipeline_from_path(&path, &output_path) {
+    // Logs go to stderr so they do not mix with the output of the program
+    tracing_subscriber::fmt()
+        .with_max_level(cli.log_level.filter())
+        .with_writer(std::io::stderr)
+        .without_time()
+        .init();
+    rosy::verifier::set_enabled(cli.verify);
+
+    match cli.command {
+        Command::Run {
+            path,
+            deterministic,
+            stats,
+            debug,
+            record,
+            replay,
+            #[cfg(feature = "plugins")]
+            plugins,
+            #[cfg(feature = "jit")]
+            jit,
+            opt_level,
+            optimisation,
+            args,
+        } => {
+            let mut engine = rosy::engine::Engine::new();
+            engine.options.language = load_language_options(&path, &cli.language);
+            engine.options.deterministic = deterministic;
+            engine.options.collect_stats = stats;
+            engine.options.debug = debug;
+            engine.options.optimisation = optimisation.options(opt_level);
+            #[cfg(feature = "jit")]
+            {
+                engine.options.jit = jit;
+            }
+            let recorded_trace = Arc::new(Mutex::new(Trace::default()));
+            if record.is_some() {
+                engine.options.trace = TraceMode::Record(recorded_trace.clone());
+            }
+            if let Some(replay) = replay {
+                match Trace::read(&replay) {
+                    Ok(trace) => engine.options.trace = TraceMode::Replay(trace),
+                    Err(err) => {
+                        pipeline::print_error(&err, &Vec::new());
+                        std::process::exit(1);
+                    }
+                }
+            }
+            engine.options.program_arguments = args;
+            #[cfg(feature = "plugins")]
+            for plugin in plugins {
+                if let Err(err) = engine.load_plugin(&plugin) {
+                    pipeline::print_error(&err, &Vec::new());
+                    return;
+                }
+            }
+            match pipeline::run_engine_pipeline_from_path(&path, &engine) {
+                Ok(context) => {
+                    if stats {
+                        print!("\n{}", context.stats);
+                    }
+                }
+                Err(err) => println!("{err}"),
+            }
+            // The trace is also saved when the run failed, so the failure can be replayed
+            if let Some(record) = record {
+                if let Err(err) = recorded_trace.lock().unwrap().write(&record) {
+                    pipeline::print_error(&err, &Vec::new());
+                }
+            }
+        }
+        Command::Test {
+            path,
+            filter,
+            fail_fast,
+        } => {
+            let mut engine = rosy::engine::Engine::new();
+            engine.options.language = load_language_options(&path, &cli.language);
+            let options = rosy::testrunner::TestOptions { filter, fail_fast };
+            match pipeline::run_test_pipeline_from_path(&path, &engine, &options) {
+                Ok(true) => {}
+                Ok(false) => std::process::exit(1),
+                Err(err) => {
+                    println!("{err}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Bench {
+            path,
+            warmup,
+            iterations,
+            filter,
+        } => {
+            let mut engine = rosy::engine::Engine::new();
+            engine.options.language = load_language_options(&path, &cli.language);
+            let options = rosy::benchmark::BenchOptions {
+                warmup,
+                iterations,
+                filter,
+            };
+            match pipeline::run_bench_pipeline_from_path(&path, &engine, &options) {
+                Ok(_) => {}
+                Err(err) => println!("{err}"),
+            }
+        }
+        Command::Compile {
+            path,
+            emit: Emit::Ir,
+            opt_level,
+            optimisation,
+            ..
+        } => {
+            let language = load_language_options(&path, &cli.language);
+            let output_path = path.with_extension("rir");
+            match pipeline::run_emit_ir_pipeline_from_path(&path, &language, &optimisation.options(opt_level)) {
+                Ok(ir) => match std::fs::write(&output_path, ir) {
+                    Ok(_) => println!("Wrote the IR to {}", output_path.display()),
+                    Err(err) => println!("Error writing {}: {}", output_path.display(), err),
+                },
+                Err(err) => println!("{err}"),
+            }
+        }
+        Command::Compile {
+            path,
+            emit: Emit::Js,
+            opt_level,
+            optimisation,
+            ..
+        } => {
+            let language = load_language_options(&path, &cli.language);
+            let output_path = path.with_extension("js");
+            match pipeline::run_emit_js_pipeline_from_path(&path, &language, &optimisation.options(opt_level)) {
+                Ok(javascript) => match std::fs::write(&output_path, javascript) {
+                    Ok(_) => println!("Wrote the JavaScript to {}", output_path.display()),
+                    Err(err) => println!("Error writing {}: {}", output_path.display(), err),
+                },
+                Err(err) => println!("{err}"),
+            }
+        }
+        Command::Compile {
+            path,
+            emit: Emit::Python,
+            opt_level,
+            optimisation,
+            ..
+        } => {
+            let language = load_language_options(&path, &cli.language);
+            let output_path = path.with_extension("py");
+            match pipeline::run_emit_python_pipeline_from_path(&path, &language, &optimisation.options(opt_level)) {
+                Ok(python) => match std::fs::write(&output_path, python) {
+                    Ok(_) => println!("Wrote the Python to {}", output_path.display()),
+                    Err(err) => println!("Error writing {}: {}", output_path.display(), err),
+                },
+                Err(err) => println!("{err}"),
+            }
+        }
+        Command::RunIr { path } => {
+            // The program is run right away, so it is compiled for this platform
+            let target = Target::host();
+            let output_path = path.with_extension(target.executable_extension());
+            if let Err(err) = pipeline::run_ir_compilation_pipeline_from_path(&path, &output_path, target) {
+                println!("{err}");
+                std::process::exit(1);
+            }
+            // A bare file name would be looked up on the PATH instead of in the current directory
+            let program = match output_path.is_relative() {
+                true => std::path::Path::new(".").join(&output_path),
+                false => output_path.clone(),
+            };
+            match std::process::Command::new(&program).status() {
+                Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+                Err(err) => {
+                    println!("Error running {}: {}", output_path.display(), err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Compile {
+            path,
+            emit: Emit::Exe,
+            target,
+            opt_level,
+            optimisation,
+        } => {
+            let output_path = std::path::PathBuf::from("output").with_extension(target.executable_extension());
+            let language = load_language_options(&path, &cli.language);
+            let optimisation = optimisation.options(opt_level);
+            match pipeline::run_compilation_pipeline_from_path(&path, &output_path, &language, target, &optimisation) {
                 Ok(_) => {}
                 Err(err) => println!("{err}"),
             }
             //exewriter::write_exe_file(&path.with_extension("exe")).unwrap();
             //println!("Compiled to {}", path.with_extension("exe").display());
         }
-        Command::Typecheck { path } => match pipeline::run_typecheck_pipeline_from_path(&path) {
-            Ok(_) => println!("Typecheck passed"),
-            Err(err) => println!("Typecheck error: {err}"),
+        Command::Typecheck {
+            path,
+            annotate,
+            decompile,
+        } if path.is_dir() => {
+            if annotate || decompile {
+                println!("Error: --annotate and --decompile need a single source file");
+                std::process::exit(1);
+            }
+
+            let files = match pipeline::find_source_files(&path) {
+                Ok(files) => files,
+                Err(err) => {
+                    pipeline::print_error(&err, &Vec::new());
+                    std::process::exit(1);
+                }
+            };
+            // Every file uses the rosy.toml closest to it
+            let files = files
+                .into_iter()
+                .map(|file| {
+                    let language = load_language_options(&file, &cli.language);
+                    (file, language)
+                })
+                .collect();
+
+            let results = pipeline::typecheck_files(files);
+            if !pipeline::print_typecheck_report(&results) {
+                std::process::exit(1);
+            }
+        }
+        Command::Typecheck {
+            path,
+            annotate,
+            decompile,
+        } => {
+            let language = load_language_options(&path, &cli.language);
+            if decompile {
+                match pipeline::run_decompile_pipeline_from_path(&path, &language, annotate) {
+                    Ok(source) => print!("{source}"),
+                    Err(err) => println!("Typecheck error: {err}"),
+                }
+            } else if annotate {
+                match pipeline::run_annotate_pipeline_from_path(&path, &language) {
+                    Ok(annotated_source) => print!("{annotated_source}"),
+                    Err(err) => println!("Typecheck error: {err}"),
+                }
+            } else {
+                match pipeline::run_typecheck_pipeline_from_path(&path, &language) {
+                    Ok(_) => println!("Typecheck passed"),
+                    Err(err) => println!("Typecheck error: {err}"),
+                }
+            }
+        }
+        Command::Difftest {
+            path,
+            opt_level,
+            optimisation,
+        } => {
+            let files = match path.is_dir() {
+                true => match pipeline::find_source_files(&path) {
+                    Ok(files) => files,
+                    Err(err) => {
+                        pipeline::print_error(&err, &Vec::new());
+                        std::process::exit(1);
+                    }
+                },
+                false => Vec::from([path]),
+            };
+            // Every file uses the rosy.toml closest to it
+            let files = files
+                .into_iter()
+                .map(|file| {
+                    let language = load_language_options(&file, &cli.language);
+                    (file, language)
+                })
+                .collect();
+
+            let results = pipeline::difftest_files(files, &difftest::available_backends(), &optimisation.options(opt_level));
+            if !pipeline::print_difftest_report(&results) {
+                std::process::exit(1);
+            }
+        }
+        Command::Rename {
+            path,
+            row,
+            col,
+            new_name,
+        } => match pipeline::run_rename_pipeline_from_path(
+            &path,
+            row.saturating_sub(1),
+            col.saturating_sub(1),
+            &new_name,
+        ) {
+            Ok(reference_count) => println!("Renamed {reference_count} occurrences to {new_name}"),
+            Err(err) => println!("{err}"),
         },
-        Command::Debug { path: _ } => {}
+        Command::Lint {
+            path,
+            style,
+            max_line_length,
+            blank_lines_between_functions,
+        } => {
+            let language = load_language_options(&path, &cli.language);
+            let style = style.then_some(StyleOptions {
+                max_line_length,
+                blank_lines_between_functions,
+            });
+            match pipeline::run_lint_pipeline_from_path(&path, &language, style.as_ref()) {
+                Ok(0) => println!("No warnings"),
+                Ok(count) => {
+                    println!("{count} warnings");
+                    std::process::exit(1);
+                }
+                Err(err) => {
+                    println!("{err}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Fix { path, dry_run } => match pipeline::run_fix_pipeline_from_path(&path, dry_run) {
+            Ok(fixes) => {
+                for fix in fixes.iter() {
+                    println!("line {}: {}", fix.row() + 1, fix.message);
+                }
+                match (fixes.len(), dry_run) {
+                    (0, _) => println!("No fixes to apply"),
+                    (count, true) => println!("{count} fixes can be applied"),
+                    (count, false) => println!("Applied {count} fixes"),
+                }
+            }
+            Err(err) => println!("{err}"),
+        },
+        Command::Debug { path } => {
+            let mut engine = rosy::engine::Engine::new();
+            engine.options.language = load_language_options(&path, &cli.language);
+            engine.options.debug = true;
+            if let Err(err) = pipeline::run_engine_pipeline_from_path(&path, &engine) {
+                println!("{err}");
+            }
+        }
     }
 }
@@ -1,4 +1,197 @@
+use std::fmt;
+
+use crate::consteval;
+use crate::deadcode;
+use crate::inliner;
 use crate::instructionsimplifier::AssemblyInstruction;
+use crate::language::LanguageOptions;
+use crate::parser::BaseExpr;
+use crate::tokenizer::Error;
+use crate::typechecker::FunctionType;
+use crate::typechecker::Type;
+use crate::verifier;
+
+// How much work goes into making the program faster, selected with -O for compile and --opt for run
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Default, clap::ValueEnum)]
+pub enum OptLevel {
+	// The program runs as written
+	#[default]
+	#[value(name = "0")]
+	O0,
+	// Constant folding, dead code elimination and the peephole optimisations of the assembly
+	#[value(name = "1")]
+	O1,
+	// Also inlining and specialization of functions
+	#[value(name = "2")]
+	O2,
+}
+
+// The optimisation passes, in the order they run
+#[derive(Clone, Copy, PartialEq, Debug, clap::ValueEnum)]
+pub enum Pass {
+	// Puts the values of calls whose arguments are all the same literal into the called function
+	Specialize,
+	// Replaces calls to functions returning a single expression by that expression
+	Inline,
+	// Computes the expressions on literals, see consteval
+	Fold,
+	// Removes statements which can never run, see deadcode
+	#[value(name = "dce")]
+	DeadCode,
+	// Removes instructions which do nothing from the compiled assembly
+	Peephole,
+}
+
+pub const PASSES: [Pass; 5] = [Pass::Specialize, Pass::Inline, Pass::Fold, Pass::DeadCode, Pass::Peephole];
+
+impl Pass {
+	// The lowest level the pass runs at
+	fn level(&self) -> OptLevel {
+		match self {
+			Pass::Fold | Pass::DeadCode | Pass::Peephole => OptLevel::O1,
+			Pass::Specialize | Pass::Inline => OptLevel::O2,
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			Pass::Specialize => "specialize",
+			Pass::Inline => "inline",
+			Pass::Fold => "fold",
+			Pass::DeadCode => "dce",
+			Pass::Peephole => "peephole",
+		}
+	}
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct OptimisationOptions {
+	pub level: OptLevel,
+	// Passes which do not run even though the level includes them, set with --disable-pass
+	pub disabled: Vec<Pass>,
+	// Prints what each pass changed once the passes have run
+	pub print_stats: bool,
+}
+
+impl OptimisationOptions {
+	pub fn with_level(level: OptLevel) -> OptimisationOptions {
+		OptimisationOptions {
+			level,
+			..Default::default()
+		}
+	}
+
+	pub fn runs(&self, pass: Pass) -> bool {
+		self.level >= pass.level() && !self.disabled.contains(&pass)
+	}
+}
+
+// How many changes each pass that ran made, for --print-opt-stats
+#[derive(Clone, Default, Debug)]
+pub struct OptimisationStats {
+	pub changes: Vec<(Pass, usize)>,
+}
+
+impl OptimisationStats {
+	pub fn record(&mut self, pass: Pass, changes: usize) {
+		self.changes.push((pass, changes));
+	}
+
+	pub fn changes_by(&self, pass: Pass) -> Option<usize> {
+		self.changes
+			.iter()
+			.find(|(recorded, _)| *recorded == pass)
+			.map(|(_, changes)| *changes)
+	}
+}
+
+impl fmt::Display for OptimisationStats {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "Optimisation passes:")?;
+		for pass in PASSES {
+			let description = match (pass, self.changes_by(pass)) {
+				(_, None) => "did not run".to_string(),
+				(Pass::Specialize, Some(count)) => format!("{} parameters specialized", count),
+				(Pass::Inline, Some(count)) => format!("{} calls inlined", count),
+				(Pass::Fold, Some(count)) => format!("{} expressions folded", count),
+				(Pass::DeadCode, Some(count)) => format!("{} statements removed", count),
+				(Pass::Peephole, Some(count)) => format!("{} instructions removed", count),
+			};
+			writeln!(f, "    {}: {}", pass.name(), description)?;
+		}
+		Ok(())
+	}
+}
+
+// Runs the passes across function calls, which work on the program before it is typechecked
+pub fn optimise_calls(
+	mut base_expressions: Vec<BaseExpr<()>>,
+	options: &OptimisationOptions,
+	stats: &mut OptimisationStats,
+) -> Result<Vec<BaseExpr<()>>, Error> {
+	if options.runs(Pass::Specialize) {
+		let (optimised, specialized) = inliner::specialize_functions(base_expressions)?;
+		verifier::verify("specialization", &optimised[..], verifier::check_program)?;
+		base_expressions = optimised;
+		stats.record(Pass::Specialize, specialized);
+	}
+	if options.runs(Pass::Inline) {
+		let (optimised, inlined) = inliner::inline_functions(base_expressions)?;
+		verifier::verify("inlining", &optimised[..], verifier::check_program)?;
+		base_expressions = optimised;
+		stats.record(Pass::Inline, inlined);
+	}
+	Ok(base_expressions)
+}
+
+// Runs all passes on a program which is interpreted without typechecking it first
+pub fn optimise_untyped_program(
+	base_expressions: Vec<BaseExpr<()>>,
+	language: &LanguageOptions,
+	options: &OptimisationOptions,
+	stats: &mut OptimisationStats,
+) -> Result<Vec<BaseExpr<()>>, Error> {
+	let mut base_expressions = optimise_calls(base_expressions, options, stats)?;
+	if options.runs(Pass::Fold) {
+		let (optimised, evaluated) = consteval::evaluate_constants_untyped(base_expressions, language)?;
+		verifier::verify("constant evaluation", &optimised[..], verifier::check_program)?;
+		base_expressions = optimised;
+		stats.record(Pass::Fold, evaluated);
+	}
+	if options.runs(Pass::DeadCode) {
+		let (optimised, removed) = deadcode::eliminate_dead_code(base_expressions)?;
+		verifier::verify("dead code elimination", &optimised[..], verifier::check_program)?;
+		base_expressions = optimised;
+		stats.record(Pass::DeadCode, removed);
+	}
+	Ok(base_expressions)
+}
+
+// Runs the passes which work on the typed program, after the typechecker has run on the result of optimise_calls
+pub fn optimise_typed_program(
+	typed_program: &mut (Vec<BaseExpr<Type>>, Vec<FunctionType>),
+	language: &LanguageOptions,
+	options: &OptimisationOptions,
+	stats: &mut OptimisationStats,
+) -> Result<(), Error> {
+	if options.runs(Pass::Fold) {
+		let evaluated = consteval::evaluate_constants(typed_program, language)?;
+		verifier::verify("constant evaluation", &*typed_program, verifier::check_typed_program)?;
+		stats.record(Pass::Fold, evaluated);
+	}
+	if options.runs(Pass::DeadCode) {
+		let (base_expressions, mut removed) = deadcode::eliminate_dead_code(std::mem::take(&mut typed_program.0))?;
+		typed_program.0 = base_expressions;
+		for function in typed_program.1.iter_mut() {
+			let (content, removed_in_function) = deadcode::eliminate_dead_code(std::mem::take(&mut function.content))?;
+			function.content = content;
+			removed += removed_in_function;
+		}
+		verifier::verify("dead code elimination", &*typed_program, verifier::check_typed_program)?;
+		stats.record(Pass::DeadCode, removed);
+	}
+	Ok(())
+}
 
 pub fn optimise_assembly(assembly: &Vec<AssemblyInstruction>) -> Vec<AssemblyInstruction> {
 	// Placeholder for optimisation logic
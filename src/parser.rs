@@ -1,20 +1,187 @@
+use crate::language::LanguageOptions;
+use crate::stack;
 use crate::tokenizer;
 use crate::tokenizer::Error;
+use crate::tokenizer::Span;
 use crate::tokenizer::SymbolType;
 use crate::tokenizer::Token;
 use crate::tokenizer::TokenData;
 use crate::tokenizer::TokenLine;
+use crate::visitor;
+use crate::visitor::Folder;
+use crate::visitor::Visitor;
 use std::f32::consts::{E, PI};
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct BaseExpr<T: Clone> {
     pub data: BaseExprData<T>,
-    pub row: usize,
-    pub col_start: usize,
-    pub col_end: usize,
+    pub span: Span,
     pub generic_data: T,
 }
 
+impl<T: Clone> BaseExpr<T> {
+    pub fn new(data: BaseExprData<T>, span: Span, generic_data: T) -> BaseExpr<T> {
+        BaseExpr {
+            data,
+            span,
+            generic_data,
+        }
+    }
+
+    // Replaces the generic data of this expression and of everything inside it, keeping the spans.
+    // The function is called on every expression, parents before their children, in source order.
+    pub fn map_data<U: Clone>(self, f: &mut impl FnMut(T) -> U) -> BaseExpr<U> {
+        // Nested blocks recurse once per level, see stack::ensure_stack
+        stack::ensure_stack(|| {
+            let span = self.span();
+            let generic_data = f(self.generic_data);
+            let data = match self.data {
+                BaseExprData::Simple { expr } => BaseExprData::Simple {
+                    expr: expr.map_data(f),
+                },
+                BaseExprData::VariableAssignment { var_name, expr } => {
+                    BaseExprData::VariableAssignment {
+                        var_name,
+                        expr: expr.map_data(f),
+                    }
+                }
+                BaseExprData::PlusEqualsStatement { var_name, expr } => {
+                    BaseExprData::PlusEqualsStatement {
+                        var_name,
+                        expr: expr.map_data(f),
+                    }
+                }
+                BaseExprData::IfStatement {
+                    condition,
+                    body,
+                    else_statement,
+                } => BaseExprData::IfStatement {
+                    condition: condition.map_data(f),
+                    body: map_body_data(body, f),
+                    else_statement: else_statement
+                        .map(|else_statement| Box::new(else_statement.map_data(f))),
+                },
+                BaseExprData::ElseIfStatement {
+                    condition,
+                    body,
+                    else_statement,
+                } => BaseExprData::ElseIfStatement {
+                    condition: condition.map_data(f),
+                    body: map_body_data(body, f),
+                    else_statement: else_statement
+                        .map(|else_statement| Box::new(else_statement.map_data(f))),
+                },
+                BaseExprData::ElseStatement { body } => BaseExprData::ElseStatement {
+                    body: map_body_data(body, f),
+                },
+                BaseExprData::ForLoop {
+                    var_name,
+                    until,
+                    body,
+                } => BaseExprData::ForLoop {
+                    var_name,
+                    until: until.map_data(f),
+                    body: map_body_data(body, f),
+                },
+                BaseExprData::WhileLoop { condition, body } => BaseExprData::WhileLoop {
+                    condition: condition.map_data(f),
+                    body: map_body_data(body, f),
+                },
+                BaseExprData::FunctionDefinition {
+                    fun_name,
+                    args,
+                    body,
+                } => BaseExprData::FunctionDefinition {
+                    fun_name,
+                    args: args.into_iter().map(|arg| arg.map_data(f)).collect(),
+                    body: map_body_data(body, f),
+                },
+                BaseExprData::Return { return_value } => BaseExprData::Return {
+                    return_value: return_value.map(|return_value| return_value.map_data(f)),
+                },
+                BaseExprData::StructDefinition { name, fields } => {
+                    BaseExprData::StructDefinition { name, fields }
+                }
+                BaseExprData::FieldAssignment {
+                    object,
+                    path,
+                    field,
+                    expr,
+                } => BaseExprData::FieldAssignment {
+                    object,
+                    path: path.into_iter().map(|accessor| accessor.map_data(f)).collect(),
+                    field,
+                    expr: expr.map_data(f),
+                },
+                BaseExprData::IndexAssignment {
+                    object,
+                    path,
+                    index,
+                    expr,
+                } => BaseExprData::IndexAssignment {
+                    object,
+                    path: path.into_iter().map(|accessor| accessor.map_data(f)).collect(),
+                    index: index.map_data(f),
+                    expr: expr.map_data(f),
+                },
+                BaseExprData::DestructuringAssignment { var_names, expr } => {
+                    BaseExprData::DestructuringAssignment {
+                        var_names,
+                        expr: expr.map_data(f),
+                    }
+                }
+                BaseExprData::Break => BaseExprData::Break,
+                BaseExprData::Continue => BaseExprData::Continue,
+                BaseExprData::Yield { value } => BaseExprData::Yield {
+                    value: value.map_data(f),
+                },
+                BaseExprData::Delete { var_name } => BaseExprData::Delete { var_name },
+                BaseExprData::Swap {
+                    first_name,
+                    second_name,
+                } => BaseExprData::Swap {
+                    first_name,
+                    second_name,
+                },
+                BaseExprData::Defer { expr } => BaseExprData::Defer {
+                    expr: expr.map_data(f),
+                },
+                BaseExprData::With {
+                    resource,
+                    var_name,
+                    body,
+                } => BaseExprData::With {
+                    resource: resource.map_data(f),
+                    var_name,
+                    body: map_body_data(body, f),
+                },
+                BaseExprData::Match { subject, arms } => BaseExprData::Match {
+                    subject: subject.map_data(f),
+                    arms: arms
+                        .into_iter()
+                        .map(|arm| {
+                            let span = arm.span();
+                            MatchArm::new(arm.pattern, map_body_data(arm.body, f), span)
+                        })
+                        .collect(),
+                },
+            };
+            BaseExpr::new(data, span, generic_data)
+        })
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+fn map_body_data<T: Clone, U: Clone>(
+    body: Vec<BaseExpr<T>>,
+    f: &mut impl FnMut(T) -> U,
+) -> Vec<BaseExpr<U>> {
+    body.into_iter().map(|base_expr| base_expr.map_data(f)).collect()
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum BaseExprData<T: Clone> {
     Simple {
@@ -24,10 +191,29 @@ pub enum BaseExprData<T: Clone> {
         var_name: String,
         expr: RecExpr<T>,
     },
+    // Assigns each element of a tuple or list to its own variable: low, high = minmax(numbers)
+    DestructuringAssignment {
+        var_names: Vec<String>,
+        expr: RecExpr<T>,
+    },
     PlusEqualsStatement {
         var_name: String,
         expr: RecExpr<T>,
     },
+    // Changes one field of a struct in place: point.x = 5, or shape.center.x = 5 through the path
+    FieldAssignment {
+        object: String,
+        path: Vec<Accessor<T>>,
+        field: String,
+        expr: RecExpr<T>,
+    },
+    // Changes one element of a list or one entry of a dict in place: ages["ann"] = 31, or grid[1][2] = 0 through the path
+    IndexAssignment {
+        object: String,
+        path: Vec<Accessor<T>>,
+        index: RecExpr<T>,
+        expr: RecExpr<T>,
+    },
     IfStatement {
         condition: RecExpr<T>,
         body: Vec<BaseExpr<T>>,
@@ -46,26 +232,369 @@ pub enum BaseExprData<T: Clone> {
         until: RecExpr<T>,
         body: Vec<BaseExpr<T>>,
     },
+    // Runs its body for as long as the condition is true, checked before every iteration
+    WhileLoop {
+        condition: RecExpr<T>,
+        body: Vec<BaseExpr<T>>,
+    },
     FunctionDefinition {
         fun_name: String,
-        args: Vec<String>,
+        args: Vec<Parameter<T>>,
         body: Vec<BaseExpr<T>>,
     },
+    // A struct with the given field names, constructed by calling it like a function: Point(1, 2)
+    StructDefinition {
+        name: String,
+        fields: Vec<String>,
+    },
     Return {
         return_value: Option<RecExpr<T>>,
     },
     Break,
+    // Skips the rest of the body of the innermost loop, going on with its next iteration
+    Continue,
+    Yield {
+        value: RecExpr<T>,
+    },
+    Delete {
+        var_name: String,
+    },
+    // Exchanges the values of two variables, written as swap a, b
+    Swap {
+        first_name: String,
+        second_name: String,
+    },
+    Defer {
+        expr: RecExpr<T>,
+    },
+    // A block which closes its resource when it ends, however it ends
+    With {
+        resource: RecExpr<T>,
+        var_name: String,
+        body: Vec<BaseExpr<T>>,
+    },
+    // Runs the body of the first case whose pattern matches the value
+    Match {
+        subject: RecExpr<T>,
+        arms: Vec<MatchArm<T>>,
+    },
+}
+
+// A step from a variable towards the field or element a nested assignment changes,
+// such as the .center of shape.center.x = 5 and the [1] of grid[1][2] = 0
+#[derive(PartialEq, Debug, Clone)]
+pub enum Accessor<T: Clone> {
+    Field { name: String, span: Span },
+    Index(RecExpr<T>),
+}
+
+impl<T: Clone> Accessor<T> {
+    pub fn map_data<U: Clone>(self, f: &mut impl FnMut(T) -> U) -> Accessor<U> {
+        match self {
+            Accessor::Field { name, span } => Accessor::Field { name, span },
+            Accessor::Index(index) => Accessor::Index(index.map_data(f)),
+        }
+    }
+}
+
+// A parameter of a function definition, with the value it gets when a call leaves it out:
+// fun greet(name, greeting = "hello"). Parameters with a default come after those without one.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Parameter<T: Clone> {
+    pub name: String,
+    pub default: Option<RecExpr<T>>,
+    // Written as *rest, the last parameter collects the arguments after the others into a list
+    pub rest: bool,
+}
+
+impl<T: Clone> Parameter<T> {
+    pub fn new(name: &str) -> Parameter<T> {
+        Parameter {
+            name: name.to_string(),
+            default: None,
+            rest: false,
+        }
+    }
+
+    pub fn map_data<U: Clone>(self, f: &mut impl FnMut(T) -> U) -> Parameter<U> {
+        Parameter {
+            name: self.name,
+            default: self.default.map(|default| default.map_data(f)),
+            rest: self.rest,
+        }
+    }
+}
+
+// The names of the parameters, in order
+pub fn parameter_names<T: Clone>(parameters: &[Parameter<T>]) -> Vec<String> {
+    parameters.iter().map(|parameter| parameter.name.clone()).collect()
+}
+
+// The parameter every argument of a call goes to, in the order of the arguments. Positional arguments fill the
+// parameters from the start and a keyword argument the parameter it names: draw(1, y = 2).
+// The caller checks the number of arguments, a positional argument past the last parameter keeps its position.
+pub fn argument_positions<T: Clone, S: AsRef<str>>(
+    function_name: &str,
+    param_names: &[S],
+    args: &[RecExpr<T>],
+) -> Result<Vec<usize>, Error> {
+    let mut positions: Vec<usize> = Vec::new();
+    for (index, arg) in args.iter().enumerate() {
+        let RecExprData::KeywordArgument { name, .. } = &arg.data else {
+            positions.push(index);
+            continue;
+        };
+        let Some(position) = param_names.iter().position(|param_name| param_name.as_ref() == name) else {
+            return Err(Error::located(
+                format!("{} has no parameter {}", function_name, name),
+                arg.span(),
+            ));
+        };
+        if positions.contains(&position) {
+            return Err(Error::located(
+                format!("The parameter {} of {} is given a value twice", name, function_name),
+                arg.span(),
+            ));
+        }
+        positions.push(position);
+    }
+    Ok(positions)
+}
+
+// The values of the arguments in the order of the parameters, see argument_positions.
+// A parameter before the last one given which the call gives no value for is None.
+pub fn place_arguments<A>(positions: &[usize], values: Vec<A>) -> Vec<Option<A>> {
+    let length = positions.iter().map(|position| position + 1).max().unwrap_or(0);
+    let mut placed: Vec<Option<A>> = (0..length).map(|_| None).collect();
+    for (position, value) in positions.iter().zip(values) {
+        placed[*position] = Some(value);
+    }
+    placed
+}
+
+// The placed arguments with those at and after the *rest parameter collected into one value by collect,
+// see place_arguments. A call which gives no argument for the rest parameter collects an empty list.
+pub fn collect_rest_arguments<A>(
+    mut placed: Vec<Option<A>>,
+    rest_position: usize,
+    collect: impl FnOnce(Vec<A>) -> A,
+) -> Vec<Option<A>> {
+    let rest: Vec<A> = match placed.len() > rest_position {
+        true => placed.split_off(rest_position).into_iter().flatten().collect(),
+        false => Vec::new(),
+    };
+    placed.resize_with(rest_position, || None);
+    placed.push(Some(collect(rest)));
+    placed
+}
+
+// The position of the *rest parameter, if there is one
+pub fn rest_position<T: Clone>(parameters: &[Parameter<T>]) -> Option<usize> {
+    parameters.iter().position(|parameter| parameter.rest)
+}
+
+// Whether any of the arguments of a call is given by name
+pub fn has_keyword_arguments<T: Clone>(args: &[RecExpr<T>]) -> bool {
+    args.iter()
+        .any(|arg| matches!(arg.data, RecExprData::KeywordArgument { .. }))
+}
+
+// One case of a match block, the span is that of its case line
+#[derive(PartialEq, Debug, Clone)]
+pub struct MatchArm<T: Clone> {
+    pub pattern: Pattern,
+    pub body: Vec<BaseExpr<T>>,
+    pub span: Span,
+}
+
+impl<T: Clone> MatchArm<T> {
+    pub fn new(pattern: Pattern, body: Vec<BaseExpr<T>>, span: Span) -> MatchArm<T> {
+        MatchArm {
+            pattern,
+            body,
+            span,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum Pattern {
+    Number(i64),
+    String(String),
+    Boolean(bool),
+    // Matches any value, which is assigned to the variable
+    Binding(String),
+    // Matches any value, written as _
+    Wildcard,
 }
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct RecExpr<T: Clone> {
     pub data: RecExprData<T>,
-    pub row: usize,
-    pub col_start: usize,
-    pub col_end: usize,
+    pub span: Span,
     pub generic_data: T,
 }
 
+impl<T: Clone> RecExpr<T> {
+    pub fn new(data: RecExprData<T>, span: Span, generic_data: T) -> RecExpr<T> {
+        RecExpr {
+            data,
+            span,
+            generic_data,
+        }
+    }
+
+    // Replaces the generic data of this expression and of all of its operands, keeping the spans.
+    // The function is called on every expression, parents before their operands, in source order.
+    pub fn map_data<U: Clone>(self, f: &mut impl FnMut(T) -> U) -> RecExpr<U> {
+        // Nested expressions recurse once per level, see stack::ensure_stack
+        stack::ensure_stack(|| {
+            let span = self.span();
+            let generic_data = f(self.generic_data);
+            let data = match self.data {
+                RecExprData::Variable { name } => RecExprData::Variable { name },
+                RecExprData::Number { number } => RecExprData::Number { number },
+                RecExprData::Float { value } => RecExprData::Float { value },
+                RecExprData::String { value } => RecExprData::String { value },
+                RecExprData::Boolean { value } => RecExprData::Boolean { value },
+                RecExprData::Assign {
+                    variable_name,
+                    right,
+                } => RecExprData::Assign {
+                    variable_name,
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::Add { left, right } => RecExprData::Add {
+                    left: Box::new(left.map_data(f)),
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::Subtract { left, right } => RecExprData::Subtract {
+                    left: Box::new(left.map_data(f)),
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::Multiply { left, right } => RecExprData::Multiply {
+                    left: Box::new(left.map_data(f)),
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::Divide { left, right } => RecExprData::Divide {
+                    left: Box::new(left.map_data(f)),
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::FloorDivide { left, right } => RecExprData::FloorDivide {
+                    left: Box::new(left.map_data(f)),
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::Power { left, right } => RecExprData::Power {
+                    left: Box::new(left.map_data(f)),
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::Or { left, right } => RecExprData::Or {
+                    left: Box::new(left.map_data(f)),
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::And { left, right } => RecExprData::And {
+                    left: Box::new(left.map_data(f)),
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::Equals { left, right } => RecExprData::Equals {
+                    left: Box::new(left.map_data(f)),
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::NotEquals { left, right } => RecExprData::NotEquals {
+                    left: Box::new(left.map_data(f)),
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::GreaterThan { left, right } => RecExprData::GreaterThan {
+                    left: Box::new(left.map_data(f)),
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::LessThan { left, right } => RecExprData::LessThan {
+                    left: Box::new(left.map_data(f)),
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::GreaterThanOrEqual { left, right } => RecExprData::GreaterThanOrEqual {
+                    left: Box::new(left.map_data(f)),
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::LessThanOrEqual { left, right } => RecExprData::LessThanOrEqual {
+                    left: Box::new(left.map_data(f)),
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::Minus { right } => RecExprData::Minus {
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::Not { right } => RecExprData::Not {
+                    right: Box::new(right.map_data(f)),
+                },
+                RecExprData::Access { object, variable } => {
+                    RecExprData::Access { object, variable }
+                }
+                RecExprData::FunctionCall {
+                    function_name,
+                    args,
+                } => RecExprData::FunctionCall {
+                    function_name,
+                    args: args.into_iter().map(|arg| arg.map_data(f)).collect(),
+                },
+                RecExprData::List { elements } => RecExprData::List {
+                    elements: elements
+                        .into_iter()
+                        .map(|element| element.map_data(f))
+                        .collect(),
+                },
+                RecExprData::ListAccess { list, index } => RecExprData::ListAccess {
+                    list: Box::new(list.map_data(f)),
+                    index: Box::new(index.map_data(f)),
+                },
+                RecExprData::Slice { list, start, end } => RecExprData::Slice {
+                    list: Box::new(list.map_data(f)),
+                    start: start.map(|start| Box::new(start.map_data(f))),
+                    end: end.map(|end| Box::new(end.map_data(f))),
+                },
+                RecExprData::KeywordArgument { name, value } => RecExprData::KeywordArgument {
+                    name,
+                    value: Box::new(value.map_data(f)),
+                },
+                RecExprData::Conditional {
+                    condition,
+                    then_value,
+                    else_value,
+                } => {
+                    // Operands in source order: then_value if condition else else_value
+                    let then_value = Box::new(then_value.map_data(f));
+                    let condition = Box::new(condition.map_data(f));
+                    RecExprData::Conditional {
+                        condition,
+                        then_value,
+                        else_value: Box::new(else_value.map_data(f)),
+                    }
+                }
+                RecExprData::Dict { entries } => RecExprData::Dict {
+                    entries: entries
+                        .into_iter()
+                        .map(|(key, value)| (key.map_data(f), value.map_data(f)))
+                        .collect(),
+                },
+                RecExprData::Tuple { elements } => RecExprData::Tuple {
+                    elements: elements
+                        .into_iter()
+                        .map(|element| element.map_data(f))
+                        .collect(),
+                },
+            };
+            RecExpr::new(data, span, generic_data)
+        })
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum RecExprData<T: Clone> {
     Variable {
@@ -74,6 +603,9 @@ pub enum RecExprData<T: Clone> {
     Number {
         number: i64,
     },
+    Float {
+        value: f64,
+    },
     String {
         value: String,
     },
@@ -100,6 +632,11 @@ pub enum RecExprData<T: Clone> {
         left: Box<RecExpr<T>>,
         right: Box<RecExpr<T>>,
     },
+    // Integer division rounded down, written as //
+    FloorDivide {
+        left: Box<RecExpr<T>>,
+        right: Box<RecExpr<T>>,
+    },
     Power {
         left: Box<RecExpr<T>>,
         right: Box<RecExpr<T>>,
@@ -153,19 +690,45 @@ pub enum RecExprData<T: Clone> {
     List {
         elements: Vec<RecExpr<T>>,
     },
+    // Indexing into any expression that results in a list, such as grid[1][0]
     ListAccess {
-        variable: String,
+        list: Box<RecExpr<T>>,
         index: Box<RecExpr<T>>,
     },
+    // A new list or string with the elements from start up to, but not including, end: xs[1:4], xs[:n].
+    // A left out start is the first element and a left out end the last, bounds outside of the value are clamped.
+    Slice {
+        list: Box<RecExpr<T>>,
+        start: Option<Box<RecExpr<T>>>,
+        end: Option<Box<RecExpr<T>>>,
+    },
+    // A dict literal, its entries as (key, value) in source order: {"a": 1, "b": 2}
+    Dict {
+        entries: Vec<(RecExpr<T>, RecExpr<T>)>,
+    },
+    // A tuple literal, its elements can each have a different type: (1, "a", true)
+    Tuple {
+        elements: Vec<RecExpr<T>>,
+    },
+    // An argument given by the name of its parameter, only found among the arguments of a call: draw(x = 1).
+    // The typechecker puts the arguments in the order of the parameters, so the backend never sees one.
+    KeywordArgument {
+        name: String,
+        value: Box<RecExpr<T>>,
+    },
+    // Gives one of two values depending on the condition, only evaluating that one: low if low < high else high
+    Conditional {
+        condition: Box<RecExpr<T>>,
+        then_value: Box<RecExpr<T>>,
+        else_value: Box<RecExpr<T>>,
+    },
 }
 
 // Generic expression, leaves out detail in e.g. operator specifics
 #[derive(PartialEq, Clone)]
 struct GenExpr {
     pub data: GenExprData,
-    pub row: usize,
-    pub col_start: usize,
-    pub col_end: usize,
+    pub span: Span,
 }
 
 #[derive(PartialEq, Clone)]
@@ -176,6 +739,9 @@ enum GenExprData {
     Number {
         number: i64,
     },
+    Float {
+        value: f64,
+    },
     String {
         value: String,
     },
@@ -199,9 +765,33 @@ enum GenExprData {
         elements: Vec<GenExpr>,
     },
     ListAccess {
-        variable: String,
+        list: Box<GenExpr>,
         index: Box<GenExpr>,
     },
+    Slice {
+        list: Box<GenExpr>,
+        start: Option<Box<GenExpr>>,
+        end: Option<Box<GenExpr>>,
+    },
+    Dict {
+        entries: Vec<(GenExpr, GenExpr)>,
+    },
+    Tuple {
+        elements: Vec<GenExpr>,
+    },
+    Access {
+        object: String,
+        variable: String,
+    },
+    KeywordArgument {
+        name: String,
+        value: Box<GenExpr>,
+    },
+    Conditional {
+        condition: Box<GenExpr>,
+        then_value: Box<GenExpr>,
+        else_value: Box<GenExpr>,
+    },
 }
 
 pub fn parse(path: &std::path::PathBuf) -> Result<Vec<BaseExpr<()>>, Error> {
@@ -216,8 +806,17 @@ pub fn parse(path: &std::path::PathBuf) -> Result<Vec<BaseExpr<()>>, Error> {
 }
 
 pub fn parse_strings(lines: Vec<&str>) -> Result<Vec<BaseExpr<()>>, Error> {
+    parse_strings_with_options(lines, &LanguageOptions::default())
+}
+
+pub fn parse_strings_with_options(
+    lines: Vec<&str>,
+    options: &LanguageOptions,
+) -> Result<Vec<BaseExpr<()>>, Error> {
+    let _span = tracing::debug_span!("parse").entered();
+
     // First: tokenize the lines
-    let token_lines = match tokenizer::tokenize(lines) {
+    let token_lines = match tokenizer::tokenize_with_options(lines, options) {
         Ok(token_lines) => token_lines,
         Err(error_message) => return Err(error_message),
     };
@@ -234,6 +833,8 @@ pub fn parse_strings(lines: Vec<&str>) -> Result<Vec<BaseExpr<()>>, Error> {
         Err(error_message) => return Err(error_message),
     };
 
+    tracing::debug!(statements = merged_base_expressions.len(), "parsed");
+    tracing::trace!("parsed program:\n{}", expressions_to_string(&merged_base_expressions));
     return Ok(merged_base_expressions);
 }
 
@@ -258,6 +859,12 @@ fn get_last_occurence(
             TokenData::Symbol {
                 symbol_type: SymbolType::SquareBracketClosed,
             } => indentation_depth += 1,
+            TokenData::Symbol {
+                symbol_type: SymbolType::CurlyBracketOpen,
+            } => indentation_depth -= 1,
+            TokenData::Symbol {
+                symbol_type: SymbolType::CurlyBracketClosed,
+            } => indentation_depth += 1,
             _ => {}
         }
         // We are only looking for top-level symbols,
@@ -302,6 +909,9 @@ fn get_last_occurence(
                         | TokenData::Symbol {
                             symbol_type: SymbolType::Slash,
                         }
+                        | TokenData::Symbol {
+                            symbol_type: SymbolType::SlashSlash,
+                        }
                         | TokenData::Symbol {
                             symbol_type: SymbolType::Hat,
                         }
@@ -344,6 +954,25 @@ fn get_last_occurence(
 }
 
 fn get_expression(tokens: &[Token]) -> Result<RecExpr<()>, Error> {
+    // An expression cut off halfway, such as f( or 1 +, is reported at the token that expected more
+    if let Some(
+        last @ Token {
+            data: TokenData::Symbol { symbol_type },
+            ..
+        },
+    ) = tokens.last()
+    {
+        if expects_continuation(symbol_type) {
+            return Err(Error::located(
+                format!(
+                    "Unexpected end of line after '{}'",
+                    tokenizer::get_symbol_from_type(symbol_type)
+                ),
+                last.span(),
+            ));
+        }
+    }
+
     // First we get the generic expressions
     match get_generic_expression(tokens) {
         // And then convert the generic expression to a recursive expression
@@ -355,10 +984,48 @@ fn get_expression(tokens: &[Token]) -> Result<RecExpr<()>, Error> {
     }
 }
 
+// Symbols that cannot end an expression, as an operand or closing bracket must follow them
+fn expects_continuation(symbol_type: &SymbolType) -> bool {
+    matches!(
+        symbol_type,
+        SymbolType::Equals
+            | SymbolType::PlusEquals
+            | SymbolType::Plus
+            | SymbolType::Minus
+            | SymbolType::Star
+            | SymbolType::Slash
+            | SymbolType::SlashSlash
+            | SymbolType::Hat
+            | SymbolType::Dot
+            | SymbolType::Comma
+            | SymbolType::ParenthesisOpen
+            | SymbolType::SquareBracketOpen
+            | SymbolType::CurlyBracketOpen
+            | SymbolType::Colon
+            | SymbolType::EqualsEquals
+            | SymbolType::NotEquals
+            | SymbolType::GreaterThan
+            | SymbolType::GreaterThanOrEqual
+            | SymbolType::LessThan
+            | SymbolType::LessThanOrEqual
+            | SymbolType::Or
+            | SymbolType::And
+            | SymbolType::Not
+    )
+}
+
+// Nested expressions recurse once per level, see stack::ensure_stack
 fn generic_expression_to_recursive_expression(gen_expr: GenExpr) -> Result<RecExpr<()>, Error> {
+    stack::ensure_stack(|| generic_expression_to_recursive_expression_unguarded(gen_expr))
+}
+
+fn generic_expression_to_recursive_expression_unguarded(
+    gen_expr: GenExpr,
+) -> Result<RecExpr<()>, Error> {
     let data = match gen_expr.data {
         GenExprData::Variable { name } => RecExprData::<()>::Variable { name },
         GenExprData::Number { number } => RecExprData::<()>::Number { number },
+        GenExprData::Float { value } => RecExprData::<()>::Float { value },
         GenExprData::String { value } => RecExprData::<()>::String { value },
         GenExprData::Boolean { value } => RecExprData::<()>::Boolean { value },
         GenExprData::UnaryOp { operator, operand } => match operator {
@@ -380,9 +1047,7 @@ fn generic_expression_to_recursive_expression(gen_expr: GenExpr) -> Result<RecEx
                         "Invalid unary operator: {}",
                         tokenizer::get_symbol_from_type(&operator)
                     ),
-                    row: gen_expr.row,
-                    col_start: gen_expr.col_start,
-                    col_end: gen_expr.col_end,
+                    span: gen_expr.span,
                 });
             }
         },
@@ -443,6 +1108,19 @@ fn generic_expression_to_recursive_expression(gen_expr: GenExpr) -> Result<RecEx
                     (_, Err(e)) => return Err(e),
                 }
             }
+            SymbolType::SlashSlash => {
+                match (
+                    generic_expression_to_recursive_expression(*left_operand),
+                    generic_expression_to_recursive_expression(*right_operand),
+                ) {
+                    (Ok(left_expr), Ok(right_expr)) => RecExprData::<()>::FloorDivide {
+                        left: Box::new(left_expr),
+                        right: Box::new(right_expr),
+                    },
+                    (Err(e), _) => return Err(e),
+                    (_, Err(e)) => return Err(e),
+                }
+            }
             SymbolType::Hat => {
                 match (
                     generic_expression_to_recursive_expression(*left_operand),
@@ -567,9 +1245,7 @@ fn generic_expression_to_recursive_expression(gen_expr: GenExpr) -> Result<RecEx
                         "Invalid binary operator: {}",
                         tokenizer::get_symbol_from_type(&operator)
                     ),
-                    row: gen_expr.row,
-                    col_start: gen_expr.col_start,
-                    col_end: gen_expr.col_end,
+                    span: gen_expr.span,
                 });
             }
         },
@@ -579,10 +1255,15 @@ fn generic_expression_to_recursive_expression(gen_expr: GenExpr) -> Result<RecEx
         } => {
             let mut rec_expr_arguments = Vec::new();
             for gen_argument in arguments {
-                match generic_expression_to_recursive_expression(gen_argument) {
-                    Ok(rec_expr_argument) => rec_expr_arguments.push(rec_expr_argument),
-                    Err(e) => return Err(e),
+                let rec_expr_argument = generic_expression_to_recursive_expression(gen_argument)?;
+                let is_keyword = matches!(rec_expr_argument.data, RecExprData::KeywordArgument { .. });
+                if !is_keyword && has_keyword_arguments(&rec_expr_arguments) {
+                    return Err(Error::located(
+                        "A positional argument cannot follow a keyword argument".to_string(),
+                        rec_expr_argument.span(),
+                    ));
                 }
+                rec_expr_arguments.push(rec_expr_argument);
             }
 
             RecExprData::<()>::FunctionCall {
@@ -603,27 +1284,153 @@ fn generic_expression_to_recursive_expression(gen_expr: GenExpr) -> Result<RecEx
                 elements: rec_expr_elements,
             }
         }
-        GenExprData::ListAccess { variable, index } => {
-            match generic_expression_to_recursive_expression(*index) {
-                Ok(rec_expr_index) => RecExprData::<()>::ListAccess {
-                    variable,
+        GenExprData::ListAccess { list, index } => {
+            match (
+                generic_expression_to_recursive_expression(*list),
+                generic_expression_to_recursive_expression(*index),
+            ) {
+                (Ok(rec_expr_list), Ok(rec_expr_index)) => RecExprData::<()>::ListAccess {
+                    list: Box::new(rec_expr_list),
                     index: Box::new(rec_expr_index),
                 },
-                Err(e) => return Err(e),
+                (Err(e), _) => return Err(e),
+                (_, Err(e)) => return Err(e),
+            }
+        }
+        GenExprData::Slice { list, start, end } => {
+            let bound = |bound: Option<Box<GenExpr>>| match bound {
+                Some(bound) => generic_expression_to_recursive_expression(*bound).map(|bound| Some(Box::new(bound))),
+                None => Ok(None),
+            };
+            RecExprData::<()>::Slice {
+                list: Box::new(generic_expression_to_recursive_expression(*list)?),
+                start: bound(start)?,
+                end: bound(end)?,
+            }
+        }
+        GenExprData::Dict { entries } => {
+            let mut rec_expr_entries = Vec::new();
+            for (gen_key, gen_value) in entries {
+                rec_expr_entries.push((
+                    generic_expression_to_recursive_expression(gen_key)?,
+                    generic_expression_to_recursive_expression(gen_value)?,
+                ));
+            }
+
+            RecExprData::<()>::Dict {
+                entries: rec_expr_entries,
+            }
+        }
+        GenExprData::Tuple { elements } => {
+            let mut rec_expr_elements = Vec::new();
+            for gen_element in elements {
+                rec_expr_elements.push(generic_expression_to_recursive_expression(gen_element)?);
+            }
+
+            RecExprData::<()>::Tuple {
+                elements: rec_expr_elements,
             }
         }
+        GenExprData::Access { object, variable } => RecExprData::<()>::Access { object, variable },
+        GenExprData::KeywordArgument { name, value } => RecExprData::<()>::KeywordArgument {
+            name,
+            value: Box::new(generic_expression_to_recursive_expression(*value)?),
+        },
+        GenExprData::Conditional {
+            condition,
+            then_value,
+            else_value,
+        } => RecExprData::<()>::Conditional {
+            condition: Box::new(generic_expression_to_recursive_expression(*condition)?),
+            then_value: Box::new(generic_expression_to_recursive_expression(*then_value)?),
+            else_value: Box::new(generic_expression_to_recursive_expression(*else_value)?),
+        },
     };
 
     return Ok(RecExpr {
         data,
-        row: gen_expr.row,
-        col_start: gen_expr.col_start,
-        col_end: gen_expr.col_end,
+        span: gen_expr.span,
         generic_data: (),
     });
 }
 
+// The slice of the list with the tokens between the square brackets, such as 1:4 or :n,
+// or None if there is no : outside of brackets between them
+fn get_slice_expression(list_tokens: &[Token], index_tokens: &[Token], bracket: Span) -> Result<Option<GenExpr>, Error> {
+    let bounds = split_top_level(index_tokens, &SymbolType::Colon);
+    let (start_tokens, end_tokens) = match bounds.as_slice() {
+        [_] => return Ok(None),
+        [start_tokens, end_tokens] => (start_tokens, end_tokens),
+        [start_tokens, end_tokens, ..] => {
+            let second_colon = &index_tokens[start_tokens.len() + end_tokens.len() + 1];
+            return Err(Error::located(
+                String::from("A slice has a start and an end, as in xs[1:4], not a step"),
+                second_colon.span(),
+            ));
+        }
+        [] => unreachable!("Splitting always gives at least one part"),
+    };
+
+    let list = get_generic_expression(list_tokens)?;
+    let bound = |tokens: &[Token]| -> Result<Option<Box<GenExpr>>, Error> {
+        match tokens.is_empty() {
+            true => Ok(None),
+            false => Ok(Some(Box::new(get_generic_expression(tokens)?))),
+        }
+    };
+    Ok(Some(GenExpr {
+        span: list.span.merge(&bracket),
+        data: GenExprData::Slice {
+            list: Box::new(list),
+            start: bound(start_tokens)?,
+            end: bound(end_tokens)?,
+        },
+    }))
+}
+
+// A conditional expression such as a if c else b, or None if the tokens are not one.
+// The first if outside of brackets starts the condition, so a if c else b if d else e ends with another conditional.
+fn get_conditional_expression(tokens: &[Token]) -> Result<Option<GenExpr>, Error> {
+    let then_tokens = split_top_level(tokens, &SymbolType::If)[0];
+    if then_tokens.is_empty() || then_tokens.len() == tokens.len() {
+        return Ok(None);
+    }
+    let if_token = &tokens[then_tokens.len()];
+    let after_if = &tokens[then_tokens.len() + 1..];
+    let condition_tokens = split_top_level(after_if, &SymbolType::Else)[0];
+    if condition_tokens.len() == after_if.len() {
+        return Err(Error::located(
+            String::from("Expected else and a value after the condition, as in a if condition else b"),
+            if_token.span(),
+        ));
+    }
+    let else_tokens = &after_if[condition_tokens.len() + 1..];
+    if condition_tokens.is_empty() || else_tokens.is_empty() {
+        return Err(Error::located(
+            String::from("Expected a condition between if and else, and a value after else"),
+            if_token.span(),
+        ));
+    }
+
+    let then_value = get_generic_expression(then_tokens)?;
+    let condition = get_generic_expression(condition_tokens)?;
+    let else_value = get_generic_expression(else_tokens)?;
+    Ok(Some(GenExpr {
+        span: then_value.span.merge(&else_value.span),
+        data: GenExprData::Conditional {
+            condition: Box::new(condition),
+            then_value: Box::new(then_value),
+            else_value: Box::new(else_value),
+        },
+    }))
+}
+
+// Nested expressions recurse once per level, see stack::ensure_stack
 fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
+    stack::ensure_stack(|| get_generic_expression_unguarded(tokens))
+}
+
+fn get_generic_expression_unguarded(tokens: &[Token]) -> Result<GenExpr, Error> {
     //let mut token_vec = Vec::from(tokens);
     //let root_token = parenthesize(&mut token_vec);
 
@@ -637,9 +1444,14 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
         SymbolType::LessThanOrEqual,
     ]);
     let precedence_five = Vec::from([SymbolType::Plus, SymbolType::Minus]);
-    let precedence_six = Vec::from([SymbolType::Star, SymbolType::Slash]);
+    let precedence_six = Vec::from([SymbolType::Star, SymbolType::Slash, SymbolType::SlashSlash]);
     let precedence_seven = Vec::from([SymbolType::Hat]);
 
+    // A conditional binds the loosest, so a + 1 if c else b chooses between a + 1 and b
+    if let Some(conditional) = get_conditional_expression(tokens)? {
+        return Ok(conditional);
+    }
+
     // Looking for the first lowest precedence operators
     if let Ok((symbol_type, index)) = get_last_occurence(tokens, precedence_one) {
         let left = get_generic_expression(&tokens[0..index]);
@@ -647,18 +1459,14 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
 
         match (left, right) {
             (Ok(left_expr), Ok(right_expr)) => {
-                let row = left_expr.row;
-                let col_start = left_expr.col_start;
-                let col_end = right_expr.col_end;
+                let span = left_expr.span.merge(&right_expr.span);
                 return Ok(GenExpr {
                     data: GenExprData::BinaryOp {
                         left_operand: Box::new(left_expr),
                         operator: symbol_type,
                         right_operand: Box::new(right_expr),
                     },
-                    row,
-                    col_start,
-                    col_end,
+                    span,
                 });
             }
             (Err(e), _) => return Err(e),
@@ -673,18 +1481,14 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
 
         match (left, right) {
             (Ok(left_expr), Ok(right_expr)) => {
-                let row = left_expr.row;
-                let col_start = left_expr.col_start;
-                let col_end = right_expr.col_end;
+                let span = left_expr.span.merge(&right_expr.span);
                 return Ok(GenExpr {
                     data: GenExprData::BinaryOp {
                         left_operand: Box::new(left_expr),
                         operator: symbol_type,
                         right_operand: Box::new(right_expr),
                     },
-                    row,
-                    col_start,
-                    col_end,
+                    span,
                 });
             }
             (Err(e), _) => return Err(e),
@@ -699,18 +1503,14 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
 
         match (left, right) {
             (Ok(left_expr), Ok(right_expr)) => {
-                let row = left_expr.row;
-                let col_start = left_expr.col_start;
-                let col_end = right_expr.col_end;
+                let span = left_expr.span.merge(&right_expr.span);
                 return Ok(GenExpr {
                     data: GenExprData::BinaryOp {
                         left_operand: Box::new(left_expr),
                         operator: symbol_type,
                         right_operand: Box::new(right_expr),
                     },
-                    row,
-                    col_start,
-                    col_end,
+                    span,
                 });
             }
             (Err(e), _) => return Err(e),
@@ -723,22 +1523,19 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
             data: TokenData::Symbol {
                 symbol_type: SymbolType::Not,
             },
-            row: row_not,
-            col_start: col_start_not,
+            span: operator_span,
             ..
         }, rest @ ..] => {
             // not statement detected
             match get_generic_expression(&rest) {
                 Ok(expr) => {
-                    let expr_col_end = expr.col_end;
+                    let span = operator_span.merge(&expr.span);
                     return Ok(GenExpr {
                         data: GenExprData::UnaryOp {
                             operator: SymbolType::Not,
                             operand: Box::new(expr),
                         },
-                        row: *row_not,
-                        col_start: *col_start_not,
-                        col_end: expr_col_end,
+                        span,
                     });
                 }
                 Err(e) => return Err(e),
@@ -754,18 +1551,14 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
 
         match (left, right) {
             (Ok(left_expr), Ok(right_expr)) => {
-                let row = left_expr.row;
-                let col_start = left_expr.col_start;
-                let col_end = right_expr.col_end;
+                let span = left_expr.span.merge(&right_expr.span);
                 return Ok(GenExpr {
                     data: GenExprData::BinaryOp {
                         left_operand: Box::new(left_expr),
                         operator: symbol_type,
                         right_operand: Box::new(right_expr),
                     },
-                    row,
-                    col_start,
-                    col_end,
+                    span,
                 });
             }
             (Err(e), _) => return Err(e),
@@ -780,18 +1573,14 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
 
         match (left, right) {
             (Ok(left_expr), Ok(right_expr)) => {
-                let row = left_expr.row;
-                let col_start = left_expr.col_start;
-                let col_end = right_expr.col_end;
+                let span = left_expr.span.merge(&right_expr.span);
                 return Ok(GenExpr {
                     data: GenExprData::BinaryOp {
                         left_operand: Box::new(left_expr),
                         operator: symbol_type,
                         right_operand: Box::new(right_expr),
                     },
-                    row,
-                    col_start,
-                    col_end,
+                    span,
                 });
             }
             (Err(e), _) => return Err(e),
@@ -806,18 +1595,14 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
 
         match (left, right) {
             (Ok(left_expr), Ok(right_expr)) => {
-                let row = left_expr.row;
-                let col_start = left_expr.col_start;
-                let col_end = right_expr.col_end;
+                let span = left_expr.span.merge(&right_expr.span);
                 return Ok(GenExpr {
                     data: GenExprData::BinaryOp {
                         left_operand: Box::new(left_expr),
                         operator: symbol_type,
                         right_operand: Box::new(right_expr),
                     },
-                    row,
-                    col_start,
-                    col_end,
+                    span,
                 });
             }
             (Err(e), _) => return Err(e),
@@ -831,22 +1616,19 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
             data: TokenData::Symbol {
                 symbol_type: SymbolType::Minus,
             },
-            row: row_not,
-            col_start: col_start_not,
+            span: operator_span,
             ..
         }, rest @ ..] => {
             // unary - statement detected
             match get_generic_expression(&rest) {
                 Ok(expr) => {
-                    let expr_col_end = expr.col_end;
+                    let span = operator_span.merge(&expr.span);
                     return Ok(GenExpr {
                         data: GenExprData::UnaryOp {
                             operator: SymbolType::Minus,
                             operand: Box::new(expr),
                         },
-                        row: *row_not,
-                        col_start: *col_start_not,
-                        col_end: expr_col_end,
+                        span,
                     });
                 }
                 Err(e) => return Err(e),
@@ -862,18 +1644,14 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
 
         match (left, right) {
             (Ok(left_expr), Ok(right_expr)) => {
-                let row = left_expr.row;
-                let col_start = left_expr.col_start;
-                let col_end = right_expr.col_end;
+                let span = left_expr.span.merge(&right_expr.span);
                 return Ok(GenExpr {
                     data: GenExprData::BinaryOp {
                         left_operand: Box::new(left_expr),
                         operator: symbol_type,
                         right_operand: Box::new(right_expr),
                     },
-                    row,
-                    col_start,
-                    col_end,
+                    span,
                 });
             }
             (Err(e), _) => return Err(e),
@@ -898,10 +1676,15 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
             ..
         }, rest @ ..]
             // Last token must be a closing parenthesis
-            if rest.last().unwrap().data
-                == TokenData::Symbol {
-                    symbol_type: SymbolType::ParenthesisClosed,
-                } =>
+            if matches!(
+                rest.last(),
+                Some(Token {
+                    data: TokenData::Symbol {
+                        symbol_type: SymbolType::ParenthesisClosed,
+                    },
+                    ..
+                })
+            ) =>
         {
             match read_function_parameters(rest) {
                 Ok(arguments) => {
@@ -910,9 +1693,7 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
                             function_name: function_name.clone(),
                             arguments: arguments,
                         },
-                        row: tokens[0].row,
-                        col_start: tokens[0].col_start,
-                        col_end: tokens[tokens.len() - 1].col_end,
+                        span: tokens[0].span.merge(&tokens[tokens.len() - 1].span),
                     })
                 }
                 Err(e) => return Err(e),
@@ -920,6 +1701,33 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
             // Possible function call
         }
 
+        // List access, where the list can itself be any expression: a[1], grid[1][0], f(x)[0]
+        [.., Token {
+            data:
+                TokenData::Symbol {
+                    symbol_type: SymbolType::SquareBracketClosed,
+                },
+            span: bracket_span,
+            ..
+        }] if matches!(find_index_bracket(tokens), Some(index) if index > 0) =>
+        {
+            let bracket_index = find_index_bracket(tokens).unwrap();
+            let index_tokens = &tokens[bracket_index + 1..tokens.len() - 1];
+            if let Some(slice) = get_slice_expression(&tokens[..bracket_index], index_tokens, *bracket_span)? {
+                return Ok(slice);
+            }
+            let list = get_generic_expression(&tokens[..bracket_index])?;
+            let index = get_generic_expression(index_tokens)?;
+            let span = list.span.merge(bracket_span);
+            return Ok(GenExpr {
+                data: GenExprData::ListAccess {
+                    list: Box::new(list),
+                    index: Box::new(index),
+                },
+                span,
+            });
+        }
+
         // List [a, b, c]
         [Token {
             data:
@@ -929,10 +1737,15 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
             ..
         }, rest @ ..]
             // Last token must be a closing parenthesis
-            if rest.last().unwrap().data
-                == TokenData::Symbol {
-                    symbol_type: SymbolType::SquareBracketClosed,
-                } =>
+            if matches!(
+                rest.last(),
+                Some(Token {
+                    data: TokenData::Symbol {
+                        symbol_type: SymbolType::SquareBracketClosed,
+                    },
+                    ..
+                })
+            ) =>
         {
             match read_list_items(rest) {
                 Ok(arguments) => {
@@ -940,51 +1753,34 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
                         data: GenExprData::List {
                             elements: arguments,
                         },
-                        row: tokens[0].row,
-                        col_start: tokens[0].col_start,
-                        col_end: tokens[tokens.len() - 1].col_end,
+                        span: tokens[0].span.merge(&tokens[tokens.len() - 1].span),
                     })
                 }
                 Err(e) => return Err(e),
             }
         }
 
-        // List access
-        [Token {
-            data: TokenData::Variable { name: variable_name },
-            row : row_variable,
-            col_start: col_start_variable,
-            ..
-        },
-        Token {
+        // Dict {key: value, ...}
+        [open_token @ Token {
             data:
                 TokenData::Symbol {
-                    symbol_type: SymbolType::SquareBracketOpen,
+                    symbol_type: SymbolType::CurlyBracketOpen,
                 },
             ..
-        }, rest @ .., Token {
+        }, content @ .., Token {
             data:
                 TokenData::Symbol {
-                    symbol_type: SymbolType::SquareBracketClosed,
+                    symbol_type: SymbolType::CurlyBracketClosed,
                 },
-            col_end: col_end_parenthesis,
+            span: bracket_span,
             ..
-        }] =>
-        {
-            match get_generic_expression(&rest) {
-                Ok(index) => {
-                    return Ok(GenExpr {
-                        data: GenExprData::ListAccess {
-                            variable: variable_name.clone(),
-                            index: Box::new(index),
-                        },
-                        row: *row_variable,
-                        col_start: *col_start_variable,
-                        col_end: *col_end_parenthesis,
-                    })
-                }
-                Err(e) => return Err(e),
-            }
+        }] => {
+            return Ok(GenExpr {
+                data: GenExprData::Dict {
+                    entries: read_dict_entries(open_token, content)?,
+                },
+                span: open_token.span.merge(bracket_span),
+            });
         }
 
         // Parentheses with content
@@ -993,21 +1789,42 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
                 TokenData::Symbol {
                     symbol_type: SymbolType::ParenthesisOpen,
                 },
-            col_start: col_start_parenthesis,
+            span: open_span,
             ..
         }, content @ .., Token {
             data:
                 TokenData::Symbol {
                     symbol_type: SymbolType::ParenthesisClosed,
                 },
-            col_end: col_end_parenthesis,
+            span: close_span,
             ..
         }] => {
+            // A comma outside of any brackets makes it a tuple, (1,) has a single element
+            let mut element_tokens = split_top_level(content, &SymbolType::Comma);
+            if element_tokens.len() > 1 {
+                if matches!(element_tokens.last(), Some(last) if last.is_empty()) {
+                    element_tokens.pop();
+                }
+                let mut elements = Vec::new();
+                for element in element_tokens {
+                    if element.is_empty() {
+                        return Err(Error::located(
+                            String::from("Expected a value between the commas of the tuple"),
+                            tokens[0].span(),
+                        ));
+                    }
+                    elements.push(get_generic_expression(element)?);
+                }
+                return Ok(GenExpr {
+                    data: GenExprData::Tuple { elements },
+                    span: open_span.merge(close_span),
+                });
+            }
+
             // Parentheses detected
             match get_generic_expression(&content) {
                 Ok(mut expr) => {
-                    expr.col_start = *col_start_parenthesis;
-                    expr.col_end = *col_end_parenthesis;
+                    expr.span = open_span.merge(close_span);
 
                     return Ok(expr);
                 }
@@ -1020,22 +1837,19 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
                 TokenData::Symbol {
                     symbol_type: SymbolType::Not,
                 },
-            row: row_not,
-            col_start: col_start_not,
+            span: operator_span,
             ..
         }, rest @ ..] => {
             // not statement detected
             match get_generic_expression(&rest) {
                 Ok(expr) => {
-                    let expr_col_end = expr.col_end;
+                    let span = operator_span.merge(&expr.span);
                     return Ok(GenExpr {
                         data: GenExprData::UnaryOp {
                             operator: SymbolType::Not,
                             operand: Box::new(expr),
                         },
-                        row: *row_not,
-                        col_start: *col_start_not,
-                        col_end: expr_col_end,
+                        span,
                     })
                 }
                 Err(e) => return Err(e),
@@ -1053,9 +1867,29 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
                 data: GenExprData::Variable {
                     name: variable_name.clone(),
                 },
-                row: tokens[0].row,
-                col_start: tokens[0].col_start,
-                col_end: tokens[0].col_end,
+                span: tokens[0].span,
+            })
+        }
+
+        // Field access: point.x
+        [Token {
+            data: TokenData::Variable { name: object },
+            ..
+        }, Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Dot,
+            },
+            ..
+        }, Token {
+            data: TokenData::Variable { name: variable },
+            ..
+        }] => {
+            return Ok(GenExpr {
+                data: GenExprData::Access {
+                    object: object.clone(),
+                    variable: variable.clone(),
+                },
+                span: tokens[0].span.merge(&tokens[2].span),
             })
         }
 
@@ -1066,9 +1900,18 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
         }] => {
             return Ok(GenExpr {
                 data: GenExprData::Number { number: *number },
-                row: tokens[0].row,
-                col_start: tokens[0].col_start,
-                col_end: tokens[0].col_end,
+                span: tokens[0].span,
+            })
+        }
+
+        // Just a float
+        [Token {
+            data: TokenData::Float { value },
+            ..
+        }] => {
+            return Ok(GenExpr {
+                data: GenExprData::Float { value: *value },
+                span: tokens[0].span,
             })
         }
 
@@ -1078,22 +1921,19 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
                 TokenData::Symbol {
                     symbol_type: SymbolType::Minus,
                 },
-            row: row_not,
-            col_start: col_start_not,
+            span: operator_span,
             ..
         }, rest @ ..] => {
             // unary - statement detected
             match get_generic_expression(&rest) {
                 Ok(expr) => {
-                    let expr_col_end = expr.col_end;
+                    let span = operator_span.merge(&expr.span);
                     return Ok(GenExpr {
                         data: GenExprData::UnaryOp {
                             operator: SymbolType::Minus,
                             operand: Box::new(expr),
                         },
-                        row: *row_not,
-                        col_start: *col_start_not,
-                        col_end: expr_col_end,
+                        span,
                     })
                 }
                 Err(e) => return Err(e),
@@ -1109,9 +1949,7 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
                 data: GenExprData::String {
                     value: value.clone(),
                 },
-                row: tokens[0].row,
-                col_start: tokens[0].col_start,
-                col_end: tokens[0].col_end,
+                span: tokens[0].span,
             })
         }
 
@@ -1124,9 +1962,7 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
         }] => {
             return Ok(GenExpr {
                 data: GenExprData::Boolean { value: true },
-                row: tokens[0].row,
-                col_start: tokens[0].col_start,
-                col_end: tokens[0].col_end,
+                span: tokens[0].span,
             })
         }
 
@@ -1139,17 +1975,18 @@ fn get_generic_expression(tokens: &[Token]) -> Result<GenExpr, Error> {
         }] => {
             return Ok(GenExpr {
                 data: GenExprData::Boolean { value: false },
-                row: tokens[0].row,
-                col_start: tokens[0].col_start,
-                col_end: tokens[0].col_end,
+                span: tokens[0].span,
             })
         }
 
         [first, .., last] => {
-            return Err(Error::LocationError { message: format!("No expression found"), row: first.row, col_start: first.col_start, col_end: last.col_end })
+            return Err(Error::located(
+                format!("No expression found"),
+                first.span().merge(&last.span()),
+            ))
         }
         [first, ..] => {
-            return Err(Error::LocationError { message: format!("No expression found"), row: first.row, col_start: first.col_start, col_end: first.col_end })
+            return Err(Error::located(format!("No expression found"), first.span()))
         }
         [] => {
             return Err(Error::SimpleError { message: format!("No expression found") })
@@ -1165,17 +2002,23 @@ fn read_function_parameters(line: &[Token]) -> Result<Vec<GenExpr>, Error> {
         Err(_) => {
             return Err(Error::LocationError {
                 message: format!("Could not find a valid function call"),
-                row: line[0].row,
-                col_start: line[0].col_start,
-                col_end: line[line.len() - 1].col_end - 1,
+                span: line[0].span.merge(&line[line.len() - 1].span),
             })
         }
     }
 }
 
+// Recurses once per item, see stack::ensure_stack
 fn read_function_parameters_rec(
     line: &[Token],
     parameters: &mut Vec<GenExpr>,
+) -> Result<String, Error> {
+    stack::ensure_stack(|| read_function_parameters_rec_unguarded(line, parameters))
+}
+
+fn read_function_parameters_rec_unguarded(
+    line: &[Token],
+    parameters: &mut Vec<GenExpr>,
 ) -> Result<String, Error> {
     // Attempt to read a function parameter by trying to find a valid expression looking at each comma
 
@@ -1216,6 +2059,9 @@ fn read_function_parameter(line: &[Token]) -> Result<(Option<GenExpr>, &[Token])
                 TokenData::Symbol {
                     symbol_type: SymbolType::SquareBracketOpen,
                 } => parenthesis_depth += 1,
+                TokenData::Symbol {
+                    symbol_type: SymbolType::CurlyBracketOpen,
+                } => parenthesis_depth += 1,
                 _ => {}
             }
             for i in 1..line.len() {
@@ -1226,7 +2072,7 @@ fn read_function_parameter(line: &[Token]) -> Result<(Option<GenExpr>, &[Token])
                         // Check if we're in main body of the function call
                         if parenthesis_depth == 1 {
                             // Attempt to get an expression from all tokens up until this comma
-                            match get_generic_expression(&line[0..i]) {
+                            match get_argument(&line[0..i]) {
                                 Ok(expr) => return Ok((Some(expr), &line[i + 1..])),
                                 Err(_) => continue,
                             }
@@ -1238,6 +2084,12 @@ fn read_function_parameter(line: &[Token]) -> Result<(Option<GenExpr>, &[Token])
                     TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketOpen,
                     } => parenthesis_depth += 1,
+                    TokenData::Symbol {
+                        symbol_type: SymbolType::CurlyBracketOpen,
+                    } => parenthesis_depth += 1,
+                    TokenData::Symbol {
+                        symbol_type: SymbolType::CurlyBracketClosed,
+                    } => parenthesis_depth -= 1,
                     TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketClosed,
                     } => parenthesis_depth -= 1,
@@ -1246,7 +2098,7 @@ fn read_function_parameter(line: &[Token]) -> Result<(Option<GenExpr>, &[Token])
                     } => {
                         parenthesis_depth -= 1;
                         if parenthesis_depth == 0 {
-                            match get_generic_expression(&line[0..i]) {
+                            match get_argument(&line[0..i]) {
                                 Ok(expr) => return Ok((Some(expr), &line[i..])),
                                 Err(e) => return Err(e),
                             }
@@ -1264,6 +2116,33 @@ fn read_function_parameter(line: &[Token]) -> Result<(Option<GenExpr>, &[Token])
     }
 }
 
+// A single argument of a call, which can name the parameter it is for: x = 1
+fn get_argument(tokens: &[Token]) -> Result<GenExpr, Error> {
+    match tokens {
+        [name_token @ Token {
+            data: TokenData::Variable { name },
+            ..
+        }, Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Equals,
+            },
+            ..
+        }, value @ ..]
+            if !value.is_empty() =>
+        {
+            let value = get_generic_expression(value)?;
+            Ok(GenExpr {
+                span: name_token.span.merge(&value.span),
+                data: GenExprData::KeywordArgument {
+                    name: name.clone(),
+                    value: Box::new(value),
+                },
+            })
+        }
+        _ => get_generic_expression(tokens),
+    }
+}
+
 fn read_list_items(line: &[Token]) -> Result<Vec<GenExpr>, Error> {
     let mut items: Vec<GenExpr> = Vec::new();
 
@@ -1272,15 +2151,21 @@ fn read_list_items(line: &[Token]) -> Result<Vec<GenExpr>, Error> {
         Err(e) => {
             return Err(Error::LocationError {
                 message: format!("Could not find a valid list"),
-                row: line[0].row,
-                col_start: line[0].col_start,
-                col_end: line[line.len() - 1].col_end - 1,
+                span: line[0].span.merge(&line[line.len() - 1].span),
             })
         }
     }
 }
 
+// Recurses once per item, see stack::ensure_stack
 fn read_list_items_rec(line: &[Token], items: &mut Vec<GenExpr>) -> Result<String, Error> {
+    stack::ensure_stack(|| read_list_items_rec_unguarded(line, items))
+}
+
+fn read_list_items_rec_unguarded(
+    line: &[Token],
+    items: &mut Vec<GenExpr>,
+) -> Result<String, Error> {
     match read_list_item(line) {
         Ok((None, _)) => return Ok(String::from("Succcess")),
         Ok((Some(item), rest)) => {
@@ -1318,6 +2203,9 @@ fn read_list_item(line: &[Token]) -> Result<(Option<GenExpr>, &[Token]), Error>
                 TokenData::Symbol {
                     symbol_type: SymbolType::SquareBracketOpen,
                 } => parenthesis_depth += 1,
+                TokenData::Symbol {
+                    symbol_type: SymbolType::CurlyBracketOpen,
+                } => parenthesis_depth += 1,
                 _ => {}
             }
             for i in 1..line.len() {
@@ -1340,6 +2228,12 @@ fn read_list_item(line: &[Token]) -> Result<(Option<GenExpr>, &[Token]), Error>
                     TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketOpen,
                     } => parenthesis_depth += 1,
+                    TokenData::Symbol {
+                        symbol_type: SymbolType::CurlyBracketOpen,
+                    } => parenthesis_depth += 1,
+                    TokenData::Symbol {
+                        symbol_type: SymbolType::CurlyBracketClosed,
+                    } => parenthesis_depth -= 1,
                     TokenData::Symbol {
                         symbol_type: SymbolType::ParenthesisClosed,
                     } => parenthesis_depth -= 1,
@@ -1366,9 +2260,103 @@ fn read_list_item(line: &[Token]) -> Result<(Option<GenExpr>, &[Token]), Error>
     }
 }
 
+// Reads the key: value entries between the curly brackets of a dict literal, a trailing comma is allowed
+fn read_dict_entries(open_token: &Token, content: &[Token]) -> Result<Vec<(GenExpr, GenExpr)>, Error> {
+    let mut entries = Vec::new();
+    let mut entry_tokens = split_top_level(content, &SymbolType::Comma);
+    if matches!(entry_tokens.last(), Some(last) if last.is_empty()) {
+        entry_tokens.pop();
+    }
+
+    for tokens in entry_tokens {
+        let Some(first) = tokens.first() else {
+            return Err(Error::located(
+                String::from("Expected an entry like key: value in the dict"),
+                open_token.span(),
+            ));
+        };
+        let entry_span = first.span().merge(&tokens[tokens.len() - 1].span());
+
+        match &split_top_level(tokens, &SymbolType::Colon)[..] {
+            [key, value] if !key.is_empty() && !value.is_empty() => {
+                entries.push((get_generic_expression(key)?, get_generic_expression(value)?));
+            }
+            _ => {
+                return Err(Error::located(
+                    String::from("Expected an entry like key: value in the dict"),
+                    entry_span,
+                ));
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+// Values separated by commas without brackets around them, as in return low, high, make a tuple
+fn get_values_expression(tokens: &[Token]) -> Result<RecExpr<()>, Error> {
+    let element_tokens = split_top_level(tokens, &SymbolType::Comma);
+    if element_tokens.len() == 1 {
+        return get_expression(tokens);
+    }
+
+    let span = tokens[0].span().merge(&tokens[tokens.len() - 1].span());
+    let mut elements = Vec::new();
+    for element in element_tokens {
+        if element.is_empty() {
+            return Err(Error::located(
+                String::from("Expected a value between the commas"),
+                span,
+            ));
+        }
+        elements.push(get_expression(element)?);
+    }
+    Ok(RecExpr::new(RecExprData::Tuple { elements }, span, ()))
+}
+
+// Splits the tokens at every separator which is not inside brackets
+fn split_top_level<'a>(tokens: &'a [Token], separator: &SymbolType) -> Vec<&'a [Token]> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut part_start = 0;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match &token.data {
+            TokenData::Symbol {
+                symbol_type:
+                    SymbolType::ParenthesisOpen
+                    | SymbolType::SquareBracketOpen
+                    | SymbolType::CurlyBracketOpen,
+            } => depth += 1,
+            TokenData::Symbol {
+                symbol_type:
+                    SymbolType::ParenthesisClosed
+                    | SymbolType::SquareBracketClosed
+                    | SymbolType::CurlyBracketClosed,
+            } => depth -= 1,
+            TokenData::Symbol { symbol_type } if depth == 0 && symbol_type == separator => {
+                parts.push(&tokens[part_start..i]);
+                part_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&tokens[part_start..]);
+    parts
+}
+
+// Nested blocks recurse once per level, see stack::ensure_stack
 fn add_to_if_statement(
     if_statement: &mut BaseExpr<()>,
     else_statement_to_add: BaseExpr<()>,
+) -> Result<String, Error> {
+    stack::ensure_stack(|| add_to_if_statement_unguarded(if_statement, else_statement_to_add))
+}
+
+fn add_to_if_statement_unguarded(
+    if_statement: &mut BaseExpr<()>,
+    else_statement_to_add: BaseExpr<()>,
 ) -> Result<String, Error> {
     match &mut if_statement.data {
         BaseExprData::IfStatement { else_statement, .. }
@@ -1382,188 +2370,58 @@ fn add_to_if_statement(
             }
         },
         _ => {
-            return Err(Error::LocationError {
-                message: format!("Could not find if statement to add else statement to"),
-                row: else_statement_to_add.row,
-                col_start: else_statement_to_add.col_start,
-                col_end: else_statement_to_add.col_end,
-            });
+            return Err(Error::located(
+                format!("Could not find if statement to add else statement to"),
+                else_statement_to_add.span(),
+            ));
         }
     }
 }
 
+// Attaches else-if and else statements to the if statement before them, in every block of the program
 fn merge_if_statements(base_expressions: Vec<BaseExpr<()>>) -> Result<Vec<BaseExpr<()>>, Error> {
-    let mut merged_statements = Vec::new();
+    return IfStatementMerger.fold_body(base_expressions);
+}
 
-    // This can probably be done without copying every single item...
+struct IfStatementMerger;
 
-    for base_expression in base_expressions {
-        match base_expression.data {
-            BaseExprData::IfStatement {
-                condition,
-                body,
-                else_statement,
-            } => {
-                // Recursively merge if statements in the body
-                let merged_body = match merge_if_statements(body) {
-                    Ok(body) => body,
-                    Err(e) => return Err(e),
-                };
+impl Folder<()> for IfStatementMerger {
+    fn fold_body(&mut self, body: Vec<BaseExpr<()>>) -> Result<Vec<BaseExpr<()>>, Error> {
+        let mut merged_statements: Vec<BaseExpr<()>> = Vec::new();
 
-                merged_statements.push(BaseExpr {
-                    data: BaseExprData::IfStatement {
-                        condition: condition,
-                        body: merged_body,
-                        else_statement: else_statement,
-                    },
-                    row: base_expression.row,
-                    col_start: base_expression.col_start,
-                    col_end: base_expression.col_end,
-                    generic_data: base_expression.generic_data,
-                });
-            }
-            BaseExprData::ElseIfStatement {
-                condition, body, ..
-            } => {
-                // Recursively merge if statements in the body
-                let merged_body = match merge_if_statements(body) {
-                    Ok(body) => body,
-                    Err(e) => return Err(e),
-                };
+        for base_expression in body {
+            // Blocks nested in the statement are merged first
+            let base_expression = self.fold_base_expr(base_expression)?;
 
-                match merged_statements.last_mut() {
-                    Some(
-                        upper_if_statement @ BaseExpr {
-                            data: BaseExprData::IfStatement { .. },
-                            ..
-                        },
-                    ) => {
-                        match add_to_if_statement(
-                            upper_if_statement,
-                            BaseExpr {
-                                data: BaseExprData::ElseIfStatement {
-                                    condition,
-                                    body: merged_body,
-                                    else_statement: None,
-                                },
-                                row: base_expression.row,
-                                col_start: base_expression.col_start,
-                                col_end: base_expression.col_end,
-                                generic_data: base_expression.generic_data,
+            match base_expression.data {
+                BaseExprData::ElseIfStatement { .. } | BaseExprData::ElseStatement { .. } => {
+                    match merged_statements.last_mut() {
+                        Some(
+                            upper_if_statement @ BaseExpr {
+                                data: BaseExprData::IfStatement { .. },
+                                ..
                             },
-                        ) {
-                            Ok(_) => {}
-                            Err(e) => return Err(e),
+                        ) => {
+                            add_to_if_statement(upper_if_statement, base_expression)?;
                         }
-                    }
-                    _ => {
-                        return Err(Error::LocationError {
-                            message: format!(
-                                "Could not find if statement to add else-if statement to"
-                            ),
-                            row: base_expression.row,
-                            col_start: base_expression.col_start,
-                            col_end: base_expression.col_end,
-                        });
-                    }
-                }
-            }
-            BaseExprData::ElseStatement { body } => {
-                // Recursively merge if statements in the body
-                let merged_body = match merge_if_statements(body) {
-                    Ok(body) => body,
-                    Err(e) => return Err(e),
-                };
-
-                match &mut merged_statements.last_mut() {
-                    Some(
-                        upper_if_statement @ BaseExpr {
-                            data: BaseExprData::IfStatement { .. },
-                            ..
-                        },
-                    ) => {
-                        match add_to_if_statement(
-                            upper_if_statement,
-                            BaseExpr {
-                                data: BaseExprData::ElseStatement { body: merged_body },
-                                row: base_expression.row,
-                                col_start: base_expression.col_start,
-                                col_end: base_expression.col_end,
-                                generic_data: base_expression.generic_data,
-                            },
-                        ) {
-                            Ok(_) => {}
-                            Err(e) => return Err(e),
+                        _ => {
+                            let kind = match base_expression.data {
+                                BaseExprData::ElseIfStatement { .. } => "else-if statement",
+                                _ => "else statement",
+                            };
+                            return Err(Error::located(
+                                format!("Could not find if statement to add {} to", kind),
+                                base_expression.span(),
+                            ));
                         }
                     }
-                    _ => {
-                        return Err(Error::LocationError {
-                            message: format!(
-                                "Could not find if statement to add else statement to"
-                            ),
-                            row: base_expression.row,
-                            col_start: base_expression.col_start,
-                            col_end: base_expression.col_end,
-                        });
-                    }
                 }
-            }
-            BaseExprData::ForLoop {
-                var_name,
-                until,
-                body,
-            } => {
-                // Recursively merge if statements in the body
-                let merged_body = match merge_if_statements(body) {
-                    Ok(body) => body,
-                    Err(e) => return Err(e),
-                };
-
-                merged_statements.push(BaseExpr {
-                    data: BaseExprData::ForLoop {
-                        var_name: var_name,
-                        until: until,
-                        body: merged_body,
-                    },
-                    row: base_expression.row,
-                    col_start: base_expression.col_start,
-                    col_end: base_expression.col_end,
-                    generic_data: base_expression.generic_data,
-                });
-            }
-            BaseExprData::FunctionDefinition {
-                fun_name,
-                args,
-                body,
-            } => {
-                // Recursively merge if statements in the body
-                let merged_body = match merge_if_statements(body) {
-                    Ok(body) => body,
-                    Err(e) => return Err(e),
-                };
-
-                merged_statements.push(BaseExpr {
-                    data: BaseExprData::FunctionDefinition {
-                        fun_name: fun_name,
-                        args: args,
-                        body: merged_body,
-                    },
-                    row: base_expression.row,
-                    col_start: base_expression.col_start,
-                    col_end: base_expression.col_end,
-                    generic_data: base_expression.generic_data,
-                });
-            }
-            other => {
-                merged_statements.push(BaseExpr {
-                    data: other,
-                    ..base_expression
-                });
+                _ => merged_statements.push(base_expression),
             }
         }
-    }
 
-    return Ok(merged_statements);
+        return Ok(merged_statements);
+    }
 }
 
 fn get_base_expressions(token_lines: &Vec<TokenLine>) -> Result<Vec<BaseExpr<()>>, Error> {
@@ -1593,8 +2451,163 @@ fn get_base_expressions_with_indentation(
     return Ok(expressions);
 }
 
+// Reads the field names of a struct, one per line below the struct line
+fn get_struct_fields(
+    token_lines_iter: &mut std::iter::Peekable<std::slice::Iter<'_, TokenLine>>,
+    indentation: usize,
+) -> Result<Vec<String>, Error> {
+    let mut fields: Vec<String> = Vec::new();
+
+    while let Some(token_line) = token_lines_iter.peek() {
+        if token_line.indentation < indentation {
+            break;
+        }
+
+        match &token_line.tokens[..] {
+            [field_token @ Token {
+                data: TokenData::Variable { name },
+                ..
+            }] => {
+                if fields.contains(name) {
+                    return Err(Error::located(
+                        format!("Field {} is defined twice", name),
+                        field_token.span(),
+                    ));
+                }
+                fields.push(name.clone());
+            }
+            [first, .., last] => {
+                return Err(Error::located(
+                    String::from("Expected a single field name"),
+                    first.span().merge(&last.span()),
+                ));
+            }
+            [only_one] => {
+                return Err(Error::located(String::from("Expected a field name"), only_one.span()));
+            }
+            [] => {}
+        }
+        token_lines_iter.next();
+    }
+
+    Ok(fields)
+}
+
+// Reads the cases of a match block, each with its body indented below it
+fn get_match_arms(
+    token_lines_iter: &mut std::iter::Peekable<std::slice::Iter<'_, TokenLine>>,
+    indentation: usize,
+) -> Result<Vec<MatchArm<()>>, Error> {
+    let mut arms = Vec::new();
+
+    while let Some(token_line) = token_lines_iter.peek() {
+        if token_line.indentation < indentation {
+            break;
+        }
+
+        let span = match &token_line.tokens[..] {
+            [first, .., last] => first.span().merge(&last.span()),
+            [only_one] => only_one.span(),
+            [] => {
+                token_lines_iter.next();
+                continue;
+            }
+        };
+        let pattern = match &token_line.tokens[..] {
+            [case_token @ Token {
+                data: TokenData::Symbol {
+                    symbol_type: SymbolType::Case,
+                },
+                ..
+            }] => {
+                return Err(Error::located(
+                    "Expected a pattern after 'case'".to_string(),
+                    span_after(case_token),
+                ));
+            }
+            [Token {
+                data: TokenData::Symbol {
+                    symbol_type: SymbolType::Case,
+                },
+                ..
+            }, pattern_tokens @ ..] => match get_pattern(pattern_tokens) {
+                Some(pattern) => pattern,
+                None => {
+                    return Err(Error::located(
+                        "Expected a number, string, boolean, name or _ as the pattern".to_string(),
+                        pattern_tokens[0].span().merge(&pattern_tokens[pattern_tokens.len() - 1].span()),
+                    ));
+                }
+            },
+            _ => {
+                return Err(Error::located(
+                    "Expected a case like: case 1".to_string(),
+                    span,
+                ));
+            }
+        };
+
+        let indentation = token_line.indentation;
+        token_lines_iter.next();
+        let body = get_base_expressions_with_indentation(token_lines_iter, indentation + 1)?;
+        arms.push(MatchArm::new(pattern, body, span));
+    }
+
+    Ok(arms)
+}
+
+fn get_pattern(tokens: &[Token]) -> Option<Pattern> {
+    match tokens {
+        [Token {
+            data: TokenData::Number { number },
+            ..
+        }] => Some(Pattern::Number(*number)),
+        [Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Minus,
+            },
+            ..
+        }, Token {
+            data: TokenData::Number { number },
+            ..
+        }] => Some(Pattern::Number(-number)),
+        [Token {
+            data: TokenData::String { value },
+            ..
+        }] => Some(Pattern::String(value.clone())),
+        [Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::True,
+            },
+            ..
+        }] => Some(Pattern::Boolean(true)),
+        [Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::False,
+            },
+            ..
+        }] => Some(Pattern::Boolean(false)),
+        [Token {
+            data: TokenData::Variable { name },
+            ..
+        }] if name == "_" => Some(Pattern::Wildcard),
+        [Token {
+            data: TokenData::Variable { name },
+            ..
+        }] => Some(Pattern::Binding(name.clone())),
+        _ => None,
+    }
+}
+
+// Nested blocks recurse once per level, see stack::ensure_stack
 fn get_base_expression(
     token_lines_iter: &mut std::iter::Peekable<std::slice::Iter<'_, TokenLine>>,
+) -> Result<BaseExpr<()>, Error> {
+    stack::ensure_stack(|| get_base_expression_unguarded(token_lines_iter))
+}
+
+fn get_base_expression_unguarded(
+    token_lines_iter: &mut std::iter::Peekable<std::slice::Iter<'_, TokenLine>>,
 ) -> Result<BaseExpr<()>, Error> {
     let Some(token_line) = token_lines_iter.next() else {
         return Err(Error::SimpleError {
@@ -1603,9 +2616,9 @@ fn get_base_expression(
     };
 
     let tokens = &token_line.tokens;
-    let (row, col_start, col_end) = match &tokens[..] {
-        [first, .., last] => (first.row, first.col_start, last.col_end),
-        [only_one] => (only_one.row, only_one.col_start, only_one.col_end),
+    let span = match &tokens[..] {
+        [first, .., last] => first.span.merge(&last.span),
+        [only_one] => only_one.span,
         _ => {
             return Err(Error::SimpleError {
                 message: format!("No tokens found"),
@@ -1614,6 +2627,37 @@ fn get_base_expression(
     };
 
     let data: BaseExprData<()> = match &tokens[..] {
+        [Token {
+            data: TokenData::Variable { name },
+            ..
+        }, ..] if split_target_assignment(tokens).is_some() => {
+            let (mut steps, rest) = split_target_assignment(tokens).unwrap();
+            let last_step = steps.pop().unwrap();
+            let mut path = Vec::new();
+            for step in steps {
+                path.push(match step {
+                    TargetStep::Field(name, span) => Accessor::Field {
+                        name: name.clone(),
+                        span,
+                    },
+                    TargetStep::Index(index) => Accessor::Index(get_expression(index)?),
+                });
+            }
+            match last_step {
+                TargetStep::Field(field, _) => BaseExprData::FieldAssignment {
+                    object: name.clone(),
+                    path,
+                    field: field.clone(),
+                    expr: get_expression(rest)?,
+                },
+                TargetStep::Index(index) => BaseExprData::IndexAssignment {
+                    object: name.clone(),
+                    path,
+                    index: get_expression(index)?,
+                    expr: get_expression(rest)?,
+                },
+            }
+        }
         [Token {
             data: TokenData::Variable { name },
             ..
@@ -1633,14 +2677,69 @@ fn get_base_expression(
             }
         }
         [Token {
-            data: TokenData::Variable { name },
+            data: TokenData::Variable { .. },
             ..
         }, Token {
-            data:
-                TokenData::Symbol {
-                    symbol_type: SymbolType::PlusEquals,
-                },
-            ..
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Comma,
+            },
+            ..
+        }, ..] if split_destructuring_assignment(tokens).is_some() => {
+            let (var_names, rest) = split_destructuring_assignment(tokens).unwrap();
+            for (i, var_name) in var_names.iter().enumerate() {
+                if var_names[..i].contains(var_name) {
+                    return Err(Error::located(
+                        format!("The variable {} is assigned twice in the same assignment", var_name),
+                        span,
+                    ));
+                }
+            }
+            BaseExprData::DestructuringAssignment {
+                var_names,
+                expr: get_values_expression(rest)?,
+            }
+        }
+        // x++ and x-- are short for x += 1 and x += -1.
+        // The tokenizer splits ++ into two pluses, so they must be directly next to each other.
+        [Token {
+            data: TokenData::Variable { name },
+            ..
+        }, first @ Token {
+            data:
+                TokenData::Symbol {
+                    symbol_type: step_symbol @ (SymbolType::Plus | SymbolType::Minus),
+                },
+            ..
+        }, second @ Token {
+            data: TokenData::Symbol { symbol_type },
+            ..
+        }] if symbol_type == step_symbol && first.span.byte_end == second.span.byte_start => {
+            let step_span = first.span().merge(&second.span());
+            let one = RecExpr::new(RecExprData::Number { number: 1 }, step_span, ());
+            let step = match step_symbol {
+                SymbolType::Plus => one,
+                _ => RecExpr::new(
+                    RecExprData::Minus {
+                        right: Box::new(one),
+                    },
+                    step_span,
+                    (),
+                ),
+            };
+            BaseExprData::PlusEqualsStatement {
+                var_name: name.clone(),
+                expr: step,
+            }
+        }
+        [Token {
+            data: TokenData::Variable { name },
+            ..
+        }, Token {
+            data:
+                TokenData::Symbol {
+                    symbol_type: SymbolType::PlusEquals,
+                },
+            ..
         }, rest @ ..] => {
             let expression = match get_expression(rest) {
                 Ok(expression) => expression,
@@ -1657,7 +2756,7 @@ fn get_base_expression(
             },
             ..
         }, rest @ ..] => {
-            let condition = match get_expression(rest) {
+            let condition = match get_condition(rest) {
                 Ok(expression) => expression,
                 Err(error_message) => return Err(error_message),
             };
@@ -1687,7 +2786,7 @@ fn get_base_expression(
             },
             ..
         }, rest @ ..] => {
-            let condition = match get_expression(rest) {
+            let condition = match get_condition(rest) {
                 Ok(expression) => expression,
                 Err(error_message) => return Err(error_message),
             };
@@ -1714,20 +2813,16 @@ fn get_base_expression(
         }, rest @ ..] => {
             match rest {
                 [first, .., last] => {
-                    return Err(Error::LocationError {
-                        message: format!("Unexpected extra tokens on else statement"),
-                        row: first.row,
-                        col_start: first.col_start,
-                        col_end: last.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Unexpected extra tokens on else statement"),
+                        first.span().merge(&last.span()),
+                    ));
                 }
                 [only_one] => {
-                    return Err(Error::LocationError {
-                        message: format!("Unexpected extra tokens on else statement"),
-                        row: only_one.row,
-                        col_start: only_one.col_start,
-                        col_end: only_one.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Unexpected extra tokens on else statement"),
+                        only_one.span(),
+                    ));
                 }
                 _ => {}
             }
@@ -1750,26 +2845,37 @@ fn get_base_expression(
         }, rest @ ..] => {
             match rest {
                 [first, .., last] => {
-                    return Err(Error::LocationError {
-                        message: format!("Unexpected extra tokens on else statement"),
-                        row: first.row,
-                        col_start: first.col_start,
-                        col_end: last.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Unexpected extra tokens on else statement"),
+                        first.span().merge(&last.span()),
+                    ));
                 }
                 [only_one] => {
-                    return Err(Error::LocationError {
-                        message: format!("Unexpected extra tokens on else statement"),
-                        row: only_one.row,
-                        col_start: only_one.col_start,
-                        col_end: only_one.col_end,
-                    });
+                    return Err(Error::located(
+                        format!("Unexpected extra tokens on else statement"),
+                        only_one.span(),
+                    ));
                 }
                 _ => {}
             }
 
             BaseExprData::Break
         }
+        [Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Continue,
+            },
+            ..
+        }, rest @ ..] => {
+            if let [first, .., last] | [first @ last] = rest {
+                return Err(Error::located(
+                    "Unexpected extra tokens on continue statement".to_string(),
+                    first.span().merge(&last.span()),
+                ));
+            }
+
+            BaseExprData::Continue
+        }
         [Token {
             data: TokenData::Symbol {
                 symbol_type: SymbolType::For,
@@ -1780,12 +2886,19 @@ fn get_base_expression(
                 name: variable_name,
             },
             ..
-        }, Token {
+        }, in_token @ Token {
             data: TokenData::Symbol {
                 symbol_type: SymbolType::In,
             },
             ..
         }, rest @ ..] => {
+            if rest.is_empty() {
+                return Err(Error::located(
+                    "Expected a range or list to loop over after 'in'".to_string(),
+                    span_after(in_token),
+                ));
+            }
+
             let range = match get_expression(rest) {
                 Ok(expression) => expression,
                 Err(error_message) => return Err(error_message),
@@ -1805,12 +2918,117 @@ fn get_base_expression(
                 body: body,
             }
         }
+        [for_token @ Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::For,
+            },
+            ..
+        }, rest @ ..] => {
+            return Err(malformed_for_loop_error(for_token, rest));
+        }
+        [while_token @ Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::While,
+            },
+            ..
+        }, rest @ ..] => {
+            if rest.is_empty() {
+                return Err(Error::located(
+                    "Expected a condition after 'while'".to_string(),
+                    span_after(while_token),
+                ));
+            }
+
+            let condition = get_condition(rest)?;
+            let body =
+                get_base_expressions_with_indentation(token_lines_iter, token_line.indentation + 1)?;
+
+            BaseExprData::WhileLoop { condition, body }
+        }
+        [Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::With,
+            },
+            ..
+        }, resource_tokens @ .., Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::As,
+            },
+            ..
+        }, Token {
+            data: TokenData::Variable {
+                name: variable_name,
+            },
+            ..
+        }] => {
+            let resource = match get_expression(resource_tokens) {
+                Ok(expression) => expression,
+                Err(error_message) => return Err(error_message),
+            };
+
+            let body = match get_base_expressions_with_indentation(
+                token_lines_iter,
+                token_line.indentation + 1,
+            ) {
+                Ok(body) => body,
+                Err(e) => return Err(e),
+            };
+
+            BaseExprData::With {
+                resource,
+                var_name: variable_name.clone(),
+                body,
+            }
+        }
+        [with_token @ Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::With,
+            },
+            ..
+        }, rest @ ..] => {
+            let span = match rest.last() {
+                Some(last) => with_token.span.merge(&last.span),
+                None => with_token.span,
+            };
+            return Err(Error::located(format!("Expected a with block like: with resource as name"), span));
+        }
+        [match_token @ Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Match,
+            },
+            ..
+        }, rest @ ..] => {
+            if rest.is_empty() {
+                return Err(Error::located(
+                    "Expected a value to match after 'match'".to_string(),
+                    span_after(match_token),
+                ));
+            }
+
+            let subject = get_expression(rest)?;
+            let arms = get_match_arms(token_lines_iter, token_line.indentation + 1)?;
+            if arms.is_empty() {
+                return Err(Error::located("Expected at least one case below the match".to_string(), span));
+            }
+
+            BaseExprData::Match { subject, arms }
+        }
+        [case_token @ Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Case,
+            },
+            ..
+        }, ..] => {
+            return Err(Error::located(
+                "A case can only be written directly below a match".to_string(),
+                case_token.span(),
+            ));
+        }
         [Token {
             data: TokenData::Symbol {
                 symbol_type: SymbolType::Fun,
             },
-            row,
-            col_start,
+            span: fun_span,
             ..
         }, Token {
             data: TokenData::Variable {
@@ -1822,27 +3040,24 @@ fn get_base_expression(
                 TokenData::Symbol {
                     symbol_type: SymbolType::ParenthesisOpen,
                 },
-            col_end,
+            span: open_span,
             ..
         }, rest @ ..] => {
             let parameters = match parse_function_parameters(rest) {
                 Ok(parameters) => parameters,
+                Err(error @ Error::LocationError { .. }) => return Err(error),
                 Err(_) => match rest {
                     [.., last] => {
-                        return Err(Error::LocationError {
-                            message: format!("Invalid function parameters"),
-                            row: *row,
-                            col_start: *col_start,
-                            col_end: last.col_end,
-                        })
+                        return Err(Error::located(
+                            format!("Invalid function parameters"),
+                            fun_span.merge(&last.span),
+                        ))
                     }
                     _ => {
-                        return Err(Error::LocationError {
-                            message: format!("Invalid function parameters"),
-                            row: *row,
-                            col_start: *col_start,
-                            col_end: *col_end,
-                        })
+                        return Err(Error::located(
+                            format!("Invalid function parameters"),
+                            fun_span.merge(open_span),
+                        ))
                     }
                 },
             };
@@ -1861,6 +3076,34 @@ fn get_base_expression(
                 body: body,
             }
         }
+        [Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Struct,
+            },
+            ..
+        }, Token {
+            data: TokenData::Variable { name: struct_name },
+            ..
+        }] => {
+            let fields = get_struct_fields(token_lines_iter, token_line.indentation + 1)?;
+
+            BaseExprData::StructDefinition {
+                name: struct_name.clone(),
+                fields,
+            }
+        }
+        [struct_token @ Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Struct,
+            },
+            ..
+        }, rest @ ..] => {
+            let span = match rest.last() {
+                Some(last) => struct_token.span.merge(&last.span),
+                None => struct_token.span,
+            };
+            return Err(Error::located(String::from("Expected a struct definition like: struct Name"), span));
+        }
         [Token {
             data: TokenData::Symbol {
                 symbol_type: SymbolType::Return,
@@ -1870,104 +3113,488 @@ fn get_base_expression(
             if rest.len() == 0 {
                 BaseExprData::Return { return_value: None }
             } else {
-                let expression = match get_expression(rest) {
-                    Ok(expression) => expression,
-                    Err(error_message) => return Err(error_message),
-                };
                 BaseExprData::Return {
-                    return_value: Some(expression),
+                    return_value: Some(get_values_expression(rest)?),
                 }
             }
         }
-        rest @ _ => {
+        [yield_token @ Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Yield,
+            },
+            ..
+        }, rest @ ..] => {
+            if rest.len() == 0 {
+                return Err(Error::located(
+                    format!("Expected a value to yield"),
+                    yield_token.span(),
+                ));
+            }
             let expression = match get_expression(rest) {
                 Ok(expression) => expression,
                 Err(error_message) => return Err(error_message),
             };
-            BaseExprData::Simple { expr: expression }
+            BaseExprData::Yield { value: expression }
+        }
+        [defer_token @ Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Defer,
+            },
+            ..
+        }, rest @ ..] => {
+            if rest.len() == 0 {
+                return Err(Error::located(
+                    format!("Expected an expression to defer"),
+                    defer_token.span(),
+                ));
+            }
+            let expression = match get_expression(rest) {
+                Ok(expression) => expression,
+                Err(error_message) => return Err(error_message),
+            };
+            BaseExprData::Defer { expr: expression }
         }
-    };
-
-    return Ok(BaseExpr {
-        data,
-        row,
-        col_start,
-        col_end,
-        generic_data: (),
-    });
-}
-
-fn parse_function_parameters(tokens: &[Token]) -> Result<Vec<String>, Error> {
-    match tokens {
         [Token {
-            data: TokenData::Variable {
-                name: parameter_name,
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Del,
             },
             ..
         }, Token {
-            data: TokenData::Symbol {
-                symbol_type: SymbolType::Comma,
+            data: TokenData::Variable {
+                name: variable_name,
             },
             ..
-        }, rest @ ..] => match parse_function_parameters(rest) {
-            Ok(mut other_parameters) => {
-                other_parameters.insert(0, parameter_name.clone());
-                return Ok(other_parameters);
-            }
-            Err(e) => return Err(e),
+        }] => BaseExprData::Delete {
+            var_name: variable_name.clone(),
         },
-
+        [del_token @ Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Del,
+            },
+            ..
+        }, rest @ ..] => {
+            let span = match rest.last() {
+                Some(last) => del_token.span.merge(&last.span),
+                None => del_token.span,
+            };
+            return Err(Error::located(format!("Expected a single variable name to delete"), span));
+        }
         [Token {
-            data: TokenData::Variable {
-                name: parameter_name,
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Swap,
             },
             ..
         }, Token {
-            data:
-                TokenData::Symbol {
-                    symbol_type: SymbolType::ParenthesisClosed,
-                },
+            data: TokenData::Variable { name: first_name },
             ..
-        }] => {
-            return Ok(vec![parameter_name.clone()]);
+        }, Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Comma,
+            },
+            ..
+        }, Token {
+            data: TokenData::Variable { name: second_name },
+            ..
+        }] => BaseExprData::Swap {
+            first_name: first_name.clone(),
+            second_name: second_name.clone(),
+        },
+        [swap_token @ Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Swap,
+            },
+            ..
+        }, rest @ ..] => {
+            let span = match rest.last() {
+                Some(last) => swap_token.span().merge(&last.span()),
+                None => swap_token.span(),
+            };
+            return Err(Error::located(
+                "Expected two variable names to swap, as in swap a, b".to_string(),
+                span,
+            ));
         }
+        rest @ _ => {
+            let expression = match get_expression(rest) {
+                Ok(expression) => expression,
+                // A line which does not parse may start with a misspelled keyword, like retrun x
+                Err(error_message) => return Err(misspelled_keyword_error(rest).unwrap_or(error_message)),
+            };
 
-        // Closing bracket
-        [Token {
-            data:
-                TokenData::Symbol {
-                    symbol_type: SymbolType::ParenthesisClosed,
+            // So does a line with a block below it, like esle
+            let starts_block = token_lines_iter
+                .peek()
+                .is_some_and(|next_line| next_line.indentation > token_line.indentation);
+            if starts_block {
+                if let Some(error) = misspelled_keyword_error(rest) {
+                    return Err(error);
+                }
+            }
+
+            BaseExprData::Simple { expr: expression }
+        }
+    };
+
+    return Ok(BaseExpr {
+        data,
+        span,
+        generic_data: (),
+    });
+}
+
+// Reads the parameters of a function definition, the tokens are those after its opening parenthesis
+fn parse_function_parameters(tokens: &[Token]) -> Result<Vec<Parameter<()>>, Error> {
+    let [content @ .., Token {
+        data: TokenData::Symbol {
+            symbol_type: SymbolType::ParenthesisClosed,
+        },
+        ..
+    }] = tokens
+    else {
+        return Err(Error::SimpleError {
+            message: "Invalid function parameter definition".to_string(),
+        });
+    };
+
+    let mut parts = split_top_level(content, &SymbolType::Comma);
+    // A trailing comma is allowed, as is an empty list
+    if matches!(parts.last(), Some(last) if last.is_empty()) {
+        parts.pop();
+    }
+
+    let mut parameters: Vec<Parameter<()>> = Vec::new();
+    for part in parts {
+        let parameter = match part {
+            [Token {
+                data: TokenData::Variable { name },
+                ..
+            }] => Parameter::new(name),
+            [Token {
+                data: TokenData::Variable { name },
+                ..
+            }, Token {
+                data: TokenData::Symbol {
+                    symbol_type: SymbolType::Equals,
                 },
-            ..
-        }, rest @ ..] => {
-            match rest {
-                [first, .., last] => {
-                    return Err(Error::LocationError {
-                        message: format!("Unexpected extra tokens on else statement"),
-                        row: first.row,
-                        col_start: first.col_start,
-                        col_end: last.col_end,
-                    });
+                ..
+            }, default @ ..]
+                if !default.is_empty() =>
+            {
+                Parameter {
+                    name: name.clone(),
+                    default: Some(get_expression(default)?),
+                    rest: false,
                 }
-                [only_one] => {
-                    return Err(Error::LocationError {
-                        message: format!("Unexpected extra tokens on else statement"),
-                        row: only_one.row,
-                        col_start: only_one.col_start,
-                        col_end: only_one.col_end,
-                    });
+            }
+            [Token {
+                data: TokenData::Symbol {
+                    symbol_type: SymbolType::Star,
+                },
+                ..
+            }, Token {
+                data: TokenData::Variable { name },
+                ..
+            }] => Parameter {
+                name: name.clone(),
+                default: None,
+                rest: true,
+            },
+            [Token {
+                data: TokenData::Symbol {
+                    symbol_type: SymbolType::Star,
+                },
+                ..
+            }, Token {
+                data: TokenData::Variable { .. },
+                ..
+            }, Token {
+                data: TokenData::Symbol {
+                    symbol_type: SymbolType::Equals,
+                },
+                ..
+            }, ..] => {
+                return Err(Error::located(
+                    "A *rest parameter cannot have a default value".to_string(),
+                    part[0].span(),
+                ))
+            }
+            _ => {
+                return Err(Error::SimpleError {
+                    message: "Invalid function parameter definition".to_string(),
+                })
+            }
+        };
+
+        if let Some(earlier) = parameters.iter().find(|earlier| earlier.rest) {
+            return Err(Error::located(
+                format!("The parameter *{} collects the remaining arguments, so it must be the last", earlier.name),
+                part[0].span(),
+            ));
+        }
+        if parameter.default.is_none() && !parameter.rest && parameters.iter().any(|earlier| earlier.default.is_some()) {
+            return Err(Error::located(
+                format!(
+                    "Parameter {} needs a default value, as it comes after a parameter with one",
+                    parameter.name
+                ),
+                part[0].span(),
+            ));
+        }
+        parameters.push(parameter);
+    }
+
+    // Defaults are computed where the function is called, where the parameters have no value yet
+    let names = parameter_names(&parameters);
+    for parameter in &parameters {
+        let Some(default) = &parameter.default else {
+            continue;
+        };
+        if let Some(used) = visitor::referenced_names_in_expression(default)
+            .into_iter()
+            .find(|name| names.contains(name))
+        {
+            return Err(Error::located(
+                format!("The default value of {} cannot use the parameter {}", parameter.name, used),
+                default.span(),
+            ));
+        }
+    }
+
+    Ok(parameters)
+}
+
+// A field or element on the way to what an assignment changes, see Accessor
+enum TargetStep<'a> {
+    Field(&'a String, Span),
+    Index(&'a [Token]),
+}
+
+// Splits an assignment to a field or element, such as point.x = 5 or grid[i][j] = 0,
+// into the fields and indices after the variable and the tokens of the value
+fn split_target_assignment(tokens: &[Token]) -> Option<(Vec<TargetStep<'_>>, &[Token])> {
+    let mut steps = Vec::new();
+    let mut i = 1;
+    loop {
+        match &tokens.get(i)?.data {
+            TokenData::Symbol {
+                symbol_type: SymbolType::Equals,
+            } if !steps.is_empty() => return Some((steps, &tokens[i + 1..])),
+            TokenData::Symbol {
+                symbol_type: SymbolType::Dot,
+            } => {
+                let name_token = tokens.get(i + 1)?;
+                let TokenData::Variable { name } = &name_token.data else {
+                    return None;
+                };
+                steps.push(TargetStep::Field(name, name_token.span()));
+                i += 2;
+            }
+            TokenData::Symbol {
+                symbol_type: SymbolType::SquareBracketOpen,
+            } => {
+                let closing = i + find_closing_bracket(&tokens[i..])?;
+                steps.push(TargetStep::Index(&tokens[i + 1..closing]));
+                i = closing + 1;
+            }
+            _ => return None,
+        }
+    }
+}
+
+// Splits an assignment to several variables, such as a, b = pair, into the names and the tokens of the value
+fn split_destructuring_assignment(tokens: &[Token]) -> Option<(Vec<String>, &[Token])> {
+    let mut var_names = Vec::new();
+    for (i, pair) in tokens.chunks(2).enumerate() {
+        let (name, separator) = match pair {
+            [Token {
+                data: TokenData::Variable { name },
+                ..
+            }, Token {
+                data: TokenData::Symbol { symbol_type },
+                ..
+            }] => (name, symbol_type),
+            _ => return None,
+        };
+        var_names.push(name.clone());
+        match separator {
+            SymbolType::Comma => {}
+            SymbolType::Equals if var_names.len() > 1 => return Some((var_names, &tokens[2 * i + 2..])),
+            _ => return None,
+        }
+    }
+    None
+}
+
+// Finds the closing bracket matching the opening bracket at the start of the tokens
+fn find_closing_bracket(tokens: &[Token]) -> Option<usize> {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match token.data {
+            TokenData::Symbol {
+                symbol_type:
+                    SymbolType::SquareBracketOpen
+                    | SymbolType::ParenthesisOpen
+                    | SymbolType::CurlyBracketOpen,
+            } => depth += 1,
+            TokenData::Symbol {
+                symbol_type:
+                    SymbolType::SquareBracketClosed
+                    | SymbolType::ParenthesisClosed
+                    | SymbolType::CurlyBracketClosed,
+            } => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
                 }
-                _ => {}
             }
+            _ => {}
+        }
+    }
+    None
+}
 
-            return Ok(Vec::new());
+// Finds the opening square bracket matching the closing bracket at the end of the tokens
+fn find_index_bracket(tokens: &[Token]) -> Option<usize> {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().rev() {
+        match token.data {
+            TokenData::Symbol {
+                symbol_type:
+                    SymbolType::SquareBracketClosed
+                    | SymbolType::ParenthesisClosed
+                    | SymbolType::CurlyBracketClosed,
+            } => depth += 1,
+            TokenData::Symbol {
+                symbol_type:
+                    SymbolType::SquareBracketOpen
+                    | SymbolType::ParenthesisOpen
+                    | SymbolType::CurlyBracketOpen,
+            } => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
         }
-        _ => {
-            return Err(Error::SimpleError {
-                message: format!("Invalid function parameter definition"),
-            })
+    }
+
+    return None;
+}
+
+// The error for a line starting with a variable name which looks like a misspelled keyword
+fn misspelled_keyword_error(tokens: &[Token]) -> Option<Error> {
+    let [first @ Token {
+        data: TokenData::Variable { name },
+        ..
+    }, ..] = tokens
+    else {
+        return None;
+    };
+
+    let keyword = tokenizer::closest_keyword(name)?;
+    Some(Error::located(
+        format!("Unknown statement; did you mean '{}'?", keyword),
+        first.span(),
+    ))
+}
+
+pub const ASSIGNMENT_IN_CONDITION_MESSAGE: &str = "Use == to compare in a condition, = assigns a variable";
+
+// The = in the tokens of a condition which was meant to be ==, like: if a = 1.
+// An = inside brackets is left alone, it belongs to a nested expression.
+pub fn find_assignment_in_condition(condition: &[Token]) -> Option<&Token> {
+    let mut depth = 0;
+    for token in condition {
+        match token.data {
+            TokenData::Symbol {
+                symbol_type:
+                    SymbolType::ParenthesisOpen
+                    | SymbolType::SquareBracketOpen
+                    | SymbolType::CurlyBracketOpen,
+            } => depth += 1,
+            TokenData::Symbol {
+                symbol_type:
+                    SymbolType::ParenthesisClosed
+                    | SymbolType::SquareBracketClosed
+                    | SymbolType::CurlyBracketClosed,
+            } => depth -= 1,
+            TokenData::Symbol {
+                symbol_type: SymbolType::Equals,
+            } if depth == 0 => return Some(token),
+            _ => {}
         }
     }
+
+    None
+}
+
+// Parses the condition of an if, else if or while statement
+fn get_condition(condition: &[Token]) -> Result<RecExpr<()>, Error> {
+    if let Some(equals_token) = find_assignment_in_condition(condition) {
+        return Err(Error::located(
+            ASSIGNMENT_IN_CONDITION_MESSAGE.to_string(),
+            equals_token.span(),
+        ));
+    }
+
+    get_expression(condition)
+}
+
+// The single column right after a token, where a missing token is expected
+fn span_after(token: &Token) -> Span {
+    token.span.after()
+}
+
+// Describes which part is missing from a for loop header that is not shaped like: for i in range
+fn malformed_for_loop_error(for_token: &Token, rest: &[Token]) -> Error {
+    let in_index = rest.iter().position(|token| {
+        matches!(
+            token.data,
+            TokenData::Symbol {
+                symbol_type: SymbolType::In
+            }
+        )
+    });
+
+    match (rest, in_index) {
+        ([], _) => Error::located(
+            "Expected a loop variable after 'for', like: for i in 10".to_string(),
+            span_after(for_token),
+        ),
+        (_, Some(0)) => Error::located(
+            "Expected a loop variable between 'for' and 'in'".to_string(),
+            rest[0].span(),
+        ),
+        (_, Some(in_index)) => Error::located(
+            "Expected a single variable name between 'for' and 'in'".to_string(),
+            rest[0].span().merge(&rest[in_index - 1].span()),
+        ),
+        (
+            [Token {
+                data: TokenData::Variable { name },
+                ..
+            }, after_variable, ..],
+            None,
+        ) => Error::located(
+            format!("Expected 'in' after the loop variable {}", name),
+            after_variable.span(),
+        ),
+        (
+            [variable @ Token {
+                data: TokenData::Variable { name },
+                ..
+            }],
+            None,
+        ) => Error::located(
+            format!("Expected 'in' after the loop variable {}", name),
+            span_after(variable),
+        ),
+        ([first, ..], None) => Error::located(
+            "Expected a loop variable after 'for', like: for i in 10".to_string(),
+            first.span(),
+        ),
+    }
 }
 
 /*
@@ -1981,244 +3608,338 @@ fn find_next_bracket(tokens: &[Token]) -> i32 {
 }
 */
 
-pub fn print_expressions(expressions: &Vec<BaseExpr<()>>) {
+// Writes expressions in a compact debugging format, one line per expression, for logging the parsed program
+pub fn expressions_to_string(expressions: &[BaseExpr<()>]) -> String {
+    let mut printer = ExpressionPrinter {
+        indentation: 0,
+        output: String::new(),
+    };
     for expression in expressions {
-        print_expression(expression, 0);
-        print!("\n");
+        printer.visit_base_expr(expression);
+        printer.write("\n");
     }
+    printer.output
 }
 
-fn print_indentation(indentation: i32) {
-    for _ in 0..indentation {
-        print!("  ")
+// Writes expressions in a compact debugging format, indenting nested blocks
+struct ExpressionPrinter {
+    indentation: i32,
+    output: String,
+}
+
+impl ExpressionPrinter {
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    // The path of a nested assignment follows its variable: "grid"[0]
+    fn print_path(&mut self, path: &[Accessor<()>]) {
+        for accessor in path {
+            match accessor {
+                Accessor::Field { name, .. } => self.write(&format!(".{name}")),
+                Accessor::Index(index) => {
+                    self.write("[");
+                    self.visit_rec_expr(index);
+                    self.write("]");
+                }
+            }
+        }
+    }
+
+    fn print_binary(&mut self, left: &RecExpr<()>, operator: &str, right: &RecExpr<()>) {
+        self.write("(");
+        self.visit_rec_expr(left);
+        self.write(&format!(" {operator} "));
+        self.visit_rec_expr(right);
+        self.write(")");
     }
 }
 
-fn print_expression(expression: &BaseExpr<()>, indentation: i32) {
-    print_indentation(indentation);
-    match &expression.data {
-        BaseExprData::Simple { expr } => print_recursive_expression(expr),
-        BaseExprData::VariableAssignment { var_name, expr } => {
-            print!("VarAssign({var_name:?}, ");
-            print_recursive_expression(expr);
-            print!(")");
+impl Visitor<()> for ExpressionPrinter {
+    fn visit_body(&mut self, body: &[BaseExpr<()>]) {
+        self.indentation += 1;
+        for expr in body {
+            self.visit_base_expr(expr);
         }
-        BaseExprData::PlusEqualsStatement { var_name, expr } => {
-            print!("PlusEquals({var_name:?}, ");
-            print_recursive_expression(expr);
-            print!(")");
+        self.indentation -= 1;
+    }
+
+    fn visit_base_expr(&mut self, expression: &BaseExpr<()>) {
+        for _ in 0..self.indentation {
+            self.write("  ")
         }
-        BaseExprData::IfStatement {
-            condition, body, ..
-        } => {
-            print!("IfSt(");
-            print_recursive_expression(condition);
-            print!(")\n");
-            for expr in body {
-                print_expression(expr, indentation + 1);
+        match &expression.data {
+            BaseExprData::Simple { expr } => self.visit_rec_expr(expr),
+            BaseExprData::VariableAssignment { var_name, expr } => {
+                self.write(&format!("VarAssign({var_name:?}, "));
+                self.visit_rec_expr(expr);
+                self.write(")");
             }
-        }
-        BaseExprData::ElseIfStatement {
-            condition, body, ..
-        } => {
-            print!("ElseIfSt(");
-            print_recursive_expression(condition);
-            print!(")");
-            for expr in body {
-                print_expression(expr, indentation + 1);
+            BaseExprData::DestructuringAssignment { var_names, expr } => {
+                self.write(&format!("DestructuringAssign({var_names:?}, "));
+                self.visit_rec_expr(expr);
+                self.write(")");
             }
-        }
-        BaseExprData::ElseStatement { body } => {
-            print!("ElseSt(");
-            for expr in body {
-                print_expression(expr, indentation + 1);
+            BaseExprData::PlusEqualsStatement { var_name, expr } => {
+                self.write(&format!("PlusEquals({var_name:?}, "));
+                self.visit_rec_expr(expr);
+                self.write(")");
             }
-            print!(")");
-        }
-        BaseExprData::ForLoop {
-            var_name,
-            until,
-            body,
-        } => {
-            print!("For({var_name:?} in ");
-            print_recursive_expression(until);
-            print!("\n");
-            for expr in body {
-                print_expression(expr, indentation + 1);
+            BaseExprData::IfStatement {
+                condition, body, ..
+            } => {
+                self.write("IfSt(");
+                self.visit_rec_expr(condition);
+                self.write(")\n");
+                self.visit_body(body);
             }
-            print!(")");
-        }
-        BaseExprData::FunctionDefinition {
-            fun_name,
-            args,
-            body,
-        } => {
-            print!("fun {fun_name}(");
-            for (i, arg) in args.iter().enumerate() {
-                print!("{arg}");
-                if i != args.len() - 1 {
-                    print!(", ");
+            BaseExprData::ElseIfStatement {
+                condition, body, ..
+            } => {
+                self.write("ElseIfSt(");
+                self.visit_rec_expr(condition);
+                self.write(")");
+                self.visit_body(body);
+            }
+            BaseExprData::ElseStatement { body } => {
+                self.write("ElseSt(");
+                self.visit_body(body);
+                self.write(")");
+            }
+            BaseExprData::ForLoop {
+                var_name,
+                until,
+                body,
+            } => {
+                self.write(&format!("For({var_name:?} in "));
+                self.visit_rec_expr(until);
+                self.write("\n");
+                self.visit_body(body);
+                self.write(")");
+            }
+            BaseExprData::WhileLoop { condition, body } => {
+                self.write("While(");
+                self.visit_rec_expr(condition);
+                self.write("\n");
+                self.visit_body(body);
+                self.write(")");
+            }
+            BaseExprData::FunctionDefinition {
+                fun_name,
+                args,
+                body,
+            } => {
+                let names: Vec<String> = args
+                    .iter()
+                    .map(|arg| match arg.rest {
+                        true => format!("*{}", arg.name),
+                        false => arg.name.clone(),
+                    })
+                    .collect();
+                self.write(&format!("fun {fun_name}({})\n", names.join(", ")));
+                for default in args.iter().filter_map(|arg| arg.default.as_ref()) {
+                    self.write("Default(");
+                    self.visit_rec_expr(default);
+                    self.write(")\n");
+                }
+                self.visit_body(body);
+            }
+            BaseExprData::Return { return_value } => {
+                self.write("Return(");
+                if let Some(expr) = return_value {
+                    self.visit_rec_expr(expr);
                 }
+                self.write(")")
             }
-            print!(")\n");
-            for expr in body {
-                print_expression(expr, indentation + 1);
+            BaseExprData::StructDefinition { name, fields } => {
+                self.write(&format!("struct {name}({})", fields.join(", ")))
             }
-        }
-        BaseExprData::Return { return_value } => {
-            print!("Return(");
-            match return_value {
-                Some(expr) => print_recursive_expression(expr),
-                None => print!(""),
+            BaseExprData::FieldAssignment {
+                object,
+                path,
+                field,
+                expr,
+            } => {
+                self.write(&format!("FieldAssign({object:?}"));
+                self.print_path(path);
+                self.write(&format!(", {field:?}, "));
+                self.visit_rec_expr(expr);
+                self.write(")");
+            }
+            BaseExprData::IndexAssignment {
+                object,
+                path,
+                index,
+                expr,
+            } => {
+                self.write(&format!("IndexAssign({object:?}"));
+                self.print_path(path);
+                self.write(", ");
+                self.visit_rec_expr(index);
+                self.write(", ");
+                self.visit_rec_expr(expr);
+                self.write(")");
+            }
+            BaseExprData::Break => self.write("break"),
+            BaseExprData::Continue => self.write("continue"),
+            BaseExprData::Yield { value } => {
+                self.write("Yield(");
+                self.visit_rec_expr(value);
+                self.write(")")
+            }
+            BaseExprData::Delete { var_name } => self.write(&format!("del {var_name}")),
+            BaseExprData::Swap {
+                first_name,
+                second_name,
+            } => self.write(&format!("swap {first_name}, {second_name}")),
+            BaseExprData::Defer { expr } => {
+                self.write("Defer(");
+                self.visit_rec_expr(expr);
+                self.write(")")
+            }
+            BaseExprData::With {
+                resource,
+                var_name,
+                body,
+            } => {
+                self.write("with ");
+                self.visit_rec_expr(resource);
+                self.write(&format!(" as {var_name}\n"));
+                self.visit_body(body);
+            }
+            BaseExprData::Match { subject, arms } => {
+                self.write("Match(");
+                self.visit_rec_expr(subject);
+                self.write(")");
+                self.indentation += 1;
+                for arm in arms {
+                    self.write("\n");
+                    for _ in 0..self.indentation {
+                        self.write("  ")
+                    }
+                    self.write(&format!("Case({:?})\n", arm.pattern));
+                    self.visit_body(&arm.body);
+                }
+                self.indentation -= 1;
             }
-            print!(")")
         }
-        BaseExprData::Break => print!("break"),
     }
-}
 
-fn print_recursive_expression(expression: &RecExpr<()>) {
-    match &expression.data {
-        RecExprData::Variable { name } => print!("Var({name:?})"),
-        RecExprData::Number { number } => print!("Num({number})"),
-        RecExprData::String { value } => print!("Str({value:?})"),
-        RecExprData::Boolean { value } => print!("Bool({value})"),
-        RecExprData::Assign {
-            variable_name,
-            right,
-        } => {
-            print!("Var({variable_name:?}) = ");
-            print_recursive_expression(&*right);
-        }
-        RecExprData::Add { left, right } => {
-            print!("(");
-            print_recursive_expression(&*left);
-            print!(" + ");
-            print_recursive_expression(&*right);
-            print!(")")
-        }
-        RecExprData::Subtract { left, right } => {
-            print!("(");
-            print_recursive_expression(&*left);
-            print!(" - ");
-            print_recursive_expression(&*right);
-            print!(")");
-        }
-        RecExprData::Multiply { left, right } => {
-            print!("(");
-            print_recursive_expression(&*left);
-            print!(" * ");
-            print_recursive_expression(&*right);
-            print!(")");
-        }
-        RecExprData::Divide { left, right } => {
-            print!("(");
-            print_recursive_expression(&*left);
-            print!(" / ");
-            print_recursive_expression(&*right);
-            print!(")");
-        }
-        RecExprData::Power { left, right } => {
-            print!("(");
-            print_recursive_expression(&*left);
-            print!(" ^ ");
-            print_recursive_expression(&*right);
-            print!(")");
-        }
-        RecExprData::Minus { right } => {
-            print!("(");
-            print!("- ");
-            print_recursive_expression(&*right);
-            print!(")");
-        }
-        RecExprData::Or { left, right } => {
-            print!("(");
-            print_recursive_expression(&*left);
-            print!(" or ");
-            print_recursive_expression(&*right);
-            print!(")");
-        }
-        RecExprData::And { left, right } => {
-            print!("(");
-            print_recursive_expression(&*left);
-            print!(" and ");
-            print_recursive_expression(&*right);
-            print!(")");
-        }
-        RecExprData::Not { right } => {
-            print!("(");
-            print!("not ");
-            print_recursive_expression(&*right);
-            print!(")");
-        }
-        RecExprData::Equals { left, right } => {
-            print!("(");
-            print_recursive_expression(&*left);
-            print!(" == ");
-            print_recursive_expression(&*right);
-            print!(")");
-        }
-        RecExprData::NotEquals { left, right } => {
-            print!("(");
-            print_recursive_expression(&*left);
-            print!(" != ");
-            print_recursive_expression(&*right);
-            print!(")");
-        }
-        RecExprData::LessThan { left, right } => {
-            print!("(");
-            print_recursive_expression(&*left);
-            print!(" < ");
-            print_recursive_expression(&*right);
-            print!(")");
-        }
-        RecExprData::LessThanOrEqual { left, right } => {
-            print!("(");
-            print_recursive_expression(&*left);
-            print!(" <= ");
-            print_recursive_expression(&*right);
-            print!(")");
-        }
-        RecExprData::GreaterThan { left, right } => {
-            print!("(");
-            print_recursive_expression(&*left);
-            print!(" > ");
-            print_recursive_expression(&*right);
-            print!(")");
-        }
-        RecExprData::GreaterThanOrEqual { left, right } => {
-            print!("(");
-            print_recursive_expression(&*left);
-            print!(" >= ");
-            print_recursive_expression(&*right);
-            print!(")");
-        }
-        RecExprData::Access { object, variable } => {
-            print!("{object:?}.{variable:?}");
-        }
-        RecExprData::FunctionCall {
-            function_name,
-            args,
-        } => {
-            print!("Call({function_name:?} with (");
-            for arg in args {
-                print_recursive_expression(arg);
-                print!(", ");
+    fn visit_rec_expr(&mut self, expression: &RecExpr<()>) {
+        match &expression.data {
+            RecExprData::Variable { name } => self.write(&format!("Var({name:?})")),
+            RecExprData::Number { number } => self.write(&format!("Num({number})")),
+            RecExprData::Float { value } => self.write(&format!("Float({value:?})")),
+            RecExprData::String { value } => self.write(&format!("Str({value:?})")),
+            RecExprData::Boolean { value } => self.write(&format!("Bool({value})")),
+            RecExprData::Assign {
+                variable_name,
+                right,
+            } => {
+                self.write(&format!("Var({variable_name:?}) = "));
+                self.visit_rec_expr(right);
+            }
+            RecExprData::Add { left, right } => self.print_binary(left, "+", right),
+            RecExprData::Subtract { left, right } => self.print_binary(left, "-", right),
+            RecExprData::Multiply { left, right } => self.print_binary(left, "*", right),
+            RecExprData::Divide { left, right } => self.print_binary(left, "/", right),
+            RecExprData::FloorDivide { left, right } => self.print_binary(left, "//", right),
+            RecExprData::Power { left, right } => self.print_binary(left, "^", right),
+            RecExprData::Minus { right } => {
+                self.write("(- ");
+                self.visit_rec_expr(right);
+                self.write(")");
+            }
+            RecExprData::Or { left, right } => self.print_binary(left, "or", right),
+            RecExprData::And { left, right } => self.print_binary(left, "and", right),
+            RecExprData::Not { right } => {
+                self.write("(not ");
+                self.visit_rec_expr(right);
+                self.write(")");
+            }
+            RecExprData::Equals { left, right } => self.print_binary(left, "==", right),
+            RecExprData::NotEquals { left, right } => self.print_binary(left, "!=", right),
+            RecExprData::LessThan { left, right } => self.print_binary(left, "<", right),
+            RecExprData::LessThanOrEqual { left, right } => self.print_binary(left, "<=", right),
+            RecExprData::GreaterThan { left, right } => self.print_binary(left, ">", right),
+            RecExprData::GreaterThanOrEqual { left, right } => {
+                self.print_binary(left, ">=", right)
+            }
+            RecExprData::Access { object, variable } => {
+                self.write(&format!("{object:?}.{variable:?}"));
+            }
+            RecExprData::FunctionCall {
+                function_name,
+                args,
+            } => {
+                self.write(&format!("Call({function_name:?} with ("));
+                for arg in args {
+                    self.visit_rec_expr(arg);
+                    self.write(", ");
+                }
+                self.write("))");
             }
-            print!("))");
-        }
-        RecExprData::List { elements } => {
-            print!("[");
-            for element in elements {
-                print_recursive_expression(element);
-                print!(", ");
+            RecExprData::List { elements } => {
+                self.write("[");
+                for element in elements {
+                    self.visit_rec_expr(element);
+                    self.write(", ");
+                }
+                self.write("]");
+            }
+            RecExprData::ListAccess { list, index } => {
+                self.visit_rec_expr(list);
+                self.write("[");
+                self.visit_rec_expr(index);
+                self.write("]");
+            }
+            RecExprData::Slice { list, start, end } => {
+                self.visit_rec_expr(list);
+                self.write("[");
+                if let Some(start) = start {
+                    self.visit_rec_expr(start);
+                }
+                self.write(":");
+                if let Some(end) = end {
+                    self.visit_rec_expr(end);
+                }
+                self.write("]");
+            }
+            RecExprData::Dict { entries } => {
+                self.write("{");
+                for (key, value) in entries {
+                    self.visit_rec_expr(key);
+                    self.write(": ");
+                    self.visit_rec_expr(value);
+                    self.write(", ");
+                }
+                self.write("}");
+            }
+            RecExprData::Tuple { elements } => {
+                self.write("Tuple(");
+                for element in elements {
+                    self.visit_rec_expr(element);
+                    self.write(", ");
+                }
+                self.write(")");
+            }
+            RecExprData::KeywordArgument { name, value } => {
+                self.write(&format!("Keyword({}) = ", name));
+                self.visit_rec_expr(value);
+            }
+            RecExprData::Conditional {
+                condition,
+                then_value,
+                else_value,
+            } => {
+                self.write("(");
+                self.visit_rec_expr(then_value);
+                self.write(" if ");
+                self.visit_rec_expr(condition);
+                self.write(" else ");
+                self.visit_rec_expr(else_value);
+                self.write(")");
             }
-            print!("]");
-        }
-        RecExprData::ListAccess { variable, index } => {
-            print!("{variable:?}[");
-            print_recursive_expression(index);
-            print!("]");
         }
     }
 }
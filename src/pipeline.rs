@@ -1,20 +1,56 @@
 use std::path;
 use std::path::PathBuf;
 
+use rayon::prelude::*;
+
+use crate::annotator;
+use crate::benchmark;
+use crate::decompiler;
 use crate::desugarer;
+use crate::difftest;
+use crate::difftest::Backend;
+use crate::difftest::Comparison;
+use crate::difftest::DiffOptions;
+use crate::difftest::Outcome;
+use crate::difftest::RunOutput;
+use crate::difftest::Verdict;
+use crate::engine::Engine;
+use crate::fixer;
 use crate::interpreter;
+use crate::jstranspiler;
+use crate::language::LanguageOptions;
+use crate::linter;
+use crate::lookup;
 use crate::parser;
+use crate::pytranspiler;
 use crate::tokenizer;
 use crate::tokenizer::Error;
+use crate::tokenizer::Span;
+use crate::testrunner;
 use crate::typechecker;
+use crate::typechecker::FunctionType;
+use crate::typechecker::Type;
 use crate::uniquify;
+use crate::verifier;
 use crate::livenessanalysis;
 use crate::compiler;
+use crate::compiler::Target;
 use crate::assembler;
 use crate::exewriter;
+use crate::elfwriter;
 use crate::optimiser;
+use crate::optimiser::OptLevel;
+use crate::optimiser::OptimisationOptions;
+use crate::optimiser::OptimisationStats;
+use crate::optimiser::Pass;
+use crate::tac;
+use crate::tacformat;
+use crate::instructionsimplifier::AssemblyInstruction;
 
-pub fn run_typecheck_pipeline_from_path(path: &std::path::PathBuf) -> Result<String, String> {
+pub fn run_typecheck_pipeline_from_path(
+    path: &std::path::PathBuf,
+    language: &LanguageOptions,
+) -> Result<String, String> {
     // Read the file into a big string
     let content = std::fs::read_to_string(path).expect("could not read file");
 
@@ -22,30 +58,438 @@ pub fn run_typecheck_pipeline_from_path(path: &std::path::PathBuf) -> Result<Str
     let lines_iterator = content.split("\n");
     let lines: Vec<&str> = lines_iterator.collect();
 
-    return run_typecheck_pipeline(lines);
+    return run_typecheck_pipeline_with_options(lines, language);
 }
 
 pub fn run_typecheck_pipeline(lines: Vec<&str>) -> Result<String, String> {
+    run_typecheck_pipeline_with_options(lines, &LanguageOptions::default())
+}
+
+pub fn run_typecheck_pipeline_with_options(
+    lines: Vec<&str>,
+    language: &LanguageOptions,
+) -> Result<String, String> {
     let lines_copy = lines.clone();
-    let base_expressions: Vec<parser::BaseExpr<()>> = match parser::parse_strings(lines) {
-        Ok(base_expressions) => base_expressions,
+    let base_expressions: Vec<parser::BaseExpr<()>> =
+        match parser::parse_strings_with_options(lines, language) {
+            Ok(base_expressions) => base_expressions,
+            Err(error) => {
+                print_error(&error, &lines_copy);
+                return Err(String::new());
+            }
+        };
+
+    let desugared_base_expressions = desugarer::desugar(base_expressions);
+
+    match typechecker::type_check_program_with_options(desugared_base_expressions, true, language) {
+        Ok(_) => {}
+        Err(error) => {
+            print_error(&error, &lines_copy);
+            return Err(String::new());
+        }
+    }
+
+    return Ok("Typecheck passed".to_string());
+}
+
+// The outcome of typechecking one file of a directory
+pub struct FileTypecheck {
+    pub path: PathBuf,
+    // The source of the file, to show where an error is
+    pub source: String,
+    pub error: Option<Error>,
+}
+
+// Finds the .rosy files in the directory and all of its subdirectories, sorted by path
+pub fn find_source_files(directory: &path::Path) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut directories = Vec::from([directory.to_path_buf()]);
+
+    while let Some(current) = directories.pop() {
+        let entries = std::fs::read_dir(&current).map_err(|error| Error::SimpleError {
+            message: format!("Could not read {}: {}", current.display(), error),
+        })?;
+
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            if entry_path.is_dir() {
+                directories.push(entry_path);
+            } else if entry_path.extension().is_some_and(|extension| extension == "rosy") {
+                files.push(entry_path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+// Typechecks the files in parallel, each with its own language options.
+// Nothing is printed, so the results can be reported in a fixed order with print_typecheck_report.
+pub fn typecheck_files(files: Vec<(PathBuf, LanguageOptions)>) -> Vec<FileTypecheck> {
+    files
+        .into_par_iter()
+        .map(|(path, language)| {
+            let _span = tracing::debug_span!("typecheck_file", path = %path.display()).entered();
+
+            match std::fs::read_to_string(&path) {
+                Ok(source) => {
+                    let error = typecheck_source(&source, &language).err();
+                    FileTypecheck { path, source, error }
+                }
+                Err(error) => FileTypecheck {
+                    error: Some(Error::SimpleError {
+                        message: format!("Could not read {}: {}", path.display(), error),
+                    }),
+                    path,
+                    source: String::new(),
+                },
+            }
+        })
+        .collect()
+}
+
+fn typecheck_source(source: &str, language: &LanguageOptions) -> Result<(), Error> {
+    let lines: Vec<&str> = source.split("\n").collect();
+    let base_expressions = parser::parse_strings_with_options(lines, language)?;
+    typechecker::type_check_program_with_options(desugarer::desugar(base_expressions), false, language)?;
+    Ok(())
+}
+
+// Prints every file which failed with its error, followed by a summary.
+// Returns whether all files passed.
+pub fn print_typecheck_report(results: &[FileTypecheck]) -> bool {
+    let mut failed = 0;
+    for result in results {
+        if let Some(error) = &result.error {
+            failed += 1;
+            println!("{}:", result.path.display());
+            print_error(error, &result.source.split("\n").collect());
+            println!();
+        }
+    }
+
+    println!(
+        "Typechecked {} files: {} passed, {} failed",
+        results.len(),
+        results.len() - failed,
+        failed
+    );
+    failed == 0
+}
+
+// The outcome of running one file on every execution engine
+pub struct FileDifftest {
+    pub path: PathBuf,
+    pub source: String,
+    pub result: Result<(RunOutput, Vec<Comparison>), Error>,
+}
+
+// Runs the files one after another on the engines, each with its own language options.
+// The files are not run in parallel, so a compiled program is not stopped by the timeout while it waits on the others.
+pub fn difftest_files(files: Vec<(PathBuf, LanguageOptions)>, backends: &[Backend], optimisation: &OptimisationOptions) -> Vec<FileDifftest> {
+    files
+        .into_iter()
+        .map(|(path, language)| {
+            let _span = tracing::debug_span!("difftest_file", path = %path.display()).entered();
+
+            match std::fs::read_to_string(&path) {
+                Ok(source) => {
+                    let options = DiffOptions {
+                        backends: backends.to_vec(),
+                        language,
+                        optimisation: optimisation.clone(),
+                    };
+                    let result = difftest::compare_backends(source.split("\n").collect(), &options);
+                    FileDifftest { path, source, result }
+                }
+                Err(error) => FileDifftest {
+                    result: Err(Error::SimpleError {
+                        message: format!("Could not read {}: {}", path.display(), error),
+                    }),
+                    path,
+                    source: String::new(),
+                },
+            }
+        })
+        .collect()
+}
+
+// Prints for every file whether the engines agreed, with what each differing engine printed, followed by a summary.
+// A file the interpreter cannot read counts as differing, a file no other engine could run is not compared.
+// Returns whether no engine differed on any file.
+pub fn print_difftest_report(results: &[FileDifftest]) -> bool {
+    let mut differed = 0;
+    let mut not_compared = 0;
+    for file in results {
+        match &file.result {
+            Ok((reference, comparisons)) => {
+                let verdict = difftest::verdict(comparisons);
+                let status = match verdict {
+                    Verdict::Agreed => "ok",
+                    Verdict::Differed => "FAILED",
+                    Verdict::NotCompared => "not compared",
+                };
+                println!("{} ... {}", file.path.display(), status);
+                match verdict {
+                    Verdict::Agreed => {}
+                    Verdict::Differed => {
+                        differed += 1;
+                        print_run_output(Backend::Interpreter.name(), reference);
+                    }
+                    Verdict::NotCompared => not_compared += 1,
+                }
+                for comparison in comparisons {
+                    match &comparison.outcome {
+                        Outcome::Same => {}
+                        Outcome::Different(output) => print_run_output(comparison.backend.name(), output),
+                        Outcome::Skipped(reason) => println!("    {} skipped: {}", comparison.backend.name(), reason),
+                    }
+                }
+            }
+            Err(error) => {
+                differed += 1;
+                println!("{} ... FAILED", file.path.display());
+                print_error(error, &file.source.split("\n").collect());
+            }
+        }
+    }
+
+    println!(
+        "Compared {} programs: {} agreed, {} differed, {} not compared",
+        results.len(),
+        results.len() - differed - not_compared,
+        differed,
+        not_compared
+    );
+    differed == 0
+}
+
+fn print_run_output(backend: &str, run: &RunOutput) {
+    println!("    {} exited with code {} and printed:", backend, run.exit_code);
+    for line in run.output.split("\n") {
+        println!("        {}", line);
+    }
+}
+
+pub fn run_annotate_pipeline_from_path(
+    path: &std::path::PathBuf,
+    language: &LanguageOptions,
+) -> Result<String, String> {
+    // Read the file into a big string
+    let content = std::fs::read_to_string(path).expect("could not read file");
+
+    // Split the string into lines and make an iterator over them
+    let lines_iterator = content.split("\n");
+    let lines: Vec<&str> = lines_iterator.collect();
+
+    return run_annotate_pipeline_with_options(lines, language);
+}
+
+// Typechecks the program and returns the source with the inferred types as a sidebar of comments
+pub fn run_annotate_pipeline(lines: Vec<&str>) -> Result<String, String> {
+    run_annotate_pipeline_with_options(lines, &LanguageOptions::default())
+}
+
+pub fn run_annotate_pipeline_with_options(
+    lines: Vec<&str>,
+    language: &LanguageOptions,
+) -> Result<String, String> {
+    let lines_copy = lines.clone();
+    let base_expressions: Vec<parser::BaseExpr<()>> =
+        match parser::parse_strings_with_options(lines, language) {
+            Ok(base_expressions) => base_expressions,
+            Err(error) => {
+                print_error(&error, &lines_copy);
+                return Err(String::new());
+            }
+        };
+
+    let desugared_base_expressions = desugarer::desugar(base_expressions);
+
+    let typed_program = match typechecker::type_check_program_with_options(
+        desugared_base_expressions.clone(),
+        false,
+        language,
+    ) {
+        Ok(typed_program) => typed_program,
         Err(error) => {
             print_error(&error, &lines_copy);
             return Err(String::new());
         }
     };
 
+    return Ok(annotator::annotate_source(
+        &lines_copy,
+        &desugared_base_expressions,
+        &typed_program,
+    ));
+}
+
+pub fn run_decompile_pipeline_from_path(
+    path: &std::path::PathBuf,
+    language: &LanguageOptions,
+    annotate_types: bool,
+) -> Result<String, String> {
+    // Read the file into a big string
+    let content = std::fs::read_to_string(path).expect("could not read file");
+
+    // Split the string into lines and make an iterator over them
+    let lines_iterator = content.split("\n");
+    let lines: Vec<&str> = lines_iterator.collect();
+
+    return run_decompile_pipeline_with_options(lines, language, annotate_types);
+}
+
+// Typechecks the program and writes the typed program back as source,
+// showing the program as it is after desugaring and typechecking
+pub fn run_decompile_pipeline(lines: Vec<&str>, annotate_types: bool) -> Result<String, String> {
+    run_decompile_pipeline_with_options(lines, &LanguageOptions::default(), annotate_types)
+}
+
+pub fn run_decompile_pipeline_with_options(
+    lines: Vec<&str>,
+    language: &LanguageOptions,
+    annotate_types: bool,
+) -> Result<String, String> {
+    let lines_copy = lines.clone();
+    let base_expressions: Vec<parser::BaseExpr<()>> =
+        match parser::parse_strings_with_options(lines, language) {
+            Ok(base_expressions) => base_expressions,
+            Err(error) => {
+                print_error(&error, &lines_copy);
+                return Err(String::new());
+            }
+        };
+
     let desugared_base_expressions = desugarer::desugar(base_expressions);
 
-    match typechecker::type_check_program(desugared_base_expressions, true) {
+    let typed_program = match typechecker::type_check_program_with_options(
+        desugared_base_expressions,
+        false,
+        language,
+    ) {
+        Ok(typed_program) => typed_program,
+        Err(error) => {
+            print_error(&error, &lines_copy);
+            return Err(String::new());
+        }
+    };
+
+    return Ok(decompiler::typed_program_to_source(
+        &typed_program,
+        annotate_types,
+    ));
+}
+
+// Renames the symbol at the given position and all of its references, and writes the result back to the file
+pub fn run_rename_pipeline_from_path(
+    path: &std::path::PathBuf,
+    row: usize,
+    col: usize,
+    new_name: &str,
+) -> Result<usize, String> {
+    // Read the file into a big string
+    let content = std::fs::read_to_string(path).expect("could not read file");
+
+    // Split the string into lines and make an iterator over them
+    let lines_iterator = content.split("\n");
+    let lines: Vec<&str> = lines_iterator.collect();
+
+    let lines_copy = lines.clone();
+    let reference_count = match lookup::find_references(lines.clone(), row, col) {
+        Ok(references) => references.len(),
+        Err(error) => {
+            print_error(&error, &lines_copy);
+            return Err(String::new());
+        }
+    };
+
+    let renamed_lines = match lookup::rename_symbol(lines, row, col, new_name) {
+        Ok(renamed_lines) => renamed_lines,
+        Err(error) => {
+            print_error(&error, &lines_copy);
+            return Err(String::new());
+        }
+    };
+
+    match std::fs::write(path, renamed_lines.join("\n")) {
         Ok(_) => {}
+        Err(err) => return Err(format!("Error writing file: {}", err)),
+    }
+
+    return Ok(reference_count);
+}
+
+// Applies the fixes for common mistakes to the file, returning the applied fixes.
+// With dry_run the fixes are only found, and the file is left as it is.
+pub fn run_fix_pipeline_from_path(
+    path: &std::path::PathBuf,
+    dry_run: bool,
+) -> Result<Vec<fixer::Fix>, String> {
+    // Read the file into a big string
+    let content = std::fs::read_to_string(path).expect("could not read file");
+
+    // Split the string into lines and make an iterator over them
+    let lines_iterator = content.split("\n");
+    let lines: Vec<&str> = lines_iterator.collect();
+
+    let lines_copy = lines.clone();
+    let (fixed_lines, fixes) = match fixer::fix_program(lines) {
+        Ok(fixed) => fixed,
         Err(error) => {
             print_error(&error, &lines_copy);
             return Err(String::new());
         }
+    };
+
+    if !dry_run && !fixes.is_empty() {
+        match std::fs::write(path, fixed_lines.join("\n")) {
+            Ok(_) => {}
+            Err(err) => return Err(format!("Error writing file: {}", err)),
+        }
     }
 
-    return Ok("Typecheck passed".to_string());
+    Ok(fixes)
+}
+
+// Prints the warnings for likely mistakes in the file, and with style options for its layout,
+// returning the number of warnings
+pub fn run_lint_pipeline_from_path(
+    path: &std::path::PathBuf,
+    language: &LanguageOptions,
+    style: Option<&linter::StyleOptions>,
+) -> Result<usize, String> {
+    // Read the file into a big string
+    let content = std::fs::read_to_string(path).expect("could not read file");
+
+    // Split the string into lines and make an iterator over them
+    let lines_iterator = content.split("\n");
+    let lines: Vec<&str> = lines_iterator.collect();
+
+    let lines_copy = lines.clone();
+    let mut warnings = match linter::lint_program(lines.clone(), language) {
+        Ok(warnings) => warnings,
+        Err(error) => {
+            print_error(&error, &lines_copy);
+            return Err(String::new());
+        }
+    };
+    if let Some(style) = style {
+        match linter::find_style_warnings(lines, language, style) {
+            Ok(style_warnings) => warnings.extend(style_warnings),
+            Err(error) => {
+                print_error(&error, &lines_copy);
+                return Err(String::new());
+            }
+        }
+        warnings.sort_by_key(|warning| (warning.span.row, warning.span.col_start));
+    }
+
+    for warning in warnings.iter() {
+        print_warning(warning, &lines_copy);
+    }
+    Ok(warnings.len())
 }
 
 pub fn run_pipeline_from_path(path: &std::path::PathBuf) -> Result<interpreter::Terminal, String> {
@@ -68,6 +512,10 @@ pub fn run_pipeline(lines: Vec<&str>) -> Result<interpreter::Terminal, String> {
             return Err(String::new());
         }
     };
+    if let Err(error) = verifier::verify("parsing", &base_expressions[..], verifier::check_program) {
+        print_error(&error, &lines_copy);
+        return Err(String::new());
+    }
 
     let output_terminal = match interpreter::interpret(base_expressions) {
         Ok(output_terminal) => output_terminal,
@@ -80,7 +528,11 @@ pub fn run_pipeline(lines: Vec<&str>) -> Result<interpreter::Terminal, String> {
     return Ok(output_terminal);
 }
 
-pub fn run_compilation_pipeline_from_path(path: &std::path::PathBuf, output_path: &std::path::PathBuf) -> Result<(), String> {
+// Runs the file with the builtins registered in the given engine, such as those loaded from plugins
+pub fn run_engine_pipeline_from_path(
+    path: &std::path::PathBuf,
+    engine: &Engine,
+) -> Result<interpreter::Context, String> {
     // Read the file into a big string
     let content = std::fs::read_to_string(path).expect("could not read file");
 
@@ -88,37 +540,116 @@ pub fn run_compilation_pipeline_from_path(path: &std::path::PathBuf, output_path
     let lines_iterator = content.split("\n");
     let lines: Vec<&str> = lines_iterator.collect();
 
-    return run_compilation_pipeline(lines, output_path);
+    let lines_copy = lines.clone();
+    match engine.execute(lines) {
+        Ok(context) => return Ok(context),
+        Err(error) => {
+            print_error(&error, &lines_copy);
+            return Err(String::new());
+        }
+    }
 }
 
-pub fn run_compilation_pipeline(lines: Vec<&str>, output_path: &std::path::PathBuf) -> Result<(), String> {
+// Runs the test functions of the file and prints their results, returns whether all of them passed
+pub fn run_test_pipeline_from_path(
+    path: &std::path::PathBuf,
+    engine: &Engine,
+    options: &testrunner::TestOptions,
+) -> Result<bool, String> {
+    // Read the file into a big string
+    let content = std::fs::read_to_string(path).expect("could not read file");
+
+    // Split the string into lines and make an iterator over them
+    let lines_iterator = content.split("\n");
+    let lines: Vec<&str> = lines_iterator.collect();
+
     let lines_copy = lines.clone();
-    let base_expressions: Vec<parser::BaseExpr<()>> = match parser::parse_strings(lines) {
-        Ok(base_expressions) => base_expressions,
+    let outcomes = match testrunner::run_tests(engine, lines, options) {
+        Ok(outcomes) => outcomes,
         Err(error) => {
             print_error(&error, &lines_copy);
             return Err(String::new());
         }
     };
 
-    let desugared_base_expressions = desugarer::desugar(base_expressions);
+    for outcome in outcomes.iter() {
+        match &outcome.error {
+            None => println!("test {} ... ok", outcome.name),
+            Some(error) => {
+                println!("test {} ... FAILED", outcome.name);
+                print_error(error, &lines_copy);
+            }
+        }
+    }
 
+    let passed = outcomes.iter().filter(|outcome| outcome.passed()).count();
+    println!(
+        "\ntest result: {} passed; {} failed",
+        passed,
+        outcomes.len() - passed
+    );
 
-    let mut typed_program =
-        match typechecker::type_check_program(desugared_base_expressions.clone(), false) {
-            Ok(typed_program) => typed_program,
-            Err(error) => {
-                print_error(&error, &lines_copy);
-                return Err(String::new());
-            }
-        };
+    return Ok(passed == outcomes.len());
+}
 
-    //print!("Typed program:\n{:#?}\n", typed_program);
+// Runs the benchmark functions of the file and prints their timings
+pub fn run_bench_pipeline_from_path(
+    path: &std::path::PathBuf,
+    engine: &Engine,
+    options: &benchmark::BenchOptions,
+) -> Result<(), String> {
+    // Read the file into a big string
+    let content = std::fs::read_to_string(path).expect("could not read file");
 
-    // Perform uniquification
-    uniquify::uniquify(&mut typed_program);
+    // Split the string into lines and make an iterator over them
+    let lines_iterator = content.split("\n");
+    let lines: Vec<&str> = lines_iterator.collect();
+
+    let lines_copy = lines.clone();
+    let results = match benchmark::run_benchmarks(engine, lines, options) {
+        Ok(results) => results,
+        Err(error) => {
+            print_error(&error, &lines_copy);
+            return Err(String::new());
+        }
+    };
+
+    for result in results.iter() {
+        println!(
+            "bench {} ... mean {:?}, median {:?}, stddev {:?} ({} runs, {} statements per run)",
+            result.name,
+            result.mean(),
+            result.median(),
+            result.std_dev(),
+            result.durations.len(),
+            result.statements_per_run
+        );
+    }
 
-    //print!("Uniquified program:\n{:#?}\n", typed_program);
+    return Ok(());
+}
+
+pub fn run_compilation_pipeline_from_path(path: &std::path::PathBuf, output_path: &std::path::PathBuf, language: &LanguageOptions, target: Target, optimisation: &OptimisationOptions) -> Result<(), String> {
+    // Read the file into a big string
+    let content = std::fs::read_to_string(path).expect("could not read file");
+
+    // Split the string into lines and make an iterator over them
+    let lines_iterator = content.split("\n");
+    let lines: Vec<&str> = lines_iterator.collect();
+
+    return run_compilation_pipeline_with_options(lines, output_path, language, target, optimisation);
+}
+
+// Compiles the program to an executable for the platform the compiler runs on, at the default level of compile
+pub fn run_compilation_pipeline(lines: Vec<&str>, output_path: &std::path::PathBuf) -> Result<(), String> {
+    let optimisation = OptimisationOptions::with_level(OptLevel::O1);
+    run_compilation_pipeline_with_options(lines, output_path, &LanguageOptions::default(), Target::host(), &optimisation)
+}
+
+pub fn run_compilation_pipeline_with_options(lines: Vec<&str>, output_path: &std::path::PathBuf, language: &LanguageOptions, target: Target, optimisation: &OptimisationOptions) -> Result<(), String> {
+    let lines_copy = lines.clone();
+    let mut stats = OptimisationStats::default();
+    let typed_program = typed_program_for_compilation(lines, language, optimisation, &mut stats)?;
 
     let assembly = match compiler::compile(typed_program) {
         Ok(assembly) => assembly,
@@ -128,69 +659,307 @@ pub fn run_compilation_pipeline(lines: Vec<&str>, output_path: &std::path::PathB
         }
     };
 
-    let optimised_assembly = optimiser::optimise_assembly(&assembly);
+    write_executable(assembly, output_path, target, optimisation, stats);
 
-    let (mut machine_code, syscalls_to_resolve, starting_point) = assembler::assemble_program(optimised_assembly);
+    return Ok(());
+}
+
+pub fn run_emit_ir_pipeline_from_path(path: &std::path::PathBuf, language: &LanguageOptions, optimisation: &OptimisationOptions) -> Result<String, String> {
+    // Read the file into a big string
+    let content = std::fs::read_to_string(path).expect("could not read file");
+
+    // Split the string into lines and make an iterator over them
+    let lines_iterator = content.split("\n");
+    let lines: Vec<&str> = lines_iterator.collect();
+
+    return run_emit_ir_pipeline_with_options(lines, language, optimisation);
+}
+
+// Compiles the program as far as the three address code and gives it in the .rir text format
+pub fn run_emit_ir_pipeline(lines: Vec<&str>) -> Result<String, String> {
+    run_emit_ir_pipeline_with_options(lines, &LanguageOptions::default(), &OptimisationOptions::with_level(OptLevel::O1))
+}
+
+pub fn run_emit_ir_pipeline_with_options(lines: Vec<&str>, language: &LanguageOptions, optimisation: &OptimisationOptions) -> Result<String, String> {
+    let lines_copy = lines.clone();
+    let mut stats = OptimisationStats::default();
+    let typed_program = typed_program_for_compilation(lines, language, optimisation, &mut stats)?;
+    if optimisation.print_stats {
+        print!("{}", stats);
+    }
+
+    match tac::generate_tac(typed_program.0, typed_program.1) {
+        Ok(instructions) => Ok(tacformat::instructions_to_text(&instructions)),
+        Err(error) => {
+            print_error(&error, &lines_copy);
+            Err(String::new())
+        }
+    }
+}
+
+pub fn run_emit_js_pipeline_from_path(path: &std::path::PathBuf, language: &LanguageOptions, optimisation: &OptimisationOptions) -> Result<String, String> {
+    // Read the file into a big string
+    let content = std::fs::read_to_string(path).expect("could not read file");
+
+    // Split the string into lines and make an iterator over them
+    let lines_iterator = content.split("\n");
+    let lines: Vec<&str> = lines_iterator.collect();
+
+    run_emit_js_pipeline_with_options(lines, language, optimisation)
+}
+
+// Typechecks the program and writes it as JavaScript
+pub fn run_emit_js_pipeline(lines: Vec<&str>) -> Result<String, String> {
+    run_emit_js_pipeline_with_options(lines, &LanguageOptions::default(), &OptimisationOptions::with_level(OptLevel::O1))
+}
+
+pub fn run_emit_js_pipeline_with_options(lines: Vec<&str>, language: &LanguageOptions, optimisation: &OptimisationOptions) -> Result<String, String> {
+    let lines_copy = lines.clone();
+    let mut stats = OptimisationStats::default();
+    // The variables keep their names, as the JavaScript is meant to be read
+    let javascript = run_passes_before_uniquification(lines, language, optimisation, &mut stats)
+        .and_then(|typed_program| jstranspiler::typed_program_to_javascript(&typed_program));
+    if optimisation.print_stats {
+        print!("{}", stats);
+    }
+
+    match javascript {
+        Ok(javascript) => Ok(javascript),
+        Err(error) => {
+            print_error(&error, &lines_copy);
+            Err(String::new())
+        }
+    }
+}
+
+pub fn run_emit_python_pipeline_from_path(path: &std::path::PathBuf, language: &LanguageOptions, optimisation: &OptimisationOptions) -> Result<String, String> {
+    // Read the file into a big string
+    let content = std::fs::read_to_string(path).expect("could not read file");
+
+    // Split the string into lines and make an iterator over them
+    let lines_iterator = content.split("\n");
+    let lines: Vec<&str> = lines_iterator.collect();
+
+    run_emit_python_pipeline_with_options(lines, language, optimisation)
+}
+
+// Typechecks the program and writes it as Python
+pub fn run_emit_python_pipeline(lines: Vec<&str>) -> Result<String, String> {
+    run_emit_python_pipeline_with_options(lines, &LanguageOptions::default(), &OptimisationOptions::with_level(OptLevel::O1))
+}
+
+pub fn run_emit_python_pipeline_with_options(lines: Vec<&str>, language: &LanguageOptions, optimisation: &OptimisationOptions) -> Result<String, String> {
+    let lines_copy = lines.clone();
+    let mut stats = OptimisationStats::default();
+    // The variables keep their names, as the Python is meant to be read
+    let python = run_passes_before_uniquification(lines, language, optimisation, &mut stats)
+        .and_then(|typed_program| pytranspiler::typed_program_to_python(&typed_program));
+    if optimisation.print_stats {
+        print!("{}", stats);
+    }
+
+    match python {
+        Ok(python) => Ok(python),
+        Err(error) => {
+            print_error(&error, &lines_copy);
+            Err(String::new())
+        }
+    }
+}
+
+pub fn run_ir_compilation_pipeline_from_path(path: &std::path::PathBuf, output_path: &std::path::PathBuf, target: Target) -> Result<(), String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) => return Err(format!("Error: could not read {}: {}", path.display(), err)),
+    };
+
+    return run_ir_compilation_pipeline(&content, output_path, target);
+}
+
+// Compiles three address code in the .rir text format to an executable, skipping everything before the backend
+pub fn run_ir_compilation_pipeline(text: &str, output_path: &std::path::PathBuf, target: Target) -> Result<(), String> {
+    let lines: Vec<&str> = text.split("\n").collect();
+
+    let instructions = match tacformat::parse_instructions(text) {
+        Ok(instructions) => instructions,
+        Err(error) => {
+            print_error(&error, &lines);
+            return Err(String::new());
+        }
+    };
+
+    let assembly = match compiler::compile_tac(&instructions) {
+        Ok(assembly) => assembly,
+        Err(error) => {
+            print_error(&error, &lines);
+            return Err(String::new());
+        }
+    };
+
+    // The three address code was written by rosy, so it is only cleaned up as at the default level of compile
+    let optimisation = OptimisationOptions::with_level(OptLevel::O1);
+    write_executable(assembly, output_path, target, &optimisation, OptimisationStats::default());
+
+    return Ok(());
+}
+
+// Runs the stages before the backend: parsing, desugaring, the optimisation passes, typechecking and uniquification
+fn typed_program_for_compilation(lines: Vec<&str>, language: &LanguageOptions, optimisation: &OptimisationOptions, stats: &mut OptimisationStats) -> Result<(Vec<parser::BaseExpr<Type>>, Vec<FunctionType>), String> {
+    let lines_copy = lines.clone();
+    match run_passes_before_compilation(lines, language, optimisation, stats) {
+        Ok(typed_program) => Ok(typed_program),
+        Err(error) => {
+            print_error(&error, &lines_copy);
+            Err(String::new())
+        }
+    }
+}
+
+// Each pass is followed by the verifier when it is enabled, so a pass that breaks the tree is found right away
+fn run_passes_before_compilation(lines: Vec<&str>, language: &LanguageOptions, optimisation: &OptimisationOptions, stats: &mut OptimisationStats) -> Result<(Vec<parser::BaseExpr<Type>>, Vec<FunctionType>), Error> {
+    let mut typed_program = run_passes_before_uniquification(lines, language, optimisation, stats)?;
+
+    tac::check_supported_before_uniquify(&typed_program)?;
+    uniquify::uniquify(&mut typed_program);
+    verifier::verify("uniquification", &typed_program, verifier::check_typed_program)?;
+
+    Ok(typed_program)
+}
+
+fn run_passes_before_uniquification(lines: Vec<&str>, language: &LanguageOptions, optimisation: &OptimisationOptions, stats: &mut OptimisationStats) -> Result<(Vec<parser::BaseExpr<Type>>, Vec<FunctionType>), Error> {
+    let base_expressions = parser::parse_strings_with_options(lines, language)?;
+    verifier::verify("parsing", &base_expressions[..], verifier::check_program)?;
+
+    let desugared_base_expressions = desugarer::desugar(base_expressions);
+    verifier::verify("desugaring", &desugared_base_expressions[..], verifier::check_program)?;
+
+    // The passes across function calls only change what the typechecker sees, so it checks their result
+    let optimised_base_expressions = optimiser::optimise_calls(desugared_base_expressions, optimisation, stats)?;
+
+    let mut typed_program = typechecker::type_check_program_with_options(optimised_base_expressions, false, language)?;
+    verifier::verify("typechecking", &typed_program, verifier::check_typed_program)?;
+
+    // Expressions on literals are computed now, so the compiled program does not have to
+    optimiser::optimise_typed_program(&mut typed_program, language, optimisation, stats)?;
+
+    return Ok(typed_program);
+}
+
+fn write_executable(assembly: Vec<AssemblyInstruction>, output_path: &std::path::PathBuf, target: Target, optimisation: &OptimisationOptions, mut stats: OptimisationStats) {
+    let (mut machine_code, syscalls_to_resolve, starting_point) = link_program(assembly, optimisation, &mut stats);
+    if optimisation.print_stats {
+        print!("{}", stats);
+    }
 
     println!("Machine code ({} bytes):", machine_code.len());
     for byte in &machine_code {
         print!("{:02X} ", byte);
     }
 
-    match exewriter::write_exe_file(&output_path, &mut machine_code, &syscalls_to_resolve, starting_point) {
+    match write_machine_code(output_path, target, &mut machine_code, syscalls_to_resolve, starting_point) {
         Ok(_) => println!("\nCompiled to {}", output_path.display()),
-        Err(err) => println!("Error writing exe file: {}", err),
+        Err(err) => println!("Error writing executable file: {}", err),
     }
+}
 
-    return Ok(());
+// Runs the peephole optimiser on the assembly when it is enabled, assembles it and adds the routines of the
+// runtime library which the program calls. Gives the machine code, the calls to resolve and where it starts.
+fn link_program(assembly: Vec<AssemblyInstruction>, optimisation: &OptimisationOptions, stats: &mut OptimisationStats) -> (Vec<u8>, Vec<(String, usize)>, usize) {
+    let optimised_assembly = match optimisation.runs(Pass::Peephole) {
+        true => {
+            let optimised_assembly = optimiser::optimise_assembly(&assembly);
+            stats.record(Pass::Peephole, assembly.len() - optimised_assembly.len());
+            optimised_assembly
+        }
+        false => assembly,
+    };
+
+    let (mut machine_code, mut syscalls_to_resolve, starting_point) = assembler::assemble_program(optimised_assembly);
+    rosy_rt::native::link(&mut machine_code, &mut syscalls_to_resolve);
+    (machine_code, syscalls_to_resolve, starting_point)
+}
+
+fn write_machine_code(output_path: &std::path::PathBuf, target: Target, machine_code: &mut Vec<u8>, syscalls_to_resolve: Vec<(String, usize)>, starting_point: usize) -> std::io::Result<()> {
+    match target {
+        Target::Windows => exewriter::write_exe_file(output_path, machine_code, &syscalls_to_resolve, starting_point),
+        Target::Linux => elfwriter::write_elf_file(output_path, machine_code, &syscalls_to_resolve, starting_point),
+    }
+}
+
+// Compiles the program to an executable, returning an error instead of printing it. The listings of the compiler
+// stages are still printed. Used by difftest, which compares what the compiled program prints with the interpreter.
+pub fn build_executable(lines: Vec<&str>, output_path: &std::path::PathBuf, language: &LanguageOptions, target: Target, optimisation: &OptimisationOptions) -> Result<(), Error> {
+    let mut stats = OptimisationStats::default();
+    let typed_program = run_passes_before_compilation(lines, language, optimisation, &mut stats)?;
+    let assembly = compiler::compile(typed_program)?;
+
+    let (mut machine_code, syscalls_to_resolve, starting_point) = link_program(assembly, optimisation, &mut stats);
+    write_machine_code(output_path, target, &mut machine_code, syscalls_to_resolve, starting_point).map_err(|err| Error::SimpleError {
+        message: format!("Error writing executable file: {}", err),
+    })
+}
+
+// Typechecks and optimises the program like the compiler, but keeps the names of the variables.
+// This is the program the transpilers write in another language.
+pub fn typed_program_for_transpiling(lines: Vec<&str>, language: &LanguageOptions, optimisation: &OptimisationOptions) -> Result<(Vec<parser::BaseExpr<Type>>, Vec<FunctionType>), Error> {
+    run_passes_before_uniquification(lines, language, optimisation, &mut OptimisationStats::default())
 }
 
 pub fn print_error(error: &Error, lines: &Vec<&str>) {
+    if let Some(span) = error.span() {
+        print_span(&span, lines);
+    }
+
     match error {
         Error::SimpleError { message } => {
             println!("Error: {}", message);
         }
-        Error::LocationError {
-            message,
-            row,
-            col_start,
-            col_end,
-        } => {
-            println!("{}", lines[*row as usize]);
-            println!(
-                "{}{}",
-                " ".repeat(*col_start as usize),
-                "^".repeat(*col_end as usize - *col_start as usize)
-            );
-            println!(
-                "Error: {} (line {}, col {})",
-                message,
-                row + 1,
-                col_start + 1
-            );
+        Error::LocationError { message, span } => {
+            println!("{}", rosy_rt::error::located_message("Error", message, span.row, span.col_start));
         }
         Error::TypeError {
             message,
             expected,
             found,
-            row,
-            col_start,
-            col_end,
+            span,
         } => {
-            println!("{}", lines[*row as usize]);
-            println!(
-                "{}{}",
-                " ".repeat(*col_start as usize),
-                "^".repeat(*col_end as usize - *col_start as usize)
-            );
+            println!("{}", rosy_rt::error::located_message("Type error", message, span.row, span.col_start));
+            println!("Expected type: {}", expected);
+            println!("Found type: {}", found);
+        }
+        Error::DelimiterError {
+            message,
+            opened,
+            expected,
+        } => {
+            println!("{}", rosy_rt::error::located_message("Error", message, opened.row, opened.col_start));
+            print_span(expected, lines);
             println!(
-                "Type error: {} (line {}, col {})",
-                message,
-                row + 1,
-                col_start + 1
+                "Closing bracket expected here (line {}, col {})",
+                expected.row + 1,
+                expected.col_start + 1
             );
-            println!("Expected type: {:?}", expected);
-            println!("Found type: {:?}", found);
         }
     }
 }
+
+pub fn print_warning(warning: &linter::Warning, lines: &Vec<&str>) {
+    print_span(&warning.span, lines);
+    println!(
+        "{}",
+        rosy_rt::error::located_message("Warning", &warning.message, warning.span.row, warning.span.col_start)
+    );
+}
+
+// Prints the first line of the span with the spanned characters marked below it,
+// a span over several lines is marked up to the end of its first line
+fn print_span(span: &Span, lines: &Vec<&str>) {
+    let line = lines[span.row];
+    let col_end = match span.is_single_row() {
+        true => span.col_end,
+        // Columns count tabs as four characters, as the tokenizer does
+        false => line.replace('\r', "").replace('\t', "    ").chars().count(),
+    };
+    println!("{}", line);
+    println!("{}", rosy_rt::error::span_marker(span.col_start, col_end));
+}
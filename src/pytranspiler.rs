@@ -0,0 +1,719 @@
+use std::collections::HashMap;
+
+use crate::builtins;
+use crate::parser::BaseExpr;
+use crate::parser::BaseExprData;
+use crate::parser::MatchArm;
+use crate::parser::Pattern;
+use crate::parser::RecExpr;
+use crate::parser::RecExprData;
+use crate::stack;
+use crate::tokenizer::Error;
+use crate::tokenizer::Span;
+use crate::transpiler;
+use crate::transpiler::string_literal;
+use crate::transpiler::WrittenFunction;
+use crate::transpiler::NAME_PLACEHOLDER;
+use crate::typechecker;
+use crate::typechecker::FunctionType;
+use crate::typechecker::Type;
+
+// Writes a typechecked program as Python, for moving a program off rosy or for comparing what the interpreter
+// prints with what Python prints for the same program.
+// The types decide how a value is written where Python differs from rosy: dividing two integers rounds towards
+// zero and booleans are printed in lowercase. Lists, dicts and structs are values in rosy, so an assignment to an
+// element or field changes a copy instead of the object other variables may share. Structs become dataclasses
+// and a match becomes the match statement of Python 3.10. Integers have no limit in Python, unlike in rosy.
+// A function which was typechecked for several argument types is written once for every different body,
+// and a nested function is written at the top level with the variables it captures as extra parameters.
+// Where it is defined, those are bound with partial to the values the variables have then.
+// Defer, with and the builtins without a Python counterpart, such as those for files, give an error.
+
+const INDENTATION: &str = "    ";
+
+// Names rosy allows which mean something else in Python, including the builtins and helpers the output uses.
+// Variables and functions with these names get an underscore appended.
+const RESERVED_NAMES: &[&str] = &[
+    "False", "None", "True", "abs", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "dataclass", "def", "del", "elif", "else", "except", "finally", "float", "for", "format_float", "from",
+    "global", "if", "import", "in", "int", "integer_power", "is", "lambda", "max", "min", "nonlocal", "not", "object", "or", "partial", "pass",
+    "print", "raise", "randrange", "range", "replace", "return", "str", "truncated_division", "try", "while",
+    "with", "yield",
+];
+
+type PyFunction = WrittenFunction<Helpers>;
+
+// Writes the output of typechecker::type_check_program as Python: the imports and helpers it needs first,
+// then the functions and the main program
+pub fn typed_program_to_python(typed_program: &(Vec<BaseExpr<Type>>, Vec<FunctionType>)) -> Result<String, Error> {
+    let functions = transpiler::written_functions(&typed_program.1, py_name, function_source)?;
+
+    let mut generator = PyGenerator::new(&functions, 0);
+    generator.write_body(&typed_program.0)?;
+
+    let mut helpers = generator.helpers;
+    for function in &functions {
+        helpers.include(&function.helpers);
+    }
+    let mut output = helpers.prelude();
+    for function in functions.iter().filter(|function| function.is_first_with_body) {
+        output.push_str(&function.source);
+    }
+    output.push_str(&generator.output);
+    Ok(output)
+}
+
+// The source of a single function instance, with a placeholder for its name
+fn function_source(function: &FunctionType, functions: &[PyFunction]) -> Result<(String, Helpers), Error> {
+    let mut generator = PyGenerator::new(functions, 0);
+    let mut parameters = Vec::new();
+    let captured = function.captured.iter().map(|(name, value_type)| (name, value_type));
+    for (name, value_type) in function.param_names.iter().zip(&function.param_types).chain(captured) {
+        generator.assign(name, value_type);
+        parameters.push(py_name(name));
+    }
+
+    // A function with yield in it is a generator in Python as well
+    generator.write_block(&function.content)?;
+    let source = format!("def {NAME_PLACEHOLDER}({}):\n{}\n\n", parameters.join(", "), generator.output);
+    Ok((source, generator.helpers))
+}
+
+// The imports and helper functions written before the program, only those it uses
+#[derive(Default, Clone)]
+struct Helpers {
+    dataclass: bool,
+    format_float: bool,
+    integer_power: bool,
+    partial: bool,
+    randrange: bool,
+    truncated_division: bool,
+}
+
+impl Helpers {
+    fn include(&mut self, other: &Helpers) {
+        self.dataclass |= other.dataclass;
+        self.format_float |= other.format_float;
+        self.integer_power |= other.integer_power;
+        self.partial |= other.partial;
+        self.randrange |= other.randrange;
+        self.truncated_division |= other.truncated_division;
+    }
+
+    fn prelude(&self) -> String {
+        let mut imports = String::new();
+        if self.dataclass {
+            imports.push_str("from dataclasses import dataclass, replace\n");
+        }
+        if self.partial {
+            imports.push_str("from functools import partial\n");
+        }
+        if self.randrange {
+            imports.push_str("from random import randrange\n");
+        }
+
+        let mut prelude = imports;
+        if !prelude.is_empty() {
+            prelude.push_str("\n\n");
+        }
+        if self.truncated_division {
+            prelude.push_str(
+                "# Dividing two integers rounds towards zero in rosy, where // rounds down\n\
+                 def truncated_division(a, b):\n    \
+                     quotient = abs(a) // abs(b)\n    \
+                     return quotient if (a < 0) == (b < 0) else -quotient\n\n\n",
+            );
+        }
+        if self.integer_power {
+            prelude.push_str(
+                "# An integer raised to a negative power would be a float in Python\n\
+                 def integer_power(base, exponent):\n    \
+                     if exponent < 0:\n        \
+                         raise ValueError(\"Cannot raise to a negative power\")\n    \
+                     return base ** exponent\n\n\n",
+            );
+        }
+        if self.format_float {
+            prelude.push_str(
+                "# Floats are written as rosy writes them: 1e20 and 1.5e-5 instead of 1e+20 and 1.5e-05\n\
+                 def format_float(value):\n    \
+                     if value != value:\n        \
+                         return \"NaN\"\n    \
+                     mantissa, e, exponent = repr(value).partition(\"e\")\n    \
+                     return mantissa + e + (str(int(exponent)) if e else \"\")\n\n\n",
+            );
+        }
+        prelude
+    }
+}
+
+struct PyGenerator<'a> {
+    output: String,
+    indentation: usize,
+    functions: &'a [PyFunction],
+    // The last type given to each variable, which decides how an element of it is assigned
+    variables: HashMap<String, Type>,
+    // The variable holding each nested function defined in the body, by its written name, once bound
+    // to the values it captures
+    closures: HashMap<String, String>,
+    helpers: Helpers,
+}
+
+impl<'a> PyGenerator<'a> {
+    fn new(functions: &'a [PyFunction], indentation: usize) -> PyGenerator<'a> {
+        PyGenerator {
+            output: String::new(),
+            indentation,
+            functions,
+            variables: HashMap::new(),
+            closures: HashMap::new(),
+            helpers: Helpers::default(),
+        }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        self.output.push_str(&INDENTATION.repeat(self.indentation));
+        self.output.push_str(line);
+        self.output.push('\n');
+    }
+
+    fn write_body(&mut self, body: &[BaseExpr<Type>]) -> Result<(), Error> {
+        for base_expr in body {
+            self.write_base_expr(base_expr)?;
+        }
+        Ok(())
+    }
+
+    // A block needs at least one statement in Python, a block which writes none gets a pass
+    fn write_block(&mut self, body: &[BaseExpr<Type>]) -> Result<(), Error> {
+        self.indentation += 1;
+        let start = self.output.len();
+        self.write_body(body)?;
+        if self.output.len() == start {
+            self.write_line("pass");
+        }
+        self.indentation -= 1;
+        Ok(())
+    }
+
+    fn assign(&mut self, name: &str, value_type: &Type) {
+        self.variables.insert(name.to_string(), value_type.clone());
+    }
+
+    fn write_base_expr(&mut self, base_expr: &BaseExpr<Type>) -> Result<(), Error> {
+        // Nested blocks recurse once per level, see stack::ensure_stack
+        stack::ensure_stack(|| match &base_expr.data {
+            // An assignment on its own is a statement, the := form is only needed inside an expression
+            BaseExprData::Simple { expr } => {
+                let line = match &expr.data {
+                    RecExprData::Assign {
+                        variable_name,
+                        right,
+                    } => {
+                        self.assign(variable_name, &right.generic_data);
+                        format!("{} = {}", py_name(variable_name), self.expression(right)?)
+                    }
+                    _ => self.expression(expr)?,
+                };
+                self.write_line(&line);
+                Ok(())
+            }
+            BaseExprData::VariableAssignment { var_name, expr } => {
+                let line = format!("{} = {}", py_name(var_name), self.expression(expr)?);
+                self.assign(var_name, &expr.generic_data);
+                self.write_line(&line);
+                Ok(())
+            }
+            // Python checks that the value has as many elements as there are variables
+            BaseExprData::DestructuringAssignment { var_names, expr } => {
+                let element_types = typechecker::destructured_types(&expr.generic_data, var_names.len());
+                for (var_name, element_type) in var_names.iter().zip(&element_types) {
+                    self.assign(var_name, element_type);
+                }
+                let names: Vec<String> = var_names.iter().map(|var_name| py_name(var_name)).collect();
+                let line = format!("{} = {}", names.join(", "), self.expression(expr)?);
+                self.write_line(&line);
+                Ok(())
+            }
+            BaseExprData::PlusEqualsStatement { var_name, expr } => {
+                let line = format!("{} += {}", py_name(var_name), self.expression(expr)?);
+                self.write_line(&line);
+                Ok(())
+            }
+            BaseExprData::FieldAssignment { path, .. } | BaseExprData::IndexAssignment { path, .. }
+                if !path.is_empty() =>
+            {
+                Err(unsupported("An assignment through several fields or indices", base_expr.span()))
+            }
+            BaseExprData::FieldAssignment {
+                object,
+                field,
+                expr,
+                ..
+            } => {
+                self.helpers.dataclass = true;
+                let object = py_name(object);
+                let line = format!("{object} = replace({object}, {field}={})", self.expression(expr)?);
+                self.write_line(&line);
+                Ok(())
+            }
+            BaseExprData::IndexAssignment {
+                object,
+                index,
+                expr,
+                ..
+            } => {
+                let index = self.expression(index)?;
+                let value = self.expression(expr)?;
+                let is_dict = matches!(self.variables.get(object), Some(Type::Dict(..)));
+                let object = py_name(object);
+                match is_dict {
+                    true => self.write_line(&format!("{object} = {{**{object}, {index}: {value}}}")),
+                    false => {
+                        self.write_line(&format!("{object} = {object}.copy()"));
+                        self.write_line(&format!("{object}[{index}] = {value}"));
+                    }
+                }
+                Ok(())
+            }
+            BaseExprData::IfStatement {
+                condition,
+                body,
+                else_statement,
+            } => self.write_if("if", condition, body, else_statement.as_deref()),
+            BaseExprData::ElseIfStatement { .. } | BaseExprData::ElseStatement { .. } => Err(Error::located(
+                "An else can only be written after an if".to_string(),
+                base_expr.span(),
+            )),
+            BaseExprData::ForLoop {
+                var_name,
+                until,
+                body,
+            } => {
+                let iterated = match &until.generic_data {
+                    Type::Integer => {
+                        self.assign(var_name, &Type::Integer);
+                        format!("range({})", self.expression(until)?)
+                    }
+                    Type::List(element_type) | Type::Generator(element_type) => {
+                        self.assign(var_name, element_type);
+                        self.expression(until)?
+                    }
+                    other => return Err(unsupported(&format!("A for loop over {:?}", other), until.span())),
+                };
+                self.write_line(&format!("for {} in {iterated}:", py_name(var_name)));
+                self.write_block(body)
+            }
+            BaseExprData::WhileLoop { condition, body } => {
+                let line = format!("while {}:", self.expression(condition)?);
+                self.write_line(&line);
+                self.write_block(body)
+            }
+            // Functions are written before the program
+            BaseExprData::FunctionDefinition { fun_name, .. } => {
+                self.bind_captured(fun_name, base_expr);
+                Ok(())
+            }
+            // A struct is defined where rosy defines it, so a later definition replaces it as in rosy
+            BaseExprData::StructDefinition { name, fields } => {
+                self.helpers.dataclass = true;
+                self.write_line("@dataclass");
+                self.write_line(&format!("class {}:", py_name(name)));
+                self.indentation += 1;
+                for field in fields {
+                    self.write_line(&format!("{field}: object"));
+                }
+                if fields.is_empty() {
+                    self.write_line("pass");
+                }
+                self.indentation -= 1;
+                Ok(())
+            }
+            BaseExprData::Return { return_value } => {
+                let line = match return_value {
+                    Some(return_value) => format!("return {}", self.expression(return_value)?),
+                    None => String::from("return"),
+                };
+                self.write_line(&line);
+                Ok(())
+            }
+            BaseExprData::Break => {
+                self.write_line("break");
+                Ok(())
+            }
+            BaseExprData::Continue => {
+                self.write_line("continue");
+                Ok(())
+            }
+            BaseExprData::Yield { value } => {
+                let line = format!("yield {}", self.expression(value)?);
+                self.write_line(&line);
+                Ok(())
+            }
+            BaseExprData::Delete { var_name } => {
+                self.write_line(&format!("del {}", py_name(var_name)));
+                Ok(())
+            }
+            BaseExprData::Swap {
+                first_name,
+                second_name,
+            } => {
+                let (first, second) = (py_name(first_name), py_name(second_name));
+                self.write_line(&format!("{first}, {second} = {second}, {first}"));
+                Ok(())
+            }
+            BaseExprData::Match { subject, arms } => self.write_match(subject, arms),
+            BaseExprData::Defer { .. } => Err(unsupported("defer", base_expr.span())),
+            BaseExprData::With { .. } => Err(unsupported("with", base_expr.span())),
+        })
+    }
+
+    // Writes an if or elif with the blocks after it
+    fn write_if(
+        &mut self,
+        keyword: &str,
+        condition: &RecExpr<Type>,
+        body: &[BaseExpr<Type>],
+        else_statement: Option<&BaseExpr<Type>>,
+    ) -> Result<(), Error> {
+        let line = format!("{keyword} {}:", self.expression(condition)?);
+        self.write_line(&line);
+        self.write_block(body)?;
+        match else_statement.map(|else_statement| &else_statement.data) {
+            Some(BaseExprData::ElseIfStatement {
+                condition,
+                body,
+                else_statement,
+            }) => self.write_if("elif", condition, body, else_statement.as_deref()),
+            Some(BaseExprData::ElseStatement { body }) => {
+                self.write_line("else:");
+                self.write_block(body)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Python does not allow cases after one which always matches, they are never reached in rosy either
+    fn write_match(&mut self, subject: &RecExpr<Type>, arms: &[MatchArm<Type>]) -> Result<(), Error> {
+        let line = format!("match {}:", self.expression(subject)?);
+        self.write_line(&line);
+        self.indentation += 1;
+        for arm in arms {
+            let pattern = match &arm.pattern {
+                Pattern::Number(number) => number.to_string(),
+                Pattern::String(value) => string_literal(value),
+                Pattern::Boolean(true) => String::from("True"),
+                Pattern::Boolean(false) => String::from("False"),
+                Pattern::Binding(name) => {
+                    self.assign(name, &subject.generic_data);
+                    py_name(name)
+                }
+                Pattern::Wildcard => String::from("_"),
+            };
+            self.write_line(&format!("case {pattern}:"));
+            self.write_block(&arm.body)?;
+            if matches!(arm.pattern, Pattern::Binding(_) | Pattern::Wildcard) {
+                break;
+            }
+        }
+        if arms.is_empty() {
+            self.write_line("case _:");
+            self.write_block(&[])?;
+        }
+        self.indentation -= 1;
+        Ok(())
+    }
+
+    fn expression(&mut self, expression: &RecExpr<Type>) -> Result<String, Error> {
+        self.expression_with_precedence(expression, LOWEST_PRECEDENCE)
+    }
+
+    fn expressions(&mut self, expressions: &[RecExpr<Type>]) -> Result<Vec<String>, Error> {
+        expressions.iter().map(|expression| self.expression(expression)).collect()
+    }
+
+    // Writes the expression, parenthesized if it binds looser than the given precedence
+    fn expression_with_precedence(&mut self, expression: &RecExpr<Type>, minimum_precedence: u8) -> Result<String, Error> {
+        // Nested expressions recurse once per level, see stack::ensure_stack
+        stack::ensure_stack(|| {
+            let source = match &expression.data {
+                RecExprData::Variable { name } => py_name(name),
+                RecExprData::Number { number } => number.to_string(),
+                RecExprData::Float { value } if value.is_finite() => format!("{value:?}"),
+                RecExprData::Float { .. } => return Err(unsupported("A float which is not finite", expression.span())),
+                RecExprData::String { value } => string_literal(value),
+                RecExprData::Boolean { value: true } => String::from("True"),
+                RecExprData::Boolean { value: false } => String::from("False"),
+                // Python only allows := in some places without parentheses
+                RecExprData::Assign {
+                    variable_name,
+                    right,
+                } => {
+                    self.assign(variable_name, &right.generic_data);
+                    format!("({} := {})", py_name(variable_name), self.expression(right)?)
+                }
+                RecExprData::Add { left, right } => self.binary(expression, left, "+", right)?,
+                RecExprData::Subtract { left, right } => self.binary(expression, left, "-", right)?,
+                RecExprData::Multiply { left, right } => self.binary(expression, left, "*", right)?,
+                RecExprData::Divide { left, right } if expression.generic_data == Type::Integer => {
+                    self.helpers.truncated_division = true;
+                    format!("truncated_division({}, {})", self.expression(left)?, self.expression(right)?)
+                }
+                RecExprData::Divide { left, right } => self.binary(expression, left, "/", right)?,
+                RecExprData::FloorDivide { left, right } => self.binary(expression, left, "//", right)?,
+                RecExprData::Power { left, right } if expression.generic_data == Type::Integer => {
+                    self.helpers.integer_power = true;
+                    format!("integer_power({}, {})", self.expression(left)?, self.expression(right)?)
+                }
+                // ** groups to the right, and binds tighter than a minus sign on its left
+                RecExprData::Power { left, right } => format!(
+                    "{} ** {}",
+                    self.expression_with_precedence(left, ATOM_PRECEDENCE)?,
+                    self.expression_with_precedence(right, POWER_PRECEDENCE)?
+                ),
+                RecExprData::Or { left, right } => self.binary(expression, left, "or", right)?,
+                RecExprData::And { left, right } => self.binary(expression, left, "and", right)?,
+                RecExprData::Equals { left, right } => self.comparison(left, "==", right)?,
+                RecExprData::NotEquals { left, right } => self.comparison(left, "!=", right)?,
+                RecExprData::GreaterThan { left, right } => self.comparison(left, ">", right)?,
+                RecExprData::LessThan { left, right } => self.comparison(left, "<", right)?,
+                RecExprData::GreaterThanOrEqual { left, right } => self.comparison(left, ">=", right)?,
+                RecExprData::LessThanOrEqual { left, right } => self.comparison(left, "<=", right)?,
+                // The operand is parenthesized unless it is a single value, which also keeps - -1 from becoming --1
+                RecExprData::Minus { right } => format!("-{}", self.expression_with_precedence(right, ATOM_PRECEDENCE)?),
+                RecExprData::Not { right } => format!("not {}", self.expression_with_precedence(right, NOT_PRECEDENCE)?),
+                RecExprData::Access { object, variable } => format!("{}.{variable}", py_name(object)),
+                RecExprData::FunctionCall {
+                    function_name,
+                    args,
+                } => self.call(expression, function_name, args)?,
+                RecExprData::List { elements } => format!("[{}]", self.expressions(elements)?.join(", ")),
+                RecExprData::Tuple { elements } if elements.len() == 1 => {
+                    format!("({},)", self.expression(&elements[0])?)
+                }
+                RecExprData::Tuple { elements } => format!("({})", self.expressions(elements)?.join(", ")),
+                // The typechecker puts the arguments of a call in the order of the parameters
+                RecExprData::KeywordArgument { .. } => {
+                    return Err(unsupported("A keyword argument outside a call", expression.span()))
+                }
+                // Written the same as in rosy, only the value after else can be another conditional unparenthesized
+                RecExprData::Conditional {
+                    condition,
+                    then_value,
+                    else_value,
+                } => format!(
+                    "{} if {} else {}",
+                    self.expression_with_precedence(then_value, LOWEST_PRECEDENCE + 1)?,
+                    self.expression_with_precedence(condition, LOWEST_PRECEDENCE + 1)?,
+                    self.expression(else_value)?
+                ),
+                RecExprData::Dict { entries } => {
+                    let mut written = Vec::new();
+                    for (key, value) in entries {
+                        written.push(format!("{}: {}", self.expression(key)?, self.expression(value)?));
+                    }
+                    format!("{{{}}}", written.join(", "))
+                }
+                RecExprData::ListAccess { list, index } => {
+                    let list_source = self.expression_with_precedence(list, ATOM_PRECEDENCE)?;
+                    format!("{list_source}[{}]", self.expression(index)?)
+                }
+                RecExprData::Slice { list, start, end } => {
+                    let list_source = self.expression_with_precedence(list, ATOM_PRECEDENCE)?;
+                    let start = match start {
+                        Some(start) => self.slice_bound(start)?,
+                        None => String::new(),
+                    };
+                    let end = match end {
+                        Some(end) => self.slice_bound(end)?,
+                        None => String::new(),
+                    };
+                    format!("{list_source}[{start}:{end}]")
+                }
+            };
+
+            Ok(match precedence(expression) < minimum_precedence {
+                true => format!("({source})"),
+                false => source,
+            })
+        })
+    }
+
+    // A negative bound counts from the end in Python but is clamped to the start in rosy
+    fn slice_bound(&mut self, bound: &RecExpr<Type>) -> Result<String, Error> {
+        match bound.data {
+            RecExprData::Number { number } if number >= 0 => Ok(number.to_string()),
+            _ => Ok(format!("max({}, 0)", self.expression(bound)?)),
+        }
+    }
+
+    fn binary(
+        &mut self,
+        expression: &RecExpr<Type>,
+        left: &RecExpr<Type>,
+        operator: &str,
+        right: &RecExpr<Type>,
+    ) -> Result<String, Error> {
+        // The right operand of a left associative operator needs parentheses at the same precedence: a - (b - c)
+        let operator_precedence = precedence(expression);
+        Ok(format!(
+            "{} {operator} {}",
+            self.expression_with_precedence(left, operator_precedence)?,
+            self.expression_with_precedence(right, operator_precedence + 1)?
+        ))
+    }
+
+    // Comparisons in Python chain, a < b < c compares b with both, so a comparison as an operand is parenthesized
+    fn comparison(&mut self, left: &RecExpr<Type>, operator: &str, right: &RecExpr<Type>) -> Result<String, Error> {
+        Ok(format!(
+            "{} {operator} {}",
+            self.expression_with_precedence(left, COMPARISON_PRECEDENCE + 1)?,
+            self.expression_with_precedence(right, COMPARISON_PRECEDENCE + 1)?
+        ))
+    }
+
+    // A call constructs a struct, calls a builtin or calls a function of the program, in that order as in the typechecker.
+    // Constructing a struct is a call of its dataclass.
+    fn call(&mut self, expression: &RecExpr<Type>, function_name: &str, args: &[RecExpr<Type>]) -> Result<String, Error> {
+        if let Type::Struct(struct_type) = &expression.generic_data {
+            if struct_type.name == function_name {
+                return Ok(format!("{}({})", py_name(function_name), self.expressions(args)?.join(", ")));
+            }
+        }
+
+        if builtins::find_builtin(function_name).is_some() {
+            return self.builtin_call(expression, function_name, args);
+        }
+
+        let function = transpiler::called_instance(self.functions, function_name, args, expression)?;
+        let mut written = self.expressions(args)?;
+        if let Some(closure) = self.closures.get(&function.written_name) {
+            return Ok(format!("{closure}({})", written.join(", ")));
+        }
+        // Within a nested function itself, the values it captured are its parameters
+        written.extend(function.captured.iter().map(|name| format!("{0}={0}", py_name(name))));
+        Ok(format!("{}({})", function.written_name, written.join(", ")))
+    }
+
+    // Binds every instance of the nested function to the values of the variables it captures, into a
+    // variable no rosy program can name, which calls after the definition go through
+    fn bind_captured(&mut self, fun_name: &str, definition: &BaseExpr<Type>) {
+        let functions = self.functions;
+        for function in functions.iter().filter(|function| function.name == fun_name && !function.captured.is_empty()) {
+            let closure = match self.closures.get(&function.written_name) {
+                Some(closure) => closure.clone(),
+                None => format!("{}_{}_{}", function.written_name, definition.span.row, definition.span.col_start),
+            };
+            let captured: Vec<String> = function.captured.iter().map(|name| format!("{0}={0}", py_name(name))).collect();
+            self.helpers.partial = true;
+            self.write_line(&format!("{closure} = partial({}, {})", function.written_name, captured.join(", ")));
+            self.closures.insert(function.written_name.clone(), closure);
+        }
+    }
+
+    fn builtin_call(&mut self, expression: &RecExpr<Type>, function_name: &str, args: &[RecExpr<Type>]) -> Result<String, Error> {
+        let source = match (function_name, args) {
+            ("print", values @ [_, ..]) => format!("print({}, end=\"\")", self.printed_values(values)?),
+            ("println", values @ [_, ..]) => format!("print({})", self.printed_values(values)?),
+            ("abs" | "float" | "int", [value]) => format!("{function_name}({})", self.expression(value)?),
+            ("min" | "max", [a, b]) => format!("{function_name}({}, {})", self.expression(a)?, self.expression(b)?),
+            ("random", [low, high]) => {
+                self.helpers.randrange = true;
+                format!("randrange({}, {})", self.expression(low)?, self.expression(high)?)
+            }
+            ("find", [text, pattern]) => self.method(text, "find", std::slice::from_ref(pattern))?,
+            ("slice", [text, start, end]) if text.generic_data == Type::String => format!(
+                "{}[{}:{}]",
+                self.expression_with_precedence(text, ATOM_PRECEDENCE)?,
+                self.expression(start)?,
+                self.expression(end)?
+            ),
+            ("pad_left", [text, rest @ ..]) => self.method(text, "rjust", rest)?,
+            ("pad_right", [text, rest @ ..]) => self.method(text, "ljust", rest)?,
+            // Parenthesized, as the product is the receiver of a method when it is padded
+            ("repeat", [text, count]) => format!(
+                "({} * {})",
+                self.expression_with_precedence(text, MULTIPLY_PRECEDENCE)?,
+                self.expression_with_precedence(count, MULTIPLY_PRECEDENCE + 1)?
+            ),
+            _ => {
+                return Err(unsupported(&format!("The builtin {}", function_name), expression.span()));
+            }
+        };
+        Ok(source)
+    }
+
+    // A call of the string method on the first argument of a builtin
+    fn method(&mut self, receiver: &RecExpr<Type>, method: &str, args: &[RecExpr<Type>]) -> Result<String, Error> {
+        let receiver = self.expression_with_precedence(receiver, ATOM_PRECEDENCE)?;
+        Ok(format!("{receiver}.{method}({})", self.expressions(args)?.join(", ")))
+    }
+
+    // The text print writes for the value, booleans are written in lowercase as rosy does
+    fn printed(&mut self, value: &RecExpr<Type>) -> Result<String, Error> {
+        match value.generic_data {
+            Type::Boolean => Ok(format!("str({}).lower()", self.expression(value)?)),
+            Type::Float => {
+                self.helpers.format_float = true;
+                Ok(format!("format_float({})", self.expression(value)?))
+            }
+            Type::Integer | Type::String => self.expression(value),
+            _ => Err(unsupported("Printing this value", value.span())),
+        }
+    }
+
+    // The arguments of print for the values of a print call, which rosy writes without a space between them
+    fn printed_values(&mut self, values: &[RecExpr<Type>]) -> Result<String, Error> {
+        let printed = values.iter().map(|value| self.printed(value)).collect::<Result<Vec<_>, _>>()?;
+        match printed.len() {
+            1 => Ok(printed.join("")),
+            _ => Ok(format!("{}, sep=\"\"", printed.join(", "))),
+        }
+    }
+}
+
+// How tightly an expression binds its operands, following the operator precedence of Python.
+// Calls, attribute references and literals bind tightest.
+const LOWEST_PRECEDENCE: u8 = 0;
+const NOT_PRECEDENCE: u8 = 3;
+const COMPARISON_PRECEDENCE: u8 = 4;
+const MULTIPLY_PRECEDENCE: u8 = 6;
+const POWER_PRECEDENCE: u8 = 8;
+const ATOM_PRECEDENCE: u8 = 9;
+
+fn precedence(expression: &RecExpr<Type>) -> u8 {
+    match &expression.data {
+        RecExprData::Conditional { .. } => LOWEST_PRECEDENCE,
+        // Negative literals are only made by constant evaluation, they are written with a minus sign
+        RecExprData::Number { number } if *number < 0 => LOWEST_PRECEDENCE,
+        RecExprData::Float { value } if value.is_sign_negative() => LOWEST_PRECEDENCE,
+        RecExprData::Or { .. } => 1,
+        RecExprData::And { .. } => 2,
+        RecExprData::Not { .. } => NOT_PRECEDENCE,
+        RecExprData::Equals { .. }
+        | RecExprData::NotEquals { .. }
+        | RecExprData::GreaterThan { .. }
+        | RecExprData::LessThan { .. }
+        | RecExprData::GreaterThanOrEqual { .. }
+        | RecExprData::LessThanOrEqual { .. } => COMPARISON_PRECEDENCE,
+        RecExprData::Add { .. } | RecExprData::Subtract { .. } => 5,
+        RecExprData::Divide { .. } | RecExprData::Power { .. } if expression.generic_data == Type::Integer => ATOM_PRECEDENCE,
+        RecExprData::Multiply { .. } | RecExprData::Divide { .. } | RecExprData::FloorDivide { .. } => {
+            MULTIPLY_PRECEDENCE
+        }
+        RecExprData::Minus { .. } => 7,
+        RecExprData::Power { .. } => POWER_PRECEDENCE,
+        _ => ATOM_PRECEDENCE,
+    }
+}
+
+fn py_name(name: &str) -> String {
+    match RESERVED_NAMES.contains(&name) {
+        true => format!("{name}_"),
+        false => name.to_string(),
+    }
+}
+
+fn unsupported(what: &str, span: Span) -> Error {
+    Error::located(format!("{} cannot be written as Python", what), span)
+}
@@ -0,0 +1,139 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+// An exact fraction, always stored in lowest terms with a positive denominator,
+// so that equal numbers have equal representations.
+// Arithmetic is checked: overflowing the 64 bit numerator or denominator is an error.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rational {
+    numerator: i64,
+    denominator: i64,
+}
+
+fn greatest_common_divisor(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn overflow_error() -> String {
+    "Rational number overflow".to_string()
+}
+
+impl Rational {
+    pub fn new(numerator: i64, denominator: i64) -> Result<Rational, String> {
+        if denominator == 0 {
+            return Err("Rational number with a denominator of zero".to_string());
+        }
+
+        // Normalized in 128 bits, as negating i64::MIN does not fit in 64 bits
+        let (numerator, denominator) = (numerator as i128, denominator as i128);
+        let divisor = greatest_common_divisor(numerator, denominator) * denominator.signum();
+
+        match (
+            i64::try_from(numerator / divisor),
+            i64::try_from(denominator / divisor),
+        ) {
+            (Ok(numerator), Ok(denominator)) => Ok(Rational {
+                numerator,
+                denominator,
+            }),
+            _ => Err(overflow_error()),
+        }
+    }
+
+    pub fn from_integer(value: i64) -> Rational {
+        Rational {
+            numerator: value,
+            denominator: 1,
+        }
+    }
+
+    pub fn numerator(&self) -> i64 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i64 {
+        self.denominator
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    pub fn checked_add(self, other: Rational) -> Result<Rational, String> {
+        let left = self.numerator.checked_mul(other.denominator);
+        let right = other.numerator.checked_mul(self.denominator);
+        let numerator = match (left, right) {
+            (Some(left), Some(right)) => left.checked_add(right),
+            _ => None,
+        };
+        let denominator = self.denominator.checked_mul(other.denominator);
+
+        match (numerator, denominator) {
+            (Some(numerator), Some(denominator)) => Rational::new(numerator, denominator),
+            _ => Err(overflow_error()),
+        }
+    }
+
+    pub fn checked_sub(self, other: Rational) -> Result<Rational, String> {
+        self.checked_add(other.checked_neg()?)
+    }
+
+    pub fn checked_mul(self, other: Rational) -> Result<Rational, String> {
+        let numerator = self.numerator.checked_mul(other.numerator);
+        let denominator = self.denominator.checked_mul(other.denominator);
+
+        match (numerator, denominator) {
+            (Some(numerator), Some(denominator)) => Rational::new(numerator, denominator),
+            _ => Err(overflow_error()),
+        }
+    }
+
+    pub fn checked_div(self, other: Rational) -> Result<Rational, String> {
+        if other.numerator == 0 {
+            return Err("Division by zero".to_string());
+        }
+
+        self.checked_mul(Rational::new(other.denominator, other.numerator)?)
+    }
+
+    pub fn checked_neg(self) -> Result<Rational, String> {
+        match self.numerator.checked_neg() {
+            Some(numerator) => Ok(Rational {
+                numerator,
+                denominator: self.denominator,
+            }),
+            None => Err(overflow_error()),
+        }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Rational) -> Ordering {
+        // The denominators are positive, so cross multiplying keeps the order.
+        // The products are computed in 128 bits so they cannot overflow.
+        let left = self.numerator as i128 * other.denominator as i128;
+        let right = other.numerator as i128 * self.denominator as i128;
+        left.cmp(&right)
+    }
+}
+
+// Whole numbers are written without a denominator
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
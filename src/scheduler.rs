@@ -0,0 +1,80 @@
+use crate::interpreter::ProgramState;
+use crate::interpreter::Terminal;
+use crate::tokenizer::Error;
+
+// Runs several programs by interleaving them: every tick, each program that has not finished yet
+// runs a fixed number of statements, counting the statements within loops and function calls too.
+// This makes many small scripts (for example one per game entity) appear to run concurrently,
+// also when a script spends its time in a long loop.
+pub struct Scheduler {
+    scripts: Vec<ScheduledScript>,
+    statements_per_tick: usize,
+}
+
+struct ScheduledScript {
+    program: ProgramState,
+    error: Option<Error>,
+}
+
+// Identifies a script added to a scheduler
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ScriptId(usize);
+
+impl Scheduler {
+    pub fn new(statements_per_tick: usize) -> Scheduler {
+        Scheduler {
+            scripts: Vec::new(),
+            statements_per_tick,
+        }
+    }
+
+    // Adds a program, which is usually created with Engine::load
+    pub fn add(&mut self, program: ProgramState) -> ScriptId {
+        self.scripts.push(ScheduledScript {
+            program,
+            error: None,
+        });
+        ScriptId(self.scripts.len() - 1)
+    }
+
+    fn is_running(script: &ScheduledScript) -> bool {
+        script.error.is_none() && !script.program.is_finished()
+    }
+
+    // Runs every unfinished script for one time slice, in the order they were added.
+    // A script which fails is stopped, the other scripts keep running.
+    // Returns the number of scripts which are still running.
+    pub fn tick(&mut self) -> usize {
+        for script in self.scripts.iter_mut() {
+            if !Self::is_running(script) {
+                continue;
+            }
+
+            if let Err(error) = script.program.step(self.statements_per_tick) {
+                script.error = Some(error);
+            }
+        }
+
+        self.running_count()
+    }
+
+    pub fn run_until_finished(&mut self) {
+        while self.tick() > 0 {}
+    }
+
+    pub fn running_count(&self) -> usize {
+        self.scripts
+            .iter()
+            .filter(|script| Self::is_running(script))
+            .count()
+    }
+
+    pub fn terminal(&self, id: ScriptId) -> &Terminal {
+        &self.scripts[id.0].program.context.terminal
+    }
+
+    // The error that stopped the script, if any
+    pub fn error(&self, id: ScriptId) -> Option<&Error> {
+        self.scripts[id.0].error.as_ref()
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+use std::ops::Deref;
+
+// The longest text which is stored inline, chosen so that a SmallString is no larger than a String with a tag,
+// which keeps Value at the size it had with String
+pub const INLINE_CAPACITY: usize = 30;
+
+// The text of a string value. Most strings in a program are short, such as literals and single words,
+// so those are stored inline: creating and copying them does not allocate.
+// Longer text is kept in a String on the heap.
+#[derive(Clone)]
+pub enum SmallString {
+    Inline {
+        length: u8,
+        bytes: [u8; INLINE_CAPACITY],
+    },
+    Heap(String),
+}
+
+impl SmallString {
+    pub fn new() -> SmallString {
+        SmallString::Inline {
+            length: 0,
+            bytes: [0; INLINE_CAPACITY],
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            // The bytes always come from a str, so they are valid utf-8
+            SmallString::Inline { length, bytes } => std::str::from_utf8(&bytes[..*length as usize]).unwrap(),
+            SmallString::Heap(text) => text,
+        }
+    }
+
+    pub fn is_inline(&self) -> bool {
+        matches!(self, SmallString::Inline { .. })
+    }
+
+    pub fn push_str(&mut self, text: &str) {
+        match self {
+            SmallString::Inline { length, bytes } if *length as usize + text.len() <= INLINE_CAPACITY => {
+                let start = *length as usize;
+                bytes[start..start + text.len()].copy_from_slice(text.as_bytes());
+                *length += text.len() as u8;
+            }
+            SmallString::Inline { .. } => {
+                let mut heap_text = String::with_capacity(self.len() + text.len());
+                heap_text.push_str(self.as_str());
+                heap_text.push_str(text);
+                *self = SmallString::Heap(heap_text);
+            }
+            SmallString::Heap(heap_text) => heap_text.push_str(text),
+        }
+    }
+}
+
+impl Default for SmallString {
+    fn default() -> Self {
+        SmallString::new()
+    }
+}
+
+impl From<&str> for SmallString {
+    fn from(text: &str) -> Self {
+        if text.len() > INLINE_CAPACITY {
+            return SmallString::Heap(text.to_string());
+        }
+
+        let mut bytes = [0; INLINE_CAPACITY];
+        bytes[..text.len()].copy_from_slice(text.as_bytes());
+        SmallString::Inline {
+            length: text.len() as u8,
+            bytes,
+        }
+    }
+}
+
+impl From<String> for SmallString {
+    // A long String is kept as it is, so its text is not copied
+    fn from(text: String) -> Self {
+        if text.len() > INLINE_CAPACITY {
+            return SmallString::Heap(text);
+        }
+        SmallString::from(text.as_str())
+    }
+}
+
+impl From<SmallString> for String {
+    fn from(text: SmallString) -> Self {
+        match text {
+            SmallString::Inline { .. } => text.as_str().to_string(),
+            SmallString::Heap(text) => text,
+        }
+    }
+}
+
+impl Deref for SmallString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for SmallString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<std::path::Path> for SmallString {
+    fn as_ref(&self) -> &std::path::Path {
+        std::path::Path::new(self.as_str())
+    }
+}
+
+// Equal text is equal, however it is stored
+impl PartialEq for SmallString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SmallString {}
+
+impl PartialEq<str> for SmallString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for SmallString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialOrd for SmallString {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SmallString {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl std::hash::Hash for SmallString {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl fmt::Display for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Debug for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
@@ -0,0 +1,14 @@
+// The passes over the syntax tree recurse once for every level of nesting in a program,
+// so deeply nested code (or a long chain like 1 + 1 + ... + 1) could overflow the stack.
+// The recursive functions of those passes run through this, which moves to a new stack
+// on the heap when the current one is about to run out.
+
+// The stack space that must be left before a recursive call, generous because the frames
+// of the large match functions are big in debug builds
+const RED_ZONE: usize = 256 * 1024;
+// The size of every new stack segment
+const STACK_SEGMENT_SIZE: usize = 4 * 1024 * 1024;
+
+pub fn ensure_stack<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(RED_ZONE, STACK_SEGMENT_SIZE, f)
+}
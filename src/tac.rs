@@ -9,10 +9,14 @@ use crate::parser::RecExprData;
 use crate::tokenizer::Error;
 use crate::typechecker::FunctionType;
 use crate::typechecker::Type;
+use crate::typechecker::type_to_string;
 use crate::defaultfunctions;
+use crate::builtins;
 use crate::codegenerator::Instruction;
+use crate::visitor;
+use crate::visitor::Visitor;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TacInstruction {
     Assign(VariableValue, TacValue),
     BinOp(VariableValue, TacValue, BinOp, TacValue),
@@ -52,7 +56,7 @@ impl std::fmt::Display for VariableValue {
 }
 
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BinOp {
     Add,
     Sub,
@@ -68,13 +72,13 @@ pub enum BinOp {
     Or,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum UnOp {
     Neg,
     Not,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ComparisonOp {
     Eq,
     Ne,
@@ -84,7 +88,7 @@ pub enum ComparisonOp {
     Ge,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TacValue {
     Constant(i64),
     Variable(String),
@@ -120,46 +124,79 @@ struct TacVariableEnvironment {
 
 // Finds a function in the environment by name and argument types
 // Returns the function label if found
-fn find_function(
-    env: &TacFunctionEnvironment,
+fn find_function<'a>(
+    env: &'a TacFunctionEnvironment,
     name: &str,
-    arg_types: Vec<Type>,
-) -> Result<(String, Type), Error> {
-    for func in &env.functions {
-        if func.name == name && func.params == arg_types {
-            return Ok((func.label.clone(), func.return_type.clone()));
-        }
+    arg_types: &[Type],
+) -> Option<&'a TacFunction> {
+    env.functions
+        .iter()
+        .find(|func| func.name == name && func.params == arg_types)
+}
+
+// The label of a function instance. The typechecker makes an instance of a function for every list of
+// argument types it is called with, and each instance is compiled on its own, so they need different labels.
+pub fn function_label(function: &FunctionType, functions: &[FunctionType]) -> String {
+    let instances: Vec<&FunctionType> = functions
+        .iter()
+        .filter(|other| other.name == function.name)
+        .collect();
+    if instances.len() == 1 {
+        return format!("func_{}", function.name);
     }
 
-    Err(Error::SimpleError {
-        message: format!(
-            "Function '{}' with specified argument types not found",
-            name
-        ),
-    })
+    let instance_index = instances
+        .iter()
+        .position(|instance| instance.param_types == function.param_types)
+        .unwrap();
+    format!("func_{}_{}", function.name, instance_index)
 }
 
-fn add_functions(
-    functions: &Vec<FunctionType>,
+// The program is compiled as a whole, so every function instance it can call is known up front:
+// all of them are registered before any code is generated, and each call is resolved to the label
+// of exactly one instance, making every call a direct call.
+fn register_functions(
+    functions: &[FunctionType],
     function_env: &mut TacFunctionEnvironment,
-    variable_env: &mut TacVariableEnvironment,
-    instructions: &mut Vec<TacInstruction>,
-    temp_counter: &mut i64,
-    label_counter: &mut i64,
 ) -> Result<(), Error> {
     for function in functions {
         if defaultfunctions::is_default_function(&function.name) {
             continue;
         }
 
-        let label = format!("func_{}", function.name);
+        if builtins::is_builtin(&function.name) {
+            return Err(Error::SimpleError {
+                message: format!(
+                    "Builtin function '{}' is not supported by the compiler",
+                    function.name
+                ),
+            });
+        }
 
         function_env.functions.push(TacFunction {
             name: function.name.clone(),
             params: function.param_types.clone(),
             return_type: function.return_type.clone(),
-            label: label.clone(),
+            label: function_label(function, functions),
         });
+    }
+    Ok(())
+}
+
+fn add_functions(
+    functions: &Vec<FunctionType>,
+    function_env: &mut TacFunctionEnvironment,
+    variable_env: &mut TacVariableEnvironment,
+    instructions: &mut Vec<TacInstruction>,
+    temp_counter: &mut i64,
+    label_counter: &mut i64,
+) -> Result<(), Error> {
+    for function in functions {
+        if defaultfunctions::is_default_function(&function.name) {
+            continue;
+        }
+
+        let label = function_label(function, functions);
 
         // Now we can generate TAC for the function body
         instructions.push(TacInstruction::FunctionLabel(label, function.param_names.clone()));
@@ -178,6 +215,58 @@ fn add_functions(
     Ok(())
 }
 
+// Generates the code of the given function instances on their own, without the default functions and
+// the top level code. Used by the JIT, which compiles a few functions of a program while it runs:
+// calls can go to any function of the program, but only the given instances get a body.
+pub fn generate_function_tac(
+    instances: &[&FunctionType],
+    functions: &[FunctionType],
+) -> Result<Vec<TacInstruction>, Error> {
+    let mut instructions = Vec::new();
+    let mut temp_counter = 0;
+    let mut label_counter = 0;
+
+    let mut function_env = TacFunctionEnvironment {
+        functions: functions
+            .iter()
+            .filter(|function| {
+                !defaultfunctions::is_default_function(&function.name)
+                    && !builtins::is_builtin(&function.name)
+            })
+            .map(|function| TacFunction {
+                name: function.name.clone(),
+                params: function.param_types.clone(),
+                return_type: function.return_type.clone(),
+                label: function_label(function, functions),
+            })
+            .collect(),
+    };
+    let mut variable_env = TacVariableEnvironment {
+        scopes: vec![TacVariableScope {
+            variables: HashMap::new(),
+        }],
+    };
+
+    for function in instances {
+        instructions.push(TacInstruction::FunctionLabel(
+            function_label(function, functions),
+            function.param_names.clone(),
+        ));
+        for expr in &function.content {
+            generate_tac_for_base_expr(
+                expr,
+                &mut instructions,
+                &mut temp_counter,
+                &mut label_counter,
+                &mut function_env,
+                &mut variable_env,
+            )?;
+        }
+    }
+
+    Ok(instructions)
+}
+
 fn find_variable_alias_in_current_scope(
     env: &TacVariableEnvironment,
     var_name: &str,
@@ -202,6 +291,60 @@ fn find_variable_alias(env: &TacVariableEnvironment, var_name: &str) -> Option<S
     None
 }
 
+// Rejects what uniquify cannot rename, before it runs: uniquify only knows the variables a function
+// binds itself, so a function reading a variable of the function around it and the names bound
+// by a destructuring assignment are reported here as unsupported, like the rest of the compiler does
+pub fn check_supported_before_uniquify(program: &(Vec<BaseExpr<Type>>, Vec<FunctionType>)) -> Result<(), Error> {
+    struct UnsupportedFinder<'a> {
+        // The variables the function being visited captures
+        captured: &'a [(String, Type)],
+        error: Option<Error>,
+    }
+
+    impl Visitor<Type> for UnsupportedFinder<'_> {
+        fn visit_base_expr(&mut self, base_expr: &BaseExpr<Type>) {
+            if self.error.is_none() {
+                if let BaseExprData::DestructuringAssignment { .. } = base_expr.data {
+                    self.error = Some(Error::located(
+                        "Assigning to several variables at once is not supported by the compiler".to_string(),
+                        base_expr.span(),
+                    ));
+                }
+            }
+            visitor::walk_base_expr(self, base_expr);
+        }
+
+        fn visit_rec_expr(&mut self, rec_expr: &RecExpr<Type>) {
+            if self.error.is_none() {
+                if let RecExprData::Variable { name } = &rec_expr.data {
+                    if self.captured.iter().any(|(captured_name, _)| captured_name == name) {
+                        self.error = Some(Error::located(
+                            "Closures are not supported by the compiler".to_string(),
+                            rec_expr.span(),
+                        ));
+                    }
+                }
+            }
+            visitor::walk_rec_expr(self, rec_expr);
+        }
+    }
+
+    let mut finder = UnsupportedFinder {
+        captured: &[],
+        error: None,
+    };
+    finder.visit_body(&program.0);
+    for function in &program.1 {
+        finder.captured = &function.captured;
+        finder.visit_body(&function.content);
+    }
+
+    match finder.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
 pub fn generate_tac(
     program: Vec<BaseExpr<Type>>,
     functions: Vec<FunctionType>,
@@ -219,6 +362,11 @@ pub fn generate_tac(
             variables: HashMap::new(),
         }],
     };
+    // The default functions and the functions of the program are all known before any body is generated,
+    // so a function can call any other function, including itself
+    defaultfunctions::add_default_functions(functions.clone(), &mut function_env, &mut instructions, &mut temp_counter, &mut label_counter);
+    register_functions(&functions, &mut function_env)?;
+
     add_functions(
         &functions,
         &mut function_env,
@@ -228,8 +376,6 @@ pub fn generate_tac(
         &mut label_counter,
     )?;
 
-    defaultfunctions::add_default_functions(functions, &mut function_env, &mut instructions, &mut temp_counter, &mut label_counter);
-
     instructions.push(TacInstruction::ProgramStart());
     for expr in program {
         generate_tac_for_base_expr(
@@ -337,6 +483,43 @@ fn generate_tac_for_base_expr(
             // End of loop
             instructions.push(TacInstruction::Label(end_label));
         }
+        BaseExprData::WhileLoop { condition, body } => {
+            let start_label = format!("L{}", label_counter);
+            *label_counter += 1;
+            let end_label = format!("L{}", label_counter);
+            *label_counter += 1;
+
+            // Start of loop, the condition is checked before every iteration
+            instructions.push(TacInstruction::Label(start_label.clone()));
+            let cond_value = generate_tac_for_rec_expr(
+                condition,
+                instructions,
+                temp_counter,
+                function_env,
+                variable_env,
+            )?;
+            instructions.push(TacInstruction::CompareAndGoto(
+                cond_value,
+                TacValue::Constant(0),
+                ComparisonOp::Eq,
+                end_label.clone(),
+            ));
+            // Loop body
+            for body_expr in body {
+                generate_tac_for_base_expr(
+                    body_expr,
+                    instructions,
+                    temp_counter,
+                    label_counter,
+                    function_env,
+                    variable_env,
+                )?;
+            }
+            // Jump back to start
+            instructions.push(TacInstruction::Goto(start_label));
+            // End of loop
+            instructions.push(TacInstruction::Label(end_label));
+        }
         BaseExprData::Return { return_value } => {
             if let Some(ret_expr) = return_value {
                 let ret_value = generate_tac_for_rec_expr(
@@ -485,6 +668,54 @@ fn generate_tac_for_base_expr(
                 )?;
             }
         }
+        BaseExprData::Yield { .. } => {
+            return Err(Error::located(
+                "Generators are not supported by the compiler".to_string(),
+                expr.span(),
+            ));
+        }
+        BaseExprData::Defer { .. } => {
+            return Err(Error::located(
+                "Defer is not supported by the compiler".to_string(),
+                expr.span(),
+            ));
+        }
+        BaseExprData::Swap { .. } => {
+            return Err(Error::located(
+                "Swap is not supported by the compiler".to_string(),
+                expr.span(),
+            ));
+        }
+        BaseExprData::DestructuringAssignment { .. } => {
+            return Err(Error::located(
+                "Assigning to several variables at once is not supported by the compiler".to_string(),
+                expr.span(),
+            ));
+        }
+        BaseExprData::With { .. } => {
+            return Err(Error::located(
+                "Resources are not supported by the compiler".to_string(),
+                expr.span(),
+            ));
+        }
+        BaseExprData::Match { .. } => {
+            return Err(Error::located(
+                "Match is not supported by the compiler".to_string(),
+                expr.span(),
+            ));
+        }
+        BaseExprData::StructDefinition { .. } | BaseExprData::FieldAssignment { .. } => {
+            return Err(Error::located(
+                "Structs are not supported by the compiler".to_string(),
+                expr.span(),
+            ));
+        }
+        BaseExprData::IndexAssignment { .. } => {
+            return Err(Error::located(
+                "Assigning to elements is not supported by the compiler".to_string(),
+                expr.span(),
+            ));
+        }
         _ => {
             // For other base expressions, we can ignore them or handle as needed
         }
@@ -501,7 +732,27 @@ fn generate_tac_for_rec_expr(
 ) -> Result<TacValue, Error> {
     match &expr.data {
         RecExprData::Number { number } => Ok(TacValue::Constant(*number)),
+        RecExprData::Float { .. } => Err(Error::located(
+            "Floats are not supported by the compiler".to_string(),
+            expr.span(),
+        )),
         RecExprData::String { value } => Ok(TacValue::StringLiteral(value.clone())),
+        RecExprData::Dict { .. } => Err(Error::located(
+            "Dicts are not supported by the compiler".to_string(),
+            expr.span(),
+        )),
+        RecExprData::Tuple { .. } => Err(Error::located(
+            "Tuples are not supported by the compiler".to_string(),
+            expr.span(),
+        )),
+        RecExprData::Conditional { .. } => Err(Error::located(
+            "Conditional expressions are not supported by the compiler".to_string(),
+            expr.span(),
+        )),
+        RecExprData::Slice { .. } => Err(Error::located(
+            "Slices are not supported by the compiler".to_string(),
+            expr.span(),
+        )),
         RecExprData::Variable { name } => Ok(TacValue::Variable(name.clone())),
         RecExprData::Boolean { value } => Ok(TacValue::Constant(if *value { 1 } else { 0 })),
         RecExprData::Add { left, right } => generate_binary_op_tac(
@@ -625,7 +876,7 @@ fn generate_tac_for_rec_expr(
             *temp_counter += 1;
             instructions.push(TacInstruction::UnaryOp(
                 VariableValue::Variable(temp_var.clone()),
-                UnOp::Neg,
+                UnOp::Not,
                 operand,
             ));
             Ok(TacValue::Variable(temp_var))
@@ -682,11 +933,20 @@ fn generate_tac_for_rec_expr(
                 arg_values.push(arg_value);
                 arg_types.push(arg.generic_data.clone());
             }
-            let (function_label, return_type) =
-                match find_function(function_env, function_name, arg_types) {
-                    Ok((label, return_type)) => (label, return_type),
-                    Err(e) => return Err(e),
-                };
+            let (function_label, return_type) = match find_function(function_env, function_name, &arg_types) {
+                Some(function) => (function.label.clone(), function.return_type.clone()),
+                None => {
+                    let arg_type_names: Vec<String> = arg_types.iter().map(type_to_string).collect();
+                    return Err(Error::located(
+                        format!(
+                            "Cannot resolve the call to {}({}) at compile time",
+                            function_name,
+                            arg_type_names.join(", ")
+                        ),
+                        expr.span(),
+                    ));
+                }
+            };
 
             /* 
             let argument_registers = vec![0, 1, 4, 3];
@@ -728,7 +988,27 @@ fn generate_tac_for_rec_expr(
                 }
             }
         }
-        RecExprData::ListAccess { variable, index } => {
+        RecExprData::ListAccess { list, index } => {
+            let list_value = generate_tac_for_rec_expr(
+                list,
+                instructions,
+                temp_counter,
+                function_env,
+                variable_env,
+            )?;
+            // The list has to be in a variable to be indexed
+            let list_variable = match list_value {
+                TacValue::Variable(name) => name,
+                other_value => {
+                    let temp_var = format!("t{}", temp_counter);
+                    *temp_counter += 1;
+                    instructions.push(TacInstruction::Assign(
+                        VariableValue::Variable(temp_var.clone()),
+                        other_value,
+                    ));
+                    temp_var
+                }
+            };
             let index_value = generate_tac_for_rec_expr(
                 index,
                 instructions,
@@ -739,7 +1019,7 @@ fn generate_tac_for_rec_expr(
             let temp_var = format!("t{}", temp_counter);
             *temp_counter += 1;
             instructions.push(TacInstruction::Assign(VariableValue::Variable(temp_var.clone()), TacValue::ListAccess {
-                list_variable: variable.clone(),
+                list_variable,
                 index: Box::new(index_value.clone()),
             }));
             Ok(TacValue::Variable(temp_var))
@@ -0,0 +1,922 @@
+// The text format of the three address code, written to .rir files.
+// Printing a list of instructions and parsing the text back gives the same instructions,
+// so a small piece of IR can be given to the backend without the program it came from.
+//
+// Variables start with a %, labels and function names are written as they are:
+//
+//     fun func_add(a, b)
+//         %t0 = %a + %b
+//         return %t0
+//     start
+//         %x = call func_add(1, 2)
+//         if %x < 10 goto L0
+//         %x = 10
+//     L0:
+//         asm mov rax, [rsp + 8]
+//
+// Everything after a ; outside of a string is a comment.
+
+use crate::codegenerator::Argument;
+use crate::codegenerator::Instruction;
+use crate::codegenerator::Register;
+use crate::codegenerator::RegisterSize;
+use crate::codegenerator::RegisterType;
+use crate::tac::BinOp;
+use crate::tac::ComparisonOp;
+use crate::tac::TacInstruction;
+use crate::tac::TacValue;
+use crate::tac::UnOp;
+use crate::tac::VariableValue;
+use crate::tokenizer::Error;
+use crate::tokenizer::Span;
+use crate::verifier;
+
+pub fn instructions_to_text(instructions: &[TacInstruction]) -> String {
+    let mut text = String::new();
+    for instruction in instructions {
+        // Labels stay at the start of the line so the blocks are easy to find
+        let indent = match instruction {
+            TacInstruction::FunctionLabel(..)
+            | TacInstruction::ProgramStart()
+            | TacInstruction::Label(_) => "",
+            _ => "    ",
+        };
+        text.push_str(indent);
+        text.push_str(&instruction_to_text(instruction));
+        text.push('\n');
+    }
+    text
+}
+
+pub fn instruction_to_text(instruction: &TacInstruction) -> String {
+    match instruction {
+        TacInstruction::Assign(var, value) => {
+            format!("{} = {}", variable_to_text(var), value_to_text(value))
+        }
+        TacInstruction::BinOp(var, left, op, right) => format!(
+            "{} = {} {} {}",
+            variable_to_text(var),
+            value_to_text(left),
+            binop_to_text(*op),
+            value_to_text(right)
+        ),
+        TacInstruction::UnaryOp(var, op, operand) => {
+            let op = match op {
+                UnOp::Neg => "neg",
+                UnOp::Not => "not",
+            };
+            format!("{} = {} {}", variable_to_text(var), op, value_to_text(operand))
+        }
+        TacInstruction::Goto(label) => format!("goto {}", label),
+        TacInstruction::CompareAndGoto(left, right, comparison, label) => format!(
+            "if {} {} {} goto {}",
+            value_to_text(left),
+            comparison_to_text(*comparison),
+            value_to_text(right),
+            label
+        ),
+        TacInstruction::Label(label) => format!("{}:", label),
+        TacInstruction::FunctionLabel(name, params) => {
+            format!("fun {}({})", name, params.join(", "))
+        }
+        TacInstruction::Call(name, args, result) => call_to_text("call", name, args, result),
+        TacInstruction::ExternCall(name, args, result) => {
+            call_to_text("extern", name, args, result)
+        }
+        TacInstruction::Return(Some(value)) => format!("return {}", value_to_text(value)),
+        TacInstruction::Return(None) => "return".to_string(),
+        TacInstruction::Push(value) => format!("push {}", value_to_text(value)),
+        TacInstruction::Pop(var) => format!("pop {}", variable_to_text(var)),
+        TacInstruction::MovRSPTo(var) => format!("{} = rsp", variable_to_text(var)),
+        TacInstruction::ProgramStart() => "start".to_string(),
+        TacInstruction::DirectInstruction(instruction) => {
+            format!("asm {}", direct_instruction_to_text(instruction))
+        }
+        TacInstruction::InstantiateList(var, elements) => format!(
+            "{} = list [{}]",
+            variable_to_text(var),
+            values_to_text(elements)
+        ),
+        TacInstruction::InstantiateStruct(var, fields) => format!(
+            "{} = struct ({})",
+            variable_to_text(var),
+            values_to_text(fields)
+        ),
+    }
+}
+
+fn call_to_text(
+    keyword: &str,
+    name: &str,
+    args: &[TacValue],
+    result: &Option<VariableValue>,
+) -> String {
+    let call = format!("{} {}({})", keyword, name, values_to_text(args));
+    match result {
+        Some(var) => format!("{} = {}", variable_to_text(var), call),
+        None => call,
+    }
+}
+
+fn variable_to_text(var: &VariableValue) -> String {
+    match var {
+        VariableValue::Variable(name) => format!("%{}", name),
+        VariableValue::VariableWithRequestedRegister(name, index) => {
+            format!("%{}@{}", name, index)
+        }
+    }
+}
+
+fn values_to_text(values: &[TacValue]) -> String {
+    values
+        .iter()
+        .map(value_to_text)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn value_to_text(value: &TacValue) -> String {
+    match value {
+        TacValue::Constant(number) => number.to_string(),
+        TacValue::Variable(name) => format!("%{}", name),
+        TacValue::StringLiteral(text) => string_to_text(text),
+        TacValue::Struct(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, value_to_text(value)))
+                .collect::<Vec<_>>();
+            format!("{{{}}}", fields.join(", "))
+        }
+        TacValue::ListAccess {
+            list_variable,
+            index,
+        } => format!("%{}[{}]", list_variable, value_to_text(index)),
+    }
+}
+
+fn string_to_text(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn binop_to_text(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+        BinOp::Eq => "==",
+        BinOp::Ne => "!=",
+        BinOp::Lt => "<",
+        BinOp::Le => "<=",
+        BinOp::Gt => ">",
+        BinOp::Ge => ">=",
+        BinOp::And => "and",
+        BinOp::Or => "or",
+    }
+}
+
+fn comparison_to_text(comparison: ComparisonOp) -> &'static str {
+    match comparison {
+        ComparisonOp::Eq => "==",
+        ComparisonOp::Ne => "!=",
+        ComparisonOp::Lt => "<",
+        ComparisonOp::Le => "<=",
+        ComparisonOp::Gt => ">",
+        ComparisonOp::Ge => ">=",
+    }
+}
+
+fn direct_instruction_to_text(instruction: &Instruction) -> String {
+    let args = |args: &[&Argument]| {
+        args.iter()
+            .map(|arg| argument_to_text(arg))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match instruction {
+        Instruction::Mov(dest, src) => format!("mov {}", args(&[dest, src])),
+        Instruction::Add(dest, a, b) => format!("add {}", args(&[dest, a, b])),
+        Instruction::Sub(dest, a, b) => format!("sub {}", args(&[dest, a, b])),
+        Instruction::Mul(dest, a, b) => format!("mul {}", args(&[dest, a, b])),
+        Instruction::Div(quotient, remainder, value, divisor) => {
+            format!("div {}", args(&[quotient, remainder, value, divisor]))
+        }
+        Instruction::Xor(dest, a, b) => format!("xor {}", args(&[dest, a, b])),
+        Instruction::And(dest, a, b) => format!("and {}", args(&[dest, a, b])),
+        Instruction::Or(dest, a, b) => format!("or {}", args(&[dest, a, b])),
+        Instruction::Not(dest, src) => format!("not {}", args(&[dest, src])),
+        Instruction::Cmp(a, b) => format!("cmp {}", args(&[a, b])),
+        Instruction::Jmp(label) => format!("jmp {}", label),
+        Instruction::Je(label) => format!("je {}", label),
+        Instruction::Jne(label) => format!("jne {}", label),
+        Instruction::Jg(label) => format!("jg {}", label),
+        Instruction::Jge(label) => format!("jge {}", label),
+        Instruction::Jl(label) => format!("jl {}", label),
+        Instruction::Jle(label) => format!("jle {}", label),
+        Instruction::Sete(dest) => format!("sete {}", args(&[dest])),
+        Instruction::Ret => "ret".to_string(),
+        Instruction::Push(value) => format!("push {}", args(&[value])),
+        Instruction::Pop(dest) => format!("pop {}", args(&[dest])),
+        Instruction::Label(name, None) => format!("label {}", name),
+        Instruction::Label(name, Some(stack_args)) => format!("label {} {}", name, stack_args),
+        Instruction::ExternCall(name) => format!("extern {}", name),
+        Instruction::Call(name) => format!("call {}", name),
+        Instruction::Nop => "nop".to_string(),
+        Instruction::ProgramStart => "start".to_string(),
+        Instruction::PreCallStackAlign(id) => format!("precall {}", id),
+        Instruction::PostCallStackAlign(id) => format!("postcall {}", id),
+        Instruction::Comment(text) => format!("comment {}", string_to_text(text)),
+    }
+}
+
+fn argument_to_text(arg: &Argument) -> String {
+    match arg {
+        Argument::Register(register) => register.to_string(),
+        Argument::Immediate(value) => value.to_string(),
+        Argument::Label(name) => format!("@{}", name),
+        Argument::MemoryAddressDirect(address) => format!("[{}]", address),
+        Argument::MemoryAddressRegister(register) => format!("[{}]", register),
+        Argument::StackMemoryOffsetDirect(offset) => format!("[rsp + {}]", offset),
+        Argument::StackMemoryOffsetRegister(register) => format!("[rsp + {}]", register),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Variable(String),
+    Number(i64),
+    Text(String),
+    Symbol(&'static str),
+}
+
+// Longer symbols first, so <= is not read as < followed by =
+const SYMBOLS: [&str; 20] = [
+    "==", "!=", "<=", ">=", "<", ">", "=", "+", "-", "*", "/", "(", ")", "[", "]", "{", "}", ",",
+    ":", "@",
+];
+
+struct LineReader {
+    tokens: Vec<(Token, Span)>,
+    position: usize,
+    line_span: Span,
+}
+
+impl LineReader {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next_span(&self) -> Span {
+        match self.tokens.get(self.position) {
+            Some((_, span)) => *span,
+            None => Span {
+                col_start: self.line_span.col_end,
+                byte_start: self.line_span.byte_end,
+                ..self.line_span
+            },
+        }
+    }
+
+    fn error(&self, message: String) -> Error {
+        Error::located(message, self.next_span())
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        token
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+
+    fn at(&self, offset: usize, expected: &Token) -> bool {
+        self.tokens
+            .get(self.position + offset)
+            .is_some_and(|(token, _)| token == expected)
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), Error> {
+        match self.eat_symbol(symbol) {
+            true => Ok(()),
+            false => Err(self.error(format!("Expected {}", symbol))),
+        }
+    }
+
+    fn eat_name(&mut self, name: &str) -> bool {
+        if self.peek() == Some(&Token::Name(name.to_string())) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_name(&mut self, name: &str) -> Result<(), Error> {
+        match self.eat_name(name) {
+            true => Ok(()),
+            false => Err(self.error(format!("Expected {}", name))),
+        }
+    }
+
+    fn read_name(&mut self) -> Result<String, Error> {
+        match self.peek() {
+            Some(Token::Name(name)) => {
+                let name = name.clone();
+                self.position += 1;
+                Ok(name)
+            }
+            _ => Err(self.error("Expected a name".to_string())),
+        }
+    }
+
+    fn read_number(&mut self) -> Result<i64, Error> {
+        match self.peek() {
+            Some(Token::Number(number)) => {
+                let number = *number;
+                self.position += 1;
+                Ok(number)
+            }
+            _ => Err(self.error("Expected a number".to_string())),
+        }
+    }
+
+    fn read_unsigned<N: TryFrom<i64>>(&mut self) -> Result<N, Error> {
+        let span = self.next_span();
+        let number = self.read_number()?;
+        N::try_from(number)
+            .map_err(|_| Error::located(format!("{} can not be negative here", number), span))
+    }
+
+    fn expect_end(&self) -> Result<(), Error> {
+        match self.is_at_end() {
+            true => Ok(()),
+            false => Err(self.error("Unexpected text after the instruction".to_string())),
+        }
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+// The span of the characters start..end of the line, which starts at byte line_start of the text
+fn char_span(line: &str, line_start: usize, row: usize, start: usize, end: usize) -> Span {
+    let byte_index = |column: usize| line.char_indices().nth(column).map_or(line.len(), |(index, _)| index);
+    Span {
+        row,
+        col_start: start,
+        row_end: row,
+        col_end: end,
+        byte_start: line_start + byte_index(start),
+        byte_end: line_start + byte_index(end),
+    }
+}
+
+fn tokenize_line(line: &str, line_start: usize, row: usize) -> Result<Vec<(Token, Span)>, Error> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let span = |start: usize, end: usize| char_span(line, line_start, row, start, end);
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c == ';' {
+            break;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c == '"' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => {
+                        return Err(Error::located(
+                            "The string is never closed".to_string(),
+                            span(start, chars.len()),
+                        ))
+                    }
+                    Some('"') => break,
+                    Some('\\') => {
+                        let escaped = match chars.get(i + 1) {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some('r') => '\r',
+                            Some('"') => '"',
+                            Some('\\') => '\\',
+                            _ => {
+                                return Err(Error::located(
+                                    "Unknown escape in the string".to_string(),
+                                    span(i, i + 2),
+                                ))
+                            }
+                        };
+                        text.push(escaped);
+                        i += 2;
+                    }
+                    Some(c) => {
+                        text.push(*c);
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+            tokens.push((Token::Text(text), span(start, i)));
+            continue;
+        }
+
+        // A minus directly before a digit is the sign of a number, unless it follows a value
+        // and so subtracts from it, as in %a - 1
+        let follows_value = matches!(
+            tokens.last(),
+            Some((Token::Variable(_) | Token::Number(_) | Token::Text(_), _))
+                | Some((Token::Symbol(")" | "]" | "}"), _))
+        );
+        let negative = c == '-'
+            && !follows_value
+            && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit());
+        if c.is_ascii_digit() || negative {
+            i += 1;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = text.parse::<i64>().map_err(|_| {
+                Error::located(format!("{} is not a valid number", text), span(start, i))
+            })?;
+            tokens.push((Token::Number(number), span(start, i)));
+            continue;
+        }
+
+        if c == '%' || is_name_char(c) {
+            i += 1;
+            while i < chars.len() && is_name_char(chars[i]) {
+                i += 1;
+            }
+            let name: String = chars[start + 1..i].iter().collect();
+            let token = match c {
+                '%' if name.is_empty() => {
+                    return Err(Error::located(
+                        "Expected a variable name after %".to_string(),
+                        span(start, i),
+                    ))
+                }
+                '%' => Token::Variable(name),
+                _ => Token::Name(chars[start..i].iter().collect()),
+            };
+            tokens.push((token, span(start, i)));
+            continue;
+        }
+
+        let rest: String = chars[i..].iter().collect();
+        match SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            Some(symbol) => {
+                i += symbol.chars().count();
+                tokens.push((Token::Symbol(symbol), span(start, i)));
+            }
+            None => {
+                return Err(Error::located(
+                    format!("Unexpected character {}", c),
+                    span(start, start + 1),
+                ))
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// Parses the text of a .rir file back into instructions
+pub fn parse_instructions(text: &str) -> Result<Vec<TacInstruction>, Error> {
+    let mut instructions = Vec::new();
+    let mut spans = Vec::new();
+    let mut line_start = 0;
+    for (row, raw_line) in text.split('\n').enumerate() {
+        let line = raw_line.strip_suffix('\r').unwrap_or(raw_line);
+        let start = line_start;
+        line_start += raw_line.len() + 1;
+        let tokens = tokenize_line(line, start, row)?;
+        if tokens.is_empty() {
+            continue;
+        }
+        let mut reader = LineReader {
+            tokens,
+            position: 0,
+            // The line without its indentation
+            line_span: char_span(line, start, row, line.chars().take_while(|c| c.is_whitespace()).count(), line.chars().count()),
+        };
+        let instruction = read_instruction(&mut reader)?;
+        reader.expect_end()?;
+        instructions.push(instruction);
+        spans.push(reader.line_span);
+    }
+    // Jumps and calls must go to a label in the same file, the backend can not resolve them otherwise
+    if let Err((index, message)) = verifier::check_tac(&instructions) {
+        return Err(Error::located(message, spans[index]));
+    }
+    Ok(instructions)
+}
+
+fn read_instruction(reader: &mut LineReader) -> Result<TacInstruction, Error> {
+    // A label definition: name:
+    if let (Some(Token::Name(name)), true) =
+        (reader.peek().cloned(), reader.at(1, &Token::Symbol(":")))
+    {
+        reader.position += 2;
+        return Ok(TacInstruction::Label(name));
+    }
+
+    if let Some(Token::Variable(_)) = reader.peek() {
+        return read_assignment(reader);
+    }
+
+    let keyword = reader.read_name()?;
+    match keyword.as_str() {
+        "start" => Ok(TacInstruction::ProgramStart()),
+        "fun" => {
+            let name = reader.read_name()?;
+            reader.expect_symbol("(")?;
+            let mut params = Vec::new();
+            while !reader.eat_symbol(")") {
+                if !params.is_empty() {
+                    reader.expect_symbol(",")?;
+                }
+                params.push(reader.read_name()?);
+            }
+            Ok(TacInstruction::FunctionLabel(name, params))
+        }
+        "goto" => Ok(TacInstruction::Goto(reader.read_name()?)),
+        "if" => {
+            let left = read_value(reader)?;
+            let comparison = read_comparison(reader)?;
+            let right = read_value(reader)?;
+            reader.expect_name("goto")?;
+            let label = reader.read_name()?;
+            Ok(TacInstruction::CompareAndGoto(left, right, comparison, label))
+        }
+        "call" => {
+            let (name, args) = read_call(reader)?;
+            Ok(TacInstruction::Call(name, args, None))
+        }
+        "extern" => {
+            let (name, args) = read_call(reader)?;
+            Ok(TacInstruction::ExternCall(name, args, None))
+        }
+        "return" if reader.is_at_end() => Ok(TacInstruction::Return(None)),
+        "return" => Ok(TacInstruction::Return(Some(read_value(reader)?))),
+        "push" => Ok(TacInstruction::Push(read_value(reader)?)),
+        "pop" => Ok(TacInstruction::Pop(read_variable(reader)?)),
+        "asm" => Ok(TacInstruction::DirectInstruction(read_direct_instruction(
+            reader,
+        )?)),
+        _ => {
+            reader.position -= 1;
+            Err(reader.error(format!("Unknown instruction {}", keyword)))
+        }
+    }
+}
+
+// Every instruction that starts with a variable writes to it
+fn read_assignment(reader: &mut LineReader) -> Result<TacInstruction, Error> {
+    let var = read_variable(reader)?;
+    reader.expect_symbol("=")?;
+
+    // Values never start with a bare name, so a name here is always a keyword
+    if let Some(Token::Name(name)) = reader.peek().cloned() {
+        match name.as_str() {
+            "rsp" => {
+                reader.position += 1;
+                return Ok(TacInstruction::MovRSPTo(var));
+            }
+            "call" | "extern" => {
+                reader.position += 1;
+                let (function, args) = read_call(reader)?;
+                return Ok(match name.as_str() {
+                    "call" => TacInstruction::Call(function, args, Some(var)),
+                    _ => TacInstruction::ExternCall(function, args, Some(var)),
+                });
+            }
+            "neg" | "not" => {
+                reader.position += 1;
+                let op = match name.as_str() {
+                    "neg" => UnOp::Neg,
+                    _ => UnOp::Not,
+                };
+                return Ok(TacInstruction::UnaryOp(var, op, read_value(reader)?));
+            }
+            "list" => {
+                reader.position += 1;
+                let elements = read_values(reader, "[", "]")?;
+                return Ok(TacInstruction::InstantiateList(var, elements));
+            }
+            "struct" => {
+                reader.position += 1;
+                let fields = read_values(reader, "(", ")")?;
+                return Ok(TacInstruction::InstantiateStruct(var, fields));
+            }
+            _ => {}
+        }
+    }
+
+    let left = read_value(reader)?;
+    if reader.is_at_end() {
+        return Ok(TacInstruction::Assign(var, left));
+    }
+    let op = read_binop(reader)?;
+    let right = read_value(reader)?;
+    Ok(TacInstruction::BinOp(var, left, op, right))
+}
+
+fn read_call(reader: &mut LineReader) -> Result<(String, Vec<TacValue>), Error> {
+    let name = reader.read_name()?;
+    let args = read_values(reader, "(", ")")?;
+    Ok((name, args))
+}
+
+fn read_values(reader: &mut LineReader, open: &str, close: &str) -> Result<Vec<TacValue>, Error> {
+    reader.expect_symbol(open)?;
+    let mut values = Vec::new();
+    while !reader.eat_symbol(close) {
+        if !values.is_empty() {
+            reader.expect_symbol(",")?;
+        }
+        values.push(read_value(reader)?);
+    }
+    Ok(values)
+}
+
+fn read_variable(reader: &mut LineReader) -> Result<VariableValue, Error> {
+    let name = match reader.next() {
+        Some(Token::Variable(name)) => name,
+        _ => {
+            reader.position -= 1;
+            return Err(reader.error("Expected a variable, like %x".to_string()));
+        }
+    };
+    if reader.eat_symbol("@") {
+        let index = reader.read_unsigned::<isize>()?;
+        return Ok(VariableValue::VariableWithRequestedRegister(name, index));
+    }
+    Ok(VariableValue::Variable(name))
+}
+
+fn read_value(reader: &mut LineReader) -> Result<TacValue, Error> {
+    match reader.peek().cloned() {
+        Some(Token::Number(number)) => {
+            reader.position += 1;
+            Ok(TacValue::Constant(number))
+        }
+        Some(Token::Text(text)) => {
+            reader.position += 1;
+            Ok(TacValue::StringLiteral(text))
+        }
+        Some(Token::Variable(name)) => {
+            reader.position += 1;
+            if reader.eat_symbol("[") {
+                let index = read_value(reader)?;
+                reader.expect_symbol("]")?;
+                return Ok(TacValue::ListAccess {
+                    list_variable: name,
+                    index: Box::new(index),
+                });
+            }
+            Ok(TacValue::Variable(name))
+        }
+        Some(Token::Symbol("{")) => {
+            reader.position += 1;
+            let mut fields = Vec::new();
+            while !reader.eat_symbol("}") {
+                if !fields.is_empty() {
+                    reader.expect_symbol(",")?;
+                }
+                let name = reader.read_name()?;
+                reader.expect_symbol(":")?;
+                fields.push((name, read_value(reader)?));
+            }
+            Ok(TacValue::Struct(fields))
+        }
+        _ => Err(reader.error("Expected a value".to_string())),
+    }
+}
+
+fn read_binop(reader: &mut LineReader) -> Result<BinOp, Error> {
+    let op = match reader.peek() {
+        Some(Token::Symbol("+")) => BinOp::Add,
+        Some(Token::Symbol("-")) => BinOp::Sub,
+        Some(Token::Symbol("*")) => BinOp::Mul,
+        Some(Token::Symbol("/")) => BinOp::Div,
+        Some(Token::Symbol("==")) => BinOp::Eq,
+        Some(Token::Symbol("!=")) => BinOp::Ne,
+        Some(Token::Symbol("<")) => BinOp::Lt,
+        Some(Token::Symbol("<=")) => BinOp::Le,
+        Some(Token::Symbol(">")) => BinOp::Gt,
+        Some(Token::Symbol(">=")) => BinOp::Ge,
+        Some(Token::Name(name)) if name == "and" => BinOp::And,
+        Some(Token::Name(name)) if name == "or" => BinOp::Or,
+        _ => return Err(reader.error("Expected an operator".to_string())),
+    };
+    reader.position += 1;
+    Ok(op)
+}
+
+fn read_comparison(reader: &mut LineReader) -> Result<ComparisonOp, Error> {
+    let comparison = match reader.peek() {
+        Some(Token::Symbol("==")) => ComparisonOp::Eq,
+        Some(Token::Symbol("!=")) => ComparisonOp::Ne,
+        Some(Token::Symbol("<")) => ComparisonOp::Lt,
+        Some(Token::Symbol("<=")) => ComparisonOp::Le,
+        Some(Token::Symbol(">")) => ComparisonOp::Gt,
+        Some(Token::Symbol(">=")) => ComparisonOp::Ge,
+        _ => return Err(reader.error("Expected a comparison".to_string())),
+    };
+    reader.position += 1;
+    Ok(comparison)
+}
+
+fn read_direct_instruction(reader: &mut LineReader) -> Result<Instruction, Error> {
+    let name = reader.read_name()?;
+    let instruction = match name.as_str() {
+        "mov" => {
+            let [dest, src] = read_arguments(reader)?;
+            Instruction::Mov(dest, src)
+        }
+        "add" | "sub" | "mul" | "xor" | "and" | "or" => {
+            let [dest, a, b] = read_arguments(reader)?;
+            match name.as_str() {
+                "add" => Instruction::Add(dest, a, b),
+                "sub" => Instruction::Sub(dest, a, b),
+                "mul" => Instruction::Mul(dest, a, b),
+                "xor" => Instruction::Xor(dest, a, b),
+                "and" => Instruction::And(dest, a, b),
+                _ => Instruction::Or(dest, a, b),
+            }
+        }
+        "div" => {
+            let [quotient, remainder, value, divisor] = read_arguments(reader)?;
+            Instruction::Div(quotient, remainder, value, divisor)
+        }
+        "not" => {
+            let [dest, src] = read_arguments(reader)?;
+            Instruction::Not(dest, src)
+        }
+        "cmp" => {
+            let [a, b] = read_arguments(reader)?;
+            Instruction::Cmp(a, b)
+        }
+        "jmp" => Instruction::Jmp(reader.read_name()?),
+        "je" => Instruction::Je(reader.read_name()?),
+        "jne" => Instruction::Jne(reader.read_name()?),
+        "jg" => Instruction::Jg(reader.read_name()?),
+        "jge" => Instruction::Jge(reader.read_name()?),
+        "jl" => Instruction::Jl(reader.read_name()?),
+        "jle" => Instruction::Jle(reader.read_name()?),
+        "sete" => {
+            let [dest] = read_arguments(reader)?;
+            Instruction::Sete(dest)
+        }
+        "ret" => Instruction::Ret,
+        "push" => {
+            let [value] = read_arguments(reader)?;
+            Instruction::Push(value)
+        }
+        "pop" => {
+            let [dest] = read_arguments(reader)?;
+            Instruction::Pop(dest)
+        }
+        "label" => {
+            let label = reader.read_name()?;
+            let stack_args = match reader.is_at_end() {
+                true => None,
+                false => Some(reader.read_unsigned::<usize>()?),
+            };
+            Instruction::Label(label, stack_args)
+        }
+        "extern" => Instruction::ExternCall(reader.read_name()?),
+        "call" => Instruction::Call(reader.read_name()?),
+        "nop" => Instruction::Nop,
+        "start" => Instruction::ProgramStart,
+        "precall" => Instruction::PreCallStackAlign(reader.read_unsigned::<usize>()?),
+        "postcall" => Instruction::PostCallStackAlign(reader.read_unsigned::<usize>()?),
+        "comment" => match reader.next() {
+            Some(Token::Text(text)) => Instruction::Comment(text),
+            _ => {
+                reader.position -= 1;
+                return Err(reader.error("Expected a string".to_string()));
+            }
+        },
+        _ => {
+            reader.position -= 1;
+            return Err(reader.error(format!("Unknown assembly instruction {}", name)));
+        }
+    };
+    Ok(instruction)
+}
+
+fn read_arguments<const N: usize>(reader: &mut LineReader) -> Result<[Argument; N], Error> {
+    let mut args = Vec::new();
+    for index in 0..N {
+        if index > 0 {
+            reader.expect_symbol(",")?;
+        }
+        args.push(read_argument(reader)?);
+    }
+    Ok(args
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("exactly N arguments are read")))
+}
+
+fn read_argument(reader: &mut LineReader) -> Result<Argument, Error> {
+    if reader.eat_symbol("@") {
+        return Ok(Argument::Label(reader.read_name()?));
+    }
+    if let Some(Token::Number(number)) = reader.peek() {
+        let number = *number;
+        reader.position += 1;
+        return Ok(Argument::Immediate(number));
+    }
+    if !reader.eat_symbol("[") {
+        return Ok(Argument::Register(read_register(reader)?));
+    }
+
+    let argument = if let Some(Token::Number(_)) = reader.peek() {
+        Argument::MemoryAddressDirect(reader.read_unsigned::<u64>()?)
+    } else {
+        let register = read_register(reader)?;
+        let is_rsp = register == Register::General(RegisterType::RSP, RegisterSize::QuadWord);
+        if is_rsp && reader.eat_symbol("+") {
+            match reader.peek() {
+                Some(Token::Number(_)) => {
+                    Argument::StackMemoryOffsetDirect(reader.read_unsigned::<u64>()?)
+                }
+                _ => Argument::StackMemoryOffsetRegister(read_register(reader)?),
+            }
+        } else {
+            Argument::MemoryAddressRegister(register)
+        }
+    };
+    reader.expect_symbol("]")?;
+    Ok(argument)
+}
+
+fn all_registers() -> Vec<Register> {
+    let sizes = [
+        RegisterSize::Byte,
+        RegisterSize::Word,
+        RegisterSize::DoubleWord,
+        RegisterSize::QuadWord,
+    ];
+    let types = [
+        RegisterType::RAX,
+        RegisterType::RBX,
+        RegisterType::RCX,
+        RegisterType::RDX,
+        RegisterType::RSI,
+        RegisterType::RDI,
+        RegisterType::RSP,
+        RegisterType::RBP,
+    ];
+    let mut registers = Vec::new();
+    for size in sizes.iter() {
+        for register_type in types.iter() {
+            registers.push(Register::General(register_type.clone(), size.clone()));
+        }
+        for number in 8..16 {
+            registers.push(Register::Extended(number, size.clone()));
+        }
+    }
+    registers
+}
+
+fn read_register(reader: &mut LineReader) -> Result<Register, Error> {
+    let span = reader.next_span();
+    let name = reader.read_name()?;
+    all_registers()
+        .into_iter()
+        .find(|register| register.to_string() == name)
+        .ok_or_else(|| Error::located(format!("{} is not a register", name), span))
+}
@@ -0,0 +1,125 @@
+use crate::engine::Engine;
+use crate::interpreter::ProgramState;
+use crate::interpreter::Value;
+use crate::parser::BaseExprData;
+use crate::tokenizer::Error;
+
+#[derive(Clone, Default)]
+pub struct TestOptions {
+    // Only runs the tests whose name contains this text
+    pub filter: Option<String>,
+    // Stops at the first failing test
+    pub fail_fast: bool,
+}
+
+// The result of running a single test function, or a single case of a parameterized test
+#[derive(Debug)]
+pub struct TestOutcome {
+    // Cases of a parameterized test are named after the test and the index of the case, such as test_add[1]
+    pub name: String,
+    // The error the test stopped with, such as a failed assert_eq
+    pub error: Option<Error>,
+}
+
+impl TestOutcome {
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+// Test functions are the top level functions whose name starts with test_
+fn is_test_name(name: &str) -> bool {
+    name.starts_with("test_")
+}
+
+// A test function with parameters is run once for every case in the global list named
+// after the test with _cases appended, each case being the list of arguments for one run
+fn cases_name(test_name: &str) -> String {
+    format!("{}_cases", test_name)
+}
+
+// Runs the top level statements of the program, then calls every test function,
+// in the order they are defined. Tests share the globals of the program, but not each other's locals.
+pub fn run_tests(
+    engine: &Engine,
+    lines: Vec<&str>,
+    options: &TestOptions,
+) -> Result<Vec<TestOutcome>, Error> {
+    let base_expressions = engine.parse(lines)?;
+
+    let tests: Vec<(String, usize)> = base_expressions
+        .iter()
+        .filter_map(|base_expr| match &base_expr.data {
+            BaseExprData::FunctionDefinition { fun_name, args, .. } if is_test_name(fun_name) => {
+                Some((fun_name.clone(), args.len()))
+            }
+            _ => None,
+        })
+        .filter(|(name, _)| match &options.filter {
+            Some(filter) => name.contains(filter.as_str()),
+            None => true,
+        })
+        .collect();
+
+    let mut program = ProgramState::new(base_expressions, engine.builtins(), &engine.options);
+    while !program.step(usize::MAX)? {}
+
+    let mut outcomes = Vec::new();
+    for (name, parameter_count) in tests {
+        let test_outcomes = match parameter_count {
+            0 => vec![TestOutcome {
+                error: program.call(&name, Vec::new()).err(),
+                name,
+            }],
+            _ => run_test_cases(&mut program, name, options.fail_fast),
+        };
+
+        for outcome in test_outcomes {
+            let failed = !outcome.passed();
+            outcomes.push(outcome);
+            if failed && options.fail_fast {
+                return Ok(outcomes);
+            }
+        }
+    }
+
+    Ok(outcomes)
+}
+
+fn run_test_cases(program: &mut ProgramState, name: String, fail_fast: bool) -> Vec<TestOutcome> {
+    let cases = match program.global(&cases_name(&name)) {
+        Some(Value::List(cases)) => cases,
+        _ => {
+            return vec![TestOutcome {
+                error: Some(Error::SimpleError {
+                    message: format!(
+                        "Test {} has parameters, but there is no list {} to run it with",
+                        name,
+                        cases_name(&name)
+                    ),
+                }),
+                name,
+            }]
+        }
+    };
+
+    let mut outcomes = Vec::new();
+    for (i, case) in cases.into_iter().enumerate() {
+        let error = match case {
+            Value::List(arguments) => program.call(&name, arguments).err(),
+            _ => Some(Error::SimpleError {
+                message: format!("Case {} of {} is not a list of arguments", i, name),
+            }),
+        };
+        let failed = error.is_some();
+        outcomes.push(TestOutcome {
+            name: format!("{}[{}]", name, i),
+            error,
+        });
+        if failed && fail_fast {
+            break;
+        }
+    }
+
+    outcomes
+}
@@ -1,12 +1,11 @@
+use crate::language::LanguageOptions;
 use crate::typechecker::Type;
 
 #[derive(PartialEq, Debug)]
 pub enum Error {
     LocationError {
         message: String,
-        row: usize,
-        col_start: usize,
-        col_end: usize,
+        span: Span,
     },
     SimpleError {
         message: String,
@@ -15,12 +14,193 @@ pub enum Error {
         message: String,
         expected: Type,
         found: Type,
-        row: usize,
-        col_start: usize,
-        col_end: usize,
+        span: Span,
+    },
+    // A bracket without a matching partner, pointing at the opening bracket
+    // and at the place where its closing bracket was expected
+    DelimiterError {
+        message: String,
+        opened: Span,
+        expected: Span,
     },
 }
 
+impl Error {
+    // An error pointing at the given place in the source
+    pub fn located(message: String, span: Span) -> Error {
+        Error::LocationError { message, span }
+    }
+
+    // A type error pointing at the given place in the source
+    pub fn type_error(message: String, expected: Type, found: Type, span: Span) -> Error {
+        Error::TypeError {
+            message,
+            expected,
+            found,
+            span,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Error::SimpleError { message }
+            | Error::LocationError { message, .. }
+            | Error::TypeError { message, .. }
+            | Error::DelimiterError { message, .. } => message,
+        }
+    }
+
+    // The place in the source the error points at, if any
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Error::LocationError { span, .. } | Error::TypeError { span, .. } => Some(*span),
+            Error::DelimiterError { opened, .. } => Some(*opened),
+            Error::SimpleError { .. } => None,
+        }
+    }
+}
+
+// A place in the source, as stored on tokens, expressions and errors.
+// Rows and columns start at 0, col_start is inclusive and col_end is exclusive.
+// The span starts on row and ends on row_end, which is a later row for an expression over several rows.
+// Columns count characters after tabs are expanded to four spaces. The byte offsets count bytes in the
+// source the lines were split from (joined with \n), as editors address it.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct Span {
+    pub row: usize,
+    pub col_start: usize,
+    pub row_end: usize,
+    pub col_end: usize,
+    pub byte_start: usize,
+    pub byte_end: usize,
+}
+
+impl Span {
+    // A span on a single row, whose line starts at byte line_start of the source.
+    // A column after the end of the line is a byte for each column further, like the column after() a span.
+    pub fn on_line(line: &str, line_start: usize, row: usize, col_start: usize, col_end: usize) -> Span {
+        let width: usize = line
+            .chars()
+            .map(|c| match c {
+                '\t' => 4,
+                '\r' => 0,
+                _ => 1,
+            })
+            .sum();
+        let byte = |column: usize| line_start + column_to_byte_index(line, column) + column.saturating_sub(width);
+        Span {
+            row,
+            col_start,
+            row_end: row,
+            col_end,
+            byte_start: byte(col_start),
+            byte_end: byte(col_end),
+        }
+    }
+
+    // A span on a single row of the lines, a row after the last line is at the end of the source
+    pub fn in_lines(lines: &[&str], row: usize, col_start: usize, col_end: usize) -> Span {
+        let line_start = lines.iter().take(row).map(|line| line.len() + 1).sum();
+        Span::on_line(lines.get(row).copied().unwrap_or(""), line_start, row, col_start, col_end)
+    }
+
+    // The span from the start of the first span up to the end of the last one,
+    // which ends on a later row when the spans are on different rows
+    pub fn merge(&self, other: &Span) -> Span {
+        let start = match (self.row, self.col_start) <= (other.row, other.col_start) {
+            true => self,
+            false => other,
+        };
+        let end = match (self.row_end, self.col_end) >= (other.row_end, other.col_end) {
+            true => self,
+            false => other,
+        };
+        Span {
+            row: start.row,
+            col_start: start.col_start,
+            row_end: end.row_end,
+            col_end: end.col_end,
+            byte_start: start.byte_start,
+            byte_end: end.byte_end,
+        }
+    }
+
+    // The column right after the span, where something missing from the source was expected
+    pub fn after(&self) -> Span {
+        Span {
+            row: self.row_end,
+            col_start: self.col_end,
+            row_end: self.row_end,
+            col_end: self.col_end + 1,
+            byte_start: self.byte_end,
+            byte_end: self.byte_end + 1,
+        }
+    }
+
+    // The last column of the span, which must hold a single byte character such as a closing bracket
+    pub fn last_column(&self) -> Span {
+        let col_start = match self.is_single_row() {
+            true => self.col_end.saturating_sub(1).max(self.col_start),
+            false => self.col_end.saturating_sub(1),
+        };
+        Span {
+            row: self.row_end,
+            col_start,
+            row_end: self.row_end,
+            col_end: self.col_end,
+            byte_start: self.byte_end.saturating_sub(1).max(self.byte_start),
+            byte_end: self.byte_end,
+        }
+    }
+
+    // The first columns of the span, which must hold single byte characters such as a keyword
+    pub fn first_columns(&self, count: usize) -> Span {
+        Span {
+            row: self.row,
+            col_start: self.col_start,
+            row_end: self.row,
+            col_end: self.col_start + count,
+            byte_start: self.byte_start,
+            byte_end: self.byte_start + count,
+        }
+    }
+
+    pub fn is_single_row(&self) -> bool {
+        self.row == self.row_end
+    }
+
+    pub fn covers(&self, row: usize, col: usize) -> bool {
+        (self.row, self.col_start) <= (row, col) && (row, col) < (self.row_end, self.col_end)
+    }
+
+    // The byte range of the span in the source the lines were split from (joined with \n)
+    pub fn byte_range(&self) -> std::ops::Range<usize> {
+        self.byte_start..self.byte_end.max(self.byte_start)
+    }
+
+    // The length of the span in bytes of the source
+    pub fn byte_len(&self) -> usize {
+        self.byte_range().len()
+    }
+}
+
+// The tokenizer expands tabs to four spaces before assigning columns,
+// this maps such a column back to a byte index in the original line
+pub fn column_to_byte_index(line: &str, column: usize) -> usize {
+    let mut current_column = 0;
+    for (byte_index, c) in line.char_indices() {
+        if current_column >= column {
+            return byte_index;
+        }
+        current_column += match c {
+            '\t' => 4,
+            '\r' => 0,
+            _ => 1,
+        };
+    }
+    line.len()
+}
+
 #[derive(PartialEq)]
 enum CharType {
     Space,
@@ -45,6 +225,7 @@ pub enum SymbolType {
     Minus,
     Star,
     Slash,
+    SlashSlash,
     Hat,
     Dot,
     Comma,
@@ -52,6 +233,9 @@ pub enum SymbolType {
     ParenthesisClosed,
     SquareBracketOpen,
     SquareBracketClosed,
+    CurlyBracketOpen,
+    CurlyBracketClosed,
+    Colon,
     EqualsEquals,
     NotEquals,
     GreaterThan,
@@ -62,6 +246,7 @@ pub enum SymbolType {
     And,
     Not,
     For,
+    While,
     In,
     If,
     Else,
@@ -69,18 +254,31 @@ pub enum SymbolType {
     QuotationMark,
     Return,
     Break,
+    Continue,
     PlusEquals,
     True,
     False,
     Struct,
+    Yield,
+    Del,
+    Swap,
+    Defer,
+    With,
+    As,
+    Match,
+    Case,
 }
 
 #[derive(PartialEq, Clone, Debug)]
 pub struct Token {
     pub data: TokenData,
-    pub row: usize,
-    pub col_start: usize,
-    pub col_end: usize,
+    pub span: Span,
+}
+
+impl Token {
+    pub fn span(&self) -> Span {
+        self.span
+    }
 }
 
 #[derive(PartialEq, Clone, Debug)]
@@ -88,6 +286,7 @@ pub enum TokenData {
     Variable { name: String },
     Symbol { symbol_type: SymbolType },
     Number { number: i64 },
+    Float { value: f64 },
     String { value: String },
 }
 
@@ -97,10 +296,15 @@ pub struct TokenLine {
     pub indentation: usize,
 }
 
-static RESERVED_SYMBOLS: [char; 16] = [
-    '=', '+', '-', '*', '/', '^', '.', ',', '(', ')', '"', '<', '>', '!', '[', ']',
+static RESERVED_SYMBOLS: [char; 19] = [
+    '=', '+', '-', '*', '/', '^', '.', ',', '(', ')', '"', '<', '>', '!', '[', ']', '{', '}', ':',
 ];
 static BINARY_OPERATORS: [&str; 9] = ["+", "-", "*", "/", "^", ".", "==", "or", "and"];
+// The words which are symbols rather than variables, see get_symbol_type
+pub static KEYWORDS: [&str; 23] = [
+    "or", "and", "not", "for", "while", "in", "if", "else", "fun", "return", "break", "continue", "true",
+    "false", "struct", "yield", "del", "swap", "defer", "with", "as", "match", "case",
+];
 
 fn get_symbol_type(symbol: &String) -> Result<SymbolType, Error> {
     match symbol {
@@ -109,6 +313,7 @@ fn get_symbol_type(symbol: &String) -> Result<SymbolType, Error> {
         s if s == "+" => Ok(SymbolType::Plus),
         s if s == "*" => Ok(SymbolType::Star),
         s if s == "/" => Ok(SymbolType::Slash),
+        s if s == "//" => Ok(SymbolType::SlashSlash),
         s if s == "^" => Ok(SymbolType::Hat),
         s if s == "." => Ok(SymbolType::Dot),
         s if s == "," => Ok(SymbolType::Comma),
@@ -116,6 +321,9 @@ fn get_symbol_type(symbol: &String) -> Result<SymbolType, Error> {
         s if s == ")" => Ok(SymbolType::ParenthesisClosed),
         s if s == "[" => Ok(SymbolType::SquareBracketOpen),
         s if s == "]" => Ok(SymbolType::SquareBracketClosed),
+        s if s == "{" => Ok(SymbolType::CurlyBracketOpen),
+        s if s == "}" => Ok(SymbolType::CurlyBracketClosed),
+        s if s == ":" => Ok(SymbolType::Colon),
         s if s == "==" => Ok(SymbolType::EqualsEquals),
         s if s == "!=" => Ok(SymbolType::NotEquals),
         s if s == ">" => Ok(SymbolType::GreaterThan),
@@ -126,6 +334,7 @@ fn get_symbol_type(symbol: &String) -> Result<SymbolType, Error> {
         s if s == "and" => Ok(SymbolType::And),
         s if s == "not" => Ok(SymbolType::Not),
         s if s == "for" => Ok(SymbolType::For),
+        s if s == "while" => Ok(SymbolType::While),
         s if s == "in" => Ok(SymbolType::In),
         s if s == "if" => Ok(SymbolType::If),
         s if s == "else" => Ok(SymbolType::Else),
@@ -133,10 +342,19 @@ fn get_symbol_type(symbol: &String) -> Result<SymbolType, Error> {
         s if s == "\"" => Ok(SymbolType::QuotationMark),
         s if s == "return" => Ok(SymbolType::Return),
         s if s == "break" => Ok(SymbolType::Break),
+        s if s == "continue" => Ok(SymbolType::Continue),
         s if s == "+=" => Ok(SymbolType::PlusEquals),
         s if s == "true" => Ok(SymbolType::True),
         s if s == "false" => Ok(SymbolType::False),
         s if s == "struct" => Ok(SymbolType::Struct),
+        s if s == "yield" => Ok(SymbolType::Yield),
+        s if s == "del" => Ok(SymbolType::Del),
+        s if s == "swap" => Ok(SymbolType::Swap),
+        s if s == "defer" => Ok(SymbolType::Defer),
+        s if s == "with" => Ok(SymbolType::With),
+        s if s == "as" => Ok(SymbolType::As),
+        s if s == "match" => Ok(SymbolType::Match),
+        s if s == "case" => Ok(SymbolType::Case),
         _ => Err(Error::SimpleError {
             message: format!("{} is not a Symbol", symbol),
         }),
@@ -150,6 +368,7 @@ pub fn get_symbol_from_type(symbol_type: &SymbolType) -> String {
         SymbolType::Plus => String::from("+"),
         SymbolType::Star => String::from("*"),
         SymbolType::Slash => String::from("/"),
+        SymbolType::SlashSlash => String::from("//"),
         SymbolType::Hat => String::from("^"),
         SymbolType::Dot => String::from("."),
         SymbolType::Comma => String::from(","),
@@ -157,6 +376,9 @@ pub fn get_symbol_from_type(symbol_type: &SymbolType) -> String {
         SymbolType::ParenthesisClosed => String::from(")"),
         SymbolType::SquareBracketOpen => String::from("["),
         SymbolType::SquareBracketClosed => String::from("]"),
+        SymbolType::CurlyBracketOpen => String::from("{"),
+        SymbolType::CurlyBracketClosed => String::from("}"),
+        SymbolType::Colon => String::from(":"),
         SymbolType::EqualsEquals => String::from("=="),
         SymbolType::NotEquals => String::from("!="),
         SymbolType::GreaterThan => String::from(">"),
@@ -167,6 +389,7 @@ pub fn get_symbol_from_type(symbol_type: &SymbolType) -> String {
         SymbolType::And => String::from("and"),
         SymbolType::Not => String::from("not"),
         SymbolType::For => String::from("for"),
+        SymbolType::While => String::from("while"),
         SymbolType::In => String::from("in"),
         SymbolType::If => String::from("if"),
         SymbolType::Else => String::from("else"),
@@ -174,10 +397,19 @@ pub fn get_symbol_from_type(symbol_type: &SymbolType) -> String {
         SymbolType::QuotationMark => String::from("\""),
         SymbolType::Return => String::from("return"),
         SymbolType::Break => String::from("break"),
+        SymbolType::Continue => String::from("continue"),
         SymbolType::PlusEquals => String::from("+="),
         SymbolType::True => String::from("true"),
         SymbolType::False => String::from("false"),
         SymbolType::Struct => String::from("struct"),
+        SymbolType::Yield => String::from("yield"),
+        SymbolType::Del => String::from("del"),
+        SymbolType::Swap => String::from("swap"),
+        SymbolType::Defer => String::from("defer"),
+        SymbolType::With => String::from("with"),
+        SymbolType::As => String::from("as"),
+        SymbolType::Match => String::from("match"),
+        SymbolType::Case => String::from("case"),
     }
 }
 
@@ -190,8 +422,8 @@ fn is_symbol(symbol: &String) -> bool {
 
 fn separate_symbols(
     symbol: &str,
-    row_index: usize,
     start_column: usize,
+    span: &dyn Fn(usize, usize) -> Span,
 ) -> Result<Vec<Token>, Error> {
     let mut symbols: Vec<Token> = Vec::new();
 
@@ -206,11 +438,9 @@ fn separate_symbols(
             Ok(symbol_type) => {
                 symbols.push(Token {
                     data: TokenData::Symbol { symbol_type },
-                    row: row_index,
-                    col_start: start_column,
-                    col_end: start_column + i,
+                    span: span(start_column, start_column + i),
                 });
-                match separate_symbols(&symbol[i..], row_index, start_column + i) {
+                match separate_symbols(&symbol[i..], start_column + i, span) {
                     Ok(mut rest_symbols) => {
                         symbols.append(&mut rest_symbols);
                         return Ok(symbols);
@@ -227,8 +457,182 @@ fn separate_symbols(
     });
 }
 
-fn count_indentation(line: &String, line_index: usize) -> Result<usize, Error> {
-    let indentation_spaces = 4;
+// Pushes the token for a number literal, which is a float if it has a decimal point or an exponent.
+// The span of the columns of the row is given by span.
+fn push_number_token(
+    number_text: &str,
+    col_start: usize,
+    col_end: usize,
+    span: &dyn Fn(usize, usize) -> Span,
+    tokens: &mut Vec<Token>,
+) -> Result<(), Error> {
+    let data = if number_text.contains(['.', 'e', 'E']) {
+        match number_text.parse::<f64>() {
+            Ok(value) if value.is_finite() => TokenData::Float { value },
+            _ => {
+                return Err(Error::located(
+                    format!("Number is too large, floats can be at most {:e}", f64::MAX),
+                    span(col_start, col_end),
+                ));
+            }
+        }
+    } else {
+        match number_text.parse::<i64>() {
+            Ok(number) => TokenData::Number { number },
+            // The smallest integer is one further from zero than the largest, so it only fits
+            // together with its minus sign: the two become a single token
+            Err(_) if is_unary_minus(tokens) && format!("-{number_text}").parse::<i64>().is_ok() => {
+                let minus = tokens.pop().unwrap();
+                tokens.push(Token {
+                    data: TokenData::Number { number: i64::MIN },
+                    span: minus.span.merge(&span(col_start, col_end)),
+                });
+                return Ok(());
+            }
+            Err(_) => {
+                return Err(Error::located(
+                    format!(
+                        "Number is too large, integers can be at most {}. Write it as {}.0 for a float",
+                        i64::MAX,
+                        number_text
+                    ),
+                    span(col_start, col_end),
+                ));
+            }
+        }
+    };
+
+    tokens.push(Token {
+        data,
+        span: span(col_start, col_end),
+    });
+    Ok(())
+}
+
+// Whether the last token is a minus which negates what follows it, rather than subtracting it
+// from the value in front of it
+fn is_unary_minus(tokens: &[Token]) -> bool {
+    match tokens {
+        [.., before, Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Minus,
+            },
+            ..
+        }] => !matches!(
+            before.data,
+            TokenData::Variable { .. }
+                | TokenData::Number { .. }
+                | TokenData::Float { .. }
+                | TokenData::String { .. }
+                | TokenData::Symbol {
+                    symbol_type: SymbolType::ParenthesisClosed
+                        | SymbolType::SquareBracketClosed
+                        | SymbolType::CurlyBracketClosed
+                        | SymbolType::True
+                        | SymbolType::False,
+                }
+        ),
+        [Token {
+            data: TokenData::Symbol {
+                symbol_type: SymbolType::Minus,
+            },
+            ..
+        }] => true,
+        _ => false,
+    }
+}
+
+// Whether the character continues the number literal read so far into a float, like the . in 3.14
+// or the e and - in 1e-3. The rest of the line is needed since a . or e is only part of the number
+// when a digit follows it.
+fn continues_float_literal(number_text: &str, c: char, rest: &[char]) -> bool {
+    let digit_at = |index: usize| rest.get(index).is_some_and(|c| c.is_ascii_digit());
+
+    match c {
+        '.' => !number_text.contains(['.', 'e', 'E']) && digit_at(0),
+        'e' | 'E' => {
+            !number_text.contains(['e', 'E'])
+                && (digit_at(0) || (matches!(rest.first(), Some('+' | '-')) && digit_at(1)))
+        }
+        '+' | '-' => number_text.ends_with(['e', 'E']),
+        _ => false,
+    }
+}
+
+// The keyword the word was most likely meant to be, such as return for retrun.
+// Longer keywords allow more typos, two letter keywords are never suggested since almost any short word is close to them.
+pub fn closest_keyword(word: &str) -> Option<&'static str> {
+    KEYWORDS
+        .iter()
+        .map(|keyword| (*keyword, edit_distance(word, keyword)))
+        .filter(|(keyword, distance)| *distance > 0 && *distance <= keyword.len() / 3)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(keyword, _)| keyword)
+}
+
+// The Levenshtein distance between the words, where swapping two neighbouring letters also counts as one edit
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // distances[i][j] is the distance between the first i letters of a and the first j letters of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution_cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + substitution_cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+// Pushes the tokens for a run of symbol characters, which can consist of multiple symbols such as '[['
+fn push_symbol_tokens(
+    symbol: &String,
+    col_start: usize,
+    col_end: usize,
+    span: &dyn Fn(usize, usize) -> Span,
+    tokens: &mut Vec<Token>,
+) -> Result<(), Error> {
+    match get_symbol_type(symbol) {
+        Ok(symbol_type) => tokens.push(Token {
+            data: TokenData::Symbol { symbol_type },
+            span: span(col_start, col_end),
+        }),
+        Err(_) => match separate_symbols(symbol, col_start, span) {
+            Ok(symbols_separated) => {
+                for symbol in symbols_separated {
+                    tokens.push(symbol);
+                }
+            }
+            Err(_) => {
+                return Err(Error::located(format!("Invalid symbol: {}", symbol), span(col_start, col_end)));
+            }
+        },
+    }
+
+    return Ok(());
+}
+
+fn count_indentation(
+    line: &String,
+    indentation_spaces: usize,
+    span: &dyn Fn(usize, usize) -> Span,
+) -> Result<usize, Error> {
     let mut indentation = 0;
     if line.len() == 0 {
         return Ok(0);
@@ -259,21 +663,38 @@ fn count_indentation(line: &String, line_index: usize) -> Result<usize, Error> {
         return Ok(leading_spaces / indentation_spaces);
     }
 
-    return Err(Error::LocationError {
-        message: format!("Invalid indentation"),
-        row: line_index,
-        col_start: 0,
-        col_end: leading_spaces,
-    });
+    return Err(Error::located(format!("Invalid indentation"), span(0, leading_spaces)));
+}
+
+// Removes the comment from the line, which starts at a # outside of a string and runs to the end of the line
+pub fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (byte_index, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..byte_index],
+            _ => {}
+        }
+    }
+
+    line
 }
 
 pub fn tokenize(lines: Vec<&str>) -> Result<Vec<TokenLine>, Error> {
+    tokenize_with_options(lines, &LanguageOptions::default())
+}
+
+pub fn tokenize_with_options(
+    lines: Vec<&str>,
+    options: &LanguageOptions,
+) -> Result<Vec<TokenLine>, Error> {
+    let _span = tracing::debug_span!("tokenize").entered();
     let mut cleaned_lines: Vec<String> = Vec::new();
     let mut line_indices: Vec<usize> = Vec::new();
 
     for (line_index, line) in lines.iter().enumerate() {
-        let mut line_cleaned = line.replace("\r", "");
-        // Removing empty lines
+        let mut line_cleaned = strip_comment(line).replace("\r", "");
+        // Removing empty lines, which includes lines with only a comment
         if line_cleaned.replace(" ", "").replace("\t", "").len() == 0 {
             continue;
         }
@@ -282,12 +703,33 @@ pub fn tokenize(lines: Vec<&str>) -> Result<Vec<TokenLine>, Error> {
         line_indices.push(line_index);
     }
 
+    // Where each line starts in the source, for the byte offsets of the spans
+    let mut line_starts: Vec<usize> = Vec::new();
+    let mut line_start = 0;
+    for line in lines.iter() {
+        line_starts.push(line_start);
+        line_start += line.len() + 1;
+    }
+
     let mut token_lines: Vec<TokenLine> = Vec::new();
 
+    // The number of brackets left open by the previous lines
+    let mut open_brackets = 0;
+
     for (line_index, line) in line_indices.iter().zip(cleaned_lines.iter()) {
-        let indentation = match count_indentation(&line, *line_index) {
-            Ok(indentation) => indentation,
-            Err(error_message) => return Err(error_message),
+        // The columns of the cleaned line are those of the original line, whose bytes the spans count
+        let span = |col_start: usize, col_end: usize| {
+            Span::on_line(lines[*line_index], line_starts[*line_index], *line_index, col_start, col_end)
+        };
+
+        // The indentation of a continuation line does not matter
+        let indentation = if open_brackets > 0 {
+            0
+        } else {
+            match count_indentation(&line, options.indentation_width, &span) {
+                Ok(indentation) => indentation,
+                Err(error_message) => return Err(error_message),
+            }
         };
 
         let mut token_line: TokenLine = TokenLine {
@@ -296,7 +738,7 @@ pub fn tokenize(lines: Vec<&str>) -> Result<Vec<TokenLine>, Error> {
         };
 
         let mut in_number = false;
-        let mut current_number = 0;
+        let mut current_number = String::new();
         let mut in_string = false;
         let mut current_string = String::new();
         let mut in_variable = false;
@@ -305,7 +747,8 @@ pub fn tokenize(lines: Vec<&str>) -> Result<Vec<TokenLine>, Error> {
         let mut current_symbol = String::new();
         let mut current_token_start = 0;
 
-        for (current_column, c) in line.chars().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        for (current_column, &c) in chars.iter().enumerate() {
             let char_type: CharType = get_char_type(c);
 
             if in_string {
@@ -316,9 +759,7 @@ pub fn tokenize(lines: Vec<&str>) -> Result<Vec<TokenLine>, Error> {
                             data: TokenData::String {
                                 value: current_string.clone(),
                             },
-                            row: *line_index,
-                            col_start: current_token_start,
-                            col_end: current_column + 1,
+                            span: span(current_token_start, current_column + 1),
                         });
 
                         in_string = false;
@@ -332,17 +773,22 @@ pub fn tokenize(lines: Vec<&str>) -> Result<Vec<TokenLine>, Error> {
                 continue;
             }
 
+            // A decimal point or exponent inside a number
+            if in_number && continues_float_literal(&current_number, c, &chars[current_column + 1..]) {
+                current_number.push(c);
+                continue;
+            }
+
             // If we move out of a number
             if in_number && char_type != CharType::Number {
-                token_line.tokens.push(Token {
-                    data: TokenData::Number {
-                        number: current_number,
-                    },
-                    row: *line_index,
-                    col_start: current_token_start,
-                    col_end: current_column,
-                });
-                current_number = 0;
+                push_number_token(
+                    &current_number,
+                    current_token_start,
+                    current_column,
+                    &span,
+                    &mut token_line.tokens,
+                )?;
+                current_number = String::new();
                 in_number = false;
             }
 
@@ -353,9 +799,7 @@ pub fn tokenize(lines: Vec<&str>) -> Result<Vec<TokenLine>, Error> {
                     // String was a symbol
                     Ok(symbol_type) => token_line.tokens.push(Token {
                         data: TokenData::Symbol { symbol_type },
-                        row: *line_index,
-                        col_start: current_token_start,
-                        col_end: current_column,
+                        span: span(current_token_start, current_column),
                     }),
 
                     // String was just a variable
@@ -363,9 +807,7 @@ pub fn tokenize(lines: Vec<&str>) -> Result<Vec<TokenLine>, Error> {
                         data: TokenData::Variable {
                             name: current_variable.clone(),
                         },
-                        row: *line_index,
-                        col_start: current_token_start,
-                        col_end: current_column,
+                        span: span(current_token_start, current_column),
                     }),
                 }
                 current_variable = String::new();
@@ -374,31 +816,13 @@ pub fn tokenize(lines: Vec<&str>) -> Result<Vec<TokenLine>, Error> {
 
             // If we move out of a symbol
             if in_symbol && char_type != CharType::Symbol {
-                match get_symbol_type(&current_symbol) {
-                    Ok(symbol_type) => token_line.tokens.push(Token {
-                        data: TokenData::Symbol { symbol_type },
-                        row: *line_index,
-                        col_start: current_token_start,
-                        col_end: current_column,
-                    }),
-                    Err(_) => {
-                        match separate_symbols(&current_symbol, *line_index, current_token_start) {
-                            Ok(symbols_separated) => {
-                                for symbol in symbols_separated {
-                                    token_line.tokens.push(symbol);
-                                }
-                            }
-                            Err(_) => {
-                                return Err(Error::LocationError {
-                                    message: format!("Invalid symbol: {}", current_symbol),
-                                    row: *line_index,
-                                    col_start: current_token_start,
-                                    col_end: current_column,
-                                });
-                            }
-                        }
-                    }
-                }
+                push_symbol_tokens(
+                    &current_symbol,
+                    current_token_start,
+                    current_column,
+                    &span,
+                    &mut token_line.tokens,
+                )?;
                 current_symbol = String::new();
                 in_symbol = false;
             }
@@ -412,22 +836,13 @@ pub fn tokenize(lines: Vec<&str>) -> Result<Vec<TokenLine>, Error> {
                         Ok(SymbolType::QuotationMark) => {
                             // Save current symbol
                             if in_symbol {
-                                match get_symbol_type(&current_symbol) {
-                                    Ok(symbol_type) => token_line.tokens.push(Token {
-                                        data: TokenData::Symbol { symbol_type },
-                                        row: *line_index,
-                                        col_start: current_token_start,
-                                        col_end: current_column,
-                                    }),
-                                    Err(_) => {
-                                        return Err(Error::LocationError {
-                                            message: format!("Invalid symbol: {}", current_symbol),
-                                            row: *line_index,
-                                            col_start: current_token_start,
-                                            col_end: current_column,
-                                        });
-                                    }
-                                }
+                                push_symbol_tokens(
+                                    &current_symbol,
+                                    current_token_start,
+                                    current_column,
+                                    &span,
+                                    &mut token_line.tokens,
+                                )?;
                             }
 
                             in_symbol = false;
@@ -460,9 +875,7 @@ pub fn tokenize(lines: Vec<&str>) -> Result<Vec<TokenLine>, Error> {
                     }
 
                     in_number = true;
-                    if let Some(number) = c.to_digit(10) {
-                        current_number = current_number * 10 + number as i64;
-                    }
+                    current_number.push(c);
                 }
 
                 CharType::Variable => {
@@ -479,14 +892,13 @@ pub fn tokenize(lines: Vec<&str>) -> Result<Vec<TokenLine>, Error> {
 
         // If we are still in a number at the end
         if in_number {
-            token_line.tokens.push(Token {
-                data: TokenData::Number {
-                    number: current_number,
-                },
-                row: *line_index,
-                col_start: current_token_start,
-                col_end: line.len(),
-            });
+            push_number_token(
+                &current_number,
+                current_token_start,
+                chars.len(),
+                &span,
+                &mut token_line.tokens,
+            )?;
         }
 
         // If we are still in a variable at the end
@@ -496,9 +908,7 @@ pub fn tokenize(lines: Vec<&str>) -> Result<Vec<TokenLine>, Error> {
                 // String was a symbol
                 Ok(symbol_type) => token_line.tokens.push(Token {
                     data: TokenData::Symbol { symbol_type },
-                    row: *line_index,
-                    col_start: current_token_start,
-                    col_end: line.len(),
+                    span: span(current_token_start, chars.len()),
                 }),
 
                 // String was just a variable
@@ -506,46 +916,131 @@ pub fn tokenize(lines: Vec<&str>) -> Result<Vec<TokenLine>, Error> {
                     data: TokenData::Variable {
                         name: current_variable.clone(),
                     },
-                    row: *line_index,
-                    col_start: current_token_start,
-                    col_end: line.len(),
+                    span: span(current_token_start, chars.len()),
                 }),
             }
         }
 
         // If we are still in a symbol at the end
         if in_symbol {
-            match get_symbol_type(&current_symbol) {
-                Ok(symbol_type) => token_line.tokens.push(Token {
-                    data: TokenData::Symbol { symbol_type },
-                    row: *line_index,
-                    col_start: current_token_start,
-                    col_end: line.len(),
-                }),
-                Err(_) => {
-                    match separate_symbols(&current_symbol, *line_index, current_token_start) {
-                        Ok(symbols_separated) => {
-                            for symbol in symbols_separated {
-                                token_line.tokens.push(symbol);
-                            }
-                        }
-                        Err(_) => {
-                            return Err(Error::LocationError {
-                                message: format!("Invalid symbol: {}", current_symbol),
-                                row: *line_index,
-                                col_start: current_token_start,
-                                col_end: line.len(),
+            push_symbol_tokens(
+                &current_symbol,
+                current_token_start,
+                chars.len(),
+                &span,
+                &mut token_line.tokens,
+            )?;
+        }
+
+        // A line with unclosed brackets continues on the next line, e.g. a list literal spanning lines
+        if open_brackets > 0 {
+            token_lines.last_mut().unwrap().tokens.append(&mut token_line.tokens);
+        } else {
+            token_lines.push(token_line);
+        }
+
+        open_brackets = count_open_brackets(&token_lines.last().unwrap().tokens);
+    }
+
+    for token_line in &token_lines {
+        check_delimiters(&token_line.tokens)?;
+    }
+
+    return Ok(token_lines);
+}
+
+// Checks that every opening bracket on a (joined) line is closed by the matching kind of bracket
+fn check_delimiters(tokens: &[Token]) -> Result<(), Error> {
+    let mut open_tokens: Vec<&Token> = Vec::new();
+
+    for token in tokens {
+        let symbol_type = match &token.data {
+            TokenData::Symbol { symbol_type } => symbol_type,
+            _ => continue,
+        };
+
+        match symbol_type {
+            SymbolType::ParenthesisOpen
+            | SymbolType::SquareBracketOpen
+            | SymbolType::CurlyBracketOpen => open_tokens.push(token),
+            SymbolType::ParenthesisClosed
+            | SymbolType::SquareBracketClosed
+            | SymbolType::CurlyBracketClosed => {
+                match open_tokens.pop() {
+                    Some(open_token) => {
+                        let open_symbol_type = match &open_token.data {
+                            TokenData::Symbol { symbol_type } => symbol_type,
+                            _ => unreachable!("Only symbols are pushed as open tokens"),
+                        };
+                        let closing_symbol_type = get_closing_symbol_type(open_symbol_type);
+                        if closing_symbol_type != *symbol_type {
+                            return Err(Error::DelimiterError {
+                                message: format!(
+                                    "Unclosed '{}' opened here, found '{}' instead of '{}'",
+                                    get_symbol_from_type(open_symbol_type),
+                                    get_symbol_from_type(symbol_type),
+                                    get_symbol_from_type(&closing_symbol_type)
+                                ),
+                                opened: open_token.span(),
+                                expected: token.span(),
                             });
                         }
                     }
+                    None => {
+                        return Err(Error::located(
+                            format!("Unmatched '{}'", get_symbol_from_type(symbol_type)),
+                            token.span(),
+                        ))
+                    }
                 }
             }
+            _ => {}
+        }
+    }
+
+    // The innermost bracket left open is reported, its closing bracket was expected after the last token
+    if let (Some(open_token), Some(last_token)) = (open_tokens.last(), tokens.last()) {
+        if let TokenData::Symbol { symbol_type } = &open_token.data {
+            return Err(Error::DelimiterError {
+                message: format!("Unclosed '{}' opened here", get_symbol_from_type(symbol_type)),
+                opened: open_token.span(),
+                expected: last_token.span.after(),
+            });
         }
+    }
+
+    Ok(())
+}
 
-        token_lines.push(token_line);
+fn get_closing_symbol_type(open_symbol_type: &SymbolType) -> SymbolType {
+    match open_symbol_type {
+        SymbolType::SquareBracketOpen => SymbolType::SquareBracketClosed,
+        SymbolType::CurlyBracketOpen => SymbolType::CurlyBracketClosed,
+        _ => SymbolType::ParenthesisClosed,
     }
+}
 
-    return Ok(token_lines);
+fn count_open_brackets(tokens: &[Token]) -> i32 {
+    let mut open_brackets = 0;
+    for token in tokens {
+        match token.data {
+            TokenData::Symbol {
+                symbol_type:
+                    SymbolType::SquareBracketOpen
+                    | SymbolType::ParenthesisOpen
+                    | SymbolType::CurlyBracketOpen,
+            } => open_brackets += 1,
+            TokenData::Symbol {
+                symbol_type:
+                    SymbolType::SquareBracketClosed
+                    | SymbolType::ParenthesisClosed
+                    | SymbolType::CurlyBracketClosed,
+            } => open_brackets -= 1,
+            _ => {}
+        }
+    }
+
+    return open_brackets;
 }
 
 pub fn print_token_lines(token_lines: &Vec<TokenLine>) {
@@ -567,6 +1062,7 @@ pub fn print_token(token: &Token) {
     match &token.data {
         TokenData::Variable { name } => print!("Var({name:?})"),
         TokenData::Number { number } => print!("Num({number})"),
+        TokenData::Float { value } => print!("Float({value:?})"),
         TokenData::String { value } => print!("Str({value:?})"),
         TokenData::Symbol { symbol_type } => print!("Sym{}", get_symbol_from_type(symbol_type)),
     }
@@ -0,0 +1,118 @@
+use crate::tokenizer::Error;
+
+// The first bytes of a trace file, followed by the version of the format
+const MAGIC: &[u8] = b"ROSYTRACE";
+const VERSION: u8 = 1;
+
+// Everything a run read from outside the program, so the run can be reproduced exactly:
+// the seed of the random number generator and the inputs such as the time and key presses, in the order they were read.
+// See Context::traced_input for how inputs are recorded and replayed.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Trace {
+    pub seed: u64,
+    pub inputs: Vec<TracedInput>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct TracedInput {
+    // What was read, such as "time" or "key"
+    pub kind: String,
+    pub value: String,
+}
+
+impl Trace {
+    // The trace file format, all numbers are little endian:
+    //   ROSYTRACE, the version as a byte, the seed as a u64, the number of inputs as a u32,
+    //   then for every input its kind and value, each as a u32 length followed by utf-8 text
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::from(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend(self.seed.to_le_bytes());
+        bytes.extend((self.inputs.len() as u32).to_le_bytes());
+
+        for input in self.inputs.iter() {
+            for text in [&input.kind, &input.value] {
+                bytes.extend((text.len() as u32).to_le_bytes());
+                bytes.extend(text.as_bytes());
+            }
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Trace, Error> {
+        let mut reader = TraceReader { bytes, position: 0 };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(trace_error("not a rosy trace"));
+        }
+        let version = reader.take(1)?[0];
+        if version != VERSION {
+            return Err(trace_error(&format!("unsupported version {}", version)));
+        }
+
+        let seed = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+        let input_count = reader.read_u32()?;
+
+        let mut inputs = Vec::new();
+        for _ in 0..input_count {
+            let kind = reader.read_string()?;
+            let value = reader.read_string()?;
+            inputs.push(TracedInput { kind, value });
+        }
+
+        if reader.position != bytes.len() {
+            return Err(trace_error("unexpected data after the last input"));
+        }
+
+        Ok(Trace { seed, inputs })
+    }
+
+    pub fn read(path: &std::path::Path) -> Result<Trace, Error> {
+        match std::fs::read(path) {
+            Ok(bytes) => Trace::from_bytes(&bytes),
+            Err(error) => Err(Error::SimpleError {
+                message: format!("Could not read {}: {}", path.display(), error),
+            }),
+        }
+    }
+
+    pub fn write(&self, path: &std::path::Path) -> Result<(), Error> {
+        std::fs::write(path, self.to_bytes()).map_err(|error| Error::SimpleError {
+            message: format!("Could not write {}: {}", path.display(), error),
+        })
+    }
+}
+
+fn trace_error(message: &str) -> Error {
+    Error::SimpleError {
+        message: format!("Invalid trace: {}", message),
+    }
+}
+
+struct TraceReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> TraceReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], Error> {
+        let end = self.position + count;
+        if end > self.bytes.len() {
+            return Err(trace_error("the file ends too early"));
+        }
+
+        let taken = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(taken)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn read_string(&mut self) -> Result<String, Error> {
+        let length = self.read_u32()? as usize;
+        String::from_utf8(self.take(length)?.to_vec()).map_err(|_| trace_error("text is not utf-8"))
+    }
+}
@@ -0,0 +1,135 @@
+use crate::builtins;
+use crate::parser::RecExpr;
+use crate::tokenizer::Error;
+use crate::typechecker::FunctionType;
+use crate::typechecker::Type;
+
+// What the transpilers, which write a typechecked program in another language, have in common:
+// naming the instances of the functions and writing string literals.
+
+// An instance of a function as it is written. Instances typechecked for different argument types share
+// a name when their bodies are written the same, the others are numbered: area, area_2.
+// The helpers are those of the prelude of the target language the body uses.
+pub struct WrittenFunction<H> {
+    pub name: String,
+    pub param_types: Vec<Type>,
+    pub written_name: String,
+    pub captured: Vec<String>,
+    pub source: String,
+    pub is_first_with_body: bool,
+    pub helpers: H,
+}
+
+// Stands for the name of a function in its source until the name is known
+pub const NAME_PLACEHOLDER: &str = "\u{0}";
+
+// Writes every instance of the functions of the program with write_function, which gives the source of an
+// instance with NAME_PLACEHOLDER for its name. The name of a function in the target language is given by written_name.
+pub fn written_functions<H: Default>(
+    function_types: &[FunctionType],
+    written_name: fn(&str) -> String,
+    mut write_function: impl FnMut(&FunctionType, &[WrittenFunction<H>]) -> Result<(String, H), Error>,
+) -> Result<Vec<WrittenFunction<H>>, Error> {
+    let mut instances: Vec<&FunctionType> = Vec::new();
+    for function in function_types {
+        // The builtins that were called are typechecked as functions without content
+        let is_builtin = function.content.is_empty() && builtins::find_builtin(&function.name).is_some();
+        let is_known = instances
+            .iter()
+            .any(|known| known.name == function.name && known.param_types == function.param_types);
+        if !is_builtin && !is_known {
+            instances.push(function);
+        }
+    }
+
+    let mut functions: Vec<WrittenFunction<H>> = instances
+        .iter()
+        .map(|function| WrittenFunction {
+            name: function.name.clone(),
+            param_types: function.param_types.clone(),
+            written_name: written_name(&function.name),
+            captured: function.captured.iter().map(|(name, _)| name.clone()).collect(),
+            source: String::new(),
+            is_first_with_body: false,
+            helpers: H::default(),
+        })
+        .collect();
+
+    // A body depends on the names of the functions it calls, so the instances are written again until the
+    // names settle. Every round can only tell more instances apart, so this ends.
+    for _ in 0..=instances.len() {
+        let mut written = Vec::new();
+        for function in &instances {
+            written.push(write_function(function, &functions)?);
+        }
+
+        let mut names: Vec<String> = Vec::new();
+        let mut is_first: Vec<bool> = Vec::new();
+        for (index, function) in functions.iter().enumerate() {
+            let same_body = (0..index).find(|&earlier| {
+                functions[earlier].name == function.name && written[earlier].0 == written[index].0
+            });
+            match same_body {
+                Some(earlier) => {
+                    names.push(names[earlier].clone());
+                    is_first.push(false);
+                }
+                None => {
+                    let variants = (0..index)
+                        .filter(|&earlier| functions[earlier].name == function.name && is_first[earlier])
+                        .count();
+                    names.push(match variants {
+                        0 => written_name(&function.name),
+                        _ => format!("{}_{}", written_name(&function.name), variants + 1),
+                    });
+                    is_first.push(true);
+                }
+            }
+        }
+
+        let settled = functions.iter().zip(&names).all(|(function, name)| function.written_name == *name);
+        for (function, ((definition, helpers), (name, is_first))) in
+            functions.iter_mut().zip(written.into_iter().zip(names.into_iter().zip(is_first)))
+        {
+            function.source = definition.replacen(NAME_PLACEHOLDER, &name, 1);
+            function.written_name = name;
+            function.is_first_with_body = is_first;
+            function.helpers = helpers;
+        }
+        if settled {
+            break;
+        }
+    }
+    Ok(functions)
+}
+
+// The instance of the function a call with these arguments calls
+pub fn called_instance<'a, H>(
+    functions: &'a [WrittenFunction<H>],
+    function_name: &str,
+    args: &[RecExpr<Type>],
+    call: &RecExpr<Type>,
+) -> Result<&'a WrittenFunction<H>, Error> {
+    let arg_types: Vec<Type> = args.iter().map(|arg| arg.generic_data.clone()).collect();
+    functions
+        .iter()
+        .find(|function| function.name == function_name && function.param_types == arg_types)
+        .ok_or_else(|| Error::located(format!("Function '{}' not found", function_name), call.span()))
+}
+
+// A double quoted string literal, with the escapes JavaScript and Python share
+pub fn string_literal(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for character in value.chars() {
+        match character {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            character => literal.push(character),
+        }
+    }
+    literal.push('"');
+    literal
+}
@@ -1,14 +1,29 @@
+use std::fmt;
+
+use crate::builtins;
+use crate::decompiler;
+use crate::interpreter::Value;
+use crate::language::LanguageOptions;
+use crate::parser;
+use crate::parser::Accessor;
 use crate::parser::BaseExpr;
 use crate::parser::BaseExprData;
+use crate::parser::MatchArm;
+use crate::parser::Parameter;
+use crate::parser::Pattern;
 use crate::parser::RecExpr;
 use crate::parser::RecExprData;
+use crate::stack;
 use crate::tokenizer::Error;
+use crate::tokenizer::Span;
+use crate::visitor;
 
 // Function type checking works as follows:
 // 1. We first preload all function definitions into a separate function environment
 //    This environment keeps all functions but without specific parameter types
 // 2. When we encounter a function call, we look for a function with the same name in the type environment
 //    If we find one with the same parameter types, we return its return type
+//    Builtin functions are added to the type environment the first time they are called with matching types
 // 3. If we do not find one with the same parameter types, we look for a function with the same name in the function environment
 //    If we find one, we type-check it with the new parameter types and add it to the type environment if successful
 
@@ -20,6 +35,57 @@ pub enum Type {
     Boolean,
     String,
     List(Box<Type>),
+    // The values yielded by a generator function
+    Generator(Box<Type>),
+    Bytes,
+    // An exact fraction, see rational::Rational
+    Rational,
+    Complex,
+    // Either a value of the inner type or an error, returned by the try_ builtins
+    Result(Box<Type>),
+    // An object owned by the host, such as an open file
+    Resource,
+    // An instance of a struct, boxed to keep Type small
+    Struct(Box<StructType>),
+    // A dict from keys of the first type to values of the second type
+    Dict(Box<Type>, Box<Type>),
+    // A fixed number of values, each with its own type
+    Tuple(Vec<Type>),
+}
+
+// Types are written as in a program, such as list[int] and dict[string, float], in every message about types
+// and in the output of --annotate. A struct is written as its name.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Undefined => write!(f, "undefined"),
+            Type::Integer => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Boolean => write!(f, "bool"),
+            Type::String => write!(f, "string"),
+            Type::List(element_type) => write!(f, "list[{}]", element_type),
+            Type::Generator(element_type) => write!(f, "generator[{}]", element_type),
+            Type::Bytes => write!(f, "bytes"),
+            Type::Rational => write!(f, "rational"),
+            Type::Complex => write!(f, "complex"),
+            Type::Result(value_type) => write!(f, "result[{}]", value_type),
+            Type::Resource => write!(f, "resource"),
+            Type::Struct(struct_type) => write!(f, "{}", struct_type.name),
+            Type::Dict(key_type, value_type) => write!(f, "dict[{}, {}]", key_type, value_type),
+            Type::Tuple(element_types) => {
+                let element_types: Vec<String> = element_types.iter().map(Type::to_string).collect();
+                write!(f, "tuple[{}]", element_types.join(", "))
+            }
+        }
+    }
+}
+
+// The field types come from the values the struct was constructed with,
+// so instances with fields of different types are of different types
+#[derive(Clone, PartialEq, Debug)]
+pub struct StructType {
+    pub name: String,
+    pub fields: Vec<(String, Type)>,
 }
 
 struct TypeBinding {
@@ -29,11 +95,19 @@ struct TypeBinding {
 
 type TypeScope = Vec<TypeBinding>;
 
+#[derive(Clone, PartialEq, Debug)]
+struct StructBinding {
+    name: String,
+    fields: Vec<String>,
+}
+
 #[derive(Clone, PartialEq, Debug)]
 struct FunctionBinding {
     name: String,
-    param_names: Vec<String>,
+    params: Vec<Parameter<()>>,
     content: Vec<BaseExpr<()>>,
+    // The variables of the enclosing function used by a function defined inside it, with their types there
+    captured: Vec<(String, Type)>,
 }
 type FunctionEnvironment = Vec<FunctionBinding>;
 
@@ -45,106 +119,67 @@ pub struct FunctionType {
     pub return_type: Type,
     pub content: Vec<BaseExpr<Type>>, // The content of the function with types filled in
     pub is_used: bool,
+    // The variables captured from the function it is defined in, empty for functions at the top level
+    pub captured: Vec<(String, Type)>,
 }
 
 struct TypeEnvironment {
     scopes: Vec<TypeScope>,
     functions: Vec<FunctionType>,
+    // The structs defined so far, in definition order
+    structs: Vec<StructBinding>,
+    options: LanguageOptions,
+    // The number of loops around the statement being checked, within the current function
+    loop_depth: usize,
+    // The function instances being checked, innermost last, with the type returned by the return statements
+    // checked so far. A recursive call gets that type instead of checking the function again, forever.
+    instances_in_progress: Vec<(String, Vec<Type>, Option<Type>)>,
+    // The functions defined inside the function being checked so far, which can only be called from there
+    local_functions: FunctionEnvironment,
 }
 
-fn print_type_env(env: &TypeEnvironment) {
-    print!("Type Environment: ");
-    for (i, scope) in env.scopes.iter().enumerate() {
-        print!("scope {}: [", i);
-        for binding in scope.iter() {
-            print!("{}: {:?}, ", binding.name, binding.value_type);
-        }
-        print!("], ");
-    }
-    print!("Functions: [");
-    for func in env.functions.iter() {
-        print!(
-            "({}: {:?} -> {:?}), ",
-            func.name, func.param_types, func.return_type
-        );
+// The types of the variables a value of the type is split into, as in low, high = minmax(numbers)
+pub fn destructured_types(value_type: &Type, count: usize) -> Vec<Type> {
+    match value_type {
+        Type::Tuple(element_types) => element_types.clone(),
+        Type::List(element_type) => vec![element_type.as_ref().clone(); count],
+        _ => vec![Type::Undefined; count],
     }
-    print!("\n");
 }
 
-fn print_function_env(func_env: &FunctionEnvironment) {
-    print!("Function Environment: [");
-    for func in func_env.iter() {
-        print!("{}({:?}), ", func.name, func.param_names);
-    }
-    print!("]\n");
+// The name of a type as it is written in rosy, for messages
+pub fn type_to_string(value_type: &Type) -> String {
+    value_type.to_string()
 }
 
-fn add_default_functions_to_env(env: &mut TypeEnvironment) {
-    env.functions.push(FunctionType {
-        name: String::from("print"),
-        param_names: vec![String::from("value")],
-        param_types: vec![Type::String],
-        return_type: Type::Undefined,
-        content: Vec::new(),
-        is_used: false,
-    });
-    env.functions.push(FunctionType {
-        name: String::from("print"),
-        param_names: vec![String::from("value")],
-        param_types: vec![Type::Integer],
-        return_type: Type::Undefined,
-        content: Vec::new(),
-        is_used: false,
-    });
-    env.functions.push(FunctionType {
-        name: String::from("print"),
-        param_names: vec![String::from("value")],
-        param_types: vec![Type::Float],
-        return_type: Type::Undefined,
-        content: Vec::new(),
-        is_used: false,
-    });
-    env.functions.push(FunctionType {
-        name: String::from("print"),
-        param_names: vec![String::from("value")],
-        param_types: vec![Type::Boolean],
-        return_type: Type::Undefined,
-        content: Vec::new(),
-        is_used: false,
-    });
+// The scopes and typed functions of the environment on one line, for logging
+fn type_env_to_string(env: &TypeEnvironment) -> String {
+    let mut description = String::new();
+    for (i, scope) in env.scopes.iter().enumerate() {
+        let bindings: Vec<String> = scope
+            .iter()
+            .map(|binding| format!("{}: {}", binding.name, type_to_string(&binding.value_type)))
+            .collect();
+        description.push_str(&format!("scope {}: [{}], ", i, bindings.join(", ")));
+    }
+    let functions: Vec<String> = env
+        .functions
+        .iter()
+        .map(|func| {
+            let param_types: Vec<String> = func.param_types.iter().map(type_to_string).collect();
+            format!("{}({}) -> {}", func.name, param_types.join(", "), type_to_string(&func.return_type))
+        })
+        .collect();
+    description.push_str(&format!("functions: [{}]", functions.join(", ")));
+    description
+}
 
-    env.functions.push(FunctionType {
-        name: String::from("println"),
-        param_names: vec![String::from("value")],
-        param_types: vec![Type::String],
-        return_type: Type::Undefined,
-        content: Vec::new(),
-        is_used: false,
-    });
-    env.functions.push(FunctionType {
-        name: String::from("println"),
-        param_names: vec![String::from("value")],
-        param_types: vec![Type::Integer],
-        return_type: Type::Undefined,
-        content: Vec::new(),
-        is_used: false,
-    });
-    env.functions.push(FunctionType {
-        name: String::from("println"),
-        param_names: vec![String::from("value")],
-        param_types: vec![Type::Float],
-        return_type: Type::Undefined,
-        content: Vec::new(),
-        is_used: false,
-    });
-    env.functions.push(FunctionType {
-        name: String::from("println"),
-        param_names: vec![String::from("value")],
-        param_types: vec![Type::Boolean],
-        return_type: Type::Undefined,
-        content: Vec::new(),
-        is_used: false,
-    });
+fn function_env_to_string(func_env: &FunctionEnvironment) -> String {
+    let functions: Vec<String> = func_env
+        .iter()
+        .map(|func| format!("{}({})", func.name, parser::parameter_names(&func.params).join(", ")))
+        .collect();
+    functions.join(", ")
 }
 
 fn preload_functions(base_expressions: &Vec<BaseExpr<()>>, func_env: &mut FunctionEnvironment) {
@@ -157,8 +192,9 @@ fn preload_functions(base_expressions: &Vec<BaseExpr<()>>, func_env: &mut Functi
             } => {
                 let func_binding = FunctionBinding {
                     name: fun_name.clone(),
-                    param_names: args.clone(),
+                    params: args.clone(),
                     content: body.clone(),
+                    captured: Vec::new(),
                 };
                 func_env.push(func_binding);
             }
@@ -167,6 +203,177 @@ fn preload_functions(base_expressions: &Vec<BaseExpr<()>>, func_env: &mut Functi
     }
 }
 
+// The function a call refers to, one defined in the current function hides one at the top level
+fn find_function_binding(
+    name: &String,
+    env: &TypeEnvironment,
+    func_env: &FunctionEnvironment,
+) -> Option<FunctionBinding> {
+    env.local_functions
+        .iter()
+        .chain(func_env.iter())
+        .find(|function| function.name == *name)
+        .cloned()
+}
+
+// Adds the default values of the parameters a call leaves out to its arguments, and checks that an argument
+// given for a parameter with a default has the type of the default. A default is checked with the variables
+// the function captures, the scope it is computed in.
+// The arguments are in the order of the parameters, a parameter skipped by keyword arguments is None.
+fn fill_in_defaults(
+    function: &FunctionBinding,
+    placed_args: &mut Vec<Option<RecExpr<Type>>>,
+    env: &mut TypeEnvironment,
+    func_env: &FunctionEnvironment,
+    call_span: Span,
+) -> Result<(), Error> {
+    let skipped = function
+        .params
+        .iter()
+        .zip(placed_args.iter())
+        .find(|(param, arg)| arg.is_none() && param.default.is_none());
+    if let Some((param, _)) = skipped {
+        return Err(Error::located(
+            format!("No value is given for the parameter {} of {}", param.name, function.name),
+            call_span,
+        ));
+    }
+
+    // Without all parameters that have no default, the call matches no function and that error is reported instead
+    let required = function.params.iter().filter(|param| param.default.is_none()).count();
+    if placed_args.len() < required || placed_args.len() > function.params.len() {
+        return Ok(());
+    }
+
+    for (position, param) in function.params.iter().enumerate() {
+        let Some(default) = &param.default else {
+            continue;
+        };
+
+        let mut default_env = TypeEnvironment {
+            scopes: vec![function
+                .captured
+                .iter()
+                .map(|(name, value_type)| TypeBinding {
+                    name: name.clone(),
+                    value_type: value_type.clone(),
+                })
+                .collect()],
+            functions: env.functions.clone(),
+            structs: env.structs.clone(),
+            options: env.options.clone(),
+            loop_depth: 0,
+            instances_in_progress: env.instances_in_progress.clone(),
+            local_functions: env.local_functions.clone(),
+        };
+        let default_typed = check_type_rec(default.clone(), &mut default_env, func_env)?;
+        // Functions the default calls are kept, as for the body of a function
+        for instance in default_env.functions {
+            if !env.functions.iter().any(|known| known.name == instance.name && known.param_types == instance.param_types) {
+                env.functions.push(instance);
+            }
+        }
+
+        match placed_args.get(position) {
+            Some(Some(arg)) if arg.generic_data != default_typed.generic_data => {
+                return Err(Error::type_error(
+                    format!("Argument {} of {} must have the type of its default value", param.name, function.name),
+                    default_typed.generic_data,
+                    arg.generic_data.clone(),
+                    arg.span(),
+                ));
+            }
+            Some(Some(_)) => {}
+            Some(None) => placed_args[position] = Some(default_typed),
+            None => placed_args.push(Some(default_typed)),
+        }
+    }
+    Ok(())
+}
+
+// Collects the arguments a call gives for the *rest parameter of the function, if it has one, into a list literal.
+// The collected arguments must have the same type. Without any, the list is empty and placed on the closing parenthesis.
+fn collect_rest_arguments(
+    function: &FunctionBinding,
+    placed_args: Vec<Option<RecExpr<Type>>>,
+    call_span: Span,
+) -> Result<Vec<Option<RecExpr<Type>>>, Error> {
+    let Some(position) = parser::rest_position(&function.params) else {
+        return Ok(placed_args);
+    };
+
+    let rest: Vec<RecExpr<Type>> = placed_args.iter().skip(position).flatten().cloned().collect();
+    let mut element_type = match rest.first() {
+        Some(first) => first.generic_data.clone(),
+        None => Type::Undefined,
+    };
+    for arg in rest.iter().skip(1) {
+        element_type = match merge_element_types(&element_type, &arg.generic_data) {
+            Some(merged_type) => merged_type,
+            None => {
+                return Err(Error::type_error(
+                    format!(
+                        "The arguments collected by *{} of {} must be of the same type",
+                        function.params[position].name, function.name
+                    ),
+                    element_type,
+                    arg.generic_data.clone(),
+                    arg.span(),
+                ));
+            }
+        };
+    }
+
+    let list_span = match (rest.first(), rest.last()) {
+        (Some(first), Some(last)) => first.span().merge(&last.span()),
+        _ => call_span.last_column(),
+    };
+    Ok(parser::collect_rest_arguments(placed_args, position, |elements| {
+        RecExpr::new(RecExprData::List { elements }, list_span, Type::List(Box::new(element_type)))
+    }))
+}
+
+// The arguments of a call in the order of the parameters. An argument which would start before the one
+// in front of it, because it was moved by a keyword argument or is a default value, is placed on the closing
+// parenthesis so the arguments stay in the order of the source.
+fn order_arguments(placed_args: Vec<Option<RecExpr<Type>>>, call_span: Span) -> Result<Vec<RecExpr<Type>>, Error> {
+    // The span of the closing parenthesis of the call
+    let end_of_call = call_span.last_column();
+
+    let mut args_typed: Vec<RecExpr<Type>> = Vec::new();
+    for arg in placed_args.into_iter().flatten() {
+        let follows_previous = match args_typed.last() {
+            Some(previous) => arg.span.byte_start >= previous.span.byte_start,
+            None => arg.span.byte_start >= call_span.byte_start,
+        };
+        match follows_previous {
+            true => args_typed.push(arg),
+            false => args_typed.push(visitor::respan(arg, end_of_call)?),
+        }
+    }
+    Ok(args_typed)
+}
+
+// The names of the parameters of what a call calls, used to place its keyword arguments.
+// Structs are found before builtins, and builtins before the functions of the program.
+fn call_parameter_names(name: &String, env: &TypeEnvironment, func_env: &FunctionEnvironment) -> Option<Vec<String>> {
+    if let Some(struct_binding) = env.structs.iter().find(|struct_binding| struct_binding.name == *name) {
+        return Some(struct_binding.fields.clone());
+    }
+    if let Some(builtin) = builtins::find_builtin(name) {
+        return Some(builtin.param_names.iter().map(|param_name| param_name.to_string()).collect());
+    }
+    // The *rest parameter only collects positional arguments, so it cannot be named
+    find_function_binding(name, env, func_env).map(|function| {
+        function
+            .params
+            .iter()
+            .filter(|param| !param.rest)
+            .map(|param| param.name.clone())
+            .collect()
+    })
+}
+
 fn find_matching_function_in_function_env(
     name: &String,
     param_types: &Vec<Type>,
@@ -175,7 +382,7 @@ fn find_matching_function_in_function_env(
     for func in func_env.iter() {
         if func.name == *name {
             // We have found a function with the correct name, now we need to check the parameter types
-            if func.param_names.len() == param_types.len() {
+            if func.params.len() == param_types.len() {
                 return Some(func.clone());
             }
         }
@@ -192,9 +399,15 @@ fn find_matching_function_in_env(
     param_types: &Vec<Type>,
     env: &mut TypeEnvironment,
     func_env: &FunctionEnvironment,
+    call_span: Span,
 ) -> Result<Type, Error> {
+    // The instances of functions defined inside other functions are kept with the rest,
+    // but they can only be called where the function is defined
+    let is_visible = env.local_functions.iter().any(|function| function.name == *name)
+        || func_env.iter().any(|function| function.name == *name)
+        || builtins::find_builtin(name).is_some();
     for function in env.functions.iter_mut() {
-        if function.name == *name {
+        if function.name == *name && is_visible {
             if function.param_types == *param_types {
                 function.is_used = true;
                 return Ok(function.return_type.clone());
@@ -202,25 +415,80 @@ fn find_matching_function_in_env(
         }
     }
 
+    let in_progress = env
+        .instances_in_progress
+        .iter()
+        .rev()
+        .find(|(in_progress_name, in_progress_types, _)| in_progress_name == name && in_progress_types == param_types);
+    if let Some((_, _, return_type)) = in_progress {
+        return match return_type {
+            Some(return_type) => Ok(return_type.clone()),
+            None => Err(Error::located(
+                format!(
+                    "The return type of {} is not known at this recursive call, return the value of the base case before it",
+                    name
+                ),
+                call_span,
+            )),
+        };
+    }
+
+    // Builtins take precedence over functions defined in the program
+    if let Some(builtin) = builtins::find_builtin(name) {
+        if let Some(return_type) = (builtin.signature)(param_types) {
+            env.functions.push(FunctionType {
+                name: name.clone(),
+                param_names: builtin.param_names_for(param_types.len()),
+                param_types: param_types.clone(),
+                return_type: return_type.clone(),
+                content: Vec::new(),
+                is_used: true,
+                captured: Vec::new(),
+            });
+            return Ok(return_type);
+        }
+    }
+
     // If we cannot find a function with that name and parameter types, we type-check the function with the given name
-    // but with these new parameter types
-    match find_matching_function_in_function_env(name, param_types, func_env) {
+    // but with these new parameter types. A function defined in the current function hides one at the top level.
+    let local_function = find_matching_function_in_function_env(name, param_types, &env.local_functions);
+    let is_local = local_function.is_some();
+    match local_function.or_else(|| find_matching_function_in_function_env(name, param_types, func_env)) {
         Some(func) => {
             // We have found a function with the correct name, now we need to type-check it with the given parameter types
             let mut new_env: TypeEnvironment = TypeEnvironment {
                 scopes: Vec::new(),
                 functions: env.functions.clone(),
+                structs: env.structs.clone(),
+                options: env.options.clone(),
+                loop_depth: 0,
+                instances_in_progress: env.instances_in_progress.clone(),
+                // A local function can call the functions defined next to it
+                local_functions: match is_local {
+                    true => env.local_functions.clone(),
+                    false => Vec::new(),
+                },
             };
             new_env.scopes.push(Vec::new());
+            new_env
+                .instances_in_progress
+                .push((name.clone(), param_types.clone(), None));
 
             // So we add the parameter types to the new environment
             // with the names given in the function definition
-            for (i, param_name) in func.param_names.iter().enumerate() {
+            for (i, param) in func.params.iter().enumerate() {
                 new_env.scopes.last_mut().unwrap().push(TypeBinding {
-                    name: param_name.clone(),
+                    name: param.name.clone(),
                     value_type: param_types[i].clone(),
                 });
             }
+            // The captured variables come after the parameters, which hide them
+            for (captured_name, captured_type) in func.captured.iter() {
+                new_env.scopes.last_mut().unwrap().push(TypeBinding {
+                    name: captured_name.clone(),
+                    value_type: captured_type.clone(),
+                });
+            }
 
             let mut expected_return_type: Option<Type> = None;
             match type_check(
@@ -237,14 +505,25 @@ fn find_matching_function_in_env(
                         None => Type::Undefined,
                     };
 
+                    // Functions first called inside this one are kept as well, so that the table of
+                    // function instances is complete, which the compiler relies on to resolve every call
+                    for function in typed_base_expressions.1 {
+                        if !env.functions.iter().any(|known| {
+                            known.name == function.name && known.param_types == function.param_types
+                        }) {
+                            env.functions.push(function);
+                        }
+                    }
+
                     // The function is successfully type-checked with the new parameter types
                     env.functions.push(FunctionType {
                         name: name.clone(),
-                        param_names: func.param_names.clone(),
+                        param_names: parser::parameter_names(&func.params),
                         param_types: param_types.clone(),
                         return_type: return_type.clone(),
                         content: typed_base_expressions.0,
                         is_used: true,
+                        captured: func.captured.clone(),
                     });
                     return Ok(return_type);
                 }
@@ -254,14 +533,61 @@ fn find_matching_function_in_env(
             }
         }
         None => {
-            return Err(Error::SimpleError {
-                message: format!(
-                    "Function '{}' with parameter types {:?} not found",
-                    name, param_types
-                ),
-            });
+            return Err(Error::located(
+                no_matching_function_message(name, param_types, func_env),
+                call_span,
+            ));
+        }
+    }
+}
+
+// Describes a call which matches no function, listing the ways a function with that name can be called
+fn no_matching_function_message(
+    name: &str,
+    param_types: &[Type],
+    func_env: &FunctionEnvironment,
+) -> String {
+    let mut candidates: Vec<String> = Vec::new();
+    for builtin in builtins::enabled_builtins() {
+        if builtin.name == name {
+            candidates.extend(builtin.describe_signatures());
+        }
+    }
+    // Parameters of functions defined in the program can have any type
+    for func in func_env.iter() {
+        if func.name == name {
+            candidates.push(format!("{}({})", func.name, parser::parameter_names(&func.params).join(", ")));
+        }
+    }
+
+    if candidates.is_empty() {
+        return format!("Function '{}' not found", name);
+    }
+
+    let arg_types: Vec<String> = param_types.iter().map(type_to_string).collect();
+
+    let mut message = format!(
+        "Function '{}' cannot be called with ({}), it can be called as:",
+        name,
+        arg_types.join(", ")
+    );
+    for candidate in candidates {
+        message.push_str(&format!("\n    {}", candidate));
+    }
+    message
+}
+
+fn remove_from_env(name: &String, env: &mut TypeEnvironment) -> bool {
+    for scope in env.scopes.iter_mut().rev() {
+        match scope.iter().position(|binding| binding.name == *name) {
+            Some(index) => {
+                scope.remove(index);
+                return true;
+            }
+            None => {}
         }
     }
+    return false;
 }
 
 fn update_in_env(value: &Type, name: &String, env: &mut TypeEnvironment) -> bool {
@@ -318,18 +644,30 @@ pub fn type_check_program(
     base_expressions: Vec<BaseExpr<()>>,
     print_results: bool,
 ) -> Result<(Vec<BaseExpr<Type>>, Vec<FunctionType>), Error> {
+    type_check_program_with_options(base_expressions, print_results, &LanguageOptions::default())
+}
+
+pub fn type_check_program_with_options(
+    base_expressions: Vec<BaseExpr<()>>,
+    print_results: bool,
+    options: &LanguageOptions,
+) -> Result<(Vec<BaseExpr<Type>>, Vec<FunctionType>), Error> {
+    let _span = tracing::debug_span!("typecheck").entered();
     let mut env: TypeEnvironment = TypeEnvironment {
         scopes: Vec::new(),
         functions: Vec::new(),
+        structs: Vec::new(),
+        options: options.clone(),
+        loop_depth: 0,
+        instances_in_progress: Vec::new(),
+        local_functions: Vec::new(),
     };
 
     env.scopes.push(Vec::new());
 
-    add_default_functions_to_env(&mut env);
-
     let mut func_env: FunctionEnvironment = Vec::new();
     preload_functions(&base_expressions, &mut func_env);
-    print_function_env(&func_env);
+    tracing::debug!("functions: {}", function_env_to_string(&func_env));
 
     let mut expected_return_type: Option<Type> = None;
 
@@ -350,78 +688,345 @@ pub fn type_check_program(
 // Type check a set of base expressions in the given environment
 // If print_results is true, it will print the types of variable assignments
 // It returns the expected return type of the program if there is one
+// Nested blocks recurse once per level, see stack::ensure_stack
 fn type_check(
     base_expressions: Vec<BaseExpr<()>>,
     env: &mut TypeEnvironment,
     func_env: &FunctionEnvironment,
     print_results: bool,
     expected_return_type: &mut Option<Type>,
+) -> Result<(Vec<BaseExpr<Type>>, Vec<FunctionType>), Error> {
+    stack::ensure_stack(|| {
+        type_check_unguarded(
+            base_expressions,
+            env,
+            func_env,
+            print_results,
+            expected_return_type,
+        )
+    })
+}
+
+fn type_check_unguarded(
+    base_expressions: Vec<BaseExpr<()>>,
+    env: &mut TypeEnvironment,
+    func_env: &FunctionEnvironment,
+    print_results: bool,
+    expected_return_type: &mut Option<Type>,
 ) -> Result<(Vec<BaseExpr<Type>>, Vec<FunctionType>), Error> {
     let mut typed_base_expressions: Vec<BaseExpr<Type>> = Vec::new();
 
     for base_expr in base_expressions {
-        print_type_env(&env);
+        tracing::trace!("{}", type_env_to_string(env));
+        let base_expr_span = base_expr.span();
         match base_expr.data {
             BaseExprData::Simple { expr: rec_expr } => {
                 let rec_expr_typed = check_type_rec(rec_expr, env, func_env)?;
                 let rec_expr_type = rec_expr_typed.generic_data.clone();
-                typed_base_expressions.push(BaseExpr {
-                    data: BaseExprData::Simple {
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::Simple {
                         expr: rec_expr_typed,
                     },
-                    row: base_expr.row,
-                    col_start: base_expr.col_start,
-                    col_end: base_expr.col_end,
-                    generic_data: rec_expr_type,
-                });
+                    base_expr_span,
+                    rec_expr_type,
+                ));
             }
             BaseExprData::VariableAssignment { var_name, expr } => {
                 let expr_typed = check_type_rec(expr, env, func_env)?;
                 let expr_type = expr_typed.generic_data.clone();
                 update_or_add_in_scope(&expr_type, &var_name, env.scopes.last_mut().unwrap());
                 if print_results {
-                    println!("Variable '{}' has type {:?}", var_name, expr_type);
+                    println!("Variable '{}' has type {}", var_name, expr_type);
                 }
-                typed_base_expressions.push(BaseExpr {
-                    data: BaseExprData::VariableAssignment {
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::VariableAssignment {
                         var_name: var_name.clone(),
                         expr: expr_typed,
                     },
-                    row: base_expr.row,
-                    col_start: base_expr.col_start,
-                    col_end: base_expr.col_end,
-                    generic_data: Type::Undefined, // We do not store the type of variable assignments
-                });
+                    base_expr_span,
+                    Type::Undefined, // We do not store the type of variable assignments,
+                ));
+            }
+            BaseExprData::DestructuringAssignment { var_names, expr } => {
+                let expr_span = expr.span();
+                let expr_typed = check_type_rec(expr, env, func_env)?;
+
+                // A tuple has its number of elements in its type, a list is checked when the program runs
+                let element_types = match &expr_typed.generic_data {
+                    Type::Tuple(element_types) if element_types.len() == var_names.len() => element_types.clone(),
+                    Type::Tuple(element_types) => {
+                        return Err(Error::located(
+                            format!(
+                                "Cannot assign {} values to {} variables",
+                                element_types.len(),
+                                var_names.len()
+                            ),
+                            expr_span,
+                        ))
+                    }
+                    Type::List(_) => destructured_types(&expr_typed.generic_data, var_names.len()),
+                    other_type => {
+                        return Err(Error::located(
+                            format!(
+                                "Cannot assign a value of type {} to {} variables, only a tuple or list can be split",
+                                other_type,
+                                var_names.len()
+                            ),
+                            expr_span,
+                        ))
+                    }
+                };
+
+                for (var_name, element_type) in var_names.iter().zip(&element_types) {
+                    update_or_add_in_scope(element_type, var_name, env.scopes.last_mut().unwrap());
+                    if print_results {
+                        println!("Variable '{}' has type {}", var_name, element_type);
+                    }
+                }
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::DestructuringAssignment {
+                        var_names,
+                        expr: expr_typed,
+                    },
+                    base_expr_span,
+                    Type::Undefined, // We do not store the type of destructuring assignments,
+                ));
             }
             BaseExprData::FunctionDefinition {
                 fun_name,
                 args,
                 body,
             } => {
-                // We don't need to do anything here, as functions are handled separately at the start of type-checking
-                // They will also not be included in the list of typed base expressions returned
+                // Functions at the top level are handled separately at the start of type-checking, and are not
+                // included in the list of typed base expressions returned.
+                // A function defined inside a function is checked when it is called, with the types the
+                // variables it captures have here. Its instances are in the list of functions, and it stays in the
+                // body as a definition without parameters or content, which marks where it captures those variables.
+                if !env.instances_in_progress.is_empty() {
+                    let param_names = parser::parameter_names(&args);
+                    let default_names = args
+                        .iter()
+                        .filter_map(|arg| arg.default.as_ref())
+                        .flat_map(visitor::referenced_names_in_expression);
+                    let captured = visitor::referenced_names(&body)
+                        .into_iter()
+                        .chain(default_names)
+                        .filter(|name| !param_names.contains(name))
+                        .filter_map(|name| find_in_env(&name, env).map(|value_type| (name, value_type)))
+                        .fold(Vec::new(), |mut captured: Vec<(String, Type)>, binding| {
+                            if !captured.iter().any(|(name, _)| *name == binding.0) {
+                                captured.push(binding);
+                            }
+                            captured
+                        });
+                    env.local_functions.retain(|function| function.name != fun_name);
+                    env.local_functions.push(FunctionBinding {
+                        name: fun_name.clone(),
+                        params: args,
+                        content: body,
+                        captured,
+                    });
+                    typed_base_expressions.push(BaseExpr::new(
+                        BaseExprData::FunctionDefinition {
+                            fun_name,
+                            args: Vec::new(),
+                            body: Vec::new(),
+                        },
+                        base_expr_span,
+                        Type::Undefined,
+                    ));
+                }
+            }
+            BaseExprData::FieldAssignment {
+                object,
+                path,
+                field,
+                expr,
+            } => {
+                let expr_typed = check_type_rec(expr, env, func_env)?;
+                let expr_type = expr_typed.generic_data.clone();
+
+                let Some(variable_type) = find_in_env(&object, env) else {
+                    return Err(Error::located(
+                        format!("Variable '{}' is not defined", object),
+                        base_expr_span,
+                    ));
+                };
+                let (path_typed, object_type) = check_type_path(path, &variable_type, env, func_env)?;
+                let Type::Struct(struct_type) = &object_type else {
+                    return Err(Error::located(
+                        format!(
+                            "Cannot assign to field '{}' of a value of type {}",
+                            field,
+                            type_to_string(&object_type)
+                        ),
+                        base_expr_span,
+                    ));
+                };
+                let Some((_, field_type)) = struct_type.fields.iter().find(|(field_name, _)| *field_name == field)
+                else {
+                    return Err(Error::located(
+                        format!("Struct '{}' has no field '{}'", struct_type.name, field),
+                        base_expr_span,
+                    ));
+                };
+
+                // The type of a struct is fixed when it is constructed, so the field keeps its type
+                if *field_type != expr_type {
+                    return Err(Error::type_error(
+                        format!(
+                            "Cannot assign a value of type {} to field '{}' of struct '{}', which is of type {}",
+                            type_to_string(&expr_type),
+                            field,
+                            struct_type.name,
+                            type_to_string(field_type)
+                        ),
+                        field_type.clone(),
+                        expr_type,
+                        expr_typed.span(),
+                    ));
+                }
+
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::FieldAssignment {
+                        object,
+                        path: path_typed,
+                        field,
+                        expr: expr_typed,
+                    },
+                    base_expr_span,
+                    Type::Undefined,
+                ));
+            }
+            BaseExprData::IndexAssignment {
+                object,
+                path,
+                index,
+                expr,
+            } => {
+                let Some(variable_type) = find_in_env(&object, env) else {
+                    return Err(Error::located(
+                        format!("Variable '{}' is not defined", object),
+                        base_expr_span,
+                    ));
+                };
+                let (path_typed, object_type) = check_type_path(path, &variable_type, env, func_env)?;
+                let index_typed = check_type_rec(index, env, func_env)?;
+                let index_type = index_typed.generic_data.clone();
+                let expr_typed = check_type_rec(expr, env, func_env)?;
+                let expr_type = expr_typed.generic_data.clone();
+
+                // The type the variable has with the new element, an empty list or dict takes it from the element
+                let (expected_index_type, assigned_type) = match &object_type {
+                    Type::List(_) => (
+                        Type::Integer,
+                        Type::List(Box::new(expr_type.clone())),
+                    ),
+                    Type::Dict(key_type, _) if **key_type == Type::Undefined => (
+                        index_type.clone(),
+                        Type::Dict(Box::new(index_type.clone()), Box::new(expr_type.clone())),
+                    ),
+                    Type::Dict(key_type, _) => (
+                        *key_type.clone(),
+                        Type::Dict(key_type.clone(), Box::new(expr_type.clone())),
+                    ),
+                    other_type => {
+                        return Err(Error::located(
+                            format!(
+                                "Cannot assign to an element of a value of type {}",
+                                type_to_string(other_type)
+                            ),
+                            base_expr_span,
+                        ));
+                    }
+                };
+
+                if index_type != expected_index_type {
+                    let message = match object_type {
+                        Type::Dict(..) => "Dict key is of the wrong type",
+                        _ => "List index must be an integer",
+                    };
+                    return Err(Error::type_error(
+                        message.to_string(),
+                        expected_index_type,
+                        index_type,
+                        index_typed.span(),
+                    ));
+                }
+                if matches!(object_type, Type::Dict(..)) && !is_dict_key_type(&index_type) {
+                    return Err(Error::located(
+                        format!(
+                            "Dict keys must be integers, booleans or strings, found {}",
+                            type_to_string(&index_type)
+                        ),
+                        index_typed.span(),
+                    ));
+                }
+
+                // Through a path the variable takes the new type of the list or dict at its end
+                let new_variable_type = merge_element_types(&object_type, &assigned_type)
+                    .and_then(|merged_type| type_with_element_at_path(&variable_type, &path_typed, merged_type));
+                let Some(new_variable_type) = new_variable_type else {
+                    let element_type = match &object_type {
+                        Type::List(element_type) | Type::Dict(_, element_type) => *element_type.clone(),
+                        other_type => other_type.clone(),
+                    };
+                    return Err(Error::type_error(
+                        format!(
+                            "Cannot assign a value of type {} to an element of '{}{}', which is of type {}",
+                            type_to_string(&expr_type),
+                            object,
+                            decompiler::path_to_source(&path_typed),
+                            type_to_string(&object_type)
+                        ),
+                        element_type,
+                        expr_type,
+                        expr_typed.span(),
+                    ));
+                };
+                update_in_env(&new_variable_type, &object, env);
+
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::IndexAssignment {
+                        object,
+                        path: path_typed,
+                        index: index_typed,
+                        expr: expr_typed,
+                    },
+                    base_expr_span,
+                    Type::Undefined,
+                ));
+            }
+            BaseExprData::StructDefinition { name, fields } => {
+                // A later definition with the same name replaces the earlier one, as in the interpreter
+                env.structs.retain(|struct_binding| struct_binding.name != name);
+                env.structs.push(StructBinding {
+                    name: name.clone(),
+                    fields: fields.clone(),
+                });
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::StructDefinition { name, fields },
+                    base_expr_span,
+                    Type::Undefined,
+                ));
             }
             BaseExprData::IfStatement {
                 condition,
                 body,
                 else_statement,
             } => {
-                let condition_row = condition.row;
-                let condition_col_start = condition.col_start;
-                let condition_col_end = condition.col_end;
+                let condition_span = condition.span();
 
                 let cond_typed = check_type_rec(condition, env, func_env)?;
                 let cond_type = cond_typed.generic_data.clone();
 
                 if cond_type != Type::Boolean {
-                    return Err(Error::TypeError {
-                        message: "If condition must be of type Boolean".to_string(),
-                        expected: Type::Boolean,
-                        found: cond_type,
-                        row: condition_row,
-                        col_start: condition_col_start,
-                        col_end: condition_col_end,
-                    });
+                    return Err(Error::type_error(
+                        "If condition must be of type Boolean".to_string(),
+                        Type::Boolean,
+                        cond_type,
+                        condition_span,
+                    ));
                 }
 
                 // Typecheck the body in a new scope
@@ -446,39 +1051,33 @@ fn type_check(
                     }
                     None => None,
                 };
-                typed_base_expressions.push(BaseExpr {
-                    data: BaseExprData::IfStatement {
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::IfStatement {
                         condition: cond_typed,
                         body: body_typed,
                         else_statement: else_typed,
                     },
-                    row: base_expr.row,
-                    col_start: base_expr.col_start,
-                    col_end: base_expr.col_end,
-                    generic_data: Type::Undefined, // We do not store the type of if statements
-                });
+                    base_expr_span,
+                    Type::Undefined, // We do not store the type of if statements,
+                ));
             }
             BaseExprData::ElseIfStatement {
                 condition,
                 body,
                 else_statement,
             } => {
-                let condition_row = condition.row;
-                let condition_col_start = condition.col_start;
-                let condition_col_end = condition.col_end;
+                let condition_span = condition.span();
 
                 let cond_typed = check_type_rec(condition, env, func_env)?;
                 let cond_type = cond_typed.generic_data.clone();
 
                 if cond_type != Type::Boolean {
-                    return Err(Error::TypeError {
-                        message: "If condition must be of type Boolean".to_string(),
-                        expected: Type::Boolean,
-                        found: cond_type,
-                        row: condition_row,
-                        col_start: condition_col_start,
-                        col_end: condition_col_end,
-                    });
+                    return Err(Error::type_error(
+                        "If condition must be of type Boolean".to_string(),
+                        Type::Boolean,
+                        cond_type,
+                        condition_span,
+                    ));
                 }
 
                 // Typecheck the body in a new scope
@@ -503,17 +1102,15 @@ fn type_check(
                     }
                     None => None,
                 };
-                typed_base_expressions.push(BaseExpr {
-                    data: BaseExprData::ElseIfStatement {
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::ElseIfStatement {
                         condition: cond_typed,
                         body: body_typed,
                         else_statement: else_typed,
                     },
-                    row: base_expr.row,
-                    col_start: base_expr.col_start,
-                    col_end: base_expr.col_end,
-                    generic_data: Type::Undefined, // We do not store the type of if statements
-                });
+                    base_expr_span,
+                    Type::Undefined, // We do not store the type of if statements,
+                ));
             }
             BaseExprData::ElseStatement { body } => {
                 // Typecheck the body in a new scope
@@ -522,13 +1119,11 @@ fn type_check(
                     type_check(body, env, func_env, print_results, expected_return_type)?.0;
                 env.scopes.pop();
 
-                typed_base_expressions.push(BaseExpr {
-                    data: BaseExprData::ElseStatement { body: body_typed },
-                    row: base_expr.row,
-                    col_start: base_expr.col_start,
-                    col_end: base_expr.col_end,
-                    generic_data: Type::Undefined, // We do not store the type of else statements
-                });
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::ElseStatement { body: body_typed },
+                    base_expr_span,
+                    Type::Undefined, // We do not store the type of else statements,
+                ));
             }
             BaseExprData::Return {
                 return_value: optional_return_value,
@@ -536,17 +1131,18 @@ fn type_check(
                 let return_value = match optional_return_value {
                     Some(rv) => rv,
                     None => {
-                        // Define the function to return undefined (no return value)
-                        *expected_return_type = Some(Type::Undefined);
+                        // Define the function to return undefined (no return value),
+                        // unless it is a generator, in which case return just ends the generator
+                        if !matches!(expected_return_type, Some(Type::Generator(_))) {
+                            *expected_return_type = Some(Type::Undefined);
+                        }
 
                         // Continue on to the next statement
                         continue;
                     }
                 };
 
-                let return_value_row = return_value.row;
-                let return_value_col_start = return_value.col_start;
-                let return_value_col_end = return_value.col_end;
+                let return_value_span = return_value.span();
 
                 // There is a return value
                 // Therefore we type-check it and compare it to the expected return type
@@ -557,56 +1153,54 @@ fn type_check(
                 match &expected_return_type {
                     Some(expected_type) => {
                         if *expected_type != return_type {
-                            return Err(Error::TypeError {
-                                message: "Return type does not match expected return type"
+                            return Err(Error::type_error(
+                                "Return type does not match expected return type"
                                     .to_string(),
-                                expected: expected_type.clone(),
-                                found: return_type,
-                                row: return_value_row,
-                                col_start: return_value_col_start,
-                                col_end: return_value_col_end,
-                            });
+                                expected_type.clone(),
+                                return_type,
+                                return_value_span,
+                            ));
                         }
                     }
                     None => {
                         // If there was no expected return type, we set it to the current return type
                         *expected_return_type = Some(return_type.clone());
+                        if let Some(instance) = env.instances_in_progress.last_mut() {
+                            instance.2 = Some(return_type.clone());
+                        }
                     }
                 }
 
-                typed_base_expressions.push(BaseExpr {
-                    data: BaseExprData::Return {
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::Return {
                         return_value: Some(return_typed),
                     },
-                    row: base_expr.row,
-                    col_start: base_expr.col_start,
-                    col_end: base_expr.col_end,
-                    generic_data: return_type,
-                });
+                    base_expr_span,
+                    return_type,
+                ));
             }
             BaseExprData::ForLoop {
                 var_name,
                 until,
                 body,
             } => {
-                let until_row = until.row;
-                let until_col_start = until.col_start;
-                let until_col_end = until.col_end;
+                let until_span = until.span();
 
                 let iteration_typed = check_type_rec(until, env, func_env)?;
                 let iteration_variable_type = match iteration_typed.generic_data.clone() {
                     Type::Integer => Type::Integer,
                     Type::List(list_type) => *list_type,
+                    Type::Generator(yielded_type) => *yielded_type,
+                    Type::Bytes => Type::Integer,
+                    Type::Dict(key_type, _) => *key_type,
                     other_type => {
-                        return Err(Error::LocationError {
-                            message: format!(
-                                "For loop iteration cannot be of type {:?}",
+                        return Err(Error::located(
+                            format!(
+                                "For loop iteration cannot be of type {}",
                                 other_type
                             ),
-                            row: until_row,
-                            col_start: until_col_start,
-                            col_end: until_col_end,
-                        });
+                            until_span,
+                        ));
                     }
                 };
 
@@ -617,426 +1211,1100 @@ fn type_check(
                     &var_name,
                     env.scopes.last_mut().unwrap(),
                 );
+                env.loop_depth += 1;
                 let body_typed =
                     type_check(body, env, func_env, print_results, expected_return_type)?.0;
+                env.loop_depth -= 1;
                 env.scopes.pop();
 
-                typed_base_expressions.push(BaseExpr {
-                    data: BaseExprData::ForLoop {
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::ForLoop {
                         var_name: var_name.clone(),
                         until: iteration_typed,
                         body: body_typed,
                     },
-                    row: base_expr.row,
-                    col_start: base_expr.col_start,
-                    col_end: base_expr.col_end,
-                    generic_data: Type::Undefined, // We do not store the type of for loops
-                });
+                    base_expr_span,
+                    Type::Undefined, // We do not store the type of for loops,
+                ));
+            }
+            BaseExprData::WhileLoop { condition, body } => {
+                let condition_span = condition.span();
+
+                let cond_typed = check_type_rec(condition, env, func_env)?;
+                let cond_type = cond_typed.generic_data.clone();
+
+                if cond_type != Type::Boolean {
+                    return Err(Error::type_error(
+                        "While condition must be of type Boolean".to_string(),
+                        Type::Boolean,
+                        cond_type,
+                        condition_span,
+                    ));
+                }
+
+                // Typecheck the body in a new scope
+                env.scopes.push(Vec::new());
+                env.loop_depth += 1;
+                let body_typed =
+                    type_check(body, env, func_env, print_results, expected_return_type)?.0;
+                env.loop_depth -= 1;
+                env.scopes.pop();
+
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::WhileLoop {
+                        condition: cond_typed,
+                        body: body_typed,
+                    },
+                    base_expr_span,
+                    Type::Undefined, // We do not store the type of while loops,
+                ));
             }
             BaseExprData::Break => {
-                typed_base_expressions.push(BaseExpr {
-                    data: BaseExprData::Break,
-                    row: base_expr.row,
-                    col_start: base_expr.col_start,
-                    col_end: base_expr.col_end,
-                    generic_data: Type::Undefined, // We do not store the type of break statements
-                });
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::Break,
+                    base_expr_span,
+                    Type::Undefined, // We do not store the type of break statements,
+                ));
             }
-            _ => {
-                unimplemented!(
-                    "Only RecExpr is implemented in type_check, not {:?}",
-                    base_expr.data
-                );
+            BaseExprData::Continue => {
+                if env.loop_depth == 0 {
+                    return Err(Error::located(
+                        "Cannot continue outside of a loop".to_string(),
+                        base_expr_span,
+                    ));
+                }
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::Continue,
+                    base_expr_span,
+                    Type::Undefined, // We do not store the type of continue statements,
+                ));
             }
-        }
-    }
-    print_type_env(&env);
-
-    // If we have an expected return type, we return it
-    Ok((typed_base_expressions, env.functions.clone()))
-}
+            BaseExprData::With {
+                resource,
+                var_name,
+                body,
+            } => {
+                let resource_span = resource.span();
+
+                let resource_typed = check_type_rec(resource, env, func_env)?;
+                if resource_typed.generic_data != Type::Resource {
+                    return Err(Error::type_error(
+                        "A with block needs a resource".to_string(),
+                        Type::Resource,
+                        resource_typed.generic_data,
+                        resource_span,
+                    ));
+                }
 
-// This function allows entry into type-checking a single rec-expr from a test
-pub fn get_type(base_expr: BaseExpr<()>) -> Result<BaseExpr<Type>, Error> {
-    let mut env: TypeEnvironment = TypeEnvironment {
-        scopes: Vec::new(),
-        functions: Vec::new(),
-    };
+                // Typechecking the body with the resource included in the scope
+                env.scopes.push(Vec::new());
+                update_or_add_in_scope(&Type::Resource, &var_name, env.scopes.last_mut().unwrap());
+                let body_typed =
+                    type_check(body, env, func_env, print_results, expected_return_type)?.0;
+                env.scopes.pop();
 
-    env.scopes.push(Vec::new());
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::With {
+                        resource: resource_typed,
+                        var_name,
+                        body: body_typed,
+                    },
+                    base_expr_span,
+                    Type::Undefined, // We do not store the type of with blocks,
+                ));
+            }
+            BaseExprData::Match { subject, arms } => {
+                let subject_typed = check_type_rec(subject, env, func_env)?;
+                let subject_type = subject_typed.generic_data.clone();
+
+                let mut arms_typed = Vec::new();
+                for (i, arm) in arms.into_iter().enumerate() {
+                    let arm_span = arm.span();
+
+                    let pattern_type = match &arm.pattern {
+                        Pattern::Number(_) => Some(Type::Integer),
+                        Pattern::String(_) => Some(Type::String),
+                        Pattern::Boolean(_) => Some(Type::Boolean),
+                        Pattern::Binding(_) | Pattern::Wildcard => None,
+                    };
+                    if let Some(pattern_type) = pattern_type {
+                        if pattern_type != subject_type {
+                            return Err(Error::type_error(
+                                "Case pattern does not match the type of the value being matched"
+                                    .to_string(),
+                                subject_type,
+                                pattern_type,
+                                arm_span,
+                            ));
+                        }
+                    }
 
-    add_default_functions_to_env(&mut env);
+                    // An earlier case with the same pattern, or one matching every value, always goes first
+                    let is_unreachable = arms_typed.iter().any(|earlier: &MatchArm<Type>| {
+                        matches!(earlier.pattern, Pattern::Binding(_) | Pattern::Wildcard)
+                            || earlier.pattern == arm.pattern
+                    });
+                    if is_unreachable {
+                        return Err(Error::located(
+                            format!("Case {} can never be reached, an earlier case matches all of its values", i + 1),
+                            arm_span,
+                        ));
+                    }
 
-    let func_env: FunctionEnvironment = Vec::new();
+                    // Typechecking the body with the bound value included in the scope
+                    env.scopes.push(Vec::new());
+                    if let Pattern::Binding(name) = &arm.pattern {
+                        update_or_add_in_scope(&subject_type, name, env.scopes.last_mut().unwrap());
+                    }
+                    let body_typed =
+                        type_check(arm.body, env, func_env, print_results, expected_return_type)?.0;
+                    env.scopes.pop();
 
-    match base_expr.data {
-        BaseExprData::Simple { expr: rec_expr } => {
-            let rec_expr_typed = check_type_rec(rec_expr, &mut env, &func_env)?;
-            let rec_expr_type = rec_expr_typed.generic_data.clone();
-            return Ok(BaseExpr {
-                data: BaseExprData::Simple {
-                    expr: rec_expr_typed,
-                },
-                row: base_expr.row,
-                col_start: base_expr.col_start,
-                col_end: base_expr.col_end,
-                generic_data: rec_expr_type,
-            });
-        }
-        _ => {
-            unimplemented!("Only RecExpr is implemented in get_type");
-        }
-    }
-}
+                    arms_typed.push(MatchArm::new(arm.pattern, body_typed, arm_span));
+                }
 
-fn check_type_rec(
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::Match {
+                        subject: subject_typed,
+                        arms: arms_typed,
+                    },
+                    base_expr_span,
+                    Type::Undefined, // We do not store the type of match statements,
+                ));
+            }
+            BaseExprData::Defer { expr } => {
+                let expr_typed = check_type_rec(expr, env, func_env)?;
+
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::Defer { expr: expr_typed },
+                    base_expr_span,
+                    Type::Undefined, // We do not store the type of defer statements,
+                ));
+            }
+            BaseExprData::Delete { var_name } => {
+                if !remove_from_env(&var_name, env) {
+                    return Err(Error::located(
+                        format!("Cannot delete {}, it is not defined", var_name),
+                        base_expr_span,
+                    ));
+                }
+
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::Delete { var_name },
+                    base_expr_span,
+                    Type::Undefined, // We do not store the type of delete statements,
+                ));
+            }
+            BaseExprData::Swap {
+                first_name,
+                second_name,
+            } => {
+                let mut types = Vec::new();
+                for name in [&first_name, &second_name] {
+                    match find_in_env(name, env) {
+                        Some(var_type) => types.push(var_type),
+                        None => {
+                            return Err(Error::located(
+                                format!("Cannot swap {}, it is not defined", name),
+                                base_expr_span,
+                            ))
+                        }
+                    }
+                }
+
+                // The variables take each other's type along with their value
+                update_in_env(&types[1], &first_name, env);
+                update_in_env(&types[0], &second_name, env);
+
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::Swap {
+                        first_name,
+                        second_name,
+                    },
+                    base_expr_span,
+                    Type::Undefined, // We do not store the type of swap statements,
+                ));
+            }
+            BaseExprData::Yield { value } => {
+                let value_span = value.span();
+
+                // A function which yields is a generator, so its return type is a generator of the yielded type
+                let value_typed = check_type_rec(value, env, func_env)?;
+                let generator_type = Type::Generator(Box::new(value_typed.generic_data.clone()));
+
+                match &expected_return_type {
+                    None | Some(Type::Undefined) => {
+                        *expected_return_type = Some(generator_type.clone());
+                    }
+                    Some(expected_type) => {
+                        if *expected_type != generator_type {
+                            return Err(Error::type_error(
+                                "Yielded type does not match the other yields and returns of this function"
+                                    .to_string(),
+                                expected_type.clone(),
+                                generator_type,
+                                value_span,
+                            ));
+                        }
+                    }
+                }
+
+                typed_base_expressions.push(BaseExpr::new(
+                    BaseExprData::Yield { value: value_typed },
+                    base_expr_span,
+                    generator_type,
+                ));
+            }
+            _ => {
+                unimplemented!(
+                    "Only RecExpr is implemented in type_check, not {:?}",
+                    base_expr.data
+                );
+            }
+        }
+    }
+    tracing::trace!("{}", type_env_to_string(env));
+
+    // If we have an expected return type, we return it
+    Ok((typed_base_expressions, env.functions.clone()))
+}
+
+// This function allows entry into type-checking a single rec-expr from a test
+pub fn get_type(base_expr: BaseExpr<()>) -> Result<BaseExpr<Type>, Error> {
+    let mut env: TypeEnvironment = TypeEnvironment {
+        scopes: Vec::new(),
+        functions: Vec::new(),
+        structs: Vec::new(),
+        options: LanguageOptions::default(),
+        loop_depth: 0,
+        instances_in_progress: Vec::new(),
+        local_functions: Vec::new(),
+    };
+
+    env.scopes.push(Vec::new());
+
+    let func_env: FunctionEnvironment = Vec::new();
+
+    let base_expr_span = base_expr.span();
+    match base_expr.data {
+        BaseExprData::Simple { expr: rec_expr } => {
+            let rec_expr_typed = check_type_rec(rec_expr, &mut env, &func_env)?;
+            let rec_expr_type = rec_expr_typed.generic_data.clone();
+            return Ok(BaseExpr::new(
+                BaseExprData::Simple {
+                    expr: rec_expr_typed,
+                },
+                base_expr_span,
+                rec_expr_type,
+            ));
+        }
+        _ => {
+            unimplemented!("Only RecExpr is implemented in get_type");
+        }
+    }
+}
+
+// Typechecks a single expression in which the given variables are defined, as Engine::eval_with does.
+// Only builtins can be called, as there is no program defining functions around the expression.
+pub fn type_check_expression(
+    expr: RecExpr<()>,
+    variables: &[(String, Type)],
+    options: &LanguageOptions,
+) -> Result<RecExpr<Type>, Error> {
+    let mut env: TypeEnvironment = TypeEnvironment {
+        scopes: vec![variables
+            .iter()
+            .map(|(name, value_type)| TypeBinding {
+                name: name.clone(),
+                value_type: value_type.clone(),
+            })
+            .collect()],
+        functions: Vec::new(),
+        structs: Vec::new(),
+        options: options.clone(),
+        loop_depth: 0,
+        instances_in_progress: Vec::new(),
+        local_functions: Vec::new(),
+    };
+
+    check_type_rec(expr, &mut env, &Vec::new())
+}
+
+// The type of a value given by the host, or None for values such as functions which have no type in a program.
+// The element types of a list or dict are merged as for a literal, an empty one has undefined element types.
+pub fn value_to_type(value: &Value) -> Option<Type> {
+    let merged_type = |values: &mut dyn Iterator<Item = &Value>| -> Option<Type> {
+        let mut merged = Type::Undefined;
+        for value in values {
+            let value_type = value_to_type(value)?;
+            merged = match merged {
+                Type::Undefined => value_type,
+                merged => merge_element_types(&merged, &value_type)?,
+            };
+        }
+        Some(merged)
+    };
+
+    Some(match value {
+        Value::Number(_) => Type::Integer,
+        Value::Float(_) => Type::Float,
+        Value::Bool(_) => Type::Boolean,
+        Value::String(_) => Type::String,
+        Value::Bytes(_) => Type::Bytes,
+        Value::Rational(_) => Type::Rational,
+        Value::Complex(_) => Type::Complex,
+        Value::Resource(_) => Type::Resource,
+        Value::List(values) => Type::List(Box::new(merged_type(&mut values.iter())?)),
+        // What a generator yields is only known by running it
+        Value::Generator(_) => return None,
+        Value::Tuple(values) => Type::Tuple(values.iter().map(value_to_type).collect::<Option<Vec<Type>>>()?),
+        Value::Frozen(value) => return value_to_type(value),
+        Value::Dict(entries) => {
+            let keys: Vec<Value> = entries.keys().map(|key| key.to_value()).collect();
+            Type::Dict(
+                Box::new(merged_type(&mut keys.iter())?),
+                Box::new(merged_type(&mut entries.values())?),
+            )
+        }
+        Value::Struct { name, fields } => Type::Struct(Box::new(StructType {
+            name: name.clone(),
+            fields: fields
+                .iter()
+                .map(|(field_name, value)| Some((field_name.clone(), value_to_type(value)?)))
+                .collect::<Option<Vec<(String, Type)>>>()?,
+        })),
+        Value::Function { .. } | Value::StandardFunction(_) | Value::StructDefinition { .. } | Value::Error(_) => {
+            return None
+        }
+    })
+}
+
+// Arithmetic with a rational number gives a rational number when the other operand is an integer or rational.
+// Floats are inexact, so they cannot be mixed with rational numbers.
+fn rational_result_type(left: &Type, right: &Type) -> Option<Type> {
+    match (left, right) {
+        (Type::Rational, Type::Rational | Type::Integer) | (Type::Integer, Type::Rational) => {
+            return Some(Type::Rational);
+        }
+        _ => return None,
+    }
+}
+
+// Arithmetic with a complex number gives a complex number when the other operand is any number
+fn complex_result_type(left: &Type, right: &Type) -> Option<Type> {
+    match (left, right) {
+        (Type::Complex, other) | (other, Type::Complex)
+            if is_number_type(other) || *other == Type::Complex =>
+        {
+            return Some(Type::Complex);
+        }
+        _ => return None,
+    }
+}
+
+// The numbers that can be ordered, complex numbers cannot
+fn is_number_type(value_type: &Type) -> bool {
+    return matches!(value_type, Type::Integer | Type::Float | Type::Rational);
+}
+
+// Arithmetic on a float gives a float, also when the other operand is an integer
+fn is_float_arithmetic(left: &Type, right: &Type) -> bool {
+    matches!(
+        (left, right),
+        (Type::Float, Type::Float) | (Type::Integer, Type::Float) | (Type::Float, Type::Integer)
+    )
+}
+
+// With strict numerics an integer is not converted to a float implicitly.
+// The error points at the integer operand, which has to be converted with float() instead.
+fn check_implicit_conversion(
+    left: &RecExpr<Type>,
+    right: &RecExpr<Type>,
+    options: &LanguageOptions,
+) -> Result<(), Error> {
+    if !options.strict_numerics {
+        return Ok(());
+    }
+
+    let integer_operand = match (&left.generic_data, &right.generic_data) {
+        (Type::Integer, Type::Float) => left,
+        (Type::Float, Type::Integer) => right,
+        _ => return Ok(()),
+    };
+
+    Err(Error::type_error(
+        "Implicit conversion of an integer to a float, convert it with float()".to_string(),
+        Type::Float,
+        Type::Integer,
+        integer_operand.span(),
+    ))
+}
+
+fn is_float_rational_mix(left: &Type, right: &Type) -> bool {
+    return matches!(
+        (left, right),
+        (Type::Float, Type::Rational) | (Type::Rational, Type::Float)
+    );
+}
+
+// The common type of two list elements, where empty nested lists fit any list type:
+// [[1], []] is a List(List(Integer))
+// Only values of these types can be the key of a dict, see interpreter::DictKey
+fn is_dict_key_type(key_type: &Type) -> bool {
+    matches!(key_type, Type::Integer | Type::Boolean | Type::String)
+}
+
+// Typechecks the indices along the path of a nested assignment, such as the [i] of grid[i][j] = 0,
+// and gives the type of the struct, list or dict the path leads to from a variable of the type
+fn check_type_path(
+    path: Vec<Accessor<()>>,
+    variable_type: &Type,
+    env: &mut TypeEnvironment,
+    func_env: &FunctionEnvironment,
+) -> Result<(Vec<Accessor<Type>>, Type), Error> {
+    let mut path_typed = Vec::new();
+    let mut current_type = variable_type.clone();
+    for accessor in path {
+        match accessor {
+            Accessor::Field { name, span } => {
+                let Type::Struct(struct_type) = &current_type else {
+                    return Err(Error::located(
+                        format!("Cannot access field '{}' of a value of type {}", name, current_type),
+                        span,
+                    ));
+                };
+                let Some((_, field_type)) = struct_type.fields.iter().find(|(field_name, _)| *field_name == name)
+                else {
+                    return Err(Error::located(
+                        format!("Struct '{}' has no field '{}'", struct_type.name, name),
+                        span,
+                    ));
+                };
+                current_type = field_type.clone();
+                path_typed.push(Accessor::Field { name, span });
+            }
+            Accessor::Index(index) => {
+                let index_typed = check_type_rec(index, env, func_env)?;
+                let index_type = index_typed.generic_data.clone();
+                let (expected_index_type, element_type, message) = match &current_type {
+                    Type::List(element_type) => (Type::Integer, *element_type.clone(), "List index must be an integer"),
+                    Type::Dict(key_type, value_type) => {
+                        (*key_type.clone(), *value_type.clone(), "Dict key is of the wrong type")
+                    }
+                    other_type => {
+                        return Err(Error::located(
+                            format!("Cannot assign to an element of a value of type {}", other_type),
+                            index_typed.span(),
+                        ))
+                    }
+                };
+                if index_type != expected_index_type {
+                    return Err(Error::type_error(
+                        message.to_string(),
+                        expected_index_type,
+                        index_type,
+                        index_typed.span(),
+                    ));
+                }
+                current_type = element_type;
+                path_typed.push(Accessor::Index(index_typed));
+            }
+        }
+    }
+    Ok((path_typed, current_type))
+}
+
+// The type of a variable after the list or dict at the end of the path took the new type.
+// A field of a struct keeps the type it was constructed with, so None if the path changes one.
+fn type_with_element_at_path(variable_type: &Type, path: &[Accessor<Type>], new_type: Type) -> Option<Type> {
+    let Some((accessor, rest)) = path.split_first() else {
+        return Some(new_type);
+    };
+    match (variable_type, accessor) {
+        (Type::List(element_type), Accessor::Index(_)) => Some(Type::List(Box::new(type_with_element_at_path(
+            element_type,
+            rest,
+            new_type,
+        )?))),
+        (Type::Dict(key_type, value_type), Accessor::Index(_)) => Some(Type::Dict(
+            key_type.clone(),
+            Box::new(type_with_element_at_path(value_type, rest, new_type)?),
+        )),
+        (Type::Struct(struct_type), Accessor::Field { name, .. }) => {
+            let (_, field_type) = struct_type.fields.iter().find(|(field_name, _)| field_name == name)?;
+            let new_field_type = type_with_element_at_path(field_type, rest, new_type)?;
+            (new_field_type == *field_type).then(|| variable_type.clone())
+        }
+        _ => None,
+    }
+}
+
+fn merge_element_types(left: &Type, right: &Type) -> Option<Type> {
+    if left == right {
+        return Some(left.clone());
+    }
+
+    match (left, right) {
+        (Type::List(left_elem), Type::List(_)) if **left_elem == Type::Undefined => {
+            return Some(right.clone());
+        }
+        (Type::List(_), Type::List(right_elem)) if **right_elem == Type::Undefined => {
+            return Some(left.clone());
+        }
+        (Type::List(left_elem), Type::List(right_elem)) => {
+            return merge_element_types(left_elem, right_elem)
+                .map(|merged_type| Type::List(Box::new(merged_type)));
+        }
+        // An empty dict has undefined key and value types
+        (Type::Dict(left_key, _), Type::Dict(..)) if **left_key == Type::Undefined => {
+            return Some(right.clone());
+        }
+        (Type::Dict(..), Type::Dict(right_key, _)) if **right_key == Type::Undefined => {
+            return Some(left.clone());
+        }
+        (Type::Dict(left_key, left_value), Type::Dict(right_key, right_value)) => {
+            return Some(Type::Dict(
+                Box::new(merge_element_types(left_key, right_key)?),
+                Box::new(merge_element_types(left_value, right_value)?),
+            ));
+        }
+        // Tuples merge element by element, [(1, []), (2, [3])] is a list of tuple[integer, list[integer]]
+        (Type::Tuple(left_elems), Type::Tuple(right_elems)) if left_elems.len() == right_elems.len() => {
+            return left_elems
+                .iter()
+                .zip(right_elems)
+                .map(|(left_elem, right_elem)| merge_element_types(left_elem, right_elem))
+                .collect::<Option<Vec<Type>>>()
+                .map(Type::Tuple);
+        }
+        _ => return None,
+    }
+}
+
+// Nested expressions recurse once per level, see stack::ensure_stack.
+// Types are compared, cloned and written by recursing into their element types without that guard,
+// so an expression whose type nests deeper than MAX_TYPE_DEPTH is an error.
+fn check_type_rec(
+    rec_expr: RecExpr<()>,
+    env: &mut TypeEnvironment,
+    func_env: &FunctionEnvironment,
+) -> Result<RecExpr<Type>, Error> {
+    let rec_expr_span = rec_expr.span();
+    let typed = stack::ensure_stack(|| check_type_rec_unguarded(rec_expr, env, func_env))?;
+    if type_depth_exceeds(&typed.generic_data, MAX_TYPE_DEPTH) {
+        return Err(Error::located(
+            format!("Types cannot be nested more than {} levels deep", MAX_TYPE_DEPTH),
+            rec_expr_span,
+        ));
+    }
+    Ok(typed)
+}
+
+const MAX_TYPE_DEPTH: usize = 100;
+
+// Whether the type has more than max_depth levels of element types, walked without recursion
+fn type_depth_exceeds(value_type: &Type, max_depth: usize) -> bool {
+    let mut pending = Vec::from([(value_type, 0)]);
+    while let Some((value_type, depth)) = pending.pop() {
+        if depth > max_depth {
+            return true;
+        }
+        match value_type {
+            Type::List(element_type) | Type::Generator(element_type) | Type::Result(element_type) => {
+                pending.push((element_type, depth + 1))
+            }
+            Type::Dict(key_type, value_type) => {
+                pending.push((key_type, depth + 1));
+                pending.push((value_type, depth + 1));
+            }
+            Type::Tuple(element_types) => pending.extend(element_types.iter().map(|element_type| (element_type, depth + 1))),
+            Type::Struct(struct_type) => {
+                pending.extend(struct_type.fields.iter().map(|(_, field_type)| (field_type, depth + 1)))
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+fn check_type_rec_unguarded(
     rec_expr: RecExpr<()>,
     env: &mut TypeEnvironment,
     func_env: &FunctionEnvironment,
 ) -> Result<RecExpr<Type>, Error> {
-    let rec_expr_row = rec_expr.row;
-    let rec_expr_col_start = rec_expr.col_start;
-    let rec_expr_col_end = rec_expr.col_end;
+    let rec_expr_span = rec_expr.span();
 
     return match rec_expr.data {
-        RecExprData::Number { number } => Ok(RecExpr {
-            data: RecExprData::Number { number },
-            row: rec_expr_row,
-            col_start: rec_expr_col_start,
-            col_end: rec_expr_col_end,
-            generic_data: Type::Integer,
-        }),
-        RecExprData::Boolean { value } => Ok(RecExpr {
-            data: RecExprData::Boolean { value },
-            row: rec_expr_row,
-            col_start: rec_expr_col_start,
-            col_end: rec_expr_col_end,
-            generic_data: Type::Boolean,
-        }),
-        RecExprData::String { value } => Ok(RecExpr {
-            data: RecExprData::String { value },
-            row: rec_expr_row,
-            col_start: rec_expr_col_start,
-            col_end: rec_expr_col_end,
-            generic_data: Type::String,
-        }),
+        RecExprData::Number { number } => Ok(RecExpr::new(
+            RecExprData::Number { number },
+            rec_expr_span,
+            Type::Integer,
+        )),
+        RecExprData::Float { value } => Ok(RecExpr::new(
+            RecExprData::Float { value },
+            rec_expr_span,
+            Type::Float,
+        )),
+        RecExprData::Boolean { value } => Ok(RecExpr::new(
+            RecExprData::Boolean { value },
+            rec_expr_span,
+            Type::Boolean,
+        )),
+        RecExprData::String { value } => Ok(RecExpr::new(
+            RecExprData::String { value },
+            rec_expr_span,
+            Type::String,
+        )),
         RecExprData::List { elements } => {
             if elements.len() == 0 {
-                return Ok(RecExpr {
-                    data: RecExprData::List {
+                return Ok(RecExpr::new(
+                    RecExprData::List {
                         elements: Vec::new(),
                     },
-                    row: rec_expr_row,
-                    col_start: rec_expr_col_start,
-                    col_end: rec_expr_col_end,
-                    generic_data: Type::List(Box::new(Type::Undefined)),
-                });
+                    rec_expr_span,
+                    Type::List(Box::new(Type::Undefined)),
+                ));
             }
-            let first_elem_typed = check_type_rec(elements[0].clone(), env, func_env)?;
-            let first_elem_type = first_elem_typed.generic_data.clone();
+            // The elements are moved rather than cloned, cloning a deeply nested element recurses as deep as it is
+            let mut elements = elements.into_iter();
+            let first_elem_typed = check_type_rec(elements.next().unwrap(), env, func_env)?;
+            let mut first_elem_type = first_elem_typed.generic_data.clone();
             let mut typed_elements = Vec::<RecExpr<Type>>::new();
             typed_elements.push(first_elem_typed);
 
-            for elem in elements.iter().skip(1) {
-                let elem_typed = check_type_rec(elem.clone(), env, func_env)?;
+            for elem in elements {
+                let elem_span = elem.span();
+                let elem_typed = check_type_rec(elem, env, func_env)?;
                 let elem_type = elem_typed.generic_data.clone();
-                if elem_type != first_elem_type {
-                    return Err(Error::TypeError {
-                        message: "List elements must be of the same type".to_string(),
-                        expected: first_elem_type,
-                        found: elem_type,
-                        row: elem.row,
-                        col_start: elem.col_start,
-                        col_end: elem.col_end,
-                    });
-                }
+                first_elem_type = match merge_element_types(&first_elem_type, &elem_type) {
+                    Some(merged_type) => merged_type,
+                    None => {
+                        return Err(Error::type_error(
+                            "List elements must be of the same type".to_string(),
+                            first_elem_type,
+                            elem_type,
+                            elem_span,
+                        ));
+                    }
+                };
                 typed_elements.push(elem_typed);
             }
-            return Ok(RecExpr {
-                data: RecExprData::List {
+            return Ok(RecExpr::new(
+                RecExprData::List {
                     elements: typed_elements,
                 },
-                row: rec_expr_row,
-                col_start: rec_expr_col_start,
-                col_end: rec_expr_col_end,
-                generic_data: Type::List(Box::new(first_elem_type)),
-            });
+                rec_expr_span,
+                Type::List(Box::new(first_elem_type)),
+            ));
+        }
+        RecExprData::Dict { entries } => {
+            let mut key_type = Type::Undefined;
+            let mut value_type = Type::Undefined;
+            let mut typed_entries = Vec::new();
+
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                let key_typed = check_type_rec(key, env, func_env)?;
+                let value_typed = check_type_rec(value, env, func_env)?;
+
+                if !is_dict_key_type(&key_typed.generic_data) {
+                    return Err(Error::located(
+                        format!(
+                            "Dict keys must be integers, booleans or strings, found {}",
+                            type_to_string(&key_typed.generic_data)
+                        ),
+                        key_typed.span(),
+                    ));
+                }
+
+                // The first entry decides the types, the others must match them
+                if i == 0 {
+                    key_type = key_typed.generic_data.clone();
+                    value_type = value_typed.generic_data.clone();
+                } else {
+                    if key_typed.generic_data != key_type {
+                        return Err(Error::type_error(
+                            "Dict keys must be of the same type".to_string(),
+                            key_type,
+                            key_typed.generic_data.clone(),
+                            key_typed.span(),
+                        ));
+                    }
+                    value_type = match merge_element_types(&value_type, &value_typed.generic_data) {
+                        Some(merged_type) => merged_type,
+                        None => {
+                            return Err(Error::type_error(
+                                "Dict values must be of the same type".to_string(),
+                                value_type,
+                                value_typed.generic_data.clone(),
+                                value_typed.span(),
+                            ));
+                        }
+                    };
+                }
+
+                typed_entries.push((key_typed, value_typed));
+            }
+
+            return Ok(RecExpr::new(
+                RecExprData::Dict {
+                    entries: typed_entries,
+                },
+                rec_expr_span,
+                Type::Dict(Box::new(key_type), Box::new(value_type)),
+            ));
+        }
+        RecExprData::Tuple { elements } => {
+            let mut typed_elements = Vec::new();
+            for element in elements {
+                typed_elements.push(check_type_rec(element, env, func_env)?);
+            }
+            let element_types = typed_elements
+                .iter()
+                .map(|element| element.generic_data.clone())
+                .collect();
+
+            return Ok(RecExpr::new(
+                RecExprData::Tuple {
+                    elements: typed_elements,
+                },
+                rec_expr_span,
+                Type::Tuple(element_types),
+            ));
         }
         RecExprData::Add { left, right } => {
-            let row = left.row;
-            let left_col_start = left.col_start;
-            let right_col_end = right.col_end;
+            let span = left.span().merge(&right.span());
             let left_typed = check_type_rec(*left, env, func_env)?;
             let right_typed = check_type_rec(*right, env, func_env)?;
             let left_type = left_typed.generic_data.clone();
             let right_type = right_typed.generic_data.clone();
+            check_implicit_conversion(&left_typed, &right_typed, &env.options)?;
 
             if left_type == Type::Integer && right_type == Type::Integer {
-                return Ok(RecExpr {
-                    data: RecExprData::Add {
+                return Ok(RecExpr::new(
+                    RecExprData::Add {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::Integer,
-                });
-            } else if (left_type == Type::Integer || left_type == Type::Float)
-                && (right_type == Type::Integer || right_type == Type::Float)
-            {
-                return Ok(RecExpr {
-                    data: RecExprData::Add {
+                    span,
+                    Type::Integer,
+                ));
+            } else if let Some(result_type) = complex_result_type(&left_type, &right_type) {
+                return Ok(RecExpr::new(
+                    RecExprData::Add {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::Float,
-                });
+                    span,
+                    result_type,
+                ));
+            } else if let Some(result_type) = rational_result_type(&left_type, &right_type) {
+                return Ok(RecExpr::new(
+                    RecExprData::Add {
+                        left: Box::new(left_typed),
+                        right: Box::new(right_typed),
+                    },
+                    span,
+                    result_type,
+                ));
+            } else if is_float_arithmetic(&left_type, &right_type) {
+                return Ok(RecExpr::new(
+                    RecExprData::Add {
+                        left: Box::new(left_typed),
+                        right: Box::new(right_typed),
+                    },
+                    span,
+                    Type::Float,
+                ));
             } else if left_type == Type::String && right_type == Type::String {
-                return Ok(RecExpr {
-                    data: RecExprData::Add {
+                return Ok(RecExpr::new(
+                    RecExprData::Add {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::String,
-                });
+                    span,
+                    Type::String,
+                ));
             } else {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for addition".to_string(),
-                    expected: left_type,
-                    found: right_type,
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                });
+                return Err(Error::type_error(
+                    "Invalid operand types for addition".to_string(),
+                    left_type,
+                    right_type,
+                    span,
+                ));
             }
         }
         RecExprData::Multiply { left, right } => {
-            let row = left.row;
-            let left_col_start = left.col_start;
-            let right_col_end = right.col_end;
+            let span = left.span().merge(&right.span());
             let left_typed = check_type_rec(*left, env, func_env)?;
             let right_typed = check_type_rec(*right, env, func_env)?;
             let left_type = left_typed.generic_data.clone();
             let right_type = right_typed.generic_data.clone();
+            check_implicit_conversion(&left_typed, &right_typed, &env.options)?;
 
             if left_type == Type::Integer && right_type == Type::Integer {
-                return Ok(RecExpr {
-                    data: RecExprData::Multiply {
+                return Ok(RecExpr::new(
+                    RecExprData::Multiply {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::Integer,
-                });
-            } else if (left_type == Type::Integer || left_type == Type::Float)
-                && (right_type == Type::Integer || right_type == Type::Float)
-            {
-                return Ok(RecExpr {
-                    data: RecExprData::Multiply {
+                    span,
+                    Type::Integer,
+                ));
+            } else if let Some(result_type) = complex_result_type(&left_type, &right_type) {
+                return Ok(RecExpr::new(
+                    RecExprData::Multiply {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::Float,
-                });
+                    span,
+                    result_type,
+                ));
+            } else if let Some(result_type) = rational_result_type(&left_type, &right_type) {
+                return Ok(RecExpr::new(
+                    RecExprData::Multiply {
+                        left: Box::new(left_typed),
+                        right: Box::new(right_typed),
+                    },
+                    span,
+                    result_type,
+                ));
+            } else if is_float_arithmetic(&left_type, &right_type) {
+                return Ok(RecExpr::new(
+                    RecExprData::Multiply {
+                        left: Box::new(left_typed),
+                        right: Box::new(right_typed),
+                    },
+                    span,
+                    Type::Float,
+                ));
             } else {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for multiplication".to_string(),
-                    expected: left_type,
-                    found: right_type,
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                });
+                return Err(Error::type_error(
+                    "Invalid operand types for multiplication".to_string(),
+                    left_type,
+                    right_type,
+                    span,
+                ));
             }
         }
         RecExprData::Divide { left, right } => {
-            let row = left.row;
-            let left_col_start = left.col_start;
-            let right_col_end = right.col_end;
+            let span = left.span().merge(&right.span());
             let left_typed = check_type_rec(*left, env, func_env)?;
             let right_typed = check_type_rec(*right, env, func_env)?;
             let left_type = left_typed.generic_data.clone();
             let right_type = right_typed.generic_data.clone();
+            check_implicit_conversion(&left_typed, &right_typed, &env.options)?;
 
             if left_type == Type::Integer && right_type == Type::Integer {
-                return Ok(RecExpr {
-                    data: RecExprData::Divide {
+                // With true division the quotient of two integers is exact, so a float
+                let result_type = match env.options.true_division {
+                    true => Type::Float,
+                    false => Type::Integer,
+                };
+                return Ok(RecExpr::new(
+                    RecExprData::Divide {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::Integer,
-                });
-            } else if (left_type == Type::Integer || left_type == Type::Float)
-                && (right_type == Type::Integer || right_type == Type::Float)
-            {
-                return Ok(RecExpr {
-                    data: RecExprData::Divide {
+                    span,
+                    result_type,
+                ));
+            } else if let Some(result_type) = complex_result_type(&left_type, &right_type) {
+                return Ok(RecExpr::new(
+                    RecExprData::Divide {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::Float,
-                });
+                    span,
+                    result_type,
+                ));
+            } else if let Some(result_type) = rational_result_type(&left_type, &right_type) {
+                return Ok(RecExpr::new(
+                    RecExprData::Divide {
+                        left: Box::new(left_typed),
+                        right: Box::new(right_typed),
+                    },
+                    span,
+                    result_type,
+                ));
+            } else if is_float_arithmetic(&left_type, &right_type) {
+                return Ok(RecExpr::new(
+                    RecExprData::Divide {
+                        left: Box::new(left_typed),
+                        right: Box::new(right_typed),
+                    },
+                    span,
+                    Type::Float,
+                ));
             } else {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for division".to_string(),
-                    expected: left_type,
-                    found: right_type,
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                });
+                return Err(Error::type_error(
+                    "Invalid operand types for division".to_string(),
+                    left_type,
+                    right_type,
+                    span,
+                ));
             }
         }
+        RecExprData::FloorDivide { left, right } => {
+            let span = left.span().merge(&right.span());
+            let left_typed = check_type_rec(*left, env, func_env)?;
+            let right_typed = check_type_rec(*right, env, func_env)?;
+            let left_type = left_typed.generic_data.clone();
+            let right_type = right_typed.generic_data.clone();
+            check_implicit_conversion(&left_typed, &right_typed, &env.options)?;
+
+            // Floor division rounds down, so only integers and floats can be divided this way
+            let result_type = if left_type == Type::Integer && right_type == Type::Integer {
+                Type::Integer
+            } else if is_float_arithmetic(&left_type, &right_type) {
+                Type::Float
+            } else {
+                return Err(Error::type_error(
+                    "Invalid operand types for floor division".to_string(),
+                    left_type,
+                    right_type,
+                    span,
+                ));
+            };
+
+            return Ok(RecExpr::new(
+                RecExprData::FloorDivide {
+                    left: Box::new(left_typed),
+                    right: Box::new(right_typed),
+                },
+                span,
+                result_type,
+            ));
+        }
         RecExprData::Subtract { left, right } => {
-            let row = left.row;
-            let left_col_start = left.col_start;
-            let right_col_end = right.col_end;
+            let span = left.span().merge(&right.span());
             let left_typed = check_type_rec(*left, env, func_env)?;
             let right_typed = check_type_rec(*right, env, func_env)?;
             let left_type = left_typed.generic_data.clone();
             let right_type = right_typed.generic_data.clone();
+            check_implicit_conversion(&left_typed, &right_typed, &env.options)?;
 
             if left_type == Type::Integer && right_type == Type::Integer {
-                return Ok(RecExpr {
-                    data: RecExprData::Subtract {
+                return Ok(RecExpr::new(
+                    RecExprData::Subtract {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::Integer,
-                });
-            } else if (left_type == Type::Integer || left_type == Type::Float)
-                && (right_type == Type::Integer || right_type == Type::Float)
-            {
-                return Ok(RecExpr {
-                    data: RecExprData::Subtract {
+                    span,
+                    Type::Integer,
+                ));
+            } else if let Some(result_type) = complex_result_type(&left_type, &right_type) {
+                return Ok(RecExpr::new(
+                    RecExprData::Subtract {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::Float,
-                });
+                    span,
+                    result_type,
+                ));
+            } else if let Some(result_type) = rational_result_type(&left_type, &right_type) {
+                return Ok(RecExpr::new(
+                    RecExprData::Subtract {
+                        left: Box::new(left_typed),
+                        right: Box::new(right_typed),
+                    },
+                    span,
+                    result_type,
+                ));
+            } else if is_float_arithmetic(&left_type, &right_type) {
+                return Ok(RecExpr::new(
+                    RecExprData::Subtract {
+                        left: Box::new(left_typed),
+                        right: Box::new(right_typed),
+                    },
+                    span,
+                    Type::Float,
+                ));
             } else {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for subtraction".to_string(),
-                    expected: left_type,
-                    found: right_type,
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                });
+                return Err(Error::type_error(
+                    "Invalid operand types for subtraction".to_string(),
+                    left_type,
+                    right_type,
+                    span,
+                ));
             }
         }
         RecExprData::Power { left, right } => {
-            let row = left.row;
-            let left_col_start = left.col_start;
-            let right_col_end = right.col_end;
+            let span = left.span().merge(&right.span());
             let left_typed = check_type_rec(*left, env, func_env)?;
             let right_typed = check_type_rec(*right, env, func_env)?;
             let left_type = left_typed.generic_data.clone();
             let right_type = right_typed.generic_data.clone();
+            check_implicit_conversion(&left_typed, &right_typed, &env.options)?;
 
             if left_type == Type::Integer && right_type == Type::Integer {
-                return Ok(RecExpr {
-                    data: RecExprData::Power {
+                return Ok(RecExpr::new(
+                    RecExprData::Power {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::Integer,
-                });
-            } else if (left_type == Type::Integer || left_type == Type::Float)
-                && (right_type == Type::Integer || right_type == Type::Float)
-            {
-                return Ok(RecExpr {
-                    data: RecExprData::Power {
+                    span,
+                    Type::Integer,
+                ));
+            } else if is_float_arithmetic(&left_type, &right_type) {
+                return Ok(RecExpr::new(
+                    RecExprData::Power {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::Float,
-                });
+                    span,
+                    Type::Float,
+                ));
             } else {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for exponentiation".to_string(),
-                    expected: left_type,
-                    found: right_type,
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                });
+                return Err(Error::type_error(
+                    "Invalid operand types for exponentiation".to_string(),
+                    left_type,
+                    right_type,
+                    span,
+                ));
             }
         }
         RecExprData::Minus { right } => {
-            let row = right.row;
-            let col_start = right.col_start;
-            let col_end = right.col_end;
+            let span = right.span();
             let right_typed = check_type_rec(*right, env, func_env)?;
             let right_type = right_typed.generic_data.clone();
 
             if right_type == Type::Integer {
-                return Ok(RecExpr {
-                    data: RecExprData::Minus {
+                return Ok(RecExpr::new(
+                    RecExprData::Minus {
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: col_start,
-                    col_end: col_end,
-                    generic_data: Type::Integer,
-                });
+                    span,
+                    Type::Integer,
+                ));
+            } else if right_type == Type::Rational || right_type == Type::Complex {
+                return Ok(RecExpr::new(
+                    RecExprData::Minus {
+                        right: Box::new(right_typed),
+                    },
+                    span,
+                    right_type,
+                ));
             } else if right_type == Type::Float {
-                return Ok(RecExpr {
-                    data: RecExprData::Minus {
+                return Ok(RecExpr::new(
+                    RecExprData::Minus {
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: col_start,
-                    col_end: col_end,
-                    generic_data: Type::Float,
-                });
+                    span,
+                    Type::Float,
+                ));
             } else {
-                return Err(Error::TypeError {
-                    message: "Invalid operand type for negation".to_string(),
-                    expected: Type::Integer,
-                    found: right_type,
-                    row: row,
-                    col_start: col_start,
-                    col_end: col_end,
-                });
+                return Err(Error::type_error(
+                    "Invalid operand type for negation".to_string(),
+                    Type::Integer,
+                    right_type,
+                    span,
+                ));
             }
         }
         RecExprData::Or { left, right } => {
-            let row = left.row;
-            let left_col_start = left.col_start;
-            let right_col_start = right.col_start;
-            let left_col_end = left.col_end;
-            let right_col_end = right.col_end;
+            let left_span = left.span();
+            let right_span = right.span();
+            let span = left_span.merge(&right_span);
 
             let left_typed = check_type_rec(*left, env, func_env)?;
             let right_typed = check_type_rec(*right, env, func_env)?;
@@ -1044,42 +2312,34 @@ fn check_type_rec(
             let right_type = right_typed.generic_data.clone();
 
             if left_type == Type::Boolean && right_type == Type::Boolean {
-                return Ok(RecExpr {
-                    data: RecExprData::Or {
+                return Ok(RecExpr::new(
+                    RecExprData::Or {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::Boolean,
-                });
+                    span,
+                    Type::Boolean,
+                ));
             } else if left_type != Type::Boolean {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for logical OR".to_string(),
-                    expected: Type::Boolean,
-                    found: left_type,
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: left_col_end,
-                });
+                return Err(Error::type_error(
+                    "Invalid operand types for logical OR".to_string(),
+                    Type::Boolean,
+                    left_type,
+                    left_span,
+                ));
             } else {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for logical OR".to_string(),
-                    expected: Type::Boolean,
-                    found: right_type,
-                    row: row,
-                    col_start: right_col_start,
-                    col_end: right_col_end,
-                });
+                return Err(Error::type_error(
+                    "Invalid operand types for logical OR".to_string(),
+                    Type::Boolean,
+                    right_type,
+                    right_span,
+                ));
             }
         }
         RecExprData::And { left, right } => {
-            let row = left.row;
-            let left_col_start = left.col_start;
-            let right_col_start = right.col_start;
-            let left_col_end = left.col_end;
-            let right_col_end = right.col_end;
+            let left_span = left.span();
+            let right_span = right.span();
+            let span = left_span.merge(&right_span);
 
             let left_typed = check_type_rec(*left, env, func_env)?;
             let right_typed = check_type_rec(*right, env, func_env)?;
@@ -1087,427 +2347,630 @@ fn check_type_rec(
             let right_type = right_typed.generic_data.clone();
 
             if left_type == Type::Boolean && right_type == Type::Boolean {
-                return Ok(RecExpr {
-                    data: RecExprData::And {
+                return Ok(RecExpr::new(
+                    RecExprData::And {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::Boolean,
-                });
+                    span,
+                    Type::Boolean,
+                ));
             } else if left_type != Type::Boolean {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for logical AND".to_string(),
-                    expected: Type::Boolean,
-                    found: left_type,
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: left_col_end,
-                });
+                return Err(Error::type_error(
+                    "Invalid operand types for logical AND".to_string(),
+                    Type::Boolean,
+                    left_type,
+                    left_span,
+                ));
             } else {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for logical AND".to_string(),
-                    expected: Type::Boolean,
-                    found: right_type,
-                    row: row,
-                    col_start: right_col_start,
-                    col_end: right_col_end,
-                });
+                return Err(Error::type_error(
+                    "Invalid operand types for logical AND".to_string(),
+                    Type::Boolean,
+                    right_type,
+                    right_span,
+                ));
             }
         }
         RecExprData::Not { right } => {
-            let row = right.row;
-            let col_start = right.col_start;
-            let col_end = right.col_end;
+            let span = right.span();
 
             let right_typed = check_type_rec(*right, env, func_env)?;
             let right_type = right_typed.generic_data.clone();
 
             if right_type == Type::Boolean {
-                return Ok(RecExpr {
-                    data: RecExprData::Not {
+                return Ok(RecExpr::new(
+                    RecExprData::Not {
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: col_start,
-                    col_end: col_end,
-                    generic_data: Type::Boolean,
-                });
+                    span,
+                    Type::Boolean,
+                ));
             } else {
-                return Err(Error::TypeError {
-                    message: "Invalid operand type for logical NOT".to_string(),
-                    expected: Type::Boolean,
-                    found: right_type,
-                    row: row,
-                    col_start: col_start,
-                    col_end: col_end,
-                });
+                return Err(Error::type_error(
+                    "Invalid operand type for logical NOT".to_string(),
+                    Type::Boolean,
+                    right_type,
+                    span,
+                ));
             }
         }
+        RecExprData::Conditional {
+            condition,
+            then_value,
+            else_value,
+        } => {
+            let condition_span = condition.span();
+            let else_span = else_value.span();
+
+            let then_typed = check_type_rec(*then_value, env, func_env)?;
+            let condition_typed = check_type_rec(*condition, env, func_env)?;
+            let else_typed = check_type_rec(*else_value, env, func_env)?;
+            let value_type = then_typed.generic_data.clone();
+
+            if condition_typed.generic_data != Type::Boolean {
+                return Err(Error::type_error(
+                    "The condition of a conditional expression must be a bool".to_string(),
+                    Type::Boolean,
+                    condition_typed.generic_data,
+                    condition_span,
+                ));
+            }
+            // Both values must have the same type, as either one can be the result
+            if else_typed.generic_data != value_type {
+                return Err(Error::type_error(
+                    "Both values of a conditional expression must have the same type".to_string(),
+                    value_type,
+                    else_typed.generic_data,
+                    else_span,
+                ));
+            }
+            Ok(RecExpr::new(
+                RecExprData::Conditional {
+                    condition: Box::new(condition_typed),
+                    then_value: Box::new(then_typed),
+                    else_value: Box::new(else_typed),
+                },
+                rec_expr_span,
+                value_type,
+            ))
+        }
         RecExprData::Equals { left, right } => {
-            let row = left.row;
-            let left_col_start = left.col_start;
-            let right_col_end = right.col_end;
+            let span = left.span().merge(&right.span());
 
             let left_typed = check_type_rec(*left, env, func_env)?;
             let right_typed = check_type_rec(*right, env, func_env)?;
             let left_type = left_typed.generic_data.clone();
             let right_type = right_typed.generic_data.clone();
 
-            if left_type == right_type {
-                return Ok(RecExpr {
-                    data: RecExprData::Equals {
+            if left_type == right_type
+                || rational_result_type(&left_type, &right_type).is_some()
+                || complex_result_type(&left_type, &right_type).is_some()
+            {
+                return Ok(RecExpr::new(
+                    RecExprData::Equals {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::Boolean,
-                });
+                    span,
+                    Type::Boolean,
+                ));
             } else {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for equality check".to_string(),
-                    expected: left_type,
-                    found: right_type,
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                });
+                return Err(Error::type_error(
+                    "Invalid operand types for equality check".to_string(),
+                    left_type,
+                    right_type,
+                    span,
+                ));
             }
         }
         RecExprData::NotEquals { left, right } => {
-            let row = left.row;
-            let left_col_start = left.col_start;
-            let right_col_end = right.col_end;
+            let span = left.span().merge(&right.span());
 
             let left_typed = check_type_rec(*left, env, func_env)?;
             let right_typed = check_type_rec(*right, env, func_env)?;
             let left_type = left_typed.generic_data.clone();
             let right_type = right_typed.generic_data.clone();
 
-            if left_type == right_type {
-                return Ok(RecExpr {
-                    data: RecExprData::NotEquals {
+            if left_type == right_type
+                || rational_result_type(&left_type, &right_type).is_some()
+                || complex_result_type(&left_type, &right_type).is_some()
+            {
+                return Ok(RecExpr::new(
+                    RecExprData::NotEquals {
                         left: Box::new(left_typed),
                         right: Box::new(right_typed),
                     },
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                    generic_data: Type::Boolean,
-                });
+                    span,
+                    Type::Boolean,
+                ));
             } else {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for inequality check".to_string(),
-                    expected: left_type,
-                    found: right_type,
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: right_col_end,
-                });
+                return Err(Error::type_error(
+                    "Invalid operand types for inequality check".to_string(),
+                    left_type,
+                    right_type,
+                    span,
+                ));
             }
         }
         RecExprData::GreaterThan { left, right } => {
-            let row = left.row;
-            let left_col_start = left.col_start;
-            let right_col_start = right.col_start;
-            let left_col_end = left.col_end;
-            let right_col_end = right.col_end;
+            let left_span = left.span();
+            let right_span = right.span();
+            let span = left_span.merge(&right_span);
 
             let left_typed = check_type_rec(*left, env, func_env)?;
             let right_typed = check_type_rec(*right, env, func_env)?;
             let left_type = left_typed.generic_data.clone();
             let right_type = right_typed.generic_data.clone();
 
-            if left_type != Type::Integer && left_type != Type::Float {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for greater-than check".to_string(),
-                    expected: Type::Integer,
-                    found: left_type,
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: left_col_end,
-                });
+            if !is_number_type(&left_type) {
+                return Err(Error::type_error(
+                    "Invalid operand types for greater-than check".to_string(),
+                    Type::Integer,
+                    left_type,
+                    left_span,
+                ));
             }
-            if right_type != Type::Integer && right_type != Type::Float {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for greater-than check".to_string(),
-                    expected: Type::Integer,
-                    found: right_type,
-                    row: row,
-                    col_start: right_col_start,
-                    col_end: right_col_end,
-                });
+            if !is_number_type(&right_type) {
+                return Err(Error::type_error(
+                    "Invalid operand types for greater-than check".to_string(),
+                    Type::Integer,
+                    right_type,
+                    right_span,
+                ));
             }
 
-            return Ok(RecExpr {
-                data: RecExprData::GreaterThan {
+            if is_float_rational_mix(&left_type, &right_type) {
+                return Err(Error::type_error(
+                    "Cannot compare a float with a rational number".to_string(),
+                    left_type,
+                    right_type,
+                    span,
+                ));
+            }
+            check_implicit_conversion(&left_typed, &right_typed, &env.options)?;
+
+            return Ok(RecExpr::new(
+                RecExprData::GreaterThan {
                     left: Box::new(left_typed),
                     right: Box::new(right_typed),
                 },
-                row: row,
-                col_start: left_col_start,
-                col_end: right_col_end,
-                generic_data: Type::Boolean,
-            });
+                span,
+                Type::Boolean,
+            ));
         }
         RecExprData::LessThan { left, right } => {
-            let row = left.row;
-            let left_col_start = left.col_start;
-            let right_col_start = right.col_start;
-            let left_col_end = left.col_end;
-            let right_col_end = right.col_end;
+            let left_span = left.span();
+            let right_span = right.span();
+            let span = left_span.merge(&right_span);
 
             let left_typed = check_type_rec(*left, env, func_env)?;
             let right_typed = check_type_rec(*right, env, func_env)?;
             let left_type = left_typed.generic_data.clone();
             let right_type = right_typed.generic_data.clone();
 
-            if left_type != Type::Integer && left_type != Type::Float {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for less-than check".to_string(),
-                    expected: Type::Integer,
-                    found: left_type,
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: left_col_end,
-                });
+            if !is_number_type(&left_type) {
+                return Err(Error::type_error(
+                    "Invalid operand types for less-than check".to_string(),
+                    Type::Integer,
+                    left_type,
+                    left_span,
+                ));
             }
-            if right_type != Type::Integer && right_type != Type::Float {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for less-than check".to_string(),
-                    expected: Type::Integer,
-                    found: right_type,
-                    row: row,
-                    col_start: right_col_start,
-                    col_end: right_col_end,
-                });
+            if !is_number_type(&right_type) {
+                return Err(Error::type_error(
+                    "Invalid operand types for less-than check".to_string(),
+                    Type::Integer,
+                    right_type,
+                    right_span,
+                ));
             }
 
-            return Ok(RecExpr {
-                data: RecExprData::LessThan {
+            if is_float_rational_mix(&left_type, &right_type) {
+                return Err(Error::type_error(
+                    "Cannot compare a float with a rational number".to_string(),
+                    left_type,
+                    right_type,
+                    span,
+                ));
+            }
+            check_implicit_conversion(&left_typed, &right_typed, &env.options)?;
+
+            return Ok(RecExpr::new(
+                RecExprData::LessThan {
                     left: Box::new(left_typed),
                     right: Box::new(right_typed),
                 },
-                row: row,
-                col_start: left_col_start,
-                col_end: right_col_end,
-                generic_data: Type::Boolean,
-            });
+                span,
+                Type::Boolean,
+            ));
         }
         RecExprData::GreaterThanOrEqual { left, right } => {
-            let row = left.row;
-            let left_col_start = left.col_start;
-            let right_col_start = right.col_start;
-            let left_col_end = left.col_end;
-            let right_col_end = right.col_end;
+            let left_span = left.span();
+            let right_span = right.span();
+            let span = left_span.merge(&right_span);
 
             let left_typed = check_type_rec(*left, env, func_env)?;
             let right_typed = check_type_rec(*right, env, func_env)?;
             let left_type = left_typed.generic_data.clone();
             let right_type = right_typed.generic_data.clone();
 
-            if left_type != Type::Integer && left_type != Type::Float {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for greater-than-or-equal check".to_string(),
-                    expected: Type::Integer,
-                    found: left_type,
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: left_col_end,
-                });
+            if !is_number_type(&left_type) {
+                return Err(Error::type_error(
+                    "Invalid operand types for greater-than-or-equal check".to_string(),
+                    Type::Integer,
+                    left_type,
+                    left_span,
+                ));
             }
-            if right_type != Type::Integer && right_type != Type::Float {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for greater-than-or-equal check".to_string(),
-                    expected: Type::Integer,
-                    found: right_type,
-                    row: row,
-                    col_start: right_col_start,
-                    col_end: right_col_end,
-                });
+            if !is_number_type(&right_type) {
+                return Err(Error::type_error(
+                    "Invalid operand types for greater-than-or-equal check".to_string(),
+                    Type::Integer,
+                    right_type,
+                    right_span,
+                ));
+            }
+
+            if is_float_rational_mix(&left_type, &right_type) {
+                return Err(Error::type_error(
+                    "Cannot compare a float with a rational number".to_string(),
+                    left_type,
+                    right_type,
+                    span,
+                ));
             }
+            check_implicit_conversion(&left_typed, &right_typed, &env.options)?;
 
-            return Ok(RecExpr {
-                data: RecExprData::GreaterThanOrEqual {
+            return Ok(RecExpr::new(
+                RecExprData::GreaterThanOrEqual {
                     left: Box::new(left_typed),
                     right: Box::new(right_typed),
                 },
-                row: row,
-                col_start: left_col_start,
-                col_end: right_col_end,
-                generic_data: Type::Boolean,
-            });
+                span,
+                Type::Boolean,
+            ));
         }
         RecExprData::LessThanOrEqual { left, right } => {
-            let row = left.row;
-            let left_col_start = left.col_start;
-            let right_col_start = right.col_start;
-            let left_col_end = left.col_end;
-            let right_col_end = right.col_end;
+            let left_span = left.span();
+            let right_span = right.span();
+            let span = left_span.merge(&right_span);
 
             let left_typed = check_type_rec(*left, env, func_env)?;
             let right_typed = check_type_rec(*right, env, func_env)?;
             let left_type = left_typed.generic_data.clone();
             let right_type = right_typed.generic_data.clone();
 
-            if left_type != Type::Integer && left_type != Type::Float {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for less-than-or-equal check".to_string(),
-                    expected: Type::Integer,
-                    found: left_type,
-                    row: row,
-                    col_start: left_col_start,
-                    col_end: left_col_end,
-                });
+            if !is_number_type(&left_type) {
+                return Err(Error::type_error(
+                    "Invalid operand types for less-than-or-equal check".to_string(),
+                    Type::Integer,
+                    left_type,
+                    left_span,
+                ));
             }
-            if right_type != Type::Integer && right_type != Type::Float {
-                return Err(Error::TypeError {
-                    message: "Invalid operand types for less-than-or-equal check".to_string(),
-                    expected: Type::Integer,
-                    found: right_type,
-                    row: row,
-                    col_start: right_col_start,
-                    col_end: right_col_end,
-                });
+            if !is_number_type(&right_type) {
+                return Err(Error::type_error(
+                    "Invalid operand types for less-than-or-equal check".to_string(),
+                    Type::Integer,
+                    right_type,
+                    right_span,
+                ));
+            }
+
+            if is_float_rational_mix(&left_type, &right_type) {
+                return Err(Error::type_error(
+                    "Cannot compare a float with a rational number".to_string(),
+                    left_type,
+                    right_type,
+                    span,
+                ));
             }
+            check_implicit_conversion(&left_typed, &right_typed, &env.options)?;
 
-            return Ok(RecExpr {
-                data: RecExprData::LessThanOrEqual {
+            return Ok(RecExpr::new(
+                RecExprData::LessThanOrEqual {
                     left: Box::new(left_typed),
                     right: Box::new(right_typed),
                 },
-                row: row,
-                col_start: left_col_start,
-                col_end: right_col_end,
-                generic_data: Type::Boolean,
-            });
+                span,
+                Type::Boolean,
+            ));
         }
         RecExprData::FunctionCall {
             function_name,
             args,
         } => {
+            // Structs do not declare the types of their fields, so those of a struct read from JSON
+            // are only known when the program runs
+            if function_name == "from_json" && builtins::find_builtin(&function_name).is_some() {
+                return Err(Error::located(
+                    String::from("The field types of a struct read with from_json cannot be typechecked"),
+                    rec_expr_span,
+                ));
+            }
+
             // First we collect all of the given parameter types so we can match against them
-            let mut arg_types: Vec<Type> = Vec::new();
             let mut args_typed: Vec<RecExpr<Type>> = Vec::new();
-            for (i, arg) in args.iter().enumerate() {
-                let arg_typed = check_type_rec(arg.clone(), env, func_env)?;
-                args_typed.push(arg_typed.clone());
-                let arg_type = arg_typed.generic_data;
-                arg_types.push(arg_type);
+            for arg in args.iter() {
+                let arg = match &arg.data {
+                    RecExprData::KeywordArgument { value, .. } => *value.clone(),
+                    _ => arg.clone(),
+                };
+                args_typed.push(check_type_rec(arg, env, func_env)?);
             }
 
+            // Keyword arguments go to the parameter they name. Calling something unknown is reported further on.
+            let mut placed_args: Vec<Option<RecExpr<Type>>> = args_typed.into_iter().map(Some).collect();
+            if parser::has_keyword_arguments(&args) {
+                if let Some(param_names) = call_parameter_names(&function_name, env, func_env) {
+                    let positions = parser::argument_positions(&function_name, &param_names, &args)?;
+                    placed_args = parser::place_arguments(&positions, placed_args.into_iter().flatten().collect());
+                }
+            }
+
+            // Calling a struct constructs an instance of it
+            if let Some(struct_binding) = env
+                .structs
+                .iter()
+                .find(|struct_binding| struct_binding.name == function_name)
+            {
+                if struct_binding.fields.len() != placed_args.len() {
+                    return Err(Error::located(
+                        format!(
+                            "Struct '{}' has {} fields, but {} values were provided",
+                            function_name,
+                            struct_binding.fields.len(),
+                            placed_args.len()
+                        ),
+                        rec_expr_span,
+                    ));
+                }
+                if let Some(position) = placed_args.iter().position(|arg| arg.is_none()) {
+                    return Err(Error::located(
+                        format!(
+                            "No value is given for the field {} of struct {}",
+                            struct_binding.fields[position], function_name
+                        ),
+                        rec_expr_span,
+                    ));
+                }
+                let args_typed = order_arguments(placed_args, rec_expr_span)?;
+                let arg_types = args_typed.iter().map(|arg| arg.generic_data.clone());
+
+                let struct_type = Type::Struct(Box::new(StructType {
+                    name: function_name.clone(),
+                    fields: struct_binding.fields.iter().cloned().zip(arg_types).collect(),
+                }));
+                return Ok(RecExpr::new(
+                    RecExprData::FunctionCall {
+                        function_name,
+                        args: args_typed,
+                    },
+                    rec_expr_span,
+                    struct_type,
+                ));
+            }
+
+            // Parameters left out of the call get their default value. Builtins go before functions
+            // defined in the program, and have no defaults.
+            match builtins::find_builtin(&function_name) {
+                Some(builtin) => {
+                    if let Some(position) = placed_args.iter().position(|arg| arg.is_none()) {
+                        return Err(Error::located(
+                            format!("No value is given for the parameter {} of {}", builtin.param_names[position], builtin.name),
+                            rec_expr_span,
+                        ));
+                    }
+                }
+                None => {
+                    if let Some(function) = find_function_binding(&function_name, env, func_env) {
+                        placed_args = collect_rest_arguments(&function, placed_args, rec_expr_span)?;
+                        fill_in_defaults(&function, &mut placed_args, env, func_env, rec_expr_span)?;
+                    }
+                }
+            }
+            let args_typed = order_arguments(placed_args, rec_expr_span)?;
+            let arg_types: Vec<Type> = args_typed.iter().map(|arg| arg.generic_data.clone()).collect();
+
             // Then we look for a matching function in the environment
             let function_type =
-                find_matching_function_in_env(&function_name, &arg_types, env, func_env);
+                find_matching_function_in_env(&function_name, &arg_types, env, func_env, rec_expr_span);
             match function_type {
                 Ok(return_type) => {
-                    // Check that the number of arguments matches the number of parameters
-                    if arg_types.len() != args.len() {
-                        return Err(Error::LocationError {
-                            message: format!(
-                                "Function '{}' expects {} arguments, but {} were provided",
-                                function_name,
-                                arg_types.len(),
-                                args.len()
-                            ),
-                            row: rec_expr_row,
-                            col_start: rec_expr_col_start,
-                            col_end: rec_expr_col_end,
-                        });
-                    }
-
-                    return Ok(RecExpr {
-                        data: RecExprData::FunctionCall {
+                    return Ok(RecExpr::new(
+                        RecExprData::FunctionCall {
                             function_name,
                             args: args_typed,
                         },
-                        row: rec_expr_row,
-                        col_start: rec_expr_col_start,
-                        col_end: rec_expr_col_end,
-                        generic_data: return_type,
-                    });
+                        rec_expr_span,
+                        return_type,
+                    ));
                 }
                 Err(error) => {
                     return Err(error);
                 }
             }
         }
-        RecExprData::ListAccess { variable, index } => {
-            let var_type = find_in_env(&variable, &env);
-            let index_row = index.row;
-            let index_col_start = index.col_start;
-            let index_col_end = index.col_end;
+        RecExprData::ListAccess { list, index } => {
+            let index_span = index.span();
+
+            let list_typed = check_type_rec(*list, env, func_env)?;
+
+            // The elements of a tuple have different types, so the index must be known before running
+            if let Type::Tuple(element_types) = &list_typed.generic_data {
+                let RecExprData::Number { number } = index.data else {
+                    return Err(Error::located(
+                        "Tuple index must be an integer literal, like t[0]".to_string(),
+                        index_span,
+                    ));
+                };
+                let Some(elem_type) = usize::try_from(number)
+                    .ok()
+                    .and_then(|i| element_types.get(i))
+                    .cloned()
+                else {
+                    return Err(Error::located(
+                        format!(
+                            "Index {} out of bounds for tuple of length {}",
+                            number,
+                            element_types.len()
+                        ),
+                        index_span,
+                    ));
+                };
+                let index_typed = check_type_rec(*index, env, func_env)?;
 
-            match var_type {
-                Some(Type::List(elem_type)) => {
-                    let index_typed = check_type_rec(*index, env, func_env)?;
-                    let index_type = index_typed.generic_data.clone();
-                    if index_type != Type::Integer {
-                        return Err(Error::TypeError {
-                            message: "List index must be an integer".to_string(),
-                            expected: Type::Integer,
-                            found: index_type,
-                            row: index_row,
-                            col_start: index_col_start,
-                            col_end: index_col_end,
-                        });
-                    }
-                    return Ok(RecExpr {
-                        data: RecExprData::ListAccess {
-                            variable,
-                            index: Box::new(index_typed),
-                        },
-                        row: rec_expr_row,
-                        col_start: rec_expr_col_start,
-                        col_end: rec_expr_col_end,
-                        generic_data: *elem_type,
-                    });
+                return Ok(RecExpr::new(
+                    RecExprData::ListAccess {
+                        list: Box::new(list_typed),
+                        index: Box::new(index_typed),
+                    },
+                    rec_expr_span,
+                    elem_type,
+                ));
+            }
+
+            // Indexing bytes gives the byte as an integer, indexing a dict gives the value for the key
+            let (elem_type, index_type_expected) = match &list_typed.generic_data {
+                Type::List(elem_type) => (*elem_type.clone(), Type::Integer),
+                Type::Bytes => (Type::Integer, Type::Integer),
+                Type::Dict(key_type, value_type) => (*value_type.clone(), *key_type.clone()),
+                other_type => {
+                    return Err(Error::type_error(
+                        format!("Cannot index into a value of type {}", other_type),
+                        Type::List(Box::new(Type::Undefined)),
+                        other_type.clone(),
+                        list_typed.span(),
+                    ));
                 }
-                Some(other_type) => {
-                    return Err(Error::TypeError {
-                        message: format!(
-                            "Variable '{}' is of type {:?}, not a list",
-                            variable, other_type
-                        ),
-                        expected: Type::List(Box::new(Type::Undefined)),
-                        found: other_type,
-                        row: rec_expr_row,
-                        col_start: rec_expr_col_start,
-                        col_end: rec_expr_col_end,
-                    });
+            };
+
+            let index_typed = check_type_rec(*index, env, func_env)?;
+            let index_type = index_typed.generic_data.clone();
+            match &list_typed.generic_data {
+                // Any key can be looked up in an empty dict, which fails when the program runs
+                Type::Dict(key_type, _) if **key_type == Type::Undefined => {}
+                Type::Dict(..) if index_type != index_type_expected => {
+                    return Err(Error::type_error(
+                        "Dict key is of the wrong type".to_string(),
+                        index_type_expected,
+                        index_type,
+                        index_span,
+                    ));
                 }
-                None => {
-                    return Err(Error::LocationError {
-                        message: format!("Variable '{}' is not defined", variable),
-                        row: rec_expr_row,
-                        col_start: rec_expr_col_start,
-                        col_end: rec_expr_col_end,
-                    });
+                _ if index_type != index_type_expected => {
+                    return Err(Error::type_error(
+                        "List index must be an integer".to_string(),
+                        Type::Integer,
+                        index_type,
+                        index_span,
+                    ));
                 }
+                _ => {}
             }
+            return Ok(RecExpr::new(
+                RecExprData::ListAccess {
+                    list: Box::new(list_typed),
+                    index: Box::new(index_typed),
+                },
+                rec_expr_span,
+                elem_type,
+            ));
+        }
+        // A slice of a list is a list of the same type, a slice of a string is a string
+        RecExprData::Slice { list, start, end } => {
+            let list_typed = check_type_rec(*list, env, func_env)?;
+            let slice_type = match &list_typed.generic_data {
+                Type::List(_) | Type::String => list_typed.generic_data.clone(),
+                other_type => {
+                    return Err(Error::type_error(
+                        format!("Cannot slice a value of type {}", other_type),
+                        Type::List(Box::new(Type::Undefined)),
+                        other_type.clone(),
+                        list_typed.span(),
+                    ));
+                }
+            };
+
+            let mut check_bound = |bound: Option<Box<RecExpr<()>>>| -> Result<Option<Box<RecExpr<Type>>>, Error> {
+                let Some(bound) = bound else {
+                    return Ok(None);
+                };
+                let bound_typed = check_type_rec(*bound, env, func_env)?;
+                if bound_typed.generic_data != Type::Integer {
+                    return Err(Error::type_error(
+                        "Slice bounds must be integers".to_string(),
+                        Type::Integer,
+                        bound_typed.generic_data.clone(),
+                        bound_typed.span(),
+                    ));
+                }
+                Ok(Some(Box::new(bound_typed)))
+            };
+            let start_typed = check_bound(start)?;
+            let end_typed = check_bound(end)?;
+
+            return Ok(RecExpr::new(
+                RecExprData::Slice {
+                    list: Box::new(list_typed),
+                    start: start_typed,
+                    end: end_typed,
+                },
+                rec_expr_span,
+                slice_type,
+            ));
+        }
+        RecExprData::Access { object, variable } => {
+            let object_type = match find_in_env(&object, env) {
+                Some(object_type) => object_type,
+                None => {
+                    return Err(Error::located(
+                        format!("Variable '{}' is not defined", object),
+                        rec_expr_span,
+                    ));
+                }
+            };
+
+            let field_type = match &object_type {
+                Type::Struct(struct_type) => {
+                    match struct_type.fields.iter().find(|(field_name, _)| *field_name == variable) {
+                        Some((_, field_type)) => field_type.clone(),
+                        None => {
+                            return Err(Error::located(
+                                format!("Struct '{}' has no field '{}'", struct_type.name, variable),
+                                rec_expr_span,
+                            ));
+                        }
+                    }
+                }
+                other_type => {
+                    return Err(Error::located(
+                        format!(
+                            "Cannot access field '{}' of a value of type {}",
+                            variable,
+                            type_to_string(other_type)
+                        ),
+                        rec_expr_span,
+                    ));
+                }
+            };
+
+            return Ok(RecExpr::new(
+                RecExprData::Access { object, variable },
+                rec_expr_span,
+                field_type,
+            ));
         }
         RecExprData::Variable { name } => {
             let var_type = find_in_env(&name, &env);
             match var_type {
                 Some(t) => {
-                    return Ok(RecExpr {
-                        data: RecExprData::Variable { name },
-                        row: rec_expr_row,
-                        col_start: rec_expr_col_start,
-                        col_end: rec_expr_col_end,
-                        generic_data: t,
-                    });
+                    return Ok(RecExpr::new(RecExprData::Variable { name }, rec_expr_span, t));
                 }
-                None => Err(Error::LocationError {
-                    message: format!("Variable '{}' is not defined", name),
-                    row: rec_expr_row,
-                    col_start: rec_expr_col_start,
-                    col_end: rec_expr_col_end,
-                }),
+                None => Err(Error::located(
+                    format!("Variable '{}' is not defined", name),
+                    rec_expr_span,
+                )),
             }
         }
 
@@ -157,6 +157,16 @@ fn uniquify_base_expr(
             }
             env.pop();
         }
+        BaseExprData::WhileLoop { condition, body } => {
+            uniquify_rec_expr(condition, env, &mut variable_collection.names);
+
+            // Uniquify the body in a new scope
+            env.push(VariableScope::new());
+            for expr in body.iter_mut() {
+                uniquify_base_expr(expr, env, variable_collection);
+            }
+            env.pop();
+        }
         BaseExprData::IfStatement { condition, body, else_statement } => {
             uniquify_rec_expr(condition, env, &mut variable_collection.names);
             env.push(VariableScope::new());
@@ -203,6 +213,7 @@ fn uniquify_rec_expr(
 ) {
     match &mut rec_expr.data {
         RecExprData::Number { .. } => {}
+        RecExprData::Float { .. } => {}
         RecExprData::Boolean { .. } => {}
         RecExprData::String { .. } => {}
         RecExprData::Variable { name } => {
@@ -216,12 +227,12 @@ fn uniquify_rec_expr(
             for arg in args.iter_mut() {
                 uniquify_rec_expr(arg, env, collected_names);
             }
-            print!("{:?}", args);
         }
         RecExprData::Add { left , right } => uniquify_binary_operator(left, right, env, collected_names),
         RecExprData::Subtract { left , right } => uniquify_binary_operator(left, right, env, collected_names),
         RecExprData::Multiply { left , right } => uniquify_binary_operator(left, right, env, collected_names),
         RecExprData::Divide { left , right } => uniquify_binary_operator(left, right, env, collected_names),
+        RecExprData::FloorDivide { left , right } => uniquify_binary_operator(left, right, env, collected_names),
         RecExprData::Power { left , right } => uniquify_binary_operator(left, right, env, collected_names),
         RecExprData::Minus { right } => uniquify_rec_expr(right, env, collected_names),
         RecExprData::And { left, right } => uniquify_binary_operator(left, right, env, collected_names),
@@ -238,15 +249,21 @@ fn uniquify_rec_expr(
                 uniquify_rec_expr(element, env, collected_names);
             }
         }
-        RecExprData::ListAccess { variable, index } => {
-            // Check the variable we are indexing into
-            if let Some(unique_name) = exists_in_environment(variable, env) {
-                *variable = unique_name;
-            } else {
-                panic!("Variable '{}' is not defined in the current scope", variable);
-            }
+        RecExprData::ListAccess { list, index } => {
+            uniquify_rec_expr(list, env, collected_names);
             uniquify_rec_expr(index, env, collected_names);
         }
+        RecExprData::Conditional { condition, then_value, else_value } => {
+            uniquify_rec_expr(then_value, env, collected_names);
+            uniquify_rec_expr(condition, env, collected_names);
+            uniquify_rec_expr(else_value, env, collected_names);
+        }
+        RecExprData::Slice { list, start, end } => {
+            uniquify_rec_expr(list, env, collected_names);
+            for bound in start.iter_mut().chain(end.iter_mut()) {
+                uniquify_rec_expr(bound, env, collected_names);
+            }
+        }
         _ => {}
     }
 }
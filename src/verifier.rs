@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use crate::codegenerator::Instruction;
+use crate::parser::BaseExpr;
+use crate::parser::BaseExprData;
+use crate::parser::RecExpr;
+use crate::parser::RecExprData;
+use crate::tac::TacInstruction;
+use crate::tacformat;
+use crate::tokenizer::Error;
+use crate::tokenizer::Span;
+use crate::typechecker::FunctionType;
+use crate::typechecker::Type;
+use crate::visitor;
+use crate::visitor::Visitor;
+
+// Checks the invariants that the passes of the toolchain rely on, after each pass.
+// A broken invariant is a bug in the pass that ran last, not in the program being compiled,
+// so the error names that pass.
+//
+// The checks always run in debug builds, and in release builds with --verify.
+
+static VERIFY: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    VERIFY.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    cfg!(debug_assertions) || VERIFY.load(Ordering::Relaxed)
+}
+
+// Runs the check on the output of the pass when verification is enabled
+pub fn verify<P: ?Sized>(
+    pass: &str,
+    output: &P,
+    check: fn(&P) -> Result<(), Error>,
+) -> Result<(), Error> {
+    if !is_enabled() {
+        return Ok(());
+    }
+    check(output).map_err(|error| match error {
+        Error::LocationError {
+            message,
+            span,
+        } => Error::LocationError {
+            message: format!("Verification failed after {}: {}", pass, message),
+            span,
+        },
+        Error::SimpleError { message } => Error::SimpleError {
+            message: format!("Verification failed after {}: {}", pass, message),
+        },
+        other => other,
+    })
+}
+
+// Checks the tree as it is after parsing or desugaring:
+// - every expression starts after the expression it is part of, and after the expressions before it
+// - the statements of a block are in the order of their rows
+pub fn check_program<T: Clone>(program: &[BaseExpr<T>]) -> Result<(), Error> {
+    let mut checker = TreeChecker::new(|_: &RecExpr<T>| None, false);
+    checker.visit_body(program);
+    checker.finish()
+}
+
+// Checks the typed tree as it is after typechecking and the passes after it, including the bodies of the
+// function instances, with the checks of check_program and:
+// - every expression has a type, except calls which give nothing and lookups into empty lists or dicts
+// - continue is only used inside of a loop of the same function
+pub fn check_typed_program(
+    typed_program: &(Vec<BaseExpr<Type>>, Vec<FunctionType>),
+) -> Result<(), Error> {
+    let mut checker = TreeChecker::new(missing_type, true);
+    checker.visit_body(&typed_program.0);
+    for function in typed_program.1.iter() {
+        checker.visit_body(&function.content);
+    }
+    checker.finish()
+}
+
+fn missing_type(rec_expr: &RecExpr<Type>) -> Option<String> {
+    match (&rec_expr.data, &rec_expr.generic_data) {
+        (RecExprData::FunctionCall { .. } | RecExprData::ListAccess { .. }, _) => None,
+        (data, Type::Undefined) => Some(format!("The expression {:?} has no type", data)),
+        _ => None,
+    }
+}
+
+// Where an expression starts, as (row, col)
+type Position = (usize, usize);
+
+struct TreeChecker<T: Clone> {
+    check_node: fn(&RecExpr<T>) -> Option<String>,
+    // For every expression around the current one: where it starts and where its last visited child starts
+    frames: Vec<(Position, Option<Position>)>,
+    // Whether the loops around a continue are checked, the typechecker rejects a continue outside of
+    // a loop so this only holds from typechecking on
+    check_loops: bool,
+    loop_depth: usize,
+    error: Option<Error>,
+}
+
+impl<T: Clone> TreeChecker<T> {
+    fn new(check_node: fn(&RecExpr<T>) -> Option<String>, check_loops: bool) -> TreeChecker<T> {
+        TreeChecker {
+            check_node,
+            frames: Vec::new(),
+            check_loops,
+            loop_depth: 0,
+            error: None,
+        }
+    }
+
+    fn fail(&mut self, error: Error) {
+        // The first broken invariant is the closest to the bug
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    // Checks the start of a node against its parent and its previous sibling, and makes it the parent
+    fn enter(&mut self, start: Position, span: Span) {
+        if let Some((parent_start, previous_sibling)) = self.frames.last_mut() {
+            if start < *parent_start {
+                let parent_start = *parent_start;
+                self.fail(Error::located(
+                    format!(
+                        "The node starts at {}:{}, before the node around it at {}:{}",
+                        start.0 + 1,
+                        start.1 + 1,
+                        parent_start.0 + 1,
+                        parent_start.1 + 1
+                    ),
+                    span,
+                ));
+            } else if matches!(previous_sibling, Some(previous) if start < *previous) {
+                let previous = previous_sibling.unwrap();
+                self.fail(Error::located(
+                    format!(
+                        "The node starts at {}:{}, before the node in front of it at {}:{}",
+                        start.0 + 1,
+                        start.1 + 1,
+                        previous.0 + 1,
+                        previous.1 + 1
+                    ),
+                    span,
+                ));
+            } else {
+                *previous_sibling = Some(start);
+            }
+        }
+        self.frames.push((start, None));
+    }
+
+    fn exit(&mut self) {
+        self.frames.pop();
+    }
+}
+
+impl<T: Clone> Visitor<T> for TreeChecker<T> {
+    fn visit_body(&mut self, body: &[BaseExpr<T>]) {
+        // A block starts a new list of siblings, the statements are checked by their rows only
+        // since a statement can start left of the statement before it
+        self.frames.push(((0, 0), None));
+        let mut previous_row = 0;
+        for base_expr in body {
+            if base_expr.span.row < previous_row {
+                self.fail(Error::located(
+                    format!(
+                        "The statement on line {} comes after a statement on line {}",
+                        base_expr.span.row + 1,
+                        previous_row + 1
+                    ),
+                    base_expr.span(),
+                ));
+            }
+            previous_row = base_expr.span.row;
+            self.frames.last_mut().unwrap().1 = None;
+            self.visit_base_expr(base_expr);
+        }
+        self.frames.pop();
+    }
+
+    fn visit_base_expr(&mut self, base_expr: &BaseExpr<T>) {
+        self.enter((base_expr.span.row, base_expr.span.col_start), base_expr.span());
+        match &base_expr.data {
+            BaseExprData::Continue if self.check_loops && self.loop_depth == 0 => {
+                self.fail(Error::located(
+                    "continue outside of a loop".to_string(),
+                    base_expr.span(),
+                ));
+            }
+            BaseExprData::ForLoop { .. } | BaseExprData::WhileLoop { .. } => {
+                self.loop_depth += 1;
+                visitor::walk_base_expr(self, base_expr);
+                self.loop_depth -= 1;
+            }
+            // A loop around a function definition does not continue into its body
+            BaseExprData::FunctionDefinition { .. } => {
+                let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+                visitor::walk_base_expr(self, base_expr);
+                self.loop_depth = loop_depth;
+            }
+            _ => visitor::walk_base_expr(self, base_expr),
+        }
+        self.exit();
+    }
+
+    fn visit_rec_expr(&mut self, rec_expr: &RecExpr<T>) {
+        if let Some(message) = (self.check_node)(rec_expr) {
+            self.fail(Error::located(message, rec_expr.span()));
+        }
+        self.enter((rec_expr.span.row, rec_expr.span.col_start), rec_expr.span());
+        visitor::walk_rec_expr(self, rec_expr);
+        self.exit();
+    }
+}
+
+// Checks the three address code, giving the index of the first broken instruction:
+// - every label is defined once
+// - jumps go to a label inside the same function, or the top level code for jumps outside of functions
+// - calls go to the label of a function
+pub fn check_tac(instructions: &[TacInstruction]) -> Result<(), (usize, String)> {
+    // The labels of every block, a block being a function or the top level code after ProgramStart
+    let mut block_of_label = HashMap::new();
+    let mut function_labels = Vec::new();
+    let mut blocks = Vec::with_capacity(instructions.len());
+    let mut block = 0;
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        if matches!(
+            instruction,
+            TacInstruction::FunctionLabel(..) | TacInstruction::ProgramStart()
+        ) {
+            block += 1;
+        }
+        blocks.push(block);
+
+        let label = match instruction {
+            TacInstruction::FunctionLabel(name, _) => {
+                function_labels.push(name.as_str());
+                name
+            }
+            TacInstruction::Label(name)
+            | TacInstruction::DirectInstruction(Instruction::Label(name, _)) => name,
+            _ => continue,
+        };
+        if block_of_label.insert(label.as_str(), block).is_some() {
+            return Err((index, format!("The label {} is defined more than once", label)));
+        }
+    }
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        let jump_target = match instruction {
+            TacInstruction::Goto(label)
+            | TacInstruction::CompareAndGoto(_, _, _, label)
+            | TacInstruction::DirectInstruction(
+                Instruction::Jmp(label)
+                | Instruction::Je(label)
+                | Instruction::Jne(label)
+                | Instruction::Jg(label)
+                | Instruction::Jge(label)
+                | Instruction::Jl(label)
+                | Instruction::Jle(label),
+            ) => label,
+            TacInstruction::Call(label, _, _)
+            | TacInstruction::DirectInstruction(Instruction::Call(label)) => {
+                if !function_labels.contains(&label.as_str()) {
+                    return Err((index, format!("Unknown function label {}", label)));
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        match block_of_label.get(jump_target.as_str()) {
+            None => return Err((index, format!("Unknown label {}", jump_target))),
+            Some(target_block) if *target_block != blocks[index] => {
+                return Err((
+                    index,
+                    format!("The jump to {} leaves the function it is in", jump_target),
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+// check_tac as an error that shows the broken instruction
+pub fn check_tac_instructions(instructions: &[TacInstruction]) -> Result<(), Error> {
+    check_tac(instructions).map_err(|(index, message)| Error::SimpleError {
+        message: format!(
+            "{} at instruction {}: {}",
+            message,
+            index,
+            tacformat::instruction_to_text(&instructions[index])
+        ),
+    })
+}
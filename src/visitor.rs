@@ -0,0 +1,597 @@
+use crate::parser::Accessor;
+use crate::parser::BaseExpr;
+use crate::parser::BaseExprData;
+use crate::parser::MatchArm;
+use crate::parser::Parameter;
+use crate::parser::RecExpr;
+use crate::parser::RecExprData;
+use crate::stack;
+use crate::tokenizer::Error;
+use crate::tokenizer::Span;
+
+// Traversals over the syntax tree, so that a pass only has to handle the expressions it cares about.
+// A pass overrides the methods for the nodes it is interested in, and calls the matching walk function
+// (or the default method) to continue into the children of a node.
+
+// Visits the tree without changing it
+pub trait Visitor<T: Clone> {
+    fn visit_body(&mut self, body: &[BaseExpr<T>]) {
+        for base_expr in body {
+            self.visit_base_expr(base_expr);
+        }
+    }
+
+    fn visit_base_expr(&mut self, base_expr: &BaseExpr<T>) {
+        walk_base_expr(self, base_expr);
+    }
+
+    fn visit_rec_expr(&mut self, rec_expr: &RecExpr<T>) {
+        walk_rec_expr(self, rec_expr);
+    }
+}
+
+// Takes the tree apart and builds it back up, stopping at the first error.
+// A pass can replace any node, and fold_body can change the number of statements in a block.
+pub trait Folder<T: Clone> {
+    fn fold_body(&mut self, body: Vec<BaseExpr<T>>) -> Result<Vec<BaseExpr<T>>, Error> {
+        body.into_iter()
+            .map(|base_expr| self.fold_base_expr(base_expr))
+            .collect()
+    }
+
+    fn fold_base_expr(&mut self, base_expr: BaseExpr<T>) -> Result<BaseExpr<T>, Error> {
+        fold_base_expr_children(self, base_expr)
+    }
+
+    fn fold_rec_expr(&mut self, rec_expr: RecExpr<T>) -> Result<RecExpr<T>, Error> {
+        fold_rec_expr_children(self, rec_expr)
+    }
+}
+
+// Visits the expressions and blocks directly inside the base expression, in source order
+pub fn walk_base_expr<T: Clone, V: Visitor<T> + ?Sized>(visitor: &mut V, base_expr: &BaseExpr<T>) {
+    // Nested blocks recurse once per level, see stack::ensure_stack
+    stack::ensure_stack(|| match &base_expr.data {
+        BaseExprData::Simple { expr }
+        | BaseExprData::VariableAssignment { expr, .. }
+        | BaseExprData::DestructuringAssignment { expr, .. }
+        | BaseExprData::PlusEqualsStatement { expr, .. }
+        | BaseExprData::Defer { expr } => visitor.visit_rec_expr(expr),
+        BaseExprData::FieldAssignment { path, expr, .. } => {
+            walk_path(visitor, path);
+            visitor.visit_rec_expr(expr);
+        }
+        BaseExprData::IndexAssignment {
+            path, index, expr, ..
+        } => {
+            walk_path(visitor, path);
+            visitor.visit_rec_expr(index);
+            visitor.visit_rec_expr(expr);
+        }
+        BaseExprData::IfStatement {
+            condition,
+            body,
+            else_statement,
+        }
+        | BaseExprData::ElseIfStatement {
+            condition,
+            body,
+            else_statement,
+        } => {
+            visitor.visit_rec_expr(condition);
+            visitor.visit_body(body);
+            if let Some(else_statement) = else_statement {
+                visitor.visit_base_expr(else_statement);
+            }
+        }
+        BaseExprData::ElseStatement { body } => visitor.visit_body(body),
+        BaseExprData::FunctionDefinition { args, body, .. } => {
+            for default in args.iter().filter_map(|arg| arg.default.as_ref()) {
+                visitor.visit_rec_expr(default);
+            }
+            visitor.visit_body(body)
+        }
+        BaseExprData::ForLoop {
+            until: condition,
+            body,
+            ..
+        }
+        | BaseExprData::WhileLoop { condition, body } => {
+            visitor.visit_rec_expr(condition);
+            visitor.visit_body(body);
+        }
+        BaseExprData::With { resource, body, .. } => {
+            visitor.visit_rec_expr(resource);
+            visitor.visit_body(body);
+        }
+        BaseExprData::Match { subject, arms } => {
+            visitor.visit_rec_expr(subject);
+            for arm in arms {
+                visitor.visit_body(&arm.body);
+            }
+        }
+        BaseExprData::Return { return_value } => {
+            if let Some(return_value) = return_value {
+                visitor.visit_rec_expr(return_value);
+            }
+        }
+        BaseExprData::Yield { value } => visitor.visit_rec_expr(value),
+        BaseExprData::StructDefinition { .. }
+        | BaseExprData::Break
+        | BaseExprData::Continue
+        | BaseExprData::Delete { .. }
+        | BaseExprData::Swap { .. } => {}
+    })
+}
+
+// Visits the indices along the path of a nested assignment, see Accessor
+fn walk_path<T: Clone, V: Visitor<T> + ?Sized>(visitor: &mut V, path: &[Accessor<T>]) {
+    for accessor in path {
+        if let Accessor::Index(index) = accessor {
+            visitor.visit_rec_expr(index);
+        }
+    }
+}
+
+// Visits the operands of the expression, in source order
+pub fn walk_rec_expr<T: Clone, V: Visitor<T> + ?Sized>(visitor: &mut V, rec_expr: &RecExpr<T>) {
+    // Nested expressions recurse once per level, see stack::ensure_stack
+    stack::ensure_stack(|| match &rec_expr.data {
+        RecExprData::Add { left, right }
+        | RecExprData::Subtract { left, right }
+        | RecExprData::Multiply { left, right }
+        | RecExprData::Divide { left, right }
+        | RecExprData::FloorDivide { left, right }
+        | RecExprData::Power { left, right }
+        | RecExprData::Or { left, right }
+        | RecExprData::And { left, right }
+        | RecExprData::Equals { left, right }
+        | RecExprData::NotEquals { left, right }
+        | RecExprData::GreaterThan { left, right }
+        | RecExprData::LessThan { left, right }
+        | RecExprData::GreaterThanOrEqual { left, right }
+        | RecExprData::LessThanOrEqual { left, right } => {
+            visitor.visit_rec_expr(left);
+            visitor.visit_rec_expr(right);
+        }
+        RecExprData::Assign { right, .. }
+        | RecExprData::Minus { right }
+        | RecExprData::Not { right }
+        | RecExprData::KeywordArgument { value: right, .. } => visitor.visit_rec_expr(right),
+        RecExprData::FunctionCall { args, .. } => {
+            for arg in args {
+                visitor.visit_rec_expr(arg);
+            }
+        }
+        RecExprData::Conditional {
+            condition,
+            then_value,
+            else_value,
+        } => {
+            visitor.visit_rec_expr(then_value);
+            visitor.visit_rec_expr(condition);
+            visitor.visit_rec_expr(else_value);
+        }
+        RecExprData::List { elements } | RecExprData::Tuple { elements } => {
+            for element in elements {
+                visitor.visit_rec_expr(element);
+            }
+        }
+        RecExprData::ListAccess { list, index } => {
+            visitor.visit_rec_expr(list);
+            visitor.visit_rec_expr(index);
+        }
+        RecExprData::Slice { list, start, end } => {
+            visitor.visit_rec_expr(list);
+            for bound in start.iter().chain(end) {
+                visitor.visit_rec_expr(bound);
+            }
+        }
+        RecExprData::Dict { entries } => {
+            for (key, value) in entries {
+                visitor.visit_rec_expr(key);
+                visitor.visit_rec_expr(value);
+            }
+        }
+        RecExprData::Variable { .. }
+        | RecExprData::Number { .. }
+        | RecExprData::Float { .. }
+        | RecExprData::String { .. }
+        | RecExprData::Boolean { .. }
+        | RecExprData::Access { .. } => {}
+    })
+}
+
+// Folds the expressions and blocks directly inside the base expression, keeping the base expression itself
+pub fn fold_base_expr_children<T: Clone, F: Folder<T> + ?Sized>(
+    folder: &mut F,
+    base_expr: BaseExpr<T>,
+) -> Result<BaseExpr<T>, Error> {
+    // Nested blocks recurse once per level, see stack::ensure_stack
+    stack::ensure_stack(|| {
+        let data = match base_expr.data {
+            BaseExprData::Simple { expr } => BaseExprData::Simple {
+                expr: folder.fold_rec_expr(expr)?,
+            },
+            BaseExprData::VariableAssignment { var_name, expr } => {
+                BaseExprData::VariableAssignment {
+                    var_name,
+                    expr: folder.fold_rec_expr(expr)?,
+                }
+            }
+            BaseExprData::DestructuringAssignment { var_names, expr } => {
+                BaseExprData::DestructuringAssignment {
+                    var_names,
+                    expr: folder.fold_rec_expr(expr)?,
+                }
+            }
+            BaseExprData::PlusEqualsStatement { var_name, expr } => {
+                BaseExprData::PlusEqualsStatement {
+                    var_name,
+                    expr: folder.fold_rec_expr(expr)?,
+                }
+            }
+            BaseExprData::FieldAssignment {
+                object,
+                path,
+                field,
+                expr,
+            } => BaseExprData::FieldAssignment {
+                object,
+                path: fold_path(folder, path)?,
+                field,
+                expr: folder.fold_rec_expr(expr)?,
+            },
+            BaseExprData::IndexAssignment {
+                object,
+                path,
+                index,
+                expr,
+            } => BaseExprData::IndexAssignment {
+                object,
+                path: fold_path(folder, path)?,
+                index: folder.fold_rec_expr(index)?,
+                expr: folder.fold_rec_expr(expr)?,
+            },
+            BaseExprData::IfStatement {
+                condition,
+                body,
+                else_statement,
+            } => BaseExprData::IfStatement {
+                condition: folder.fold_rec_expr(condition)?,
+                body: folder.fold_body(body)?,
+                else_statement: fold_else_statement(folder, else_statement)?,
+            },
+            BaseExprData::ElseIfStatement {
+                condition,
+                body,
+                else_statement,
+            } => BaseExprData::ElseIfStatement {
+                condition: folder.fold_rec_expr(condition)?,
+                body: folder.fold_body(body)?,
+                else_statement: fold_else_statement(folder, else_statement)?,
+            },
+            BaseExprData::ElseStatement { body } => BaseExprData::ElseStatement {
+                body: folder.fold_body(body)?,
+            },
+            BaseExprData::ForLoop {
+                var_name,
+                until,
+                body,
+            } => BaseExprData::ForLoop {
+                var_name,
+                until: folder.fold_rec_expr(until)?,
+                body: folder.fold_body(body)?,
+            },
+            BaseExprData::WhileLoop { condition, body } => BaseExprData::WhileLoop {
+                condition: folder.fold_rec_expr(condition)?,
+                body: folder.fold_body(body)?,
+            },
+            BaseExprData::FunctionDefinition {
+                fun_name,
+                args,
+                body,
+            } => BaseExprData::FunctionDefinition {
+                fun_name,
+                args: args
+                    .into_iter()
+                    .map(|arg| {
+                        Ok(Parameter {
+                            name: arg.name,
+                            default: match arg.default {
+                                Some(default) => Some(folder.fold_rec_expr(default)?),
+                                None => None,
+                            },
+                            rest: arg.rest,
+                        })
+                    })
+                    .collect::<Result<_, Error>>()?,
+                body: folder.fold_body(body)?,
+            },
+            BaseExprData::Return { return_value } => BaseExprData::Return {
+                return_value: match return_value {
+                    Some(return_value) => Some(folder.fold_rec_expr(return_value)?),
+                    None => None,
+                },
+            },
+            BaseExprData::Yield { value } => BaseExprData::Yield {
+                value: folder.fold_rec_expr(value)?,
+            },
+            BaseExprData::Defer { expr } => BaseExprData::Defer {
+                expr: folder.fold_rec_expr(expr)?,
+            },
+            BaseExprData::With {
+                resource,
+                var_name,
+                body,
+            } => BaseExprData::With {
+                resource: folder.fold_rec_expr(resource)?,
+                var_name,
+                body: folder.fold_body(body)?,
+            },
+            BaseExprData::Match { subject, arms } => BaseExprData::Match {
+                subject: folder.fold_rec_expr(subject)?,
+                arms: arms
+                    .into_iter()
+                    .map(|arm| {
+                        let span = arm.span();
+                        Ok(MatchArm::new(arm.pattern, folder.fold_body(arm.body)?, span))
+                    })
+                    .collect::<Result<_, Error>>()?,
+            },
+            other @ (BaseExprData::StructDefinition { .. }
+            | BaseExprData::Break
+            | BaseExprData::Continue
+            | BaseExprData::Delete { .. }
+            | BaseExprData::Swap { .. }) => other,
+        };
+
+        Ok(BaseExpr { data, ..base_expr })
+    })
+}
+
+fn fold_else_statement<T: Clone, F: Folder<T> + ?Sized>(
+    folder: &mut F,
+    else_statement: Option<Box<BaseExpr<T>>>,
+) -> Result<Option<Box<BaseExpr<T>>>, Error> {
+    match else_statement {
+        Some(else_statement) => Ok(Some(Box::new(folder.fold_base_expr(*else_statement)?))),
+        None => Ok(None),
+    }
+}
+
+// Folds the operands of the expression, keeping the expression itself
+pub fn fold_rec_expr_children<T: Clone, F: Folder<T> + ?Sized>(
+    folder: &mut F,
+    rec_expr: RecExpr<T>,
+) -> Result<RecExpr<T>, Error> {
+    // Nested expressions recurse once per level, see stack::ensure_stack
+    stack::ensure_stack(|| {
+        let data = match rec_expr.data {
+            RecExprData::Assign {
+                variable_name,
+                right,
+            } => RecExprData::Assign {
+                variable_name,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::Add { left, right } => RecExprData::Add {
+                left: fold_operand(folder, *left)?,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::Subtract { left, right } => RecExprData::Subtract {
+                left: fold_operand(folder, *left)?,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::Multiply { left, right } => RecExprData::Multiply {
+                left: fold_operand(folder, *left)?,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::Divide { left, right } => RecExprData::Divide {
+                left: fold_operand(folder, *left)?,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::FloorDivide { left, right } => RecExprData::FloorDivide {
+                left: fold_operand(folder, *left)?,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::Power { left, right } => RecExprData::Power {
+                left: fold_operand(folder, *left)?,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::Minus { right } => RecExprData::Minus {
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::Or { left, right } => RecExprData::Or {
+                left: fold_operand(folder, *left)?,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::And { left, right } => RecExprData::And {
+                left: fold_operand(folder, *left)?,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::Not { right } => RecExprData::Not {
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::Equals { left, right } => RecExprData::Equals {
+                left: fold_operand(folder, *left)?,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::NotEquals { left, right } => RecExprData::NotEquals {
+                left: fold_operand(folder, *left)?,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::GreaterThan { left, right } => RecExprData::GreaterThan {
+                left: fold_operand(folder, *left)?,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::LessThan { left, right } => RecExprData::LessThan {
+                left: fold_operand(folder, *left)?,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::GreaterThanOrEqual { left, right } => RecExprData::GreaterThanOrEqual {
+                left: fold_operand(folder, *left)?,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::LessThanOrEqual { left, right } => RecExprData::LessThanOrEqual {
+                left: fold_operand(folder, *left)?,
+                right: fold_operand(folder, *right)?,
+            },
+            RecExprData::FunctionCall {
+                function_name,
+                args,
+            } => RecExprData::FunctionCall {
+                function_name,
+                args: args
+                    .into_iter()
+                    .map(|arg| folder.fold_rec_expr(arg))
+                    .collect::<Result<_, _>>()?,
+            },
+            RecExprData::List { elements } => RecExprData::List {
+                elements: elements
+                    .into_iter()
+                    .map(|element| folder.fold_rec_expr(element))
+                    .collect::<Result<_, _>>()?,
+            },
+            RecExprData::ListAccess { list, index } => RecExprData::ListAccess {
+                list: fold_operand(folder, *list)?,
+                index: fold_operand(folder, *index)?,
+            },
+            RecExprData::Slice { list, start, end } => RecExprData::Slice {
+                list: fold_operand(folder, *list)?,
+                start: start.map(|start| fold_operand(folder, *start)).transpose()?,
+                end: end.map(|end| fold_operand(folder, *end)).transpose()?,
+            },
+            RecExprData::Dict { entries } => RecExprData::Dict {
+                entries: entries
+                    .into_iter()
+                    .map(|(key, value)| Ok((folder.fold_rec_expr(key)?, folder.fold_rec_expr(value)?)))
+                    .collect::<Result<_, Error>>()?,
+            },
+            RecExprData::Tuple { elements } => RecExprData::Tuple {
+                elements: elements
+                    .into_iter()
+                    .map(|element| folder.fold_rec_expr(element))
+                    .collect::<Result<_, _>>()?,
+            },
+            RecExprData::KeywordArgument { name, value } => RecExprData::KeywordArgument {
+                name,
+                value: fold_operand(folder, *value)?,
+            },
+            RecExprData::Conditional {
+                condition,
+                then_value,
+                else_value,
+            } => {
+                let then_value = fold_operand(folder, *then_value)?;
+                RecExprData::Conditional {
+                    condition: fold_operand(folder, *condition)?,
+                    then_value,
+                    else_value: fold_operand(folder, *else_value)?,
+                }
+            }
+            other @ (RecExprData::Variable { .. }
+            | RecExprData::Number { .. }
+            | RecExprData::Float { .. }
+            | RecExprData::String { .. }
+            | RecExprData::Boolean { .. }
+            | RecExprData::Access { .. }) => other,
+        };
+
+        Ok(RecExpr { data, ..rec_expr })
+    })
+}
+
+fn fold_path<T: Clone, F: Folder<T> + ?Sized>(
+    folder: &mut F,
+    path: Vec<Accessor<T>>,
+) -> Result<Vec<Accessor<T>>, Error> {
+    path.into_iter()
+        .map(|accessor| match accessor {
+            Accessor::Index(index) => Ok(Accessor::Index(folder.fold_rec_expr(index)?)),
+            field => Ok(field),
+        })
+        .collect()
+}
+
+fn fold_operand<T: Clone, F: Folder<T> + ?Sized>(
+    folder: &mut F,
+    operand: RecExpr<T>,
+) -> Result<Box<RecExpr<T>>, Error> {
+    Ok(Box::new(folder.fold_rec_expr(operand)?))
+}
+
+// The names of the variables and functions the statements read or change, which a function defined
+// around them captures from the function it is defined in
+pub fn referenced_names<T: Clone>(body: &[BaseExpr<T>]) -> Vec<String> {
+    let mut collector = NameCollector { names: Vec::new() };
+    collector.visit_body(body);
+    collector.names
+}
+
+// The same for a single expression, such as the default value of a parameter
+pub fn referenced_names_in_expression<T: Clone>(rec_expr: &RecExpr<T>) -> Vec<String> {
+    let mut collector = NameCollector { names: Vec::new() };
+    collector.visit_rec_expr(rec_expr);
+    collector.names
+}
+
+struct NameCollector {
+    names: Vec<String>,
+}
+
+impl NameCollector {
+    fn add(&mut self, name: &String) {
+        if !self.names.contains(name) {
+            self.names.push(name.clone());
+        }
+    }
+}
+
+impl<T: Clone> Visitor<T> for NameCollector {
+    fn visit_base_expr(&mut self, base_expr: &BaseExpr<T>) {
+        match &base_expr.data {
+            BaseExprData::PlusEqualsStatement { var_name, .. }
+            | BaseExprData::Delete { var_name } => self.add(var_name),
+            BaseExprData::FieldAssignment { object, .. }
+            | BaseExprData::IndexAssignment { object, .. } => self.add(object),
+            BaseExprData::Swap {
+                first_name,
+                second_name,
+            } => {
+                self.add(first_name);
+                self.add(second_name);
+            }
+            _ => {}
+        }
+        walk_base_expr(self, base_expr);
+    }
+
+    fn visit_rec_expr(&mut self, rec_expr: &RecExpr<T>) {
+        match &rec_expr.data {
+            RecExprData::Variable { name } => self.add(name),
+            RecExprData::Assign { variable_name, .. } => self.add(variable_name),
+            RecExprData::FunctionCall { function_name, .. } => self.add(function_name),
+            RecExprData::Access { object, .. } => self.add(object),
+            _ => {}
+        }
+        walk_rec_expr(self, rec_expr);
+    }
+}
+
+// Gives every node of the expression the same place in the source, for an expression moved to another
+// place such as an inlined function body or a default value filled in at a call
+pub fn respan<T: Clone>(rec_expr: RecExpr<T>, span: Span) -> Result<RecExpr<T>, Error> {
+    struct Respanner {
+        span: Span,
+    }
+
+    impl<T: Clone> Folder<T> for Respanner {
+        fn fold_rec_expr(&mut self, rec_expr: RecExpr<T>) -> Result<RecExpr<T>, Error> {
+            let rec_expr = fold_rec_expr_children(self, rec_expr)?;
+            Ok(RecExpr::new(rec_expr.data, self.span, rec_expr.generic_data))
+        }
+    }
+
+    Respanner { span }.fold_rec_expr(rec_expr)
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use rosy::benchmark;
+use rosy::benchmark::BenchOptions;
+use rosy::benchmark::BenchResult;
+use rosy::engine::Engine;
+
+#[test]
+fn run_bench_functions() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun bench_sum()",
+        "    total = 0",
+        "    for i in 10",
+        "        total = total + i",
+        "    return total",
+        "fun bench_nothing()",
+        "    return 0",
+        "fun helper()",
+        "    return 0",
+    ]);
+
+    let options = BenchOptions {
+        warmup: 1,
+        iterations: 3,
+        filter: None,
+    };
+    let results = benchmark::run_benchmarks(&Engine::new(), program, &options).unwrap();
+
+    let names: Vec<&str> = results.iter().map(|result| result.name.as_str()).collect();
+    assert_eq!(names, vec!["bench_sum", "bench_nothing"]);
+    assert!(results.iter().all(|result| result.durations.len() == 3));
+    assert_eq!(results[0].statements_per_run, 13);
+    assert_eq!(results[1].statements_per_run, 1);
+
+    // Benchmarks are called without arguments
+    let program = Vec::from(["fun bench_with(n)", "    return n"]);
+    assert!(benchmark::run_benchmarks(&Engine::new(), program, &options).is_err());
+}
+
+#[test]
+fn bench_result_statistics() {
+    let result = BenchResult {
+        name: String::from("bench"),
+        durations: vec![
+            Duration::from_millis(4),
+            Duration::from_millis(2),
+            Duration::from_millis(8),
+            Duration::from_millis(6),
+        ],
+        statements_per_run: 0,
+    };
+
+    assert_eq!(result.mean(), Duration::from_millis(5));
+    assert_eq!(result.median(), Duration::from_millis(5));
+    assert_eq!(
+        result.std_dev().as_micros(),
+        (5.0f64.sqrt() * 1000.0) as u128
+    );
+}
@@ -0,0 +1,133 @@
+#![cfg(feature = "core")]
+
+use rosy::consteval;
+use rosy::desugarer;
+use rosy::language::LanguageOptions;
+use rosy::parser;
+use rosy::typechecker;
+
+// The program after constant evaluation, in the debugging format of the parser
+fn evaluate(program: Vec<&str>, language: &LanguageOptions) -> String {
+    let parsed = parser::parse_strings_with_options(program, language).unwrap();
+    let mut typed_program =
+        typechecker::type_check_program_with_options(desugarer::desugar(parsed), false, language).unwrap();
+    consteval::evaluate_constants(&mut typed_program, language).unwrap();
+
+    let untyped: Vec<_> = typed_program
+        .0
+        .into_iter()
+        .map(|base_expr| base_expr.map_data(&mut |_| ()))
+        .collect();
+    parser::expressions_to_string(&untyped)
+}
+
+#[cfg(all(feature = "math", feature = "string", feature = "list"))]
+#[test]
+fn evaluate_constants_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "seconds = 60 * 60 * 24",
+        "label = pad_left(\"7\", 3, \"0\") + \"!\"",
+        "big = abs(-2) > 1 and not false",
+        "grid = make_grid(2, 1, 0)",
+        "half = seconds / 2 + seconds",
+    ]);
+
+    let evaluated = evaluate(program, &LanguageOptions::default());
+    #[rustfmt::skip]
+    let expected = [
+        "VarAssign(\"seconds\", Num(86400))",
+        "VarAssign(\"label\", Str(\"007!\"))",
+        "VarAssign(\"big\", Bool(true))",
+        "VarAssign(\"grid\", [[Num(0), ], [Num(0), ], ])",
+        // seconds is a variable, so nothing is evaluated here
+        "VarAssign(\"half\", ((Var(\"seconds\") / Num(2)) + Var(\"seconds\")))",
+        "",
+    ].join("\n");
+    assert_eq!(evaluated, expected);
+}
+
+#[cfg(feature = "math")]
+#[test]
+fn leave_failing_and_impure_expressions_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 1 / 0",
+        "b = random(1, 6)",
+        "c = 7 / 2",
+    ]);
+
+    // Division by zero fails when the program runs, and random gives a new number every run
+    #[rustfmt::skip]
+    let expected = [
+        "VarAssign(\"a\", (Num(1) / Num(0)))",
+        "VarAssign(\"b\", Call(\"random\" with (Num(1), Num(6), )))",
+        "VarAssign(\"c\", Num(3))",
+        "",
+    ].join("\n");
+    assert_eq!(evaluate(program.clone(), &LanguageOptions::default()), expected);
+
+    // The language options are those of the program
+    let true_division = LanguageOptions {
+        true_division: true,
+        ..LanguageOptions::default()
+    };
+    assert!(evaluate(program, &true_division).ends_with("VarAssign(\"c\", Float(3.5))\n"));
+}
+
+#[cfg(feature = "math")]
+#[test]
+fn evaluate_constants_untyped_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 2 * 3 + 1",
+        "b = abs(-4)",
+        "fun min(x, y)",
+        "    return x",
+        "c = min(5, 1)",
+    ]);
+
+    let parsed = parser::parse_strings(program).unwrap();
+    let (evaluated, count) = consteval::evaluate_constants_untyped(parsed, &LanguageOptions::default()).unwrap();
+
+    // The interpreter calls the min defined by the program, so it is not evaluated as the builtin
+    let printed = parser::expressions_to_string(&evaluated);
+    assert!(printed.contains("VarAssign(\"a\", Num(7))"));
+    assert!(printed.contains("VarAssign(\"b\", Num(4))"));
+    assert!(printed.contains("VarAssign(\"c\", Call(\"min\" with (Num(5), Num(1), )))"));
+    // 2 * 3 and -4 are folded on their own before the expressions around them
+    assert_eq!(count, 4);
+}
+
+#[cfg(all(feature = "string", feature = "list"))]
+#[test]
+fn leave_overflowing_and_large_expressions_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 9223372036854775807 + 1",
+        "b = 2 * 4611686018427387904",
+        "c = 2 ^ 64",
+        "d = repeat(\"ab\", 4611686018427387904)",
+        "e = make_grid(3037000500, 3037000500, 0)",
+        "f = pad_left(\"7\", 1000000, \"0\")",
+        "g = repeat(\"ab\", 2)",
+    ]);
+
+    // These would overflow or build a huge literal, so they are left for the program to run into
+    #[rustfmt::skip]
+    let expected = [
+        "VarAssign(\"a\", (Num(9223372036854775807) + Num(1)))",
+        "VarAssign(\"b\", (Num(2) * Num(4611686018427387904)))",
+        "VarAssign(\"c\", (Num(2) ^ Num(64)))",
+        "VarAssign(\"d\", Call(\"repeat\" with (Str(\"ab\"), Num(4611686018427387904), )))",
+        "VarAssign(\"e\", Call(\"make_grid\" with (Num(3037000500), Num(3037000500), Num(0), )))",
+        "VarAssign(\"f\", Call(\"pad_left\" with (Str(\"7\"), Num(1000000), Str(\"0\"), )))",
+        "VarAssign(\"g\", Str(\"abab\"))",
+        "",
+    ].join("\n");
+    assert_eq!(evaluate(program.clone(), &LanguageOptions::default()), expected);
+
+    let parsed = parser::parse_strings(program).unwrap();
+    let (_, count) = consteval::evaluate_constants_untyped(parsed, &LanguageOptions::default()).unwrap();
+    assert_eq!(count, 1);
+}
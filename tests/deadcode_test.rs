@@ -0,0 +1,69 @@
+use rosy::deadcode;
+use rosy::parser;
+
+// The program after dead code elimination, in the debugging format of the parser
+fn eliminate(program: Vec<&str>) -> (String, usize) {
+    let parsed = parser::parse_strings(program).unwrap();
+    let (optimised, removed) = deadcode::eliminate_dead_code(parsed).unwrap();
+    (parser::expressions_to_string(&optimised), removed)
+}
+
+#[test]
+fn remove_unreachable_statements_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun f()",
+        "    return 1",
+        "    println(2)",
+        "while false",
+        "    println(3)",
+        "if false",
+        "    println(4)",
+        "println(5)",
+    ]);
+
+    let (optimised, removed) = eliminate(program);
+    assert_eq!(removed, 3);
+    assert!(!optimised.contains("Num(2)"));
+    assert!(!optimised.contains("Num(3)"));
+    assert!(!optimised.contains("Num(4)"));
+    assert!(optimised.contains("Num(5)"));
+}
+
+#[test]
+fn remove_branches_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "if false",
+        "    println(1)",
+        "else if true",
+        "    println(2)",
+        "else",
+        "    println(3)",
+    ]);
+
+    // The else if which is always taken takes the place of the if, and the else after it is never reached
+    let (optimised, removed) = eliminate(program);
+    assert_eq!(removed, 2);
+    assert!(!optimised.contains("Num(1)"));
+    assert!(optimised.contains("Num(2)"));
+    assert!(!optimised.contains("Num(3)"));
+}
+
+#[test]
+fn keep_definitions_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "if false",
+        "    x = 1",
+        "fun f()",
+        "    return 1",
+        "    y = 2",
+    ]);
+
+    // Later statements could still refer to the names, so their definitions stay
+    let (optimised, removed) = eliminate(program);
+    assert_eq!(removed, 0);
+    assert!(optimised.contains("VarAssign(\"x\", Num(1))"));
+    assert!(optimised.contains("VarAssign(\"y\", Num(2))"));
+}
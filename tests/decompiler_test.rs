@@ -0,0 +1,146 @@
+use rosy::decompiler;
+use rosy::desugarer;
+use rosy::engine::Engine;
+use rosy::parser;
+use rosy::pipeline;
+use rosy::typechecker;
+
+fn to_lines(source: &str) -> Vec<&str> {
+    source.lines().collect()
+}
+
+#[test]
+fn program_to_source_roundtrip_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "struct Point",
+        "\tx",
+        "\ty",
+        "fun scale(p, factor)",
+        "\treturn Point(p.x * factor, p.y * factor)",
+        "p = scale(Point(1, 2), 3)",
+        "t = (p.x, (1,), \"a\")",
+        "s = t[0][1:p.x + 1] + t[2][:1]",
+        "p.x = 4",
+        "if p.x > 2 and not (p.y == 3)",
+        "\tprint(p.x)",
+        "else if p.x == 1",
+        "\tprint(\"one\")",
+        "else",
+        "\tfor i in [1, 2.5, -3]",
+        "\t\tcontinue",
+        "while false",
+        "\tbreak",
+        "swap a, b",
+        "del a",
+        "match p.y",
+        "\tcase -1",
+        "\t\tprint(\"below\")",
+        "\tcase true",
+        "\t\tprint(\"true\")",
+        "\tcase y",
+        "\t\tprint(y)",
+    ]);
+
+    let parsed = parser::parse_strings(program.clone()).unwrap();
+    let source = decompiler::program_to_source(&parsed);
+
+    assert_eq!(source, program.join("\n") + "\n");
+    // Writing the program again gives the same tree
+    assert_eq!(parser::parse_strings(to_lines(&source)).unwrap(), parsed);
+}
+
+#[test]
+fn parentheses_follow_precedence_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = (1 + 2) * 3 - (4 - 5)",
+        "b = 1 - 2 - 3",
+        "c = 2 ^ (3 ^ 2)",
+        "d = -(a + b) // 2",
+        "e = (a or b) and c",
+        "f = (a if e else b) + 1",
+        "g = (a if e else b) if c else d if e else f",
+    ]);
+
+    let parsed = parser::parse_strings(program.clone()).unwrap();
+    let source = decompiler::program_to_source(&parsed);
+
+    assert_eq!(source, program.join("\n") + "\n");
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn typed_program_to_source_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun add(a, b)",
+        "\treturn a + b",
+        "x = add(1, 2)",
+        "y = add(1.5, 2.0)",
+        "x += 1",
+        "print(x)",
+    ]);
+
+    let parsed = parser::parse_strings(program).unwrap();
+    let typed_program = typechecker::type_check_program(desugarer::desugar(parsed), false).unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        "fun add(a, b)  # add(a: int, b: int) -> int, add(a: float, b: float) -> float",
+        "\treturn a + b",
+        "x = add(1, 2)  # x: int",
+        "y = add(1.5, 2.0)  # y: float",
+        // The desugared form of x += 1
+        "x = x + 1  # x: int",
+        "print(x)",
+    ];
+    assert_eq!(
+        decompiler::typed_program_to_source(&typed_program, true),
+        expected.join("\n") + "\n"
+    );
+}
+
+#[test]
+fn decompile_pipeline_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "total = 0",
+        "for i in 3",
+        "\ttotal += i",
+    ]);
+
+    #[rustfmt::skip]
+    let expected = [
+        "total = 0",
+        "for i in 3",
+        "\ttotal = total + i",
+    ];
+    assert_eq!(
+        pipeline::run_decompile_pipeline(program, false).unwrap(),
+        expected.join("\n") + "\n"
+    );
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn nested_function_roundtrip_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun outer()",
+        "\tx = 1",
+        "\tfun show()",
+        "\t\tprintln(x)",
+        "\tx = 2",
+        "\tshow()",
+        "outer()",
+    ]);
+
+    // The nested function stays where it is defined, so it still captures x before it is reassigned
+    let source = pipeline::run_decompile_pipeline(program.clone(), false).unwrap();
+    assert_eq!(source, program.join("\n") + "\n");
+
+    let engine = Engine::new();
+    assert_eq!(engine.run(program).unwrap(), vec!["1".to_string(), String::new()]);
+    assert_eq!(engine.run(to_lines(&source)).unwrap(), vec!["1".to_string(), String::new()]);
+}
@@ -1,21 +1,19 @@
 use rosy::desugarer;
+use rosy::tokenizer::Span;
 use rosy::parser::{self, BaseExpr, BaseExprData, RecExpr, RecExprData};
 
 #[test]
 fn simple_number() {
+    let lines = vec!["42"];
     let program = vec![BaseExpr {
         data: BaseExprData::Simple {
             expr: RecExpr {
                 data: RecExprData::Number { number: 42 },
-                row: 0,
-                col_start: 0,
-                col_end: 2,
+                span: Span::in_lines(&lines, 0, 0, 2),
                 generic_data: (),
             },
         },
-        row: 0,
-        col_start: 0,
-        col_end: 2,
+        span: Span::in_lines(&lines, 0, 0, 2),
         generic_data: (),
     }];
 
@@ -23,15 +21,11 @@ fn simple_number() {
         data: BaseExprData::Simple {
             expr: RecExpr {
                 data: RecExprData::Number { number: 42 },
-                row: 0,
-                col_start: 0,
-                col_end: 2,
+                span: Span::in_lines(&lines, 0, 0, 2),
                 generic_data: (),
             },
         },
-        row: 0,
-        col_start: 0,
-        col_end: 2,
+        span: Span::in_lines(&lines, 0, 0, 2),
         generic_data: (),
     }];
 
@@ -44,6 +38,7 @@ fn plus_equals_statement() {
     // x += (5 * 3)
     // should desugar to
     // x = x + (5 * 3)
+    let lines = vec!["x += 5 * 3"];
     let program = vec![BaseExpr {
         data: BaseExprData::PlusEqualsStatement {
             var_name: String::from("x"),
@@ -51,28 +46,20 @@ fn plus_equals_statement() {
                 data: RecExprData::Multiply {
                     left: Box::new(RecExpr {
                         data: RecExprData::Number { number: 5 },
-                        row: 0,
-                        col_start: 5,
-                        col_end: 6,
+                        span: Span::in_lines(&lines, 0, 5, 6),
                         generic_data: (),
                     }),
                     right: Box::new(RecExpr {
                         data: RecExprData::Number { number: 3 },
-                        row: 0,
-                        col_start: 9,
-                        col_end: 10,
+                        span: Span::in_lines(&lines, 0, 9, 10),
                         generic_data: (),
                     }),
                 },
-                row: 0,
-                col_start: 5,
-                col_end: 10,
+                span: Span::in_lines(&lines, 0, 5, 10),
                 generic_data: (),
             },
         },
-        row: 0,
-        col_start: 0,
-        col_end: 10,
+        span: Span::in_lines(&lines, 0, 0, 10),
         generic_data: (),
     }];
 
@@ -85,43 +72,31 @@ fn plus_equals_statement() {
                         data: RecExprData::Variable {
                             name: String::from("x"),
                         },
-                        row: 0,
-                        col_start: 0,
-                        col_end: 1,
+                        span: Span::in_lines(&lines, 0, 0, 1),
                         generic_data: (),
                     }),
                     right: Box::new(RecExpr {
                         data: RecExprData::Multiply {
                             left: Box::new(RecExpr {
                                 data: RecExprData::Number { number: 5 },
-                                row: 0,
-                                col_start: 5,
-                                col_end: 6,
+                                span: Span::in_lines(&lines, 0, 5, 6),
                                 generic_data: (),
                             }),
                             right: Box::new(RecExpr {
                                 data: RecExprData::Number { number: 3 },
-                                row: 0,
-                                col_start: 9,
-                                col_end: 10,
+                                span: Span::in_lines(&lines, 0, 9, 10),
                                 generic_data: (),
                             }),
                         },
-                        row: 0,
-                        col_start: 5,
-                        col_end: 10,
+                        span: Span::in_lines(&lines, 0, 5, 10),
                         generic_data: (),
                     }),
                 },
-                row: 0,
-                col_start: 0,
-                col_end: 10,
+                span: Span::in_lines(&lines, 0, 0, 10),
                 generic_data: (),
             },
         },
-        row: 0,
-        col_start: 0,
-        col_end: 10,
+        span: Span::in_lines(&lines, 0, 0, 10),
         generic_data: (),
     }];
 
@@ -134,20 +109,17 @@ fn plus_equals_statement_simple() {
     // x += 5
     // should desugar to
     // x = x + 5
+    let lines = vec!["x += 5"];
     let program = vec![BaseExpr {
         data: BaseExprData::PlusEqualsStatement {
             var_name: String::from("x"),
             expr: RecExpr {
                 data: RecExprData::Number { number: 5 },
-                row: 0,
-                col_start: 5,
-                col_end: 6,
+                span: Span::in_lines(&lines, 0, 5, 6),
                 generic_data: (),
             },
         },
-        row: 0,
-        col_start: 0,
-        col_end: 6,
+        span: Span::in_lines(&lines, 0, 0, 6),
         generic_data: (),
     }];
 
@@ -160,28 +132,20 @@ fn plus_equals_statement_simple() {
                         data: RecExprData::Variable {
                             name: String::from("x"),
                         },
-                        row: 0,
-                        col_start: 0,
-                        col_end: 1,
+                        span: Span::in_lines(&lines, 0, 0, 1),
                         generic_data: (),
                     }),
                     right: Box::new(RecExpr {
                         data: RecExprData::Number { number: 5 },
-                        row: 0,
-                        col_start: 5,
-                        col_end: 6,
+                        span: Span::in_lines(&lines, 0, 5, 6),
                         generic_data: (),
                     }),
                 },
-                row: 0,
-                col_start: 0,
-                col_end: 6,
+                span: Span::in_lines(&lines, 0, 0, 6),
                 generic_data: (),
             },
         },
-        row: 0,
-        col_start: 0,
-        col_end: 6,
+        span: Span::in_lines(&lines, 0, 0, 6),
         generic_data: (),
     }];
 
@@ -196,14 +160,17 @@ fn nested_plus_equals_statement() {
     // should desugar to
     // for i in 5
     //     x = x + 5
+    #[rustfmt::skip]
+    let lines = vec![
+        "for i in 5",
+        "    x += 5",
+    ];
     let program = vec![BaseExpr {
         data: BaseExprData::ForLoop {
             var_name: String::from("i"),
             until: RecExpr {
                 data: RecExprData::Number { number: 5 },
-                row: 0,
-                col_start: 9,
-                col_end: 10,
+                span: Span::in_lines(&lines, 0, 9, 10),
                 generic_data: (),
             },
             body: vec![BaseExpr {
@@ -211,21 +178,15 @@ fn nested_plus_equals_statement() {
                     var_name: String::from("x"),
                     expr: RecExpr {
                         data: RecExprData::Number { number: 5 },
-                        row: 1,
-                        col_start: 5,
-                        col_end: 6,
+                        span: Span::in_lines(&lines, 1, 5, 6),
                         generic_data: (),
                     },
                 },
-                row: 1,
-                col_start: 4,
-                col_end: 10,
+                span: Span::in_lines(&lines, 1, 4, 10),
                 generic_data: (),
             }],
         },
-        row: 0,
-        col_start: 0,
-        col_end: 10,
+        span: Span::in_lines(&lines, 0, 0, 10),
         generic_data: (),
     }];
 
@@ -234,9 +195,7 @@ fn nested_plus_equals_statement() {
             var_name: String::from("i"),
             until: RecExpr {
                 data: RecExprData::Number { number: 5 },
-                row: 0,
-                col_start: 9,
-                col_end: 10,
+                span: Span::in_lines(&lines, 0, 9, 10),
                 generic_data: (),
             },
             body: vec![BaseExpr {
@@ -248,34 +207,24 @@ fn nested_plus_equals_statement() {
                                 data: RecExprData::Variable {
                                     name: String::from("x"),
                                 },
-                                row: 1,
-                                col_start: 4,
-                                col_end: 5,
+                                span: Span::in_lines(&lines, 1, 4, 5),
                                 generic_data: (),
                             }),
                             right: Box::new(RecExpr {
                                 data: RecExprData::Number { number: 5 },
-                                row: 1,
-                                col_start: 5,
-                                col_end: 6,
+                                span: Span::in_lines(&lines, 1, 5, 6),
                                 generic_data: (),
                             }),
                         },
-                        row: 1,
-                        col_start: 4,
-                        col_end: 10,
+                        span: Span::in_lines(&lines, 1, 4, 10),
                         generic_data: (),
                     },
                 },
-                row: 1,
-                col_start: 4,
-                col_end: 10,
+                span: Span::in_lines(&lines, 1, 4, 10),
                 generic_data: (),
             }],
         },
-        row: 0,
-        col_start: 0,
-        col_end: 10,
+        span: Span::in_lines(&lines, 0, 0, 10),
         generic_data: (),
     }];
 
@@ -0,0 +1,128 @@
+use rosy::difftest;
+use rosy::difftest::Backend;
+use rosy::difftest::Comparison;
+use rosy::difftest::DiffOptions;
+use rosy::difftest::Outcome;
+use rosy::difftest::RunOutput;
+use rosy::difftest::Verdict;
+use rosy::language::LanguageOptions;
+use rosy::optimiser::OptimisationOptions;
+
+// The transpiled engines whose runtime is installed, the native backend is left out as it cannot compile strings yet
+fn transpiled_backends() -> Vec<Backend> {
+    difftest::available_backends()
+        .into_iter()
+        .filter(|backend| matches!(backend, Backend::Interpreter | Backend::JavaScript | Backend::Python))
+        .collect()
+}
+
+fn options(backends: Vec<Backend>) -> DiffOptions {
+    DiffOptions {
+        backends,
+        language: LanguageOptions::default(),
+        optimisation: OptimisationOptions::default(),
+    }
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn engines_agree_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun area(width, height)",
+        "    return width * height",
+        "total = 0",
+        "for i in 4",
+        "    total = total + area(i, 2)",
+        "print(\"total: \")",
+        "println(total)",
+        "println(7 / 2 == 3)",
+    ]);
+
+    let backends = transpiled_backends();
+    let (reference, comparisons) = difftest::compare_backends(program, &options(backends.clone())).unwrap();
+
+    assert_eq!(
+        reference,
+        RunOutput {
+            output: String::from("total: 12\ntrue\n"),
+            exit_code: 0,
+        }
+    );
+    assert_eq!(comparisons.len(), backends.len() - 1);
+    for comparison in comparisons {
+        assert_eq!(comparison.outcome, Outcome::Same, "{} differs", comparison.backend.name());
+    }
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn runtime_error_exit_code_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "println(\"before\")",
+        "println(1 / 0)",
+        "println(\"after\")",
+    ]);
+
+    // JavaScript divides by zero without an error
+    let backends = transpiled_backends()
+        .into_iter()
+        .filter(|backend| *backend != Backend::JavaScript)
+        .collect();
+    let (reference, comparisons) = difftest::compare_backends(program, &options(backends)).unwrap();
+
+    // The program stops at the error, with what it printed before it
+    assert_eq!(reference.output, "before\n");
+    assert_eq!(reference.exit_code, 1);
+    for comparison in comparisons {
+        assert_eq!(comparison.outcome, Outcome::Same, "{} differs", comparison.backend.name());
+    }
+}
+
+#[test]
+fn unsupported_program_is_skipped_test() {
+    let program = Vec::from(["println(read_file(\"missing.txt\"))"]);
+
+    let (reference, comparisons) =
+        difftest::compare_backends(program, &options(Vec::from([Backend::Interpreter, Backend::Native]))).unwrap();
+
+    assert_eq!(reference.exit_code, 1);
+    assert_eq!(comparisons.len(), 1);
+    assert_eq!(comparisons[0].backend, Backend::Native);
+    assert!(matches!(comparisons[0].outcome, Outcome::Skipped(_)));
+    // Nothing was compared with the interpreter, so the engines did not agree either
+    assert_eq!(difftest::verdict(&comparisons), Verdict::NotCompared);
+}
+
+#[test]
+fn verdict_test() {
+    let comparison = |backend, outcome| Comparison { backend, outcome };
+    let different = RunOutput {
+        output: String::from("1\n"),
+        exit_code: 0,
+    };
+
+    assert_eq!(difftest::verdict(&[]), Verdict::NotCompared);
+    assert_eq!(
+        difftest::verdict(&[
+            comparison(Backend::Native, Outcome::Skipped(String::from("unsupported"))),
+            comparison(Backend::Python, Outcome::Same),
+        ]),
+        Verdict::Agreed
+    );
+    assert_eq!(
+        difftest::verdict(&[
+            comparison(Backend::Native, Outcome::Same),
+            comparison(Backend::Python, Outcome::Different(different)),
+        ]),
+        Verdict::Differed
+    );
+}
+
+#[test]
+fn unreadable_program_is_an_error_test() {
+    let program = Vec::from(["x = (1 +"]);
+
+    assert!(difftest::compare_backends(program, &options(transpiled_backends())).is_err());
+}
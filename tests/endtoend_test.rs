@@ -1,6 +1,14 @@
+#![cfg(feature = "core")]
+
 // in tests/your_tests.rs
 use assert_cmd::Command;
-use rosy::pipeline::run_compilation_pipeline;
+use rosy::compiler::Target;
+use rosy::engine::Engine;
+use rosy::language::LanguageOptions;
+use rosy::optimiser::OptLevel;
+use rosy::optimiser::OptimisationOptions;
+use rosy::pipeline::run_compilation_pipeline_with_options;
+use rosy::pipeline::run_pipeline;
 
 // Feature test checklist
 // v - Addition:
@@ -26,7 +34,7 @@ use rosy::pipeline::run_compilation_pipeline;
 // - Further arithmetic combinations:
 //   - Test operator precedence
 //   - Test parentheses altering precedence
-//   - Negative numbers
+// v  - Negative numbers
 // - Boolean operations:
 // v  - True literal
 // v  - False literal
@@ -103,7 +111,20 @@ use rosy::pipeline::run_compilation_pipeline;
 // v  - Access with variable index
 //   - Modify list elements
 
+// Runs the program compiled and interpreted, both must give the expected output
 fn run_and_compare(program: Vec<&str>, expected_output: String) {
+	let terminal = match run_pipeline(program.clone()) {
+		Ok(terminal) => terminal,
+		Err(err) => panic!("Interpreter failed: {}", err),
+	};
+	assert_eq!(terminal.join("\n").trim(), expected_output, "interpreted output differs");
+
+	let stdout = compile_and_run(program, &OptimisationOptions::with_level(OptLevel::O1));
+	assert_eq!(stdout, expected_output, "compiled output differs");
+}
+
+// Compiles the program for the platform the tests run on, runs it and gives what it printed
+fn compile_and_run(program: Vec<&str>, optimisation: &OptimisationOptions) -> String {
 	// create a unique output filename in the temp dir to avoid collisions
 	let mut output_path = std::env::temp_dir();
 	let ext = if cfg!(windows) { ".exe" } else { "" };
@@ -118,7 +139,7 @@ fn run_and_compare(program: Vec<&str>, expected_output: String) {
 	output_path.push(format!("{}{}", unique, ext));
 
 	// compile to the unique path
-	match run_compilation_pipeline(program, &output_path) {
+	match run_compilation_pipeline_with_options(program, &output_path, &LanguageOptions::default(), Target::host(), optimisation) {
 		Ok(_) => {}
 		Err(err) => panic!("Pipeline failed: {}", err),
 	}
@@ -136,8 +157,7 @@ fn run_and_compare(program: Vec<&str>, expected_output: String) {
 		panic!("Compiled binary failed. status: {:?}\nstderr: {}", exec_output.status, stderr);
 	}
 
-	let stdout = String::from_utf8_lossy(&exec_output.stdout).trim().to_string();
-	assert_eq!(stdout, expected_output);
+	String::from_utf8_lossy(&exec_output.stdout).trim().to_string()
 }
 
 #[test]
@@ -361,6 +381,33 @@ fn simple_print_with_variable_subtraction_variable_unaffected() {
 	run_and_compare(program, expected_output.to_string());
 }
 
+#[test]
+fn print_negative_subtraction_result() {
+	let program: Vec<&str> = vec![
+		"a = 3",
+		"b = 10",
+		"print(a - b)",
+	];
+
+	let expected_output = "-7";
+
+	run_and_compare(program, expected_output.to_string());
+}
+
+#[test]
+fn println_negative_and_zero() {
+	let program: Vec<&str> = vec![
+		"a = 0",
+		"println(a - 120)",
+		"println(a)",
+		"print(a - 1)",
+	];
+
+	let expected_output = "-120\n0\n-1";
+
+	run_and_compare(program, expected_output.to_string());
+}
+
 #[test]
 fn if_statement_condition_true() {
 	let program: Vec<&str> = vec![
@@ -1077,4 +1124,54 @@ fn list_update() {
 
 	// TODO enable
 	//run_and_compare(program, expected_output.to_string());
-}
\ No newline at end of file
+}
+#[test]
+fn executable_format_per_target() {
+	let program: Vec<&str> = vec![
+		"a = 1",
+		"print(a)",
+	];
+
+	for (target, magic) in [(Target::Windows, &b"MZ"[..]), (Target::Linux, &b"\x7FELF"[..])] {
+		let mut output_path = std::env::temp_dir();
+		output_path.push(format!("rosy_target_test_{}_{:?}", std::process::id(), target));
+
+		let optimisation = OptimisationOptions::with_level(OptLevel::O1);
+		match run_compilation_pipeline_with_options(program.clone(), &output_path, &LanguageOptions::default(), target, &optimisation) {
+			Ok(_) => {}
+			Err(err) => panic!("Pipeline failed: {}", err),
+		}
+
+		let bytes = std::fs::read(&output_path).unwrap();
+		let _ = std::fs::remove_file(&output_path);
+		assert!(bytes.starts_with(magic), "{:?} executable starts with {:02X?}", target, &bytes[..4]);
+	}
+}
+
+#[test]
+fn same_output_at_every_optimisation_level() {
+	let program: Vec<&str> = vec![
+		"fun square(x)",
+		"    return x * x",
+		"fun scaled(value, factor)",
+		"    return value * factor",
+		"println(square(3))",
+		"println(scaled(3, 10))",
+		"println(scaled(4, 10))",
+		"println(60 * 60)",
+		"while false",
+		"    println(0)",
+	];
+
+	let expected_output = "9\n30\n40\n3600";
+
+	for level in [OptLevel::O0, OptLevel::O1, OptLevel::O2] {
+		let mut engine = Engine::new();
+		engine.options.optimisation = OptimisationOptions::with_level(level);
+		let terminal = engine.run(program.clone()).unwrap();
+		assert_eq!(terminal.join("\n").trim(), expected_output, "interpreted output differs at {:?}", level);
+
+		let stdout = compile_and_run(program.clone(), &OptimisationOptions::with_level(level));
+		assert_eq!(stdout, expected_output, "compiled output differs at {:?}", level);
+	}
+}
@@ -0,0 +1,529 @@
+use rosy::builtins::Builtin;
+use rosy::engine::Engine;
+use rosy::interpreter::{Context, Value};
+
+fn double(args: Vec<Value>, _context: &mut Context) -> Result<Option<Value>, String> {
+    match args.as_slice() {
+        [Value::Number(value)] => Ok(Some(Value::Number(value * 2))),
+        _ => Err("double expects an integer".to_string()),
+    }
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn register_native_builtin() {
+    let mut engine = Engine::new();
+    engine.register_builtin(Builtin {
+        name: "double",
+        param_names: &["value"],
+        signature: |_| None,
+        implementation: double,
+    });
+
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "println(double(21))",
+    ]);
+
+    assert_eq!(engine.run(program).unwrap(), vec!["42".to_string(), String::new()]);
+
+    // Errors from native builtins are reported at the call
+    assert!(engine.run(Vec::from(["double(\"x\")"])).is_err());
+}
+
+#[cfg(feature = "plugins")]
+#[test]
+fn load_missing_plugin() {
+    let mut engine = Engine::new();
+    assert!(engine.load_plugin(std::path::Path::new("does_not_exist.so")).is_err());
+}
+
+#[cfg(all(feature = "math", feature = "time"))]
+#[test]
+fn deterministic_runs_are_identical() {
+    let mut engine = Engine::new();
+    engine.options.deterministic = true;
+
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "for i in 5",
+        "    println(random(0, 1000))",
+        "println(now())",
+    ]);
+
+    let first_run = engine.run(program.clone()).unwrap();
+    assert_eq!(first_run, engine.run(program.clone()).unwrap());
+    assert_eq!(first_run[5], "0");
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn collect_execution_stats() {
+    let mut engine = Engine::new();
+    engine.options.collect_stats = true;
+
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun greet(name)",
+        "    println(\"hi \" + name)",
+        "names = [\"a\", \"b\"]",
+        "for name in names",
+        "    greet(name)",
+    ]);
+
+    let stats = engine.execute(program).unwrap().stats;
+
+    assert_eq!(stats.statements_executed, 7);
+    // names, name and the greet function, plus the name parameter inside the call
+    assert_eq!(stats.peak_environment_size, 4);
+    assert_eq!(stats.lists_allocated, 1);
+    // Two string literals in the list, and a literal and a concatenation per call
+    assert_eq!(stats.strings_allocated, 6);
+    // The second call reuses the scope of the first
+    assert_eq!(stats.scopes_reused, 1);
+    assert_eq!(stats.function_calls.get("greet"), Some(&2));
+    assert_eq!(stats.function_calls.get("println"), Some(&2));
+
+    // Without the option nothing is collected
+    engine.options.collect_stats = false;
+    assert_eq!(engine.execute(Vec::from(["a = 1"])).unwrap().stats.statements_executed, 0);
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn trigger_event_handlers() {
+    let mut engine = Engine::new();
+
+    // Triggering before a script is started is an error
+    assert!(engine.trigger("tick", Vec::new()).is_err());
+
+    #[rustfmt::skip]
+    engine.start(Vec::from([
+        "speed = 2",
+        "fun update(dt)",
+        "    println(dt * speed)",
+        "    return dt * speed",
+        "fun log(dt)",
+        "    println(\"tick\")",
+    ])).unwrap();
+
+    engine.on("tick", "update");
+    engine.on("tick", "log");
+
+    for _ in 0..2 {
+        let results = engine.trigger("tick", vec![Value::Number(3)]).unwrap();
+        assert!(matches!(results.as_slice(), [Some(Value::Number(6)), None]));
+    }
+
+    assert_eq!(
+        engine.script().unwrap().context.terminal,
+        vec!["6", "tick", "6", "tick", ""]
+    );
+
+    // Events without handlers do nothing, handlers which fail report their error
+    assert!(engine.trigger("draw", Vec::new()).unwrap().is_empty());
+    engine.on("draw", "missing");
+    assert!(engine.trigger("draw", Vec::new()).is_err());
+    assert!(engine.trigger("tick", Vec::new()).is_err());
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn deferred_expressions_run_on_error() {
+    let mut engine = Engine::new();
+
+    #[rustfmt::skip]
+    engine.start(Vec::from([
+        "fun fail()",
+        "    defer println(\"cleaned up\")",
+        "    return missing",
+    ])).unwrap();
+
+    engine.on("tick", "fail");
+    assert!(engine.trigger("tick", Vec::new()).is_err());
+    assert_eq!(engine.script().unwrap().context.terminal, vec!["cleaned up", ""]);
+}
+
+#[cfg(all(feature = "core", feature = "time"))]
+#[test]
+fn sleep_suspends_polled_program() {
+    use rosy::interpreter::{ProgramPoll, Suspension};
+    use std::time::Duration;
+
+    let mut engine = Engine::new();
+    engine.options.suspend_on_sleep = true;
+
+    #[rustfmt::skip]
+    let mut program = engine.load(Vec::from([
+        "println(1)",
+        "sleep(250)",
+        "println(2)",
+    ])).unwrap();
+
+    assert_eq!(
+        program.poll().unwrap(),
+        ProgramPoll::Suspended(Suspension::Sleep(Duration::from_millis(250)))
+    );
+    assert_eq!(program.context.terminal, vec!["1", ""]);
+
+    assert_eq!(program.poll().unwrap(), ProgramPoll::Finished);
+    assert_eq!(program.context.terminal, vec!["1", "2", ""]);
+}
+
+#[cfg(all(feature = "core", feature = "time"))]
+#[test]
+fn sleep_suspends_within_loops_and_functions() {
+    use rosy::interpreter::{ProgramPoll, Suspension};
+    use std::time::Duration;
+
+    let mut engine = Engine::new();
+    engine.options.suspend_on_sleep = true;
+
+    #[rustfmt::skip]
+    let mut program = engine.load(Vec::from([
+        "fun wait(milliseconds)",
+        "    sleep(milliseconds)",
+        "    println(\"waited\")",
+        "for i in 2",
+        "    sleep(10)",
+        "    println(i)",
+        "wait(20)",
+    ])).unwrap();
+
+    let sleep = |milliseconds| ProgramPoll::Suspended(Suspension::Sleep(Duration::from_millis(milliseconds)));
+    assert_eq!(program.poll().unwrap(), sleep(10));
+    assert_eq!(program.context.terminal, vec![""]);
+    assert_eq!(program.poll().unwrap(), sleep(10));
+    assert_eq!(program.context.terminal, vec!["0", ""]);
+    assert_eq!(program.poll().unwrap(), sleep(20));
+    assert_eq!(program.context.terminal, vec!["0", "1", ""]);
+
+    assert_eq!(program.poll().unwrap(), ProgramPoll::Finished);
+    assert_eq!(program.context.terminal, vec!["0", "1", "waited", ""]);
+}
+
+#[cfg(all(feature = "core", feature = "flags"))]
+#[test]
+fn read_program_flags() {
+    let mut engine = Engine::new();
+    engine.options.program_arguments = ["--name", "rosy", "--count=3", "--verbose"]
+        .iter()
+        .map(|argument| argument.to_string())
+        .collect();
+
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "println(flag_string(\"name\", \"world\"))",
+        "println(flag_int(\"count\", 1) + 1)",
+        "println(flag_bool(\"verbose\", false))",
+        "println(flag_bool(\"quiet\", false))",
+        "println(flag_string(\"missing\", \"default\"))",
+    ]);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "rosy",
+        "4",
+        "true",
+        "false",
+        "default",
+        "",
+    ]);
+
+    assert_eq!(engine.run(program).unwrap(), expected);
+
+    engine.options.program_arguments = vec!["--count=many".to_string()];
+    assert!(engine.run(Vec::from(["flag_int(\"count\", 1)"])).is_err());
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn breakpoint_opens_debugger() {
+    use rosy::interpreter::ProgramPoll;
+
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun double(a)",
+        "    b = a * 2",
+        "    breakpoint()",
+        "    return b",
+        "println(double(3))",
+    ]);
+
+    // Outside of debug mode breakpoint() does nothing
+    let mut engine = Engine::new();
+    assert_eq!(engine.run(program.clone()).unwrap(), vec!["6", ""]);
+
+    engine.options.debug = true;
+    let mut script = engine.load(program.clone()).unwrap();
+    script.context.set_debugger_input(Box::new(std::io::Cursor::new("vars\np b + 1\ncontinue\n")));
+    script.poll().unwrap();
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "Paused at breakpoint on line 3",
+        "Type help for the commands",
+        "(debug) vars",
+        "a: integer = 3",
+        "b: integer = 6",
+        "(debug) p b + 1",
+        "integer 7",
+        "(debug) continue",
+        "6",
+        "",
+    ]);
+    assert_eq!(script.context.terminal, expected);
+
+    // Quitting ends the program at the breakpoint, without an error to inspect afterwards
+    let mut script = engine.load(program).unwrap();
+    script.context.set_debugger_input(Box::new(std::io::Cursor::new("quit\nvars\n")));
+    assert_eq!(script.poll().unwrap(), ProgramPoll::Finished);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "Paused at breakpoint on line 3",
+        "Type help for the commands",
+        "(debug) quit",
+        "",
+    ]);
+    assert_eq!(script.context.terminal, expected);
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn error_opens_debugger_in_debug_mode() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun divide(a, b)",
+        "    return a / b",
+        "fun average(total, count)",
+        "    return divide(total, count)",
+        "println(average(6, 0))",
+    ]);
+
+    let mut engine = Engine::new();
+    engine.options.debug = true;
+    let mut script = engine.load(program).unwrap();
+    script.context.set_debugger_input(Box::new(std::io::Cursor::new(
+        "stack\nvars\nframe 1\np total * 2\ncontinue\nquit\n",
+    )));
+
+    // The error is still reported once the debugger is left
+    assert!(script.poll().is_err());
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "Stopped by an error on line 2: Division by zero",
+        "Type help for the commands",
+        "(debug) stack",
+        ">#0 divide on line 2",
+        " #1 average on line 4",
+        " #2 <main> on line 5",
+        "(debug) vars",
+        "a: integer = 6",
+        "b: integer = 0",
+        "(debug) frame 1",
+        "#1 average on line 4",
+        "(debug) p total * 2",
+        "integer 12",
+        "(debug) continue",
+        "The program cannot continue after an error, use quit to stop",
+        "(debug) quit",
+        "",
+    ]);
+    assert_eq!(script.context.terminal, expected);
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn debugger_steps_back_and_forth() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "x = 1",
+        "breakpoint()",
+        "y = 2",
+        "x = 3",
+        "println(x + y)",
+    ]);
+
+    let mut engine = Engine::new();
+    engine.options.debug = true;
+    let mut script = engine.load(program).unwrap();
+    script.context.set_debugger_input(Box::new(std::io::Cursor::new(
+        "step\nstep\nback\nback\nstep\nstep\nstep\nstep\ncontinue\n",
+    )));
+
+    assert!(script.poll().is_ok());
+
+    // Going back runs the program again up to the previous statement, without pausing at the breakpoints before it
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "Paused at breakpoint on line 2",
+        "Type help for the commands",
+        "(debug) step",
+        "Paused before line 3",
+        "Type help for the commands",
+        "(debug) step",
+        "Paused before line 4",
+        "+ y = 2",
+        "Type help for the commands",
+        "(debug) back",
+        "Paused before line 3",
+        "- y",
+        "Type help for the commands",
+        "(debug) back",
+        "Paused before line 2",
+        "Type help for the commands",
+        "(debug) step",
+        "Paused at breakpoint on line 2",
+        "Type help for the commands",
+        "(debug) step",
+        "Paused before line 3",
+        "Type help for the commands",
+        "(debug) step",
+        "Paused before line 4",
+        "+ y = 2",
+        "Type help for the commands",
+        "(debug) step",
+        "Paused before line 5",
+        "~ x = 3, was 1",
+        "Type help for the commands",
+        "(debug) continue",
+        "5",
+        "",
+    ]);
+    assert_eq!(script.context.terminal, expected);
+}
+
+#[cfg(all(feature = "math", feature = "time"))]
+#[test]
+fn record_and_replay_run() {
+    use rosy::interpreter::TraceMode;
+    use rosy::trace::Trace;
+    use std::sync::{Arc, Mutex};
+
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "println(random(0, 1000000))",
+        "println(now())",
+        "println(random(0, 1000000))",
+    ]);
+
+    let mut engine = Engine::new();
+    let recorded = Arc::new(Mutex::new(Trace::default()));
+    engine.options.trace = TraceMode::Record(recorded.clone());
+    let recorded_output = engine.run(program.clone()).unwrap();
+
+    let trace = recorded.lock().unwrap().clone();
+    assert_eq!(trace.inputs.len(), 1);
+    assert_eq!(trace.inputs[0].kind, "time");
+    assert_eq!(recorded_output[1], trace.inputs[0].value);
+
+    // The replay reads the same seed and time, so it prints the same output
+    engine.options.trace = TraceMode::Replay(trace.clone());
+    assert_eq!(engine.run(program.clone()).unwrap(), recorded_output);
+
+    // A replay fails when the program reads more than was recorded
+    let mut longer = program.clone();
+    longer.push("println(now())");
+    engine.options.trace = TraceMode::Replay(trace);
+    assert!(engine.run(longer).is_err());
+}
+
+#[test]
+fn evaluate_expression_with_variables() {
+    let engine = Engine::new();
+
+    let variables = [("price", Value::Number(3)), ("count", Value::Number(4))];
+    assert!(matches!(
+        engine.eval_with("price * count + 1", &variables),
+        Ok(Some(Value::Number(13)))
+    ));
+    let scores = [("scores", Value::List(Vec::from([Value::Number(2), Value::Number(9)])))];
+    assert!(matches!(
+        engine.eval_with("\"high\" if scores[1] > 5 else \"low\"", &scores),
+        Ok(Some(Value::String(label))) if label.as_str() == "high"
+    ));
+
+    // The expression is typechecked against the values before anything is evaluated
+    match engine.eval_with("price + \"x\"", &variables) {
+        Err(error) => assert!(error.span().is_some()),
+        Ok(_) => panic!("Expected a type error"),
+    }
+    assert!(engine.eval_with("missing * 2", &variables).is_err());
+
+    // Statements and several expressions are not formulas
+    #[rustfmt::skip]
+    let rejected = [
+        ("total = price * count", "Expected an expression, such as price * count, not a statement"),
+        ("price\ncount", "Expected a single expression, such as price * count"),
+        ("", "Expected a single expression, such as price * count"),
+    ];
+    for (expression, expected_message) in rejected {
+        match engine.eval_with(expression, &variables) {
+            Err(error) => assert_eq!(error.message(), expected_message),
+            Ok(_) => panic!("Expected an error for {:?}", expression),
+        }
+    }
+}
+
+#[cfg(all(feature = "core", feature = "math"))]
+#[test]
+fn sandboxed_engine_only_accepts_expressions() {
+    let engine = Engine::sandboxed();
+    assert!(engine.is_sandboxed());
+    assert!(!Engine::new().is_sandboxed());
+
+    let variables = [("width", Value::Number(3)), ("height", Value::Number(-4))];
+    assert!(matches!(
+        engine.eval_with("width * abs(height)", &variables),
+        Ok(Some(Value::Number(12)))
+    ));
+
+    // Statements are rejected when the program is parsed, before anything runs
+    #[rustfmt::skip]
+    let rejected = [
+        (Vec::from(["while true", "    x = 1"]), "A sandboxed engine only accepts expressions, such as price * count, not a loop"),
+        (Vec::from(["fun f()", "    return 1"]), "A sandboxed engine only accepts expressions, such as price * count, not a function definition"),
+        (Vec::from(["1 + 2", "total = 3"]), "A sandboxed engine only accepts expressions, such as price * count, not an assignment"),
+        (Vec::from(["max(1, abs(read_file(\"secret\")))"]), "read_file cannot be called in a sandboxed engine, which only has builtins such as abs and max that cannot affect the host"),
+        (Vec::from(["print(1)"]), "print cannot be called in a sandboxed engine, which only has builtins such as abs and max that cannot affect the host"),
+        (Vec::from(["repeat(\"ab\", 9223372036854775807)"]), "repeat cannot be called in a sandboxed engine, which only has builtins such as abs and max that cannot affect the host"),
+    ];
+    for (program, expected_message) in rejected {
+        match engine.parse(program.clone()) {
+            Err(error) => {
+                assert_eq!(error.message(), expected_message);
+                assert!(error.span().is_some());
+            }
+            Ok(_) => panic!("Expected an error for {:?}", program),
+        }
+    }
+
+    match engine.eval_with("now() + width", &variables) {
+        Err(error) => assert_eq!(
+            error.message(),
+            "now cannot be called in a sandboxed engine, which only has builtins such as abs and max that cannot affect the host"
+        ),
+        Ok(_) => panic!("Expected an error for a call to now"),
+    }
+
+    // A hostile formula gets an error back instead of exhausting the memory of the host
+    for formula in [
+        "repeat(\"ab\", 9223372036854775807)",
+        "make_grid(3037000500, 3037000500, 0)",
+        "pad_left(\"7\", width * 1000000000)",
+    ] {
+        assert!(engine.eval_with(formula, &variables).is_err(), "Expected an error for {}", formula);
+    }
+
+    // Builtins registered by the host can be called
+    let mut engine = Engine::sandboxed();
+    engine.register_builtin(Builtin {
+        name: "double",
+        param_names: &["value"],
+        signature: |_| None,
+        implementation: double,
+    });
+    assert!(engine.parse(Vec::from(["double(abs(-2))"])).is_ok());
+}
@@ -0,0 +1,101 @@
+use rosy::fixer::{self, Edit, Fix};
+use rosy::tokenizer::Span;
+
+#[test]
+fn assignment_in_condition_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 1",
+        "if a = 1",
+        "    println(a)",
+        "else if a = [1 = 1]",
+        "    println(a)",
+        "while a == 2 or a = 3",
+        "    println(a)",
+    ]);
+
+    let fixes = fixer::find_assignment_in_condition_fixes(program.clone()).unwrap();
+
+    let replace = |row, col_start| Fix {
+        message: String::from("Use == to compare in a condition, = assigns a variable"),
+        edit: Edit::Replace {
+            span: Span::in_lines(&program, row, col_start, col_start + 1),
+            text: String::from("=="),
+        },
+    };
+    // The = inside the brackets is not part of the comparison
+    assert_eq!(fixes, Vec::from([replace(1, 5), replace(3, 10), replace(5, 18)]));
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "a = 1",
+        "if a == 1",
+        "    println(a)",
+        "else if a == [1 = 1]",
+        "    println(a)",
+        "while a == 2 or a == 3",
+        "    println(a)",
+    ]);
+    assert_eq!(fixer::apply_fixes(program, &fixes), expected);
+}
+
+#[test]
+fn unused_assignment_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 1",
+        "unused = a + 2",
+        "called = print(a)",
+        "count = 0",
+        "count += 1",
+        "fun f(x)",
+        "    temp = x * 2",
+        "    return x",
+        "if true",
+        "    only = 1",
+        "println(f(a))",
+    ]);
+
+    let (fixed_lines, fixes) = fixer::fix_program(program).unwrap();
+
+    // Assignments with a function call are kept, as are variables which are changed with += and
+    // the only statement of a block
+    let messages: Vec<(usize, &str)> = fixes.iter().map(|fix| (fix.row(), fix.message.as_str())).collect();
+    assert_eq!(
+        messages,
+        Vec::from([
+            (1, "Remove the assignment to unused, which is never used"),
+            (6, "Remove the assignment to temp, which is never used"),
+        ])
+    );
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "a = 1",
+        "called = print(a)",
+        "count = 0",
+        "count += 1",
+        "fun f(x)",
+        "    return x",
+        "if true",
+        "    only = 1",
+        "println(f(a))",
+    ]);
+    assert_eq!(fixed_lines, expected);
+}
+
+#[test]
+fn fix_program_without_mistakes_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 1",
+        "println(a)",
+    ]);
+
+    let (fixed_lines, fixes) = fixer::fix_program(program.clone()).unwrap();
+    assert!(fixes.is_empty());
+    assert_eq!(fixed_lines, program);
+
+    // Mistakes which cannot be fixed are reported as errors
+    assert!(fixer::fix_program(Vec::from(["a = (1"])).is_err());
+}
@@ -0,0 +1,65 @@
+use rosy::inliner;
+use rosy::parser;
+
+fn parse(program: Vec<&str>) -> Vec<parser::BaseExpr<()>> {
+    parser::parse_strings(program).unwrap()
+}
+
+#[test]
+fn inline_functions_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun square(x)",
+        "    return x * x",
+        "n = 3",
+        "a = square(n)",
+        "b = square(n + 1)",
+    ]);
+
+    // Only arguments which are literals or variables are inlined, so n + 1 is still computed once
+    let (inlined, count) = inliner::inline_functions(parse(program)).unwrap();
+    let printed = parser::expressions_to_string(&inlined);
+    assert_eq!(count, 1);
+    assert!(printed.contains("VarAssign(\"a\", (Var(\"n\") * Var(\"n\")))"));
+    assert!(printed.contains("VarAssign(\"b\", Call(\"square\""));
+}
+
+#[test]
+fn leave_unknown_calls_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = twice(1)",
+        "fun twice(x)",
+        "    return x + x",
+        "fun half(x)",
+        "    return x / 2",
+        "half = 4",
+        "b = half(2)",
+    ]);
+
+    // twice is called before it is defined and half is also a variable, so neither call is known
+    let (_, count) = inliner::inline_functions(parse(program)).unwrap();
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn specialize_functions_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun area(r, scale)",
+        "    return scale * r * r",
+        "fun count(step)",
+        "    step = step + 1",
+        "    return step",
+        "a = area(2, 3)",
+        "b = area(5, 3)",
+        "c = count(1)",
+    ]);
+
+    // r differs between the calls and count changes step, so only scale is specialized
+    let (specialized, count) = inliner::specialize_functions(parse(program)).unwrap();
+    let printed = parser::expressions_to_string(&specialized);
+    assert_eq!(count, 1);
+    assert!(printed.contains("((Num(3) * Var(\"r\")) * Var(\"r\"))"));
+    assert!(printed.contains("Var(\"step\")"));
+}
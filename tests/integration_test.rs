@@ -1,3 +1,4 @@
+use rosy::tokenizer::Span;
 use rosy::{
     interpreter::{self, Terminal},
     parser::{BaseExpr, RecExpr},
@@ -15,6 +16,7 @@ fn compare(actual: Result<Terminal, String>, expected: Terminal) {
     }
 }
 
+#[cfg(feature = "core")]
 #[test]
 fn addition_test() {
     #[rustfmt::skip]
@@ -36,6 +38,7 @@ fn addition_test() {
     compare(actual, str_to_string(expected));
 }
 
+#[cfg(feature = "core")]
 #[test]
 fn simple_arithmetic_test() {
     #[rustfmt::skip]
@@ -66,6 +69,7 @@ fn simple_arithmetic_test() {
     compare(actual, str_to_string(expected));
 }
 
+#[cfg(feature = "core")]
 #[test]
 fn advanced_arithmetic_with_parentheses_test() {
     #[rustfmt::skip]
@@ -99,6 +103,7 @@ fn advanced_arithmetic_with_parentheses_test() {
     compare(actual, str_to_string(expected));
 }
 
+#[cfg(feature = "core")]
 #[test]
 fn test_order_of_operations() {
     #[rustfmt::skip]
@@ -167,6 +172,7 @@ fn test_order_of_operations() {
     compare(actual, str_to_string(expected));
 }
 
+#[cfg(feature = "core")]
 #[test]
 fn test_variable_shadowing() {
     #[rustfmt::skip]
@@ -225,6 +231,7 @@ fn test_variable_shadowing() {
     compare(actual, str_to_string(expected));
 }
 
+#[cfg(feature = "core")]
 #[test]
 fn unary_vs_binary_minus_test() {
     #[rustfmt::skip]
@@ -263,6 +270,7 @@ fn unary_vs_binary_minus_test() {
     compare(actual, str_to_string(expected));
 }
 
+#[cfg(feature = "core")]
 #[test]
 fn and_or_not_statements_test() {
     #[rustfmt::skip]
@@ -330,6 +338,7 @@ fn and_or_not_statements_test() {
     compare(actual, str_to_string(expected));
 }
 
+#[cfg(feature = "core")]
 #[test]
 fn if_statements_test() {
     // Test if, else if and else
@@ -394,6 +403,7 @@ fn if_statements_test() {
     compare(actual, str_to_string(expected));
 }
 
+#[cfg(feature = "core")]
 #[test]
 fn for_loop_test() {
     #[rustfmt::skip]
@@ -416,6 +426,7 @@ fn for_loop_test() {
 
     compare(actual, str_to_string(expected));
 }
+#[cfg(feature = "core")]
 #[test]
 fn comparison_operators_test() {
     #[rustfmt::skip]
@@ -495,6 +506,7 @@ fn comparison_operators_test() {
     compare(actual, str_to_string(expected));
 }
 
+#[cfg(feature = "core")]
 #[test]
 fn advanced_ooo_test() {
     #[rustfmt::skip]
@@ -521,6 +533,7 @@ fn advanced_ooo_test() {
     compare(actual, str_to_string(expected));
 }
 
+#[cfg(feature = "core")]
 #[test]
 fn list_test() {
     #[rustfmt::skip]
@@ -583,6 +596,7 @@ fn list_test() {
     compare(actual, str_to_string(expected));
 }
 
+#[cfg(feature = "core")]
 #[test]
 fn list_append_test() {
     #[rustfmt::skip]
@@ -623,3 +637,1724 @@ fn list_append_test() {
 
     compare(actual, str_to_string(expected));
 }
+
+#[cfg(all(feature = "core", feature = "math"))]
+#[test]
+fn math_builtins_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "println(abs(0 - 4))",
+        "println(min(3, 7))",
+        "println(max(3, 7))",
+        "println(min(-9223372036854775808, 0))",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "4",
+        "3",
+        "7",
+        "-9223372036854775808",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    // The smallest integer has no positive counterpart
+    assert!(pipeline::run_pipeline(Vec::from(["abs(-9223372036854775808)"])).is_err());
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn early_return_restores_scope_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun f(x)",
+        "    return x + 1",
+        "l = []",
+        "for i in 3",
+        "    l += [f(i)]",
+        "println(l)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "[1, 2, 3]",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn generator_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun evens(n)",
+        "    for i in n",
+        "        if i / 2 * 2 == i",
+        "            yield i",
+        "        if i == 4",
+        "            return",
+        "for e in evens(10)",
+        "    println(e)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "0",
+        "2",
+        "4",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn infinite_generator_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun naturals()",
+        "    println(\"started\")",
+        "    n = 0",
+        "    while true",
+        "        yield n",
+        "        n = n + 1",
+        "numbers = naturals()",
+        "println(\"created\")",
+        "for n in numbers",
+        "    if n == 3",
+        "        break",
+        "    println(n)",
+        "for n in numbers",
+        "    println(n)",
+        "    break",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // The body only runs when values are taken, and the second loop goes on where the first one stopped
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "created",
+        "started",
+        "0",
+        "1",
+        "2",
+        "4",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(all(feature = "core", feature = "string"))]
+#[test]
+fn string_builtins_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "text = \"hello world\"",
+        "println(find(text, \"world\"))",
+        "println(find(text, \"moon\"))",
+        "println(slice(text, 0, 5))",
+        "println(pad_left(\"7\", 3, \"0\"))",
+        "println(pad_right(\"ab\", 4) + \"|\")",
+        "println(repeat(\"ab\", 3))",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "6",
+        "-1",
+        "hello",
+        "007",
+        "ab  |",
+        "ababab",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    assert!(pipeline::run_pipeline(Vec::from(["slice(\"abc\", 2, 5)"])).is_err());
+    // A result too large to allocate is an error the program can report, not a crash
+    assert!(pipeline::run_pipeline(Vec::from(["repeat(\"ab\", 9223372036854775807)"])).is_err());
+    assert!(pipeline::run_pipeline(Vec::from(["pad_left(\"7\", 9223372036854775807)"])).is_err());
+    assert!(pipeline::run_pipeline(Vec::from(["pad_right(\"7\", 4611686018427387904, \"0\")"])).is_err());
+}
+
+#[cfg(all(feature = "core", feature = "io"))]
+#[test]
+fn csv_builtins_test() {
+    let path = std::env::temp_dir().join("rosy_csv_builtins_test.csv");
+    let path_string = path.to_str().unwrap().replace('\\', "/");
+
+    let write_line = format!("write_csv(\"{}\", [a, b])", path_string);
+    let read_line = format!("rows = read_csv(\"{}\")", path_string);
+
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = [\"name\", \"note\"]",
+        "b = [\"rosy\", \"small, typed\"]",
+        write_line.as_str(),
+        read_line.as_str(),
+        "row = rows[1]",
+        "println(row[1])",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "small, typed",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "name,note\nrosy,\"small, typed\"\n"
+    );
+
+    // Quoted fields can contain quotes and newlines
+    std::fs::write(&path, "\"say \"\"hi\"\"\",\"two\nlines\"\r\n").unwrap();
+    let actual = pipeline::run_pipeline(Vec::from([read_line.as_str(), "println(rows)"]));
+    compare(actual, str_to_string(Vec::from(["[[say \"hi\", two\nlines]]", ""])));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(all(feature = "core", feature = "io", feature = "string"))]
+#[test]
+fn bytes_builtins_test() {
+    let path = std::env::temp_dir().join("rosy_bytes_builtins_test.bin");
+    let path_string = path.to_str().unwrap().replace('\\', "/");
+
+    let write_line = format!("write_bytes(\"{}\", to_bytes([137, 80, 0, 255]))", path_string);
+    let read_line = format!("data = read_bytes(\"{}\")", path_string);
+
+    #[rustfmt::skip]
+    let program = Vec::from([
+        write_line.as_str(),
+        read_line.as_str(),
+        "println(data)",
+        "println(data[3])",
+        "println(slice(data, 1, 3))",
+        "text = to_bytes(\"héllo\", \"utf-8\")",
+        "println(text[1])",
+        "println(from_bytes(slice(text, 3, 6), \"ascii\"))",
+        "println(from_bytes(to_bytes(\"é\", \"latin-1\"), \"latin-1\"))",
+        "total = 0",
+        "for byte in slice(data, 0, 2)",
+        "    total += byte",
+        "println(total)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "bytes[137, 80, 0, 255]",
+        "255",
+        "bytes[80, 0]",
+        "195",
+        "llo",
+        "é",
+        "217",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+    assert_eq!(std::fs::read(&path).unwrap(), vec![137, 80, 0, 255]);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(all(feature = "core", feature = "terminal"))]
+#[test]
+fn terminal_builtins_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "println(\"before\")",
+        "clear_screen()",
+        "move_cursor(2, 1)",
+        "println(colored(\"ok\", \"green\"))",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "\x1b[32mok\x1b[0m",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    assert!(pipeline::run_pipeline(Vec::from(["colored(\"ok\", \"mauve\")"])).is_err());
+}
+
+#[cfg(all(feature = "core", feature = "io"))]
+#[test]
+fn write_ppm_test() {
+    let path = std::env::temp_dir().join("rosy_write_ppm_test.ppm");
+    let path_string = path.to_str().unwrap().replace('\\', "/");
+
+    let write_line = format!("write_ppm(\"{}\", 2, 1, pixels)", path_string);
+
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "pixels = [255, 0, 0]",
+        "pixels = pixels + [0, 0, 255]",
+        write_line.as_str(),
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    compare(actual, str_to_string(Vec::from([""])));
+    assert_eq!(
+        std::fs::read(&path).unwrap(),
+        b"P6\n2 1\n255\n\xff\x00\x00\x00\x00\xff".to_vec()
+    );
+
+    // The number of color values has to match the size of the image
+    let write_line = format!("write_ppm(\"{}\", 2, 2, [0, 0, 0])", path_string);
+    assert!(pipeline::run_pipeline(Vec::from([write_line.as_str()])).is_err());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(all(feature = "core", feature = "list"))]
+#[test]
+fn nested_list_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "grid = [",
+        "    [0, 1],",
+        "    [2, 3],",
+        "]",
+        "println(grid[1][0])",
+        "words = [[\"a\", \"b\"], [\"c\"]]",
+        "println(words[0][1])",
+        "cells = make_grid(2, 3, 0)",
+        "println(cells)",
+        "println(dimensions(cells))",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "2",
+        "b",
+        "[[0, 0, 0], [0, 0, 0]]",
+        "[2, 3]",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    // Rows of different lengths have no dimensions
+    assert!(pipeline::run_pipeline(Vec::from(["dimensions([[1], [2, 3]])"])).is_err());
+    // A grid too large to allocate is an error the program can report, not a crash
+    assert!(pipeline::run_pipeline(Vec::from(["make_grid(3037000500, 3037000500, 0)"])).is_err());
+    assert!(pipeline::run_pipeline(Vec::from(["make_grid(9223372036854775807, 2, 0)"])).is_err());
+    assert!(pipeline::run_pipeline(Vec::from(["make_grid(1000000000000, 0, 0)"])).is_err());
+}
+
+#[cfg(all(feature = "core", feature = "math"))]
+#[test]
+fn base_conversion_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "println(to_base(10, 2))",
+        "println(to_base(255, 16))",
+        "println(to_base(0 - 8, 8))",
+        "println(to_base(35, 36))",
+        "println(from_base(\"1010\", 2))",
+        "println(from_base(\"FF\", 16))",
+        "println(from_base(\"-z\", 36))",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "1010",
+        "ff",
+        "-10",
+        "z",
+        "10",
+        "255",
+        "-35",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    assert!(pipeline::run_pipeline(Vec::from(["from_base(\"12\", 2)"])).is_err());
+    assert!(pipeline::run_pipeline(Vec::from(["to_base(5, 37)"])).is_err());
+}
+
+#[cfg(all(feature = "core", feature = "math"))]
+#[test]
+fn rational_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = rational(1, 3)",
+        "b = a + rational(1, 6)",
+        "println(b)",
+        "println(b * 4)",
+        "println(0 - b)",
+        "println(1 / rational(3, 7))",
+        "println(b == rational(2, 4))",
+        "println(a < b)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "1/2",
+        "2",
+        "-1/2",
+        "7/3",
+        "true",
+        "true",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    assert!(pipeline::run_pipeline(Vec::from(["a = rational(1, 0)"])).is_err());
+    assert!(pipeline::run_pipeline(Vec::from(["a = rational(1, 2) / rational(0, 1)"])).is_err());
+}
+
+#[cfg(all(feature = "core", feature = "math"))]
+#[test]
+fn complex_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "z = complex(1, 2)",
+        "w = z * z + 1",
+        "println(w)",
+        "println(w / complex(0, 1))",
+        "println(0 - z)",
+        "println(complex(rational(1, 2), 0) - z)",
+        "println(magnitude(complex(3, 4)))",
+        "println(magnitude(z) > 2)",
+        "println(z == complex(1, 2))",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "-2+4i",
+        "4+2i",
+        "-1-2i",
+        "-0.5-2i",
+        "5.0",
+        "true",
+        "true",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    assert!(pipeline::run_pipeline(Vec::from(["z = complex(1, 2) / complex(0, 0)"])).is_err());
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn increment_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "count = 0",
+        "countdown = 10",
+        "for i in 5",
+        "    count++",
+        "    countdown--",
+        "println(count)",
+        "println(countdown)",
+        "println(5 ^ --2)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "5",
+        "5",
+        "25",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn swap_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 1",
+        "b = \"two\"",
+        "swap a, b",
+        "println(a)",
+        "println(b)",
+        "x = 5",
+        "if true",
+        "    y = 3",
+        "    swap x, y",
+        "    println(y)",
+        "println(x)",
+        "swap x, x",
+        "println(x)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "two",
+        "1",
+        "5",
+        "3",
+        "3",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    assert!(pipeline::run_pipeline(Vec::from(["a = 1", "swap a, b"])).is_err());
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn while_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "i = 0",
+        "while i < 3",
+        "    println(i)",
+        "    i += 1",
+        "n = 0",
+        "while true",
+        "    n += 1",
+        "    if n == 5",
+        "        break",
+        "println(n)",
+        "for j in 2",
+        "    while true",
+        "        break",
+        "    println(j)",
+        "while false",
+        "    println(\"never\")",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // A break only leaves the innermost loop
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "0",
+        "1",
+        "2",
+        "5",
+        "0",
+        "1",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    assert!(pipeline::run_pipeline(Vec::from(["while 1", "    a = 1"])).is_err());
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn float_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 3.14",
+        "println(a)",
+        "println(1e-3)",
+        "println(2 * a)",
+        "println(a - 0.14 == 3.0)",
+        "println(-2.5 + 1)",
+        "println(7.0 / 2)",
+        "println(1.5e2 > 100)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // Integers are converted to floats when mixed with them
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "3.14",
+        "0.001",
+        "6.28",
+        "true",
+        "-1.5",
+        "3.5",
+        "true",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn comment_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "# Sums the numbers below 4",
+        "total = 0",
+        "for i in 4 # i goes from 0 to 3",
+        "    # adds i to the total",
+        "    total += i",
+        "# the loop is over",
+        "println(total) # prints 6",
+        "println(\"# is kept in strings\")",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "6",
+        "# is kept in strings",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn in_place_update_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "x = 1",
+        "x += 2",
+        "y = x",
+        "x = x + 0.5",
+        "println(x)",
+        "println(y)",
+        "l = []",
+        "l += [1]",
+        "l = l + 2",
+        "l = l + [3, 4]",
+        "copy = l",
+        "l += [5]",
+        "println(l)",
+        "println(copy)",
+        "i = 2",
+        "println(i < y)",
+        "println(1.5 < i)",
+        "fun f(n)",
+        "    n += y",
+        "    return n",
+        "println(f(i))",
+        "println(i)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // Updating a variable in place does not change the copies made of it before
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "3.5",
+        "3",
+        "[1, 2, 3, 4, 5]",
+        "[1, 2, 3, 4]",
+        "true",
+        "true",
+        "5",
+        "2",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    assert!(pipeline::run_pipeline(Vec::from(["l = []", "l += print(1)"])).is_err());
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn continue_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "for i in 6",
+        "    if i == 1 or i == 3 or i == 5",
+        "        continue",
+        "    println(i)",
+        "n = 0",
+        "while n < 5",
+        "    n += 1",
+        "    if n < 4",
+        "        continue",
+        "    println(n)",
+        "for i in 2",
+        "    for j in 2",
+        "        if j == 0",
+        "            continue",
+        "        println(j)",
+        "    println(i)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // A continue only skips the rest of the innermost loop body
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "0",
+        "2",
+        "4",
+        "4",
+        "5",
+        "1",
+        "0",
+        "1",
+        "1",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(all(feature = "core", feature = "math"))]
+#[test]
+fn dump_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun add(a, b)",
+        "    return a + b",
+        "dump([1, [2, 3], \"a\"])",
+        "dump(add)",
+        "dump(abs)",
+        "dump(rational(1, 2))",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "list of length 3",
+        "    integer 1",
+        "    list of length 2",
+        "        integer 2",
+        "        integer 3",
+        "    string \"a\"",
+        "function add(a, b)",
+        "standard function abs(value)",
+        "rational 1/2",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(all(feature = "core", feature = "math"))]
+#[test]
+fn floor_division_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "println(7 / 2)",
+        "println(7 // 2)",
+        "println(-7 / 2)",
+        "println(-7 // 2)",
+        "println(7 // -2)",
+        "println(1 + 9 // 2 * 2)",
+        "println(magnitude(complex(3, 4)) // 2)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "3",
+        "3",
+        "-3",
+        "-4",
+        "-4",
+        "9",
+        "2.0",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    assert!(pipeline::run_pipeline(Vec::from(["a = 1 // 0"])).is_err());
+    assert!(pipeline::run_pipeline(Vec::from(["a = 1 / 0"])).is_err());
+    // The smallest integer divided by -1 is one too large
+    assert!(pipeline::run_pipeline(Vec::from(["a = -9223372036854775808 // -1"])).is_err());
+    assert!(pipeline::run_pipeline(Vec::from(["a = -9223372036854775808 // 1", "println(a)"])).is_ok());
+}
+
+#[cfg(all(feature = "core", feature = "math"))]
+#[test]
+fn float_conversion_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "println(float(3))",
+        "println(float(rational(1, 4)))",
+        "println(int(magnitude(complex(3, 4))))",
+        "println(int(real(complex(rational(-7, 2), 0))))",
+        "println(float(2) < magnitude(complex(1, 2)))",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "3.0",
+        "0.25",
+        "5",
+        "-3",
+        "true",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn delete_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 1",
+        "b = 2",
+        "del a",
+        "a = b + 1",
+        "println(a)",
+        "fun shadow()",
+        "    b = 5",
+        "    del b",
+        "    return b",
+        "println(shadow())",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "3",
+        "2",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    assert!(pipeline::run_pipeline(Vec::from(["a = 1", "del a", "println(a)"])).is_err());
+    assert!(pipeline::run_pipeline(Vec::from(["del a"])).is_err());
+}
+
+#[cfg(all(feature = "core", feature = "math", feature = "io"))]
+#[test]
+fn error_values_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = try_from_base(\"ff\", 16)",
+        "println(is_error(a))",
+        "println(unwrap(a) + 1)",
+        "b = try_from_base(\"12\", 2)",
+        "if is_error(b)",
+        "    println(error_message(b))",
+        "c = try_read_file(\"this file does not exist\")",
+        "println(is_error(c))",
+        "println(is_error(5))",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "false",
+        "256",
+        "Cannot read '12' as a number in base 2: invalid digit found in string",
+        "true",
+        "false",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    // Unwrapping an error stops the program with its message
+    assert!(pipeline::run_pipeline(Vec::from(["a = unwrap(try_from_base(\"12\", 2))"])).is_err());
+    assert!(pipeline::run_pipeline(Vec::from(["a = error_message(try_from_base(\"1\", 2))"])).is_err());
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn defer_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun work(name)",
+        "    defer println(\"closed \" + name)",
+        "    defer println(\"flushed \" + name)",
+        "    println(\"working on \" + name)",
+        "    return 1",
+        "    println(\"unreachable\")",
+        "a = work(\"a\")",
+        "println(a)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "working on a",
+        "flushed a",
+        "closed a",
+        "1",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    // Only function calls can defer
+    assert!(pipeline::run_pipeline(Vec::from(["defer println(1)"])).is_err());
+}
+
+#[cfg(all(feature = "core", feature = "io"))]
+#[test]
+fn with_test() {
+    let path = std::env::temp_dir().join("rosy_with_test.txt");
+    let path_string = path.to_str().unwrap().replace('\\', "/");
+
+    let write_line = format!("    with open_file(\"{}\", \"w\") as file", path_string);
+    let read_line = format!("with open_file(\"{}\", \"r\") as file", path_string);
+
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun save()",
+        write_line.as_str(),
+        "        write_line(file, \"first\")",
+        "        write_line(file, \"second\")",
+        "        return 2",
+        "    return 0",
+        "println(save())",
+        read_line.as_str(),
+        "    for line in read_lines(file)",
+        "        println(line)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "2",
+        "first",
+        "second",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    // The resource is closed once the block ends
+    let program = Vec::from([read_line.as_str(), "    a = 1", "lines = read_lines(file)"]);
+    assert!(pipeline::run_pipeline(program).is_err());
+
+    // If statements inside the block have their else branches attached
+    let program = Vec::from([
+        read_line.as_str(),
+        "    if 1 == 1",
+        "        println(\"then\")",
+        "    else",
+        "        println(\"else\")",
+    ]);
+    compare(pipeline::run_pipeline(program), str_to_string(Vec::from(["then", ""])));
+
+    // Only resources can be used in a with block
+    assert!(pipeline::run_pipeline(Vec::from(["with 5 as five", "    a = five"])).is_err());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn match_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun describe(n)",
+        "    match n",
+        "        case 0",
+        "            println(\"fizz\")",
+        "        case -1",
+        "            return",
+        "        case rest",
+        "            println(rest * 10)",
+        "    println(\"described\")",
+        "for i in 3",
+        "    describe(i)",
+        "describe(-1)",
+        "match \"b\"",
+        "    case \"a\"",
+        "        println(\"a\")",
+        "    case _",
+        "        println(\"not a\")",
+        "match true",
+        "    case false",
+        "        println(\"false\")",
+        "println(\"done\")",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "fizz",
+        "described",
+        "10",
+        "described",
+        "20",
+        "described",
+        "not a",
+        "done",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn for_loop_snapshot_test() {
+    // Changing the list inside the loop does not change the elements the loop visits
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "items = [1, 2, 3]",
+        "for item in items",
+        "    items = items + [item * 10]",
+        "    println(item)",
+        "println(items)",
+        "count = 2",
+        "for i in count",
+        "    count = 5",
+        "    println(i)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "1",
+        "2",
+        "3",
+        "[1, 2, 3, 10, 20, 30]",
+        "0",
+        "1",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn deeply_nested_test() {
+    // Far deeper than any hand written program, no pass may overflow the stack on it
+    let sum = format!("a = {}", vec!["1"; 1000].join(" + "));
+    let parenthesized = format!("b = {}1{}", "(".repeat(500), ")".repeat(500));
+    let mut program = vec![sum, parenthesized];
+    for depth in 0..200 {
+        program.push(format!("{}if a > {}", "    ".repeat(depth), depth));
+    }
+    program.push(format!("{}println(a + b)", "    ".repeat(200)));
+    let program: Vec<&str> = program.iter().map(|line| line.as_str()).collect();
+
+    assert!(pipeline::run_typecheck_pipeline(program.clone()).is_ok());
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "1001",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[test]
+fn deeply_nested_list_test() {
+    let nested = |depth: usize| format!("x = {}1{}", "[".repeat(depth), "]".repeat(depth));
+
+    let program = nested(50);
+    assert!(pipeline::run_typecheck_pipeline(Vec::from([program.as_str()])).is_ok());
+
+    // Types are not walked with the stack guard of the passes, so they have a depth limit instead
+    let program = nested(200);
+    let parsed = rosy::parser::parse_strings(Vec::from([program.as_str()])).unwrap();
+    match rosy::typechecker::type_check_program(parsed, false) {
+        Err(rosy::tokenizer::Error::LocationError { message, span: Span { col_start, .. } }) => {
+            assert_eq!(message, "Types cannot be nested more than 100 levels deep");
+            assert_eq!(col_start, 103);
+        }
+        other => panic!("Expected an error, got {:?}", other.map(|_| ())),
+    }
+    assert!(pipeline::run_emit_js_pipeline(Vec::from([program.as_str()])).is_err());
+    assert!(pipeline::run_annotate_pipeline(Vec::from([program.as_str()])).is_err());
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn struct_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "struct Point",
+        "    x",
+        "    y",
+        "fun length_squared(p)",
+        "    return p.x * p.x + p.y * p.y",
+        "p = Point(3, 4)",
+        "println(p)",
+        "println(p.y)",
+        "println(length_squared(p))",
+        "named = Point(\"a\", [1, 2])",
+        "println(named.x + named.x)",
+        "println(named)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "Point { x: 3, y: 4 }",
+        "4",
+        "25",
+        "aa",
+        "Point { x: a, y: [1, 2] }",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn field_assignment_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "struct Counter",
+        "    count",
+        "fun increment(counter)",
+        "    counter.count = counter.count + 1",
+        "    return counter",
+        "c = Counter(0)",
+        "copy = c",
+        "c.count = 5",
+        "println(c.count)",
+        "println(copy.count)",
+        "incremented = increment(c)",
+        "println(incremented.count)",
+        "println(c.count)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // Structs are values: changing a field does not change the copies
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "5",
+        "0",
+        "6",
+        "5",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn nested_assignment_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "struct Point",
+        "    x",
+        "struct Shape",
+        "    center",
+        "grid = [[1, 2], [3, 4]]",
+        "copy = grid",
+        "grid[1][0] = 9",
+        "println(grid[1][0], copy[1][0])",
+        "shape = Shape(Point(1))",
+        "shape.center.x = 5",
+        "center = shape.center",
+        "println(center.x)",
+        "ages = {\"ann\": [30, 31]}",
+        "ages[\"ann\"][1] = 32",
+        "println(ages[\"ann\"][1])",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // The element is changed in place, the copies keep their value
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "93",
+        "5",
+        "32",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    let program = Vec::from(["grid = [[1, 2]]", "grid[1][0] = 3"]);
+    assert!(pipeline::run_pipeline(program).is_err());
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn dict_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "ages = {\"ann\": 31, \"bob\": 27}",
+        "copy = ages",
+        "ages[\"cid\"] = 40",
+        "ages[\"ann\"] = ages[\"ann\"] + 1",
+        "println(ages[\"ann\"])",
+        "println(ages)",
+        "println(copy)",
+        "grid = {",
+        "    1: [1, 2],",
+        "    2: [],",
+        "}",
+        "grid[2] = [3]",
+        "println(grid[1][1] + grid[2][0])",
+        "scores = [1, 2, 3]",
+        "scores[0] = 7",
+        "println(scores)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // Dicts are printed in the order in which their keys were added, and assigning to an entry does not
+    // change the copies
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "32",
+        "{ann: 32, bob: 27, cid: 40}",
+        "{ann: 31, bob: 27}",
+        "5",
+        "[7, 2, 3]",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(all(feature = "core", feature = "dict"))]
+#[test]
+fn dict_builtins_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "ages = {\"bob\": 27, \"ann\": 31}",
+        "println(keys(ages))",
+        "println(values(ages))",
+        "println(items(ages))",
+        "println(get(ages, \"ann\", 0))",
+        "println(get(ages, \"cid\", 0))",
+        "older = remove(ages, \"bob\")",
+        "println(older)",
+        "println(ages)",
+        "println(remove(ages, \"cid\"))",
+        "total = 0",
+        "for name in ages",
+        "    println(name)",
+        "    total = total + ages[name]",
+        "println(total)",
+        "for entry in items({1: true, 0: false})",
+        "    println(entry[0])",
+        "ages[\"abe\"] = 50",
+        "ages[\"bob\"] = 28",
+        "println(ages)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // Keys come in the order in which they were added, and remove gives a new dict without changing the old one
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "[bob, ann]",
+        "[27, 31]",
+        "[(bob, 27), (ann, 31)]",
+        "31",
+        "0",
+        "{ann: 31}",
+        "{bob: 27, ann: 31}",
+        "{bob: 27, ann: 31}",
+        "bob",
+        "ann",
+        "58",
+        "1",
+        "0",
+        "{bob: 28, ann: 31, abe: 50}",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "ages = remove({\"ann\": 31, \"bob\": 27}, \"bob\")",
+        "total = get(ages, \"cid\", 0)",
+        "for name in ages",
+        "    total = total + ages[name]",
+        "for age in values(ages)",
+        "    total = total + age",
+        "println(total)",
+    ]);
+    assert!(pipeline::run_typecheck_pipeline(program).is_ok());
+
+    // The key and the default have to match the types of the dict
+    let program = Vec::from(["ages = {\"ann\": 31}", "println(get(ages, 1, 0))"]);
+    assert!(pipeline::run_typecheck_pipeline(program).is_err());
+
+    let program = Vec::from(["ages = {\"ann\": 31}", "println(get(ages, \"ann\", \"none\"))"]);
+    assert!(pipeline::run_typecheck_pipeline(program).is_err());
+
+    let program = Vec::from(["ages = {\"ann\": 31}", "println(remove(ages, true))"]);
+    assert!(pipeline::run_typecheck_pipeline(program).is_err());
+}
+
+#[cfg(all(feature = "core", feature = "json"))]
+#[test]
+fn json_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "struct Config",
+        "    name",
+        "    sizes",
+        "    scale",
+        "config = Config(\"tiles\", [16, 32], 1.5)",
+        "text = to_json(config)",
+        "println(text)",
+        "read = from_json(Config, text)",
+        "println(read)",
+        "println(read.sizes[1] + 1)",
+        "println(to_json({\"b\": (1, true), \"a\": (2, false)}))",
+        "println(from_json(Config, to_json({\"scale\": 2.0, \"sizes\": [], \"name\": \"a b\"})))",
+        "println(from_json(Config, to_json({\"scale\": {\"x\": 1}, \"sizes\": [[1], []], \"name\": \"\"})))",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // Fields are read by name, and objects inside the struct become dicts
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "{\"name\": \"tiles\", \"sizes\": [16, 32], \"scale\": 1.5}",
+        "Config { name: tiles, sizes: [16, 32], scale: 1.5 }",
+        "33",
+        "{\"b\": [1, true], \"a\": [2, false]}",
+        "Config { name: a b, sizes: [], scale: 2.0 }",
+        "Config { name: , sizes: [[1], []], scale: {x: 1} }",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    // Every field has to be given, nothing else may be, and lists hold values of one type
+    #[rustfmt::skip]
+    let invalid = [
+        "from_json(Config, to_json({\"name\": \"a\", \"sizes\": []}))",
+        "from_json(Config, to_json({\"name\": \"a\", \"sizes\": [], \"scale\": 1, \"extra\": 1}))",
+        "from_json(Config, to_json(Config(\"a\", [1, \"b\"], 1)))",
+        "from_json(Config, to_json([1, 2]))",
+        "from_json(Config, \"{\")",
+    ];
+    for call in invalid {
+        let program = Vec::from(["struct Config", "    name", "    sizes", "    scale", call]);
+        assert!(pipeline::run_pipeline(program).is_err(), "{}", call);
+    }
+
+    let program = Vec::from(["struct Point", "    x", "p = Point(1)", "println(to_json(p))"]);
+    assert!(pipeline::run_typecheck_pipeline(program).is_ok());
+
+    // Field types only known when the program runs, and dict keys which are not strings, cannot be checked
+    let program = Vec::from(["struct Point", "    x", "p = from_json(Point, to_json(Point(1)))"]);
+    assert!(pipeline::run_typecheck_pipeline(program).is_err());
+
+    let program = Vec::from(["println(to_json({1: 2}))"]);
+    assert!(pipeline::run_typecheck_pipeline(program).is_err());
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn freeze_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "struct Config",
+        "    name",
+        "    sizes",
+        "fun show(config)",
+        "    println(config.name, \" \", config.sizes[1])",
+        "    return config",
+        "config = freeze(Config(\"tiles\", [16, 32]))",
+        "returned = show(config)",
+        "sizes = config.sizes",
+        "sizes[0] = 8",
+        "println(sizes)",
+        "println(config)",
+        "items = freeze([1, 2])",
+        "items = items + [3]",
+        "items[0] = 5",
+        "println(items)",
+        "counts = freeze([1])",
+        "counts += [2]",
+        "counts[0] = 0",
+        "println(counts)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // A value read out of a frozen one, or made from it, can be changed
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "tiles 32",
+        "[8, 32]",
+        "Config { name: tiles, sizes: [16, 32] }",
+        "[5, 2, 3]",
+        "[0, 2]",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    // It stays frozen when assigned, passed to a function or returned from one
+    #[rustfmt::skip]
+    let invalid = [
+        ("config.name = \"other\"", 4),
+        ("config.sizes[0] = 1", 4),
+        ("copy = config\ncopy.sizes = []", 5),
+        ("fun rename(c)\n    c.name = \"other\"\nrename(config)", 5),
+        ("ages = freeze({\"ann\": 31})\nages[\"bob\"] = 27", 5),
+    ];
+    for (statements, row) in invalid {
+        let source = format!("struct Config\n    name\n    sizes\nconfig = freeze(Config(\"a\", [1]))\n{}", statements);
+        let parsed = rosy::parser::parse_strings(source.lines().collect()).unwrap();
+        match interpreter::interpret(parsed) {
+            Err(rosy::tokenizer::Error::LocationError { message, span }) => {
+                assert_eq!(message, "Cannot assign to a field or element of a frozen value");
+                assert_eq!(span.row, row, "{}", statements);
+            }
+            other => panic!("Expected an error for {}, got {:?}", statements, other.map(|_| ())),
+        }
+    }
+
+    let program = Vec::from(["grid = freeze([[1]])", "println(grid[0][0])"]);
+    assert!(pipeline::run_typecheck_pipeline(program).is_ok());
+
+    let program = Vec::from(["number = freeze(1)"]);
+    assert!(pipeline::run_typecheck_pipeline(program).is_err());
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn tuple_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun min_max(values)",
+        "\tsmallest = values[0]",
+        "\tlargest = values[0]",
+        "\tfor value in values",
+        "\t\tif value < smallest",
+        "\t\t\tsmallest = value",
+        "\t\tif value > largest",
+        "\t\t\tlargest = value",
+        "\treturn (smallest, largest)",
+        "bounds = min_max([3, 1, 4])",
+        "println(bounds[1] - bounds[0])",
+        "println((\"ann\", 31, true))",
+        "println((1,))",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "3",
+        "(ann, 31, true)",
+        "(1,)",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn closure_test() {
+    // A function defined inside another keeps the values of the variables it uses from there
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun make_adder(n)",
+        "    fun add(x)",
+        "        return x + n",
+        "    return add",
+        "add_two = make_adder(2)",
+        "add_ten = make_adder(10)",
+        "println(add_two(3))",
+        "println(add_ten(3))",
+        "fun counter()",
+        "    count = 5",
+        "    fun step()",
+        "        count += 1",
+        "        return count",
+        "    println(step())",
+        "    println(step())",
+        "    println(count)",
+        "counter()",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // Each call starts from the captured values, so changes in a call are not seen outside it
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "5",
+        "13",
+        "6",
+        "6",
+        "5",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn default_parameter_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun greet(name, greeting = \"hello\")",
+        "    println(greeting + \", \" + name)",
+        "greet(\"ann\")",
+        "greet(\"bob\", \"hi\")",
+        "fun outer(x)",
+        "    base = 10",
+        "    fun add(y, z = base * 2)",
+        "        return y + z",
+        "    return add(x) + add(x, 1)",
+        "println(outer(5))",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // A default is computed at each call which leaves its parameter out, with the variables the function captured
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "hello, ann",
+        "hi, bob",
+        "31",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    // All parameters without a default must be given
+    #[rustfmt::skip]
+    let missing = Vec::from([
+        "fun greet(name, greeting = \"hello\")",
+        "    println(greeting)",
+        "greet()",
+    ]);
+    assert!(pipeline::run_pipeline(missing).is_err());
+}
+
+#[cfg(all(feature = "core", feature = "string"))]
+#[test]
+fn keyword_argument_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun draw(x, y, symbol = \"*\", size = 1)",
+        "    print(symbol)",
+        "    print(x)",
+        "    print(y)",
+        "    println(size)",
+        "draw(y = 2, x = 1)",
+        "draw(3, 4, size = 5)",
+        "draw(5, size = 2, y = 6)",
+        "struct Point",
+        "    x",
+        "    y",
+        "p = Point(y = 8, x = 7)",
+        "println(p.x - p.y)",
+        "println(pad_left(fill = \"0\", text = \"7\", width = 3))",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // Keyword arguments go to the parameter they name, the parameters left out get their default
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "*121",
+        "*345",
+        "*562",
+        "-1",
+        "007",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    // A parameter without a default must be given, and a name must be a parameter of the function
+    #[rustfmt::skip]
+    let programs = [
+        Vec::from(["fun f(a, b)", "    return a", "f(b = 1)"]),
+        Vec::from(["fun f(a, b)", "    return a", "f(1, c = 2)"]),
+        Vec::from(["fun f(a, b)", "    return a", "f(1, a = 2)"]),
+    ];
+    for program in programs {
+        assert!(pipeline::run_pipeline(program).is_err());
+    }
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn rest_parameter_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun total(label, *numbers)",
+        "    sum = 0",
+        "    for number in numbers",
+        "        sum = sum + number",
+        "    println(label, \": \", sum)",
+        "total(\"none\")",
+        "total(\"three\", 1, 2, 3)",
+        "total(label = \"named\")",
+        "println(\"a\", 1, true)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // The arguments after the others are collected into a list, and print writes all of its values
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "none: 0",
+        "three: 6",
+        "named: 0",
+        "a1true",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    // The parameters before the rest parameter must still be given, and the rest parameter cannot be named
+    #[rustfmt::skip]
+    let programs = [
+        Vec::from(["fun f(a, *rest)", "    return a", "f()"]),
+        Vec::from(["fun f(a, *rest)", "    return a", "f(1, rest = 2)"]),
+    ];
+    for program in programs {
+        assert!(pipeline::run_pipeline(program).is_err());
+    }
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn destructuring_assignment_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun minmax(numbers)",
+        "    low = numbers[0]",
+        "    high = numbers[0]",
+        "    for number in numbers",
+        "        if number < low",
+        "            low = number",
+        "        if number > high",
+        "            high = number",
+        "    return low, high",
+        "low, high = minmax([3, 9, 1, 4])",
+        "println(low, \" \", high)",
+        "first, second = [\"a\", \"b\"]",
+        "first, second = second, first",
+        "println(first, second)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "1 9",
+        "ba",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+
+    // A list is only known to have as many elements as there are variables when the program runs
+    let program = Vec::from(["a, b = [1, 2, 3]"]);
+    assert!(pipeline::run_pipeline(program).is_err());
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn conditional_expression_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun describe(n)",
+        "    return \"negative\" if n < 0 else \"zero\" if n == 0 else \"positive\"",
+        "println(describe(-4), \" \", describe(0), \" \", describe(7))",
+        "count = 0",
+        "println(10 / count if count != 0 else 0)",
+        "low = 3",
+        "high = 8",
+        "println((low if low < high else high) + 1)",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // Only the value which is chosen is evaluated, so the division by zero does not happen
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "negative zero positive",
+        "0",
+        "4",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn slice_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "xs = [1, 2, 3, 4, 5]",
+        "n = 2",
+        "println(xs[1:4], xs[:n], xs[3:])",
+        "println(xs[-2:100], xs[4:1])",
+        "println(\"hello\"[1:3], \"hello\"[:-1], \"|\")",
+    ]);
+
+    let actual = pipeline::run_pipeline(program);
+
+    // Bounds outside of the list are clamped to it, and a start after the end gives an empty slice
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "[2, 3, 4][1, 2][4, 5]",
+        "[1, 2, 3, 4, 5][]",
+        "el|",
+        "",
+    ]);
+
+    compare(actual, str_to_string(expected));
+}
@@ -1,3 +1,4 @@
+use rosy::tokenizer::Span;
 use rosy::{
     interpreter::{self, Terminal},
     parser::BaseExpr,
@@ -14,8 +15,16 @@ fn compare(actual: Result<Terminal, Error>, expected: Terminal) {
     }
 }
 
+#[cfg(feature = "core")]
 #[test]
 fn number_test() {
+    #[rustfmt::skip]
+    let lines = Vec::from([
+        "println(0)",
+        "println(1)",
+        "println(12)",
+        "println(234589374)",
+    ]);
     let program = Vec::from([
         BaseExpr {
             data: BaseExprData::Simple {
@@ -24,21 +33,15 @@ fn number_test() {
                         function_name: String::from("println"),
                         args: Vec::from([RecExpr {
                             data: RecExprData::Number { number: 0 },
-                            row: 0,
-                            col_start: 8,
-                            col_end: 9,
+                            span: Span::in_lines(&lines, 0, 8, 9),
                             generic_data: (),
                         }]),
                     },
-                    row: 0,
-                    col_start: 0,
-                    col_end: 10,
+                    span: Span::in_lines(&lines, 0, 0, 10),
                     generic_data: (),
                 },
             },
-            row: 0,
-            col_start: 0,
-            col_end: 10,
+            span: Span::in_lines(&lines, 0, 0, 10),
             generic_data: (),
         },
         BaseExpr {
@@ -48,21 +51,15 @@ fn number_test() {
                         function_name: String::from("println"),
                         args: Vec::from([RecExpr {
                             data: RecExprData::Number { number: 1 },
-                            row: 1,
-                            col_start: 8,
-                            col_end: 9,
+                            span: Span::in_lines(&lines, 1, 8, 9),
                             generic_data: (),
                         }]),
                     },
-                    row: 1,
-                    col_start: 0,
-                    col_end: 10,
+                    span: Span::in_lines(&lines, 1, 0, 10),
                     generic_data: (),
                 },
             },
-            row: 1,
-            col_start: 0,
-            col_end: 10,
+            span: Span::in_lines(&lines, 1, 0, 10),
             generic_data: (),
         },
         BaseExpr {
@@ -72,21 +69,15 @@ fn number_test() {
                         function_name: String::from("println"),
                         args: Vec::from([RecExpr {
                             data: RecExprData::Number { number: 12 },
-                            row: 2,
-                            col_start: 8,
-                            col_end: 10,
+                            span: Span::in_lines(&lines, 2, 8, 10),
                             generic_data: (),
                         }]),
                     },
-                    row: 2,
-                    col_start: 0,
-                    col_end: 11,
+                    span: Span::in_lines(&lines, 2, 0, 11),
                     generic_data: (),
                 },
             },
-            row: 2,
-            col_start: 0,
-            col_end: 11,
+            span: Span::in_lines(&lines, 2, 0, 11),
             generic_data: (),
         },
         BaseExpr {
@@ -96,21 +87,15 @@ fn number_test() {
                         function_name: String::from("println"),
                         args: Vec::from([RecExpr {
                             data: RecExprData::Number { number: 234589374 },
-                            row: 3,
-                            col_start: 8,
-                            col_end: 16,
+                            span: Span::in_lines(&lines, 3, 8, 16),
                             generic_data: (),
                         }]),
                     },
-                    row: 3,
-                    col_start: 0,
-                    col_end: 17,
+                    span: Span::in_lines(&lines, 3, 0, 17),
                     generic_data: (),
                 },
             },
-            row: 3,
-            col_start: 0,
-            col_end: 17,
+            span: Span::in_lines(&lines, 3, 0, 17),
             generic_data: (),
         },
     ]);
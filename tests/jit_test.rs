@@ -0,0 +1,113 @@
+#![cfg(feature = "jit")]
+
+use rosy::engine::Engine;
+use rosy::tokenizer::Span;
+use rosy::interpreter::Context;
+use rosy::tokenizer::Error;
+
+fn run(program: &[&str], jit: bool) -> Result<Context, Error> {
+    let mut engine = Engine::new();
+    engine.options.collect_stats = true;
+    engine.options.jit = jit;
+    engine.execute(program.to_vec())
+}
+
+#[test]
+fn hot_functions_are_compiled_test() {
+    #[rustfmt::skip]
+    let program = [
+        "fun fib(n)",
+        "    if n < 2",
+        "        return n",
+        "    return fib(n - 1) + fib(n - 2)",
+        "fun multiples(limit, step)",
+        "    total = 0",
+        "    for i in limit",
+        "        if i / step * step == i and not (i == 3)",
+        "            total = total + i",
+        "    return total",
+        "println(fib(16))",
+        "i = 0",
+        "while i < 150",
+        "    x = multiples(i, 3)",
+        "    i = i + 1",
+        "println(x)",
+        "println(multiples(-5, 2))",
+    ];
+
+    let interpreted = run(&program, false).unwrap();
+    let compiled = run(&program, true).unwrap();
+
+    assert_eq!(compiled.terminal, interpreted.terminal);
+    assert_eq!(interpreted.stats.compiled_calls, 0);
+    assert!(compiled.stats.compiled_calls > 0);
+    // Recursive calls inside of compiled code do not go through the interpreter
+    assert!(compiled.stats.function_calls["fib"] < interpreted.stats.function_calls["fib"]);
+}
+
+#[test]
+fn calls_fall_back_to_the_interpreter_test() {
+    #[rustfmt::skip]
+    let program = [
+        "fun depth(n)",
+        "    if n == 0",
+        "        return 0",
+        "    return depth(n - 1) + 1",
+        "fun quotient(a, b)",
+        "    return a / b",
+        "fun shout(text)",
+        "    return text + \"!\"",
+        "i = 0",
+        "while i < 150",
+        "    x = depth(i)",
+        "    y = quotient(i, 1)",
+        "    z = shout(\"hi\")",
+        "    i = i + 1",
+        // Too deep for a compiled call
+        "println(depth(3000))",
+        "println(quotient(-9223372036854775807 - 1, -1))",
+        "println(z)",
+        "println(quotient(1, 0))",
+    ];
+
+    let interpreted = run(&program, false);
+    let compiled = run(&program, true);
+
+    // The division by zero is reported by the interpreter, at the same place
+    assert!(matches!(&compiled, Err(Error::LocationError { message, span: Span { row: 5, .. } }) if message == "Division by zero"));
+    assert_eq!(compiled.as_ref().err(), interpreted.as_ref().err());
+
+    let program = &program[..program.len() - 1];
+    let interpreted = run(program, false).unwrap();
+    let compiled = run(program, true).unwrap();
+    assert_eq!(compiled.terminal, interpreted.terminal);
+    assert!(compiled.stats.compiled_calls > 0);
+}
+
+#[test]
+fn functions_using_globals_are_interpreted_test() {
+    #[rustfmt::skip]
+    let program = [
+        "offset = 1",
+        "fun shifted(n)",
+        "    return n + offset",
+        "fun count_down(n)",
+        "    for i in n",
+        "        n = n - 1",
+        "    return n",
+        "i = 0",
+        "while i < 150",
+        "    offset = i",
+        "    x = shifted(i)",
+        "    y = count_down(i)",
+        "    i = i + 1",
+        "println(x)",
+        "println(y)",
+    ];
+
+    let interpreted = run(&program, false).unwrap();
+    let compiled = run(&program, true).unwrap();
+
+    assert_eq!(compiled.terminal, interpreted.terminal);
+    assert_eq!(compiled.stats.compiled_calls, 0);
+}
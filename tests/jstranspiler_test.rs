@@ -0,0 +1,241 @@
+#![cfg(feature = "core")]
+
+use rosy::difftest;
+use rosy::tokenizer::Span;
+use rosy::difftest::Backend;
+use rosy::difftest::DiffOptions;
+use rosy::difftest::Outcome;
+use rosy::jstranspiler;
+use rosy::language::LanguageOptions;
+use rosy::optimiser::OptLevel;
+use rosy::optimiser::OptimisationOptions;
+use rosy::parser;
+use rosy::pipeline;
+use rosy::tokenizer::Error;
+use rosy::typechecker;
+
+#[test]
+fn typed_program_to_javascript_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun half(value)",
+        "    return value / 2",
+        "println(half(7))",
+        "println(half(7.0))",
+        "xs = [1, 2]",
+        "for x in 2",
+        "    xs[x] = half(x)",
+        "if xs[0] > 0",
+        "    println(xs[0] + xs[1])",
+    ]);
+
+    let javascript = pipeline::run_emit_js_pipeline(program).unwrap();
+
+    // Integer division is truncated, the float instance gets its own function and floats print with a decimal point.
+    // Variables are declared at the top, and an assigned element makes a changed copy of the list.
+    #[rustfmt::skip]
+    let expected = [
+        "// Floats are written as rosy writes them: 3.0 instead of 3, -0.0 instead of 0,",
+        "// and 1e20 or 1.5e-5 from 1e16 on and below 1e-4",
+        "function formatFloat(value) {",
+        "    const magnitude = Math.abs(value);",
+        "    if (Number.isNaN(value)) {",
+        "        return \"NaN\";",
+        "    }",
+        "    if (magnitude === Infinity) {",
+        "        return value > 0 ? \"inf\" : \"-inf\";",
+        "    }",
+        "    if (magnitude !== 0 && (magnitude < 1e-4 || magnitude >= 1e16)) {",
+        "        return value.toExponential().replace(\"e+\", \"e\");",
+        "    }",
+        "    if (Number.isInteger(value)) {",
+        "        return (Object.is(value, -0) ? \"-\" : \"\") + value.toFixed(1);",
+        "    }",
+        "    return String(value);",
+        "}",
+        "",
+        "// The numbers a for loop over an integer goes through",
+        "function* range(count) {",
+        "    for (let i = 0; i < count; i++) {",
+        "        yield i;",
+        "    }",
+        "}",
+        "",
+        "// The result of integer arithmetic, an error where a number can no longer hold it exactly",
+        "function exactInteger(value) {",
+        "    if (!Number.isSafeInteger(value)) {",
+        "        throw new Error(\"Integer overflow\");",
+        "    }",
+        "    return value;",
+        "}",
+        "",
+        "function half(value) {",
+        "    return Math.trunc(value / 2);",
+        "}",
+        "",
+        "function half_2(value) {",
+        "    return value / 2;",
+        "}",
+        "",
+        "let x, xs;",
+        "console.log(half(7));",
+        "console.log(formatFloat(half_2(7.0)));",
+        "xs = [1, 2];",
+        "for (x of range(2)) {",
+        "    xs = xs.with(x, half(x));",
+        "}",
+        "if (xs[0] > 0) {",
+        "    console.log(exactInteger(xs[0] + xs[1]));",
+        "}",
+        "",
+    ].join("\n");
+    assert_eq!(javascript, expected);
+}
+
+#[test]
+fn javascript_names_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun count(values)",
+        "    new = 0",
+        "    for value in values",
+        "        new = new + 1",
+        "    return new",
+        "print(count([1]))",
+        "println(\"!\")",
+    ]);
+
+    let javascript = pipeline::run_emit_js_pipeline(program).unwrap();
+
+    // Names JavaScript reserves get an underscore, and print keeps the line open until println ends it
+    assert!(javascript.contains("    new_ = exactInteger(new_ + 1);\n"));
+    assert!(javascript.contains("print(count([1]));\nprintln(\"!\");\n"));
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn unsupported_javascript_test() {
+    let program = parser::parse_strings(Vec::from(["text = read_file(\"a.txt\")"])).unwrap();
+    let typed_program = typechecker::type_check_program(program, false).unwrap();
+
+    match jstranspiler::typed_program_to_javascript(&typed_program) {
+        Err(Error::LocationError { message, span: Span { col_start, .. } }) => {
+            assert_eq!(message, "The builtin read_file cannot be written as JavaScript");
+            assert_eq!(col_start, 7);
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn javascript_float_printing_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "println(1e20)",
+        "println(-1e20)",
+        "println(1e16)",
+        "println(9900000000000000.0)",
+        "println(-0.0)",
+        "println(3.0)",
+        "println(0.0001)",
+        "println(1.5e-5)",
+        "println(0.1 + 0.2)",
+    ]);
+
+    // Node is not installed everywhere the tests run
+    if !difftest::available_backends().contains(&Backend::JavaScript) {
+        return;
+    }
+    let options = DiffOptions {
+        backends: Vec::from([Backend::Interpreter, Backend::JavaScript]),
+        language: LanguageOptions::default(),
+        optimisation: OptimisationOptions::default(),
+    };
+    let (reference, comparisons) = difftest::compare_backends(program, &options).unwrap();
+
+    assert_eq!(reference.output, "1e20\n-1e20\n1e16\n9900000000000000.0\n-0.0\n3.0\n0.0001\n1.5e-5\n0.30000000000000004\n");
+    assert_eq!(comparisons[0].outcome, Outcome::Same);
+}
+
+#[test]
+fn javascript_closure_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun outer(x)",
+        "    fun inner(y)",
+        "        return x + y",
+        "    x = x * 2",
+        "    return inner(10)",
+        "println(outer(1))",
+    ]);
+
+    let javascript = pipeline::run_emit_js_pipeline(program.clone()).unwrap();
+
+    // The captured variable is bound where the function is defined, so the later assignment does not change it
+    assert!(javascript.contains("function inner(x, y) {\n"));
+    assert!(javascript.contains("    inner_1_4 = inner.bind(null, x);\n    x = exactInteger(x * 2);\n    return inner_1_4(10);\n"));
+
+    if !difftest::available_backends().contains(&Backend::JavaScript) {
+        return;
+    }
+    let options = DiffOptions {
+        backends: Vec::from([Backend::Interpreter, Backend::JavaScript]),
+        language: LanguageOptions::default(),
+        optimisation: OptimisationOptions::default(),
+    };
+    let (reference, comparisons) = difftest::compare_backends(program, &options).unwrap();
+
+    assert_eq!(reference.output, "11\n");
+    assert_eq!(comparisons[0].outcome, Outcome::Same);
+}
+
+#[test]
+fn javascript_integer_arithmetic_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun power(base, exponent)",
+        "    return base ^ exponent",
+        "println(power(3, 4))",
+        "println(power(2, 62))",
+    ]);
+
+    let javascript = pipeline::run_emit_js_pipeline(program.clone()).unwrap();
+    assert!(javascript.contains("    return integerPower(base, exponent);\n"));
+
+    // An integer written as a literal must be exact as well
+    let optimisation = OptimisationOptions::with_level(OptLevel::O1);
+    let typed_program =
+        pipeline::typed_program_for_transpiling(Vec::from(["println(2 ^ 62)"]), &LanguageOptions::default(), &optimisation).unwrap();
+    let error = jstranspiler::typed_program_to_javascript(&typed_program).unwrap_err();
+    assert_eq!(error.message(), "An integer beyond 2^53 cannot be written as JavaScript");
+
+    if !difftest::available_backends().contains(&Backend::JavaScript) {
+        return;
+    }
+    let options = DiffOptions {
+        backends: Vec::from([Backend::Interpreter, Backend::JavaScript]),
+        language: LanguageOptions::default(),
+        optimisation: OptimisationOptions::default(),
+    };
+
+    // A result no number holds exactly is an error instead of a rounded value
+    let (_, comparisons) = difftest::compare_backends(program, &options).unwrap();
+    match &comparisons[0].outcome {
+        Outcome::Different(output) => {
+            assert_eq!(output.output, "81\n");
+            assert_eq!(output.exit_code, 1);
+        }
+        other => panic!("Expected the overflow to stop the program, got {:?}", other),
+    }
+
+    // Like the interpreter, a negative exponent is an error
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun power(base, exponent)",
+        "    return base ^ exponent",
+        "println(power(2, -1))",
+    ]);
+    let (reference, comparisons) = difftest::compare_backends(program, &options).unwrap();
+    assert_eq!(reference.exit_code, 1);
+    assert_eq!(comparisons[0].outcome, Outcome::Same);
+}
@@ -0,0 +1,208 @@
+use rosy::desugarer;
+use rosy::tokenizer::Span;
+use rosy::engine::Engine;
+use rosy::language::LanguageOptions;
+use rosy::parser;
+use rosy::tokenizer::Error;
+use rosy::typechecker;
+use rosy::typechecker::Type;
+
+fn type_check(program: Vec<&str>, options: &LanguageOptions) -> Result<(), Error> {
+    let base_expressions = parser::parse_strings_with_options(program, options)?;
+    typechecker::type_check_program_with_options(
+        desugarer::desugar(base_expressions),
+        false,
+        options,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn from_toml_test() {
+    #[rustfmt::skip]
+    let config = [
+        "# Options for this project",
+        "[language]",
+        "indentation_width = 2",
+        "strict_typing = true",
+        "",
+        "strict_numerics = true",
+        "true_division = true",
+        "experimental_features = []",
+    ].join("\n");
+
+    let expected = LanguageOptions {
+        indentation_width: 2,
+        strict_typing: true,
+        strict_numerics: true,
+        true_division: true,
+        experimental_features: Vec::new(),
+    };
+    assert_eq!(LanguageOptions::from_toml(&config), Ok(expected));
+
+    // Options that are left out keep their default value
+    assert_eq!(LanguageOptions::from_toml("[language]"), Ok(LanguageOptions::default()));
+    assert_eq!(LanguageOptions::from_toml(""), Ok(LanguageOptions::default()));
+
+    // A comment can follow a value, a # inside a string is part of it
+    #[rustfmt::skip]
+    let config = [
+        "[language]  # the only table",
+        "strict_numerics = true  # no mixing of integers and floats",
+        "indentation_width = 2# two spaces",
+    ].join("\n");
+    let options = LanguageOptions::from_toml(&config).unwrap();
+    assert!(options.strict_numerics);
+    assert_eq!(options.indentation_width, 2);
+
+    let message = |config: &str| match LanguageOptions::from_toml(config) {
+        Err(Error::SimpleError { message }) => message,
+        other => panic!("Expected an error for '{}', got {:?}", config, other),
+    };
+    assert_eq!(
+        message("[language]\nindentation_width = 0"),
+        "rosy.toml line 2: indentation_width must be a positive integer, found '0'"
+    );
+    assert_eq!(
+        message("[language]\nstrict_typing = yes"),
+        "rosy.toml line 2: strict_typing must be true or false, found 'yes'"
+    );
+    assert_eq!(
+        message("[language]\nexperimental_features = [\"teleport\"]"),
+        "rosy.toml line 2: Unknown experimental feature 'teleport'"
+    );
+    assert_eq!(
+        message("[language]\nstrict = true"),
+        "rosy.toml line 2: Unknown language option 'strict'"
+    );
+    assert_eq!(
+        message("strict_typing = true"),
+        "rosy.toml line 1: 'strict_typing' must be in the [language] table"
+    );
+    assert_eq!(message("[package]"), "rosy.toml line 1: Unknown table [package]");
+    assert_eq!(
+        message("[language]\nexperimental_features = [\"#teleport\"]  # comment"),
+        "rosy.toml line 2: Unknown experimental feature '#teleport'"
+    );
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn indentation_width_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 0",
+        "for i in 3",
+        "  if i > 0",
+        "    a += i",
+        "println(a)",
+    ]);
+
+    // Two spaces is not a whole level of the default indentation
+    assert!(parser::parse_strings(program.clone()).is_err());
+
+    let mut engine = Engine::new();
+    engine.options.language.indentation_width = 2;
+    let actual = engine.run(program).unwrap();
+    assert_eq!(actual, Vec::from(["3", ""]));
+}
+
+#[cfg(all(feature = "core", feature = "math"))]
+#[test]
+fn strict_numerics_test() {
+    #[rustfmt::skip]
+    let mixed = Vec::from([
+        "m = magnitude(complex(3, 4))",
+        "a = m + 1",
+    ]);
+    #[rustfmt::skip]
+    let compared = Vec::from([
+        "m = magnitude(complex(3, 4))",
+        "println(2 < m)",
+    ]);
+    #[rustfmt::skip]
+    let converted = Vec::from([
+        "m = magnitude(complex(3, 4))",
+        "a = m + float(1)",
+        "println(float(2) < m)",
+        "b = int(m) + 1",
+    ]);
+
+    let strict = LanguageOptions {
+        strict_numerics: true,
+        ..LanguageOptions::default()
+    };
+
+    assert!(type_check(mixed.clone(), &LanguageOptions::default()).is_ok());
+    assert!(type_check(compared.clone(), &LanguageOptions::default()).is_ok());
+
+    // The error points at the integer that is converted
+    assert_eq!(
+        type_check(mixed.clone(), &strict),
+        Err(Error::TypeError {
+            message: String::from(
+                "Implicit conversion of an integer to a float, convert it with float()"
+            ),
+            expected: Type::Float,
+            found: Type::Integer,
+            span: Span::in_lines(&mixed, 1, 8, 9),
+        })
+    );
+    assert!(matches!(
+        type_check(compared, &strict),
+        Err(Error::TypeError { span: Span { row: 1, col_start: 8, .. }, .. })
+    ));
+    assert_eq!(type_check(converted, &strict), Ok(()));
+}
+
+#[cfg(all(feature = "core", feature = "math"))]
+#[test]
+fn true_division_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 7 / 2",
+        "println(a)",
+        "println(6 / 3 + 1)",
+        "println(7 // 2)",
+    ]);
+
+    let mut engine = Engine::new();
+    let actual = engine.run(program.clone()).unwrap();
+    assert_eq!(actual, Vec::from(["3", "3", "3", ""]));
+
+    engine.options.language.true_division = true;
+    let actual = engine.run(program.clone()).unwrap();
+    assert_eq!(actual, Vec::from(["3.5", "3.0", "3", ""]));
+
+    // The typechecker agrees: the quotient is a float, so it cannot be passed where an integer is expected
+    let options = LanguageOptions {
+        true_division: true,
+        ..LanguageOptions::default()
+    };
+    assert!(type_check(Vec::from(["a = abs(7 // 2)"]), &options).is_ok());
+    assert!(type_check(Vec::from(["a = abs(7 / 2)"]), &options).is_err());
+    assert!(type_check(Vec::from(["a = abs(7 / 2)"]), &LanguageOptions::default()).is_ok());
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn strict_typing_test() {
+    // Without strict typing the error is only found when the second line runs
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "println(1)",
+        "a = 1 + \"a\"",
+    ]);
+
+    let mut engine = Engine::new();
+    assert!(matches!(
+        engine.execute(program.clone()),
+        Err(Error::LocationError { .. })
+    ));
+
+    engine.options.language.strict_typing = true;
+    assert!(matches!(
+        engine.execute(program),
+        Err(Error::TypeError { .. })
+    ));
+}
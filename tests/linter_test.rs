@@ -0,0 +1,152 @@
+use rosy::language::LanguageOptions;
+use rosy::linter::{self, StyleOptions, Warning};
+use rosy::tokenizer::Span;
+
+fn lint(program: Vec<&str>) -> Vec<Warning> {
+    linter::lint_program(program, &LanguageOptions::default()).unwrap()
+}
+
+#[test]
+fn never_ending_loop_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "i = 0",
+        "while i < 10",
+        "    println(i)",
+        "while true",
+        "    for j in 3",
+        "        break",
+        "total = 0",
+        "while total < 10 and i < 5",
+        "    total = total + 1",
+    ]);
+
+    // The break only leaves the inner loop, and the last loop changes one of the variables it reads
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        Warning {
+            message: String::from("This loop never ends, as its body does not change i and does not break or return"),
+            span: Span::in_lines(&program, 1, 6, 12),
+        },
+        Warning {
+            message: String::from("This loop never ends, as its condition is always the same and its body does not break or return"),
+            span: Span::in_lines(&program, 3, 6, 10),
+        },
+    ]);
+    assert_eq!(lint(program), expected);
+}
+
+#[test]
+fn loop_which_can_end_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "numbers = [1, 2, 3]",
+        "while len(numbers) > 0",
+        "    numbers[0] = 0",
+        "fun find(limit)",
+        "    while true",
+        "        return limit",
+        "while read_key() != \"q\"",
+        "    println(\"again\")",
+        "while false",
+        "    println(\"never\")",
+        "x = 5",
+        "while x > 0",
+        "    a, x = [1, 0]",
+    ]);
+
+    // Changing an element, a return, a builtin which reads input, a false condition and destructuring all let the loop end
+    assert_eq!(lint(program), Vec::new());
+}
+
+#[test]
+fn never_running_loop_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "for i in 0",
+        "    println(i)",
+        "for i in -2",
+        "    println(i)",
+        "for item in []",
+        "    println(item)",
+        "for i in 1",
+        "    println(i)",
+    ]);
+
+    let messages: Vec<String> = lint(program).into_iter().map(|warning| warning.message).collect();
+    #[rustfmt::skip]
+    assert_eq!(messages, Vec::from([
+        "This loop never runs, as it counts up to 0",
+        "This loop never runs, as it counts up to -2",
+        "This loop never runs, as the list it loops over is empty",
+    ]));
+}
+
+#[test]
+fn style_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun add(a, b)",
+        "    return a+b",
+        "# doubles the value",
+        "fun double(x)",
+        "    return x * 2",
+        "",
+        "fun first(*rest)",
+        "    return -rest[0]",
+        "total=add(1, -2) * 3",
+        "println(total - double(3) if total >= 0 else first(1, 2))",
+    ]);
+    let style = StyleOptions {
+        max_line_length: 50,
+        blank_lines_between_functions: 1,
+    };
+
+    let warnings = linter::find_style_warnings(program.clone(), &LanguageOptions::default(), &style).unwrap();
+
+    // The comment above double belongs to it, and a minus or star without a value before it is written against its operand
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        Warning {
+            message: String::from("Put one space on each side of +"),
+            span: Span::in_lines(&program, 1, 12, 13),
+        },
+        Warning {
+            message: String::from("Put a blank line between functions"),
+            span: Span::in_lines(&program, 3, 0, 3),
+        },
+        Warning {
+            message: String::from("Put one space on each side of ="),
+            span: Span::in_lines(&program, 8, 5, 6),
+        },
+        Warning {
+            message: String::from("This line is 57 characters long, more than the maximum of 50"),
+            span: Span::in_lines(&program, 9, 50, 57),
+        },
+    ]);
+    assert_eq!(warnings, expected);
+
+    let relaxed = StyleOptions {
+        max_line_length: 0,
+        blank_lines_between_functions: 2,
+    };
+    let program = Vec::from(["fun a()", "    return 1", "", "fun b()", "    return 2"]);
+    let messages: Vec<String> = linter::find_style_warnings(program, &LanguageOptions::default(), &relaxed)
+        .unwrap()
+        .into_iter()
+        .map(|warning| warning.message)
+        .collect();
+    assert_eq!(messages, Vec::from(["Put 2 blank lines between functions"]));
+
+    // x++ and x-- are written against the variable
+    let program = Vec::from(["y = 1", "y++", "y--", "y = y+-1"]);
+    let warnings = linter::find_style_warnings(program.clone(), &LanguageOptions::default(), &style).unwrap();
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        Warning {
+            message: String::from("Put one space on each side of +"),
+            span: Span::in_lines(&program, 3, 5, 6),
+        },
+    ]);
+    assert_eq!(warnings, expected);
+}
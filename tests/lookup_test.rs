@@ -0,0 +1,197 @@
+use rosy::lookup;
+use rosy::typechecker::Type;
+
+#[test]
+fn type_at_variable_and_innermost_expression() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 5",
+        "b = [a + 1, 2]",
+        "c = \"text\"",
+    ]);
+
+    // Hovering the assigned variable gives the type of the assignment
+    assert_eq!(lookup::type_at_in_lines(program.clone(), 1, 0), Some(Type::List(Box::new(Type::Integer))));
+    // Hovering an operand gives the type of the operand, not of the whole list
+    assert_eq!(lookup::type_at_in_lines(program.clone(), 1, 5), Some(Type::Integer));
+    // Hovering the operator gives the type of the addition
+    assert_eq!(lookup::type_at_in_lines(program.clone(), 1, 7), Some(Type::Integer));
+    assert_eq!(lookup::type_at_in_lines(program.clone(), 2, 6), Some(Type::String));
+}
+
+#[test]
+fn type_at_inside_function_body() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun greet(name)",
+        "    return \"hi \" + name",
+        "greet(\"you\")",
+    ]);
+
+    assert_eq!(lookup::type_at_in_lines(program.clone(), 1, 17), Some(Type::String));
+}
+
+#[test]
+fn type_at_without_expression() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 5",
+        "",
+        "b = a",
+    ]);
+
+    assert_eq!(lookup::type_at_in_lines(program.clone(), 1, 0), None);
+    assert_eq!(lookup::type_at_in_lines(program.clone(), 0, 40), None);
+}
+
+#[test]
+fn type_at_with_type_error() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 5 + \"text\"",
+    ]);
+
+    assert_eq!(lookup::type_at_in_lines(program, 0, 4), None);
+}
+
+#[test]
+fn find_references_is_scope_aware() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "x = 1",
+        "fun f(x)",
+        "    y = x + 1",
+        "    return y",
+        "x = f(x)",
+    ]);
+
+    let positions = |references: Vec<lookup::SymbolOccurrence>| -> Vec<(usize, usize)> {
+        references.iter().map(|reference| (reference.span.row, reference.span.col_start)).collect()
+    };
+
+    // The parameter x shadows the global x inside the function
+    assert_eq!(positions(lookup::find_references(program.clone(), 2, 8).unwrap()), vec![(1, 6), (2, 8)]);
+    assert_eq!(positions(lookup::find_references(program.clone(), 0, 0).unwrap()), vec![(0, 0), (4, 0), (4, 6)]);
+    assert_eq!(positions(lookup::find_references(program.clone(), 4, 4).unwrap()), vec![(1, 4), (4, 4)]);
+}
+
+#[test]
+fn rename_symbol_rewrites_all_references() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "total = 0",
+        "for i in [1, 2]",
+        "\ttotal += i",
+        "print(total)",
+    ]);
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "sum = 0",
+        "for i in [1, 2]",
+        "\tsum += i",
+        "print(sum)",
+    ]);
+
+    assert_eq!(lookup::rename_symbol(program.clone(), 3, 7, "sum").unwrap(), expected);
+
+    // Keywords are not valid names, and names that are not defined in the program cannot be renamed
+    assert!(lookup::rename_symbol(program.clone(), 0, 0, "for").is_err());
+    assert!(lookup::rename_symbol(program.clone(), 3, 0, "show").is_err());
+}
+
+#[test]
+fn rename_symbol_rejects_names_already_in_use() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "x = 1",
+        "fun f(x)",
+        "    return x + 1",
+        "y = f(x)",
+        "println(x)",
+        "fun g()",
+        "    z = 2",
+        "    return x + z",
+    ]);
+
+    // Renaming would make a reference mean another symbol: the global y, the function f,
+    // or the z which is local to g
+    #[rustfmt::skip]
+    let renames = [
+        ((0, 0), "y", (3, 0)),
+        ((1, 6), "f", (1, 4)),
+        ((0, 0), "z", (7, 11)),
+    ];
+    for ((row, col), new_name, (expected_row, expected_col)) in renames {
+        match lookup::rename_symbol(program.clone(), row, col, new_name) {
+            Err(error) => {
+                assert_eq!(
+                    error.message(),
+                    format!("Cannot rename 'x' to '{}', as '{}' already refers to something else here", new_name, new_name)
+                );
+                let span = error.span().unwrap();
+                assert_eq!((span.row, span.col_start), (expected_row, expected_col));
+            }
+            Ok(lines) => panic!("Expected an error renaming to {}, got {:?}", new_name, lines),
+        }
+    }
+
+    // A name which is only used in another function can be taken
+    let renamed = lookup::rename_symbol(program.clone(), 1, 6, "z").unwrap();
+    assert_eq!(renamed[1..3], ["fun f(z)", "    return z + 1"]);
+}
+
+#[test]
+fn find_definition_of_symbols() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun square(n)",
+        "    return n * n",
+        "a = square(3)",
+        "a = a + 1",
+        "print(a)",
+    ]);
+
+    let definition_position = |row: usize, col: usize| {
+        lookup::find_definition(program.clone(), row, col)
+            .unwrap()
+            .map(|definition| (definition.span.row, definition.span.col_start))
+    };
+
+    assert_eq!(definition_position(1, 11), Some((0, 11)));
+    assert_eq!(definition_position(2, 6), Some((0, 4)));
+    // A reassigned variable is defined where it was first assigned
+    assert_eq!(definition_position(4, 6), Some((2, 0)));
+    // Default functions are not defined in the program
+    assert_eq!(definition_position(4, 0), None);
+    assert!(lookup::find_definition(program.clone(), 1, 4).is_err());
+}
+
+#[test]
+fn find_unused_symbols() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun unused(a, b)",
+        "    return a",
+        "fun used(c)",
+        "    d = c",
+        "    return 1",
+        "e = used(2)",
+    ]);
+
+    let unused: Vec<(String, usize)> = lookup::find_unused_symbols(program)
+        .unwrap()
+        .iter()
+        .map(|symbol| (symbol.name.clone(), symbol.span.row))
+        .collect();
+
+    assert_eq!(
+        unused,
+        vec![
+            ("unused".to_string(), 0),
+            ("b".to_string(), 0),
+            ("d".to_string(), 3),
+            ("e".to_string(), 5),
+        ]
+    );
+}
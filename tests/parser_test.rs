@@ -1,4 +1,5 @@
-use rosy::parser::{self, BaseExpr, BaseExprData, RecExpr, RecExprData};
+use rosy::parser::{self, Accessor, BaseExpr, BaseExprData, MatchArm, Pattern, RecExpr, RecExprData};
+use rosy::tokenizer::Span;
 use rosy::pipeline::print_error;
 use rosy::tokenizer::Error;
 
@@ -55,15 +56,11 @@ fn simple_variable() {
                     data: RecExprData::Variable {
                         name: String::from("a_b"),
                     },
-                    row: 0,
-                    col_start: 0,
-                    col_end: 3,
+                    span: Span::in_lines(&program_copy, 0, 0, 3),
                     generic_data: (),
                 },
             },
-            row: 0,
-            col_start: 0,
-            col_end: 3,
+            span: Span::in_lines(&program_copy, 0, 0, 3),
             generic_data: (),
         },
         BaseExpr {
@@ -72,15 +69,11 @@ fn simple_variable() {
                     data: RecExprData::Variable {
                         name: String::from("long_variable"),
                     },
-                    row: 1,
-                    col_start: 0,
-                    col_end: 13,
+                    span: Span::in_lines(&program_copy, 1, 0, 13),
                     generic_data: (),
                 },
             },
-            row: 1,
-            col_start: 0,
-            col_end: 13,
+            span: Span::in_lines(&program_copy, 1, 0, 13),
             generic_data: (),
         },
         BaseExpr {
@@ -89,15 +82,11 @@ fn simple_variable() {
                     data: RecExprData::Variable {
                         name: String::from("var"),
                     },
-                    row: 2,
-                    col_start: 0,
-                    col_end: 3,
+                    span: Span::in_lines(&program_copy, 2, 0, 3),
                     generic_data: (),
                 },
             },
-            row: 2,
-            col_start: 0,
-            col_end: 3,
+            span: Span::in_lines(&program_copy, 2, 0, 3),
             generic_data: (),
         },
     ]);
@@ -121,60 +110,44 @@ fn simple_integer() {
             data: BaseExprData::Simple {
                 expr: RecExpr {
                     data: RecExprData::Number { number: 0 },
-                    row: 0,
-                    col_start: 0,
-                    col_end: 1,
+                    span: Span::in_lines(&program_copy, 0, 0, 1),
                     generic_data: (),
                 },
             },
-            row: 0,
-            col_start: 0,
-            col_end: 1,
+            span: Span::in_lines(&program_copy, 0, 0, 1),
             generic_data: (),
         },
         BaseExpr {
             data: BaseExprData::Simple {
                 expr: RecExpr {
                     data: RecExprData::Number { number: 1 },
-                    row: 1,
-                    col_start: 0,
-                    col_end: 1,
+                    span: Span::in_lines(&program_copy, 1, 0, 1),
                     generic_data: (),
                 },
             },
-            row: 1,
-            col_start: 0,
-            col_end: 1,
+            span: Span::in_lines(&program_copy, 1, 0, 1),
             generic_data: (),
         },
         BaseExpr {
             data: BaseExprData::Simple {
                 expr: RecExpr {
                     data: RecExprData::Number { number: 12 },
-                    row: 2,
-                    col_start: 0,
-                    col_end: 2,
+                    span: Span::in_lines(&program_copy, 2, 0, 2),
                     generic_data: (),
                 },
             },
-            row: 2,
-            col_start: 0,
-            col_end: 2,
+            span: Span::in_lines(&program_copy, 2, 0, 2),
             generic_data: (),
         },
         BaseExpr {
             data: BaseExprData::Simple {
                 expr: RecExpr {
                     data: RecExprData::Number { number: 234589374 },
-                    row: 3,
-                    col_start: 0,
-                    col_end: 9,
+                    span: Span::in_lines(&program_copy, 3, 0, 9),
                     generic_data: (),
                 },
             },
-            row: 3,
-            col_start: 0,
-            col_end: 9,
+            span: Span::in_lines(&program_copy, 3, 0, 9),
             generic_data: (),
         },
     ]);
@@ -196,30 +169,22 @@ fn simple_boolean() {
             data: BaseExprData::Simple {
                 expr: RecExpr {
                     data: RecExprData::Boolean { value: true },
-                    row: 0,
-                    col_start: 0,
-                    col_end: 4,
+                    span: Span::in_lines(&program_copy, 0, 0, 4),
                     generic_data: (),
                 },
             },
-            row: 0,
-            col_start: 0,
-            col_end: 4,
+            span: Span::in_lines(&program_copy, 0, 0, 4),
             generic_data: (),
         },
         BaseExpr {
             data: BaseExprData::Simple {
                 expr: RecExpr {
                     data: RecExprData::Boolean { value: false },
-                    row: 1,
-                    col_start: 0,
-                    col_end: 5,
+                    span: Span::in_lines(&program_copy, 1, 0, 5),
                     generic_data: (),
                 },
             },
-            row: 1,
-            col_start: 0,
-            col_end: 5,
+            span: Span::in_lines(&program_copy, 1, 0, 5),
             generic_data: (),
         },
     ]);
@@ -243,15 +208,11 @@ fn simple_string() {
                     data: RecExprData::String {
                         value: String::from("blah"),
                     },
-                    row: 0,
-                    col_start: 0,
-                    col_end: 6,
+                    span: Span::in_lines(&program_copy, 0, 0, 6),
                     generic_data: (),
                 },
             },
-            row: 0,
-            col_start: 0,
-            col_end: 6,
+            span: Span::in_lines(&program_copy, 0, 0, 6),
             generic_data: (),
         },
         BaseExpr {
@@ -260,15 +221,11 @@ fn simple_string() {
                     data: RecExprData::String {
                         value: String::from("fun in for loop  { } () (*)^)*& _+-=    spaces"),
                     },
-                    row: 1,
-                    col_start: 0,
-                    col_end: 48,
+                    span: Span::in_lines(&program_copy, 1, 0, 48),
                     generic_data: (),
                 },
             },
-            row: 1,
-            col_start: 0,
-            col_end: 48,
+            span: Span::in_lines(&program_copy, 1, 0, 48),
             generic_data: (),
         },
     ]);
@@ -292,41 +249,29 @@ fn order_of_operations_test() {
                         data: RecExprData::Add {
                             left: Box::new(RecExpr {
                                 data: RecExprData::Number { number: 1 },
-                                row: 0,
-                                col_start: 0,
-                                col_end: 1,
+                                span: Span::in_lines(&program_copy, 0, 0, 1),
                                 generic_data: (),
                             }),
                             right: Box::new(RecExpr {
                                 data: RecExprData::Number { number: 2 },
-                                row: 0,
-                                col_start: 4,
-                                col_end: 5,
+                                span: Span::in_lines(&program_copy, 0, 4, 5),
                                 generic_data: (),
                             }),
                         },
-                        row: 0,
-                        col_start: 0,
-                        col_end: 5,
+                        span: Span::in_lines(&program_copy, 0, 0, 5),
                         generic_data: (),
                     }),
                     right: Box::new(RecExpr {
                         data: RecExprData::Number { number: 3 },
-                        row: 0,
-                        col_start: 8,
-                        col_end: 9,
+                        span: Span::in_lines(&program_copy, 0, 8, 9),
                         generic_data: (),
                     }),
                 },
-                row: 0,
-                col_start: 0,
-                col_end: 9,
+                span: Span::in_lines(&program_copy, 0, 0, 9),
                 generic_data: (),
             },
         },
-        row: 0,
-        col_start: 0,
-        col_end: 9,
+        span: Span::in_lines(&program_copy, 0, 0, 9),
         generic_data: (),
     }]);
 
@@ -356,28 +301,20 @@ fn simple_arithmetic() {
                     data: RecExprData::Add {
                         left: Box::new(RecExpr {
                             data: RecExprData::Number { number: 1 },
-                            row: 0,
-                            col_start: 0,
-                            col_end: 1,
+                            span: Span::in_lines(&program_copy, 0, 0, 1),
                             generic_data: (),
                         }),
                         right: Box::new(RecExpr {
                             data: RecExprData::Number { number: 2 },
-                            row: 0,
-                            col_start: 4,
-                            col_end: 5,
+                            span: Span::in_lines(&program_copy, 0, 4, 5),
                             generic_data: (),
                         }),
                     },
-                    row: 0,
-                    col_start: 0,
-                    col_end: 5,
+                    span: Span::in_lines(&program_copy, 0, 0, 5),
                     generic_data: (),
                 },
             },
-            row: 0,
-            col_start: 0,
-            col_end: 5,
+            span: Span::in_lines(&program_copy, 0, 0, 5),
             generic_data: (),
         },
         BaseExpr {
@@ -386,28 +323,20 @@ fn simple_arithmetic() {
                     data: RecExprData::Multiply {
                         left: Box::new(RecExpr {
                             data: RecExprData::Number { number: 12 },
-                            row: 1,
-                            col_start: 0,
-                            col_end: 2,
+                            span: Span::in_lines(&program_copy, 1, 0, 2),
                             generic_data: (),
                         }),
                         right: Box::new(RecExpr {
                             data: RecExprData::Number { number: 3 },
-                            row: 1,
-                            col_start: 5,
-                            col_end: 6,
+                            span: Span::in_lines(&program_copy, 1, 5, 6),
                             generic_data: (),
                         }),
                     },
-                    row: 1,
-                    col_start: 0,
-                    col_end: 6,
+                    span: Span::in_lines(&program_copy, 1, 0, 6),
                     generic_data: (),
                 },
             },
-            row: 1,
-            col_start: 0,
-            col_end: 6,
+            span: Span::in_lines(&program_copy, 1, 0, 6),
             generic_data: (),
         },
         BaseExpr {
@@ -416,28 +345,20 @@ fn simple_arithmetic() {
                     data: RecExprData::Divide {
                         left: Box::new(RecExpr {
                             data: RecExprData::Number { number: 12 },
-                            row: 2,
-                            col_start: 0,
-                            col_end: 2,
+                            span: Span::in_lines(&program_copy, 2, 0, 2),
                             generic_data: (),
                         }),
                         right: Box::new(RecExpr {
                             data: RecExprData::Number { number: 3 },
-                            row: 2,
-                            col_start: 5,
-                            col_end: 6,
+                            span: Span::in_lines(&program_copy, 2, 5, 6),
                             generic_data: (),
                         }),
                     },
-                    row: 2,
-                    col_start: 0,
-                    col_end: 6,
+                    span: Span::in_lines(&program_copy, 2, 0, 6),
                     generic_data: (),
                 },
             },
-            row: 2,
-            col_start: 0,
-            col_end: 6,
+            span: Span::in_lines(&program_copy, 2, 0, 6),
             generic_data: (),
         },
         BaseExpr {
@@ -446,28 +367,20 @@ fn simple_arithmetic() {
                     data: RecExprData::Power {
                         left: Box::new(RecExpr {
                             data: RecExprData::Number { number: 12 },
-                            row: 3,
-                            col_start: 0,
-                            col_end: 2,
+                            span: Span::in_lines(&program_copy, 3, 0, 2),
                             generic_data: (),
                         }),
                         right: Box::new(RecExpr {
                             data: RecExprData::Number { number: 3 },
-                            row: 3,
-                            col_start: 5,
-                            col_end: 6,
+                            span: Span::in_lines(&program_copy, 3, 5, 6),
                             generic_data: (),
                         }),
                     },
-                    row: 3,
-                    col_start: 0,
-                    col_end: 6,
+                    span: Span::in_lines(&program_copy, 3, 0, 6),
                     generic_data: (),
                 },
             },
-            row: 3,
-            col_start: 0,
-            col_end: 6,
+            span: Span::in_lines(&program_copy, 3, 0, 6),
             generic_data: (),
         },
         BaseExpr {
@@ -476,28 +389,20 @@ fn simple_arithmetic() {
                     data: RecExprData::Subtract {
                         left: Box::new(RecExpr {
                             data: RecExprData::Number { number: 12 },
-                            row: 4,
-                            col_start: 0,
-                            col_end: 2,
+                            span: Span::in_lines(&program_copy, 4, 0, 2),
                             generic_data: (),
                         }),
                         right: Box::new(RecExpr {
                             data: RecExprData::Number { number: 3 },
-                            row: 4,
-                            col_start: 5,
-                            col_end: 6,
+                            span: Span::in_lines(&program_copy, 4, 5, 6),
                             generic_data: (),
                         }),
                     },
-                    row: 4,
-                    col_start: 0,
-                    col_end: 6,
+                    span: Span::in_lines(&program_copy, 4, 0, 6),
                     generic_data: (),
                 },
             },
-            row: 4,
-            col_start: 0,
-            col_end: 6,
+            span: Span::in_lines(&program_copy, 4, 0, 6),
             generic_data: (),
         },
         BaseExpr {
@@ -506,28 +411,20 @@ fn simple_arithmetic() {
                     data: RecExprData::Add {
                         left: Box::new(RecExpr {
                             data: RecExprData::Number { number: 12 },
-                            row: 5,
-                            col_start: 0,
-                            col_end: 2,
+                            span: Span::in_lines(&program_copy, 5, 0, 2),
                             generic_data: (),
                         }),
                         right: Box::new(RecExpr {
                             data: RecExprData::Number { number: 3 },
-                            row: 5,
-                            col_start: 5,
-                            col_end: 6,
+                            span: Span::in_lines(&program_copy, 5, 5, 6),
                             generic_data: (),
                         }),
                     },
-                    row: 5,
-                    col_start: 0,
-                    col_end: 6,
+                    span: Span::in_lines(&program_copy, 5, 0, 6),
                     generic_data: (),
                 },
             },
-            row: 5,
-            col_start: 0,
-            col_end: 6,
+            span: Span::in_lines(&program_copy, 5, 0, 6),
             generic_data: (),
         },
         BaseExpr {
@@ -538,41 +435,29 @@ fn simple_arithmetic() {
                             data: RecExprData::Multiply {
                                 left: Box::new(RecExpr {
                                     data: RecExprData::Number { number: 12 },
-                                    row: 6,
-                                    col_start: 0,
-                                    col_end: 2,
+                                    span: Span::in_lines(&program_copy, 6, 0, 2),
                                     generic_data: (),
                                 }),
                                 right: Box::new(RecExpr {
                                     data: RecExprData::Number { number: 3 },
-                                    row: 6,
-                                    col_start: 5,
-                                    col_end: 6,
+                                    span: Span::in_lines(&program_copy, 6, 5, 6),
                                     generic_data: (),
                                 }),
                             },
-                            row: 6,
-                            col_start: 0,
-                            col_end: 6,
+                            span: Span::in_lines(&program_copy, 6, 0, 6),
                             generic_data: (),
                         }),
                         right: Box::new(RecExpr {
                             data: RecExprData::Number { number: 4 },
-                            row: 6,
-                            col_start: 9,
-                            col_end: 10,
+                            span: Span::in_lines(&program_copy, 6, 9, 10),
                             generic_data: (),
                         }),
                     },
-                    row: 6,
-                    col_start: 0,
-                    col_end: 10,
+                    span: Span::in_lines(&program_copy, 6, 0, 10),
                     generic_data: (),
                 },
             },
-            row: 6,
-            col_start: 0,
-            col_end: 10,
+            span: Span::in_lines(&program_copy, 6, 0, 10),
             generic_data: (),
         },
         BaseExpr {
@@ -581,43 +466,31 @@ fn simple_arithmetic() {
                     data: RecExprData::Multiply {
                         left: Box::new(RecExpr {
                             data: RecExprData::Number { number: 12 },
-                            row: 7,
-                            col_start: 0,
-                            col_end: 2,
+                            span: Span::in_lines(&program_copy, 7, 0, 2),
                             generic_data: (),
                         }),
                         right: Box::new(RecExpr {
                             data: RecExprData::Add {
                                 left: Box::new(RecExpr {
                                     data: RecExprData::Number { number: 3 },
-                                    row: 7,
-                                    col_start: 6,
-                                    col_end: 7,
+                                    span: Span::in_lines(&program_copy, 7, 6, 7),
                                     generic_data: (),
                                 }),
                                 right: Box::new(RecExpr {
                                     data: RecExprData::Number { number: 4 },
-                                    row: 7,
-                                    col_start: 10,
-                                    col_end: 11,
+                                    span: Span::in_lines(&program_copy, 7, 10, 11),
                                     generic_data: (),
                                 }),
                             },
-                            row: 7,
-                            col_start: 5,
-                            col_end: 12,
+                            span: Span::in_lines(&program_copy, 7, 5, 12),
                             generic_data: (),
                         }),
                     },
-                    row: 7,
-                    col_start: 0,
-                    col_end: 12,
+                    span: Span::in_lines(&program_copy, 7, 0, 12),
                     generic_data: (),
                 },
             },
-            row: 7,
-            col_start: 0,
-            col_end: 12,
+            span: Span::in_lines(&program_copy, 7, 0, 12),
             generic_data: (),
         },
         BaseExpr {
@@ -630,114 +503,82 @@ fn simple_arithmetic() {
                                     data: RecExprData::Multiply {
                                         left: Box::new(RecExpr {
                                             data: RecExprData::Number { number: 12 },
-                                            row: 8,
-                                            col_start: 0,
-                                            col_end: 2,
+                                            span: Span::in_lines(&program_copy, 8, 0, 2),
                                             generic_data: (),
                                         }),
                                         right: Box::new(RecExpr {
                                             data: RecExprData::Add {
                                                 left: Box::new(RecExpr {
                                                     data: RecExprData::Number { number: 3 },
-                                                    row: 8,
-                                                    col_start: 6,
-                                                    col_end: 7,
+                                                    span: Span::in_lines(&program_copy, 8, 6, 7),
                                                     generic_data: (),
                                                 }),
                                                 right: Box::new(RecExpr {
                                                     data: RecExprData::Divide {
                                                         left: Box::new(RecExpr {
                                                             data: RecExprData::Number { number: 4 },
-                                                            row: 8,
-                                                            col_start: 11,
-                                                            col_end: 12,
+                                                            span: Span::in_lines(&program_copy, 8, 11, 12),
                                                             generic_data: (),
                                                         }),
                                                         right: Box::new(RecExpr {
                                                             data: RecExprData::Number { number: 2 },
-                                                            row: 8,
-                                                            col_start: 15,
-                                                            col_end: 16,
+                                                            span: Span::in_lines(&program_copy, 8, 15, 16),
                                                             generic_data: (),
                                                         }),
                                                     },
-                                                    row: 8,
-                                                    col_start: 10,
-                                                    col_end: 17,
+                                                    span: Span::in_lines(&program_copy, 8, 10, 17),
                                                     generic_data: (),
                                                 }),
                                             },
-                                            row: 8,
-                                            col_start: 5,
-                                            col_end: 18,
+                                            span: Span::in_lines(&program_copy, 8, 5, 18),
                                             generic_data: (),
                                         }),
                                     },
-                                    row: 8,
-                                    col_start: 0,
-                                    col_end: 18,
+                                    span: Span::in_lines(&program_copy, 8, 0, 18),
                                     generic_data: (),
                                 }),
                                 right: Box::new(RecExpr {
                                     data: RecExprData::Divide {
                                         left: Box::new(RecExpr {
                                             data: RecExprData::Number { number: 5 },
-                                            row: 8,
-                                            col_start: 21,
-                                            col_end: 22,
+                                            span: Span::in_lines(&program_copy, 8, 21, 22),
                                             generic_data: (),
                                         }),
                                         right: Box::new(RecExpr {
                                             data: RecExprData::Power {
                                                 left: Box::new(RecExpr {
                                                     data: RecExprData::Number { number: 6 },
-                                                    row: 8,
-                                                    col_start: 26,
-                                                    col_end: 27,
+                                                    span: Span::in_lines(&program_copy, 8, 26, 27),
                                                     generic_data: (),
                                                 }),
                                                 right: Box::new(RecExpr {
                                                     data: RecExprData::Number { number: 7 },
-                                                    row: 8,
-                                                    col_start: 30,
-                                                    col_end: 31,
+                                                    span: Span::in_lines(&program_copy, 8, 30, 31),
                                                     generic_data: (),
                                                 }),
                                             },
-                                            row: 8,
-                                            col_start: 25,
-                                            col_end: 32,
+                                            span: Span::in_lines(&program_copy, 8, 25, 32),
                                             generic_data: (),
                                         }),
                                     },
-                                    row: 8,
-                                    col_start: 21,
-                                    col_end: 32,
+                                    span: Span::in_lines(&program_copy, 8, 21, 32),
                                     generic_data: (),
                                 }),
                             },
-                            row: 8,
-                            col_start: 0,
-                            col_end: 32,
+                            span: Span::in_lines(&program_copy, 8, 0, 32),
                             generic_data: (),
                         }),
                         right: Box::new(RecExpr {
                             data: RecExprData::Number { number: 8 },
-                            row: 8,
-                            col_start: 35,
-                            col_end: 36,
+                            span: Span::in_lines(&program_copy, 8, 35, 36),
                             generic_data: (),
                         }),
                     },
-                    row: 8,
-                    col_start: 0,
-                    col_end: 36,
+                    span: Span::in_lines(&program_copy, 8, 0, 36),
                     generic_data: (),
                 },
             },
-            row: 8,
-            col_start: 0,
-            col_end: 36,
+            span: Span::in_lines(&program_copy, 8, 0, 36),
             generic_data: (),
         },
     ]);
@@ -763,15 +604,11 @@ fn variable_assignment_test() {
                 var_name: String::from("a"),
                 expr: RecExpr {
                     data: RecExprData::Number { number: 1 },
-                    row: 0,
-                    col_start: 4,
-                    col_end: 5,
+                    span: Span::in_lines(&program_copy, 0, 4, 5),
                     generic_data: (),
                 },
             },
-            row: 0,
-            col_start: 0,
-            col_end: 5,
+            span: Span::in_lines(&program_copy, 0, 0, 5),
             generic_data: (),
         },
         BaseExpr {
@@ -779,15 +616,11 @@ fn variable_assignment_test() {
                 var_name: String::from("a1_b2"),
                 expr: RecExpr {
                     data: RecExprData::Number { number: 25 },
-                    row: 1,
-                    col_start: 8,
-                    col_end: 10,
+                    span: Span::in_lines(&program_copy, 1, 8, 10),
                     generic_data: (),
                 },
             },
-            row: 1,
-            col_start: 0,
-            col_end: 10,
+            span: Span::in_lines(&program_copy, 1, 0, 10),
             generic_data: (),
         },
         BaseExpr {
@@ -797,28 +630,20 @@ fn variable_assignment_test() {
                     data: RecExprData::Multiply {
                         left: Box::new(RecExpr {
                             data: RecExprData::Number { number: 25 },
-                            row: 2,
-                            col_start: 8,
-                            col_end: 10,
+                            span: Span::in_lines(&program_copy, 2, 8, 10),
                             generic_data: (),
                         }),
                         right: Box::new(RecExpr {
                             data: RecExprData::Number { number: 2 },
-                            row: 2,
-                            col_start: 13,
-                            col_end: 14,
+                            span: Span::in_lines(&program_copy, 2, 13, 14),
                             generic_data: (),
                         }),
                     },
-                    row: 2,
-                    col_start: 8,
-                    col_end: 14,
+                    span: Span::in_lines(&program_copy, 2, 8, 14),
                     generic_data: (),
                 },
             },
-            row: 2,
-            col_start: 0,
-            col_end: 14,
+            span: Span::in_lines(&program_copy, 2, 0, 14),
             generic_data: (),
         },
         BaseExpr {
@@ -828,28 +653,20 @@ fn variable_assignment_test() {
                     data: RecExprData::Multiply {
                         left: Box::new(RecExpr {
                             data: RecExprData::Number { number: 25 },
-                            row: 3,
-                            col_start: 9,
-                            col_end: 11,
+                            span: Span::in_lines(&program_copy, 3, 9, 11),
                             generic_data: (),
                         }),
                         right: Box::new(RecExpr {
                             data: RecExprData::Number { number: 2 },
-                            row: 3,
-                            col_start: 14,
-                            col_end: 15,
+                            span: Span::in_lines(&program_copy, 3, 14, 15),
                             generic_data: (),
                         }),
                     },
-                    row: 3,
-                    col_start: 8,
-                    col_end: 16,
+                    span: Span::in_lines(&program_copy, 3, 8, 16),
                     generic_data: (),
                 },
             },
-            row: 3,
-            col_start: 0,
-            col_end: 16,
+            span: Span::in_lines(&program_copy, 3, 0, 16),
             generic_data: (),
         },
         BaseExpr {
@@ -859,21 +676,915 @@ fn variable_assignment_test() {
                     data: RecExprData::String {
                         value: String::from("string"),
                     },
-                    row: 4,
-                    col_start: 8,
-                    col_end: 16,
+                    span: Span::in_lines(&program_copy, 4, 8, 16),
                     generic_data: (),
                 },
             },
-            row: 4,
-            col_start: 0,
-            col_end: 16,
+            span: Span::in_lines(&program_copy, 4, 0, 16),
             generic_data: (),
         },
     ]);
 
     compare_linewise(expressions, expected, &program_copy);
 }
+
+#[test]
+fn yield_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "yield 12",
+    ]);
+    let program_copy = program.clone();
+    let expressions = parser::parse_strings(program);
+    let expected = Vec::from([BaseExpr {
+        data: BaseExprData::Yield {
+            value: RecExpr {
+                data: RecExprData::Number { number: 12 },
+                span: Span::in_lines(&program_copy, 0, 6, 8),
+                generic_data: (),
+            },
+        },
+        span: Span::in_lines(&program_copy, 0, 0, 8),
+        generic_data: (),
+    }]);
+
+    compare(expressions, expected, &program_copy);
+
+    // A yield always needs a value
+    assert!(parser::parse_strings(Vec::from(["yield"])).is_err());
+}
+
+#[test]
+fn defer_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "defer 12",
+    ]);
+    let program_copy = program.clone();
+    let expressions = parser::parse_strings(program);
+    let expected = Vec::from([BaseExpr {
+        data: BaseExprData::Defer {
+            expr: RecExpr {
+                data: RecExprData::Number { number: 12 },
+                span: Span::in_lines(&program_copy, 0, 6, 8),
+                generic_data: (),
+            },
+        },
+        span: Span::in_lines(&program_copy, 0, 0, 8),
+        generic_data: (),
+    }]);
+
+    compare(expressions, expected, &program_copy);
+
+    assert!(parser::parse_strings(Vec::from(["defer"])).is_err());
+}
+
+#[test]
+fn with_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "with a as b",
+        "    b",
+    ]);
+    let program_copy = program.clone();
+    let expressions = parser::parse_strings(program);
+    let expected = Vec::from([BaseExpr {
+        data: BaseExprData::With {
+            resource: RecExpr {
+                data: RecExprData::Variable {
+                    name: String::from("a"),
+                },
+                span: Span::in_lines(&program_copy, 0, 5, 6),
+                generic_data: (),
+            },
+            var_name: String::from("b"),
+            body: Vec::from([BaseExpr {
+                data: BaseExprData::Simple {
+                    expr: RecExpr {
+                        data: RecExprData::Variable {
+                            name: String::from("b"),
+                        },
+                        span: Span::in_lines(&program_copy, 1, 4, 5),
+                        generic_data: (),
+                    },
+                },
+                span: Span::in_lines(&program_copy, 1, 4, 5),
+                generic_data: (),
+            }]),
+        },
+        span: Span::in_lines(&program_copy, 0, 0, 11),
+        generic_data: (),
+    }]);
+
+    compare(expressions, expected, &program_copy);
+
+    // A with block always names its resource
+    assert!(parser::parse_strings(Vec::from(["with a", "    b"])).is_err());
+    assert!(parser::parse_strings(Vec::from(["with as b", "    b"])).is_err());
+}
+
+#[test]
+fn match_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "match x",
+        "    case -1",
+        "        a",
+        "    case _",
+        "        b",
+    ]);
+    let program_copy = program.clone();
+    let expressions = parser::parse_strings(program);
+    let variable_line = |name: &str, row: usize| BaseExpr {
+        data: BaseExprData::Simple {
+            expr: RecExpr {
+                data: RecExprData::Variable {
+                    name: String::from(name),
+                },
+                span: Span::in_lines(&program_copy, row, 8, 9),
+                generic_data: (),
+            },
+        },
+        span: Span::in_lines(&program_copy, row, 8, 9),
+        generic_data: (),
+    };
+    let expected = Vec::from([BaseExpr {
+        data: BaseExprData::Match {
+            subject: RecExpr {
+                data: RecExprData::Variable {
+                    name: String::from("x"),
+                },
+                span: Span::in_lines(&program_copy, 0, 6, 7),
+                generic_data: (),
+            },
+            arms: Vec::from([
+                MatchArm {
+                    pattern: Pattern::Number(-1),
+                    body: Vec::from([variable_line("a", 2)]),
+                    span: Span::in_lines(&program_copy, 1, 4, 11),
+                },
+                MatchArm {
+                    pattern: Pattern::Wildcard,
+                    body: Vec::from([variable_line("b", 4)]),
+                    span: Span::in_lines(&program_copy, 3, 4, 10),
+                },
+            ]),
+        },
+        span: Span::in_lines(&program_copy, 0, 0, 7),
+        generic_data: (),
+    }]);
+
+    compare(expressions, expected, &program_copy);
+
+    let arms = |program: Vec<&str>| match parser::parse_strings(program).unwrap().remove(0).data {
+        BaseExprData::Match { arms, .. } => arms.into_iter().map(|arm| arm.pattern).collect::<Vec<_>>(),
+        other => panic!("Expected a match, got {:?}", other),
+    };
+    assert_eq!(
+        arms(Vec::from(["match x", "    case \"a\"", "        a", "    case false", "        a", "    case y", "        y"])),
+        Vec::from([Pattern::String(String::from("a")), Pattern::Boolean(false), Pattern::Binding(String::from("y"))])
+    );
+
+    // Every match has cases, which have a single pattern
+    assert!(parser::parse_strings(Vec::from(["match"])).is_err());
+    assert!(parser::parse_strings(Vec::from(["match x", "a = 1"])).is_err());
+    assert!(parser::parse_strings(Vec::from(["match x", "    a = 1"])).is_err());
+    assert!(parser::parse_strings(Vec::from(["match x", "    case", "        a"])).is_err());
+    assert!(parser::parse_strings(Vec::from(["match x", "    case 1 + 2", "        a"])).is_err());
+    assert!(parser::parse_strings(Vec::from(["case 1", "    a"])).is_err());
+}
+
+#[test]
+fn delete_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "del a",
+    ]);
+    let program_copy = program.clone();
+    let expressions = parser::parse_strings(program);
+    let expected = Vec::from([BaseExpr {
+        data: BaseExprData::Delete {
+            var_name: String::from("a"),
+        },
+        span: Span::in_lines(&program_copy, 0, 0, 5),
+        generic_data: (),
+    }]);
+
+    compare(expressions, expected, &program_copy);
+
+    // Only a single variable can be deleted
+    assert!(parser::parse_strings(Vec::from(["del"])).is_err());
+    assert!(parser::parse_strings(Vec::from(["del a b"])).is_err());
+    assert!(parser::parse_strings(Vec::from(["del a[0]"])).is_err());
+}
+
+#[test]
+fn increment_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a++",
+        "b--",
+    ]);
+    let program_copy = program.clone();
+    let expressions = parser::parse_strings(program);
+    let one = |row| RecExpr {
+        data: RecExprData::Number { number: 1 },
+        span: Span::in_lines(&program_copy, row, 1, 3),
+        generic_data: (),
+    };
+    let expected = Vec::from([
+        BaseExpr {
+            data: BaseExprData::PlusEqualsStatement {
+                var_name: String::from("a"),
+                expr: one(0),
+            },
+            span: Span::in_lines(&program_copy, 0, 0, 3),
+            generic_data: (),
+        },
+        BaseExpr {
+            data: BaseExprData::PlusEqualsStatement {
+                var_name: String::from("b"),
+                expr: RecExpr {
+                    data: RecExprData::Minus {
+                        right: Box::new(one(1)),
+                    },
+                    span: Span::in_lines(&program_copy, 1, 1, 3),
+                    generic_data: (),
+                },
+            },
+            span: Span::in_lines(&program_copy, 1, 0, 3),
+            generic_data: (),
+        },
+    ]);
+
+    compare(expressions, expected, &program_copy);
+
+    // The operators must be directly next to each other and end the statement
+    assert!(parser::parse_strings(Vec::from(["a+ +"])).is_err());
+    assert!(parser::parse_strings(Vec::from(["a+-"])).is_err());
+    assert!(parser::parse_strings(Vec::from(["a++ 1"])).is_err());
+    assert!(parser::parse_strings(Vec::from(["1++"])).is_err());
+}
+
+#[test]
+fn swap_test() {
+    let program = Vec::from(["swap a, b"]);
+    let program_copy = program.clone();
+    let expressions = parser::parse_strings(program);
+    let expected = Vec::from([BaseExpr {
+        data: BaseExprData::Swap {
+            first_name: String::from("a"),
+            second_name: String::from("b"),
+        },
+        span: Span::in_lines(&program_copy, 0, 0, 9),
+        generic_data: (),
+    }]);
+
+    compare(expressions, expected, &program_copy);
+
+    assert!(parser::parse_strings(Vec::from(["swap a"])).is_err());
+    assert!(parser::parse_strings(Vec::from(["swap a b"])).is_err());
+    assert!(parser::parse_strings(Vec::from(["swap a, b, c"])).is_err());
+    assert!(parser::parse_strings(Vec::from(["swap a, b[0]"])).is_err());
+}
+
+#[test]
+fn map_data_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = [1, 2 + 3]",
+        "if a[0] > 0",
+        "    print(a)",
+        "else",
+        "    print(-1)",
+    ]);
+
+    let expressions = parser::parse_strings(program).unwrap();
+
+    // Every expression is numbered, parents before their children
+    let mut counter = 0;
+    let numbered: Vec<BaseExpr<usize>> = expressions
+        .clone()
+        .into_iter()
+        .map(|base_expr| {
+            base_expr.map_data(&mut |_| {
+                counter += 1;
+                counter
+            })
+        })
+        .collect();
+
+    assert_eq!(counter, 20);
+    assert_eq!(numbered[0].generic_data, 1);
+    assert_eq!(numbered[0].span(), expressions[0].span());
+    match &numbered[0].data {
+        BaseExprData::VariableAssignment { expr, .. } => assert_eq!(expr.generic_data, 2),
+        _ => panic!("Expected a variable assignment"),
+    }
+
+    // Mapping back only changes the generic data
+    let unnumbered: Vec<BaseExpr<()>> = numbered
+        .into_iter()
+        .map(|base_expr| base_expr.map_data(&mut |_| ()))
+        .collect();
+    assert_eq!(unnumbered, expressions);
+}
+#[test]
+fn unexpected_end_of_line_test() {
+    #[rustfmt::skip]
+    let programs = Vec::from([
+        ("a = 1 +", "'+'"),
+        ("a = 7 //", "'//'"),
+        ("a = b and", "'and'"),
+        ("print(a) ==", "'=='"),
+    ]);
+
+    for (line, symbol) in programs {
+        match parser::parse_strings(Vec::from([line])) {
+            Err(Error::LocationError { message, .. }) => {
+                assert_eq!(message, format!("Unexpected end of line after {}", symbol))
+            }
+            other => panic!("Expected an error for '{}', got {:?}", line, other),
+        }
+    }
+}
+
+#[test]
+fn for_loop_header_test() {
+    #[rustfmt::skip]
+    let programs = Vec::from([
+        ("for", "Expected a loop variable after 'for', like: for i in 10", 3, 4),
+        ("for 10", "Expected a loop variable after 'for', like: for i in 10", 4, 6),
+        ("for in 10", "Expected a loop variable between 'for' and 'in'", 4, 6),
+        ("for i, j in 10", "Expected a single variable name between 'for' and 'in'", 4, 8),
+        ("for 1 in 10", "Expected a single variable name between 'for' and 'in'", 4, 5),
+        ("for i", "Expected 'in' after the loop variable i", 5, 6),
+        ("for i 10", "Expected 'in' after the loop variable i", 6, 8),
+        ("for i in", "Expected a range or list to loop over after 'in'", 8, 9),
+    ]);
+
+    for (line, expected_message, expected_start, expected_end) in programs {
+        match parser::parse_strings(Vec::from([line, "    a = 1"])) {
+            Err(Error::LocationError {
+                message,
+                span: Span { row, col_start, col_end, .. },
+            }) => {
+                assert_eq!(message, expected_message);
+                assert_eq!((row, col_start, col_end), (0, expected_start, expected_end), "{}", line);
+            }
+            other => panic!("Expected an error for '{}', got {:?}", line, other),
+        }
+    }
+}
+
+#[test]
+fn while_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "while a",
+        "    break",
+    ]);
+    let program_copy = program.clone();
+    let expressions = parser::parse_strings(program);
+    let expected = Vec::from([BaseExpr {
+        data: BaseExprData::WhileLoop {
+            condition: RecExpr {
+                data: RecExprData::Variable {
+                    name: String::from("a"),
+                },
+                span: Span::in_lines(&program_copy, 0, 6, 7),
+                generic_data: (),
+            },
+            body: Vec::from([BaseExpr {
+                data: BaseExprData::Break,
+                span: Span::in_lines(&program_copy, 1, 4, 9),
+                generic_data: (),
+            }]),
+        },
+        span: Span::in_lines(&program_copy, 0, 0, 7),
+        generic_data: (),
+    }]);
+
+    compare(expressions, expected, &program_copy);
+
+    match parser::parse_strings(Vec::from(["while", "    a = 1"])) {
+        Err(Error::LocationError {
+            message,
+            span: Span { col_start, col_end, .. },
+        }) => {
+            assert_eq!(message, "Expected a condition after 'while'");
+            assert_eq!((col_start, col_end), (5, 6));
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn continue_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "for i in 3",
+        "    continue",
+    ]);
+    let expressions = parser::parse_strings(program).unwrap();
+    match &expressions[0].data {
+        BaseExprData::ForLoop { body, .. } => {
+            assert_eq!(body[0].data, BaseExprData::Continue);
+            assert_eq!((body[0].span.row, body[0].span.col_start, body[0].span.col_end), (1, 4, 12));
+        }
+        other => panic!("Expected a for loop, got {:?}", other),
+    }
+
+    match parser::parse_strings(Vec::from(["for i in 3", "    continue 1"])) {
+        Err(Error::LocationError { message, .. }) => {
+            assert_eq!(message, "Unexpected extra tokens on continue statement");
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn misspelled_keyword_test() {
+    #[rustfmt::skip]
+    let programs = Vec::from([
+        (Vec::from(["fun f(x)", "    retrun x"]), "return", 1, 4, 10),
+        (Vec::from(["fnu f(x)", "    a = x"]), "fun", 0, 0, 3),
+        (Vec::from(["if true", "    a = 1", "esle", "    a = 2"]), "else", 2, 0, 4),
+        (Vec::from(["whlie a < 3", "    a += 1"]), "while", 0, 0, 5),
+    ]);
+
+    for (program, keyword, expected_row, expected_col_start, expected_col_end) in programs {
+        match parser::parse_strings(program.clone()) {
+            Err(Error::LocationError {
+                message,
+                span: Span { row, col_start, col_end, .. },
+            }) => {
+                assert_eq!(message, format!("Unknown statement; did you mean '{}'?", keyword));
+                assert_eq!((row, col_start, col_end), (expected_row, expected_col_start, expected_col_end));
+            }
+            other => panic!("Expected an error for {:?}, got {:?}", program, other),
+        }
+    }
+
+    // Lines which parse are left alone, even if the variable looks like a keyword
+    assert!(parser::parse_strings(Vec::from(["esle = 1", "println(esle)"])).is_ok());
+    // So are lines starting with a name which is not close to any keyword
+    match parser::parse_strings(Vec::from(["x y"])) {
+        Err(Error::LocationError { message, .. }) => assert_eq!(message, "No expression found"),
+        other => panic!("Expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn struct_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "struct Point",
+        "    x",
+        "    y",
+        "a = p.x",
+    ]);
+    let expressions = parser::parse_strings(program).unwrap();
+
+    assert_eq!(
+        expressions[0].data,
+        BaseExprData::StructDefinition {
+            name: String::from("Point"),
+            fields: Vec::from([String::from("x"), String::from("y")]),
+        }
+    );
+    match &expressions[1].data {
+        BaseExprData::VariableAssignment { expr, .. } => {
+            assert_eq!(
+                expr.data,
+                RecExprData::Access {
+                    object: String::from("p"),
+                    variable: String::from("x"),
+                }
+            );
+            assert_eq!((expr.span.row, expr.span.col_start, expr.span.col_end), (3, 4, 7));
+        }
+        other => panic!("Expected an assignment, got {:?}", other),
+    }
+
+    #[rustfmt::skip]
+    let invalid_programs = Vec::from([
+        (Vec::from(["struct Point", "    x y"]), "Expected a single field name"),
+        (Vec::from(["struct Point", "    x", "    x"]), "Field x is defined twice"),
+        (Vec::from(["struct Point(x)"]), "Expected a struct definition like: struct Name"),
+    ]);
+    for (program, expected_message) in invalid_programs {
+        match parser::parse_strings(program.clone()) {
+            Err(Error::LocationError { message, .. }) => assert_eq!(message, expected_message),
+            other => panic!("Expected an error for {:?}, got {:?}", program, other),
+        }
+    }
+}
+
+#[test]
+fn field_assignment_test() {
+    let expressions = parser::parse_strings(Vec::from(["point.x = point.y + 1"])).unwrap();
+
+    match &expressions[0].data {
+        BaseExprData::FieldAssignment {
+            object,
+            path,
+            field,
+            expr,
+        } => {
+            assert_eq!((object.as_str(), field.as_str()), ("point", "x"));
+            assert!(path.is_empty());
+            assert!(matches!(expr.data, RecExprData::Add { .. }));
+            assert_eq!((expr.span.row, expr.span.col_start, expr.span.col_end), (0, 10, 21));
+        }
+        other => panic!("Expected a field assignment, got {:?}", other),
+    }
+}
+
+#[test]
+fn dict_test() {
+    let expressions = parser::parse_strings(Vec::from(["ages = {\"ann\": f(1, 2), \"bob\": [3],}"])).unwrap();
+
+    match &expressions[0].data {
+        BaseExprData::VariableAssignment { expr, .. } => match &expr.data {
+            RecExprData::Dict { entries } => {
+                assert_eq!(entries.len(), 2);
+                assert!(matches!(&entries[0].0.data, RecExprData::String { value } if value == "ann"));
+                assert!(matches!(entries[0].1.data, RecExprData::FunctionCall { .. }));
+                assert!(matches!(entries[1].1.data, RecExprData::List { .. }));
+                assert_eq!((expr.span.col_start, expr.span.col_end), (7, 36));
+            }
+            other => panic!("Expected a dict, got {:?}", other),
+        },
+        other => panic!("Expected a variable assignment, got {:?}", other),
+    }
+
+    match parser::parse_strings(Vec::from(["ages = {\"ann\" 31}"])) {
+        Err(Error::LocationError {
+            message,
+            span: Span { col_start, col_end, .. },
+        }) => {
+            assert_eq!(message, "Expected an entry like key: value in the dict");
+            assert_eq!((col_start, col_end), (8, 16));
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn tuple_test() {
+    let expressions = parser::parse_strings(Vec::from([
+        "pair = (1, (\"a\",), f(2, 3))",
+        "grouped = (1 + 2) * 3",
+    ]))
+    .unwrap();
+
+    match &expressions[0].data {
+        BaseExprData::VariableAssignment { expr, .. } => match &expr.data {
+            RecExprData::Tuple { elements } => {
+                assert_eq!(elements.len(), 3);
+                assert!(matches!(elements[0].data, RecExprData::Number { number: 1 }));
+                // A trailing comma makes a tuple of a single element
+                assert!(matches!(&elements[1].data, RecExprData::Tuple { elements } if elements.len() == 1));
+                assert!(matches!(elements[2].data, RecExprData::FunctionCall { .. }));
+                assert_eq!((expr.span.col_start, expr.span.col_end), (7, 27));
+            }
+            other => panic!("Expected a tuple, got {:?}", other),
+        },
+        other => panic!("Expected a variable assignment, got {:?}", other),
+    }
+
+    // Parentheses without a comma only group
+    match &expressions[1].data {
+        BaseExprData::VariableAssignment { expr, .. } => {
+            assert!(matches!(expr.data, RecExprData::Multiply { .. }))
+        }
+        other => panic!("Expected a variable assignment, got {:?}", other),
+    }
+
+    match parser::parse_strings(Vec::from(["pair = (1, , 2)"])) {
+        Err(Error::LocationError { message, span: Span { col_start, .. } }) => {
+            assert_eq!(message, "Expected a value between the commas of the tuple");
+            assert_eq!(col_start, 7);
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn index_assignment_test() {
+    let expressions = parser::parse_strings(Vec::from(["ages[names[0]] = ages[\"ann\"] + 1"])).unwrap();
+
+    match &expressions[0].data {
+        BaseExprData::IndexAssignment {
+            object,
+            path,
+            index,
+            expr,
+        } => {
+            assert_eq!(object, "ages");
+            assert!(path.is_empty());
+            assert!(matches!(index.data, RecExprData::ListAccess { .. }));
+            assert!(matches!(expr.data, RecExprData::Add { .. }));
+        }
+        other => panic!("Expected an index assignment, got {:?}", other),
+    }
+
+    // Comparing an element is not an assignment
+    let expressions = parser::parse_strings(Vec::from(["ages[0] == 1"])).unwrap();
+    assert!(matches!(expressions[0].data, BaseExprData::Simple { .. }));
+}
+
+#[test]
+fn nested_assignment_test() {
+    // The fields and indices before the last one make up the path from the variable
+    let expressions = parser::parse_strings(Vec::from(["grid[row][col + 1] = 0", "shapes[0].center.x = 1.5"])).unwrap();
+
+    match &expressions[0].data {
+        BaseExprData::IndexAssignment {
+            object, path, index, ..
+        } => {
+            assert_eq!(object, "grid");
+            assert!(matches!(path.as_slice(), [Accessor::Index(row)] if matches!(row.data, RecExprData::Variable { .. })));
+            assert!(matches!(index.data, RecExprData::Add { .. }));
+        }
+        other => panic!("Expected an index assignment, got {:?}", other),
+    }
+    match &expressions[1].data {
+        BaseExprData::FieldAssignment {
+            object, path, field, ..
+        } => {
+            assert_eq!((object.as_str(), field.as_str()), ("shapes", "x"));
+            match path.as_slice() {
+                [Accessor::Index(_), Accessor::Field { name, span }] => {
+                    assert_eq!(name, "center");
+                    assert_eq!((span.col_start, span.col_end), (10, 16));
+                }
+                other => panic!("Expected an index and a field, got {:?}", other),
+            }
+        }
+        other => panic!("Expected a field assignment, got {:?}", other),
+    }
+}
+
+#[test]
+fn default_parameter_test() {
+    let expressions = parser::parse_strings(Vec::from([
+        "fun greet(name, greeting = \"hello\", times = 1 + 1,)",
+        "    return greeting",
+    ]))
+    .unwrap();
+
+    match &expressions[0].data {
+        BaseExprData::FunctionDefinition { args, .. } => {
+            assert_eq!(parser::parameter_names(args), ["name", "greeting", "times"]);
+            assert!(args[0].default.is_none());
+            assert!(matches!(&args[1].default, Some(default) if matches!(default.data, RecExprData::String { .. })));
+            assert!(matches!(&args[2].default, Some(default) if matches!(default.data, RecExprData::Add { .. })));
+        }
+        other => panic!("Expected a function definition, got {:?}", other),
+    }
+
+    // A parameter without a default cannot follow one with a default
+    match parser::parse_strings(Vec::from(["fun f(a = 1, b)", "    return b"])) {
+        Err(Error::LocationError { message, span: Span { col_start, .. } }) => {
+            assert_eq!(message, "Parameter b needs a default value, as it comes after a parameter with one");
+            assert_eq!(col_start, 13);
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+
+    // Nor can a default use the other parameters, which have no value yet where it is computed
+    match parser::parse_strings(Vec::from(["fun area(width, height = width * 2)", "    return height"])) {
+        Err(Error::LocationError { message, span: Span { col_start, .. } }) => {
+            assert_eq!(message, "The default value of height cannot use the parameter width");
+            assert_eq!(col_start, 25);
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn keyword_argument_test() {
+    let expressions = parser::parse_strings(Vec::from(["draw(1, y = 2 + 3)"])).unwrap();
+
+    match &expressions[0].data {
+        BaseExprData::Simple { expr } => match &expr.data {
+            RecExprData::FunctionCall { args, .. } => {
+                assert!(matches!(args[0].data, RecExprData::Number { number: 1 }));
+                match &args[1].data {
+                    RecExprData::KeywordArgument { name, value } => {
+                        assert_eq!(name, "y");
+                        assert!(matches!(value.data, RecExprData::Add { .. }));
+                    }
+                    other => panic!("Expected a keyword argument, got {:?}", other),
+                }
+                assert_eq!((args[1].span.col_start, args[1].span.col_end), (8, 17));
+            }
+            other => panic!("Expected a call, got {:?}", other),
+        },
+        other => panic!("Expected an expression, got {:?}", other),
+    }
+
+    // Positional arguments go to the first parameters, so they cannot come after a keyword argument
+    match parser::parse_strings(Vec::from(["draw(x = 1, 2)"])) {
+        Err(Error::LocationError { message, span: Span { col_start, .. } }) => {
+            assert_eq!(message, "A positional argument cannot follow a keyword argument");
+            assert_eq!(col_start, 12);
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+}
+
+
+#[test]
+fn rest_parameter_test() {
+    let expressions = parser::parse_strings(Vec::from(["fun total(label, *numbers)", "    return label"])).unwrap();
+
+    match &expressions[0].data {
+        BaseExprData::FunctionDefinition { args, .. } => {
+            assert_eq!(parser::parameter_names(args), ["label", "numbers"]);
+            assert!(!args[0].rest);
+            assert!(args[1].rest && args[1].default.is_none());
+        }
+        other => panic!("Expected a function definition, got {:?}", other),
+    }
+
+    // The rest parameter collects the arguments after the others, so it must be the last and has no default
+    #[rustfmt::skip]
+    let programs = [
+        ("fun f(*rest, b)", "The parameter *rest collects the remaining arguments, so it must be the last", 13),
+        ("fun f(a, *rest = 1)", "A *rest parameter cannot have a default value", 9),
+    ];
+    for (definition, expected_message, expected_col) in programs {
+        match parser::parse_strings(Vec::from([definition, "    return 1"])) {
+            Err(Error::LocationError { message, span: Span { col_start, .. } }) => {
+                assert_eq!(message, expected_message);
+                assert_eq!(col_start, expected_col);
+            }
+            other => panic!("Expected an error, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn destructuring_assignment_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun minmax(a, b)",
+        "    return a, b",
+        "low, high = minmax(1, 2)",
+    ]);
+    let expressions = parser::parse_strings(program).unwrap();
+
+    // Values separated by commas are returned as a tuple
+    match &expressions[0].data {
+        BaseExprData::FunctionDefinition { body, .. } => match &body[0].data {
+            BaseExprData::Return {
+                return_value: Some(return_value),
+            } => assert!(matches!(&return_value.data, RecExprData::Tuple { elements } if elements.len() == 2)),
+            other => panic!("Expected a return, got {:?}", other),
+        },
+        other => panic!("Expected a function definition, got {:?}", other),
+    }
+    match &expressions[1].data {
+        BaseExprData::DestructuringAssignment { var_names, expr } => {
+            assert_eq!(var_names, &["low", "high"]);
+            assert!(matches!(expr.data, RecExprData::FunctionCall { .. }));
+        }
+        other => panic!("Expected a destructuring assignment, got {:?}", other),
+    }
+
+    // A variable can only be given one of the values
+    match parser::parse_strings(Vec::from(["a, a = 1, 2"])) {
+        Err(Error::LocationError { message, .. }) => {
+            assert_eq!(message, "The variable a is assigned twice in the same assignment")
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn conditional_expression_test() {
+    let expressions = parser::parse_strings(Vec::from(["sign = -1 if n < 0 else 0 if n == 0 else 1"])).unwrap();
+
+    // The value after else holds the next conditional, and the condition is what is between if and else
+    match &expressions[0].data {
+        BaseExprData::VariableAssignment { expr, .. } => match &expr.data {
+            RecExprData::Conditional {
+                condition,
+                then_value,
+                else_value,
+            } => {
+                assert!(matches!(condition.data, RecExprData::LessThan { .. }));
+                assert!(matches!(then_value.data, RecExprData::Minus { .. }));
+                assert!(matches!(else_value.data, RecExprData::Conditional { .. }));
+            }
+            other => panic!("Expected a conditional, got {:?}", other),
+        },
+        other => panic!("Expected a variable assignment, got {:?}", other),
+    }
+
+    #[rustfmt::skip]
+    let programs = [
+        ("x = 1 if y", "Expected else and a value after the condition, as in a if condition else b"),
+        ("x = 1 if else 2", "Expected a condition between if and else, and a value after else"),
+        ("x = 1 if y else", "Expected a condition between if and else, and a value after else"),
+    ];
+    for (line, expected_message) in programs {
+        match parser::parse_strings(Vec::from([line])) {
+            Err(Error::LocationError { message, span: Span { col_start, .. } }) => {
+                assert_eq!(message, expected_message);
+                assert_eq!(col_start, 6);
+            }
+            other => panic!("Expected an error for {}, got {:?}", line, other),
+        }
+    }
+}
+
+#[test]
+fn slice_test() {
+    let expressions = parser::parse_strings(Vec::from(["a = xs[1:n + 1]", "b = xs[:2]", "c = \"text\"[2:]"])).unwrap();
+
+    // Each bound is a whole expression, and a bound which is left out is None
+    let bounds: Vec<(bool, bool)> = expressions
+        .iter()
+        .map(|expression| match &expression.data {
+            BaseExprData::VariableAssignment { expr, .. } => match &expr.data {
+                RecExprData::Slice { start, end, .. } => (start.is_some(), end.is_some()),
+                other => panic!("Expected a slice, got {:?}", other),
+            },
+            other => panic!("Expected a variable assignment, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(bounds, [(true, true), (false, true), (true, false)]);
+
+    match &expressions[0].data {
+        BaseExprData::VariableAssignment { expr, .. } => match &expr.data {
+            RecExprData::Slice { end: Some(end), .. } => assert!(matches!(end.data, RecExprData::Add { .. })),
+            other => panic!("Expected a slice with an end, got {:?}", other),
+        },
+        other => panic!("Expected a variable assignment, got {:?}", other),
+    }
+
+    match parser::parse_strings(Vec::from(["a = xs[1:4:2]"])) {
+        Err(Error::LocationError { message, span: Span { col_start, .. } }) => {
+            assert_eq!(message, "A slice has a start and an end, as in xs[1:4], not a step");
+            assert_eq!(col_start, 10);
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn assignment_in_condition_test() {
+    #[rustfmt::skip]
+    let programs = Vec::from([
+        (Vec::from(["if x = 5", "    a = 1"]), 0, 5),
+        (Vec::from(["if x == 5", "    a = 1", "else if x = 6", "    a = 2"]), 2, 10),
+        (Vec::from(["while (x + 1) = 5", "    a = 1"]), 0, 14),
+    ]);
+
+    for (program, expected_row, expected_col) in programs {
+        match parser::parse_strings(program.clone()) {
+            Err(Error::LocationError {
+                message,
+                span: Span { row, col_start, col_end, .. },
+            }) => {
+                assert_eq!(message, "Use == to compare in a condition, = assigns a variable");
+                assert_eq!((row, col_start, col_end), (expected_row, expected_col, expected_col + 1));
+            }
+            other => panic!("Expected an error for {:?}, got {:?}", program, other),
+        }
+    }
+
+    // An = inside brackets belongs to a nested expression, and is not mistaken for a comparison
+    assert!(!matches!(
+        parser::parse_strings(Vec::from(["if f(x = 5)", "    a = 1"])),
+        Err(Error::LocationError { message, .. }) if message.starts_with("Use ==")
+    ));
+}
+
+#[test]
+fn expressions_to_string_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun add(a, b)",
+        "    return a + b",
+        "x = add(1, [2])",
+    ]);
+
+    let expressions = parser::parse_strings(program).unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        "fun add(a, b)",
+        "  Return((Var(\"a\") + Var(\"b\")))",
+        "VarAssign(\"x\", Call(\"add\" with (Num(1), [Num(2), ], )))",
+        "",
+    ].join("\n");
+    assert_eq!(parser::expressions_to_string(&expressions), expected);
+}
 /*
 #[test]
 fn if_statements_test_small() {
@@ -0,0 +1,204 @@
+#![cfg(feature = "core")]
+
+use rosy::difftest;
+use rosy::tokenizer::Span;
+use rosy::difftest::Backend;
+use rosy::difftest::DiffOptions;
+use rosy::difftest::Outcome;
+use rosy::language::LanguageOptions;
+use rosy::optimiser::OptimisationOptions;
+use rosy::parser;
+use rosy::pipeline;
+use rosy::pytranspiler;
+use rosy::tokenizer::Error;
+use rosy::typechecker;
+
+#[test]
+fn typed_program_to_python_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun half(value)",
+        "    return value / 2",
+        "println(half(7))",
+        "println(half(7.0))",
+        "xs = [1, 2]",
+        "for x in 2",
+        "    xs[x] = half(x)",
+        "if xs[0] > 0",
+        "    println(xs[0] + xs[1])",
+    ]);
+
+    let python = pipeline::run_emit_python_pipeline(program).unwrap();
+
+    // Integer division rounds towards zero, the float instance gets its own function and floats print as in rosy,
+    // and an assigned element changes a copy of the list
+    #[rustfmt::skip]
+    let expected = [
+        "# Dividing two integers rounds towards zero in rosy, where // rounds down",
+        "def truncated_division(a, b):",
+        "    quotient = abs(a) // abs(b)",
+        "    return quotient if (a < 0) == (b < 0) else -quotient",
+        "",
+        "",
+        "# Floats are written as rosy writes them: 1e20 and 1.5e-5 instead of 1e+20 and 1.5e-05",
+        "def format_float(value):",
+        "    if value != value:",
+        "        return \"NaN\"",
+        "    mantissa, e, exponent = repr(value).partition(\"e\")",
+        "    return mantissa + e + (str(int(exponent)) if e else \"\")",
+        "",
+        "",
+        "def half(value):",
+        "    return truncated_division(value, 2)",
+        "",
+        "",
+        "def half_2(value):",
+        "    return value / 2",
+        "",
+        "",
+        "print(half(7))",
+        "print(format_float(half_2(7.0)))",
+        "xs = [1, 2]",
+        "for x in range(2):",
+        "    xs = xs.copy()",
+        "    xs[x] = half(x)",
+        "if xs[0] > 0:",
+        "    print(xs[0] + xs[1])",
+        "",
+    ].join("\n");
+    assert_eq!(python, expected);
+}
+
+#[test]
+fn python_structs_and_names_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "struct Point",
+        "    x",
+        "    y",
+        "fun count(values)",
+        "    pass = 0",
+        "    for value in values",
+        "        pass = pass + 1",
+        "    return pass",
+        "p = Point(count([1]), 2)",
+        "p.x = 3",
+        "print(p.x == 3)",
+        "println(\"!\")",
+    ]);
+
+    let python = pipeline::run_emit_python_pipeline(program).unwrap();
+
+    // Names Python reserves get an underscore, structs are dataclasses and a field is assigned on a copy
+    assert!(python.starts_with("from dataclasses import dataclass, replace\n"));
+    assert!(python.contains("        pass_ = pass_ + 1\n"));
+    assert!(python.contains("@dataclass\nclass Point:\n    x: object\n    y: object\n"));
+    assert!(python.contains("p = replace(p, x=3)\n"));
+    assert!(python.contains("print(str(p.x == 3).lower(), end=\"\")\nprint(\"!\")\n"));
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn unsupported_python_test() {
+    let program = parser::parse_strings(Vec::from(["text = read_file(\"a.txt\")"])).unwrap();
+    let typed_program = typechecker::type_check_program(program, false).unwrap();
+
+    match pytranspiler::typed_program_to_python(&typed_program) {
+        Err(Error::LocationError { message, span: Span { col_start, .. } }) => {
+            assert_eq!(message, "The builtin read_file cannot be written as Python");
+            assert_eq!(col_start, 7);
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn python_float_printing_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "println(1e20)",
+        "println(-1e20)",
+        "println(1e16)",
+        "println(9900000000000000.0)",
+        "println(-0.0)",
+        "println(0.0001)",
+        "println(1.5e-5)",
+        "println(\"x: \", 2.5e-7)",
+    ]);
+
+    // Python is not installed everywhere the tests run
+    if !difftest::available_backends().contains(&Backend::Python) {
+        return;
+    }
+    let options = DiffOptions {
+        backends: Vec::from([Backend::Interpreter, Backend::Python]),
+        language: LanguageOptions::default(),
+        optimisation: OptimisationOptions::default(),
+    };
+    let (reference, comparisons) = difftest::compare_backends(program, &options).unwrap();
+
+    assert_eq!(reference.output, "1e20\n-1e20\n1e16\n9900000000000000.0\n-0.0\n0.0001\n1.5e-5\nx: 2.5e-7\n");
+    assert_eq!(comparisons[0].outcome, Outcome::Same);
+}
+
+#[test]
+fn python_closure_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun outer(x)",
+        "    fun inner(y)",
+        "        return x + y",
+        "    x = x * 2",
+        "    return inner(10)",
+        "println(outer(1))",
+    ]);
+
+    let python = pipeline::run_emit_python_pipeline(program.clone()).unwrap();
+
+    // The captured variable is bound where the function is defined, so the later assignment does not change it
+    assert!(python.starts_with("from functools import partial\n"));
+    assert!(python.contains("def inner(y, x):\n"));
+    assert!(python.contains("    inner_1_4 = partial(inner, x=x)\n    x = x * 2\n    return inner_1_4(10)\n"));
+
+    if !difftest::available_backends().contains(&Backend::Python) {
+        return;
+    }
+    let options = DiffOptions {
+        backends: Vec::from([Backend::Interpreter, Backend::Python]),
+        language: LanguageOptions::default(),
+        optimisation: OptimisationOptions::default(),
+    };
+    let (reference, comparisons) = difftest::compare_backends(program, &options).unwrap();
+
+    assert_eq!(reference.output, "11\n");
+    assert_eq!(comparisons[0].outcome, Outcome::Same);
+}
+
+#[test]
+fn python_negative_power_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun power(base, exponent)",
+        "    return base ^ exponent",
+        "println(power(3, 4))",
+        "println(power(2, -1))",
+    ]);
+
+    let python = pipeline::run_emit_python_pipeline(program.clone()).unwrap();
+    assert!(python.contains("    return integer_power(base, exponent)\n"));
+
+    if !difftest::available_backends().contains(&Backend::Python) {
+        return;
+    }
+    let options = DiffOptions {
+        backends: Vec::from([Backend::Interpreter, Backend::Python]),
+        language: LanguageOptions::default(),
+        optimisation: OptimisationOptions::default(),
+    };
+
+    // Like the interpreter, a negative exponent is an error instead of the float 0.5
+    let (reference, comparisons) = difftest::compare_backends(program, &options).unwrap();
+    assert_eq!(reference.output, "81\n");
+    assert_eq!(reference.exit_code, 1);
+    assert_eq!(comparisons[0].outcome, Outcome::Same);
+}
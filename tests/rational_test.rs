@@ -0,0 +1,33 @@
+use rosy::rational::Rational;
+
+#[test]
+fn normalized_representation() {
+    let value = Rational::new(6, -4).unwrap();
+    assert_eq!(value.numerator(), -3);
+    assert_eq!(value.denominator(), 2);
+    assert_eq!(value.to_string(), "-3/2");
+
+    assert_eq!(Rational::new(4, 2).unwrap().to_string(), "2");
+    assert_eq!(Rational::new(0, -5).unwrap(), Rational::from_integer(0));
+    assert!(Rational::new(1, 0).is_err());
+}
+
+#[test]
+fn exact_arithmetic() {
+    let third = Rational::new(1, 3).unwrap();
+    let sum = third.checked_add(third).unwrap().checked_add(third).unwrap();
+    assert_eq!(sum, Rational::from_integer(1));
+
+    let quotient = third.checked_div(Rational::new(2, 9).unwrap()).unwrap();
+    assert_eq!(quotient, Rational::new(3, 2).unwrap());
+
+    assert!(third < Rational::new(1, 2).unwrap());
+}
+
+#[test]
+fn overflow_is_an_error() {
+    let large = Rational::from_integer(i64::MAX);
+    assert!(large.checked_add(Rational::from_integer(1)).is_err());
+    assert!(Rational::from_integer(i64::MIN).checked_neg().is_err());
+    assert!(Rational::new(i64::MIN, -1).is_err());
+}
@@ -0,0 +1,95 @@
+use rosy_rt::error::located_message;
+use rosy_rt::error::span_marker;
+use rosy_rt::format::format_float;
+use rosy_rt::format::format_integer;
+use rosy_rt::format::format_list;
+use rosy_rt::format::format_tuple;
+use rosy_rt::index::check_index;
+use rosy_rt::index::check_slice;
+use rosy_rt::index::clamp_slice;
+use rosy_rt::native::link;
+use rosy_rt::native::INTEGER_OUTPUT;
+use rosy_rt::text;
+
+#[test]
+fn format_values() {
+    assert_eq!(format_integer(-9223372036854775808), "-9223372036854775808");
+    assert_eq!(format_float(1.0), "1.0");
+    assert_eq!(format_list(&["1".to_string(), "2".to_string()]), "[1, 2]");
+    assert_eq!(format_list(&[]), "[]");
+    assert_eq!(format_tuple(&["1".to_string()]), "(1,)");
+    assert_eq!(format_tuple(&["1".to_string(), "true".to_string()]), "(1, true)");
+}
+
+#[test]
+fn index_checks() {
+    assert_eq!(check_index(2, 3, "list"), Ok(2));
+    assert_eq!(check_index(3, 3, "list"), Err("Index 3 out of bounds for list of length 3".to_string()));
+    assert_eq!(check_index(-1, 3, "tuple"), Err("Index -1 out of bounds for tuple of length 3".to_string()));
+    assert_eq!(check_slice(1, 3, 3, "bytes"), Ok(1..3));
+    assert_eq!(check_slice(2, 1, 3, "bytes"), Err("Cannot slice from 2 to 1 in bytes of length 3".to_string()));
+    assert_eq!(clamp_slice(Some(-2), Some(10), 3), 0..3);
+    assert_eq!(clamp_slice(None, Some(1), 3), 0..1);
+    assert_eq!(clamp_slice(Some(2), None, 3), 2..3);
+    assert_eq!(clamp_slice(Some(3), Some(1), 5), 3..3);
+}
+
+#[test]
+fn text_operations() {
+    assert_eq!(text::find("héllo", "llo"), 2);
+    assert_eq!(text::find("hello", "x"), -1);
+    assert_eq!(text::slice("héllo", 1, 3), Ok("él".to_string()));
+    assert_eq!(text::pad_left("7", 3, '0'), Ok("007".to_string()));
+    assert_eq!(text::pad_right("ab", 1, ' '), Ok("ab".to_string()));
+    assert_eq!(text::repeat("ab", -1), Err("Cannot repeat a string -1 times".to_string()));
+    // Results which would not fit in memory are errors instead of aborting the process
+    assert_eq!(
+        text::repeat("ab", i64::MAX),
+        Err("Cannot repeat a string 9223372036854775807 times, as the result would be too large".to_string())
+    );
+    assert_eq!(
+        text::pad_left("7", i64::MAX, '0'),
+        Err("Cannot pad a string to a width of 9223372036854775807, as the result would be too large".to_string())
+    );
+    assert!(text::pad_right("7", i64::MAX, ' ').is_err());
+}
+
+#[test]
+fn error_messages() {
+    assert_eq!(located_message("Error", "Unknown name", 0, 4), "Error: Unknown name (line 1, col 5)");
+    assert_eq!(span_marker(2, 5), "  ^^^");
+    assert_eq!(span_marker(5, 2), "     ");
+}
+
+#[test]
+fn native_imports_point_at_calls() {
+    for (_, at) in INTEGER_OUTPUT.imports {
+        assert_eq!(INTEGER_OUTPUT.code[at - 2..*at], [0xFF, 0x15]);
+    }
+}
+
+#[test]
+fn link_turns_runtime_calls_into_direct_calls() {
+    // call [rip + 0] to println, then to ExitProcess
+    let mut machine_code = vec![0xFF, 0x15, 0, 0, 0, 0, 0xFF, 0x15, 0, 0, 0, 0];
+    let mut extern_calls = vec![("rosy_rt_println_int".to_string(), 2), ("ExitProcess".to_string(), 8)];
+
+    link(&mut machine_code, &mut extern_calls);
+
+    // The routine is added once, at the next multiple of 16
+    assert_eq!(machine_code.len(), 16 + INTEGER_OUTPUT.code.len());
+    assert_eq!(machine_code[0], 0xE8);
+    let offset = i32::from_le_bytes(machine_code[1..5].try_into().unwrap());
+    assert_eq!(5 + offset as usize, 16 + 4);
+    assert_eq!(machine_code[5], 0x90);
+
+    // The calls of the routine are left for the executable writer, next to the other extern calls
+    assert_eq!(
+        extern_calls,
+        vec![
+            ("GetStdHandle".to_string(), 16 + 91),
+            ("WriteFile".to_string(), 16 + 121),
+            ("ExitProcess".to_string(), 8),
+        ]
+    );
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "core")]
+
+use rosy::engine::Engine;
+use rosy::scheduler::Scheduler;
+
+#[test]
+fn scripts_are_interleaved() {
+    let engine = Engine::new();
+    let mut scheduler = Scheduler::new(2);
+
+    #[rustfmt::skip]
+    let first = scheduler.add(engine.load(Vec::from([
+        "a = 1",
+        "println(a)",
+        "a = a + 1",
+        "println(a)",
+    ])).unwrap());
+
+    #[rustfmt::skip]
+    let second = scheduler.add(engine.load(Vec::from([
+        "println(\"b\")",
+    ])).unwrap());
+
+    assert_eq!(scheduler.tick(), 1);
+    assert_eq!(scheduler.terminal(first), &vec!["1".to_string(), String::new()]);
+    assert_eq!(scheduler.terminal(second), &vec!["b".to_string(), String::new()]);
+
+    assert_eq!(scheduler.tick(), 0);
+    assert_eq!(
+        scheduler.terminal(first),
+        &vec!["1".to_string(), "2".to_string(), String::new()]
+    );
+}
+
+#[test]
+fn loops_are_interleaved() {
+    let engine = Engine::new();
+    let mut scheduler = Scheduler::new(2);
+
+    #[rustfmt::skip]
+    let first = scheduler.add(engine.load(Vec::from([
+        "fun count(name, n)",
+        "    for i in n",
+        "        println(name, i)",
+        "count(\"a\", 3)",
+    ])).unwrap());
+
+    #[rustfmt::skip]
+    let second = scheduler.add(engine.load(Vec::from([
+        "i = 0",
+        "while i < 3",
+        "    println(\"b\", i)",
+        "    i = i + 1",
+    ])).unwrap());
+
+    // The definition and call of count, and the assignment and the start of the while loop
+    assert_eq!(scheduler.tick(), 2);
+    assert_eq!(scheduler.terminal(first), &vec![String::new()]);
+    assert_eq!(scheduler.terminal(second), &vec![String::new()]);
+
+    // The start of the for loop in count and its first iteration, and one iteration of the while loop
+    assert_eq!(scheduler.tick(), 2);
+    assert_eq!(scheduler.terminal(first), &vec!["a0".to_string(), String::new()]);
+    assert_eq!(scheduler.terminal(second), &vec!["b0".to_string(), String::new()]);
+
+    assert_eq!(scheduler.tick(), 1);
+    assert_eq!(
+        scheduler.terminal(first),
+        &vec!["a0".to_string(), "a1".to_string(), "a2".to_string(), String::new()]
+    );
+    assert_eq!(scheduler.terminal(second), &vec!["b0".to_string(), "b1".to_string(), String::new()]);
+
+    scheduler.run_until_finished();
+    assert_eq!(
+        scheduler.terminal(second),
+        &vec!["b0".to_string(), "b1".to_string(), "b2".to_string(), String::new()]
+    );
+}
+
+#[test]
+fn failing_script_does_not_stop_others() {
+    let engine = Engine::new();
+    let mut scheduler = Scheduler::new(1);
+
+    let failing = scheduler.add(engine.load(Vec::from(["println(missing)", "println(1)"])).unwrap());
+    let working = scheduler.add(engine.load(Vec::from(["println(1)", "println(2)"])).unwrap());
+
+    scheduler.run_until_finished();
+
+    assert!(scheduler.error(failing).is_some());
+    assert_eq!(scheduler.terminal(failing), &vec![String::new()]);
+    assert!(scheduler.error(working).is_none());
+    assert_eq!(
+        scheduler.terminal(working),
+        &vec!["1".to_string(), "2".to_string(), String::new()]
+    );
+}
@@ -0,0 +1,43 @@
+use rosy::interpreter::Value;
+use rosy::smallstring::{SmallString, INLINE_CAPACITY};
+
+#[test]
+fn inline_and_heap_test() {
+    let short = SmallString::from("hello");
+    assert!(short.is_inline());
+    assert_eq!(short.as_str(), "hello");
+
+    let longest_inline = "a".repeat(INLINE_CAPACITY);
+    assert!(SmallString::from(longest_inline.as_str()).is_inline());
+
+    let long = SmallString::from("a".repeat(INLINE_CAPACITY + 1));
+    assert!(!long.is_inline());
+    assert_eq!(long.len(), INLINE_CAPACITY + 1);
+
+    // Multi-byte characters count by their bytes
+    let text = "ĳ".repeat(INLINE_CAPACITY / 2);
+    assert_eq!(SmallString::from(text.as_str()).as_str(), text);
+
+    // Equal text is equal however it is stored
+    assert_eq!(SmallString::from("abc"), SmallString::Heap(String::from("abc")));
+    assert!(SmallString::from("abc") < SmallString::from("abd"));
+    assert_eq!(format!("{} {:?}", short, short), "hello \"hello\"");
+
+    // A string value is no larger than it was with String
+    assert!(std::mem::size_of::<Value>() <= 72);
+}
+
+#[test]
+fn push_str_test() {
+    let mut text = SmallString::new();
+    text.push_str("hello");
+    text.push_str(", world");
+    assert!(text.is_inline());
+    assert_eq!(text, "hello, world");
+
+    // Growing past the inline capacity moves the text to the heap
+    text.push_str(&"!".repeat(INLINE_CAPACITY));
+    assert!(!text.is_inline());
+    assert_eq!(text.as_str(), format!("hello, world{}", "!".repeat(INLINE_CAPACITY)));
+    assert_eq!(String::from(text.clone()), text.as_str());
+}
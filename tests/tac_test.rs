@@ -0,0 +1,134 @@
+use rosy::compiler::Target;
+use rosy::tokenizer::Span;
+use rosy::desugarer;
+use rosy::language::LanguageOptions;
+use rosy::optimiser::OptimisationOptions;
+use rosy::parser;
+use rosy::pipeline;
+use rosy::tac::{self, TacInstruction};
+use rosy::tokenizer::Error;
+use rosy::typechecker;
+
+fn generate(program: Vec<&str>) -> Result<Vec<TacInstruction>, Error> {
+    let parsed = parser::parse_strings(program).unwrap();
+    let (typed, functions) = typechecker::type_check_program(desugarer::desugar(parsed), false).unwrap();
+    tac::generate_tac(typed, functions)
+}
+
+fn function_labels(instructions: &[TacInstruction]) -> Vec<String> {
+    instructions
+        .iter()
+        .filter_map(|instruction| match instruction {
+            TacInstruction::FunctionLabel(label, _) => Some(label.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn called_labels(instructions: &[TacInstruction]) -> Vec<String> {
+    instructions
+        .iter()
+        .filter_map(|instruction| match instruction {
+            TacInstruction::Call(label, _, _) => Some(label.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn direct_calls_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun id(x)",
+        "    return x",
+        "fun increment(x)",
+        "    println(x)",
+        "    return add_one(x)",
+        "fun add_one(x)",
+        "    return x + 1",
+        "a = id(1)",
+        "b = id(true)",
+        "c = increment(a)",
+    ]);
+
+    let instructions = generate(program).unwrap();
+
+    // Every instance of id gets its own label, and add_one is known although it is only called inside increment
+    let mut labels = function_labels(&instructions);
+    labels.sort();
+    assert_eq!(labels, Vec::from(["func_add_one", "func_id_0", "func_id_1", "func_increment", "func_println_int"]));
+    assert_eq!(
+        called_labels(&instructions),
+        Vec::from(["func_println_int", "func_add_one", "func_id_0", "func_id_1", "func_increment"])
+    );
+}
+
+#[test]
+fn unresolved_call_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun f(x)",
+        "    return x",
+        "a = f(1)",
+    ]);
+
+    // Without the function instances from the typechecker the call cannot be resolved
+    let parsed = parser::parse_strings(program).unwrap();
+    let (typed, _) = typechecker::type_check_program(desugarer::desugar(parsed), false).unwrap();
+    match tac::generate_tac(typed, Vec::new()) {
+        Err(Error::LocationError {
+            message,
+            span: Span { row, col_start, col_end, .. },
+        }) => {
+            assert_eq!(message, "Cannot resolve the call to f(int) at compile time");
+            assert_eq!((row, col_start, col_end), (2, 4, 8));
+        }
+        other => panic!("Expected an error, got {:?}", other.map(|_| ())),
+    }
+}
+
+// The error of compiling the program, which must fail before anything is written
+fn compile_error(program: Vec<&str>) -> Error {
+    let output_path = std::env::temp_dir().join("rosy_tac_test_unsupported");
+    let optimisation = OptimisationOptions::default();
+    match pipeline::build_executable(program, &output_path, &LanguageOptions::default(), Target::Linux, &optimisation) {
+        Err(error) => error,
+        Ok(()) => panic!("Expected the program not to compile"),
+    }
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn destructuring_is_not_supported_test() {
+    #[rustfmt::skip]
+    let programs = [
+        Vec::from(["x, y = (1, 2)", "print(x)"]),
+        Vec::from(["fun f()", "    return (1, 2)", "a, b = f()", "print(a)"]),
+    ];
+
+    for program in programs {
+        let error = compile_error(program);
+        assert_eq!(error.message(), "Assigning to several variables at once is not supported by the compiler");
+        assert!(error.span().is_some());
+    }
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn closures_are_not_supported_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun outer(n)",
+        "    fun inner(k)",
+        "        return k + n",
+        "    return inner(1)",
+        "print(outer(2))",
+    ]);
+
+    // The error is at the variable the nested function reads from the function around it
+    let error = compile_error(program);
+    assert_eq!(error.message(), "Closures are not supported by the compiler");
+    let span = error.span().unwrap();
+    assert_eq!((span.row, span.col_start), (2, 19));
+}
@@ -0,0 +1,137 @@
+use rosy::codegenerator::Argument;
+use rosy::tokenizer::Span;
+use rosy::codegenerator::Instruction;
+use rosy::codegenerator::Register;
+use rosy::codegenerator::RegisterSize;
+use rosy::pipeline;
+use rosy::tac::BinOp;
+use rosy::tac::ComparisonOp;
+use rosy::tac::TacInstruction;
+use rosy::tac::TacValue;
+use rosy::tac::UnOp;
+use rosy::tac::VariableValue;
+use rosy::tacformat;
+use rosy::tokenizer::Error;
+
+fn var(name: &str) -> VariableValue {
+    VariableValue::Variable(name.to_string())
+}
+
+fn value(name: &str) -> TacValue {
+    TacValue::Variable(name.to_string())
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn compiled_program_roundtrip_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun add(a, b)",
+        "\treturn a + b",
+        "total = 0",
+        "i = 0",
+        "while i < 10",
+        "\tif i == 3",
+        "\t\ttotal = add(total, 0 - i)",
+        "\telse",
+        "\t\ttotal = add(total, i)",
+        "\ti = i + 1",
+        "print(total)",
+    ]);
+
+    let text = pipeline::run_emit_ir_pipeline(program).unwrap();
+    let instructions = tacformat::parse_instructions(&text).unwrap();
+
+    // The default print function is written with assembly instructions
+    assert!(instructions
+        .iter()
+        .any(|instruction| matches!(instruction, TacInstruction::DirectInstruction(_))));
+    assert_eq!(tacformat::instructions_to_text(&instructions), text);
+}
+
+#[test]
+fn parse_instructions_test() {
+    #[rustfmt::skip]
+    let text = [
+        "fun func_sub(a, b) ; subtracts b from a",
+        "    %t0 = %a - -1",
+        "    %t1 = neg %t0",
+        "    return %t1",
+        "start",
+        "    %x@1 = call func_sub(5, %y)",
+        "    %l = list [1, \"a \\\"quoted\\\" text\", {x: 2}]",
+        "    if %l[0] >= -2 goto L0",
+        "    extern ExitProcess(0)",
+        "L0:",
+        "    asm mov [rsp + 8], r8d",
+        "    asm label loop 2",
+        "    asm jne loop",
+    ].join("\n");
+
+    let expected = vec![
+        TacInstruction::FunctionLabel("func_sub".to_string(), vec!["a".to_string(), "b".to_string()]),
+        TacInstruction::BinOp(var("t0"), value("a"), BinOp::Sub, TacValue::Constant(-1)),
+        TacInstruction::UnaryOp(var("t1"), UnOp::Neg, value("t0")),
+        TacInstruction::Return(Some(value("t1"))),
+        TacInstruction::ProgramStart(),
+        TacInstruction::Call(
+            "func_sub".to_string(),
+            vec![TacValue::Constant(5), value("y")],
+            Some(VariableValue::VariableWithRequestedRegister("x".to_string(), 1)),
+        ),
+        TacInstruction::InstantiateList(
+            var("l"),
+            vec![
+                TacValue::Constant(1),
+                TacValue::StringLiteral("a \"quoted\" text".to_string()),
+                TacValue::Struct(vec![("x".to_string(), TacValue::Constant(2))]),
+            ],
+        ),
+        TacInstruction::CompareAndGoto(
+            TacValue::ListAccess {
+                list_variable: "l".to_string(),
+                index: Box::new(TacValue::Constant(0)),
+            },
+            TacValue::Constant(-2),
+            ComparisonOp::Ge,
+            "L0".to_string(),
+        ),
+        TacInstruction::ExternCall("ExitProcess".to_string(), vec![TacValue::Constant(0)], None),
+        TacInstruction::Label("L0".to_string()),
+        TacInstruction::DirectInstruction(Instruction::Mov(
+            Argument::StackMemoryOffsetDirect(8),
+            Argument::Register(Register::Extended(8, RegisterSize::DoubleWord)),
+        )),
+        TacInstruction::DirectInstruction(Instruction::Label("loop".to_string(), Some(2))),
+        TacInstruction::DirectInstruction(Instruction::Jne("loop".to_string())),
+    ];
+
+    let instructions = tacformat::parse_instructions(&text).unwrap();
+    assert_eq!(instructions, expected);
+    // Registers keep their size when written back
+    assert!(tacformat::instructions_to_text(&instructions).contains("    asm mov [rsp + 8], r8d\n"));
+}
+
+#[test]
+fn parse_instructions_errors_test() {
+    let cases = [
+        ("start\n    goto L3", "Unknown label L3", 1, 4),
+        ("start\n    %x = %y ? 1", "Unexpected character ?", 1, 12),
+        ("start\n    jump L0", "Unknown instruction jump", 1, 4),
+        ("start\n    asm mov rax, rzx", "rzx is not a register", 1, 17),
+        ("L0:\nL0:", "The label L0 is defined more than once", 1, 0),
+    ];
+
+    for (text, expected_message, expected_row, expected_col) in cases {
+        match tacformat::parse_instructions(text) {
+            Err(Error::LocationError {
+                message,
+                span: Span { row, col_start, .. },
+            }) => {
+                assert_eq!(message, expected_message);
+                assert_eq!((row, col_start), (expected_row, expected_col));
+            }
+            other => panic!("Expected an error for {:?}, got {:?}", text, other),
+        }
+    }
+}
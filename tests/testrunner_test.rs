@@ -0,0 +1,131 @@
+use rosy::engine::Engine;
+use rosy::testrunner;
+use rosy::testrunner::TestOptions;
+use rosy::tokenizer::Error;
+
+fn error_message(error: &Option<Error>) -> String {
+    match error {
+        Some(Error::LocationError { message, .. }) => message.clone(),
+        other => panic!("Expected a located error, found {:?}", other),
+    }
+}
+
+#[cfg(all(feature = "core", feature = "testing"))]
+#[test]
+fn run_test_functions() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "base = 10",
+        "fun add_base(value)",
+        "    return value + base",
+        "fun test_add()",
+        "    assert_eq(add_base(1), 11)",
+        "fun test_wrong()",
+        "    assert_eq(add_base(1), 12)",
+        "fun helper()",
+        "    assert_eq(1, 2)",
+    ]);
+
+    let outcomes = testrunner::run_tests(&Engine::new(), program, &TestOptions::default()).unwrap();
+
+    // Only functions starting with test_ are run, in the order they are defined
+    let names: Vec<&str> = outcomes
+        .iter()
+        .map(|outcome| outcome.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["test_add", "test_wrong"]);
+
+    assert!(outcomes[0].passed());
+    assert_eq!(
+        error_message(&outcomes[1].error),
+        "assert_eq failed: expected 12, found 11"
+    );
+}
+
+#[cfg(all(feature = "core", feature = "testing"))]
+#[test]
+fn assert_eq_list_differences() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun test_nested()",
+        "    assert_eq([[1, 2], [3]], [[1, 5], [3, 4]])",
+        "fun test_longer()",
+        "    assert_eq([\"a\", \"b\"], [\"a\"])",
+    ]);
+
+    let outcomes = testrunner::run_tests(&Engine::new(), program, &TestOptions::default()).unwrap();
+
+    assert_eq!(
+        error_message(&outcomes[0].error),
+        "assert_eq failed with 2 differences:\n    at [0][1]: expected 5, found 2\n    at [1][1]: missing 4"
+    );
+    assert_eq!(
+        error_message(&outcomes[1].error),
+        "assert_eq failed: at [1]: unexpected \"b\""
+    );
+}
+
+#[cfg(all(feature = "core", feature = "testing"))]
+#[test]
+fn parameterized_tests() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "test_add_cases = [[1, 2, 3], [2, 2, 5], [0, 0, 0]]",
+        "fun test_add(a, b, sum)",
+        "    assert_eq(a + b, sum)",
+        "fun test_missing_cases(a)",
+        "    assert_eq(a, a)",
+    ]);
+
+    let outcomes = testrunner::run_tests(&Engine::new(), program, &TestOptions::default()).unwrap();
+
+    let names: Vec<&str> = outcomes
+        .iter()
+        .map(|outcome| outcome.name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "test_add[0]",
+            "test_add[1]",
+            "test_add[2]",
+            "test_missing_cases"
+        ]
+    );
+
+    let passed: Vec<bool> = outcomes.iter().map(|outcome| outcome.passed()).collect();
+    assert_eq!(passed, vec![true, false, true, false]);
+}
+
+#[test]
+fn filter_and_fail_fast() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun test_first()",
+        "    assert_eq(1, 2)",
+        "fun test_second()",
+        "    assert_eq(1, 1)",
+        "fun test_third()",
+        "    assert_eq(1, 1)",
+    ]);
+
+    let options = TestOptions {
+        filter: Some(String::from("ir")),
+        fail_fast: false,
+    };
+    let outcomes = testrunner::run_tests(&Engine::new(), program.clone(), &options).unwrap();
+    let names: Vec<&str> = outcomes
+        .iter()
+        .map(|outcome| outcome.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["test_first", "test_third"]);
+
+    // Nothing runs after the first failure
+    let options = TestOptions {
+        filter: None,
+        fail_fast: true,
+    };
+    let outcomes = testrunner::run_tests(&Engine::new(), program, &options).unwrap();
+    assert_eq!(outcomes.len(), 1);
+    assert!(!outcomes[0].passed());
+}
@@ -1,5 +1,6 @@
 use rosy::tokenizer;
 use tokenizer::Error;
+use tokenizer::Span;
 use tokenizer::SymbolType;
 use tokenizer::Token;
 use tokenizer::TokenData;
@@ -61,7 +62,7 @@ fn variable_test() {
         "a1",
         "a1b34nh_4",
     ]);
-    let tokens = tokenizer::tokenize(program);
+    let tokens = tokenizer::tokenize(program.clone());
 
     let expected = Vec::from([
         TokenLine {
@@ -69,9 +70,7 @@ fn variable_test() {
                 data: TokenData::Variable {
                     name: String::from("a"),
                 },
-                row: 0,
-                col_start: 0,
-                col_end: 1,
+                span: Span::in_lines(&program, 0, 0, 1),
             }]),
             indentation: 0,
         },
@@ -80,9 +79,7 @@ fn variable_test() {
                 data: TokenData::Variable {
                     name: String::from("multiple_words"),
                 },
-                row: 1,
-                col_start: 0,
-                col_end: 14,
+                span: Span::in_lines(&program, 1, 0, 14),
             }]),
             indentation: 0,
         },
@@ -91,9 +88,7 @@ fn variable_test() {
                 data: TokenData::Variable {
                     name: String::from("CapITalS"),
                 },
-                row: 2,
-                col_start: 0,
-                col_end: 8,
+                span: Span::in_lines(&program, 2, 0, 8),
             }]),
             indentation: 0,
         },
@@ -102,9 +97,7 @@ fn variable_test() {
                 data: TokenData::Variable {
                     name: String::from("a1"),
                 },
-                row: 3,
-                col_start: 0,
-                col_end: 2,
+                span: Span::in_lines(&program, 3, 0, 2),
             }]),
             indentation: 0,
         },
@@ -113,9 +106,7 @@ fn variable_test() {
                 data: TokenData::Variable {
                     name: String::from("a1b34nh_4"),
                 },
-                row: 4,
-                col_start: 0,
-                col_end: 9,
+                span: Span::in_lines(&program, 4, 0, 9),
             }]),
             indentation: 0,
         },
@@ -135,90 +126,90 @@ fn arithmetic_test() {
         "var = 15",
         "var2 = (15 + 16 / 4) ^2 * 4 / 2",
     ]);
-    let tokens = tokenizer::tokenize(program);
+    let tokens = tokenizer::tokenize(program.clone());
 
     let expected = Vec::from([
         TokenLine {
             #[rustfmt::skip]
             tokens: Vec::from([
-                Token { data: TokenData::Number { number: 2 }, row: 0, col_start: 0, col_end: 1 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Plus }, row: 0, col_start: 2, col_end: 3 },
-                Token { data: TokenData::Number { number: 3 }, row: 0, col_start: 4, col_end: 5 },
+                Token { data: TokenData::Number { number: 2 }, span: Span::in_lines(&program, 0, 0, 1) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Plus }, span: Span::in_lines(&program, 0, 2, 3) },
+                Token { data: TokenData::Number { number: 3 }, span: Span::in_lines(&program, 0, 4, 5) },
             ]),
             indentation: 0,
         },
         TokenLine {
             #[rustfmt::skip]
             tokens: Vec::from([
-                Token { data: TokenData::Number { number: 2 }, row: 1, col_start: 0, col_end: 1 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Plus }, row: 1, col_start: 2, col_end: 3 },
-                Token { data: TokenData::Number { number: 3 }, row: 1, col_start: 4, col_end: 5 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Star }, row: 1, col_start: 6, col_end: 7 },
-                Token { data: TokenData::Number { number: 4 }, row: 1, col_start: 8, col_end: 9 },
+                Token { data: TokenData::Number { number: 2 }, span: Span::in_lines(&program, 1, 0, 1) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Plus }, span: Span::in_lines(&program, 1, 2, 3) },
+                Token { data: TokenData::Number { number: 3 }, span: Span::in_lines(&program, 1, 4, 5) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Star }, span: Span::in_lines(&program, 1, 6, 7) },
+                Token { data: TokenData::Number { number: 4 }, span: Span::in_lines(&program, 1, 8, 9) },
             ]),
             indentation: 0,
         },
         TokenLine {
             #[rustfmt::skip]
             tokens: Vec::from([
-                Token { data: TokenData::Number { number: 23 }, row: 2, col_start: 0, col_end: 2 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Plus }, row: 2, col_start: 3, col_end: 4 },
-                Token { data: TokenData::Number { number: 28 }, row: 2, col_start: 5, col_end: 7 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Slash }, row: 2, col_start: 8, col_end: 9 },
-                Token { data: TokenData::Number { number: 15 }, row: 2, col_start: 10, col_end: 12 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Star }, row: 2, col_start: 13, col_end: 14 },
-                Token { data: TokenData::Number { number: 7 }, row: 2, col_start: 15, col_end: 16 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Minus }, row: 2, col_start: 17, col_end: 18 },
-                Token { data: TokenData::Number { number: 13 }, row: 2, col_start: 19, col_end: 21 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Hat }, row: 2, col_start: 22, col_end: 23 },
-                Token { data: TokenData::Number { number: 2 }, row: 2, col_start: 24, col_end: 25 },
+                Token { data: TokenData::Number { number: 23 }, span: Span::in_lines(&program, 2, 0, 2) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Plus }, span: Span::in_lines(&program, 2, 3, 4) },
+                Token { data: TokenData::Number { number: 28 }, span: Span::in_lines(&program, 2, 5, 7) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Slash }, span: Span::in_lines(&program, 2, 8, 9) },
+                Token { data: TokenData::Number { number: 15 }, span: Span::in_lines(&program, 2, 10, 12) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Star }, span: Span::in_lines(&program, 2, 13, 14) },
+                Token { data: TokenData::Number { number: 7 }, span: Span::in_lines(&program, 2, 15, 16) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Minus }, span: Span::in_lines(&program, 2, 17, 18) },
+                Token { data: TokenData::Number { number: 13 }, span: Span::in_lines(&program, 2, 19, 21) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Hat }, span: Span::in_lines(&program, 2, 22, 23) },
+                Token { data: TokenData::Number { number: 2 }, span: Span::in_lines(&program, 2, 24, 25) },
             ]),
             indentation: 0,
         },
         TokenLine {
             #[rustfmt::skip]
             tokens: Vec::from([
-                Token { data: TokenData::Number { number: 23 }, row: 3, col_start: 0, col_end: 2 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Plus }, row: 3, col_start: 2, col_end: 3 },
-                Token { data: TokenData::Number { number: 28 }, row: 3, col_start: 3, col_end: 5 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Slash }, row: 3, col_start: 5, col_end: 6 },
-                Token { data: TokenData::Number { number: 15 }, row: 3, col_start: 6, col_end: 8 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Star }, row: 3, col_start: 8, col_end: 9 },
-                Token { data: TokenData::Number { number: 7 }, row: 3, col_start: 9, col_end: 10 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Minus }, row: 3, col_start: 10, col_end: 11 },
-                Token { data: TokenData::Number { number: 13 }, row: 3, col_start: 11, col_end: 13 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Hat }, row: 3, col_start: 13, col_end: 14 },
-                Token { data: TokenData::Number { number: 2 }, row: 3, col_start: 14, col_end: 15 },
+                Token { data: TokenData::Number { number: 23 }, span: Span::in_lines(&program, 3, 0, 2) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Plus }, span: Span::in_lines(&program, 3, 2, 3) },
+                Token { data: TokenData::Number { number: 28 }, span: Span::in_lines(&program, 3, 3, 5) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Slash }, span: Span::in_lines(&program, 3, 5, 6) },
+                Token { data: TokenData::Number { number: 15 }, span: Span::in_lines(&program, 3, 6, 8) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Star }, span: Span::in_lines(&program, 3, 8, 9) },
+                Token { data: TokenData::Number { number: 7 }, span: Span::in_lines(&program, 3, 9, 10) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Minus }, span: Span::in_lines(&program, 3, 10, 11) },
+                Token { data: TokenData::Number { number: 13 }, span: Span::in_lines(&program, 3, 11, 13) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Hat }, span: Span::in_lines(&program, 3, 13, 14) },
+                Token { data: TokenData::Number { number: 2 }, span: Span::in_lines(&program, 3, 14, 15) },
             ]),
             indentation: 0,
         },
         TokenLine {
             #[rustfmt::skip]
             tokens: Vec::from([
-                Token { data: TokenData::Variable { name: String::from("var") }, row: 4, col_start: 0, col_end: 3 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Equals }, row: 4, col_start: 4, col_end: 5 },
-                Token { data: TokenData::Number { number: 15 }, row: 4, col_start: 6, col_end: 8 },
+                Token { data: TokenData::Variable { name: String::from("var") }, span: Span::in_lines(&program, 4, 0, 3) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Equals }, span: Span::in_lines(&program, 4, 4, 5) },
+                Token { data: TokenData::Number { number: 15 }, span: Span::in_lines(&program, 4, 6, 8) },
             ]),
             indentation: 0,
         },
         TokenLine {
             #[rustfmt::skip]
             tokens: Vec::from([
-                Token { data: TokenData::Variable { name: String::from("var2") }, row: 5, col_start: 0, col_end: 4 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Equals }, row: 5, col_start: 5, col_end: 6 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::ParenthesisOpen }, row: 5, col_start: 7, col_end: 8 },
-                Token { data: TokenData::Number { number: 15 }, row: 5, col_start: 8, col_end: 10 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Plus }, row: 5, col_start: 11, col_end: 12 },
-                Token { data: TokenData::Number { number: 16 }, row: 5, col_start: 13, col_end: 15 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Slash }, row: 5, col_start: 16, col_end: 17 },
-                Token { data: TokenData::Number { number: 4 }, row: 5, col_start: 18, col_end: 19 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::ParenthesisClosed }, row: 5, col_start: 19, col_end: 20 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Hat }, row: 5, col_start: 21, col_end: 22 },
-                Token { data: TokenData::Number { number: 2 }, row: 5, col_start: 22, col_end: 23 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Star }, row: 5, col_start: 24, col_end: 25 },
-                Token { data: TokenData::Number { number: 4 }, row: 5, col_start: 26, col_end: 27 },
-                Token { data: TokenData::Symbol { symbol_type: SymbolType::Slash }, row: 5, col_start: 28, col_end: 29 },
-                Token { data: TokenData::Number { number: 2 }, row: 5, col_start: 30, col_end: 31 },
+                Token { data: TokenData::Variable { name: String::from("var2") }, span: Span::in_lines(&program, 5, 0, 4) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Equals }, span: Span::in_lines(&program, 5, 5, 6) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::ParenthesisOpen }, span: Span::in_lines(&program, 5, 7, 8) },
+                Token { data: TokenData::Number { number: 15 }, span: Span::in_lines(&program, 5, 8, 10) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Plus }, span: Span::in_lines(&program, 5, 11, 12) },
+                Token { data: TokenData::Number { number: 16 }, span: Span::in_lines(&program, 5, 13, 15) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Slash }, span: Span::in_lines(&program, 5, 16, 17) },
+                Token { data: TokenData::Number { number: 4 }, span: Span::in_lines(&program, 5, 18, 19) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::ParenthesisClosed }, span: Span::in_lines(&program, 5, 19, 20) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Hat }, span: Span::in_lines(&program, 5, 21, 22) },
+                Token { data: TokenData::Number { number: 2 }, span: Span::in_lines(&program, 5, 22, 23) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Star }, span: Span::in_lines(&program, 5, 24, 25) },
+                Token { data: TokenData::Number { number: 4 }, span: Span::in_lines(&program, 5, 26, 27) },
+                Token { data: TokenData::Symbol { symbol_type: SymbolType::Slash }, span: Span::in_lines(&program, 5, 28, 29) },
+                Token { data: TokenData::Number { number: 2 }, span: Span::in_lines(&program, 5, 30, 31) },
             ]),
             indentation: 0,
         },
@@ -234,7 +225,7 @@ fn boolean_test() {
         "true",
         "false",
     ]);
-    let tokens = tokenizer::tokenize(program);
+    let tokens = tokenizer::tokenize(program.clone());
 
     let expected = Vec::from([
         TokenLine {
@@ -242,9 +233,7 @@ fn boolean_test() {
                 data: TokenData::Symbol {
                     symbol_type: SymbolType::True,
                 },
-                row: 0,
-                col_start: 0,
-                col_end: 4,
+                span: Span::in_lines(&program, 0, 0, 4),
             }]),
             indentation: 0,
         },
@@ -253,9 +242,7 @@ fn boolean_test() {
                 data: TokenData::Symbol {
                     symbol_type: SymbolType::False,
                 },
-                row: 1,
-                col_start: 0,
-                col_end: 5,
+                span: Span::in_lines(&program, 1, 0, 5),
             }]),
             indentation: 0,
         },
@@ -282,7 +269,7 @@ fn if_statement_test() {
         "    var2 = 15",
         "    var3 = 15",
     ]);
-    let tokens = tokenizer::tokenize(program);
+    let tokens = tokenizer::tokenize(program.clone());
 
     let expected = Vec::from([
         TokenLine {
@@ -291,29 +278,21 @@ fn if_statement_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::If,
                     },
-                    row: 0,
-                    col_start: 0,
-                    col_end: 2,
+                    span: Span::in_lines(&program, 0, 0, 2),
                 },
                 Token {
                     data: TokenData::Number { number: 2 },
-                    row: 0,
-                    col_start: 3,
-                    col_end: 4,
+                    span: Span::in_lines(&program, 0, 3, 4),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Plus,
                     },
-                    row: 0,
-                    col_start: 5,
-                    col_end: 6,
+                    span: Span::in_lines(&program, 0, 5, 6),
                 },
                 Token {
                     data: TokenData::Number { number: 3 },
-                    row: 0,
-                    col_start: 7,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 0, 7, 8),
                 },
             ]),
             indentation: 0,
@@ -324,23 +303,17 @@ fn if_statement_test() {
                     data: TokenData::Variable {
                         name: String::from("var"),
                     },
-                    row: 1,
-                    col_start: 4,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 1, 4, 7),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Equals,
                     },
-                    row: 1,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 1, 8, 9),
                 },
                 Token {
                     data: TokenData::Number { number: 15 },
-                    row: 1,
-                    col_start: 10,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 1, 10, 12),
                 },
             ]),
             indentation: 1,
@@ -351,43 +324,31 @@ fn if_statement_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::If,
                     },
-                    row: 2,
-                    col_start: 0,
-                    col_end: 2,
+                    span: Span::in_lines(&program, 2, 0, 2),
                 },
                 Token {
                     data: TokenData::Number { number: 2 },
-                    row: 2,
-                    col_start: 3,
-                    col_end: 4,
+                    span: Span::in_lines(&program, 2, 3, 4),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Plus,
                     },
-                    row: 2,
-                    col_start: 5,
-                    col_end: 6,
+                    span: Span::in_lines(&program, 2, 5, 6),
                 },
                 Token {
                     data: TokenData::Number { number: 3 },
-                    row: 2,
-                    col_start: 7,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 2, 7, 8),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Star,
                     },
-                    row: 2,
-                    col_start: 9,
-                    col_end: 10,
+                    span: Span::in_lines(&program, 2, 9, 10),
                 },
                 Token {
                     data: TokenData::Number { number: 4 },
-                    row: 2,
-                    col_start: 11,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 2, 11, 12),
                 },
             ]),
             indentation: 0,
@@ -398,23 +359,17 @@ fn if_statement_test() {
                     data: TokenData::Variable {
                         name: String::from("var"),
                     },
-                    row: 3,
-                    col_start: 4,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 3, 4, 7),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Equals,
                     },
-                    row: 3,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 3, 8, 9),
                 },
                 Token {
                     data: TokenData::Number { number: 15 },
-                    row: 3,
-                    col_start: 10,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 3, 10, 12),
                 },
             ]),
             indentation: 1,
@@ -425,23 +380,17 @@ fn if_statement_test() {
                     data: TokenData::Variable {
                         name: String::from("var2"),
                     },
-                    row: 4,
-                    col_start: 4,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 4, 4, 8),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Equals,
                     },
-                    row: 4,
-                    col_start: 9,
-                    col_end: 10,
+                    span: Span::in_lines(&program, 4, 9, 10),
                 },
                 Token {
                     data: TokenData::Number { number: 15 },
-                    row: 4,
-                    col_start: 11,
-                    col_end: 13,
+                    span: Span::in_lines(&program, 4, 11, 13),
                 },
             ]),
             indentation: 1,
@@ -452,43 +401,31 @@ fn if_statement_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::If,
                     },
-                    row: 5,
-                    col_start: 0,
-                    col_end: 2,
+                    span: Span::in_lines(&program, 5, 0, 2),
                 },
                 Token {
                     data: TokenData::Number { number: 2 },
-                    row: 5,
-                    col_start: 3,
-                    col_end: 4,
+                    span: Span::in_lines(&program, 5, 3, 4),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Plus,
                     },
-                    row: 5,
-                    col_start: 5,
-                    col_end: 6,
+                    span: Span::in_lines(&program, 5, 5, 6),
                 },
                 Token {
                     data: TokenData::Number { number: 3 },
-                    row: 5,
-                    col_start: 7,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 5, 7, 8),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Star,
                     },
-                    row: 5,
-                    col_start: 9,
-                    col_end: 10,
+                    span: Span::in_lines(&program, 5, 9, 10),
                 },
                 Token {
                     data: TokenData::Number { number: 4 },
-                    row: 5,
-                    col_start: 11,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 5, 11, 12),
                 },
             ]),
             indentation: 0,
@@ -499,23 +436,17 @@ fn if_statement_test() {
                     data: TokenData::Variable {
                         name: String::from("var"),
                     },
-                    row: 6,
-                    col_start: 4,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 6, 4, 7),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Equals,
                     },
-                    row: 6,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 6, 8, 9),
                 },
                 Token {
                     data: TokenData::Number { number: 15 },
-                    row: 6,
-                    col_start: 10,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 6, 10, 12),
                 },
             ]),
             indentation: 1,
@@ -526,23 +457,17 @@ fn if_statement_test() {
                     data: TokenData::Variable {
                         name: String::from("var2"),
                     },
-                    row: 7,
-                    col_start: 4,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 7, 4, 8),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Equals,
                     },
-                    row: 7,
-                    col_start: 9,
-                    col_end: 10,
+                    span: Span::in_lines(&program, 7, 9, 10),
                 },
                 Token {
                     data: TokenData::Number { number: 15 },
-                    row: 7,
-                    col_start: 11,
-                    col_end: 13,
+                    span: Span::in_lines(&program, 7, 11, 13),
                 },
             ]),
             indentation: 1,
@@ -553,23 +478,17 @@ fn if_statement_test() {
                     data: TokenData::Variable {
                         name: String::from("var3"),
                     },
-                    row: 8,
-                    col_start: 4,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 8, 4, 8),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Equals,
                     },
-                    row: 8,
-                    col_start: 9,
-                    col_end: 10,
+                    span: Span::in_lines(&program, 8, 9, 10),
                 },
                 Token {
                     data: TokenData::Number { number: 15 },
-                    row: 8,
-                    col_start: 11,
-                    col_end: 13,
+                    span: Span::in_lines(&program, 8, 11, 13),
                 },
             ]),
             indentation: 1,
@@ -604,7 +523,7 @@ fn else_and_else_if_test() {
         "else",
         "    var = 19",
     ]);
-    let tokens = tokenizer::tokenize(program);
+    let tokens = tokenizer::tokenize(program.clone());
 
     let expected = Vec::from([
         TokenLine {
@@ -613,29 +532,21 @@ fn else_and_else_if_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::If,
                     },
-                    row: 0,
-                    col_start: 0,
-                    col_end: 2,
+                    span: Span::in_lines(&program, 0, 0, 2),
                 },
                 Token {
                     data: TokenData::Number { number: 2 },
-                    row: 0,
-                    col_start: 3,
-                    col_end: 4,
+                    span: Span::in_lines(&program, 0, 3, 4),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Plus,
                     },
-                    row: 0,
-                    col_start: 5,
-                    col_end: 6,
+                    span: Span::in_lines(&program, 0, 5, 6),
                 },
                 Token {
                     data: TokenData::Number { number: 3 },
-                    row: 0,
-                    col_start: 7,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 0, 7, 8),
                 },
             ]),
             indentation: 0,
@@ -646,23 +557,17 @@ fn else_and_else_if_test() {
                     data: TokenData::Variable {
                         name: String::from("var"),
                     },
-                    row: 1,
-                    col_start: 4,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 1, 4, 7),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Equals,
                     },
-                    row: 1,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 1, 8, 9),
                 },
                 Token {
                     data: TokenData::Number { number: 15 },
-                    row: 1,
-                    col_start: 10,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 1, 10, 12),
                 },
             ]),
             indentation: 1,
@@ -672,9 +577,7 @@ fn else_and_else_if_test() {
                 data: TokenData::Symbol {
                     symbol_type: SymbolType::Else,
                 },
-                row: 2,
-                col_start: 0,
-                col_end: 4,
+                span: Span::in_lines(&program, 2, 0, 4),
             }]),
             indentation: 0,
         },
@@ -684,23 +587,17 @@ fn else_and_else_if_test() {
                     data: TokenData::Variable {
                         name: String::from("var"),
                     },
-                    row: 3,
-                    col_start: 4,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 3, 4, 7),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Equals,
                     },
-                    row: 3,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 3, 8, 9),
                 },
                 Token {
                     data: TokenData::Number { number: 16 },
-                    row: 3,
-                    col_start: 10,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 3, 10, 12),
                 },
             ]),
             indentation: 1,
@@ -711,29 +608,21 @@ fn else_and_else_if_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::If,
                     },
-                    row: 4,
-                    col_start: 0,
-                    col_end: 2,
+                    span: Span::in_lines(&program, 4, 0, 2),
                 },
                 Token {
                     data: TokenData::Number { number: 2 },
-                    row: 4,
-                    col_start: 3,
-                    col_end: 4,
+                    span: Span::in_lines(&program, 4, 3, 4),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Plus,
                     },
-                    row: 4,
-                    col_start: 5,
-                    col_end: 6,
+                    span: Span::in_lines(&program, 4, 5, 6),
                 },
                 Token {
                     data: TokenData::Number { number: 3 },
-                    row: 4,
-                    col_start: 7,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 4, 7, 8),
                 },
             ]),
             indentation: 0,
@@ -744,23 +633,17 @@ fn else_and_else_if_test() {
                     data: TokenData::Variable {
                         name: String::from("var"),
                     },
-                    row: 5,
-                    col_start: 4,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 5, 4, 7),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Equals,
                     },
-                    row: 5,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 5, 8, 9),
                 },
                 Token {
                     data: TokenData::Number { number: 15 },
-                    row: 5,
-                    col_start: 10,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 5, 10, 12),
                 },
             ]),
             indentation: 1,
@@ -771,51 +654,37 @@ fn else_and_else_if_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Else,
                     },
-                    row: 6,
-                    col_start: 0,
-                    col_end: 4,
+                    span: Span::in_lines(&program, 6, 0, 4),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::If,
                     },
-                    row: 6,
-                    col_start: 5,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 6, 5, 7),
                 },
                 Token {
                     data: TokenData::Number { number: 2 },
-                    row: 6,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 6, 8, 9),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Plus,
                     },
-                    row: 6,
-                    col_start: 10,
-                    col_end: 11,
+                    span: Span::in_lines(&program, 6, 10, 11),
                 },
                 Token {
                     data: TokenData::Number { number: 3 },
-                    row: 6,
-                    col_start: 12,
-                    col_end: 13,
+                    span: Span::in_lines(&program, 6, 12, 13),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Star,
                     },
-                    row: 6,
-                    col_start: 14,
-                    col_end: 15,
+                    span: Span::in_lines(&program, 6, 14, 15),
                 },
                 Token {
                     data: TokenData::Number { number: 4 },
-                    row: 6,
-                    col_start: 16,
-                    col_end: 17,
+                    span: Span::in_lines(&program, 6, 16, 17),
                 },
             ]),
             indentation: 0,
@@ -826,23 +695,17 @@ fn else_and_else_if_test() {
                     data: TokenData::Variable {
                         name: String::from("var"),
                     },
-                    row: 7,
-                    col_start: 4,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 7, 4, 7),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Equals,
                     },
-                    row: 7,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 7, 8, 9),
                 },
                 Token {
                     data: TokenData::Number { number: 17 },
-                    row: 7,
-                    col_start: 10,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 7, 10, 12),
                 },
             ]),
             indentation: 1,
@@ -853,51 +716,37 @@ fn else_and_else_if_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Else,
                     },
-                    row: 8,
-                    col_start: 0,
-                    col_end: 4,
+                    span: Span::in_lines(&program, 8, 0, 4),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::If,
                     },
-                    row: 8,
-                    col_start: 5,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 8, 5, 7),
                 },
                 Token {
                     data: TokenData::Number { number: 2 },
-                    row: 8,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 8, 8, 9),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Plus,
                     },
-                    row: 8,
-                    col_start: 10,
-                    col_end: 11,
+                    span: Span::in_lines(&program, 8, 10, 11),
                 },
                 Token {
                     data: TokenData::Number { number: 3 },
-                    row: 8,
-                    col_start: 12,
-                    col_end: 13,
+                    span: Span::in_lines(&program, 8, 12, 13),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Star,
                     },
-                    row: 8,
-                    col_start: 14,
-                    col_end: 15,
+                    span: Span::in_lines(&program, 8, 14, 15),
                 },
                 Token {
                     data: TokenData::Number { number: 4 },
-                    row: 8,
-                    col_start: 16,
-                    col_end: 17,
+                    span: Span::in_lines(&program, 8, 16, 17),
                 },
             ]),
             indentation: 0,
@@ -908,23 +757,17 @@ fn else_and_else_if_test() {
                     data: TokenData::Variable {
                         name: String::from("var"),
                     },
-                    row: 9,
-                    col_start: 4,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 9, 4, 7),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Equals,
                     },
-                    row: 9,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 9, 8, 9),
                 },
                 Token {
                     data: TokenData::Number { number: 18 },
-                    row: 9,
-                    col_start: 10,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 9, 10, 12),
                 },
             ]),
             indentation: 1,
@@ -934,9 +777,7 @@ fn else_and_else_if_test() {
                 data: TokenData::Symbol {
                     symbol_type: SymbolType::Else,
                 },
-                row: 10,
-                col_start: 0,
-                col_end: 4,
+                span: Span::in_lines(&program, 10, 0, 4),
             }]),
             indentation: 0,
         },
@@ -946,23 +787,17 @@ fn else_and_else_if_test() {
                     data: TokenData::Variable {
                         name: String::from("var"),
                     },
-                    row: 11,
-                    col_start: 4,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 11, 4, 7),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Equals,
                     },
-                    row: 11,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 11, 8, 9),
                 },
                 Token {
                     data: TokenData::Number { number: 19 },
-                    row: 11,
-                    col_start: 10,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 11, 10, 12),
                 },
             ]),
             indentation: 1,
@@ -985,7 +820,7 @@ fn function_def_test() {
         "fun add(a, b, c, d)",
         "    return a + b + c + d",
     ]);
-    let tokens = tokenizer::tokenize(program);
+    let tokens = tokenizer::tokenize(program.clone());
 
     let expected = Vec::from([
         TokenLine {
@@ -994,33 +829,25 @@ fn function_def_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Fun,
                     },
-                    row: 0,
-                    col_start: 0,
-                    col_end: 3,
+                    span: Span::in_lines(&program, 0, 0, 3),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("add"),
                     },
-                    row: 0,
-                    col_start: 4,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 0, 4, 7),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::ParenthesisOpen,
                     },
-                    row: 0,
-                    col_start: 7,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 0, 7, 8),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::ParenthesisClosed,
                     },
-                    row: 0,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 0, 8, 9),
                 },
             ]),
             indentation: 0,
@@ -1031,15 +858,11 @@ fn function_def_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Return,
                     },
-                    row: 1,
-                    col_start: 4,
-                    col_end: 10,
+                    span: Span::in_lines(&program, 1, 4, 10),
                 },
                 Token {
                     data: TokenData::Number { number: 0 },
-                    row: 1,
-                    col_start: 11,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 1, 11, 12),
                 },
             ]),
             indentation: 1,
@@ -1050,41 +873,31 @@ fn function_def_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Fun,
                     },
-                    row: 2,
-                    col_start: 0,
-                    col_end: 3,
+                    span: Span::in_lines(&program, 2, 0, 3),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("add"),
                     },
-                    row: 2,
-                    col_start: 4,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 2, 4, 7),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::ParenthesisOpen,
                     },
-                    row: 2,
-                    col_start: 7,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 2, 7, 8),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("a"),
                     },
-                    row: 2,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 2, 8, 9),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::ParenthesisClosed,
                     },
-                    row: 2,
-                    col_start: 9,
-                    col_end: 10,
+                    span: Span::in_lines(&program, 2, 9, 10),
                 },
             ]),
             indentation: 0,
@@ -1095,17 +908,13 @@ fn function_def_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Return,
                     },
-                    row: 3,
-                    col_start: 4,
-                    col_end: 10,
+                    span: Span::in_lines(&program, 3, 4, 10),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("a"),
                     },
-                    row: 3,
-                    col_start: 11,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 3, 11, 12),
                 },
             ]),
             indentation: 1,
@@ -1116,57 +925,43 @@ fn function_def_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Fun,
                     },
-                    row: 4,
-                    col_start: 0,
-                    col_end: 3,
+                    span: Span::in_lines(&program, 4, 0, 3),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("add"),
                     },
-                    row: 4,
-                    col_start: 4,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 4, 4, 7),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::ParenthesisOpen,
                     },
-                    row: 4,
-                    col_start: 7,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 4, 7, 8),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("a"),
                     },
-                    row: 4,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 4, 8, 9),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 4,
-                    col_start: 9,
-                    col_end: 10,
+                    span: Span::in_lines(&program, 4, 9, 10),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("b"),
                     },
-                    row: 4,
-                    col_start: 11,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 4, 11, 12),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::ParenthesisClosed,
                     },
-                    row: 4,
-                    col_start: 12,
-                    col_end: 13,
+                    span: Span::in_lines(&program, 4, 12, 13),
                 },
             ]),
             indentation: 0,
@@ -1177,33 +972,25 @@ fn function_def_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Return,
                     },
-                    row: 5,
-                    col_start: 4,
-                    col_end: 10,
+                    span: Span::in_lines(&program, 5, 4, 10),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("a"),
                     },
-                    row: 5,
-                    col_start: 11,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 5, 11, 12),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Plus,
                     },
-                    row: 5,
-                    col_start: 13,
-                    col_end: 14,
+                    span: Span::in_lines(&program, 5, 13, 14),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("b"),
                     },
-                    row: 5,
-                    col_start: 15,
-                    col_end: 16,
+                    span: Span::in_lines(&program, 5, 15, 16),
                 },
             ]),
             indentation: 1,
@@ -1214,89 +1001,67 @@ fn function_def_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Fun,
                     },
-                    row: 6,
-                    col_start: 0,
-                    col_end: 3,
+                    span: Span::in_lines(&program, 6, 0, 3),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("add"),
                     },
-                    row: 6,
-                    col_start: 4,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 6, 4, 7),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::ParenthesisOpen,
                     },
-                    row: 6,
-                    col_start: 7,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 6, 7, 8),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("a"),
                     },
-                    row: 6,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 6, 8, 9),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 6,
-                    col_start: 9,
-                    col_end: 10,
+                    span: Span::in_lines(&program, 6, 9, 10),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("b"),
                     },
-                    row: 6,
-                    col_start: 11,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 6, 11, 12),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 6,
-                    col_start: 12,
-                    col_end: 13,
+                    span: Span::in_lines(&program, 6, 12, 13),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("c"),
                     },
-                    row: 6,
-                    col_start: 14,
-                    col_end: 15,
+                    span: Span::in_lines(&program, 6, 14, 15),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 6,
-                    col_start: 15,
-                    col_end: 16,
+                    span: Span::in_lines(&program, 6, 15, 16),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("d"),
                     },
-                    row: 6,
-                    col_start: 17,
-                    col_end: 18,
+                    span: Span::in_lines(&program, 6, 17, 18),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::ParenthesisClosed,
                     },
-                    row: 6,
-                    col_start: 18,
-                    col_end: 19,
+                    span: Span::in_lines(&program, 6, 18, 19),
                 },
             ]),
             indentation: 0,
@@ -1307,65 +1072,49 @@ fn function_def_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Return,
                     },
-                    row: 7,
-                    col_start: 4,
-                    col_end: 10,
+                    span: Span::in_lines(&program, 7, 4, 10),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("a"),
                     },
-                    row: 7,
-                    col_start: 11,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 7, 11, 12),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Plus,
                     },
-                    row: 7,
-                    col_start: 13,
-                    col_end: 14,
+                    span: Span::in_lines(&program, 7, 13, 14),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("b"),
                     },
-                    row: 7,
-                    col_start: 15,
-                    col_end: 16,
+                    span: Span::in_lines(&program, 7, 15, 16),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Plus,
                     },
-                    row: 7,
-                    col_start: 17,
-                    col_end: 18,
+                    span: Span::in_lines(&program, 7, 17, 18),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("c"),
                     },
-                    row: 7,
-                    col_start: 19,
-                    col_end: 20,
+                    span: Span::in_lines(&program, 7, 19, 20),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Plus,
                     },
-                    row: 7,
-                    col_start: 21,
-                    col_end: 22,
+                    span: Span::in_lines(&program, 7, 21, 22),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("d"),
                     },
-                    row: 7,
-                    col_start: 23,
-                    col_end: 24,
+                    span: Span::in_lines(&program, 7, 23, 24),
                 },
             ]),
             indentation: 1,
@@ -1381,7 +1130,7 @@ fn function_calls_test() {
     let program = Vec::from([
         "a(\"hi\")",
     ]);
-    let tokens = tokenizer::tokenize(program);
+    let tokens = tokenizer::tokenize(program.clone());
 
     let expected = Vec::from([TokenLine {
         tokens: Vec::from([
@@ -1389,33 +1138,25 @@ fn function_calls_test() {
                 data: TokenData::Variable {
                     name: String::from("a"),
                 },
-                row: 0,
-                col_start: 0,
-                col_end: 1,
+                span: Span::in_lines(&program, 0, 0, 1),
             },
             Token {
                 data: TokenData::Symbol {
                     symbol_type: SymbolType::ParenthesisOpen,
                 },
-                row: 0,
-                col_start: 1,
-                col_end: 2,
+                span: Span::in_lines(&program, 0, 1, 2),
             },
             Token {
                 data: TokenData::String {
                     value: String::from("hi"),
                 },
-                row: 0,
-                col_start: 2,
-                col_end: 6,
+                span: Span::in_lines(&program, 0, 2, 6),
             },
             Token {
                 data: TokenData::Symbol {
                     symbol_type: SymbolType::ParenthesisClosed,
                 },
-                row: 0,
-                col_start: 6,
-                col_end: 7,
+                span: Span::in_lines(&program, 0, 6, 7),
             },
         ]),
         indentation: 0,
@@ -1433,7 +1174,7 @@ fn array_test() {
         "[[true, false], [false, true]]",
         "[[f([1,2], [[3],[true]]), 2], [3, 4]]",
     ]);
-    let tokens = tokenizer::tokenize(program);
+    let tokens = tokenizer::tokenize(program.clone());
 
     let expected = Vec::from([
         TokenLine {
@@ -1442,51 +1183,37 @@ fn array_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketOpen,
                     },
-                    row: 0,
-                    col_start: 0,
-                    col_end: 1,
+                    span: Span::in_lines(&program, 0, 0, 1),
                 },
                 Token {
                     data: TokenData::Number { number: 1 },
-                    row: 0,
-                    col_start: 1,
-                    col_end: 2,
+                    span: Span::in_lines(&program, 0, 1, 2),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 0,
-                    col_start: 2,
-                    col_end: 3,
+                    span: Span::in_lines(&program, 0, 2, 3),
                 },
                 Token {
                     data: TokenData::Number { number: 2 },
-                    row: 0,
-                    col_start: 4,
-                    col_end: 5,
+                    span: Span::in_lines(&program, 0, 4, 5),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 0,
-                    col_start: 5,
-                    col_end: 6,
+                    span: Span::in_lines(&program, 0, 5, 6),
                 },
                 Token {
                     data: TokenData::Number { number: 3 },
-                    row: 0,
-                    col_start: 7,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 0, 7, 8),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketClosed,
                     },
-                    row: 0,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 0, 8, 9),
                 },
             ]),
             indentation: 0,
@@ -1497,175 +1224,127 @@ fn array_test() {
                     data: TokenData::Variable {
                         name: String::from("a"),
                     },
-                    row: 1,
-                    col_start: 0,
-                    col_end: 1,
+                    span: Span::in_lines(&program, 1, 0, 1),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Equals,
                     },
-                    row: 1,
-                    col_start: 2,
-                    col_end: 3,
+                    span: Span::in_lines(&program, 1, 2, 3),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketOpen,
                     },
-                    row: 1,
-                    col_start: 4,
-                    col_end: 5,
+                    span: Span::in_lines(&program, 1, 4, 5),
                 },
                 Token {
                     data: TokenData::Number { number: 1 },
-                    row: 1,
-                    col_start: 5,
-                    col_end: 6,
+                    span: Span::in_lines(&program, 1, 5, 6),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Plus,
                     },
-                    row: 1,
-                    col_start: 7,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 1, 7, 8),
                 },
                 Token {
                     data: TokenData::Number { number: 1 },
-                    row: 1,
-                    col_start: 9,
-                    col_end: 10,
+                    span: Span::in_lines(&program, 1, 9, 10),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 1,
-                    col_start: 10,
-                    col_end: 11,
+                    span: Span::in_lines(&program, 1, 10, 11),
                 },
                 Token {
                     data: TokenData::Number { number: 2 },
-                    row: 1,
-                    col_start: 12,
-                    col_end: 13,
+                    span: Span::in_lines(&program, 1, 12, 13),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Star,
                     },
-                    row: 1,
-                    col_start: 14,
-                    col_end: 15,
+                    span: Span::in_lines(&program, 1, 14, 15),
                 },
                 Token {
                     data: TokenData::Number { number: 2 },
-                    row: 1,
-                    col_start: 16,
-                    col_end: 17,
+                    span: Span::in_lines(&program, 1, 16, 17),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Slash,
                     },
-                    row: 1,
-                    col_start: 17,
-                    col_end: 18,
+                    span: Span::in_lines(&program, 1, 17, 18),
                 },
                 Token {
                     data: TokenData::Number { number: 3 },
-                    row: 1,
-                    col_start: 18,
-                    col_end: 19,
+                    span: Span::in_lines(&program, 1, 18, 19),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 1,
-                    col_start: 19,
-                    col_end: 20,
+                    span: Span::in_lines(&program, 1, 19, 20),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("f"),
                     },
-                    row: 1,
-                    col_start: 21,
-                    col_end: 22,
+                    span: Span::in_lines(&program, 1, 21, 22),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::ParenthesisOpen,
                     },
-                    row: 1,
-                    col_start: 22,
-                    col_end: 23,
+                    span: Span::in_lines(&program, 1, 22, 23),
                 },
                 Token {
                     data: TokenData::Number { number: 1 },
-                    row: 1,
-                    col_start: 23,
-                    col_end: 24,
+                    span: Span::in_lines(&program, 1, 23, 24),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 1,
-                    col_start: 24,
-                    col_end: 25,
+                    span: Span::in_lines(&program, 1, 24, 25),
                 },
                 Token {
                     data: TokenData::Number { number: 2 },
-                    row: 1,
-                    col_start: 26,
-                    col_end: 27,
+                    span: Span::in_lines(&program, 1, 26, 27),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 1,
-                    col_start: 27,
-                    col_end: 28,
+                    span: Span::in_lines(&program, 1, 27, 28),
                 },
                 Token {
                     data: TokenData::Number { number: 3 },
-                    row: 1,
-                    col_start: 29,
-                    col_end: 30,
+                    span: Span::in_lines(&program, 1, 29, 30),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::ParenthesisClosed,
                     },
-                    row: 1,
-                    col_start: 30,
-                    col_end: 31,
+                    span: Span::in_lines(&program, 1, 30, 31),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 1,
-                    col_start: 31,
-                    col_end: 32,
+                    span: Span::in_lines(&program, 1, 31, 32),
                 },
                 Token {
                     data: TokenData::Number { number: 4 },
-                    row: 1,
-                    col_start: 33,
-                    col_end: 34,
+                    span: Span::in_lines(&program, 1, 33, 34),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketClosed,
                     },
-                    row: 1,
-                    col_start: 34,
-                    col_end: 35,
+                    span: Span::in_lines(&program, 1, 34, 35),
                 },
             ]),
             indentation: 0,
@@ -1676,105 +1355,79 @@ fn array_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketOpen,
                     },
-                    row: 2,
-                    col_start: 0,
-                    col_end: 1,
+                    span: Span::in_lines(&program, 2, 0, 1),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketOpen,
                     },
-                    row: 2,
-                    col_start: 1,
-                    col_end: 2,
+                    span: Span::in_lines(&program, 2, 1, 2),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::True,
                     },
-                    row: 2,
-                    col_start: 2,
-                    col_end: 6,
+                    span: Span::in_lines(&program, 2, 2, 6),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 2,
-                    col_start: 6,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 2, 6, 7),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::False,
                     },
-                    row: 2,
-                    col_start: 8,
-                    col_end: 13,
+                    span: Span::in_lines(&program, 2, 8, 13),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketClosed,
                     },
-                    row: 2,
-                    col_start: 13,
-                    col_end: 14,
+                    span: Span::in_lines(&program, 2, 13, 14),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 2,
-                    col_start: 14,
-                    col_end: 15,
+                    span: Span::in_lines(&program, 2, 14, 15),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketOpen,
                     },
-                    row: 2,
-                    col_start: 16,
-                    col_end: 17,
+                    span: Span::in_lines(&program, 2, 16, 17),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::False,
                     },
-                    row: 2,
-                    col_start: 17,
-                    col_end: 22,
+                    span: Span::in_lines(&program, 2, 17, 22),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 2,
-                    col_start: 22,
-                    col_end: 23,
+                    span: Span::in_lines(&program, 2, 22, 23),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::True,
                     },
-                    row: 2,
-                    col_start: 24,
-                    col_end: 28,
+                    span: Span::in_lines(&program, 2, 24, 28),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketClosed,
                     },
-                    row: 2,
-                    col_start: 28,
-                    col_end: 29,
+                    span: Span::in_lines(&program, 2, 28, 29),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketClosed,
                     },
-                    row: 2,
-                    col_start: 29,
-                    col_end: 30,
+                    span: Span::in_lines(&program, 2, 29, 30),
                 },
             ]),
             indentation: 0,
@@ -1785,229 +1438,169 @@ fn array_test() {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketOpen,
                     },
-                    row: 3,
-                    col_start: 0,
-                    col_end: 1,
+                    span: Span::in_lines(&program, 3, 0, 1),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketOpen,
                     },
-                    row: 3,
-                    col_start: 1,
-                    col_end: 2,
+                    span: Span::in_lines(&program, 3, 1, 2),
                 },
                 Token {
                     data: TokenData::Variable {
                         name: String::from("f"),
                     },
-                    row: 3,
-                    col_start: 2,
-                    col_end: 3,
+                    span: Span::in_lines(&program, 3, 2, 3),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::ParenthesisOpen,
                     },
-                    row: 3,
-                    col_start: 3,
-                    col_end: 4,
+                    span: Span::in_lines(&program, 3, 3, 4),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketOpen,
                     },
-                    row: 3,
-                    col_start: 4,
-                    col_end: 5,
+                    span: Span::in_lines(&program, 3, 4, 5),
                 },
                 Token {
                     data: TokenData::Number { number: 1 },
-                    row: 3,
-                    col_start: 5,
-                    col_end: 6,
+                    span: Span::in_lines(&program, 3, 5, 6),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 3,
-                    col_start: 6,
-                    col_end: 7,
+                    span: Span::in_lines(&program, 3, 6, 7),
                 },
                 Token {
                     data: TokenData::Number { number: 2 },
-                    row: 3,
-                    col_start: 7,
-                    col_end: 8,
+                    span: Span::in_lines(&program, 3, 7, 8),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketClosed,
                     },
-                    row: 3,
-                    col_start: 8,
-                    col_end: 9,
+                    span: Span::in_lines(&program, 3, 8, 9),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 3,
-                    col_start: 9,
-                    col_end: 10,
+                    span: Span::in_lines(&program, 3, 9, 10),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketOpen,
                     },
-                    row: 3,
-                    col_start: 11,
-                    col_end: 12,
+                    span: Span::in_lines(&program, 3, 11, 12),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketOpen,
                     },
-                    row: 3,
-                    col_start: 12,
-                    col_end: 13,
+                    span: Span::in_lines(&program, 3, 12, 13),
                 },
                 Token {
                     data: TokenData::Number { number: 3 },
-                    row: 3,
-                    col_start: 13,
-                    col_end: 14,
+                    span: Span::in_lines(&program, 3, 13, 14),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketClosed,
                     },
-                    row: 3,
-                    col_start: 14,
-                    col_end: 15,
+                    span: Span::in_lines(&program, 3, 14, 15),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 3,
-                    col_start: 15,
-                    col_end: 16,
+                    span: Span::in_lines(&program, 3, 15, 16),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketOpen,
                     },
-                    row: 3,
-                    col_start: 16,
-                    col_end: 17,
+                    span: Span::in_lines(&program, 3, 16, 17),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::True,
                     },
-                    row: 3,
-                    col_start: 17,
-                    col_end: 21,
+                    span: Span::in_lines(&program, 3, 17, 21),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketClosed,
                     },
-                    row: 3,
-                    col_start: 21,
-                    col_end: 22,
+                    span: Span::in_lines(&program, 3, 21, 22),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketClosed,
                     },
-                    row: 3,
-                    col_start: 22,
-                    col_end: 23,
+                    span: Span::in_lines(&program, 3, 22, 23),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::ParenthesisClosed,
                     },
-                    row: 3,
-                    col_start: 23,
-                    col_end: 24,
+                    span: Span::in_lines(&program, 3, 23, 24),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 3,
-                    col_start: 24,
-                    col_end: 25,
+                    span: Span::in_lines(&program, 3, 24, 25),
                 },
                 Token {
                     data: TokenData::Number { number: 2 },
-                    row: 3,
-                    col_start: 26,
-                    col_end: 27,
+                    span: Span::in_lines(&program, 3, 26, 27),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketClosed,
                     },
-                    row: 3,
-                    col_start: 27,
-                    col_end: 28,
+                    span: Span::in_lines(&program, 3, 27, 28),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 3,
-                    col_start: 28,
-                    col_end: 29,
+                    span: Span::in_lines(&program, 3, 28, 29),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketOpen,
                     },
-                    row: 3,
-                    col_start: 30,
-                    col_end: 31,
+                    span: Span::in_lines(&program, 3, 30, 31),
                 },
                 Token {
                     data: TokenData::Number { number: 3 },
-                    row: 3,
-                    col_start: 31,
-                    col_end: 32,
+                    span: Span::in_lines(&program, 3, 31, 32),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::Comma,
                     },
-                    row: 3,
-                    col_start: 32,
-                    col_end: 33,
+                    span: Span::in_lines(&program, 3, 32, 33),
                 },
                 Token {
                     data: TokenData::Number { number: 4 },
-                    row: 3,
-                    col_start: 34,
-                    col_end: 35,
+                    span: Span::in_lines(&program, 3, 34, 35),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketClosed,
                     },
-                    row: 3,
-                    col_start: 35,
-                    col_end: 36,
+                    span: Span::in_lines(&program, 3, 35, 36),
                 },
                 Token {
                     data: TokenData::Symbol {
                         symbol_type: SymbolType::SquareBracketClosed,
                     },
-                    row: 3,
-                    col_start: 36,
-                    col_end: 37,
+                    span: Span::in_lines(&program, 3, 36, 37),
                 },
             ]),
             indentation: 0,
@@ -2021,3 +1614,202 @@ fn full_test() {
     let program = Vec::from([""]);
     let tokens = tokenizer::tokenize(program);
 }
+
+#[test]
+fn span_test() {
+    // The tab is expanded to four columns and é takes two bytes
+    let program = Vec::from(["a = 1", "\tb = \"é\" + c + 1"]);
+    let tokens = tokenizer::tokenize(program.clone()).unwrap();
+
+    let b = tokens[1].tokens[0].span();
+    let c = tokens[1].tokens[4].span();
+    assert_eq!(b, Span::in_lines(&program, 1, 4, 5));
+    assert_eq!(c.byte_range(), 18..19);
+
+    // Merging does not depend on the order of the spans
+    let merged = Span::in_lines(&program, 1, 4, 15);
+    assert_eq!(b.merge(&c), merged);
+    assert_eq!(c.merge(&b), merged);
+    assert_eq!(merged.byte_range(), 7..19);
+    assert_eq!(merged.byte_len(), 12);
+
+    assert!(merged.covers(1, 4));
+    assert!(merged.covers(1, 14));
+    assert!(!merged.covers(1, 15));
+    assert!(!merged.covers(0, 4));
+
+    let error = Error::located(String::from("message"), c);
+    assert_eq!(error.span(), Some(c));
+    let error = Error::SimpleError { message: String::from("message") };
+    assert_eq!(error.span(), None);
+}
+
+#[test]
+fn multi_row_span_test() {
+    // The closing bracket is left of the opening one, the merge still ends after it starts
+    let program = Vec::from(["x = [10,", "  2]"]);
+    let open = Span::in_lines(&program, 0, 4, 5);
+    let close = Span::in_lines(&program, 1, 3, 4);
+
+    let merged = Span { row: 0, col_start: 4, row_end: 1, col_end: 4, byte_start: 4, byte_end: 13 };
+    assert_eq!(open.merge(&close), merged);
+    assert_eq!(close.merge(&open), merged);
+    assert!(!merged.is_single_row());
+    assert_eq!(merged.byte_range(), 4..13);
+    assert_eq!(merged.byte_len(), 9);
+
+    assert!(merged.covers(0, 4));
+    assert!(merged.covers(0, 20));
+    assert!(merged.covers(1, 0));
+    assert!(merged.covers(1, 3));
+    assert!(!merged.covers(1, 4));
+    assert!(!merged.covers(0, 3));
+
+    // Errors keep the whole span
+    let error = Error::located(String::from("message"), merged);
+    assert_eq!(error.span(), Some(merged));
+}
+
+#[test]
+fn delimiter_test() {
+    // Brackets may be closed on a later line
+    let program = Vec::from(["a = f(1, [2]", "b = 3"]);
+    assert!(tokenizer::tokenize(Vec::from(["a = [1,", "    2]", "b = f((a))"])).is_ok());
+
+    // The expected closing bracket is placed after the last token of the joined lines
+    assert_eq!(
+        tokenizer::tokenize(program.clone()),
+        Err(Error::DelimiterError {
+            message: String::from("Unclosed '(' opened here"),
+            opened: Span::in_lines(&program, 0, 5, 6),
+            expected: Span::in_lines(&program, 1, 5, 6),
+        })
+    );
+
+    assert_eq!(
+        tokenizer::tokenize(Vec::from(["a = (1]"])),
+        Err(Error::DelimiterError {
+            message: String::from("Unclosed '(' opened here, found ']' instead of ')'"),
+            opened: Span::in_lines(&["a = (1]"], 0, 4, 5),
+            expected: Span::in_lines(&["a = (1]"], 0, 6, 7),
+        })
+    );
+
+    assert_eq!(
+        tokenizer::tokenize(Vec::from(["a = 1)"])),
+        Err(Error::LocationError {
+            message: String::from("Unmatched ')'"),
+            span: Span::in_lines(&["a = 1)"], 0, 5, 6),
+        })
+    );
+}
+
+#[test]
+fn number_overflow_test() {
+    let largest = tokenizer::tokenize(Vec::from(["a = 9223372036854775807"])).unwrap();
+    assert_eq!(
+        largest[0].tokens[2].data,
+        TokenData::Number { number: i64::MAX }
+    );
+
+    let too_large = Err(Error::LocationError {
+        message: String::from(
+            "Number is too large, integers can be at most 9223372036854775807. Write it as 9223372036854775808.0 for a float",
+        ),
+        span: Span::in_lines(&["a = 9223372036854775808"], 0, 4, 23),
+    });
+    assert_eq!(tokenizer::tokenize(Vec::from(["a = 9223372036854775808"])), too_large);
+    assert_eq!(tokenizer::tokenize(Vec::from(["a = 9223372036854775808 + 1"])), too_large);
+    assert!(tokenizer::tokenize(Vec::from(["a = f(100000000000000000000000)"])).is_err());
+
+    // The smallest integer is read together with its minus sign
+    for line in ["a = -9223372036854775808", "a = f(1, - 9223372036854775808)", "-9223372036854775808"] {
+        let tokens = tokenizer::tokenize(Vec::from([line])).unwrap();
+        let smallest = tokens[0].tokens.iter().filter(|token| matches!(token.data, TokenData::Number { .. })).last().unwrap();
+        assert_eq!(smallest.data, TokenData::Number { number: i64::MIN });
+        assert_eq!(smallest.span.col_start, line.find('-').unwrap());
+    }
+    // A minus after a value subtracts, so the number after it has to fit on its own
+    assert!(tokenizer::tokenize(Vec::from(["a = b - 9223372036854775808"])).is_err());
+    assert!(tokenizer::tokenize(Vec::from(["a = (b) - 9223372036854775808"])).is_err());
+    assert!(tokenizer::tokenize(Vec::from(["a = -9223372036854775809"])).is_err());
+}
+
+#[test]
+fn float_test() {
+    let tokens = tokenizer::tokenize(Vec::from(["a = 3.14 + 1e-3 * 2.5E2"])).unwrap();
+    let data: Vec<TokenData> = tokens[0].tokens.iter().map(|token| token.data.clone()).collect();
+    assert_eq!(
+        data[2..],
+        [
+            TokenData::Float { value: 3.14 },
+            TokenData::Symbol { symbol_type: SymbolType::Plus },
+            TokenData::Float { value: 0.001 },
+            TokenData::Symbol { symbol_type: SymbolType::Star },
+            TokenData::Float { value: 250.0 },
+        ]
+    );
+    assert_eq!((tokens[0].tokens[2].span.col_start, tokens[0].tokens[2].span.col_end), (4, 8));
+
+    // A . or e without a digit after it is not part of the number
+    let tokens = tokenizer::tokenize(Vec::from(["a = 1.b + 2e"])).unwrap();
+    let data: Vec<TokenData> = tokens[0].tokens.iter().map(|token| token.data.clone()).collect();
+    assert_eq!(
+        data[2..],
+        [
+            TokenData::Number { number: 1 },
+            TokenData::Symbol { symbol_type: SymbolType::Dot },
+            TokenData::Variable { name: String::from("b") },
+            TokenData::Symbol { symbol_type: SymbolType::Plus },
+            TokenData::Number { number: 2 },
+            TokenData::Variable { name: String::from("e") },
+        ]
+    );
+
+    assert!(tokenizer::tokenize(Vec::from(["a = 1e400"])).is_err());
+}
+
+#[test]
+fn closest_keyword_test() {
+    assert_eq!(tokenizer::edit_distance("kitten", "sitting"), 3);
+    assert_eq!(tokenizer::edit_distance("esle", "else"), 1);
+    assert_eq!(tokenizer::edit_distance("", "for"), 3);
+
+    assert_eq!(tokenizer::closest_keyword("retrun"), Some("return"));
+    assert_eq!(tokenizer::closest_keyword("fnu"), Some("fun"));
+    assert_eq!(tokenizer::closest_keyword("esle"), Some("else"));
+    assert_eq!(tokenizer::closest_keyword("contineu"), Some("continue"));
+    // Keywords themselves, names far from any keyword and anything close to a two letter keyword are not corrected
+    assert_eq!(tokenizer::closest_keyword("return"), None);
+    assert_eq!(tokenizer::closest_keyword("counter"), None);
+    assert_eq!(tokenizer::closest_keyword("it"), None);
+}
+
+#[test]
+fn comment_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "# A comment on its own line",
+        "a = 1 # after code",
+        "if a == 1",
+        "        # at any indentation",
+        "    b = \"# not a comment\"",
+        "  # inside the block",
+        "    c = 2#",
+    ]);
+    let tokens = tokenizer::tokenize(program).unwrap();
+
+    // Comments leave no tokens, and lines with only a comment are skipped like empty lines
+    let rows: Vec<(usize, usize, usize)> = tokens
+        .iter()
+        .map(|line| (line.tokens[0].span.row, line.indentation, line.tokens.len()))
+        .collect();
+    assert_eq!(rows, Vec::from([(1, 0, 3), (2, 0, 4), (4, 1, 3), (6, 1, 3)]));
+    assert_eq!(
+        tokens[2].tokens[2].data,
+        TokenData::String { value: String::from("# not a comment") }
+    );
+
+    assert_eq!(tokenizer::strip_comment("a = 1 # one"), "a = 1 ");
+    assert_eq!(tokenizer::strip_comment("s = \"#\" # one"), "s = \"#\" ");
+}
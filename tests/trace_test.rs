@@ -0,0 +1,35 @@
+use rosy::tokenizer::Error;
+use rosy::trace::{Trace, TracedInput};
+
+#[test]
+fn trace_bytes_round_trip() {
+    let trace = Trace {
+        seed: 0x1234_5678_9ABC_DEF0,
+        inputs: Vec::from([
+            TracedInput {
+                kind: String::from("time"),
+                value: String::from("1700000000000"),
+            },
+            TracedInput {
+                kind: String::from("key"),
+                value: String::from("é"),
+            },
+        ]),
+    };
+
+    assert_eq!(Trace::from_bytes(&trace.to_bytes()), Ok(trace.clone()));
+    assert_eq!(Trace::from_bytes(&Trace::default().to_bytes()), Ok(Trace::default()));
+
+    let message = |bytes: &[u8]| match Trace::from_bytes(bytes) {
+        Err(Error::SimpleError { message }) => message,
+        other => panic!("Expected an error, got {:?}", other),
+    };
+
+    let bytes = trace.to_bytes();
+    assert_eq!(message(b"not a trace"), "Invalid trace: not a rosy trace");
+    assert_eq!(message(&bytes[..bytes.len() - 1]), "Invalid trace: the file ends too early");
+    assert_eq!(
+        message(&[bytes.as_slice(), &[0]].concat()),
+        "Invalid trace: unexpected data after the last input"
+    );
+}
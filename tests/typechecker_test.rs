@@ -1,23 +1,22 @@
 use rosy::parser::{self, BaseExpr, BaseExprData, RecExpr, RecExprData};
+use rosy::tokenizer::Span;
 use rosy::tokenizer::Error;
+use rosy::pipeline;
 use rosy::typechecker;
-use rosy::typechecker::Type;
+use rosy::typechecker::{StructType, Type};
 
 #[test]
 fn simple_number() {
+    let lines = vec!["42"];
     let program = BaseExpr {
         data: BaseExprData::Simple {
             expr: RecExpr {
                 data: RecExprData::Number { number: 42 },
-                row: 0,
-                col_start: 0,
-                col_end: 2,
+                span: Span::in_lines(&lines, 0, 0, 2),
                 generic_data: (),
             },
         },
-        row: 0,
-        col_start: 0,
-        col_end: 2,
+        span: Span::in_lines(&lines, 0, 0, 2),
         generic_data: (),
     };
 
@@ -25,15 +24,11 @@ fn simple_number() {
         data: BaseExprData::Simple {
             expr: RecExpr {
                 data: RecExprData::Number { number: 42 },
-                row: 0,
-                col_start: 0,
-                col_end: 2,
+                span: Span::in_lines(&lines, 0, 0, 2),
                 generic_data: Type::Integer,
             },
         },
-        row: 0,
-        col_start: 0,
-        col_end: 2,
+        span: Span::in_lines(&lines, 0, 0, 2),
         generic_data: Type::Integer,
     };
     let result = typechecker::get_type(program);
@@ -47,21 +42,18 @@ fn simple_number() {
 
 #[test]
 fn simple_string() {
+    let lines = vec!["\"Hello, World!\""];
     let program = BaseExpr {
         data: BaseExprData::Simple {
             expr: RecExpr {
                 data: RecExprData::String {
                     value: String::from("Hello, World!"),
                 },
-                row: 0,
-                col_start: 0,
-                col_end: 16,
+                span: Span::in_lines(&lines, 0, 0, 16),
                 generic_data: (),
             },
         },
-        row: 0,
-        col_start: 0,
-        col_end: 16,
+        span: Span::in_lines(&lines, 0, 0, 16),
         generic_data: (),
     };
 
@@ -71,15 +63,11 @@ fn simple_string() {
                 data: RecExprData::String {
                     value: String::from("Hello, World!"),
                 },
-                row: 0,
-                col_start: 0,
-                col_end: 16,
+                span: Span::in_lines(&lines, 0, 0, 16),
                 generic_data: Type::String,
             },
         },
-        row: 0,
-        col_start: 0,
-        col_end: 16,
+        span: Span::in_lines(&lines, 0, 0, 16),
         generic_data: Type::String,
     };
 
@@ -94,34 +82,27 @@ fn simple_string() {
 
 #[test]
 fn addition_of_integers() {
+    let lines = vec!["", " 5 + 10"];
     let program = BaseExpr {
         data: BaseExprData::Simple {
             expr: RecExpr {
                 data: RecExprData::Add {
                     left: Box::new(RecExpr {
                         data: RecExprData::Number { number: 5 },
-                        row: 1,
-                        col_start: 1,
-                        col_end: 2,
+                        span: Span::in_lines(&lines, 1, 1, 2),
                         generic_data: (),
                     }),
                     right: Box::new(RecExpr {
                         data: RecExprData::Number { number: 10 },
-                        row: 1,
-                        col_start: 5,
-                        col_end: 6,
+                        span: Span::in_lines(&lines, 1, 5, 6),
                         generic_data: (),
                     }),
                 },
-                row: 1,
-                col_start: 1,
-                col_end: 6,
+                span: Span::in_lines(&lines, 1, 1, 6),
                 generic_data: (),
             },
         },
-        row: 1,
-        col_start: 1,
-        col_end: 6,
+        span: Span::in_lines(&lines, 1, 1, 6),
         generic_data: (),
     };
 
@@ -131,28 +112,20 @@ fn addition_of_integers() {
                 data: RecExprData::Add {
                     left: Box::new(RecExpr {
                         data: RecExprData::Number { number: 5 },
-                        row: 1,
-                        col_start: 1,
-                        col_end: 2,
+                        span: Span::in_lines(&lines, 1, 1, 2),
                         generic_data: Type::Integer,
                     }),
                     right: Box::new(RecExpr {
                         data: RecExprData::Number { number: 10 },
-                        row: 1,
-                        col_start: 5,
-                        col_end: 6,
+                        span: Span::in_lines(&lines, 1, 5, 6),
                         generic_data: Type::Integer,
                     }),
                 },
-                row: 1,
-                col_start: 1,
-                col_end: 6,
+                span: Span::in_lines(&lines, 1, 1, 6),
                 generic_data: Type::Integer,
             },
         },
-        row: 1,
-        col_start: 1,
-        col_end: 6,
+        span: Span::in_lines(&lines, 1, 1, 6),
         generic_data: Type::Integer,
     };
     let result = typechecker::get_type(program);
@@ -166,36 +139,29 @@ fn addition_of_integers() {
 
 #[test]
 fn type_error_in_addition() {
+    let lines = vec!["", " 5 + \"Hello\""];
     let program = BaseExpr {
         data: BaseExprData::Simple {
             expr: RecExpr {
                 data: RecExprData::Add {
                     left: Box::new(RecExpr {
                         data: RecExprData::Number { number: 5 },
-                        row: 1,
-                        col_start: 1,
-                        col_end: 2,
+                        span: Span::in_lines(&lines, 1, 1, 2),
                         generic_data: (),
                     }),
                     right: Box::new(RecExpr {
                         data: RecExprData::String {
                             value: String::from("Hello"),
                         },
-                        row: 1,
-                        col_start: 5,
-                        col_end: 10,
+                        span: Span::in_lines(&lines, 1, 5, 10),
                         generic_data: (),
                     }),
                 },
-                row: 1,
-                col_start: 1,
-                col_end: 10,
+                span: Span::in_lines(&lines, 1, 1, 10),
                 generic_data: (),
             },
         },
-        row: 1,
-        col_start: 1,
-        col_end: 10,
+        span: Span::in_lines(&lines, 1, 1, 10),
         generic_data: (),
     };
 
@@ -206,9 +172,7 @@ fn type_error_in_addition() {
                 message,
                 expected,
                 found,
-                row,
-                col_start,
-                col_end,
+                span: Span { row, col_start, col_end, .. },
             } => {
                 //assert_eq!(message, "Cannot add types Integer and String");
                 assert_eq!(expected, Type::Integer);
@@ -228,3 +192,1075 @@ fn type_error_in_addition() {
 
 #[test]
 fn simple_variable() {}
+
+#[cfg(feature = "core")]
+#[test]
+fn annotate_source_with_inferred_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun double(x)",
+        "    return x * 2",
+        "a = double(3)",
+        "b = [a, 2]",
+        "for i in b",
+        "    println(i)",
+    ]);
+
+    let actual = match pipeline::run_annotate_pipeline(program) {
+        Ok(annotated_source) => annotated_source,
+        Err(e) => panic!("Annotation failed: {}", e),
+    };
+
+    #[rustfmt::skip]
+    let expected = [
+        "fun double(x)   # double(x: int) -> int",
+        "    return x * 2",
+        "a = double(3)   # a: int",
+        "b = [a, 2]      # b: list[int]",
+        "for i in b      # i: int",
+        "    println(i)",
+        "",
+    ].join("\n");
+
+    assert_eq!(actual, expected);
+}
+
+#[cfg(feature = "math")]
+#[test]
+fn builtin_function_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = max(1, abs(2))",
+        "b = min(a, 3)",
+    ]);
+
+    let annotated = pipeline::run_annotate_pipeline(program).unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        "a = max(1, abs(2))   # a: int",
+        "b = min(a, 3)        # b: int",
+        "",
+    ].join("\n");
+
+    assert_eq!(annotated, expected);
+
+    // Builtins only accept the argument types in their signature
+    let parsed = parser::parse_strings(Vec::from(["a = abs(\"text\")"])).unwrap();
+    assert!(typechecker::type_check_program(parsed, false).is_err());
+}
+
+#[test]
+fn generator_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun names()",
+        "    yield \"a\"",
+        "    yield \"b\"",
+        "for name in names()",
+        "    n = name",
+    ]);
+
+    let parsed = parser::parse_strings(program).unwrap();
+    let (_, functions) = typechecker::type_check_program(parsed, false).unwrap();
+    let names = functions.iter().find(|function| function.name == "names").unwrap();
+    assert_eq!(names.return_type, Type::Generator(Box::new(Type::String)));
+
+    // All yields of a generator must have the same type
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun mixed()",
+        "    yield 1",
+        "    yield \"b\"",
+        "x = mixed()",
+    ]);
+
+    let parsed = parser::parse_strings(program).unwrap();
+    assert!(matches!(
+        typechecker::type_check_program(parsed, false),
+        Err(Error::TypeError { .. })
+    ));
+}
+
+#[test]
+fn recursive_function_types() {
+    // The recursive calls get the type of the return statement before them
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun fib(n)",
+        "    if n < 2",
+        "        return n",
+        "    return fib(n - 1) + fib(n - 2)",
+        "x = fib(10)",
+    ]);
+
+    let parsed = parser::parse_strings(program).unwrap();
+    let (_, functions) = typechecker::type_check_program(parsed, false).unwrap();
+    let instances: Vec<_> = functions.iter().filter(|function| function.name == "fib").collect();
+    assert_eq!(instances.len(), 1);
+    assert_eq!(instances[0].return_type, Type::Integer);
+
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun forever(n)",
+        "    return forever(n - 1)",
+        "x = forever(10)",
+    ]);
+
+    let parsed = parser::parse_strings(program).unwrap();
+    match typechecker::type_check_program(parsed, false) {
+        Err(Error::LocationError { message, span: Span { row, .. } }) => {
+            assert_eq!(
+                message,
+                "The return type of forever is not known at this recursive call, return the value of the base case before it"
+            );
+            assert_eq!(row, 1);
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn delete_types() {
+    // A deleted variable can be defined again with another type
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 1",
+        "del a",
+        "a = \"text\"",
+    ]);
+
+    let annotated = pipeline::run_annotate_pipeline(program).unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        "a = 1        # a: int",
+        "del a",
+        "a = \"text\"   # a: string",
+        "",
+    ].join("\n");
+
+    assert_eq!(annotated, expected);
+
+    let parsed = parser::parse_strings(Vec::from(["a = 1", "del a", "b = a"])).unwrap();
+    assert!(typechecker::type_check_program(parsed, false).is_err());
+}
+
+#[cfg(all(feature = "core", feature = "io"))]
+#[test]
+fn error_value_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = try_read_file(\"name\")",
+        "b = is_error(a)",
+        "c = error_message(a)",
+        "d = unwrap(a)",
+    ]);
+
+    let annotated = pipeline::run_annotate_pipeline(program).unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        "a = try_read_file(\"name\")   # a: result[string]",
+        "b = is_error(a)             # b: bool",
+        "c = error_message(a)        # c: string",
+        "d = unwrap(a)               # d: string",
+        "",
+    ].join("\n");
+
+    assert_eq!(annotated, expected);
+
+    // The value of a result can only be used after unwrapping it
+    let parsed = parser::parse_strings(Vec::from(["a = try_from_base(\"1\", 2) + 1"])).unwrap();
+    assert!(typechecker::type_check_program(parsed, false).is_err());
+}
+
+#[test]
+fn while_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "i = 0",
+        "while i < 3",
+        "    i = i + 1",
+    ]);
+
+    let annotated = pipeline::run_annotate_pipeline(program).unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        "i = 0           # i: int",
+        "while i < 3",
+        "    i = i + 1   # i: int",
+        "",
+    ].join("\n");
+
+    assert_eq!(annotated, expected);
+
+    let parsed = parser::parse_strings(Vec::from(["while 1", "    a = 1"])).unwrap();
+    assert!(matches!(
+        typechecker::type_check_program(parsed, false),
+        Err(Error::TypeError {
+            expected: Type::Boolean,
+            found: Type::Integer,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn continue_outside_loop() {
+    let in_loop = parser::parse_strings(Vec::from(["while true", "    continue"])).unwrap();
+    assert!(typechecker::type_check_program(in_loop, false).is_ok());
+
+    #[rustfmt::skip]
+    let in_function = Vec::from([
+        "fun f()",
+        "    continue",
+        "for i in 3",
+        "    f()",
+    ]);
+    for program in [Vec::from(["continue"]), Vec::from(["if true", "    continue"]), in_function] {
+        let parsed = parser::parse_strings(program).unwrap();
+        match typechecker::type_check_program(parsed, false) {
+            Err(Error::LocationError { message, .. }) => {
+                assert_eq!(message, "Cannot continue outside of a loop")
+            }
+            other => panic!("Expected an error, got {:?}", other),
+        }
+    }
+}
+
+#[cfg(all(feature = "core", feature = "io"))]
+#[test]
+fn with_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "with open_file(\"name\", \"r\") as file",
+        "    lines = read_lines(file)",
+    ]);
+
+    let annotated = pipeline::run_annotate_pipeline(program).unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        "with open_file(\"name\", \"r\") as file   # file: resource",
+        "    lines = read_lines(file)          # lines: list[string]",
+        "",
+    ].join("\n");
+
+    assert_eq!(annotated, expected);
+
+    let parsed = parser::parse_strings(Vec::from(["with 5 as five", "    a = five"])).unwrap();
+    assert!(matches!(
+        typechecker::type_check_program(parsed, false),
+        Err(Error::TypeError { .. })
+    ));
+}
+
+#[test]
+fn match_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun describe(n)",
+        "    match n",
+        "        case 0",
+        "            return \"none\"",
+        "        case other",
+        "            half = other / 2",
+        "            return \"some\"",
+        "d = describe(3)",
+    ]);
+
+    let annotated = pipeline::run_annotate_pipeline(program).unwrap();
+    assert!(annotated.contains("# half: int"));
+    assert!(annotated.contains("# d: string"));
+
+    let check = |program: Vec<&str>| typechecker::type_check_program(parser::parse_strings(program).unwrap(), false);
+
+    // The patterns have the type of the value being matched
+    assert!(matches!(
+        check(Vec::from(["match 1", "    case \"one\"", "        a = 1"])),
+        Err(Error::TypeError { .. })
+    ));
+    // All cases return values of the same type
+    assert!(matches!(
+        check(Vec::from([
+            "fun f(n)",
+            "    match n",
+            "        case 0",
+            "            return 0",
+            "        case _",
+            "            return true",
+            "f(1)",
+        ])),
+        Err(Error::TypeError { .. })
+    ));
+    // Cases after one which matches every value are never reached
+    match check(Vec::from(["match 1", "    case _", "        a = 1", "    case 2", "        a = 2"])) {
+        Err(Error::LocationError { message, span: Span { row, .. } }) => {
+            assert_eq!(message, "Case 2 can never be reached, an earlier case matches all of its values");
+            assert_eq!(row, 3);
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+    assert!(check(Vec::from(["match 1", "    case 2", "        a = 1", "    case 2", "        a = 2"])).is_err());
+}
+
+#[cfg(all(feature = "core", feature = "list"))]
+#[test]
+fn nested_list_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "grid = [[1, 2], []]",
+        "row = grid[0]",
+        "cell = grid[1][0]",
+        "cells = make_grid(2, 3, \"x\")",
+    ]);
+
+    let annotated = pipeline::run_annotate_pipeline(program).unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        "grid = [[1, 2], []]            # grid: list[list[int]]",
+        "row = grid[0]                  # row: list[int]",
+        "cell = grid[1][0]              # cell: int",
+        "cells = make_grid(2, 3, \"x\")   # cells: list[list[string]]",
+        "",
+    ].join("\n");
+
+    assert_eq!(annotated, expected);
+
+    // Nested element types must match as well
+    let parsed = parser::parse_strings(Vec::from(["grid = [[1], [\"a\"]]"])).unwrap();
+    assert!(matches!(
+        typechecker::type_check_program(parsed, false),
+        Err(Error::TypeError { .. })
+    ));
+}
+
+#[cfg(all(feature = "core", feature = "math"))]
+#[test]
+fn rational_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "half = rational(1, 2)",
+        "sum = half + 1",
+        "less = 1 < half",
+    ]);
+
+    let annotated = pipeline::run_annotate_pipeline(program).unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        "half = rational(1, 2)   # half: rational",
+        "sum = half + 1          # sum: rational",
+        "less = 1 < half         # less: bool",
+        "",
+    ].join("\n");
+
+    assert_eq!(annotated, expected);
+
+    let parsed = parser::parse_strings(Vec::from(["x = rational(1, 2) + \"a\""])).unwrap();
+    assert!(matches!(
+        typechecker::type_check_program(parsed, false),
+        Err(Error::TypeError { .. })
+    ));
+}
+
+#[cfg(all(feature = "core", feature = "math"))]
+#[test]
+fn complex_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "z = complex(1, rational(1, 2))",
+        "w = z * z + 1",
+        "m = magnitude(w)",
+    ]);
+
+    let annotated = pipeline::run_annotate_pipeline(program).unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        "z = complex(1, rational(1, 2))   # z: complex",
+        "w = z * z + 1                    # w: complex",
+        "m = magnitude(w)                 # m: float",
+        "",
+    ].join("\n");
+
+    assert_eq!(annotated, expected);
+
+    // Complex numbers have no order
+    let parsed = parser::parse_strings(Vec::from(["b = complex(1, 2) < 3"])).unwrap();
+    assert!(matches!(
+        typechecker::type_check_program(parsed, false),
+        Err(Error::TypeError { .. })
+    ));
+}
+
+#[test]
+fn float_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 2.5",
+        "b = a * 2",
+        "c = 1e3 > b",
+        "d = -a",
+    ]);
+
+    let annotated = pipeline::run_annotate_pipeline(program).unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        "a = 2.5       # a: float",
+        "b = a * 2     # b: float",
+        "c = 1e3 > b   # c: bool",
+        "d = -a        # d: float",
+        "",
+    ].join("\n");
+
+    assert_eq!(annotated, expected);
+}
+
+#[test]
+fn struct_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "struct Point",
+        "    x",
+        "    y",
+        "p = Point(1, 2.5)",
+        "a = p.x",
+        "b = p.y",
+    ]);
+
+    let parsed = parser::parse_strings(program).unwrap();
+    let (typed, _) = typechecker::type_check_program(parsed, false).unwrap();
+    let variable_type = |index: usize| match &typed[index].data {
+        BaseExprData::VariableAssignment { expr, .. } => expr.generic_data.clone(),
+        other => panic!("Expected an assignment, got {:?}", other),
+    };
+
+    // The field types are those of the values the struct was constructed with
+    assert_eq!(
+        variable_type(1),
+        Type::Struct(Box::new(StructType {
+            name: String::from("Point"),
+            fields: Vec::from([
+                (String::from("x"), Type::Integer),
+                (String::from("y"), Type::Float),
+            ]),
+        }))
+    );
+    assert_eq!(variable_type(2), Type::Integer);
+    assert_eq!(variable_type(3), Type::Float);
+
+    let message = |program: Vec<&str>| {
+        let parsed = parser::parse_strings(program).unwrap();
+        match typechecker::type_check_program(parsed, false) {
+            Err(Error::LocationError { message, .. }) => message,
+            other => panic!("Expected an error, got {:?}", other),
+        }
+    };
+    assert_eq!(
+        message(Vec::from(["struct Point", "    x", "p = Point(1)", "a = p.z"])),
+        "Struct 'Point' has no field 'z'"
+    );
+    assert_eq!(
+        message(Vec::from(["struct Point", "    x", "p = Point(1, 2)"])),
+        "Struct 'Point' has 1 fields, but 2 values were provided"
+    );
+    assert_eq!(
+        message(Vec::from(["p = 1", "a = p.x"])),
+        "Cannot access field 'x' of a value of type int"
+    );
+}
+
+#[test]
+fn field_assignment_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "struct Point",
+        "    x",
+        "p = Point(1)",
+        "p.x = p.x * 2",
+    ]);
+    let parsed = parser::parse_strings(program).unwrap();
+    assert!(typechecker::type_check_program(parsed, false).is_ok());
+
+    // The field keeps the type it was constructed with
+    let parsed = parser::parse_strings(Vec::from(["struct Point", "    x", "p = Point(1)", "p.x = \"a\""])).unwrap();
+    match typechecker::type_check_program(parsed, false) {
+        Err(Error::TypeError {
+            message,
+            expected,
+            found,
+            span: Span { row, col_start, col_end, .. },
+        }) => {
+            assert_eq!(
+                message,
+                "Cannot assign a value of type string to field 'x' of struct 'Point', which is of type int"
+            );
+            assert_eq!((expected, found), (Type::Integer, Type::String));
+            assert_eq!((row, col_start, col_end), (3, 6, 9));
+        }
+        other => panic!("Expected a type error, got {:?}", other),
+    }
+
+    let parsed = parser::parse_strings(Vec::from(["p = 1", "p.x = 2"])).unwrap();
+    match typechecker::type_check_program(parsed, false) {
+        Err(Error::LocationError { message, .. }) => {
+            assert_eq!(message, "Cannot assign to field 'x' of a value of type int")
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn nested_assignment_types() {
+    let check = |program: Vec<&str>| typechecker::type_check_program(parser::parse_strings(program).unwrap(), false);
+
+    // Every step of the path is checked, and an empty dict at its end takes the type of the new entry
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "struct Point",
+        "    x",
+        "struct Shape",
+        "    center",
+        "grid = [[1, 2], [3, 4]]",
+        "grid[1][0] = 9",
+        "shapes = [Shape(Point(0.5))]",
+        "shapes[0].center.x = 1.5",
+        "groups = {\"a\": {}}",
+        "groups[\"a\"][1] = true",
+        "flag = groups[\"a\"][1]",
+    ]);
+    let (typed, _) = check(program).unwrap();
+    match &typed.last().unwrap().data {
+        BaseExprData::VariableAssignment { expr, .. } => assert_eq!(expr.generic_data, Type::Boolean),
+        other => panic!("Expected a variable assignment, got {:?}", other),
+    }
+
+    // The errors point at the index, field or value which is wrong
+    #[rustfmt::skip]
+    let programs = [
+        (Vec::from(["grid = [[1, 2]]", "grid[0][1] = \"a\""]), 13, "Cannot assign a value of type string to an element of 'grid[0]', which is of type list[int]"),
+        (Vec::from(["grid = [[1, 2]]", "grid[\"a\"][1] = 3"]), 5, "List index must be an integer"),
+        (Vec::from(["struct Point", "    x", "p = [Point(1.5)]", "p[0].y.z = 2"]), 5, "Struct 'Point' has no field 'y'"),
+        (Vec::from(["struct Point", "    x", "p = [Point(1.5)]", "p[0].x = 2"]), 9, "Cannot assign a value of type int to field 'x' of struct 'Point', which is of type float"),
+    ];
+    for (program, expected_col, expected_message) in programs {
+        match check(program) {
+            Err(error @ (Error::TypeError { .. } | Error::LocationError { .. })) => {
+                assert_eq!(error.message(), expected_message);
+                assert_eq!(error.span().map(|span| span.col_start), Some(expected_col));
+            }
+            other => panic!("Expected an error for {}, got {:?}", expected_message, other),
+        }
+    }
+
+    // The types in the error are those of the element and the assigned value
+    for program in [Vec::from(["xs = [1, 2]", "xs[0] = \"a\""]), Vec::from(["ages = {\"ann\": 31}", "ages[\"bo\"] = \"a\""])] {
+        match check(program) {
+            Err(Error::TypeError { expected, found, .. }) => {
+                assert_eq!(expected, Type::Integer);
+                assert_eq!(found, Type::String);
+            }
+            other => panic!("Expected a type error, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn dict_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "ages = {\"ann\": 31}",
+        "age = ages[\"ann\"]",
+        "empty = {}",
+        "empty[1] = [true]",
+        "first = empty[1][0]",
+    ]);
+    let parsed = parser::parse_strings(program).unwrap();
+    let typed_program = typechecker::type_check_program(parsed, false).unwrap();
+    let assigned_types: Vec<Type> = typed_program
+        .0
+        .iter()
+        .filter_map(|base_expr| match &base_expr.data {
+            BaseExprData::VariableAssignment { expr, .. } => Some(expr.generic_data.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        assigned_types,
+        Vec::from([
+            Type::Dict(Box::new(Type::String), Box::new(Type::Integer)),
+            Type::Integer,
+            Type::Dict(Box::new(Type::Undefined), Box::new(Type::Undefined)),
+            Type::Boolean,
+        ])
+    );
+
+    let type_error = |program: Vec<&str>| match typechecker::type_check_program(parser::parse_strings(program).unwrap(), false) {
+        Err(Error::TypeError {
+            message,
+            expected,
+            found,
+            ..
+        }) => (message, expected, found),
+        other => panic!("Expected a type error, got {:?}", other),
+    };
+    assert_eq!(
+        type_error(Vec::from(["d = {1: 2, 3: \"a\"}"])),
+        (String::from("Dict values must be of the same type"), Type::Integer, Type::String)
+    );
+    assert_eq!(
+        type_error(Vec::from(["d = {1: 2}", "x = d[\"a\"]"])),
+        (String::from("Dict key is of the wrong type"), Type::Integer, Type::String)
+    );
+    assert_eq!(
+        type_error(Vec::from(["d = {1: 2}", "d[\"a\"] = 3"])),
+        (String::from("Dict key is of the wrong type"), Type::Integer, Type::String)
+    );
+
+    let parsed = parser::parse_strings(Vec::from(["d = {[1]: 2}"])).unwrap();
+    match typechecker::type_check_program(parsed, false) {
+        Err(Error::LocationError { message, .. }) => {
+            assert_eq!(message, "Dict keys must be integers, booleans or strings, found list[int]")
+        }
+        other => panic!("Expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn tuple_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "person = (\"ann\", 31, true)",
+        "age = person[1]",
+        "pairs = [(1, []), (2, [3])]",
+        "first = pairs[0][1]",
+    ]);
+    let parsed = parser::parse_strings(program).unwrap();
+    let typed_program = typechecker::type_check_program(parsed, false).unwrap();
+    let assigned_types: Vec<Type> = typed_program
+        .0
+        .iter()
+        .filter_map(|base_expr| match &base_expr.data {
+            BaseExprData::VariableAssignment { expr, .. } => Some(expr.generic_data.clone()),
+            _ => None,
+        })
+        .collect();
+    let pair_type = Type::Tuple(Vec::from([Type::Integer, Type::List(Box::new(Type::Integer))]));
+    assert_eq!(
+        assigned_types,
+        Vec::from([
+            Type::Tuple(Vec::from([Type::String, Type::Integer, Type::Boolean])),
+            Type::Integer,
+            Type::List(Box::new(pair_type)),
+            Type::List(Box::new(Type::Integer)),
+        ])
+    );
+
+    let error_message = |program: Vec<&str>| match typechecker::type_check_program(parser::parse_strings(program).unwrap(), false) {
+        Err(Error::LocationError { message, .. }) => message,
+        other => panic!("Expected an error, got {:?}", other),
+    };
+    assert_eq!(
+        error_message(Vec::from(["t = (1, 2)", "x = t[2]"])),
+        "Index 2 out of bounds for tuple of length 2"
+    );
+    assert_eq!(
+        error_message(Vec::from(["t = (1, 2)", "i = 0", "x = t[i]"])),
+        "Tuple index must be an integer literal, like t[0]"
+    );
+    assert_eq!(
+        error_message(Vec::from(["t = (1, 2)", "t[0] = 3"])),
+        "Cannot assign to an element of a value of type tuple[int, int]"
+    );
+}
+
+#[cfg(all(feature = "core", feature = "list", feature = "string"))]
+#[test]
+fn function_signature_help() {
+    let message = |program: Vec<&str>| {
+        let parsed = parser::parse_strings(program).unwrap();
+        match typechecker::type_check_program(parsed, false) {
+            Err(Error::LocationError { message, .. }) => message,
+            other => panic!("Expected a call error, got {:?}", other),
+        }
+    };
+
+    // Every number of arguments a builtin accepts is listed, with the types accepted for each parameter
+    assert_eq!(
+        message(Vec::from(["a = pad_left(1)"])),
+        [
+            "Function 'pad_left' cannot be called with (int), it can be called as:",
+            "    pad_left(text: string, width: int)",
+            "    pad_left(text: string, width: int, fill: string)",
+        ]
+        .join("\n")
+    );
+    assert_eq!(
+        message(Vec::from(["a = slice(true, 1, 2)"])),
+        [
+            "Function 'slice' cannot be called with (bool, int, int), it can be called as:",
+            "    slice(text: string | bytes, start: int, end: int)",
+        ]
+        .join("\n")
+    );
+    assert_eq!(
+        message(Vec::from(["a = make_grid(2)"])),
+        [
+            "Function 'make_grid' cannot be called with (int), it can be called as:",
+            "    make_grid(rows: int, cols: int, fill: any)",
+        ]
+        .join("\n")
+    );
+
+    // Parameters of functions defined in the program have no declared type
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun add(a, b)",
+        "    return a + b",
+        "c = add(1)",
+    ]);
+    assert_eq!(
+        message(program),
+        [
+            "Function 'add' cannot be called with (int), it can be called as:",
+            "    add(a, b)",
+        ]
+        .join("\n")
+    );
+
+    assert_eq!(message(Vec::from(["a = missing(1)"])), "Function 'missing' not found");
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn typecheck_directory() {
+    let directory = std::env::temp_dir().join("rosy_typecheck_directory_test");
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(directory.join("nested")).unwrap();
+    std::fs::write(directory.join("good.rosy"), "a = 1\nprintln(a)").unwrap();
+    std::fs::write(directory.join("nested").join("bad.rosy"), "b = 1 + true").unwrap();
+    std::fs::write(directory.join("notes.txt"), "not rosy").unwrap();
+
+    let files = pipeline::find_source_files(&directory).unwrap();
+    assert_eq!(
+        files,
+        Vec::from([directory.join("good.rosy"), directory.join("nested").join("bad.rosy")])
+    );
+
+    let results = pipeline::typecheck_files(
+        files
+            .into_iter()
+            .map(|file| (file, rosy::language::LanguageOptions::default()))
+            .collect(),
+    );
+    assert!(results[0].error.is_none());
+    assert!(matches!(results[1].error, Some(Error::TypeError { span: Span { row: 0, .. }, .. })));
+    assert!(!pipeline::print_typecheck_report(&results));
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn closure_types() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun scaled(values, factor)",
+        "    fun scale(value)",
+        "        return value * factor",
+        "    total = 0",
+        "    for value in values",
+        "        total = total + scale(value)",
+        "    return total",
+        "s = scaled([1, 2], 3)",
+    ]);
+
+    let (_, functions) = typechecker::type_check_program(parser::parse_strings(program).unwrap(), false).unwrap();
+
+    // The nested function records the variable it captures with its type in the enclosing function
+    let scale = functions.iter().find(|function| function.name == "scale").unwrap();
+    assert_eq!(scale.captured, vec![(String::from("factor"), Type::Integer)]);
+    assert_eq!(scale.return_type, Type::Integer);
+    let scaled = functions.iter().find(|function| function.name == "scaled").unwrap();
+    assert!(scaled.captured.is_empty());
+
+    // A nested function cannot be called outside the function it is defined in
+    #[rustfmt::skip]
+    let outside = Vec::from([
+        "fun outer()",
+        "    fun inner()",
+        "        return 1",
+        "    return inner()",
+        "a = outer()",
+        "b = inner()",
+    ]);
+    assert!(typechecker::type_check_program(parser::parse_strings(outside).unwrap(), false).is_err());
+}
+
+#[test]
+fn default_parameter_types() {
+    let check = |program: Vec<&str>| typechecker::type_check_program(parser::parse_strings(program).unwrap(), false);
+
+    // A call leaving out a parameter gets its default as the argument
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun scale(x, factor = 2.5)",
+        "    return x * factor",
+        "a = scale(2)",
+        "b = scale(2, 4.0)",
+    ]);
+    let (typed, functions) = check(program).unwrap();
+    match &typed[0].data {
+        BaseExprData::VariableAssignment { expr, .. } => match &expr.data {
+            RecExprData::FunctionCall { args, .. } => {
+                assert_eq!(args.len(), 2);
+                assert_eq!(args[1].generic_data, Type::Float);
+            }
+            other => panic!("Expected a call, got {:?}", other),
+        },
+        other => panic!("Expected a variable assignment, got {:?}", other),
+    }
+    assert_eq!(functions.iter().filter(|function| function.name == "scale").count(), 1);
+
+    // An argument given for a parameter with a default must have the type of the default
+    #[rustfmt::skip]
+    let mismatch = Vec::from([
+        "fun scale(x, factor = 2.5)",
+        "    return x * factor",
+        "a = scale(2, \"big\")",
+    ]);
+    match check(mismatch) {
+        Err(Error::TypeError {
+            expected,
+            found,
+            span: Span { col_start, .. },
+            ..
+        }) => {
+            assert_eq!((expected, found), (Type::Float, Type::String));
+            assert_eq!(col_start, 13);
+        }
+        other => panic!("Expected a type error, got {:?}", other),
+    }
+}
+
+#[cfg(all(feature = "core", feature = "string"))]
+#[test]
+fn keyword_argument_types() {
+    let check = |program: Vec<&str>| typechecker::type_check_program(parser::parse_strings(program).unwrap(), false);
+
+    // The arguments of a typed call are in the order of the parameters, with the defaults filled in
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun label(text, count, suffix = \"!\")",
+        "    return repeat(text, count) + suffix",
+        "a = label(count = 2, text = \"x\")",
+    ]);
+    let (typed, _) = check(program).unwrap();
+    match &typed[0].data {
+        BaseExprData::VariableAssignment { expr, .. } => match &expr.data {
+            RecExprData::FunctionCall { args, .. } => {
+                let types: Vec<Type> = args.iter().map(|arg| arg.generic_data.clone()).collect();
+                assert_eq!(types, [Type::String, Type::Integer, Type::String]);
+            }
+            other => panic!("Expected a call, got {:?}", other),
+        },
+        other => panic!("Expected a variable assignment, got {:?}", other),
+    }
+
+    // The name must be that of a parameter, which is given a value once
+    #[rustfmt::skip]
+    let programs = [
+        ("a = label(1, size = 2)", "label has no parameter size", 13),
+        ("a = label(1, text = 2)", "The parameter text of label is given a value twice", 13),
+        ("a = label(count = 2)", "No value is given for the parameter text of label", 4),
+    ];
+    for (call, expected_message, expected_col) in programs {
+        let program = Vec::from(["fun label(text, count)", "    return count", call]);
+        match check(program) {
+            Err(Error::LocationError { message, span: Span { col_start, .. } }) => {
+                assert_eq!(message, expected_message);
+                assert_eq!(col_start, expected_col);
+            }
+            other => panic!("Expected an error, got {:?}", other),
+        }
+    }
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn rest_parameter_types() {
+    let check = |program: Vec<&str>| typechecker::type_check_program(parser::parse_strings(program).unwrap(), false);
+
+    // The arguments for the rest parameter are passed as a list
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun first(label, *values)",
+        "    return values[0]",
+        "a = first(\"x\", 1.5, 2.5)",
+    ]);
+    let (typed, functions) = check(program).unwrap();
+    match &typed[0].data {
+        BaseExprData::VariableAssignment { expr, .. } => {
+            assert_eq!(expr.generic_data, Type::Float);
+            match &expr.data {
+                RecExprData::FunctionCall { args, .. } => {
+                    assert_eq!(args.len(), 2);
+                    assert_eq!(args[1].generic_data, Type::List(Box::new(Type::Float)));
+                    assert!(matches!(&args[1].data, RecExprData::List { elements } if elements.len() == 2));
+                }
+                other => panic!("Expected a call, got {:?}", other),
+            }
+        }
+        other => panic!("Expected a variable assignment, got {:?}", other),
+    }
+    let first = functions.iter().find(|function| function.name == "first").unwrap();
+    assert_eq!(first.param_types, [Type::String, Type::List(Box::new(Type::Float))]);
+
+    // The collected arguments must have the same type
+    #[rustfmt::skip]
+    let mismatch = Vec::from([
+        "fun first(label, *values)",
+        "    return label",
+        "a = first(\"x\", 1, \"two\")",
+    ]);
+    match check(mismatch) {
+        Err(Error::TypeError {
+            expected,
+            found,
+            span: Span { col_start, .. },
+            ..
+        }) => {
+            assert_eq!((expected, found), (Type::Integer, Type::String));
+            assert_eq!(col_start, 18);
+        }
+        other => panic!("Expected a type error, got {:?}", other),
+    }
+
+    // print takes one or more values that can be printed
+    assert!(check(Vec::from(["print(\"a\", 1, 2.5)"])).is_ok());
+    assert!(check(Vec::from(["print()"])).is_err());
+}
+
+#[test]
+fn destructuring_assignment_types() {
+    let check = |program: Vec<&str>| typechecker::type_check_program(parser::parse_strings(program).unwrap(), false);
+
+    // Every variable takes the type of its element of the tuple, or the element type of the list
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun pair()",
+        "    return \"a\", 1",
+        "name, count = pair()",
+        "x, y = [1.5, 2.5]",
+        "total = count + 1",
+        "scaled = x * y",
+    ]);
+    let (typed, _) = check(program).unwrap();
+    let types: Vec<Type> = typed[2..]
+        .iter()
+        .map(|base_expr| match &base_expr.data {
+            BaseExprData::VariableAssignment { expr, .. } => expr.generic_data.clone(),
+            other => panic!("Expected a variable assignment, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(types, [Type::Integer, Type::Float]);
+
+    // A tuple must have as many elements as there are variables, and other values cannot be split
+    #[rustfmt::skip]
+    let programs = [
+        (Vec::from(["a, b, c = (1, 2)"]), "Cannot assign 2 values to 3 variables"),
+        (Vec::from(["a, b = 5"]), "Cannot assign a value of type int to 2 variables, only a tuple or list can be split"),
+    ];
+    for (program, expected_message) in programs {
+        match check(program) {
+            Err(error) => assert_eq!(error.message(), expected_message),
+            Ok(_) => panic!("Expected an error for {}", expected_message),
+        }
+    }
+}
+
+#[test]
+fn conditional_types() {
+    let check = |program: Vec<&str>| typechecker::type_check_program(parser::parse_strings(program).unwrap(), false);
+
+    let (typed, _) = check(Vec::from(["n = 3", "label = \"big\" if n > 2 else \"small\""])).unwrap();
+    match &typed[1].data {
+        BaseExprData::VariableAssignment { expr, .. } => assert_eq!(expr.generic_data, Type::String),
+        other => panic!("Expected a variable assignment, got {:?}", other),
+    }
+
+    // The condition must be a bool, and the value after else must have the type of the one before if
+    #[rustfmt::skip]
+    let programs = [
+        ("x = 1 if 2 else 3", "The condition of a conditional expression must be a bool", 9),
+        ("x = 1 if true else 2.5", "Both values of a conditional expression must have the same type", 19),
+    ];
+    for (line, expected_message, expected_col) in programs {
+        match check(Vec::from([line])) {
+            Err(error) => {
+                assert_eq!(error.message(), expected_message);
+                assert_eq!(error.span().map(|span| span.col_start), Some(expected_col));
+            }
+            Ok(_) => panic!("Expected an error for {}", line),
+        }
+    }
+}
+
+#[test]
+fn slice_types() {
+    let check = |program: Vec<&str>| typechecker::type_check_program(parser::parse_strings(program).unwrap(), false);
+
+    // A slice has the type of what is sliced
+    let (typed, _) = check(Vec::from(["xs = [1.5, 2.5]", "a = xs[1:]", "b = \"hello\"[:2]"])).unwrap();
+    let types: Vec<Type> = typed[1..]
+        .iter()
+        .map(|expression| match &expression.data {
+            BaseExprData::VariableAssignment { expr, .. } => expr.generic_data.clone(),
+            other => panic!("Expected a variable assignment, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(types, [Type::List(Box::new(Type::Float)), Type::String]);
+
+    #[rustfmt::skip]
+    let programs = [
+        ("x = 5[1:2]", "Cannot slice a value of type int", 4),
+        ("x = [1, 2][0.5:]", "Slice bounds must be integers", 11),
+        ("x = [1, 2][:true]", "Slice bounds must be integers", 12),
+    ];
+    for (line, expected_message, expected_col) in programs {
+        match check(Vec::from([line])) {
+            Err(error) => {
+                assert_eq!(error.message(), expected_message);
+                assert_eq!(error.span().map(|span| span.col_start), Some(expected_col));
+            }
+            Ok(_) => panic!("Expected an error for {}", line),
+        }
+    }
+}
+
+#[test]
+fn type_display() {
+    // Types are written as in a program, nested types inside square brackets
+    #[rustfmt::skip]
+    let types = [
+        (Type::Integer, "int"),
+        (Type::Boolean, "bool"),
+        (Type::List(Box::new(Type::List(Box::new(Type::Integer)))), "list[list[int]]"),
+        (Type::Dict(Box::new(Type::String), Box::new(Type::Float)), "dict[string, float]"),
+        (Type::Tuple(Vec::from([Type::Integer, Type::Result(Box::new(Type::String))])), "tuple[int, result[string]]"),
+    ];
+    for (value_type, expected) in types {
+        assert_eq!(value_type.to_string(), expected);
+    }
+
+    // A type error names the types the same way
+    let program = Vec::from(["a = [1, 2]", "b = a + true"]);
+    match typechecker::type_check_program(parser::parse_strings(program).unwrap(), false) {
+        Err(error @ Error::TypeError { .. }) => assert!(!error.message().contains("Integer")),
+        other => panic!("Expected a type error, got {:?}", other),
+    }
+}
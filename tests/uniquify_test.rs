@@ -1,4 +1,5 @@
 use rosy::parser::{self, BaseExpr, BaseExprData, RecExpr, RecExprData};
+use rosy::tokenizer::Span;
 use rosy::pipeline::print_error;
 use rosy::tokenizer::Error;
 use rosy::typechecker::Type::*;
@@ -12,20 +13,17 @@ use std::collections::{HashMap, HashSet};
 
 #[test]
 fn test_uniquify_simple() {
+    let lines = vec!["x = 5"];
     let mut program = (vec![BaseExpr {
         data: BaseExprData::VariableAssignment {
             var_name: "x".to_string(),
             expr: RecExpr {
                 data: RecExprData::Number { number: 5 },
-                row: 0,
-                col_start: 5,
-                col_end: 6,
+                span: Span::in_lines(&lines, 0, 5, 6),
                 generic_data: Integer,
             },
         },
-        row: 0,
-        col_start: 0,
-        col_end: 6,
+        span: Span::in_lines(&lines, 0, 0, 6),
         generic_data: Integer,
     }],
     // The function list is empty for this test
@@ -37,15 +35,11 @@ fn test_uniquify_simple() {
             var_name: "x0".to_string(),
             expr: RecExpr {
                 data: RecExprData::Number { number: 5 },
-                row: 0,
-                col_start: 5,
-                col_end: 6,
+                span: Span::in_lines(&lines, 0, 5, 6),
                 generic_data: Integer,
             },
         },
-        row: 0,
-        col_start: 0,
-        col_end: 6,
+        span: Span::in_lines(&lines, 0, 0, 6),
         generic_data: Integer,
     }],
     // The function list is empty for this test
@@ -58,6 +52,13 @@ fn test_uniquify_simple() {
 
 #[test]
 fn test_function() {
+    #[rustfmt::skip]
+    let lines = vec![
+        "a = 5",
+        "fun f(a)",
+        "    return a + 2",
+        "f(a)",
+    ];
     let mut program = (vec![BaseExpr {
             data: VariableAssignment {
                 var_name: "a".to_string(),
@@ -65,15 +66,11 @@ fn test_function() {
                     data: Number {
                         number: 5,
                     },
-                    row: 0,
-                    col_start: 4,
-                    col_end: 5,
+                    span: Span::in_lines(&lines, 0, 4, 5),
                     generic_data: Integer,
                 },
             },
-            row: 0,
-            col_start: 0,
-            col_end: 5,
+            span: Span::in_lines(&lines, 0, 0, 5),
             generic_data: Undefined,
         },
         BaseExpr {
@@ -86,22 +83,16 @@ fn test_function() {
                                 data: Variable {
                                     name: "a".to_string(),
                                 },
-                                row: 3,
-                                col_start: 2,
-                                col_end: 3,
+                                span: Span::in_lines(&lines, 3, 2, 3),
                                 generic_data: Integer,
                             },
                         ],
                     },
-                    row: 3,
-                    col_start: 0,
-                    col_end: 4,
+                    span: Span::in_lines(&lines, 3, 0, 4),
                     generic_data: Integer,
                 },
             },
-            row: 3,
-            col_start: 0,
-            col_end: 4,
+            span: Span::in_lines(&lines, 3, 0, 4),
             generic_data: Integer,
         },],
     vec![FunctionType {
@@ -113,6 +104,7 @@ fn test_function() {
                 Integer,
             ],
             is_used: false,
+            captured: Vec::new(),
             return_type: Integer,
             content: vec![
                 BaseExpr {
@@ -124,31 +116,23 @@ fn test_function() {
                                         data: Variable {
                                             name: "a".to_string(),
                                         },
-                                        row: 2,
-                                        col_start: 11,
-                                        col_end: 12,
+                                        span: Span::in_lines(&lines, 2, 11, 12),
                                         generic_data: Integer,
                                     }),
                                     right: Box::new(RecExpr {
                                         data: Number {
                                             number: 2,
                                         },
-                                        row: 2,
-                                        col_start: 15,
-                                        col_end: 16,
+                                        span: Span::in_lines(&lines, 2, 15, 16),
                                         generic_data: Integer,
                                     }),
                                 },
-                                row: 2,
-                                col_start: 11,
-                                col_end: 16,
+                                span: Span::in_lines(&lines, 2, 11, 16),
                                 generic_data: Integer,
                             },
                         ),
                     },
-                    row: 2,
-                    col_start: 4,
-                    col_end: 16,
+                    span: Span::in_lines(&lines, 2, 4, 16),
                     generic_data: Integer,
                 },
             ],
@@ -162,15 +146,11 @@ fn test_function() {
                     data: Number {
                         number: 5,
                     },
-                    row: 0,
-                    col_start: 4,
-                    col_end: 5,
+                    span: Span::in_lines(&lines, 0, 4, 5),
                     generic_data: Integer,
                 },
             },
-            row: 0,
-            col_start: 0,
-            col_end: 5,
+            span: Span::in_lines(&lines, 0, 0, 5),
             generic_data: Undefined,
         },
         BaseExpr {
@@ -183,22 +163,16 @@ fn test_function() {
                                 data: Variable {
                                     name: "a1".to_string(),
                                 },
-                                row: 3,
-                                col_start: 2,
-                                col_end: 3,
+                                span: Span::in_lines(&lines, 3, 2, 3),
                                 generic_data: Integer,
                             },
                         ],
                     },
-                    row: 3,
-                    col_start: 0,
-                    col_end: 4,
+                    span: Span::in_lines(&lines, 3, 0, 4),
                     generic_data: Integer,
                 },
             },
-            row: 3,
-            col_start: 0,
-            col_end: 4,
+            span: Span::in_lines(&lines, 3, 0, 4),
             generic_data: Integer,
         },],
     vec![FunctionType {
@@ -210,6 +184,7 @@ fn test_function() {
                 Integer,
             ],
             is_used: false,
+            captured: Vec::new(),
             return_type: Integer,
             content: vec![
                 BaseExpr {
@@ -221,31 +196,23 @@ fn test_function() {
                                         data: Variable {
                                             name: "a0".to_string(),
                                         },
-                                        row: 2,
-                                        col_start: 11,
-                                        col_end: 12,
+                                        span: Span::in_lines(&lines, 2, 11, 12),
                                         generic_data: Integer,
                                     }),
                                     right: Box::new(RecExpr {
                                         data: Number {
                                             number: 2,
                                         },
-                                        row: 2,
-                                        col_start: 15,
-                                        col_end: 16,
+                                        span: Span::in_lines(&lines, 2, 15, 16),
                                         generic_data: Integer,
                                     }),
                                 },
-                                row: 2,
-                                col_start: 11,
-                                col_end: 16,
+                                span: Span::in_lines(&lines, 2, 11, 16),
                                 generic_data: Integer,
                             },
                         ),
                     },
-                    row: 2,
-                    col_start: 4,
-                    col_end: 16,
+                    span: Span::in_lines(&lines, 2, 4, 16),
                     generic_data: Integer,
                 },
             ],
@@ -0,0 +1,131 @@
+use rosy::desugarer;
+use rosy::parser;
+use rosy::parser::BaseExpr;
+use rosy::parser::BaseExprData;
+use rosy::parser::RecExprData;
+use rosy::tac::TacInstruction;
+use rosy::tac::TacValue;
+use rosy::tac::VariableValue;
+use rosy::tokenizer::Error;
+use rosy::typechecker;
+use rosy::typechecker::Type;
+use rosy::verifier;
+
+fn error_message<T>(result: Result<T, Error>) -> String {
+    match result {
+        Err(Error::LocationError { message, .. }) | Err(Error::SimpleError { message }) => message,
+        Err(other) => panic!("Expected a located or simple error, got {:?}", other),
+        Ok(_) => panic!("Expected an error"),
+    }
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn valid_programs_pass_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "fun count_to(limit)",
+        "\ttotal = 0",
+        "\tfor i in limit",
+        "\t\tif i == 2",
+        "\t\t\tcontinue",
+        "\t\ttotal += i",
+        "\treturn (total, [total])",
+        "ages = {\"ann\": 31}",
+        "result = count_to(ages[\"ann\"])",
+        "println(result[1][0] * -2)",
+    ]);
+
+    let parsed = parser::parse_strings(program).unwrap();
+    assert_eq!(verifier::check_program(&parsed), Ok(()));
+
+    let desugared = desugarer::desugar(parsed);
+    assert_eq!(verifier::check_program(&desugared), Ok(()));
+
+    let typed_program = typechecker::type_check_program(desugared, false).unwrap();
+    assert_eq!(verifier::check_typed_program(&typed_program), Ok(()));
+}
+
+#[test]
+fn spans_out_of_order_test() {
+    let mut parsed = parser::parse_strings(Vec::from(["x = 1 + 2", "y = x"])).unwrap();
+
+    // The right operand moved in front of the addition
+    if let BaseExprData::VariableAssignment { expr, .. } = &mut parsed[0].data {
+        if let RecExprData::Add { right, .. } = &mut expr.data {
+            right.span.col_start = 2;
+        }
+    }
+    assert_eq!(
+        error_message(verifier::check_program(&parsed)),
+        "The node starts at 1:3, before the node around it at 1:5"
+    );
+
+    let mut parsed = parser::parse_strings(Vec::from(["x = 1", "y = x"])).unwrap();
+    parsed.swap(0, 1);
+    assert_eq!(
+        error_message(verifier::check_program(&parsed)),
+        "The statement on line 1 comes after a statement on line 2"
+    );
+}
+
+#[test]
+fn typed_program_invariants_test() {
+    let parsed = parser::parse_strings(Vec::from(["x = 1 + 2"])).unwrap();
+    let mut typed_program = typechecker::type_check_program(parsed, false).unwrap();
+
+    if let BaseExprData::VariableAssignment { expr, .. } = &mut typed_program.0[0].data {
+        expr.generic_data = Type::Undefined;
+    }
+    assert!(error_message(verifier::check_typed_program(&typed_program)).ends_with("has no type"));
+
+    let parsed = parser::parse_strings(Vec::from(["x = 1"])).unwrap();
+    let mut typed_program = typechecker::type_check_program(parsed, false).unwrap();
+    let span = typed_program.0[0].span();
+    typed_program
+        .0
+        .push(BaseExpr::new(BaseExprData::Continue, span, Type::Undefined));
+    assert_eq!(
+        error_message(verifier::check_typed_program(&typed_program)),
+        "continue outside of a loop"
+    );
+}
+
+#[test]
+fn tac_jumps_test() {
+    let goto_into_function = Vec::from([
+        TacInstruction::FunctionLabel("func_f".to_string(), Vec::new()),
+        TacInstruction::Label("L0".to_string()),
+        TacInstruction::Return(None),
+        TacInstruction::ProgramStart(),
+        TacInstruction::Goto("L0".to_string()),
+    ]);
+    assert_eq!(
+        verifier::check_tac(&goto_into_function),
+        Err((4, "The jump to L0 leaves the function it is in".to_string()))
+    );
+
+    let call_to_label = Vec::from([
+        TacInstruction::ProgramStart(),
+        TacInstruction::Label("L0".to_string()),
+        TacInstruction::Call(
+            "L0".to_string(),
+            Vec::from([TacValue::Constant(1)]),
+            Some(VariableValue::Variable("x".to_string())),
+        ),
+    ]);
+    assert_eq!(
+        error_message(verifier::check_tac_instructions(&call_to_label)),
+        "Unknown function label L0 at instruction 2: %x = call L0(1)"
+    );
+}
+
+#[test]
+fn verify_names_the_pass_test() {
+    // Tests run in debug builds, where verification is always on
+    let call_to_nothing = Vec::from([TacInstruction::Call("func_f".to_string(), Vec::new(), None)]);
+    assert_eq!(
+        error_message(verifier::verify("optimising", call_to_nothing.as_slice(), verifier::check_tac_instructions)),
+        "Verification failed after optimising: Unknown function label func_f at instruction 0: call func_f()"
+    );
+}
@@ -0,0 +1,107 @@
+use rosy::interpreter;
+use rosy::parser;
+use rosy::parser::BaseExpr;
+use rosy::parser::BaseExprData;
+use rosy::parser::RecExpr;
+use rosy::parser::RecExprData;
+use rosy::tokenizer::Error;
+use rosy::visitor;
+use rosy::visitor::Folder;
+use rosy::visitor::Visitor;
+
+// Collects the names of all variables that are read, in source order
+struct VariableCollector {
+    names: Vec<String>,
+}
+
+impl Visitor<()> for VariableCollector {
+    fn visit_rec_expr(&mut self, rec_expr: &RecExpr<()>) {
+        if let RecExprData::Variable { name } = &rec_expr.data {
+            self.names.push(name.clone());
+        }
+        visitor::walk_rec_expr(self, rec_expr);
+    }
+}
+
+// Doubles every number literal
+struct NumberDoubler;
+
+impl Folder<()> for NumberDoubler {
+    fn fold_rec_expr(&mut self, rec_expr: RecExpr<()>) -> Result<RecExpr<()>, Error> {
+        match rec_expr.data {
+            RecExprData::Number { number } => Ok(RecExpr {
+                data: RecExprData::Number { number: number * 2 },
+                ..rec_expr
+            }),
+            _ => visitor::fold_rec_expr_children(self, rec_expr),
+        }
+    }
+}
+
+// Removes every break statement
+struct BreakRemover;
+
+impl Folder<()> for BreakRemover {
+    fn fold_body(&mut self, body: Vec<BaseExpr<()>>) -> Result<Vec<BaseExpr<()>>, Error> {
+        body.into_iter()
+            .filter(|base_expr| base_expr.data != BaseExprData::Break)
+            .map(|base_expr| self.fold_base_expr(base_expr))
+            .collect()
+    }
+}
+
+#[test]
+fn visitor_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 1",
+        "fun f(x)",
+        "    return x + a",
+        "for i in [a, 2]",
+        "    if i > a",
+        "        println(f(i))",
+        "    else",
+        "        println(-i)",
+    ]);
+
+    let base_expressions = parser::parse_strings(program).unwrap();
+    let mut collector = VariableCollector { names: Vec::new() };
+    collector.visit_body(&base_expressions);
+
+    assert_eq!(collector.names, vec!["x", "a", "a", "i", "a", "i", "i"]);
+}
+
+#[cfg(feature = "core")]
+#[test]
+fn folder_test() {
+    #[rustfmt::skip]
+    let program = Vec::from([
+        "a = 1",
+        "for i in 3",
+        "    if i == 1",
+        "        println(a + i)",
+        "        break",
+        "    else",
+        "        println(i)",
+    ]);
+
+    let base_expressions = parser::parse_strings(program).unwrap();
+
+    // i runs from 0 to 5, and the loop no longer stops after the matching branch
+    let doubled = NumberDoubler.fold_body(base_expressions).unwrap();
+    let without_breaks = BreakRemover.fold_body(doubled).unwrap();
+    let actual = interpreter::interpret(without_breaks).unwrap();
+
+    #[rustfmt::skip]
+    let expected = Vec::from([
+        "0",
+        "1",
+        "4",
+        "3",
+        "4",
+        "5",
+        "",
+    ]);
+
+    assert_eq!(actual, expected);
+}